@@ -9,62 +9,270 @@
 use std::{
     cell::RefCell,
     collections::BTreeMap,
-    io::{stdout, BufRead, BufReader, Write},
+    fmt::Display,
+    io::{self, stdin, stdout, Result as IoResult, Write},
+    path::Path,
     rc::Rc,
+    str::FromStr,
     sync::{Arc, RwLock},
     thread,
     time::{Duration, SystemTime},
 };
 
-use burn::backend::Wgpu;
 use clap::{builder::BoolishValueParser, Arg, ArgAction};
+use crossterm::{event::KeyCode, terminal::size as terminal_size};
 
-use tarpc::{client, context, tokio_serde::formats::Bincode};
-use tokio::{net::lookup_host, sync::RwLock as RwLockTokio};
+use tarpc::{client::RpcError, context};
+use tokio::{net::lookup_host, sync::RwLock as RwLockTokio, task::JoinHandle};
 
-use self::ui::TermUI;
+use self::ui::{
+    compose_chat_message,
+    music::{MusicPlayer, Tune},
+    settings::Settings,
+    MapEditor, ReplayViewer, Spectator, Splash, TermUI, UI,
+};
 
-use umpire_ai::AI;
+use umpire_ai::AnyBackendAI;
 
-use umpire_tui::color::{palette16, palette24, palette256};
+use umpire_tui::{
+    color::{palette16, palette24, palette256, Palette, Theme},
+    screen::Screen,
+};
 
 use common::{
     cli::{self, players_arg},
     conf,
     game::{
-        ai::{AISpec, AiDevice},
-        map::gen::MapType,
-        player::PlayerControl,
+        ai::{AISpec, AiBackendKind, AiDevice},
+        chat::ChatMessage,
+        error::GameError,
+        map::{
+            file as map_file,
+            gen::{MapType, Symmetry},
+            MapData, Terrain,
+        },
+        obs::FogOfWar,
+        player::{PlayerControl, PlayerTurn},
+        replay::Replay,
         turn_async::TurnTaker,
-        Game, IGame, PlayerNum, PlayerSecret, PlayerType,
+        unit::stats::{self, UnitStatsRegistry},
+        Game, GameOver, IGame, PlayerNum, PlayerSecret, PlayerType, UmpireResult,
     },
+    lobby::GameID,
     log::LogTarget,
     name::{city_namer, unit_namer},
-    rpc::{RpcGame, UmpireRpcClient},
-    util::{init_rng, Dims, Wrap2d},
+    rpc::{self, transport::Compression, RpcGame, UmpireRpcClient},
+    util::{init_rng, Dims, Rect, Wrap2d},
 };
 
 pub mod ui;
 
 const MIN_LOAD_SCREEN_DISPLAY_TIME: Duration = Duration::from_secs(3);
 
-fn print_loading_screen() {
-    let bytes: &[u8] = include_bytes!("../../images/1945_Baseball_Umpire.txt");
-    let r = BufReader::new(bytes);
-    for line in r.lines() {
-        let l = line.unwrap();
-        println!("{}", l);
+/// How often keys pressed are looked for while waiting on the server
+const KEY_CHECK_INTERVAL: Duration = Duration::from_millis(50);
+
+/// Look back on a decided game from the point of view of `ctrl`'s player
+async fn show_game_stats(
+    ui: &mut TermUI,
+    game: &Arc<RwLockTokio<dyn IGame>>,
+    ctrl: &mut PlayerControl,
+    game_over: GameOver,
+) -> IoResult<()> {
+    let lost = |err: GameError| io::Error::new(io::ErrorKind::ConnectionAborted, err);
+    let scores = game.read().await.player_scores().await.map_err(lost)?;
+    let histories = game.read().await.player_histories().await.map_err(lost)?;
+    let turn = PlayerTurn::spectate(ctrl).await;
+    ui.show_game_stats(&turn, game_over, &scores, &histories)
+        .await
+}
+
+/// Fill the terminal with the splash screen, drawn in the colors of `palette`
+fn show_splash_screen(palette: &Palette) -> IoResult<()> {
+    let (width, height) = terminal_size()?;
+    let mut screen = Screen::new(stdout(), Dims::new(width, height));
+
+    let splash = Splash::new();
+    screen.render_widget(splash.widget(palette), Rect::new(0, 0, width, height));
+
+    screen.flush()
+}
+
+fn load_palette(
+    color_depth: u16,
+    seed: Option<u64>,
+    num_players: PlayerNum,
+    fog_darkness: f64,
+    theme: &Theme,
+) -> Palette {
+    match color_depth {
+        16 | 256 => match color_depth {
+            16 => palette16(num_players, theme).expect("Error loading 16-color palette"),
+            256 => palette256(num_players, theme).expect("Error loading 256-color palette"),
+            x => panic!("Unsupported color depth {}", x),
+        },
+        24 => {
+            let rng = init_rng(seed);
+            palette24(rng, num_players, fog_darkness, theme)
+            // match palette24(num_players, fog_darkness) {
+            //     Ok(palette) => run_ui(game, use_alt_screen, palette, unicode, quiet, confirm_turn_end),
+            //     Err(err) => eprintln!("Error loading truecolor palette: {}", err)
+            // }
+        }
+        x => panic!("Unsupported color depth {}", x),
     }
+}
 
-    println!();
+/// Ask `question` at the terminal, taking no answer to mean `default`
+fn prompt<T: Display + FromStr>(question: &str, default: T) -> Result<T, String> {
+    print!("{} [{}]: ", question, default);
+    stdout()
+        .flush()
+        .map_err(|err| format!("Error prompting at the terminal: {}", err))?;
+
+    let mut line = String::new();
+    stdin()
+        .read_line(&mut line)
+        .map_err(|err| format!("Error reading from the terminal: {}", err))?;
+
+    let line = line.trim();
+    if line.is_empty() {
+        Ok(default)
+    } else {
+        line.parse()
+            .map_err(|_| format!("'{}' isn't a valid answer", line))
+    }
+}
 
-    println!("{}: {}", conf::APP_NAME, conf::APP_SUBTITLE);
-    stdout().flush().unwrap();
+/// Join one of the server's games, giving its ID
+///
+/// The game joined is `game_id` if given. Otherwise, if the server hosts more than one game, the user picks one.
+async fn join_game(client: &UmpireRpcClient, game_id: Option<GameID>) -> Result<GameID, String> {
+    let games = client
+        .games(context::current())
+        .await
+        .map_err(|err| format!("Error listing the server's games: {}", err))?;
+
+    let game_id = match game_id {
+        Some(game_id) => game_id,
+        None if games.len() <= 1 => 0,
+        None => {
+            for (game_id, lobby) in games.iter() {
+                let status = if lobby.started() {
+                    String::from("started")
+                } else {
+                    format!("{} open slot(s)", lobby.open_slots().count())
+                };
+                println!("Game {}: {}", game_id, status);
+            }
+
+            // Suggest the first game still to start
+            let default = games
+                .iter()
+                .find(|(_, lobby)| !lobby.started())
+                .map_or(0, |(game_id, _)| *game_id);
+
+            prompt("Game to join", default)?
+        }
+    };
+
+    client
+        .join_game(context::current(), game_id)
+        .await
+        .map_err(|err| format!("Error joining game {}: {}", game_id, err))?
+        .map_err(|err| format!("Couldn't join game {}: {}", game_id, err))?;
+
+    Ok(game_id)
+}
+
+/// Wait in the background for chat messages after the first `since` to reach the player with `secret`
+fn wait_chat(
+    game: &Arc<RwLockTokio<dyn IGame>>,
+    secret: PlayerSecret,
+    since: usize,
+) -> JoinHandle<UmpireResult<Vec<ChatMessage>>> {
+    let game = Arc::clone(game);
+    tokio::spawn(async move {
+        game.read()
+            .await
+            .wait_chat_messages(secret, since, conf::LONG_POLL_TIMEOUT)
+            .await
+    })
+}
+
+/// Claim a player slot in the server's lobby, then ready up and wait for everyone else to
+///
+/// The slot claimed is `slot` if given; otherwise the user picks one. The player goes by `name` in the chat, if given.
+async fn join_lobby(
+    client: &UmpireRpcClient,
+    slot: Option<PlayerNum>,
+    name: Option<String>,
+) -> Result<(), String> {
+    let lobby_err = |err: RpcError| format!("Error checking the lobby: {}", err);
+
+    let mut lobby = client.lobby(context::current()).await.map_err(lobby_err)?;
+    if lobby.started() {
+        return Err(String::from(
+            "The game has already started; pass --spectate to watch it",
+        ));
+    }
+
+    println!("Players:\n{}", lobby);
+
+    let player = match slot {
+        Some(player) => player,
+        None => {
+            let default = lobby
+                .open_slots()
+                .next()
+                .ok_or_else(|| String::from("No player slots are open; the game is full"))?;
+            prompt("Player to play as", default)?
+        }
+    };
+
+    let secret = client
+        .claim_slot(context::current(), player)
+        .await
+        .map_err(lobby_err)?
+        .map_err(|err| format!("Couldn't claim player {}: {}", player, err))?;
+
+    if let Some(name) = name {
+        client
+            .set_player_name(context::current(), secret, name)
+            .await
+            .map_err(lobby_err)?
+            .map_err(|err| format!("Couldn't set the player's name: {}", err))?;
+    }
+
+    let mut started = client
+        .ready(context::current())
+        .await
+        .map_err(lobby_err)?
+        .map_err(|err| format!("Couldn't ready up: {}", err))?;
+
+    println!("Playing as player {}", player);
+
+    let mut waiting_on = None;
+    while !started {
+        lobby = client
+            .wait_lobby(context::current(), lobby, conf::LONG_POLL_TIMEOUT)
+            .await
+            .map_err(lobby_err)?;
+
+        if waiting_on != Some(lobby.waiting_on()) {
+            waiting_on = Some(lobby.waiting_on());
+            println!("Waiting on {} more player(s)", lobby.waiting_on());
+        }
+
+        started = lobby.started();
+    }
+
+    Ok(())
 }
 
 #[tokio::main]
 async fn main() -> Result<(), String> {
-    let matches = cli::app(conf::APP_NAME, "fwWHMS")
+    let matches = cli::app(conf::APP_NAME, "fwWHMSUYmbk")
         .version(conf::APP_VERSION)
         .author("Josh Hansen <hansen.joshuaa@gmail.com>")
         .about(conf::APP_SUBTITLE)
@@ -95,6 +303,15 @@ async fn main() -> Result<(), String> {
                     width.map_err(|_e| format!("Invalid map height '{}'", s))
                 }),
         )
+        .arg(
+            Arg::new("theme")
+                .short('T')
+                .long("theme")
+                .help("TOML file of colors to use in place of the palette's own, as in data/theme.toml")
+                .value_parser(|s: &str| {
+                    Theme::load(s).map_err(|err| format!("Error loading theme {}: {}", s, err))
+                }),
+        )
         .arg(
             Arg::new("nosplash")
                 .short('n')
@@ -109,6 +326,15 @@ async fn main() -> Result<(), String> {
                 .help("Don't produce sound")
                 .action(ArgAction::SetTrue),
         )
+        .arg(
+            Arg::new("music")
+                .short('A')
+                .long("music")
+                .help("WAV file to play as the theme music in place of the built-in one")
+                .value_parser(|s: &str| {
+                    Tune::load_wav(s).map_err(|err| format!("Error loading music {}: {}", s, err))
+                }),
+        )
         .arg(
             Arg::new("unicode")
                 .short('u')
@@ -121,11 +347,92 @@ async fn main() -> Result<(), String> {
                 .long("confirm")
                 .help("Wait for explicit confirmation of turn end."),
         )
-        .arg(players_arg().required_unless_present("server"))
+        .arg(
+            Arg::new("auto_end_turn")
+                .long("auto-end-turn")
+                .help("End the turn as soon as nothing is left to do, even if the settings say to confirm")
+                .action(ArgAction::SetTrue)
+                .conflicts_with("confirm_turn_end"),
+        )
+        .arg(
+            Arg::new("load")
+                .short('l')
+                .long("load")
+                .help("Resume a saved game")
+                .num_args(0..=1)
+                .default_missing_value(conf::SAVE_PATH)
+                .conflicts_with("server"),
+        )
+        .arg(
+            Arg::new("record")
+                .short('R')
+                .long("record")
+                .help("Record a replay of the game, saved when the game ends")
+                .num_args(0..=1)
+                .default_missing_value(conf::REPLAY_PATH)
+                .conflicts_with("server"),
+        )
+        .arg(
+            Arg::new("replay")
+                .short('r')
+                .long("replay")
+                .help("Watch a recorded replay rather than playing")
+                .num_args(0..=1)
+                .default_missing_value(conf::REPLAY_PATH)
+                .conflicts_with_all(["server", "load", "record"]),
+        )
+        .arg(
+            Arg::new("spectate")
+                .long("spectate")
+                .help("Watch the game on the server without controlling any player")
+                .action(ArgAction::SetTrue)
+                .requires("server"),
+        )
+        .arg(
+            Arg::new("game")
+                .long("game")
+                .help("The game to join, of those hosted on the server")
+                .value_parser(clap::value_parser!(GameID))
+                .requires("server"),
+        )
+        .arg(
+            Arg::new("slot")
+                .long("slot")
+                .help("The player to play as on the server, rather than choosing one in the lobby")
+                .value_parser(clap::value_parser!(PlayerNum))
+                .requires("server")
+                .conflicts_with("spectate"),
+        )
+        .arg(
+            Arg::new("name")
+                .long("name")
+                .help("The name to go by in the chat on the server")
+                .requires("server")
+                .conflicts_with("spectate"),
+        )
+        .arg(
+            Arg::new("compression")
+                .long("compression")
+                .help("How to compress the server's responses: 'zstd' or 'none'; the server may not go along with it")
+                .default_value("zstd")
+                .value_parser(|s: &str| Compression::try_from(s))
+                .requires("server"),
+        )
+        .arg(
+            Arg::new("edit")
+                .short('e')
+                .long("edit")
+                .help("Edit a map file rather than playing, starting a new map if the file doesn't exist")
+                .num_args(0..=1)
+                .default_missing_value(conf::MAP_PATH)
+                .conflicts_with_all(["server", "load", "record", "replay"]),
+        )
+        .mut_arg("map_file", |arg| arg.conflicts_with_all(["server", "load"]))
+        .arg(players_arg().required_unless_present_any(["server", "replay", "edit"]))
         .arg(
             Arg::new("server")
                 .help("Server to connect to; game runs locally if omitted")
-                .required_unless_present("players"),
+                .required_unless_present_any(["players", "replay", "edit"]),
         )
         .get_matches();
 
@@ -140,19 +447,26 @@ async fn main() -> Result<(), String> {
     //     .collect()
     // ;
 
-    let nosplash = matches.contains_id("nosplash");
-
-    let start_time = SystemTime::now();
-    if !nosplash {
-        print_loading_screen();
+    if let Some(unit_stats) = matches.get_one::<UnitStatsRegistry>("unit_stats") {
+        stats::install(unit_stats.clone()).unwrap();
     }
 
-    if !nosplash {
-        let elapsed_time = SystemTime::now().duration_since(start_time).unwrap();
-        if elapsed_time < MIN_LOAD_SCREEN_DISPLAY_TIME {
-            let remaining = MIN_LOAD_SCREEN_DISPLAY_TIME - elapsed_time;
-            thread::sleep(remaining);
-        }
+    // The flags, where given, win out over the saved settings
+    let mut settings = Settings::load(conf::SETTINGS_PATH).unwrap_or_else(|err| {
+        eprintln!("Ignoring settings in {}: {}", conf::SETTINGS_PATH, err);
+        Settings::default()
+    });
+    if matches.contains_id("unicode") {
+        settings.unicode = true;
+    }
+    if matches.get_flag("quiet") {
+        settings.sound = false;
+    }
+    if matches.contains_id("confirm_turn_end") {
+        settings.confirm_turn_end = true;
+    }
+    if matches.get_flag("auto_end_turn") {
+        settings.confirm_turn_end = false;
     }
 
     let use_alt_screen = matches.get_one::<bool>("use_alt_screen").copied().unwrap();
@@ -162,11 +476,47 @@ async fn main() -> Result<(), String> {
         .parse()
         .unwrap();
     let fog_darkness = *matches.get_one::<f64>("fog_darkness").unwrap();
-    let unicode = matches.contains_id("unicode");
-    let quiet = matches.contains_id("quiet");
-    let confirm_turn_end = matches.contains_id("confirm_turn_end");
+    let theme = matches
+        .get_one::<Theme>("theme")
+        .cloned()
+        .unwrap_or_default();
+
+    let nosplash = matches.get_flag("nosplash");
+
+    // The music player is started even when only the game would play music, so it can be turned on mid-game
+    let music = settings.sound.then(|| {
+        let tune = matches
+            .get_one::<Tune>("music")
+            .cloned()
+            .unwrap_or_else(Tune::theme);
+        let volume = if nosplash {
+            settings.game_music_volume()
+        } else {
+            settings.splash_music_volume()
+        };
+        MusicPlayer::play(tune, volume)
+    });
 
-    let local_server = matches.contains_id("players");
+    let start_time = SystemTime::now();
+    if !nosplash {
+        // Player colors aren't shown on the splash screen, so it needn't wait to learn how many players there are
+        let palette = load_palette(color_depth, None, 1, fog_darkness, &theme);
+        if let Err(err) = show_splash_screen(&palette) {
+            eprintln!("Error showing splash screen: {}", err);
+        }
+    }
+
+    if !nosplash {
+        let elapsed_time = SystemTime::now().duration_since(start_time).unwrap();
+        if elapsed_time < MIN_LOAD_SCREEN_DISPLAY_TIME {
+            let remaining = MIN_LOAD_SCREEN_DISPLAY_TIME - elapsed_time;
+            thread::sleep(remaining);
+        }
+
+        if let Some(music) = music.as_ref() {
+            music.set_volume(settings.game_music_volume());
+        }
+    }
 
     let mut seed = matches.get_one::<u64>("random_seed").cloned();
     let mut rng = init_rng(seed);
@@ -174,6 +524,57 @@ async fn main() -> Result<(), String> {
         *seed = seed.wrapping_add(4938439);
     }
 
+    if let Some(replay_path) = matches.get_one::<String>("replay") {
+        let replay = Replay::load(replay_path)
+            .map_err(|err| format!("Error loading replay {}: {}", replay_path, err))?;
+
+        let (initial_game, _secrets) = replay.initial_game(None);
+
+        let palette = load_palette(
+            color_depth,
+            seed,
+            initial_game.num_players(),
+            fog_darkness,
+            &theme,
+        );
+
+        let mut ui = TermUI::new(initial_game.dims(), palette, settings, use_alt_screen).unwrap();
+
+        return ReplayViewer::new(&replay)
+            .await
+            .run(&mut ui)
+            .await
+            .map_err(|err| format!("Error playing replay {}: {}", replay_path, err));
+    }
+
+    if let Some(map_path) = matches.get_one::<String>("edit") {
+        let map = if Path::new(map_path).exists() {
+            map_file::load(map_path)
+                .map_err(|err| format!("Error loading map {}: {}", map_path, err))?
+        } else {
+            let map_width = *matches.get_one::<u16>("map_width").unwrap();
+            let map_height = *matches.get_one::<u16>("map_height").unwrap();
+            MapData::new(Dims::new(map_width, map_height), |_loc| Terrain::Water)
+        };
+
+        // Without a player specification, allow for the two players that map files can hold units for
+        let num_players = matches
+            .get_one::<Vec<PlayerType>>("players")
+            .map_or(2, |player_types| player_types.len())
+            .max(map.players());
+
+        let palette = load_palette(color_depth, seed, num_players, fog_darkness, &theme);
+
+        let mut ui = TermUI::new(map.dims(), palette, settings, use_alt_screen).unwrap();
+
+        return MapEditor::new(map, map_path.clone(), num_players)
+            .run(&mut ui)
+            .await
+            .map_err(|err| format!("Error editing map {}: {}", map_path, err));
+    }
+
+    let local_server = matches.contains_id("players");
+
     let (game, secrets, num_players, dims, player_types) = if local_server {
         let player_types = matches.get_one::<Vec<PlayerType>>("players").unwrap();
 
@@ -183,28 +584,99 @@ async fn main() -> Result<(), String> {
         let wrapping = *matches.get_one::<Wrap2d>("wrapping").unwrap();
         let map_type = matches.get_one::<MapType>("map_type").copied().unwrap();
 
-        let fog_of_war = *matches.get_one::<bool>("fog").unwrap();
-
-        let map_dims: Dims = Dims::new(map_width, map_height);
-        if (map_dims.area() as PlayerNum) < num_players {
-            return Err(format!("Map dimensions of {} give an area of {} which is not enough room for {} players; area of {} or greater required.",
-                map_dims, map_dims.area(), num_players, num_players));
-        }
+        let fog_of_war = *matches.get_one::<FogOfWar>("fog").unwrap();
 
-        let city_namer = city_namer(&mut rng);
         let unit_namer = unit_namer(Some(init_rng(seed)));
 
-        let (game, secrets) = Game::new(
-            Some(rng),
-            false,
-            map_dims,
-            map_type,
-            city_namer,
-            player_types.len(),
-            fog_of_war,
-            Some(Arc::new(RwLock::new(unit_namer))),
-            wrapping,
-        );
+        let (mut game, secrets) = if let Some(load_path) = matches.get_one::<String>("load") {
+            let (game, secrets) = Game::load(load_path, Some(Arc::new(RwLock::new(unit_namer))))
+                .map_err(|err| format!("Error loading saved game {}: {}", load_path, err))?;
+
+            if game.num_players() != num_players {
+                return Err(format!(
+                    "Saved game {} has {} players but {} player types were specified",
+                    load_path,
+                    game.num_players(),
+                    num_players
+                ));
+            }
+
+            (game, secrets)
+        } else if let Some(map) = matches.get_one::<MapData>("map_file") {
+            if map.players() != num_players {
+                return Err(format!(
+                    "The map file has {} players but {} player types were specified",
+                    map.players(),
+                    num_players
+                ));
+            }
+
+            Game::new_with_map(
+                Some(rng),
+                false,
+                map.clone(),
+                num_players,
+                fog_of_war,
+                Some(Arc::new(RwLock::new(unit_namer))),
+                wrapping,
+            )
+        } else {
+            let map_dims: Dims = Dims::new(map_width, map_height);
+            if (map_dims.area() as PlayerNum) < num_players {
+                return Err(format!("Map dimensions of {} give an area of {} which is not enough room for {} players; area of {} or greater required.",
+                    map_dims, map_dims.area(), num_players, num_players));
+            }
+
+            let symmetry = matches.get_one::<Symmetry>("symmetry").copied();
+            if let Some(symmetry) = symmetry {
+                symmetry.check(map_dims, num_players)?;
+            }
+
+            let mut city_namer = city_namer(&mut rng);
+
+            if matches.get_flag("fair_starts") {
+                let (map, report) = map_type.generate_fair(
+                    &mut rng,
+                    map_dims,
+                    num_players,
+                    &mut city_namer,
+                    symmetry,
+                    wrapping,
+                );
+                for imbalance in report.imbalances() {
+                    eprintln!("Warning: unbalanced starts. {}", imbalance);
+                }
+
+                Game::new_with_map(
+                    Some(rng),
+                    false,
+                    map,
+                    num_players,
+                    fog_of_war,
+                    Some(Arc::new(RwLock::new(unit_namer))),
+                    wrapping,
+                )
+            } else {
+                Game::new(
+                    Some(rng),
+                    false,
+                    map_dims,
+                    map_type,
+                    symmetry,
+                    city_namer,
+                    player_types.len(),
+                    fog_of_war,
+                    Some(Arc::new(RwLock::new(unit_namer))),
+                    wrapping,
+                )
+            }
+        };
+
+        if matches.contains_id("record") {
+            game.record_replay();
+        }
+
+        let map_dims = game.dims();
         (
             Arc::new(RwLockTokio::new(game)) as Arc<RwLockTokio<dyn IGame>>,
             secrets
@@ -227,7 +699,12 @@ async fn main() -> Result<(), String> {
                 "No address returned looking up server domain name",
             ))?;
 
-        let transport = tarpc::serde_transport::tcp::connect(server_addr, Bincode::default)
+        let compression = matches
+            .get_one::<Compression>("compression")
+            .copied()
+            .unwrap();
+
+        let client = rpc::connect(server_addr, compression)
             .await
             .map_err(|err| {
                 format!(
@@ -236,9 +713,23 @@ async fn main() -> Result<(), String> {
                 )
             })?;
 
-        // let (client_transport, server_transport) = tarpc::transport::channel::unbounded();
+        let game_id = join_game(&client, matches.get_one::<GameID>("game").copied()).await?;
 
-        let client = UmpireRpcClient::new(client::Config::default(), transport).spawn();
+        // Spectators look on without taking a slot
+        if matches.get_flag("spectate") {
+            client
+                .spectate(context::current())
+                .await
+                .map_err(|err| format!("Error registering as a spectator: {}", err))?
+                .map_err(|err| format!("Couldn't spectate: {}", err))?;
+        } else {
+            join_lobby(
+                &client,
+                matches.get_one::<PlayerNum>("slot").copied(),
+                matches.get_one::<String>("name").cloned(),
+            )
+            .await?;
+        }
 
         let secrets = client
             .player_secrets_known(context::current())
@@ -252,33 +743,63 @@ async fn main() -> Result<(), String> {
 
         let player_types = client.player_types(context::current()).await.unwrap();
 
-        let game = Arc::new(RwLockTokio::new(RpcGame::new(client))) as Arc<RwLockTokio<dyn IGame>>;
+        let game = Arc::new(RwLockTokio::new(RpcGame::new(
+            client,
+            server_addr,
+            compression,
+            game_id,
+            secrets.clone(),
+        ))) as Arc<RwLockTokio<dyn IGame>>;
 
-        let num_players = game.read().await.num_players().await;
+        let num_players = game
+            .read()
+            .await
+            .num_players()
+            .await
+            .map_err(|err| err.to_string())?;
 
-        let dims = game.read().await.dims().await;
+        let dims = game
+            .read()
+            .await
+            .dims()
+            .await
+            .map_err(|err| err.to_string())?;
 
         (game, secrets, num_players, dims, player_types)
     };
 
     let device: AiDevice = Default::default();
+    let backend = *matches.get_one::<AiBackendKind>("backend").unwrap();
 
-    let palette = match color_depth {
-        16 | 256 => match color_depth {
-            16 => palette16(num_players).expect("Error loading 16-color palette"),
-            256 => palette256(num_players).expect("Error loading 256-color palette"),
-            x => panic!("Unsupported color depth {}", x),
-        },
-        24 => {
-            let rng = init_rng(seed);
-            palette24(rng, num_players, fog_darkness)
-            // match palette24(num_players, fog_darkness) {
-            //     Ok(palette) => run_ui(game, use_alt_screen, palette, unicode, quiet, confirm_turn_end),
-            //     Err(err) => eprintln!("Error loading truecolor palette: {}", err)
-            // }
+    let palette = load_palette(color_depth, seed, num_players, fog_darkness, &theme);
+
+    if matches.get_flag("spectate") {
+        let mut ui = TermUI::new(dims, palette, settings, use_alt_screen).unwrap();
+        if let Some(music) = music {
+            ui.set_music(music);
         }
-        x => panic!("Unsupported color depth {}", x),
-    };
+
+        let mut spectator = Spectator::new(Arc::clone(&game)).await;
+        spectator
+            .run(&mut ui)
+            .await
+            .map_err(|err| format!("Error spectating: {}", err))?;
+
+        if let Some(game_over) = game
+            .read()
+            .await
+            .game_over()
+            .await
+            .map_err(|err| err.to_string())?
+        {
+            let mut ctrl = spectator.view_ctrl().await;
+            show_game_stats(&mut ui, &game, &mut ctrl, game_over)
+                .await
+                .map_err(|err| format!("Error showing game statistics: {}", err))?;
+        }
+
+        return Ok(());
+    }
 
     // Make PlayerControl's for all players we have secrets for
     let mut ctrls: Vec<Option<PlayerControl>> = Vec::with_capacity(num_players);
@@ -294,26 +815,21 @@ async fn main() -> Result<(), String> {
     {
         // Scope for the UI. When it goes out of scope it will clean up the terminal, threads, audio, etc.
 
-        let mut ui = TermUI::new(
-            dims,
-            palette,
-            unicode,
-            confirm_turn_end,
-            quiet,
-            use_alt_screen,
-        )
-        .unwrap();
+        let mut ui = TermUI::new(dims, palette, settings, use_alt_screen).unwrap();
+        if let Some(music) = music {
+            ui.set_music(music);
+        }
 
         // We can share one instance of RandomAI across players since it's stateless
         // let mut random_ai = RandomAI::new(0);
 
         // AIs indexed by spec
-        let mut ais: BTreeMap<AISpec, Rc<RefCell<AI<Wgpu>>>> = BTreeMap::new();
+        let mut ais: BTreeMap<AISpec, Rc<RefCell<AnyBackendAI>>> = BTreeMap::new();
 
         if local_server {
             for ptype in player_types.iter() {
                 if let PlayerType::AI(ai_type) = ptype {
-                    let ai: AI<Wgpu> = ai_type.clone().into();
+                    let ai = AnyBackendAI::new(ai_type.clone(), backend)?;
                     let ai = Rc::new(RefCell::new(ai));
                     // let player: Rc<RefCell<dyn TurnTaker>> = ai_type.clone().into();
                     ais.insert(ai_type.clone(), ai);
@@ -321,16 +837,52 @@ async fn main() -> Result<(), String> {
             }
         }
 
+        // Whether the server was last seen holding play
+        let mut paused = false;
+
         // Re-init rng because the Game took ownership of the first one
         'outer: loop {
-            if game.read().await.victor().await.is_some() {
+            if let Some(game_over) = game
+                .read()
+                .await
+                .game_over()
+                .await
+                .map_err(|err| err.to_string())?
+            {
+                // Look back on the game from the point of view of any local player
+                if let Some(ctrl) = ctrls.iter_mut().flatten().next() {
+                    show_game_stats(&mut ui, &game, ctrl, game_over)
+                        .await
+                        .unwrap();
+                }
                 break 'outer;
             }
 
-            let player = game.read().await.current_player().await;
+            let player = game
+                .read()
+                .await
+                .current_player()
+                .await
+                .map_err(|err| err.to_string())?;
+
+            if game
+                .read()
+                .await
+                .paused()
+                .await
+                .map_err(|err| err.to_string())?
+                != paused
+            {
+                paused = !paused;
+                ui.log_message(if paused {
+                    "The game is paused"
+                } else {
+                    "The game is resumed"
+                });
+            }
 
-            // Only take the turn locally if we have the corresponding player's secret
-            if let Some(_secret) = secrets[player] {
+            // Only take the turn locally if we have the corresponding player's secret, and play isn't held
+            if let (Some(_secret), false) = (secrets[player], paused) {
                 ui.log_message(format!("Player {}'s turn", player));
 
                 let ctrl = ctrls.get_mut(player).unwrap().as_mut().unwrap();
@@ -350,7 +902,10 @@ async fn main() -> Result<(), String> {
                         assert!(turn_outcome.training_instances.is_none());
 
                         if turn_outcome.quit {
-                            turn.force_end_turn().await.unwrap();
+                            // Unless the server already ended it, the player having run out of time
+                            if !turn.ended() {
+                                turn.force_end_turn().await.unwrap();
+                            }
                             break;
                         }
                     }
@@ -371,14 +926,82 @@ async fn main() -> Result<(), String> {
                     }
                 }
 
-                turn.force_end_turn().await.unwrap();
-                debug_assert!(turn.ended());
+                // A turn the server ended for running out of time has no summary to show
+                if !turn.ended() {
+                    let turn_num = turn.start().turn;
+                    let turn_ended = turn.force_end_turn().await.unwrap();
+                    debug_assert!(turn.ended());
+
+                    if let PlayerType::Human = player_types[player] {
+                        ui.show_turn_summary(&turn, player, turn_num, turn_ended.summary)
+                            .await
+                            .unwrap();
+                    }
+                }
             } else {
-                tokio::time::sleep(Duration::from_millis(500)).await;
+                // The server tells us when play comes back to one of our players, or that it hasn't yet
+                let waiting = {
+                    let game = Arc::clone(&game);
+                    tokio::spawn(async move {
+                        game.read()
+                            .await
+                            .wait_my_turn(conf::LONG_POLL_TIMEOUT)
+                            .await
+                    })
+                };
+
+                let Some(ctrl) = ctrls.iter_mut().flatten().next() else {
+                    waiting.await.unwrap().map_err(|err| err.to_string())?;
+                    continue;
+                };
+
+                // While the others take their turns, keep up with the chat and let the user chime in
+                let secret = secrets[ctrl.player].unwrap();
+                let mut turn = PlayerTurn::spectate(ctrl).await;
+                let mut chatting = wait_chat(&game, secret, ui.chat_received());
+
+                while !waiting.is_finished() {
+                    if chatting.is_finished() {
+                        let messages = chatting.await.unwrap().map_err(|err| err.to_string())?;
+                        ui.receive_chat_messages(&turn, messages).await.unwrap();
+                        chatting = wait_chat(&game, secret, ui.chat_received());
+                    }
+
+                    match ui.get_key_timeout(KEY_CHECK_INTERVAL) {
+                        Ok(key) if key.code == KeyCode::Char(conf::KEY_CHAT) => {
+                            // Sending the message changes the game, which can't happen while it's waited on
+                            waiting.abort();
+                            chatting.abort();
+                            compose_chat_message(&mut ui, &mut turn).await.unwrap();
+                        }
+                        Ok(key) if key.code == KeyCode::Char(conf::KEY_QUIT) => {
+                            waiting.abort();
+                            chatting.abort();
+                            break 'outer;
+                        }
+                        _ => {}
+                    }
+                }
+                chatting.abort();
+
+                // Unless we stopped waiting to chat
+                if let Ok(Err(err)) = waiting.await {
+                    return Err(err.to_string());
+                }
             }
         }
     } // UI drops here, deinitializing the user interface
 
+    if let Some(record_path) = matches.get_one::<String>("record") {
+        let state = game.read().await.clone_underlying_game_state()?;
+        if let Some(replay) = state.replay() {
+            replay
+                .save(record_path)
+                .map_err(|err| format!("Error saving replay to {}: {}", record_path, err))?;
+            println!("Replay saved to {}", record_path);
+        }
+    }
+
     println!(
         "\n\n\tHe rules a moment: Chaos umpire sits,
     \tAnd by decision more embroils the fray
@@ -7,33 +7,46 @@
 use std::{
     borrow::Cow,
     cmp,
-    io::{stdout, Result as IoResult, Stdout, Write},
+    collections::{BTreeSet, VecDeque},
+    io::{stdout, Result as IoResult},
     sync::{
-        mpsc::{channel, sync_channel, Receiver, RecvError, SyncSender},
-        Mutex,
+        atomic::{AtomicBool, Ordering},
+        mpsc::{channel, sync_channel, Receiver, RecvError, RecvTimeoutError, SyncSender},
+        Arc, Mutex,
     },
     thread::{self, JoinHandle},
+    time::{Duration, Instant},
 };
 
 use async_trait::async_trait;
 use crossterm::{
-    cursor::{Hide, MoveTo, Show},
-    event::{read as read_event, Event, KeyCode, KeyEvent},
-    queue,
-    style::{Attribute, Print, SetAttribute, SetBackgroundColor},
+    cursor::{Hide, Show},
+    event::{
+        read as read_event, DisableMouseCapture, EnableMouseCapture, Event, KeyCode, KeyEvent,
+        MouseButton, MouseEventKind,
+    },
+    style::{Attribute, SetAttribute, SetBackgroundColor},
     terminal::{
         disable_raw_mode, enable_raw_mode, size as terminal_size, Clear, ClearType,
         EnterAlternateScreen, LeaveAlternateScreen,
     },
 };
 
+use ratatui::{
+    layout::{Constraint, Direction, Layout, Rect as Area},
+    widgets::{Clear as Blank, Widget},
+};
+
 use common::{
     colors::Colors,
     conf::{self, HEADER_HEIGHT},
     game::{
         ai::AiDevice,
+        alert::Alert,
+        chat::ChatMessage,
         city::City,
         combat::{CombatCapable, CombatOutcome, CombatParticipant},
+        history::PlayerHistory,
         map::Tile,
         move_::Move,
         obs::{LocatedObs, Obs},
@@ -41,25 +54,47 @@ use common::{
         turn::TurnOutcome,
         turn_async::TurnTaker,
         unit::Unit,
+        GameOver, PlayerNum, TurnNum, TurnSummary,
     },
     log::{LogTarget, Message, MessageSource},
-    util::{sleep_millis, Dims, Location, Rect, Vec2d},
+    util::{Dims, Location, Rect, Vec2d},
 };
 
 use umpire_tui::{
     color::Palette,
     map::Map,
-    scroll::{ScrollableComponent, Scroller},
+    paint,
+    screen::{self, Screen},
+    scroll::{ScrollMarks, ScrollableComponent},
     sym::Sym,
     Component, Draw,
 };
 
 use self::{
-    audio::{play_sounds, Sounds},
+    audio::{play_sounds, Effect, Sounds},
     buf::RectBuffer,
+    chat::ChatInput,
+    indicators::{Header, Standing},
     mode::ModeStatus,
+    music::MusicPlayer,
+    settings::Settings,
+    stats::stats_rows,
+    summary::{TurnSummaryPanel, SUMMARY_HEIGHT, SUMMARY_WIDTH},
+    toast::Toast,
 };
 
+/// Something the user did, as passed along by the input thread
+#[derive(Clone, Copy, Debug)]
+pub enum Input {
+    Key(KeyEvent),
+
+    /// A left click at the given terminal column and row
+    Click(Location),
+
+    /// The terminal was resized to the given dimensions
+    Resize(Dims),
+}
+
 #[async_trait]
 pub trait MoveAnimator {
     async fn animate_move(&mut self, game: &PlayerTurn, move_result: &Move) -> IoResult<()>;
@@ -70,9 +105,17 @@ pub trait MoveAnimator {
 pub trait UI: LogTarget + MoveAnimator {
     fn confirm_turn_end(&self) -> bool;
 
+    fn settings(&self) -> Settings;
+
+    /// Put `settings` into effect; the caller should redraw
+    fn set_settings(&mut self, settings: Settings);
+
     /// Center the map view on the given map location
     fn center_map(&mut self, map_loc: Location);
 
+    /// Center the map view on the given map location if it's out of view
+    fn ensure_map_loc_visible(&mut self, map_loc: Location);
+
     fn clear_sidebar(&mut self);
 
     fn viewport_rect(&self) -> Rect;
@@ -81,6 +124,11 @@ pub trait UI: LogTarget + MoveAnimator {
 
     fn term_dims(&self) -> Dims;
 
+    /// Lay the interface out anew for a terminal of the given dimensions
+    ///
+    /// Nothing is redrawn; follow with a call to `draw`.
+    fn set_term_dims(&mut self, term_dims: Dims);
+
     fn unicode(&self) -> bool;
 
     async fn cursor_map_loc(&self, mode: &Mode, game: &PlayerTurn) -> Option<Location>;
@@ -105,7 +153,7 @@ pub trait UI: LogTarget + MoveAnimator {
 
     /// Renders a particular location in the map viewport
     ///
-    /// Flushes stdout for convenience
+    /// Flushes the screen for convenience
     async fn draw_map_tile_and_flush(
         &mut self,
         game: &PlayerTurn,
@@ -128,8 +176,19 @@ pub trait UI: LogTarget + MoveAnimator {
     ) -> IoResult<()>;
 
     /// Block until a key is pressed; return that key
+    ///
+    /// Any clicks in the meantime are ignored.
     fn get_key(&self) -> Result<KeyEvent, RecvError>;
 
+    /// Wait up to `timeout` for a key to be pressed; return that key
+    fn get_key_timeout(&self, timeout: Duration) -> Result<KeyEvent, RecvTimeoutError>;
+
+    /// Block until a key is pressed or the mouse is clicked; return what happened
+    fn get_input(&self) -> Result<Input, RecvError>;
+
+    /// Wait up to `timeout` for a key to be pressed or the mouse to be clicked; return what happened
+    fn get_input_timeout(&self, timeout: Duration) -> Result<Input, RecvTimeoutError>;
+
     fn map_to_viewport_coords(&self, map_loc: Location) -> Option<Location>;
 
     fn play_sound(&self, sound: Sounds);
@@ -140,8 +199,44 @@ pub trait UI: LogTarget + MoveAnimator {
 
     fn scroll_map_relative<V: Into<Vec2d<i32>>>(&mut self, direction: V);
 
+    /// Direct the viewport shift keys to the other map, when the viewport is split
+    fn switch_viewport(&mut self);
+
+    /// Shade the given map locations as where the selected unit can go, in place of any shaded before
+    fn set_reachable(&mut self, reachable: BTreeSet<Location>);
+
     fn set_sidebar_row(&mut self, row_idx: usize, row: String);
 
+    /// Cover the map, log, and sidebar with a blank full-screen buffer
+    fn open_full_screen(&mut self);
+
+    /// Uncover the map, log, and sidebar again; the caller should redraw
+    fn close_full_screen(&mut self);
+
+    /// The number of rows in the full-screen buffer
+    fn full_screen_height(&self) -> usize;
+
+    /// Rows beyond the full-screen buffer's height are ignored
+    fn set_full_screen_row(&mut self, row_idx: usize, row: String);
+
+    /// Give the bottom line of the log over to typing a chat message
+    fn open_chat_input(&mut self);
+
+    /// Return the chat line to the log; the caller should redraw
+    fn close_chat_input(&mut self);
+
+    /// The chat message typed so far
+    fn set_chat_input(&mut self, text: String);
+
+    /// Log any chat messages that have arrived since last checked
+    async fn receive_chat(&mut self, game: &PlayerTurn) -> IoResult<()>;
+
+    /// Pop alerts up over the map one at a time, in place of any still waiting to be shown
+    fn set_alerts(&mut self, alerts: Vec<Alert>);
+
+    /// Dismiss the alert being shown, returning where it happened
+    fn jump_to_alert(&mut self) -> Option<Location>;
+
     async fn viewport_to_map_coords(
         &self,
         game: &PlayerTurn,
@@ -180,10 +275,22 @@ impl UI for DefaultUI {
         false
     }
 
+    fn settings(&self) -> Settings {
+        Settings::default()
+    }
+
+    fn set_settings(&mut self, _settings: Settings) {
+        // do nothing
+    }
+
     fn center_map(&mut self, _map_loc: Location) {
         // do nothing
     }
 
+    fn ensure_map_loc_visible(&mut self, _map_loc: Location) {
+        // do nothing
+    }
+
     fn viewport_rect(&self) -> Rect {
         Rect::new(0, 0, 0, 0)
     }
@@ -196,6 +303,10 @@ impl UI for DefaultUI {
         Dims::new(0, 0)
     }
 
+    fn set_term_dims(&mut self, _term_dims: Dims) {
+        // do nothing
+    }
+
     fn unicode(&self) -> bool {
         false
     }
@@ -242,7 +353,7 @@ impl UI for DefaultUI {
 
     /// Renders a particular location in the map viewport
     ///
-    /// Flushes stdout for convenience
+    /// Flushes the screen for convenience
     async fn draw_map_tile_and_flush(
         &mut self,
         _game: &PlayerTurn,
@@ -272,6 +383,18 @@ impl UI for DefaultUI {
         Ok(KeyEvent::from(KeyCode::Null))
     }
 
+    fn get_key_timeout(&self, _timeout: Duration) -> Result<KeyEvent, RecvTimeoutError> {
+        Err(RecvTimeoutError::Timeout)
+    }
+
+    fn get_input(&self) -> Result<Input, RecvError> {
+        Ok(Input::Key(KeyEvent::from(KeyCode::Null)))
+    }
+
+    fn get_input_timeout(&self, _timeout: Duration) -> Result<Input, RecvTimeoutError> {
+        Err(RecvTimeoutError::Timeout)
+    }
+
     fn map_to_viewport_coords(&self, _map_loc: Location) -> Option<Location> {
         None
     }
@@ -292,10 +415,58 @@ impl UI for DefaultUI {
         // do nothing
     }
 
+    fn open_full_screen(&mut self) {
+        // do nothing
+    }
+
+    fn close_full_screen(&mut self) {
+        // do nothing
+    }
+
+    fn full_screen_height(&self) -> usize {
+        0
+    }
+
+    fn set_full_screen_row(&mut self, _row_idx: usize, _row: String) {
+        // do nothing
+    }
+
+    fn open_chat_input(&mut self) {
+        // do nothing
+    }
+
+    fn close_chat_input(&mut self) {
+        // do nothing
+    }
+
+    fn set_chat_input(&mut self, _text: String) {
+        // do nothing
+    }
+
+    async fn receive_chat(&mut self, _game: &PlayerTurn) -> IoResult<()> {
+        Ok(()) // do nothing
+    }
+
+    fn set_alerts(&mut self, _alerts: Vec<Alert>) {
+        // do nothing
+    }
+
+    fn jump_to_alert(&mut self) -> Option<Location> {
+        None
+    }
+
     fn scroll_map_relative<V: Into<Vec2d<i32>>>(&mut self, _direction: V) {
         // do nothing
     }
 
+    fn switch_viewport(&mut self) {
+        // do nothing
+    }
+
+    fn set_reachable(&mut self, _reachable: BTreeSet<Location>) {
+        // do nothing
+    }
+
     async fn viewport_to_map_coords(
         &self,
         _game: &PlayerTurn,
@@ -307,11 +478,27 @@ impl UI for DefaultUI {
 
 mod audio;
 mod buf;
+mod chat;
+mod editor;
 mod indicators;
 mod log;
 mod mode;
+pub mod music;
+mod replay;
+pub mod settings;
+mod spectator;
+mod splash;
+mod stats;
+mod summary;
+mod toast;
+mod unit_info;
+
+pub use self::chat::compose_chat_message;
+pub use self::editor::MapEditor;
+pub use self::replay::ReplayViewer;
+pub use self::spectator::Spectator;
+pub use self::splash::Splash;
 
-use self::indicators::{CurrentPlayer, Turn};
 use self::log::LogArea;
 use self::mode::Mode;
 
@@ -321,11 +508,17 @@ const MAX_MID_HEIGHT: u16 = 25;
 pub enum ViewportSize {
     Regular,
     Theater,
+
+    /// As `Theater`, but with two maps side by side, each scrolled on its own
+    Split,
+
     Fullscreen,
 }
 
 impl ViewportSize {
-    fn rect(&self, term_dims: Dims) -> Rect {
+    /// Where each part of the interface goes on a terminal of dimensions `term_dims`
+    fn areas(&self, term_dims: Dims) -> Areas {
+        let term = Area::new(0, 0, term_dims.width, term_dims.height);
         let mid_y = match term_dims.height {
             0 => 0,
             1 | 2 => 1,
@@ -333,118 +526,250 @@ impl ViewportSize {
             4 | 5 => 3,
             x => cmp::min(x / 2, MAX_MID_HEIGHT),
         };
-        match *self {
-            ViewportSize::Regular => Rect {
-                left: 0,
-                top: HEADER_HEIGHT,
-                width: (term_dims.width - V_SCROLLBAR_WIDTH) / 2,
-                height: mid_y,
-            },
-            ViewportSize::Theater => Rect {
-                left: 0,
-                top: HEADER_HEIGHT,
-                width: term_dims.width - V_SCROLLBAR_WIDTH,
-                height: mid_y,
-            },
-            ViewportSize::Fullscreen => Rect {
-                left: 0,
-                top: 0,
-                width: term_dims.width - V_SCROLLBAR_WIDTH,
-                height: term_dims.height - H_SCROLLBAR_HEIGHT - 1,
-            },
+
+        if let ViewportSize::Fullscreen = *self {
+            let rows = Layout::default()
+                .direction(Direction::Vertical)
+                .constraints([
+                    Constraint::Min(0),
+                    Constraint::Length(H_SCROLLBAR_HEIGHT),
+                    Constraint::Length(1),
+                ])
+                .split(term);
+            let log = Layout::default()
+                .direction(Direction::Horizontal)
+                .constraints([Constraint::Ratio(1, 2), Constraint::Min(0)])
+                .split(rows[2])[0];
+
+            return Areas {
+                header: Rect::new(0, 0, 0, 0),
+                viewport: screen::rect(without_v_scrollbar(rows[0])),
+                log: screen::rect(log),
+                sidebar: Rect::new(0, 0, 0, 0),
+                second_viewport: Rect::new(0, 0, 0, 0),
+                full_screen: full_screen_rect(term),
+            };
         }
-    }
-}
 
-fn current_player_rect() -> Rect {
-    Rect {
-        left: 10,
-        top: 0,
-        width: 21,
-        height: 1,
-    }
-}
+        // Header, then the map and its horizontal scrollbar, then a gap, then whatever goes beneath the map
+        let rows = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([
+                Constraint::Length(HEADER_HEIGHT),
+                Constraint::Length(mid_y),
+                Constraint::Length(H_SCROLLBAR_HEIGHT),
+                Constraint::Length(1),
+                Constraint::Min(0),
+            ])
+            .split(term);
+
+        // The map's column, its vertical scrollbar, a gap, and the sidebar's column
+        let columns = |area: Area, map_width: Constraint| {
+            Layout::default()
+                .direction(Direction::Horizontal)
+                .constraints([
+                    map_width,
+                    Constraint::Length(V_SCROLLBAR_WIDTH),
+                    Constraint::Length(1),
+                    Constraint::Min(0),
+                ])
+                .split(area)
+        };
 
-fn turn_rect(current_player_rect: Rect) -> Rect {
-    Rect {
-        left: current_player_rect.right() + 2,
-        top: 0,
-        width: 11,
-        height: 1,
-    }
-}
+        let mut second_viewport = Area::default();
+        let (viewport, log, sidebar) = match *self {
+            ViewportSize::Regular => {
+                let body = Area::new(0, rows[1].y, term.width, term.height - rows[1].y);
+                let body_columns = columns(
+                    body,
+                    Constraint::Length(term_dims.width.saturating_sub(V_SCROLLBAR_WIDTH) / 2),
+                );
+                let viewport = Area {
+                    height: rows[1].height,
+                    ..body_columns[0]
+                };
+                let log = Area {
+                    y: rows[4].y,
+                    height: rows[4].height,
+                    ..body_columns[0]
+                };
+                (viewport, log, body_columns[3])
+            }
+            ViewportSize::Theater => {
+                let bottom_columns = columns(rows[4], Constraint::Ratio(1, 2));
+                (
+                    without_v_scrollbar(rows[1]),
+                    bottom_columns[0],
+                    bottom_columns[3],
+                )
+            }
+            ViewportSize::Split => {
+                // Each half of the map row holds a viewport and its vertical scrollbar
+                let halves = Layout::default()
+                    .direction(Direction::Horizontal)
+                    .constraints([Constraint::Ratio(1, 2), Constraint::Min(0)])
+                    .split(rows[1]);
+                second_viewport = without_v_scrollbar(halves[1]);
+
+                let bottom_columns = columns(rows[4], Constraint::Ratio(1, 2));
+                (
+                    without_v_scrollbar(halves[0]),
+                    bottom_columns[0],
+                    bottom_columns[3],
+                )
+            }
+            ViewportSize::Fullscreen => unreachable!(),
+        };
 
-fn log_area_rect(term_dims: Dims, viewport_size: ViewportSize) -> Rect {
-    let viewport_rect = viewport_size.rect(term_dims);
-
-    match viewport_size {
-        ViewportSize::Regular => Rect {
-            left: 0,
-            top: viewport_rect.bottom() + 2,
-            width: viewport_rect.width,
-            height: term_dims.height - viewport_rect.height - 4,
-        },
-        ViewportSize::Theater => {
-            log_area_rect(term_dims, ViewportSize::Regular).set_width(viewport_rect.width / 2)
-        }
-        ViewportSize::Fullscreen => {
-            log_area_rect(term_dims, ViewportSize::Theater).set_top(viewport_rect.bottom() + 1)
+        Areas {
+            header: screen::rect(rows[0]),
+            viewport: screen::rect(viewport),
+            log: screen::rect(log),
+            sidebar: screen::rect(sidebar),
+            second_viewport: screen::rect(second_viewport),
+            full_screen: full_screen_rect(term),
         }
     }
 }
 
+/// Where each part of the interface goes
+///
+/// Parts not shown at the current viewport size get empty rects.
+struct Areas {
+    header: Rect,
+    viewport: Rect,
+    log: Rect,
+    sidebar: Rect,
+
+    /// Where the second map goes when the viewport is split
+    second_viewport: Rect,
+
+    /// Where a screen covering everything but the header goes
+    full_screen: Rect,
+}
+
 /// Rectangle in which to draw the sidebar
 fn sidebar_rect(term_dims: Dims, viewport_size: ViewportSize) -> Rect {
-    let viewport_rect = viewport_size.rect(term_dims);
-
-    match viewport_size {
-        ViewportSize::Regular => Rect {
-            left: viewport_rect.width + V_SCROLLBAR_WIDTH + 1,
-            top: HEADER_HEIGHT + 1,
-            width: term_dims.width - viewport_rect.width - 2,
-            height: term_dims.height - HEADER_HEIGHT,
-        },
-        ViewportSize::Theater => Rect {
-            left: sidebar_rect(term_dims, ViewportSize::Regular).left,
-            top: viewport_rect.bottom() + 2,
-            width: sidebar_rect(term_dims, ViewportSize::Regular).width,
-            height: term_dims.height - viewport_rect.height - 4,
-        },
-        ViewportSize::Fullscreen => Rect {
-            left: sidebar_rect(term_dims, ViewportSize::Regular).left,
-            top: term_dims.height,
-            width: sidebar_rect(term_dims, ViewportSize::Regular).width,
-            height: 1,
-        },
+    viewport_size.areas(term_dims).sidebar
+}
+
+/// `area` less the column on its right taken by the viewport's vertical scrollbar
+fn without_v_scrollbar(area: Area) -> Area {
+    Area {
+        width: area.width.saturating_sub(V_SCROLLBAR_WIDTH),
+        ..area
     }
 }
 
+fn full_screen_rect(term: Area) -> Rect {
+    let top = HEADER_HEIGHT + 1;
+    Rect::new(0, top, term.width, term.height.saturating_sub(top))
+}
+
 const H_SCROLLBAR_HEIGHT: u16 = 1;
 const V_SCROLLBAR_WIDTH: u16 = 1;
 
+/// How long an alert is shown before giving way to the next
+const ALERT_DURATION: Duration = Duration::from_secs(8);
+
+/// Draw `map`, then the marks just outside its viewport, along its bottom and right, showing where it's scrolled to
+async fn render_map_and_marks(
+    map: &mut Map,
+    game: &PlayerTurn<'_>,
+    screen: &mut Screen,
+    palette: &Palette,
+) -> IoResult<()> {
+    map.draw_no_flush(game, screen, palette).await?;
+
+    let viewport_rect = map.rect();
+    let marks = ScrollMarks::new(map, game.dims(), palette);
+    screen.render_widget(
+        marks,
+        Rect::new(
+            viewport_rect.left,
+            viewport_rect.top,
+            viewport_rect.width + V_SCROLLBAR_WIDTH,
+            viewport_rect.height + H_SCROLLBAR_HEIGHT,
+        ),
+    );
+    Ok(())
+}
+
+/// Render `widget` within `rect`, over whatever was there before
+fn render(screen: &mut Screen, widget: impl Widget, rect: Rect) {
+    screen.render_widget(Blank, rect);
+    screen.render_widget(widget, rect);
+}
+
+/// Start the thread that plays sounds, returning its handle and the sender by which sounds are passed to it
+fn spawn_audio_thread() -> (JoinHandle<()>, SyncSender<Sounds>) {
+    let (tx, rx) = sync_channel(2048);
+    let handle = thread::Builder::new()
+        .name("audio".to_string())
+        .spawn(move || {
+            play_sounds(rx, Sounds::Silence).unwrap();
+        })
+        .unwrap();
+    (handle, tx)
+}
+
 /// The terminal-based user interface.
 pub struct TermUI {
-    stdout: Stdout,
+    screen: Screen,
     term_dims: Dims,
     viewport_size: ViewportSize,
 
-    map_scroller: Scroller<Map>,
+    map: Map,
+
+    /// The map on the right when the viewport is split; its rect is empty otherwise
+    ///
+    /// Units, the cursor, and clicks all belong to the main map. This one is only for keeping an eye elsewhere.
+    second_map: Map,
+
+    /// Whether the viewport shift keys scroll the second map rather than the main one
+    second_map_focused: bool,
+
     log: LogArea,
     sidebar_buf: RectBuffer,
-    current_player: CurrentPlayer,
-    turn: Turn,
+
+    /// A screen drawn in place of the map, log, and sidebar, while one is open
+    full_screen_buf: Option<RectBuffer>,
+
+    /// The line a chat message is being typed into, if one is
+    chat_input: Option<ChatInput>,
+
+    /// How many chat messages have been logged so far
+    chat_received: usize,
+
+    /// The standing shown in the header, and the turn and player it was fetched for
+    standing: Option<(TurnNum, PlayerNum, Option<Standing>)>,
+
+    /// Set while text is being typed, so the input thread doesn't take the quit key as the end of input
+    typing: Arc<AtomicBool>,
+
+    /// Alerts yet to be dismissed, the one being shown first
+    alerts: VecDeque<Alert>,
+
+    /// When the alert being shown first appeared
+    alert_shown: Option<Instant>,
+
     palette: Palette,
-    unicode: bool,
-    confirm_turn_end: bool,
+    settings: Settings,
 
     /// Whether or not to use Crossterm's alternate screen. Useful to disable this when debugging messages are desired.
     use_alt_screen: bool,
 
-    /// Sender by which to send sound events to the audio thread (if not quieted)
+    /// Sender by which to send sound events to the audio thread (if sound was ever turned on)
     audio_thread_tx: Option<SyncSender<Sounds>>,
 
+    /// The theme music, if it was started
+    music: Option<MusicPlayer>,
+
     /// Receiver by which to get input events from the input thread
-    input_thread_rx: Mutex<Receiver<KeyEvent>>,
+    input_thread_rx: Mutex<Receiver<Input>>,
+
+    /// Terminal dimensions from a resize passed over while waiting for a key, to be laid out on the next draw
+    pending_term_dims: Mutex<Option<Dims>>,
 
     /// We need to keep the audio thread handle because the thread is killed when it goes out of scope.
     _audio_thread_handle: Option<JoinHandle<()>>,
@@ -460,32 +785,24 @@ impl TermUI {
     pub fn new(
         map_dims: Dims,
         palette: Palette,
-        unicode: bool,
-        confirm_turn_end: bool,
-        quiet: bool,
+        settings: Settings,
         use_alt_screen: bool,
-    ) -> Result<Self, crossterm::ErrorKind> {
+    ) -> IoResult<Self> {
         let (width, height) = terminal_size()?;
         let term_dims = Dims { width, height };
         // let term_dims = Dims::new(120, 60);
 
         let viewport_size = ViewportSize::Regular;
-        let viewport_rect = viewport_size.rect(term_dims);
-        let sidebar_rect = sidebar_rect(term_dims, viewport_size);
-
-        let map = Map::new(viewport_rect, map_dims, unicode);
-
-        // The scroller has the same dimensions as the scrolled, just draws over it
-        let map_scroller = Scroller::new(viewport_rect, map);
+        let areas = viewport_size.areas(term_dims);
 
-        let log_rect = log_area_rect(term_dims, viewport_size);
-        let log = LogArea::new(log_rect);
-
-        let cp_rect = current_player_rect();
-        let current_player = CurrentPlayer::new(cp_rect);
+        let map = Map::new(areas.viewport, map_dims, settings.unicode);
+        let second_map = Map::new(areas.second_viewport, map_dims, settings.unicode);
+        let log = LogArea::new(areas.log);
 
         // The input thread
         let (input_thread_tx, input_thread_rx) = channel();
+        let typing = Arc::new(AtomicBool::new(false));
+        let input_typing = Arc::clone(&typing);
         let input_thread_handle = thread::Builder::new()
             .name("input".to_string())
             .spawn(move || {
@@ -493,26 +810,31 @@ impl TermUI {
 
                 loop {
                     match read_event() {
-                        Ok(event) => {
-                            match event {
-                                Event::FocusGained => {}
-                                Event::FocusLost => {}
-                                Event::Paste(_) => {}
-                                Event::Key(key_event) => {
-                                    let will_return =
-                                        key_event.code == KeyCode::Char(conf::KEY_QUIT);
-                                    input_thread_tx.send(key_event).unwrap();
-
-                                    if will_return {
-                                        break;
-                                    }
+                        Ok(event) => match event {
+                            Event::FocusGained => {}
+                            Event::FocusLost => {}
+                            Event::Paste(_) => {}
+                            Event::Key(key_event) => {
+                                let will_return = key_event.code == KeyCode::Char(conf::KEY_QUIT)
+                                    && !input_typing.load(Ordering::Relaxed);
+                                input_thread_tx.send(Input::Key(key_event)).unwrap();
+
+                                if will_return {
+                                    break;
                                 }
-                                Event::Mouse(_mouse_event) => {}
-                                Event::Resize(_columns, _rows) => {
-                                    //TODO Handle resize events
+                            }
+                            Event::Mouse(mouse_event) => {
+                                if mouse_event.kind == MouseEventKind::Down(MouseButton::Left) {
+                                    let loc = Location::new(mouse_event.column, mouse_event.row);
+                                    input_thread_tx.send(Input::Click(loc)).unwrap();
                                 }
                             }
-                        }
+                            Event::Resize(columns, rows) => {
+                                input_thread_tx
+                                    .send(Input::Resize(Dims::new(columns, rows)))
+                                    .unwrap();
+                            }
+                        },
                         Err(err) => {
                             eprintln!("Error reading event: {}", err);
                             break;
@@ -525,47 +847,48 @@ impl TermUI {
             .unwrap();
 
         // The audio thread (if applicable)
-        let (audio_thread_handle, audio_thread_tx) = if !quiet {
-            let (tx, rx) = sync_channel(2048);
-            let handle = thread::Builder::new()
-                .name("audio".to_string())
-                .spawn(move || {
-                    play_sounds(rx, Sounds::Silence).unwrap();
-                })
-                .unwrap();
+        let (audio_thread_handle, audio_thread_tx) = if settings.sound {
+            let (handle, tx) = spawn_audio_thread();
             (Some(handle), Some(tx))
         } else {
             (None, None)
         };
 
-        let mut stdout = stdout();
+        let mut screen = Screen::new(stdout(), term_dims);
 
         if use_alt_screen {
-            queue!(stdout, EnterAlternateScreen).unwrap();
+            screen.queue_raw(EnterAlternateScreen).unwrap();
         }
+        screen.queue_raw(EnableMouseCapture).unwrap();
 
         let mut ui = Self {
-            stdout,
+            screen,
             term_dims,
             viewport_size,
 
-            map_scroller,
+            map,
+            second_map,
+            second_map_focused: false,
             log,
-            sidebar_buf: RectBuffer::new(sidebar_rect),
-            current_player,
-
-            turn: Turn::new(turn_rect(cp_rect)),
+            sidebar_buf: RectBuffer::new(areas.sidebar),
+            full_screen_buf: None,
+            chat_input: None,
+            chat_received: 0,
+            standing: None,
+            typing,
+            alerts: VecDeque::new(),
+            alert_shown: None,
 
             palette,
 
-            unicode,
-
-            confirm_turn_end,
+            settings,
 
             use_alt_screen,
 
             audio_thread_tx,
+            music: None,
             input_thread_rx: Mutex::new(input_thread_rx),
+            pending_term_dims: Mutex::new(None),
             _audio_thread_handle: audio_thread_handle,
             _input_thread_handle: input_thread_handle,
         };
@@ -575,13 +898,206 @@ impl TermUI {
         Ok(ui)
     }
 
+    /// Show what befell `player` over the turn just ended, boxed over the middle of the map
+    ///
+    /// The panel stays up until the map is next drawn, so the summary is logged too.
+    pub async fn show_turn_summary(
+        &mut self,
+        game: &PlayerTurn<'_>,
+        player: PlayerNum,
+        turn: TurnNum,
+        summary: TurnSummary,
+    ) -> IoResult<()> {
+        self.log_message(Message {
+            text: format!(
+                "Turn {} summary: {} units produced, {} battles won, {} lost, {} cities gained, {} lost, {} tiles explored",
+                turn,
+                summary.units_produced,
+                summary.battles_won,
+                summary.battles_lost,
+                summary.cities_gained,
+                summary.cities_lost,
+                summary.tiles_explored
+            ),
+            mark: Some('Σ'),
+            fg_color: Some(Colors::Text),
+            bg_color: None,
+            source: Some(MessageSource::UI),
+        });
+        self.draw_log(game).await?;
+
+        if self.full_screen_buf.is_some() {
+            return self.screen.flush();
+        }
+
+        let viewport_rect = self.viewport_rect();
+        let width = SUMMARY_WIDTH.min(viewport_rect.width);
+        let height = SUMMARY_HEIGHT.min(viewport_rect.height);
+        let rect = Rect::new(
+            viewport_rect.left + (viewport_rect.width - width) / 2,
+            viewport_rect.top + (viewport_rect.height - height) / 2,
+            width,
+            height,
+        );
+
+        let panel = TurnSummaryPanel::new(player, turn, summary);
+        render(&mut self.screen, panel.widget(&self.palette), rect);
+        self.screen.flush()?;
+
+        // Uncover the map the next time it's drawn
+        self.map.invalidate_rect(rect);
+        self.second_map.invalidate_rect(rect);
+        Ok(())
+    }
+
+    /// Show the first alert waiting, once any that has been up long enough is let go
+    ///
+    /// Alerts only expire as the map is redrawn, so one can't vanish before the user has had a chance to act.
+    fn draw_toast(&mut self) {
+        let now = Instant::now();
+        if let Some(shown) = self.alert_shown {
+            if now.duration_since(shown) >= ALERT_DURATION {
+                self.alerts.pop_front();
+                self.alert_shown = None;
+            }
+        }
+
+        let alert = match self.alerts.front() {
+            Some(alert) => alert,
+            None => return,
+        };
+        self.alert_shown.get_or_insert(now);
+
+        let toast = Toast::new(self.viewport_rect(), alert, self.alerts.len() - 1);
+        render(&mut self.screen, toast.widget(&self.palette), toast.rect());
+
+        // The map beneath shows through again once the toast is gone
+        self.map.invalidate_rect(toast.rect());
+        self.second_map.invalidate_rect(toast.rect());
+    }
+
+    /// The header for the current player and turn
+    ///
+    /// The player's standing is fetched once a turn rather than on every draw, its counts from the turn's sync.
+    async fn header(&mut self, game: &PlayerTurn<'_>) -> Header {
+        let player = game.current_player().await;
+        let turn = game.turn().await;
+
+        let standing = match self.standing {
+            Some((standing_turn, standing_player, standing))
+                if standing_turn == turn && standing_player == player =>
+            {
+                standing
+            }
+            _ => {
+                // Only a player can ask after their own score, so there's none to show while spectating
+                let standing = match game.player_score().await {
+                    Ok(score) => Some(Standing {
+                        score,
+                        cities: game.synced_cities().count(),
+                        units: game.synced_units().count(),
+                    }),
+                    Err(_) => None,
+                };
+                self.standing = Some((turn, player, standing));
+                standing
+            }
+        };
+
+        Header::new(player, turn, standing, &self.palette)
+    }
+
+    /// Render the header, the log, the map, and the sidebar, or whatever of them is shown
+    async fn render_all(&mut self, game: &PlayerTurn<'_>) -> IoResult<()> {
+        let areas = self.viewport_size.areas(self.term_dims);
+
+        if areas.header.height > 0 {
+            let header = self.header(game).await;
+            render(&mut self.screen, header, areas.header);
+        }
+
+        if let Some(full_screen_buf) = self.full_screen_buf.as_ref() {
+            let widget = full_screen_buf.widget(&self.palette);
+            render(&mut self.screen, widget, full_screen_buf.rect());
+            return Ok(());
+        }
+
+        self.render_log();
+        self.render_map(game).await?;
+        let sidebar = self.sidebar_buf.widget(&self.palette);
+        render(&mut self.screen, sidebar, self.sidebar_buf.rect());
+        Ok(())
+    }
+
+    fn render_log(&mut self) {
+        render(
+            &mut self.screen,
+            self.log.widget(&self.palette),
+            self.log.rect(),
+        );
+
+        if let Some(chat_input) = self.chat_input.as_ref() {
+            render(
+                &mut self.screen,
+                chat_input.widget(&self.palette),
+                chat_input.rect(),
+            );
+        }
+    }
+
+    async fn render_map(&mut self, game: &PlayerTurn<'_>) -> IoResult<()> {
+        render_map_and_marks(&mut self.map, game, &mut self.screen, &self.palette).await?;
+
+        if self.second_map.rect().width > 0 {
+            render_map_and_marks(&mut self.second_map, game, &mut self.screen, &self.palette)
+                .await?;
+        }
+
+        self.draw_toast();
+        Ok(())
+    }
+
+    /// Cover the screen with how the game went for each player, until a key is pressed
+    pub async fn show_game_stats(
+        &mut self,
+        game: &PlayerTurn<'_>,
+        game_over: GameOver,
+        scores: &[f64],
+        histories: &[PlayerHistory],
+    ) -> IoResult<()> {
+        self.open_full_screen();
+        let rows = stats_rows(
+            game_over,
+            scores,
+            histories,
+            self.term_dims.width as usize,
+            self.settings.unicode,
+        );
+        for (i, row) in rows.into_iter().enumerate() {
+            self.set_full_screen_row(i, row);
+        }
+        self.draw(game).await?;
+
+        // The input thread may already be gone if we're quitting; either way we're done here
+        self.get_key().ok();
+
+        self.close_full_screen();
+        Ok(())
+    }
+
+    /// Take charge of the theme music, so its volume follows the settings from here on
+    pub fn set_music(&mut self, music: MusicPlayer) {
+        music.set_volume(self.settings.game_music_volume());
+        self.music = Some(music);
+    }
+
     fn clear(&mut self) {
         // write!(self.stdout, "{}", clear::All).unwrap();
         // self.stdout.queue(Clear(ClearType::All));
-        queue!(
-            self.stdout,
-            Clear(ClearType::All),
-            SetBackgroundColor(self.palette.get_single(Colors::Background))
+        paint!(
+            self.screen,
+            SetBackgroundColor(self.palette.get_single(Colors::Background)),
+            Clear(ClearType::All)
         )
         .unwrap();
 
@@ -599,21 +1115,47 @@ impl TermUI {
         viewport_size: ViewportSize,
     ) -> IoResult<()> {
         self.viewport_size = viewport_size;
+        self.layout();
+        self.draw(game).await
+    }
 
+    /// Fit every component to the current terminal dimensions and viewport size
+    fn layout(&mut self) {
         self.clear();
 
-        self.log
-            .set_rect(log_area_rect(self.term_dims, self.viewport_size));
+        let areas = self.viewport_size.areas(self.term_dims);
+
+        if let Some(chat_input) = self.chat_input.as_mut() {
+            let log_rect = Rect {
+                height: areas.log.height.saturating_sub(1),
+                ..areas.log
+            };
+            chat_input.set_rect(Rect::new(
+                log_rect.left,
+                log_rect.bottom(),
+                log_rect.width,
+                1,
+            ));
+            self.log.set_rect(log_rect);
+        } else {
+            self.log.set_rect(areas.log);
+        }
 
-        self.map_scroller
-            .set_rect(self.viewport_size.rect(self.term_dims));
+        self.map.set_rect(areas.viewport);
+        self.second_map.set_rect(areas.second_viewport);
 
-        self.sidebar_buf
-            .set_rect(sidebar_rect(self.term_dims, self.viewport_size));
+        self.sidebar_buf.set_rect(areas.sidebar);
 
-        self.sidebar_buf.dirty();
+        if let Some(full_screen_buf) = self.full_screen_buf.as_mut() {
+            full_screen_buf.set_rect(areas.full_screen);
+        }
+    }
 
-        self.draw(game).await
+    /// Keep a resize that arrived while only keys were wanted, so the next draw can lay it out
+    fn defer_resize(&self, input: Input) {
+        if let Input::Resize(term_dims) = input {
+            *self.pending_term_dims.lock().unwrap() = Some(term_dims);
+        }
     }
 
     async fn draw_located_observations(
@@ -622,20 +1164,16 @@ impl TermUI {
         located_obs: &[LocatedObs],
     ) -> IoResult<()> {
         for located_obs in located_obs {
-            if let Some(viewport_loc) = self
-                .map_scroller
-                .scrollable
-                .map_to_viewport_coords(located_obs.loc)
-            {
+            if let Some(viewport_loc) = self.map.map_to_viewport_coords(located_obs.loc) {
                 // let (city,unit) = if let Obs::Observed{ref tile,..} = located_obs.item {
                 //     (Some(tile.city.as_ref()), Some(tile.unit.as_ref()))
                 // } else {
                 //     (Some(None),Some(None))
                 // };
 
-                self.map_scroller.scrollable.draw_tile_no_flush(
+                self.map.draw_tile_no_flush(
                     game,
-                    &mut self.stdout,
+                    &mut self.screen,
                     viewport_loc,
                     false,
                     false,
@@ -657,12 +1195,15 @@ impl TermUI {
         attacker_loc: Location,
         defender_loc: Location,
     ) -> IoResult<()> {
-        let map = &mut self.map_scroller.scrollable;
+        self.play_sound(Sounds::Effect(Effect::Combat));
+
+        let animation_speed = self.settings.animation_speed;
+        let map = &mut self.map;
 
         let attacker_viewport_loc = map.map_to_viewport_coords(attacker_loc);
         let defender_viewport_loc = map.map_to_viewport_coords(defender_loc);
-        let attacker_sym = outcome.attacker().sym(self.unicode);
-        let defender_sym = outcome.defender().sym(self.unicode);
+        let attacker_sym = outcome.attacker().sym(self.settings.unicode);
+        let defender_sym = outcome.defender().sym(self.settings.unicode);
 
         for damage_recipient in outcome.received_damage_sequence() {
             let viewport_loc = match *damage_recipient {
@@ -677,7 +1218,7 @@ impl TermUI {
             if let Some(viewport_loc) = viewport_loc {
                 map.draw_tile_and_flush(
                     game,
-                    &mut self.stdout,
+                    &mut self.screen,
                     viewport_loc,
                     true,
                     false,
@@ -687,10 +1228,10 @@ impl TermUI {
                     None,
                     &self.palette,
                 )?;
-                sleep_millis(100);
+                animation_speed.pause(100);
                 map.draw_tile_and_flush(
                     game,
-                    &mut self.stdout,
+                    &mut self.screen,
                     viewport_loc,
                     false,
                     false,
@@ -701,21 +1242,70 @@ impl TermUI {
                     &self.palette,
                 )?;
             } else {
-                sleep_millis(100);
+                animation_speed.pause(100);
             }
         }
 
         Ok(())
     }
 
-    fn ensure_map_loc_visible(&mut self, map_loc: Location) {
-        self.map_scroller
-            .scrollable
-            .center_viewport_if_not_visible(map_loc);
+    /// How many chat messages have been received, so those after can be waited on
+    pub fn chat_received(&self) -> usize {
+        self.chat_received
     }
 
-    fn map(&self) -> &Map {
-        &self.map_scroller.scrollable
+    /// Log chat messages that came after the first `chat_received`, as when waited on
+    pub async fn receive_chat_messages(
+        &mut self,
+        game: &PlayerTurn<'_>,
+        messages: Vec<ChatMessage>,
+    ) -> IoResult<()> {
+        if messages.is_empty() {
+            return Ok(());
+        }
+
+        self.chat_received += messages.len();
+        self.log_chat(messages);
+        self.draw_log(game).await
+    }
+
+    /// Log the chat messages brought by syncing at the start of the turn, skipping those already received
+    async fn receive_synced_chat(
+        &mut self,
+        game: &PlayerTurn<'_>,
+        messages: Vec<ChatMessage>,
+    ) -> IoResult<()> {
+        let synced = game.chat_synced();
+        let first = synced - messages.len();
+
+        let unseen: Vec<ChatMessage> = messages
+            .into_iter()
+            .skip(self.chat_received.saturating_sub(first))
+            .collect();
+        if unseen.is_empty() {
+            return Ok(());
+        }
+
+        self.chat_received = self.chat_received.max(synced);
+        self.log_chat(unseen);
+        self.draw_log(game).await
+    }
+
+    fn log_chat(&mut self, messages: Vec<ChatMessage>) {
+        for message in messages {
+            self.log_message(Message {
+                text: match message.sender_name {
+                    Some(ref name) => {
+                        format!("{} (Player {}): {}", name, message.sender, message.text)
+                    }
+                    None => format!("Player {}: {}", message.sender, message.text),
+                },
+                mark: Some('"'),
+                fg_color: Some(Colors::Player(message.sender)),
+                bg_color: None,
+                source: Some(MessageSource::Chat),
+            });
+        }
     }
 }
 
@@ -763,6 +1353,16 @@ impl MoveAnimator for TermUI {
                 was_combat = true;
             }
 
+            if move_.struck_mine {
+                self.log_message(Message {
+                    text: format!("Unit {} struck a mine at {}", move_result.unit, target_loc),
+                    mark: Some('*'),
+                    fg_color: Some(Colors::Combat),
+                    bg_color: None,
+                    source: Some(MessageSource::UI),
+                });
+            }
+
             if move_.distance_moved() > 0 {
                 self.log_message(Message {
                     text: format!(
@@ -790,15 +1390,19 @@ impl MoveAnimator for TermUI {
 
             current_loc = target_loc;
 
-            self.stdout.flush().unwrap();
+            self.screen.flush().unwrap();
 
             if move_idx < move_result.components.len() - 1 {
-                sleep_millis(100);
+                self.settings.animation_speed.pause(100);
             }
         }
 
+        if move_result.conquered_city().is_some() {
+            self.play_sound(Sounds::Effect(Effect::CityCaptured));
+        }
+
         if move_result.unit.moves_remaining() == 0 {
-            sleep_millis(250);
+            self.settings.animation_speed.pause(250);
         }
 
         Ok(())
@@ -808,7 +1412,7 @@ impl MoveAnimator for TermUI {
 #[async_trait]
 impl UI for TermUI {
     fn viewport_rect(&self) -> Rect {
-        self.viewport_size.rect(self.term_dims)
+        self.viewport_size.areas(self.term_dims).viewport
     }
 
     fn viewport_size(&self) -> ViewportSize {
@@ -819,12 +1423,22 @@ impl UI for TermUI {
         self.term_dims
     }
 
+    fn set_term_dims(&mut self, term_dims: Dims) {
+        self.term_dims = term_dims;
+        self.screen.resize(term_dims);
+        self.layout();
+    }
+
     fn unicode(&self) -> bool {
-        self.unicode
+        self.settings.unicode
     }
 
     fn center_map(&mut self, map_loc: Location) {
-        self.map_scroller.scrollable.center_viewport(map_loc);
+        self.map.center_viewport(map_loc);
+    }
+
+    fn ensure_map_loc_visible(&mut self, map_loc: Location) {
+        self.map.center_viewport_if_not_visible(map_loc);
     }
 
     fn clear_sidebar(&mut self) {
@@ -833,8 +1447,12 @@ impl UI for TermUI {
 
     async fn cursor_map_loc(&self, mode: &Mode, game: &PlayerTurn) -> Option<Location> {
         match *mode {
-            Mode::SetProduction { city_loc } => Some(city_loc),
-            Mode::GetUnitOrders { unit_id, .. } => {
+            Mode::SetProduction { city_loc, .. } => Some(city_loc),
+            Mode::GetUnitOrders { unit_id, .. }
+            | Mode::Groups {
+                unit_id: Some(unit_id),
+                ..
+            } => {
                 let unit_loc = game.player_unit_loc(unit_id).await.unwrap();
                 Some(unit_loc)
             }
@@ -843,11 +1461,15 @@ impl UI for TermUI {
     }
 
     async fn cursor_viewport_loc(&self, mode: &Mode, game: &PlayerTurn) -> Option<Location> {
-        let map = &self.map_scroller.scrollable;
+        let map = &self.map;
 
         match *mode {
-            Mode::SetProduction { city_loc } => map.map_to_viewport_coords(city_loc),
-            Mode::GetUnitOrders { unit_id, .. } => {
+            Mode::SetProduction { city_loc, .. } => map.map_to_viewport_coords(city_loc),
+            Mode::GetUnitOrders { unit_id, .. }
+            | Mode::Groups {
+                unit_id: Some(unit_id),
+                ..
+            } => {
                 let unit_loc = game.player_unit_loc(unit_id).await.unwrap();
                 map.map_to_viewport_coords(unit_loc)
             }
@@ -860,35 +1482,69 @@ impl UI for TermUI {
         ctrl: &'a PlayerTurn,
         viewport_loc: Location,
     ) -> Option<Cow<'a, Tile>> {
-        self.map_scroller
-            .scrollable
-            .current_player_tile(ctrl, viewport_loc)
-            .await
+        self.map.current_player_tile(ctrl, viewport_loc).await
     }
 
     async fn draw_current_player(&mut self, ctrl: &PlayerTurn) -> IoResult<()> {
-        self.current_player
-            .draw(ctrl, &mut self.stdout, &self.palette)
-            .await
+        let header_rect = self.viewport_size.areas(self.term_dims).header;
+        if header_rect.height > 0 {
+            let header = self.header(ctrl).await;
+            render(&mut self.screen, header, header_rect);
+        }
+        self.screen.flush()
     }
 
-    async fn draw_log(&mut self, ctrl: &PlayerTurn) -> IoResult<()> {
-        self.log.draw(ctrl, &mut self.stdout, &self.palette).await // this will flush
+    async fn draw_log(&mut self, _ctrl: &PlayerTurn) -> IoResult<()> {
+        if self.full_screen_buf.is_some() {
+            return Ok(());
+        }
+        self.render_log();
+        self.screen.flush()
     }
 
     async fn draw_map(&mut self, ctrl: &PlayerTurn) -> IoResult<()> {
-        self.map_scroller
-            .draw(ctrl, &mut self.stdout, &self.palette)
-            .await
+        if self.full_screen_buf.is_some() {
+            return Ok(());
+        }
+        self.render_map(ctrl).await?;
+        self.screen.flush()
     }
 
     fn confirm_turn_end(&self) -> bool {
-        self.confirm_turn_end
+        self.settings.confirm_turn_end
+    }
+
+    fn settings(&self) -> Settings {
+        self.settings
+    }
+
+    fn set_settings(&mut self, settings: Settings) {
+        if settings.unicode != self.settings.unicode {
+            self.map.set_unicode(settings.unicode);
+            self.second_map.set_unicode(settings.unicode);
+        }
+
+        if settings.sound && self.audio_thread_tx.is_none() {
+            let (handle, tx) = spawn_audio_thread();
+            self._audio_thread_handle = Some(handle);
+            self.audio_thread_tx = Some(tx);
+        }
+
+        if let Some(music) = self.music.as_ref() {
+            music.set_volume(settings.game_music_volume());
+        }
+
+        self.settings = settings;
     }
 
     async fn draw(&mut self, game: &PlayerTurn) -> IoResult<()> {
+        let pending_term_dims = self.pending_term_dims.lock().unwrap().take();
+        if let Some(term_dims) = pending_term_dims {
+            self.set_term_dims(term_dims);
+        }
+
         self.draw_no_flush(game).await?;
-        self.stdout.flush()
+        self.screen.flush()
     }
 
     async fn draw_map_tile_and_flush(
@@ -911,9 +1567,9 @@ impl UI for TermUI {
         // observations.
         obs_override: Option<&Obs>,
     ) -> IoResult<()> {
-        self.map_scroller.scrollable.draw_tile_and_flush(
+        self.map.draw_tile_and_flush(
             game,
-            &mut self.stdout,
+            &mut self.screen,
             viewport_loc,
             highlight,
             unit_active,
@@ -926,56 +1582,49 @@ impl UI for TermUI {
     }
 
     async fn draw_no_flush(&mut self, game: &PlayerTurn) -> IoResult<()> {
-        // write!(self.stdout, "{}{}{}{}",
-        //     // termion::clear::All,
-        //     goto(0,0),
-        //     termion::style::Underline,
-        //     conf::APP_NAME,
-        //     StrongReset::new(&self.palette)
-        // ).unwrap();
-        queue!(
-            self.stdout,
-            MoveTo(0, 0),
-            SetAttribute(Attribute::Underlined),
-            Print(conf::APP_NAME.to_string()),
-            SetAttribute(Attribute::Reset),
-            SetBackgroundColor(self.palette.get_single(Colors::Background))
-        )?;
-
-        self.log
-            .draw_no_flush(game, &mut self.stdout, &self.palette)
-            .await?;
-        self.current_player
-            .draw_no_flush(game, &mut self.stdout, &self.palette)
-            .await?;
-        self.map_scroller
-            .draw_no_flush(game, &mut self.stdout, &self.palette)
-            .await?;
-        self.turn
-            .draw_no_flush(game, &mut self.stdout, &self.palette)
-            .await?;
-        self.sidebar_buf
-            .draw_no_flush(game, &mut self.stdout, &self.palette)
-            .await?;
-
-        // write!(self.stdout, "{}{}", StrongReset::new(&self.palette), termion::cursor::Hide).unwrap();
-        queue!(
-            self.stdout,
+        self.render_all(game).await?;
+
+        paint!(
+            self.screen,
             SetAttribute(Attribute::Reset),
             SetBackgroundColor(self.palette.get_single(Colors::Background)),
             Hide
-        )?;
-
-        Ok(())
+        )
     }
 
     /// Block until a key is pressed; return that key
     fn get_key(&self) -> Result<KeyEvent, RecvError> {
+        let rx = self.input_thread_rx.lock().unwrap();
+        loop {
+            match rx.recv()? {
+                Input::Key(key) => return Ok(key),
+                input => self.defer_resize(input),
+            }
+        }
+    }
+
+    fn get_key_timeout(&self, timeout: Duration) -> Result<KeyEvent, RecvTimeoutError> {
+        let deadline = Instant::now() + timeout;
+        let rx = self.input_thread_rx.lock().unwrap();
+        loop {
+            let remaining = deadline.saturating_duration_since(Instant::now());
+            match rx.recv_timeout(remaining)? {
+                Input::Key(key) => return Ok(key),
+                input => self.defer_resize(input),
+            }
+        }
+    }
+
+    fn get_input(&self) -> Result<Input, RecvError> {
         self.input_thread_rx.lock().unwrap().recv()
     }
 
+    fn get_input_timeout(&self, timeout: Duration) -> Result<Input, RecvTimeoutError> {
+        self.input_thread_rx.lock().unwrap().recv_timeout(timeout)
+    }
+
     fn map_to_viewport_coords(&self, map_loc: Location) -> Option<Location> {
-        self.map_scroller.scrollable.map_to_viewport_coords(map_loc)
+        self.map.map_to_viewport_coords(map_loc)
     }
 
     // /// Return Some(key) if a key from the input thread is waiting for us, otherwise return None
@@ -984,6 +1633,10 @@ impl UI for TermUI {
     // }
 
     fn play_sound(&self, sound: Sounds) {
+        if !self.settings.sound {
+            return;
+        }
+
         if let Some(tx) = self.audio_thread_tx.as_ref() {
             tx.send(sound).unwrap();
         }
@@ -996,30 +1649,118 @@ impl UI for TermUI {
     async fn rotate_viewport_size(&mut self, game: &PlayerTurn) -> IoResult<()> {
         let new_size = match self.viewport_size {
             ViewportSize::Regular => ViewportSize::Theater,
-            ViewportSize::Theater => ViewportSize::Fullscreen,
+            ViewportSize::Theater => ViewportSize::Split,
+            ViewportSize::Split => ViewportSize::Fullscreen,
             ViewportSize::Fullscreen => ViewportSize::Regular,
         };
 
+        match new_size {
+            // The second map starts out looking where the main one does
+            ViewportSize::Split => self.second_map.set_viewport_offset(self.map.offset()),
+            _ => self.second_map_focused = false,
+        }
+
         self.set_viewport_size(game, new_size).await?;
         self.draw(game).await
     }
 
     fn scroll_map_relative<V: Into<Vec2d<i32>>>(&mut self, direction: V) {
-        self.map_scroller
-            .scrollable
-            .scroll_relative(direction.into());
+        if self.second_map_focused {
+            self.second_map.scroll_relative(direction.into());
+        } else {
+            self.map.scroll_relative(direction.into());
+        }
+    }
+
+    fn switch_viewport(&mut self) {
+        if let ViewportSize::Split = self.viewport_size {
+            self.second_map_focused = !self.second_map_focused;
+        }
+    }
+
+    fn set_reachable(&mut self, reachable: BTreeSet<Location>) {
+        self.second_map.set_reachable(reachable.clone());
+        self.map.set_reachable(reachable);
     }
 
     fn set_sidebar_row(&mut self, row_idx: usize, row: String) {
         self.sidebar_buf.set_row(row_idx, row)
     }
 
+    fn open_full_screen(&mut self) {
+        if self.full_screen_buf.is_none() {
+            self.clear();
+            let areas = self.viewport_size.areas(self.term_dims);
+            self.full_screen_buf = Some(RectBuffer::new(areas.full_screen));
+        }
+    }
+
+    fn close_full_screen(&mut self) {
+        if self.full_screen_buf.take().is_some() {
+            self.layout();
+        }
+    }
+
+    fn full_screen_height(&self) -> usize {
+        self.full_screen_buf
+            .as_ref()
+            .map_or(0, |buf| buf.rect().height as usize)
+    }
+
+    fn set_full_screen_row(&mut self, row_idx: usize, row: String) {
+        if let Some(full_screen_buf) = self.full_screen_buf.as_mut() {
+            if row_idx < full_screen_buf.rect().height as usize {
+                full_screen_buf.set_row(row_idx, row);
+            }
+        }
+    }
+
+    fn open_chat_input(&mut self) {
+        if self.chat_input.is_none() {
+            self.chat_input = Some(ChatInput::new(Rect::new(0, 0, 0, 0)));
+            self.typing.store(true, Ordering::Relaxed);
+            self.layout();
+        }
+    }
+
+    fn close_chat_input(&mut self) {
+        if self.chat_input.take().is_some() {
+            self.typing.store(false, Ordering::Relaxed);
+            self.layout();
+        }
+    }
+
+    fn set_chat_input(&mut self, text: String) {
+        if let Some(chat_input) = self.chat_input.as_mut() {
+            chat_input.set_text(text);
+        }
+    }
+
+    async fn receive_chat(&mut self, game: &PlayerTurn) -> IoResult<()> {
+        // Only players can read the chat, so there's nothing to receive while spectating
+        match game.chat_messages_since(self.chat_received).await {
+            Ok(messages) => self.receive_chat_messages(game, messages).await,
+            Err(_) => Ok(()),
+        }
+    }
+
+    fn set_alerts(&mut self, alerts: Vec<Alert>) {
+        self.alerts = alerts.into();
+        self.alert_shown = None;
+    }
+
+    fn jump_to_alert(&mut self) -> Option<Location> {
+        let alert = self.alerts.pop_front()?;
+        self.alert_shown = None;
+        Some(alert.loc())
+    }
+
     async fn viewport_to_map_coords(
         &self,
         game: &PlayerTurn,
         viewport_loc: Location,
     ) -> Option<Location> {
-        self.map().viewport_to_map_coords(game, viewport_loc)
+        self.map.viewport_to_map_coords(game, viewport_loc)
     }
 }
 
@@ -1036,6 +1777,11 @@ impl TurnTaker for TermUI {
             //FIXME Code smell: refused bequest
         }
 
+        // Catch up on what changed since last turn. Only players can sync, so there's nothing to do while spectating
+        if let Ok(messages) = ctrl.sync_turn().await {
+            self.receive_synced_chat(ctrl, messages).await.unwrap();
+        }
+
         let mut prev_mode: Option<Mode> = None;
         let mut mode = self::mode::Mode::TurnStart;
 
@@ -1063,9 +1809,11 @@ impl TurnTaker for TermUI {
 
 impl Drop for TermUI {
     fn drop(&mut self) {
+        self.screen.queue_raw(DisableMouseCapture).unwrap();
+
         if self.use_alt_screen {
-            queue!(self.stdout, LeaveAlternateScreen).unwrap();
-            queue!(self.stdout, Show).unwrap();
+            self.screen.queue_raw(LeaveAlternateScreen).unwrap();
+            self.screen.queue_raw(Show).unwrap();
         }
 
         if let Some(ref tx) = self.audio_thread_tx {
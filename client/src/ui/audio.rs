@@ -13,14 +13,34 @@ use synth::{oscillator, Envelope, Oscillator, Point, Synth};
 use thiserror::Error;
 
 const CHANNELS: i32 = 2;
-const SAMPLE_HZ: f64 = 44_100.0;
+pub(in crate::ui) const SAMPLE_HZ: f64 = 44_100.0;
 
 pub enum Sounds {
     Silence,
     Unit(UnitType),
+    Effect(Effect),
     // Intro,
 }
 
+/// Short sounds played once when something happens, rather than held like a unit's tone
+#[derive(Clone, Copy)]
+pub enum Effect {
+    Combat,
+    UnitProduced,
+    CityCaptured,
+}
+
+impl Effect {
+    /// How long the effect takes to fade out, in milliseconds
+    fn duration(&self) -> f64 {
+        match self {
+            Self::Combat => 400.0,
+            Self::UnitProduced => 300.0,
+            Self::CityCaptured => 900.0,
+        }
+    }
+}
+
 pub(in crate::ui) trait Noisy {
     fn freqs(&self) -> Vec<f32>;
     fn volume(&self) -> f32;
@@ -48,6 +68,11 @@ impl Noisy for UnitType {
                 LetterOctave(Letter::Db, 1).hz(),
             ],
             UnitType::Carrier => vec![LetterOctave(Letter::C, 1).hz()],
+            UnitType::Artillery => vec![LetterOctave(Letter::G, 2).hz()],
+            UnitType::Helicopter => vec![LetterOctave(Letter::E, 3).hz()],
+            UnitType::Paratrooper => vec![LetterOctave(Letter::A, 3).hz()],
+            UnitType::Missile => vec![LetterOctave(Letter::B, 4).hz()],
+            UnitType::Engineer => vec![LetterOctave(Letter::D, 3).hz()],
         }
     }
 
@@ -63,20 +88,55 @@ impl Noisy for UnitType {
             UnitType::Cruiser => 0.15,
             UnitType::Battleship => 0.15,
             UnitType::Carrier => 0.15,
+            UnitType::Artillery => 0.05,
+            UnitType::Helicopter => 0.1,
+            UnitType::Paratrooper => 0.0,
+            UnitType::Missile => 0.1,
+            UnitType::Engineer => 0.0,
         }
     }
 }
 
-fn synth_for_sound(
-    sound: Sounds,
-) -> Synth<
+impl Noisy for Effect {
+    fn freqs(&self) -> Vec<f32> {
+        match self {
+            // A low tritone, for a suitably unsettling clash
+            Self::Combat => vec![
+                LetterOctave(Letter::C, 1).hz(),
+                LetterOctave(Letter::Gb, 1).hz(),
+            ],
+            Self::UnitProduced => vec![
+                LetterOctave(Letter::C, 4).hz(),
+                LetterOctave(Letter::E, 4).hz(),
+                LetterOctave(Letter::G, 4).hz(),
+            ],
+            Self::CityCaptured => vec![
+                LetterOctave(Letter::C, 3).hz(),
+                LetterOctave(Letter::G, 3).hz(),
+                LetterOctave(Letter::C, 4).hz(),
+            ],
+        }
+    }
+
+    fn volume(&self) -> f32 {
+        match self {
+            Self::Combat => 0.2,
+            Self::UnitProduced => 0.1,
+            Self::CityCaptured => 0.15,
+        }
+    }
+}
+
+type SoundSynth = Synth<
     synth::instrument::mode::Poly,
     (),
     synth::oscillator::waveform::Square,
     Envelope,
     Envelope,
     (),
-> {
+>;
+
+fn synth_for_sound(sound: Sounds) -> SoundSynth {
     match sound {
         Sounds::Silence => Synth::poly(()),
         Sounds::Unit(unit_type) => {
@@ -140,6 +200,31 @@ fn synth_for_sound(
             } else {
                 Synth::poly(())
             }
+        }
+        Sounds::Effect(effect) => {
+            let volume = effect.volume();
+
+            // Start at full volume and die away, played once rather than looped
+            let amp_env = Envelope::from(vec![
+                Point::new(0.0, volume.into(), 0.0),
+                Point::new(1.0, 0.0, 0.0),
+            ]);
+            let freq_env =
+                Envelope::from(vec![Point::new(0.0, 0.0, 0.0), Point::new(1.0, 0.0, 0.0)]);
+
+            let oscillator = Oscillator::new(oscillator::waveform::Square, amp_env, freq_env, ());
+
+            let mut synth = Synth::poly(())
+                .oscillator(oscillator)
+                .duration(effect.duration())
+                .num_voices(4)
+                .volume(volume);
+
+            for freq in effect.freqs() {
+                synth.note_on(freq, 1.0);
+            }
+
+            synth
         } // Sounds::Intro => {
           //     unimplemented!();
           // }
@@ -148,6 +233,9 @@ fn synth_for_sound(
 
 #[derive(Debug, Error)]
 pub enum AudioError {
+    #[error("No default output device")]
+    NoOutputDevice,
+
     #[error("Error building output stream")]
     CantBuildOutputStream,
 
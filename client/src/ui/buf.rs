@@ -1,137 +1,61 @@
-use std::{
-    collections::BTreeSet,
-    io::{Result as IoResult, Stdout},
+use ratatui::{
+    style::Style,
+    text::Line,
+    widgets::{Paragraph, Widget},
 };
 
-use async_trait::async_trait;
+use common::{colors::Colors, util::Rect};
 
-use crossterm::{
-    cursor::MoveTo,
-    queue,
-    style::{style, PrintStyledContent},
-};
-
-use common::{game::player::PlayerTurn, util::Rect};
-
-use umpire_tui::{color::Palette, Component, Draw};
+use umpire_tui::color::Palette;
 
-/// A buffer to help with smooth drawing of rectangular regions
+/// Rows of text filling a rectangular region
 ///
-/// Instead of having different bits of code clear and write over the same region, leading to a flickering effect,
-/// we make one piece of code responsible for drawing on one region. Clients update the buffer contents and tell
-/// the buffer to draw, but the buffer works out the most efficient way to do the drawing without flickering.
+/// Different bits of code update the rows, and the whole region is rendered from them at once, so nothing is left
+/// over from whatever was drawn there before.
 pub(in crate::ui) struct RectBuffer {
     rect: Rect,
     rows: Vec<Option<String>>,
-    dirty_rows: BTreeSet<usize>,
-    blank_row: String,
 }
 impl RectBuffer {
     pub fn new(rect: Rect) -> Self {
-        let blank_row: String = (0..rect.width).map(|_| " ").collect();
         Self {
             rect,
             rows: (0..rect.height).map(|_| None).collect(),
-            dirty_rows: BTreeSet::new(),
-            blank_row,
         }
     }
 
     pub fn set_row(&mut self, row_idx: usize, row: String) {
-        self.set(row_idx, Some(row));
+        self.rows[row_idx] = Some(row);
     }
 
     pub fn clear(&mut self) {
-        for row_idx in 0..self.rect.height {
-            self.clear_row(row_idx as usize);
-        }
-    }
-
-    /// Mark all rows as dirty and in need of redrawing
-    pub fn dirty(&mut self) {
-        for i in 0..self.rows.len() {
-            self.dirty_rows.insert(i);
+        for row in self.rows.iter_mut() {
+            *row = None;
         }
     }
 
-    pub fn clear_row(&mut self, row_idx: usize) {
-        self.set(row_idx, None);
-    }
-
-    // /// Draw an individual row
-    // ///
-    // /// The row will then be marked clean
-    // pub fn draw_row(&mut self, row_idx: usize, stdout: &mut Stdout) {
-    //     if self.dirty_rows.contains(&row_idx) {
-    //         self._draw_row(row_idx, stdout);
-    //         self.dirty_rows.remove(&row_idx);
-    //     }
-    // }
-
-    fn _draw_row(&self, row_idx: usize, stdout: &mut Stdout) -> IoResult<()> {
-        queue!(
-            stdout,
-            MoveTo(self.rect.left, self.rect.top + row_idx as u16),
-            PrintStyledContent(style(if let Some(ref row) = self.rows[row_idx] {
-                row.clone()
-            } else {
-                self.blank_row.clone()
-            }))
-        )
-    }
+    pub fn set_rect(&mut self, rect: Rect) {
+        self.rect = rect;
 
-    fn set(&mut self, row_idx: usize, maybe_row: Option<String>) {
-        self.rows[row_idx] = maybe_row.map(|mut row| {
-            while row.len() < self.blank_row.len() {
-                row.push(' ');
-            }
-            row
-        });
-        // self.dirty = true;
-        // self.rows_dirty[row_idx] = true;
-        self.dirty_rows.insert(row_idx);
+        // Keep what rows still fit
+        self.rows.resize(rect.height as usize, None);
     }
-}
 
-#[async_trait]
-impl Draw for RectBuffer {
-    async fn draw_no_flush(
-        &mut self,
-        _game: &PlayerTurn<'_>,
-        stdout: &mut Stdout,
-        _palette: &Palette,
-    ) -> IoResult<()> {
-        for dirty_row_idx in &self.dirty_rows {
-            self._draw_row(*dirty_row_idx, stdout)?;
-        }
-        self.dirty_rows.clear();
-        // if self.dirty {
-        //     for (row_idx, row) in self.rows.iter().enumerate() {
-        //         if self.rows_dirty[row_idx] {
-        //             queue!(stdout, Goto(self.rect.left, self.rect.top + row_idx as u16), Output(
-        //                 if let Some(row) = row {
-        //                     row.clone()
-        //                 } else {
-        //                     self.blank_row.clone()
-        //                 }
-        //             )).unwrap();
-        //             self.rows_dirty[row_idx] = false;
-        //         }
-        //     }
-        //     stdout.flush().unwrap();
-
-        //     self.dirty = false;
-        // }
-        Ok(())
+    pub fn rect(&self) -> Rect {
+        self.rect
     }
-}
 
-impl Component for RectBuffer {
-    fn set_rect(&mut self, rect: Rect) {
-        self.rect = rect;
-    }
+    pub fn widget(&self, palette: &Palette) -> impl Widget + '_ {
+        let lines: Vec<Line> = self
+            .rows
+            .iter()
+            .map(|row| Line::raw(row.as_deref().unwrap_or("")))
+            .collect();
 
-    fn rect(&self) -> Rect {
-        self.rect
+        Paragraph::new(lines).style(
+            Style::default()
+                .fg(palette.get_single(Colors::Text).into())
+                .bg(palette.get_single(Colors::Background).into()),
+        )
     }
 }
@@ -0,0 +1,110 @@
+//! Chatting with the other players
+//!
+//! A message is typed into a line that takes the place of the bottom line of the log. Messages received are logged in
+//! their senders' colors.
+
+use std::io::Result as IoResult;
+
+use crossterm::event::KeyCode;
+
+use ratatui::{
+    style::Style,
+    text::{Line, Span},
+    widgets::{Paragraph, Widget},
+};
+
+use common::{
+    colors::Colors,
+    game::{chat::MAX_CHAT_MESSAGE_LEN, player::PlayerTurn},
+    util::Rect,
+};
+
+use umpire_tui::color::Palette;
+
+use super::UI;
+
+const PROMPT: &str = "Say: ";
+
+/// The line a chat message is typed into
+pub(in crate::ui) struct ChatInput {
+    rect: Rect,
+    text: String,
+}
+
+impl ChatInput {
+    pub(in crate::ui) fn new(rect: Rect) -> Self {
+        Self {
+            rect,
+            text: String::new(),
+        }
+    }
+
+    pub(in crate::ui) fn set_text(&mut self, text: String) {
+        self.text = text;
+    }
+
+    pub(in crate::ui) fn set_rect(&mut self, rect: Rect) {
+        self.rect = rect;
+    }
+
+    pub(in crate::ui) fn rect(&self) -> Rect {
+        self.rect
+    }
+
+    pub(in crate::ui) fn widget(&self, palette: &Palette) -> impl Widget + '_ {
+        // Keep the end of the text in view, with room for the cursor after it
+        let room = (self.rect.width as usize).saturating_sub(PROMPT.len() + 1);
+        let len = self.text.chars().count();
+        let shown: String = self.text.chars().skip(len.saturating_sub(room)).collect();
+
+        let style = Style::default().bg(palette.get_single(Colors::Background).into());
+        Paragraph::new(Line::from(vec![
+            Span::styled(PROMPT, style.fg(palette.get_single(Colors::Notice).into())),
+            Span::styled(
+                shown + "_",
+                style.fg(palette.get_single(Colors::Text).into()),
+            ),
+        ]))
+        .style(style)
+    }
+}
+
+/// Let the user type a chat message and send it to the other players
+///
+/// Enter sends the message; Esc abandons it.
+pub async fn compose_chat_message<U: UI + Send + Sync>(
+    ui: &mut U,
+    game: &mut PlayerTurn<'_>,
+) -> IoResult<()> {
+    let mut text = String::new();
+
+    ui.open_chat_input();
+    ui.draw(game).await?;
+
+    // If the input thread has exited we're probably already quitting, so that ends the message too
+    while let Ok(key) = ui.get_key() {
+        match key.code {
+            KeyCode::Enter => {
+                if !text.trim().is_empty() {
+                    if let Err(err) = game.send_chat_message(text).await {
+                        ui.log_message(format!("Couldn't send message: {}", err));
+                    }
+                }
+                break;
+            }
+            KeyCode::Esc => break,
+            KeyCode::Backspace => {
+                text.pop();
+            }
+            KeyCode::Char(c) if text.chars().count() < MAX_CHAT_MESSAGE_LEN => text.push(c),
+            _ => {}
+        }
+
+        ui.set_chat_input(text.clone());
+        ui.draw_log(game).await?;
+    }
+
+    ui.close_chat_input();
+    ui.receive_chat(game).await?;
+    ui.draw(game).await
+}
@@ -0,0 +1,278 @@
+//! Editing of map files
+//!
+//! Maps are painted one tile at a time and saved in the layered map file format, ready to be played on with
+//! `--map-file`. While editing, the map is rendered as player 0 would see it with nothing hidden.
+
+use std::{fmt, fs, io::Result as IoResult, sync::Arc};
+
+use crossterm::event::{KeyCode, KeyEvent};
+use tokio::sync::RwLock as RwLockTokio;
+
+use common::{
+    conf::{self, key_desc},
+    game::{
+        map::{file as map_file, MapData, Terrain},
+        obs::ObsTracker,
+        player::{PlayerControl, PlayerTurn},
+        unit::UnitType,
+        Alignment, Game, IGame, PlayerNum,
+    },
+    util::{Direction, Location, Wrap2d},
+};
+
+use super::{mode::cols, Input, UI};
+
+/// What painting a tile puts there
+#[derive(Clone, Copy)]
+enum Brush {
+    Terrain(Terrain),
+    City,
+    Unit(UnitType),
+}
+
+impl fmt::Display for Brush {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Terrain(terrain) => write!(f, "{}", terrain),
+            Self::City => write!(f, "City"),
+            Self::Unit(type_) => write!(f, "{}", type_),
+        }
+    }
+}
+
+/// Paints terrain, cities, and units onto a map, and saves it to a file
+pub struct MapEditor {
+    map: MapData,
+
+    /// Where the map is saved to
+    path: String,
+
+    num_players: PlayerNum,
+
+    cursor: Location,
+
+    brush: Brush,
+
+    /// The player that cities and units are placed for
+    player: PlayerNum,
+}
+
+impl MapEditor {
+    pub fn new(map: MapData, path: String, num_players: PlayerNum) -> Self {
+        Self {
+            map,
+            path,
+            num_players,
+            cursor: Location::new(0, 0),
+            brush: Brush::Terrain(Terrain::Land),
+            player: 0,
+        }
+    }
+
+    /// Edit the map until the user quits
+    pub async fn run<U: UI + Send + Sync>(&mut self, ui: &mut U) -> IoResult<()> {
+        ui.log_message(format!(
+            "Editing {}. Press {} to save.",
+            self.path,
+            key_desc(conf::KEY_SAVE)
+        ));
+        self.draw(ui).await?;
+
+        loop {
+            match ui.get_input() {
+                Ok(Input::Key(key)) => {
+                    if !self.handle_key(ui, key) {
+                        return Ok(());
+                    }
+                }
+                Ok(Input::Click(_)) => continue,
+                Ok(Input::Resize(term_dims)) => ui.set_term_dims(term_dims),
+                // The input thread has exited, so we're probably already quitting
+                Err(_err) => return Ok(()),
+            }
+
+            self.draw(ui).await?;
+        }
+    }
+
+    /// Return false if the user asked to quit
+    fn handle_key<U: UI>(&mut self, ui: &mut U, key: KeyEvent) -> bool {
+        match key.code {
+            KeyCode::Char(conf::KEY_QUIT) => return false,
+            KeyCode::Char(conf::KEY_SAVE) => {
+                let text = map_file::to_layered_text(&self.map);
+                ui.log_message(match fs::write(&self.path, text) {
+                    Ok(()) => format!("Saved map to {}", self.path),
+                    Err(err) => format!("Couldn't save map to {}: {}", self.path, err),
+                });
+            }
+            KeyCode::Char(conf::KEY_EDITOR_PAINT) | KeyCode::Enter => {
+                if let Err(err) = self.paint() {
+                    ui.log_message(err);
+                }
+            }
+            KeyCode::Char(conf::KEY_EDITOR_ERASE) => self.erase(),
+            KeyCode::Char(conf::KEY_EDITOR_TERRAIN) => {
+                self.brush = Brush::Terrain(match self.brush {
+                    Brush::Terrain(Terrain::Land) => Terrain::Forest,
+                    Brush::Terrain(Terrain::Forest) => Terrain::Mountains,
+                    Brush::Terrain(Terrain::Mountains) => Terrain::Water,
+                    _ => Terrain::Land,
+                });
+            }
+            KeyCode::Char(conf::KEY_EDITOR_CITY) => self.brush = Brush::City,
+            KeyCode::Char(conf::KEY_EDITOR_UNIT) => {
+                let types = UnitType::values();
+                self.brush = Brush::Unit(match self.brush {
+                    Brush::Unit(type_) => {
+                        let idx = types.iter().position(|t| *t == type_).unwrap();
+                        types[(idx + 1) % types.len()]
+                    }
+                    _ => types[0],
+                });
+            }
+            KeyCode::Char(conf::KEY_EDITOR_PLAYER) => {
+                self.player = (self.player + 1) % self.num_players;
+            }
+            KeyCode::Char(c) => {
+                if let Ok(dir) = Direction::try_from(c) {
+                    if let Some(loc) =
+                        self.cursor
+                            .shift_wrapped(dir, self.map.dims(), Wrap2d::NEITHER)
+                    {
+                        self.cursor = loc;
+                    }
+                } else if let Ok(dir) = Direction::try_from_viewport_shift(c) {
+                    ui.scroll_map_relative(dir);
+                }
+            }
+            _ => {}
+        }
+
+        true
+    }
+
+    /// Paint the tile under the cursor with the brush
+    ///
+    /// Whatever was on the tile is replaced, so the map is saved just as it looks. Units take on their default
+    /// terrain, as they do in plain map files.
+    fn paint(&mut self) -> Result<(), String> {
+        let loc = self.cursor;
+        let alignment = Alignment::Belligerent {
+            player: self.player,
+        };
+
+        match self.brush {
+            Brush::Terrain(terrain) => {
+                self.erase();
+                self.map.set_terrain(loc, terrain).unwrap();
+                self.map.set_river(loc, None).unwrap();
+            }
+            Brush::City => {
+                if self.player > 9 {
+                    return Err(String::from(
+                        "Only players 0 through 9 can have cities in a map file",
+                    ));
+                }
+
+                self.erase();
+                self.map.set_terrain(loc, Terrain::Land).unwrap();
+                self.map
+                    .new_city(loc, alignment, format!("City_{}_{}", loc.x, loc.y))?;
+            }
+            Brush::Unit(type_) => {
+                if self.player > 1 {
+                    return Err(String::from(
+                        "Only players 0 and 1 can have units in a map file",
+                    ));
+                }
+
+                self.erase();
+                self.map.set_terrain(loc, type_.default_terrain()).unwrap();
+                self.map
+                    .new_unit(loc, type_, alignment, format!("Unit_{}_{}", loc.x, loc.y))
+                    .map_err(|err| err.to_string())?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Clear the tile under the cursor of any city and units
+    fn erase(&mut self) {
+        self.map.pop_city_by_loc(self.cursor);
+        while self.map.pop_toplevel_unit_by_loc(self.cursor).is_some() {}
+    }
+
+    /// Control of a throwaway game on the map as it stands, for rendering it
+    fn view_ctrl(&self) -> PlayerControl {
+        let dims = self.map.dims();
+
+        let (game, secrets) = Game::new_with_map(
+            None,
+            false,
+            self.map.clone(),
+            self.num_players,
+            false,
+            None,
+            Wrap2d::NEITHER,
+        );
+
+        let mut observations = ObsTracker::new(dims);
+        for loc in dims.iter_locs() {
+            observations.track_observation(loc, self.map.tile(loc).unwrap(), 0, 0);
+        }
+
+        let game = Arc::new(RwLockTokio::new(game)) as Arc<RwLockTokio<dyn IGame>>;
+        PlayerControl::from_observations(game, 0, secrets[0], dims, observations, Wrap2d::NEITHER)
+    }
+
+    async fn draw<U: UI + Send + Sync>(&self, ui: &mut U) -> IoResult<()> {
+        self.write_sidebar(ui);
+
+        let mut ctrl = self.view_ctrl();
+        let turn = PlayerTurn::spectate(&mut ctrl).await;
+
+        if ui.map_to_viewport_coords(self.cursor).is_none() {
+            ui.center_map(self.cursor);
+        }
+
+        ui.draw(&turn).await?;
+
+        let cursor_viewport_loc = ui.map_to_viewport_coords(self.cursor).unwrap();
+        ui.draw_map_tile_and_flush(
+            &turn,
+            cursor_viewport_loc,
+            true,
+            false,
+            None,
+            None,
+            None,
+            None,
+        )
+        .await
+    }
+
+    fn write_sidebar<U: UI>(&self, ui: &mut U) {
+        ui.clear_sidebar();
+
+        ui.set_sidebar_row(0, format!("Map Editor: {}", self.path));
+        ui.set_sidebar_row(1, format!("  Cursor at {}", self.cursor));
+        ui.set_sidebar_row(2, format!("  Brush: {}", self.brush));
+        ui.set_sidebar_row(3, format!("  Placing for player {}", self.player));
+
+        let controls = [
+            ("Paint", key_desc(conf::KEY_EDITOR_PAINT) + " or Enter"),
+            ("Next terrain", key_desc(conf::KEY_EDITOR_TERRAIN)),
+            ("City", key_desc(conf::KEY_EDITOR_CITY)),
+            ("Next unit type", key_desc(conf::KEY_EDITOR_UNIT)),
+            ("Next player", key_desc(conf::KEY_EDITOR_PLAYER)),
+            ("Erase city and units", key_desc(conf::KEY_EDITOR_ERASE)),
+            ("Save", key_desc(conf::KEY_SAVE)),
+            ("Quit", key_desc(conf::KEY_QUIT)),
+        ];
+        for (i, (desc, key)) in controls.into_iter().enumerate() {
+            ui.set_sidebar_row(5 + i, cols(desc, key));
+        }
+    }
+}
@@ -1,89 +1,83 @@
-use std::io::{Result as IoResult, Stdout};
-
-use async_trait::async_trait;
-
-use crossterm::{
-    queue,
-    style::{style, PrintStyledContent},
+use ratatui::{
+    buffer::Buffer,
+    layout::{Constraint, Direction, Layout, Rect as Area},
+    style::{Modifier, Style},
+    text::Span,
+    widgets::{Paragraph, Widget},
 };
 
-use common::{game::player::PlayerTurn, util::Rect};
-
-use umpire_tui::{color::Palette, Component, Draw};
-
-pub struct CurrentPlayer {
-    rect: Rect,
-}
-
-impl CurrentPlayer {
-    pub fn new(rect: Rect) -> Self {
-        CurrentPlayer { rect }
-    }
-}
-
-#[async_trait]
-impl Draw for CurrentPlayer {
-    async fn draw_no_flush(
-        &mut self,
-        game: &PlayerTurn<'_>,
-        stdout: &mut Stdout,
-        _palette: &Palette,
-    ) -> IoResult<()> {
-        let player = game.current_player().await;
-        queue!(
-            *stdout,
-            self.goto(0, 0),
-            PrintStyledContent(style(format!("Current Player: {}  ", player)))
-        )
-    }
-}
-
-impl Component for CurrentPlayer {
-    fn set_rect(&mut self, rect: Rect) {
-        self.rect = rect;
-    }
+use common::{
+    colors::Colors,
+    conf,
+    game::{PlayerNum, TurnNum},
+};
 
-    fn rect(&self) -> Rect {
-        self.rect
-    }
-}
+use umpire_tui::color::Palette;
 
-pub struct Turn {
-    rect: Rect,
+/// How a player stands: their score, as the AI is trained to maximize it, and what they control
+#[derive(Clone, Copy, Debug)]
+pub(in crate::ui) struct Standing {
+    pub score: f64,
+    pub cities: usize,
+    pub units: usize,
 }
 
-impl Turn {
-    pub fn new(rect: Rect) -> Self {
-        Turn { rect }
-    }
+/// The line across the top of the screen: the game's name, whose turn it is, which turn, and how that player stands
+///
+/// The standing is left off when it isn't known, as when spectating.
+pub(in crate::ui) struct Header {
+    player: PlayerNum,
+    turn: TurnNum,
+    standing: Option<Standing>,
+    style: Style,
 }
 
-#[async_trait]
-impl Draw for Turn {
-    async fn draw_no_flush(
-        &mut self,
-        game: &PlayerTurn<'_>,
-        stdout: &mut Stdout,
-        _palette: &Palette,
-    ) -> IoResult<()> {
-        // write!(*stdout, "{}Turn: {}", self.goto(0, 0), game.turn()).unwrap();
-
-        let turn = game.turn().await;
-
-        queue!(
-            *stdout,
-            self.goto(0, 0),
-            PrintStyledContent(style(format!("Turn: {}", turn)))
-        )
+impl Header {
+    pub(in crate::ui) fn new(
+        player: PlayerNum,
+        turn: TurnNum,
+        standing: Option<Standing>,
+        palette: &Palette,
+    ) -> Self {
+        Self {
+            player,
+            turn,
+            standing,
+            style: Style::default()
+                .fg(palette.get_single(Colors::Text).into())
+                .bg(palette.get_single(Colors::Background).into()),
+        }
     }
 }
 
-impl Component for Turn {
-    fn set_rect(&mut self, rect: Rect) {
-        self.rect = rect;
-    }
-
-    fn rect(&self) -> Rect {
-        self.rect
+impl Widget for Header {
+    fn render(self, area: Area, buf: &mut Buffer) {
+        let parts = Layout::default()
+            .direction(Direction::Horizontal)
+            .constraints([
+                Constraint::Length(10),
+                Constraint::Length(23),
+                Constraint::Length(11),
+                Constraint::Length(15),
+                Constraint::Length(12),
+                Constraint::Length(11),
+                Constraint::Min(0),
+            ])
+            .split(area);
+
+        buf.set_style(area, self.style);
+        Paragraph::new(Span::styled(
+            conf::APP_NAME,
+            self.style.add_modifier(Modifier::UNDERLINED),
+        ))
+        .render(parts[0], buf);
+        Paragraph::new(format!("Current Player: {}", self.player)).render(parts[1], buf);
+        Paragraph::new(format!("Turn: {}", self.turn)).render(parts[2], buf);
+
+        if let Some(standing) = self.standing {
+            Paragraph::new(format!("Score: {:.0}", standing.score)).render(parts[3], buf);
+            Paragraph::new(format!("Cities: {}", standing.cities)).render(parts[4], buf);
+            Paragraph::new(format!("Units: {}", standing.units)).render(parts[5], buf);
+        }
     }
 }
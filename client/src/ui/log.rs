@@ -1,25 +1,19 @@
-use std::{
-    collections::VecDeque,
-    io::{Result as IoResult, Stdout},
-};
-
-use async_trait::async_trait;
+use std::collections::VecDeque;
 
-use crossterm::{
-    queue,
-    style::{Attribute, Color, Print, SetAttribute, SetBackgroundColor, SetForegroundColor},
+use ratatui::{
+    style::{Color, Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, Paragraph, Widget},
 };
 
 use common::{
     colors::Colors,
-    game::player::PlayerTurn,
     log::{LogTarget, Message},
     util::{grapheme_len, grapheme_substr, Rect},
 };
 
-use umpire_tui::{color::Palette, Component, Draw};
+use umpire_tui::color::Palette;
 
-//TODO Use a RectBuffer to improve draw performance
 pub(in crate::ui) struct LogArea {
     rect: Rect,
     messages: VecDeque<Message>,
@@ -36,42 +30,62 @@ impl LogArea {
     }
 
     fn max_messages(&self) -> u16 {
-        self.rect.height - 1
+        self.rect.height.saturating_sub(1)
     }
 
-    fn draw_log_line_no_flush(
-        &self,
-        stdout: &mut Stdout,
-        palette: &Palette,
-        i: usize,
-    ) -> IoResult<()> {
+    fn log_line(&self, palette: &Palette, i: usize) -> Line<'_> {
         let message: &Message = self.messages.get(i).unwrap_or(&self.empty_message);
 
         let mut text = grapheme_substr(&message.text, self.rect.width as usize);
-        let num_spaces = self.rect.width as usize - grapheme_len(&text);
+        let num_spaces = (self.rect.width as usize).saturating_sub(grapheme_len(&text));
         for _ in 0..num_spaces {
             text.push(' ');
         }
 
         let mark = message.mark.unwrap_or(' ');
-        let fg_color: Color = message.fg_color.map_or_else(
-            || palette.get_single(Colors::Text),
-            |fg_color| palette.get_single(fg_color),
-        );
-
-        let bg_color: Color = message.bg_color.map_or_else(
-            || palette.get_single(Colors::Background),
-            |bg_color| palette.get_single(bg_color),
-        );
-
-        // write!(*stdout, "{}┃{}{}{}{}", self.goto(0, i as u16+1), mark, Fg(fg_color), Bg(bg_color), text).unwrap();
-        queue!(
-            *stdout,
-            self.goto(0, i as u16 + 1),
-            SetForegroundColor(fg_color),
-            SetBackgroundColor(bg_color),
-            Print(format!("|{}", mark)),
-            Print(text)
+        let fg_color: Color = message
+            .fg_color
+            .map_or_else(
+                || palette.get_single(Colors::Text),
+                |fg_color| palette.get(fg_color, true),
+            )
+            .into();
+
+        let bg_color: Color = message
+            .bg_color
+            .map_or_else(
+                || palette.get_single(Colors::Background),
+                |bg_color| palette.get(bg_color, true),
+            )
+            .into();
+
+        Line::styled(
+            format!("|{}{}", mark, text),
+            Style::default().fg(fg_color).bg(bg_color),
+        )
+    }
+
+    pub fn set_rect(&mut self, rect: Rect) {
+        self.rect = rect;
+    }
+
+    pub fn rect(&self) -> Rect {
+        self.rect
+    }
+
+    pub fn widget(&self, palette: &Palette) -> impl Widget + '_ {
+        let background = Style::default().bg(palette.get_single(Colors::Background).into());
+        let lines: Vec<Line> = (0..self.max_messages() as usize)
+            .map(|i| self.log_line(palette, i))
+            .collect();
+
+        Paragraph::new(lines).block(
+            Block::default()
+                .title(Span::styled(
+                    "Message Log",
+                    Style::default().add_modifier(Modifier::UNDERLINED),
+                ))
+                .style(background),
         )
     }
 
@@ -110,45 +124,3 @@ impl LogTarget for LogArea {
         }
     }
 }
-
-#[async_trait]
-impl Draw for LogArea {
-    async fn draw_no_flush(
-        &mut self,
-        _game: &PlayerTurn<'_>,
-        stdout: &mut Stdout,
-        palette: &Palette,
-    ) -> IoResult<()> {
-        // write!(*stdout,
-        //     "{}{}Message Log{}",
-        //     self.goto(0, 0),
-        //     Underline,
-        //     StrongReset::new(palette),
-        // ).unwrap();
-
-        queue!(
-            *stdout,
-            self.goto(0, 0),
-            SetAttribute(Attribute::Underlined),
-            Print(String::from("Message Log")),
-            SetAttribute(Attribute::Reset),
-            SetBackgroundColor(palette.get_single(Colors::Background))
-        )?;
-
-        for i in 0..self.rect.height {
-            self.draw_log_line_no_flush(stdout, palette, i as usize)?;
-        }
-
-        Ok(())
-    }
-}
-
-impl Component for LogArea {
-    fn set_rect(&mut self, rect: Rect) {
-        self.rect = rect;
-    }
-
-    fn rect(&self) -> Rect {
-        self.rect
-    }
-}
@@ -1,19 +1,31 @@
-use std::sync::mpsc::RecvError;
+use std::{
+    collections::BTreeSet,
+    sync::mpsc::{RecvError, RecvTimeoutError},
+};
 
 use crossterm::event::{KeyCode, KeyEvent};
 
 use common::{
+    colors::Colors,
     conf,
-    game::{player::PlayerTurn, unit::UnitID, PlayerNum},
+    game::{
+        error::GameError,
+        player::PlayerTurn,
+        unit::{orders::Waypoints, UnitID},
+        PlayerNum,
+    },
+    log::{Message, MessageSource},
     util::{Direction, Location, Rect},
 };
 
-use crate::ui::{sidebar_rect, UI};
+use crate::ui::{sidebar_rect, Input, UI};
 
 use self::{
-    examine::ExamineMode, get_orders::GetOrdersMode, get_unit_orders::GetUnitOrdersMode,
-    quit::QuitMode, set_production::SetProductionMode, set_productions::SetProductionsMode,
-    turn_over::TurnOverMode, turn_resume::TurnResumeMode, turn_start::TurnStartMode,
+    chat::ChatMode, city_list::CityListMode, diplomacy::DiplomacyMode, examine::ExamineMode,
+    get_orders::GetOrdersMode, get_unit_orders::GetUnitOrdersMode, groups::GroupsMode,
+    quit::QuitMode, resign::ResignMode, set_production::SetProductionMode,
+    set_productions::SetProductionsMode, settings::SettingsMode, turn_over::TurnOverMode,
+    turn_resume::TurnResumeMode, turn_start::TurnStartMode, unit_roster::UnitRosterMode,
     victory::VictoryMode,
 };
 
@@ -23,8 +35,10 @@ pub enum Mode {
     TurnResume,
     TurnOver,
     SetProductions,
+    /// Choosing what the city at `city_loc` produces, with the unit type at the given index in the menu selected
     SetProduction {
         city_loc: Location,
+        selected: usize,
     },
     GetOrders,
     GetUnitOrders {
@@ -32,14 +46,40 @@ pub enum Mode {
         first_move: bool,
     },
     Quit,
+    /// Offer to resign before quitting
+    Resign,
     Examine {
         cursor_viewport_loc: Location,
         most_recently_active_unit_id: Option<UnitID>,
+        /// Index of the unit group being given orders, if any
+        group: Option<usize>,
+        /// The unit being air-dropped, if any
+        paradrop: Option<UnitID>,
+        /// Stops queued on the way to wherever is picked
+        waypoints: Waypoints,
         first: bool,
     },
     Victory {
         victor: PlayerNum,
     },
+    Diplomacy {
+        selected: Option<PlayerNum>,
+    },
+    Groups {
+        unit_id: Option<UnitID>,
+        selected: Option<usize>,
+    },
+    Settings,
+    /// The list of the player's cities, with the city at the given index in it selected
+    CityList {
+        selected: usize,
+    },
+    /// The roster of the player's units, with the unit at the given index in it selected
+    UnitRoster {
+        selected: usize,
+    },
+    /// Typing a chat message
+    Chat,
 }
 
 impl Mode {
@@ -58,16 +98,29 @@ impl Mode {
             return ModeStatus::Continue;
         }
 
+        // The server ends the turn itself once the player runs out of time
+        if game.out_of_time().await {
+            ui.log_message(Message {
+                text: "Out of time; the turn is over".to_string(),
+                mark: Some('X'),
+                fg_color: Some(Colors::Notice),
+                bg_color: None,
+                source: Some(MessageSource::Game),
+            });
+            return ModeStatus::TurnOver;
+        }
+
         let continue_ = match *self {
             Mode::TurnStart => TurnStartMode {}.run(game, ui, self, prev_mode).await,
             Mode::TurnResume => TurnResumeMode {}.run(game, ui, self, prev_mode).await,
             Mode::TurnOver => TurnOverMode {}.run(game, ui, self, prev_mode).await,
             Mode::SetProductions => SetProductionsMode {}.run(game, ui, self, prev_mode).await,
-            Mode::SetProduction { city_loc } => {
+            Mode::SetProduction { city_loc, selected } => {
                 let rect = sidebar_rect(ui.term_dims(), ui.viewport_size());
                 let mode = SetProductionMode {
                     rect,
                     loc: city_loc,
+                    selected,
                     unicode: ui.unicode(),
                 };
 
@@ -79,25 +132,71 @@ impl Mode {
                 first_move,
             } => {
                 let rect = sidebar_rect(ui.term_dims(), ui.viewport_size());
-                GetUnitOrdersMode {
+                let status = GetUnitOrdersMode {
                     rect,
                     unit_id,
                     first_move,
                 }
                 .run(game, ui, self, prev_mode)
-                .await
+                .await;
+
+                // Only the unit getting orders has its destinations shown
+                ui.set_reachable(BTreeSet::new());
+                status
             }
             Mode::Quit => QuitMode {}.run(game, ui, self, prev_mode).await,
+            Mode::Resign => ResignMode {}.run(game, ui, self, prev_mode).await,
             Mode::Examine {
                 cursor_viewport_loc,
                 most_recently_active_unit_id,
+                group,
+                paradrop,
+                waypoints,
                 first,
             } => {
-                ExamineMode::new(cursor_viewport_loc, most_recently_active_unit_id, first)
+                ExamineMode::new(
+                    cursor_viewport_loc,
+                    most_recently_active_unit_id,
+                    group,
+                    paradrop,
+                    waypoints,
+                    first,
+                )
+                .run(game, ui, self, prev_mode)
+                .await
+            }
+            Mode::Victory { victor } => VictoryMode { victor }.run(game, ui, self, prev_mode).await,
+            Mode::Diplomacy { selected } => {
+                let rect = sidebar_rect(ui.term_dims(), ui.viewport_size());
+                DiplomacyMode { rect, selected }
                     .run(game, ui, self, prev_mode)
                     .await
             }
-            Mode::Victory { victor } => VictoryMode { victor }.run(game, ui, self, prev_mode).await,
+            Mode::Groups { unit_id, selected } => {
+                let rect = sidebar_rect(ui.term_dims(), ui.viewport_size());
+                GroupsMode {
+                    rect,
+                    unit_id,
+                    selected,
+                }
+                .run(game, ui, self, prev_mode)
+                .await
+            }
+            Mode::Settings => {
+                let rect = sidebar_rect(ui.term_dims(), ui.viewport_size());
+                SettingsMode { rect }.run(game, ui, self, prev_mode).await
+            }
+            Mode::CityList { selected } => {
+                CityListMode { selected }
+                    .run(game, ui, self, prev_mode)
+                    .await
+            }
+            Mode::UnitRoster { selected } => {
+                UnitRosterMode { selected }
+                    .run(game, ui, self, prev_mode)
+                    .await
+            }
+            Mode::Chat => ChatMode {}.run(game, ui, self, prev_mode).await,
         };
 
         *prev_mode = Some(*self);
@@ -136,6 +235,9 @@ pub enum StateDisposition {
 pub enum KeyStatus {
     Handled(StateDisposition),
     Unhandled(KeyEvent),
+
+    /// A tile of the map was clicked; this is its location in the viewport
+    Clicked(Location),
 }
 
 pub trait IMode {
@@ -154,7 +256,52 @@ pub trait IMode {
         ui: &mut U,
         mode: &mut Mode,
     ) -> Result<KeyStatus, RecvError> {
-        let key = ui.get_key()?;
+        // Show anything the other players have said before waiting on this player
+        ui.receive_chat(game).await.unwrap();
+
+        // Wait no longer than the turn has left, if it's timed, so the time running out doesn't go unnoticed. Moving
+        // on is enough, as the turn's end is picked up before the next mode runs
+        let input = match game.turn_time_remaining().await {
+            Ok(Some(remaining)) => match ui.get_input_timeout(remaining) {
+                Ok(input) => input,
+                Err(RecvTimeoutError::Timeout) => {
+                    return Ok(KeyStatus::Handled(StateDisposition::Next))
+                }
+                Err(RecvTimeoutError::Disconnected) => return Err(RecvError),
+            },
+            // The time ran out already
+            Err(GameError::NotPlayersTurn { .. }) => {
+                return Ok(KeyStatus::Handled(StateDisposition::Next))
+            }
+            _ => ui.get_input()?,
+        };
+
+        let key = match input {
+            Input::Key(key) => key,
+            Input::Click(term_loc) => {
+                let rect = ui.viewport_rect();
+                let in_viewport = (rect.left..rect.right()).contains(&term_loc.x)
+                    && (rect.top..rect.bottom()).contains(&term_loc.y);
+
+                // Clicks off the map are ignored
+                return Ok(if in_viewport {
+                    KeyStatus::Clicked(Location::new(term_loc.x - rect.left, term_loc.y - rect.top))
+                } else {
+                    KeyStatus::Handled(StateDisposition::Stay)
+                });
+            }
+            Input::Resize(term_dims) => {
+                ui.set_term_dims(term_dims);
+
+                if let Some(loc) = ui.cursor_map_loc(mode, game).await {
+                    ui.center_map(loc);
+                }
+                ui.draw(game).await.unwrap();
+
+                return Ok(KeyStatus::Handled(StateDisposition::Stay));
+            }
+        };
+
         if let KeyCode::Char(c) = key.code {
             if let Ok(dir) = Direction::try_from_viewport_shift(c) {
                 ui.scroll_map_relative(dir);
@@ -167,6 +314,10 @@ pub trait IMode {
                     *mode = Mode::Quit;
                     return Ok(KeyStatus::Handled(StateDisposition::Quit));
                 }
+                conf::KEY_RESIGN => {
+                    *mode = Mode::Resign;
+                    return Ok(KeyStatus::Handled(StateDisposition::Next));
+                }
                 conf::KEY_EXAMINE => {
                     // println!("Rect: {:?}", ui.viewport_rect());
                     // println!("Center: {:?}", ui.viewport_rect().center());
@@ -176,22 +327,7 @@ pub trait IMode {
                     let cursor_viewport_loc =
                         cursor_viewport_loc.unwrap_or(ui.viewport_rect().center());
 
-                    let cursor_map_loc = ui.cursor_map_loc(mode, game).await;
-
-                    let most_recently_active_unit_id =
-                        if let Some(most_recently_active_unit_loc) = cursor_map_loc {
-                            game.player_toplevel_unit_by_loc(most_recently_active_unit_loc)
-                                .await
-                                .map(|unit| unit.id)
-                        } else {
-                            None
-                        };
-
-                    *mode = Mode::Examine {
-                        cursor_viewport_loc,
-                        most_recently_active_unit_id,
-                        first: true,
-                    };
+                    self.examine(game, ui, mode, cursor_viewport_loc).await;
                     return Ok(KeyStatus::Handled(StateDisposition::Next));
                 }
                 conf::KEY_VIEWPORT_SIZE_ROTATE => {
@@ -203,11 +339,97 @@ pub trait IMode {
 
                     return Ok(KeyStatus::Handled(StateDisposition::Stay));
                 }
+                conf::KEY_VIEWPORT_SWITCH => {
+                    ui.switch_viewport();
+                    return Ok(KeyStatus::Handled(StateDisposition::Stay));
+                }
+                conf::KEY_DIPLOMACY => {
+                    *mode = Mode::Diplomacy { selected: None };
+                    return Ok(KeyStatus::Handled(StateDisposition::Next));
+                }
+                conf::KEY_SETTINGS => {
+                    *mode = Mode::Settings;
+                    return Ok(KeyStatus::Handled(StateDisposition::Next));
+                }
+                conf::KEY_CHAT => {
+                    *mode = Mode::Chat;
+                    return Ok(KeyStatus::Handled(StateDisposition::Next));
+                }
+                conf::KEY_JUMP_TO_ALERT => {
+                    if let Some(loc) = ui.jump_to_alert() {
+                        ui.center_map(loc);
+                    }
+                    ui.draw_map(game).await.unwrap();
+                    return Ok(KeyStatus::Handled(StateDisposition::Stay));
+                }
+                conf::KEY_CITY_LIST => {
+                    *mode = Mode::CityList { selected: 0 };
+                    return Ok(KeyStatus::Handled(StateDisposition::Next));
+                }
+                conf::KEY_UNIT_ROSTER => {
+                    *mode = Mode::UnitRoster { selected: 0 };
+                    return Ok(KeyStatus::Handled(StateDisposition::Next));
+                }
+                conf::KEY_GROUPS => {
+                    let unit_id = cursor_unit_id(game, ui, mode).await;
+
+                    *mode = Mode::Groups {
+                        unit_id,
+                        selected: None,
+                    };
+                    return Ok(KeyStatus::Handled(StateDisposition::Next));
+                }
+                conf::KEY_SAVE => {
+                    let message = match game.clone_underlying_game_state().await {
+                        Ok(state) => match state.save(conf::SAVE_PATH) {
+                            Ok(()) => format!("Game saved to {}", conf::SAVE_PATH),
+                            Err(err) => format!("Error saving game: {}", err),
+                        },
+                        Err(err) => format!("Can't save game: {}", err),
+                    };
+                    ui.log_message(message);
+                    ui.draw_log(game).await.unwrap();
+
+                    return Ok(KeyStatus::Handled(StateDisposition::Stay));
+                }
                 _ => {}
             }
         }
         Ok(KeyStatus::Unhandled(key))
     }
+
+    /// Switch to examine mode with the cursor at `cursor_viewport_loc`
+    ///
+    /// The player's unit under the current cursor, if any, can then be sent wherever is picked.
+    async fn examine<U: UI + Send + Sync>(
+        &self,
+        game: &PlayerTurn<'_>,
+        ui: &U,
+        mode: &mut Mode,
+        cursor_viewport_loc: Location,
+    ) {
+        let most_recently_active_unit_id = cursor_unit_id(game, ui, mode).await;
+
+        *mode = Mode::Examine {
+            cursor_viewport_loc,
+            most_recently_active_unit_id,
+            group: None,
+            paradrop: None,
+            waypoints: Waypoints::new(),
+            first: true,
+        };
+    }
+}
+
+/// The ID of the player's top-level unit under the cursor, if any
+async fn cursor_unit_id<U: UI>(game: &PlayerTurn<'_>, ui: &U, mode: &Mode) -> Option<UnitID> {
+    match ui.cursor_map_loc(mode, game).await {
+        Some(loc) => game
+            .player_toplevel_unit_by_loc(loc)
+            .await
+            .map(|unit| unit.id),
+        None => None,
+    }
 }
 
 trait IVisibleMode: IMode {
@@ -231,7 +453,7 @@ const COL_WIDTH: usize = 21;
 /// Concatenate two strings in a columnar fashion.
 ///
 /// The width of the left column is set by `COL_WIDTH`. s1 is right-padded up to `COL_WIDTH`, then s2 is appended.
-fn cols<S1: ToString, S2: ToString>(s1: S1, s2: S2) -> String {
+pub(in crate::ui) fn cols<S1: ToString, S2: ToString>(s1: S1, s2: S2) -> String {
     let s1 = s1.to_string();
     let s2 = s2.to_string();
 
@@ -245,13 +467,20 @@ fn cols<S1: ToString, S2: ToString>(s1: S1, s2: S2) -> String {
     c
 }
 
+mod chat;
+mod city_list;
+mod diplomacy;
 mod examine;
 mod get_orders;
 mod get_unit_orders;
+mod groups;
 mod quit;
+mod resign;
 mod set_production;
 mod set_productions;
+mod settings;
 mod turn_over;
 mod turn_resume;
 mod turn_start;
+mod unit_roster;
 mod victory;
@@ -0,0 +1,23 @@
+use common::game::player::PlayerTurn;
+
+use crate::ui::{compose_chat_message, UI};
+
+use super::{IMode, Mode, ModeStatus};
+
+/// Type a chat message to the other players, then carry on with the turn
+pub(in crate::ui) struct ChatMode;
+
+impl IMode for ChatMode {
+    async fn run<U: UI + Send + Sync>(
+        &self,
+        game: &mut PlayerTurn<'_>,
+        ui: &mut U,
+        mode: &mut Mode,
+        _prev_mode: &Option<Mode>,
+    ) -> ModeStatus {
+        compose_chat_message(ui, game).await.unwrap();
+
+        *mode = Mode::TurnResume;
+        ModeStatus::Continue
+    }
+}
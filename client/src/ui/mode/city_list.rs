@@ -0,0 +1,187 @@
+use crossterm::event::KeyCode;
+
+use common::{
+    conf,
+    game::{city::City, player::PlayerTurn, unit::UnitType},
+    log::{Message, MessageSource},
+};
+
+use crate::ui::UI;
+
+use super::{IMode, KeyStatus, Mode, ModeStatus, StateDisposition};
+
+/// Rows of the full screen not given over to the list of cities
+const NON_LIST_ROWS: usize = 5;
+
+/// Every city the player owns on one screen
+///
+/// The arrow keys select a city. Its production can then be changed by unit type key, or the map can be brought back
+/// centered on it.
+pub(in crate::ui) struct CityListMode {
+    pub selected: usize,
+}
+impl CityListMode {
+    fn write_buf<U: UI>(&self, ui: &mut U, cities: &[City]) {
+        let list_rows = ui.full_screen_height().saturating_sub(NON_LIST_ROWS);
+
+        // Scroll just far enough to keep the selected city in view
+        let first = (self.selected + 1).saturating_sub(list_rows);
+
+        ui.set_full_screen_row(0, format!("Cities ({})", cities.len()));
+        ui.set_full_screen_row(
+            2,
+            format!(
+                " {:<24}{:<12}{:<14}{}",
+                "City", "Location", "Production", "Progress"
+            ),
+        );
+
+        for row_idx in 0..list_rows {
+            let row = cities
+                .get(first + row_idx)
+                .map_or_else(String::new, |city| {
+                    let marker = if first + row_idx == self.selected {
+                        '>'
+                    } else {
+                        ' '
+                    };
+                    let (production, progress) = match city.production() {
+                        Some(unit_type) => (
+                            unit_type.to_string(),
+                            format!("{}/{}", city.production_progress, unit_type.cost()),
+                        ),
+                        None => (String::from("None"), String::from("-")),
+                    };
+                    format!(
+                        "{}{:<24}{:<12}{:<14}{}",
+                        marker,
+                        city.name(),
+                        city.loc.to_string(),
+                        production,
+                        progress
+                    )
+                });
+            ui.set_full_screen_row(3 + row_idx, row);
+        }
+
+        ui.set_full_screen_row(
+            3 + list_rows + 1,
+            format!(
+                "Up/Down: select   unit key: set production   {}: no production   Enter: go to city   Esc: done",
+                conf::KEY_NO_PRODUCTION
+            ),
+        );
+    }
+
+    /// Change the production of `city` as `key` asks, describing the result
+    ///
+    /// Returns None if `key` isn't a production key.
+    async fn set_production(game: &mut PlayerTurn<'_>, city: &City, key: char) -> Option<String> {
+        Some(if let Ok(unit_type) = UnitType::try_from_key(key) {
+            match game.set_production_by_loc(city.loc, unit_type).await {
+                Ok(_) => format!("Set {}'s production to {}", city.short_desc(), unit_type),
+                Err(err) => format!("Couldn't set production: {}", err),
+            }
+        } else if key == conf::KEY_NO_PRODUCTION {
+            match game.clear_production(city.loc, true).await {
+                Ok(_) => format!("Cleared {}'s production", city.short_desc()),
+                Err(err) => format!("Couldn't clear production: {}", err),
+            }
+        } else {
+            return None;
+        })
+    }
+}
+
+impl IMode for CityListMode {
+    async fn run<U: UI + Send + Sync>(
+        &self,
+        game: &mut PlayerTurn<'_>,
+        ui: &mut U,
+        mode: &mut Mode,
+        _prev_mode: &Option<Mode>,
+    ) -> ModeStatus {
+        let mut cities = game.player_cities().await;
+        cities.sort_by(|a, b| a.name().cmp(b.name()));
+
+        ui.open_full_screen();
+        self.write_buf(ui, &cities);
+        ui.draw(game).await.unwrap();
+
+        loop {
+            match self.get_key(game, ui, mode).await {
+                Ok(key) => match key {
+                    KeyStatus::Unhandled(key) => match key.code {
+                        KeyCode::Esc => {
+                            ui.close_full_screen();
+                            ui.draw(game).await.unwrap();
+                            *mode = Mode::TurnResume;
+                            return ModeStatus::Continue;
+                        }
+                        KeyCode::Up if self.selected > 0 => {
+                            *mode = Mode::CityList {
+                                selected: self.selected - 1,
+                            };
+                            return ModeStatus::Continue;
+                        }
+                        KeyCode::Down if self.selected + 1 < cities.len() => {
+                            *mode = Mode::CityList {
+                                selected: self.selected + 1,
+                            };
+                            return ModeStatus::Continue;
+                        }
+                        KeyCode::Enter => {
+                            if let Some(city) = cities.get(self.selected) {
+                                ui.close_full_screen();
+                                ui.center_map(city.loc);
+                                ui.draw(game).await.unwrap();
+
+                                let viewport_loc = ui
+                                    .map_to_viewport_coords(city.loc)
+                                    .unwrap_or(ui.viewport_rect().center());
+                                self.examine(game, ui, mode, viewport_loc).await;
+                                return ModeStatus::Continue;
+                            }
+                        }
+                        KeyCode::Char(c) => {
+                            if let Some(city) = cities.get(self.selected) {
+                                if let Some(text) = Self::set_production(game, city, c).await {
+                                    ui.log_message(Message {
+                                        text,
+                                        mark: Some('·'),
+                                        bg_color: None,
+                                        fg_color: None,
+                                        source: Some(MessageSource::Mode),
+                                    });
+
+                                    // List the city's new production
+                                    return ModeStatus::Continue;
+                                }
+                            }
+                        }
+                        _ => {}
+                    },
+                    // The map is covered, so there's nothing to click on
+                    KeyStatus::Clicked(_) => {}
+                    KeyStatus::Handled(state_disposition) => match state_disposition {
+                        StateDisposition::Quit => {
+                            ui.close_full_screen();
+                            return ModeStatus::Quit;
+                        }
+                        StateDisposition::Next => {
+                            ui.close_full_screen();
+                            ui.draw(game).await.unwrap();
+                            return ModeStatus::Continue;
+                        }
+                        StateDisposition::Stay => {}
+                    },
+                },
+                Err(_err) => {
+                    // RecvError comes from the input thread exiting before the UI itself.
+                    // So, just quit the app, we're probably already trying to do so.
+                    return ModeStatus::Quit;
+                }
+            }
+        }
+    }
+}
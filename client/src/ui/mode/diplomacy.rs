@@ -0,0 +1,170 @@
+use crossterm::event::KeyCode;
+
+use common::{
+    conf,
+    game::{diplomacy::DiplomaticStatus, player::PlayerTurn, PlayerNum},
+    log::{Message, MessageSource},
+    util::Rect,
+};
+
+use crate::ui::UI;
+
+use super::{cols, IMode, IVisibleMode, KeyStatus, Mode, ModeStatus, StateDisposition};
+
+/// Review and negotiate relations with the other players
+///
+/// Number keys select a player; the treaty keys then act on the selected player.
+pub(in crate::ui) struct DiplomacyMode {
+    pub rect: Rect,
+    pub selected: Option<PlayerNum>,
+}
+impl DiplomacyMode {
+    async fn write_buf<U: UI>(&self, game: &PlayerTurn<'_>, ui: &mut U) {
+        let player = game.current_player().await;
+        let proposals = game.treaty_proposals().await;
+
+        ui.clear_sidebar();
+        ui.set_sidebar_row(0, String::from("Diplomacy"));
+
+        let mut y = 2;
+        for other in (0..game.num_players().await).filter(|other| *other != player) {
+            let status = game.diplomatic_status(other).await.unwrap();
+
+            let marker = if self.selected == Some(other) {
+                '>'
+            } else {
+                ' '
+            };
+
+            let mut row = cols(format!("{}[{}] Player {}", marker, other, other), status);
+            if let Some((_, proposal)) = proposals.iter().find(|(from, _)| *from == other) {
+                row.push_str(format!(" (offers {})", proposal).as_str());
+            }
+
+            ui.set_sidebar_row(y, row);
+            y += 1;
+        }
+
+        y += 1;
+        ui.set_sidebar_row(y, cols("Propose peace:", conf::KEY_PROPOSE_PEACE));
+        ui.set_sidebar_row(y + 2, cols("Propose alliance:", conf::KEY_PROPOSE_ALLIANCE));
+        ui.set_sidebar_row(y + 4, cols("Accept offer:", conf::KEY_ACCEPT_TREATY));
+        ui.set_sidebar_row(y + 6, cols("Declare war:", conf::KEY_DECLARE_WAR));
+        ui.set_sidebar_row(y + 8, cols("Done:", "Esc"));
+    }
+
+    /// Carry out the negotiation bound to `key` with `other`, describing the result
+    async fn negotiate(&self, game: &mut PlayerTurn<'_>, key: char, other: PlayerNum) -> String {
+        match key {
+            conf::KEY_PROPOSE_PEACE | conf::KEY_PROPOSE_ALLIANCE => {
+                let status = if key == conf::KEY_PROPOSE_PEACE {
+                    DiplomaticStatus::Peace
+                } else {
+                    DiplomaticStatus::Alliance
+                };
+                match game.propose_treaty(other, status).await {
+                    Ok(()) => format!("Proposed {} to player {}", status, other),
+                    Err(err) => format!("Couldn't propose {}: {}", status, err),
+                }
+            }
+            conf::KEY_ACCEPT_TREATY => match game.accept_treaty(other).await {
+                Ok(status) => format!("Now at {} with player {}", status, other),
+                Err(err) => format!("Couldn't accept treaty: {}", err),
+            },
+            conf::KEY_DECLARE_WAR => match game.declare_war(other).await {
+                Ok(_prior_status) => format!("Declared war on player {}", other),
+                Err(err) => format!("Couldn't declare war: {}", err),
+            },
+            _ => unreachable!(),
+        }
+    }
+}
+
+impl IMode for DiplomacyMode {
+    async fn run<U: UI + Send + Sync>(
+        &self,
+        game: &mut PlayerTurn<'_>,
+        ui: &mut U,
+        mode: &mut Mode,
+        _prev_mode: &Option<Mode>,
+    ) -> ModeStatus {
+        self.write_buf(game, ui).await;
+        ui.draw_no_flush(game).await.unwrap();
+
+        let player = game.current_player().await;
+        let num_players = game.num_players().await;
+
+        loop {
+            match self.get_key(game, ui, mode).await {
+                Ok(key) => match key {
+                    KeyStatus::Unhandled(key) => {
+                        if key.code == KeyCode::Esc {
+                            Self::clear_buf(ui);
+                            *mode = Mode::TurnResume;
+                            return ModeStatus::Continue;
+                        }
+
+                        if let KeyCode::Char(c) = key.code {
+                            if let Some(other) = c.to_digit(10) {
+                                let other = other as PlayerNum;
+                                if other < num_players && other != player {
+                                    *mode = Mode::Diplomacy {
+                                        selected: Some(other),
+                                    };
+                                    return ModeStatus::Continue;
+                                }
+                            } else if [
+                                conf::KEY_PROPOSE_PEACE,
+                                conf::KEY_PROPOSE_ALLIANCE,
+                                conf::KEY_ACCEPT_TREATY,
+                                conf::KEY_DECLARE_WAR,
+                            ]
+                            .contains(&c)
+                            {
+                                if let Some(other) = self.selected {
+                                    let text = self.negotiate(game, c, other).await;
+                                    ui.log_message(Message {
+                                        text,
+                                        mark: Some('·'),
+                                        bg_color: None,
+                                        fg_color: None,
+                                        source: Some(MessageSource::Mode),
+                                    });
+                                    ui.draw_log(game).await.unwrap();
+
+                                    // Redraw with the new status
+                                    return ModeStatus::Continue;
+                                }
+                            }
+                        }
+                    }
+                    KeyStatus::Clicked(viewport_loc) => {
+                        Self::clear_buf(ui);
+                        self.examine(game, ui, mode, viewport_loc).await;
+                        return ModeStatus::Continue;
+                    }
+                    KeyStatus::Handled(state_disposition) => match state_disposition {
+                        StateDisposition::Quit => return ModeStatus::Quit,
+                        StateDisposition::Next => return ModeStatus::Continue,
+                        StateDisposition::Stay => {}
+                    },
+                },
+                Err(_err) => {
+                    // RecvError comes from the input thread exiting before the UI itself.
+                    // So, just quit the app, we're probably already trying to do so.
+                    return ModeStatus::Quit;
+                }
+            }
+        }
+    }
+}
+
+impl IVisibleMode for DiplomacyMode {
+    fn clear_buf<U: UI>(ui: &mut U) {
+        ui.clear_sidebar();
+    }
+
+    fn rect(&self) -> Rect {
+        self.rect
+    }
+}
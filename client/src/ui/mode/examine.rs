@@ -1,23 +1,37 @@
 use std::{borrow::Cow, io::Result as IoResult};
 
-use crossterm::event::KeyCode;
+use crossterm::event::{KeyCode, KeyEvent};
 
 use common::{
     colors::Colors,
+    conf,
     game::{
-        alignment::AlignedMaybe, error::GameError, map::Tile, player::PlayerTurn, unit::UnitID,
+        alignment::AlignedMaybe,
+        error::GameError,
+        map::Tile,
+        player::PlayerTurn,
+        unit::{
+            orders::{Orders, Waypoints},
+            UnitID,
+        },
     },
     log::{Message, MessageSource},
     util::{Direction, Location, Wrap2d},
 };
 
-use crate::ui::UI;
+use crate::ui::{unit_info::UnitInfo, UI};
 
 use super::{IMode, KeyStatus, Mode, ModeStatus, StateDisposition};
 
 pub(in crate::ui) struct ExamineMode {
     cursor_viewport_loc: Location,
     most_recently_active_unit_id: Option<UnitID>,
+    /// Index of the unit group being given orders, if any
+    group: Option<usize>,
+    /// The unit being air-dropped, if any
+    paradrop: Option<UnitID>,
+    /// Stops queued on the way to wherever is picked
+    waypoints: Waypoints,
     /// This is the first examine mode state we've been in since being in non-examine-mode states
     first: bool,
 }
@@ -25,11 +39,17 @@ impl ExamineMode {
     pub(in crate::ui::mode) fn new(
         cursor_viewport_loc: Location,
         most_recently_active_unit_id: Option<UnitID>,
+        group: Option<usize>,
+        paradrop: Option<UnitID>,
+        waypoints: Waypoints,
         first: bool,
     ) -> Self {
         Self {
             cursor_viewport_loc,
             most_recently_active_unit_id,
+            group,
+            paradrop,
+            waypoints,
             first,
         }
     }
@@ -71,13 +91,164 @@ impl ExamineMode {
         .await
     }
 
+    /// Order the unit `escort_id` to escort the player's unit under the cursor, if any
+    async fn escort<U: UI>(&self, game: &mut PlayerTurn<'_>, ui: &mut U, escort_id: UnitID) {
+        let target = match self.current_player_tile(game, ui).await {
+            Some(tile) => tile.unit.as_ref().map(|unit| (unit.id, unit.alignment)),
+            None => None,
+        };
+
+        let player = game.current_player().await;
+        let target_id = match target {
+            Some((target_id, alignment)) if alignment.is_friendly_to_player(player) => target_id,
+            _ => {
+                ui.log_message("Only your own units can be escorted");
+                return;
+            }
+        };
+
+        match game.propose_order_unit_escort(escort_id, target_id).await {
+            Ok(proposed_orders_outcome) => {
+                if let Some(proposed_move) = proposed_orders_outcome.outcome.move_.as_ref() {
+                    ui.animate_move(game, proposed_move).await.unwrap();
+                }
+                ui.log_message(format!("Ordered unit to escort unit {:?}", target_id));
+
+                game.take_action(proposed_orders_outcome.action)
+                    .await
+                    .unwrap();
+            }
+            Err(ref orders_err) => ui.log_message(Message {
+                text: format!("{}", orders_err),
+                mark: Some('-'),
+                fg_color: Some(Colors::Notice),
+                bg_color: Some(Colors::Background),
+                source: Some(MessageSource::UI),
+            }),
+        }
+    }
+
+    /// Give the unit group at index `group` the orders, animating the members' moves
+    async fn order_group<U: UI>(
+        &self,
+        game: &mut PlayerTurn<'_>,
+        ui: &mut U,
+        group: usize,
+        orders: Orders,
+    ) {
+        let name = match game.unit_groups().await.into_iter().nth(group) {
+            Some((name, _members)) => name,
+            None => return,
+        };
+
+        match game.order_unit_group(name.clone(), orders).await {
+            Ok(orders_results) => {
+                for orders_result in orders_results.iter() {
+                    match orders_result {
+                        Ok(orders_outcome) => {
+                            if let Some(move_) = orders_outcome.move_() {
+                                ui.animate_move(game, move_).await.unwrap();
+                            }
+                        }
+                        Err(err) => ui.log_message(format!("{}", err)),
+                    }
+                }
+                ui.log_message(format!(
+                    "{} is {}",
+                    name,
+                    orders.present_progressive_description()
+                ));
+            }
+            Err(err) => ui.log_message(Message {
+                text: format!("{}", err),
+                mark: Some('-'),
+                fg_color: Some(Colors::Notice),
+                bg_color: Some(Colors::Background),
+                source: Some(MessageSource::UI),
+            }),
+        }
+    }
+
+    /// Air-drop the unit `unit_id` onto the tile under the cursor, animating the drop
+    async fn paradrop<U: UI>(&self, game: &mut PlayerTurn<'_>, ui: &mut U, unit_id: UnitID) {
+        let dest = ui
+            .viewport_to_map_coords(game, self.cursor_viewport_loc)
+            .await
+            .unwrap();
+
+        match game.paradrop(unit_id, dest).await {
+            Ok(move_) => {
+                ui.animate_move(game, &move_).await.unwrap();
+                if move_.moved_successfully() {
+                    ui.log_message(format!("Dropped unit at {}", move_.ending_loc().unwrap()));
+                } else {
+                    ui.log_message("The drop failed and the unit was lost");
+                }
+            }
+            Err(err) => ui.log_message(Message {
+                text: format!("{}", err),
+                mark: Some('-'),
+                fg_color: Some(Colors::Notice),
+                bg_color: Some(Colors::Background),
+                source: Some(MessageSource::UI),
+            }),
+        }
+    }
+
+    /// Show what's known of the unit under the cursor, if any, in the sidebar
+    ///
+    /// Only the player's own units have their moves, orders, and cargo shown.
+    async fn write_buf<U: UI>(&self, game: &PlayerTurn<'_>, ui: &mut U) {
+        ui.clear_sidebar();
+
+        let rows = match self.current_player_tile(game, ui).await {
+            Some(tile) => match tile.unit.as_ref() {
+                Some(unit) => {
+                    let info = UnitInfo::new(unit);
+                    if unit.belongs_to_player(game.current_player().await) {
+                        info.rows(ui.unicode())
+                    } else {
+                        info.outward_rows(ui.unicode())
+                    }
+                }
+                None => Vec::new(),
+            },
+            None => Vec::new(),
+        };
+
+        for (i, row) in rows.into_iter().enumerate() {
+            ui.set_sidebar_row(i, row);
+        }
+    }
+
     fn next_examine_mode(&self, new_loc: Location) -> Mode {
         Mode::Examine {
             cursor_viewport_loc: new_loc,
             most_recently_active_unit_id: self.most_recently_active_unit_id,
+            group: self.group,
+            paradrop: self.paradrop,
+            waypoints: self.waypoints,
             first: false,
         }
     }
+
+    /// Orders to go to `dest` by way of the waypoints queued so far
+    fn go_to_orders(&self, dest: Location) -> Orders {
+        match self.waypoints.split_first() {
+            Some((first, mut rest)) => {
+                // There's always room, as the first waypoint has made way
+                rest.push(dest);
+                Orders::GoTo {
+                    dest: first,
+                    waypoints: rest,
+                }
+            }
+            None => Orders::GoTo {
+                dest,
+                waypoints: Waypoints::new(),
+            },
+        }
+    }
 }
 
 impl IMode for ExamineMode {
@@ -88,6 +259,8 @@ impl IMode for ExamineMode {
         mode: &mut Mode,
         _prev_mode: &Option<Mode>,
     ) -> ModeStatus {
+        self.write_buf(game, ui).await;
+        ui.draw_no_flush(game).await.unwrap();
         self.draw_tile(game, ui).await.unwrap();
 
         let description = {
@@ -106,9 +279,49 @@ impl IMode for ExamineMode {
         }
         ui.draw_log(game).await.unwrap(); // this will flush
 
-        match self.get_key(game, ui, mode).await {
+        let key = match self.get_key(game, ui, mode).await {
+            // Clicking the tile under the cursor acts on it as Enter would
+            Ok(KeyStatus::Clicked(viewport_loc)) if viewport_loc == self.cursor_viewport_loc => {
+                Ok(KeyStatus::Unhandled(KeyEvent::from(KeyCode::Enter)))
+            }
+            key => key,
+        };
+
+        match key {
             Ok(key) => match key {
                 KeyStatus::Unhandled(key) => {
+                    if let Some(unit_id) = self.paradrop {
+                        if key.code == KeyCode::Enter {
+                            self.paradrop(game, ui, unit_id).await;
+                            *mode = Mode::TurnResume;
+
+                            self.clean_up(game, ui).await.unwrap();
+                            return ModeStatus::Continue;
+                        }
+                    }
+
+                    if let Some(group) = self.group {
+                        let attack = key.code == KeyCode::Char(conf::KEY_GROUP_ATTACK);
+                        if key.code == KeyCode::Enter || attack {
+                            let loc = ui
+                                .viewport_to_map_coords(game, self.cursor_viewport_loc)
+                                .await
+                                .unwrap();
+
+                            let orders = if attack {
+                                Orders::Attack { target: loc }
+                            } else {
+                                self.go_to_orders(loc)
+                            };
+
+                            self.order_group(game, ui, group, orders).await;
+                            *mode = Mode::TurnResume;
+
+                            self.clean_up(game, ui).await.unwrap();
+                            return ModeStatus::Continue;
+                        }
+                    }
+
                     if key.code == KeyCode::Esc {
                         // Don't leave the examine-mode log message hanging around. They accumulate and get really ugly.
                         ui.pop_log_message();
@@ -156,7 +369,10 @@ impl IMode for ExamineMode {
                             } else if let Some(ref city) = tile.city {
                                 let player = game.current_player().await;
                                 if city.belongs_to_player(player) {
-                                    *mode = Mode::SetProduction { city_loc: city.loc };
+                                    *mode = Mode::SetProduction {
+                                        city_loc: city.loc,
+                                        selected: 0,
+                                    };
                                     self.clean_up(game, ui).await.unwrap();
                                     return ModeStatus::Continue;
                                 }
@@ -173,7 +389,10 @@ impl IMode for ExamineMode {
                                 .unwrap();
 
                             let proposed_result = game
-                                .propose_order_unit_go_to(most_recently_active_unit_id, dest)
+                                .propose_set_and_follow_orders(
+                                    most_recently_active_unit_id,
+                                    self.go_to_orders(dest),
+                                )
                                 .await;
 
                             match proposed_result {
@@ -203,7 +422,44 @@ impl IMode for ExamineMode {
                             return ModeStatus::Continue;
                         }
                     } else if let KeyCode::Char(c) = key.code {
-                        if let Ok(dir) = Direction::try_from(c) {
+                        if c == conf::KEY_ADD_WAYPOINT
+                            && self.paradrop.is_none()
+                            && (self.most_recently_active_unit_id.is_some() || self.group.is_some())
+                        {
+                            let loc = ui
+                                .viewport_to_map_coords(game, self.cursor_viewport_loc)
+                                .await
+                                .unwrap();
+
+                            let mut waypoints = self.waypoints;
+                            if waypoints.push(loc) {
+                                ui.log_message(format!(
+                                    "Waypoint {} at {}; keep adding ({}) or pick the destination (Enter)",
+                                    waypoints.len(),
+                                    loc,
+                                    conf::KEY_ADD_WAYPOINT
+                                ));
+                                // Start a new examining message, leaving this one showing above it
+                                *mode = Mode::Examine {
+                                    cursor_viewport_loc: self.cursor_viewport_loc,
+                                    most_recently_active_unit_id: self.most_recently_active_unit_id,
+                                    group: self.group,
+                                    paradrop: None,
+                                    waypoints,
+                                    first: true,
+                                };
+                            } else {
+                                ui.log_message("No room for more waypoints");
+                            }
+                        } else if c == conf::KEY_ESCORT {
+                            if let Some(escort_id) = self.most_recently_active_unit_id {
+                                self.escort(game, ui, escort_id).await;
+                                *mode = Mode::TurnResume;
+
+                                self.clean_up(game, ui).await.unwrap();
+                                return ModeStatus::Continue;
+                            }
+                        } else if let Ok(dir) = Direction::try_from(c) {
                             let dims = ui.viewport_rect().dims();
                             if let Some(new_loc) =
                                 self.cursor_viewport_loc
@@ -230,6 +486,13 @@ impl IMode for ExamineMode {
                     self.clean_up(game, ui).await.unwrap();
                     ModeStatus::Continue
                 }
+                KeyStatus::Clicked(viewport_loc) => {
+                    // Clicking any other tile moves the cursor there
+                    *mode = self.next_examine_mode(viewport_loc);
+
+                    self.clean_up(game, ui).await.unwrap();
+                    ModeStatus::Continue
+                }
                 KeyStatus::Handled(state_disposition) => match state_disposition {
                     StateDisposition::Quit => ModeStatus::Quit,
                     StateDisposition::Next | StateDisposition::Stay => ModeStatus::Continue,
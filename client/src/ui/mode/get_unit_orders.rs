@@ -1,16 +1,21 @@
+use std::collections::BTreeSet;
+
 use crossterm::event::KeyCode;
 
 use common::{
     conf::{self, key_desc},
     game::{
         action::PlayerActionOutcome,
+        combat::CombatOdds,
+        map::Improvement,
+        move_::Move,
         player::PlayerTurn,
-        unit::{Fuel, UnitID},
+        unit::{orders::Waypoints, TransportMode, UnitID},
     },
-    util::{Direction, Rect},
+    util::{Direction, Location, Rect},
 };
 
-use crate::ui::{audio::Sounds, UI};
+use crate::ui::{audio::Sounds, unit_info::UnitInfo, UI};
 
 use super::{cols, IMode, IVisibleMode, KeyStatus, Mode, ModeStatus, StateDisposition};
 
@@ -36,24 +41,11 @@ impl GetUnitOrdersMode {
     async fn write_buf<U: UI>(&self, game: &PlayerTurn<'_>, ui: &mut U) {
         let unit = game.player_unit_by_id(self.unit_id).await.unwrap();
 
-        ui.set_sidebar_row(0, format!("Get Orders for {}", unit));
-
-        let mut moves_s = format!(
-            "  Moves: {} / {}",
-            unit.moves_remaining,
-            unit.movement_per_turn()
-        );
-
-        if let Fuel::Limited { max, remaining } = unit.fuel {
-            // Right pad
-            while moves_s.len() < 18 {
-                moves_s.push(' ');
-            }
-
-            moves_s.push_str(format!("Fuel: {} / {}", remaining, max).as_str());
-        }
+        let info = UnitInfo::new(&unit);
 
-        ui.set_sidebar_row(2, moves_s);
+        ui.set_sidebar_row(0, format!("Get Orders for {}", unit));
+        ui.set_sidebar_row(1, info.hp_row(ui.unicode()));
+        ui.set_sidebar_row(2, info.moves_row());
 
         ui.set_sidebar_row(
             4,
@@ -84,9 +76,134 @@ impl GetUnitOrdersMode {
         ui.set_sidebar_row(8, cols("Examine:", conf::KEY_EXAMINE));
         ui.set_sidebar_row(10, cols("Explore:", conf::KEY_EXPLORE));
         ui.set_sidebar_row(12, cols("Skip:", key_desc(conf::KEY_SKIP)));
-        ui.set_sidebar_row(14, cols("Sentry:", conf::KEY_SENTRY));
-        ui.set_sidebar_row(16, cols("Disband:", conf::KEY_DISBAND));
-        ui.set_sidebar_row(18, cols("Quit:", conf::KEY_QUIT));
+        ui.set_sidebar_row(14, cols("Next unit:", conf::KEY_NEXT_UNIT));
+        ui.set_sidebar_row(16, cols("Sentry:", conf::KEY_SENTRY));
+        ui.set_sidebar_row(18, cols("Disband:", conf::KEY_DISBAND));
+
+        let mut y = 20;
+        if unit.transport_mode() == TransportMode::Air {
+            ui.set_sidebar_row(y, cols("Land on carrier:", conf::KEY_LOAD));
+            ui.set_sidebar_row(y + 2, cols("Launch:", conf::KEY_UNLOAD));
+            ui.set_sidebar_row(y + 4, cols("Rebase:", conf::KEY_REBASE));
+            y += 6;
+        } else {
+            ui.set_sidebar_row(y, cols("Board carrier:", conf::KEY_LOAD));
+            ui.set_sidebar_row(y + 2, cols("Disembark:", conf::KEY_UNLOAD));
+            y += 4;
+        }
+        if unit.type_.attack_range().is_some() {
+            ui.set_sidebar_row(y, cols("Bombard:", conf::KEY_BOMBARD));
+            y += 2;
+        }
+        if unit.type_.paradrop_range().is_some() {
+            ui.set_sidebar_row(y, cols("Paradrop:", conf::KEY_PARADROP));
+            y += 2;
+        }
+        if unit.type_.can_build() {
+            ui.set_sidebar_row(y, cols("Build road:", conf::KEY_BUILD_ROAD));
+            ui.set_sidebar_row(y + 2, cols("Fortify:", conf::KEY_BUILD_FORTIFICATION));
+            ui.set_sidebar_row(y + 4, cols("Build radar:", conf::KEY_BUILD_RADAR));
+            y += 6;
+        }
+        if unit.type_.can_lay_mines() {
+            ui.set_sidebar_row(y, cols("Lay mines:", conf::KEY_LAY_MINES));
+            y += 2;
+        }
+        if unit.type_.can_sweep_mines() {
+            ui.set_sidebar_row(y, cols("Sweep mines:", conf::KEY_SWEEP_MINES));
+            y += 2;
+        }
+        ui.set_sidebar_row(y, cols("Diplomacy:", conf::KEY_DIPLOMACY));
+        ui.set_sidebar_row(y + 2, cols("Unit groups:", conf::KEY_GROUPS));
+        ui.set_sidebar_row(y + 4, cols("Cities:", conf::KEY_CITY_LIST));
+        ui.set_sidebar_row(y + 6, cols("Units:", conf::KEY_UNIT_ROSTER));
+        ui.set_sidebar_row(y + 8, cols("Settings:", conf::KEY_SETTINGS));
+        ui.set_sidebar_row(y + 10, cols("Quit:", conf::KEY_QUIT));
+        ui.set_sidebar_row(y + 12, cols("Resign:", conf::KEY_RESIGN));
+    }
+
+    /// Show the odds of attacking whatever is at `dest` in the sidebar
+    async fn write_odds_buf<U: UI>(
+        &self,
+        game: &PlayerTurn<'_>,
+        ui: &mut U,
+        dest: Location,
+        odds: &CombatOdds,
+    ) {
+        let attacker = game.player_unit_by_id(self.unit_id).await.unwrap();
+        let defender = game.tile(dest).and_then(|tile| {
+            tile.defender()
+                .map(|unit| unit.medium_desc())
+                .or_else(|| tile.city.as_ref().map(|city| city.short_desc()))
+        });
+
+        ui.clear_sidebar();
+        ui.set_sidebar_row(0, format!("Attack {}?", dest));
+        ui.set_sidebar_row(2, format!("  Attacker: {}", attacker.medium_desc()));
+        ui.set_sidebar_row(3, format!("  Defender: {}", defender.unwrap_or_default()));
+        ui.set_sidebar_row(
+            5,
+            cols(
+                "  Win probability:",
+                format!("{:.0}%", odds.victory_prob * 100.0),
+            ),
+        );
+        ui.set_sidebar_row(
+            6,
+            cols(
+                "  Damage taken:",
+                format!("{:.1} hp expected", odds.expected_attacker_damage()),
+            ),
+        );
+        ui.set_sidebar_row(
+            7,
+            cols(
+                "  Damage dealt:",
+                format!("{:.1} hp expected", odds.expected_defender_damage()),
+            ),
+        );
+    }
+
+    /// The unit awaiting orders after this one, wrapping around to the first
+    ///
+    /// `None` if no other unit awaits orders.
+    async fn next_unit(&self, game: &PlayerTurn<'_>) -> Option<UnitID> {
+        let requests = game.player_unit_orders_requests().await;
+        let next_idx = requests
+            .iter()
+            .position(|unit_id| *unit_id == self.unit_id)
+            .map_or(0, |idx| idx + 1);
+
+        requests
+            .iter()
+            .cycle()
+            .skip(next_idx)
+            .take(requests.len())
+            .cloned()
+            .find(|unit_id| *unit_id != self.unit_id)
+    }
+
+    /// The mode to go to once the unit has moved
+    async fn mode_after_move(&self, game: &PlayerTurn<'_>, move_: &Move) -> Mode {
+        if let Some(conquered_city) = move_.conquered_city() {
+            Mode::SetProduction {
+                city_loc: conquered_city.loc,
+                selected: 0,
+            }
+        } else if game
+            .player_unit_orders_requests()
+            .await
+            .iter()
+            .cloned()
+            .any(|unit_id| unit_id == self.unit_id)
+        {
+            Mode::GetUnitOrders {
+                unit_id: self.unit_id,
+                first_move: false,
+            }
+        } else {
+            Mode::GetOrders
+        }
     }
 }
 
@@ -113,9 +230,25 @@ impl IMode for GetUnitOrdersMode {
             if self.first_move {
                 ui.play_sound(Sounds::Unit(unit.type_));
                 ui.center_map(unit.loc);
+            } else {
+                // The unit may have moved out of view since it was first centered on
+                ui.ensure_map_loc_visible(unit.loc);
             }
 
             self.write_buf(game, ui).await;
+
+            // Shade where the unit can step next, and everywhere it could get to with the moves it has left
+            let mut reachable = game
+                .player_unit_legal_one_step_destinations(self.unit_id)
+                .await
+                .unwrap_or_default();
+            reachable.extend(
+                game.player_unit_reachable_this_turn(self.unit_id)
+                    .await
+                    .unwrap_or_default(),
+            );
+            ui.set_reachable(reachable);
+
             ui.draw_no_flush(game).await.unwrap();
 
             let viewport_loc = ui.map_to_viewport_coords(unit.loc).unwrap();
@@ -144,6 +277,50 @@ impl IMode for GetUnitOrdersMode {
                                 if let Some(dest) =
                                     unit_loc.shift_wrapped(dir, game.dims(), game.wrapping())
                                 {
+                                    if let Ok(odds) = game.combat_odds(self.unit_id, dest).await {
+                                        self.write_odds_buf(game, ui, dest, &odds).await;
+
+                                        if ui.settings().confirm_attacks {
+                                            ui.set_sidebar_row(
+                                                9,
+                                                cols("Attack:", format!("{} or Enter", c)),
+                                            );
+                                            ui.set_sidebar_row(10, cols("Call off:", "Esc"));
+                                            ui.draw(game).await.unwrap();
+
+                                            let confirmed = match self.get_key(game, ui, mode).await
+                                            {
+                                                Ok(KeyStatus::Unhandled(key)) => {
+                                                    key.code == KeyCode::Char(c)
+                                                        || key.code == KeyCode::Enter
+                                                }
+                                                Ok(KeyStatus::Handled(state_disposition)) => {
+                                                    match state_disposition {
+                                                        StateDisposition::Quit => {
+                                                            return ModeStatus::Quit
+                                                        }
+                                                        StateDisposition::Next => {
+                                                            return ModeStatus::Continue
+                                                        }
+                                                        StateDisposition::Stay => false,
+                                                    }
+                                                }
+                                                Ok(KeyStatus::Clicked(_)) => false,
+                                                Err(_err) => return ModeStatus::Quit,
+                                            };
+
+                                            if !confirmed {
+                                                ui.log_message("Attack called off");
+                                                ui.clear_sidebar();
+                                                self.write_buf(game, ui).await;
+                                                ui.draw(game).await.unwrap();
+                                                continue;
+                                            }
+                                        } else {
+                                            ui.draw_no_flush(game).await.unwrap();
+                                        }
+                                    }
+
                                     let proposed_move =
                                         game.propose_move_unit_by_id(self.unit_id, dest).await;
 
@@ -151,6 +328,7 @@ impl IMode for GetUnitOrdersMode {
                                         Ok(ref proposed_move_result) => {
                                             let move_ = &proposed_move_result.outcome;
 
+                                            ui.set_reachable(BTreeSet::new());
                                             ui.animate_move(game, move_).await.unwrap();
 
                                             let move_ = match
@@ -159,24 +337,7 @@ impl IMode for GetUnitOrdersMode {
                                                         _ => panic!("Did not find PlayerActionOutcome::MoveUnit as expected"),
                                                     };
 
-                                            if let Some(conquered_city) = move_.conquered_city() {
-                                                *mode = Mode::SetProduction {
-                                                    city_loc: conquered_city.loc,
-                                                };
-                                            } else if game
-                                                .player_unit_orders_requests()
-                                                .await
-                                                .iter()
-                                                .cloned()
-                                                .any(|unit_id| unit_id == self.unit_id)
-                                            {
-                                                *mode = Mode::GetUnitOrders {
-                                                    unit_id: self.unit_id,
-                                                    first_move: false,
-                                                };
-                                            } else {
-                                                *mode = Mode::GetOrders;
-                                            }
+                                            *mode = self.mode_after_move(game, &move_).await;
 
                                             Self::clear_buf(ui);
                                             return ModeStatus::Continue;
@@ -191,6 +352,17 @@ impl IMode for GetUnitOrdersMode {
                                 *mode = Mode::GetOrders;
                                 Self::clear_buf(ui);
                                 return ModeStatus::Continue;
+                            } else if c == conf::KEY_NEXT_UNIT {
+                                if let Some(unit_id) = self.next_unit(game).await {
+                                    // This unit keeps its place in line, to come around to again
+                                    *mode = Mode::GetUnitOrders {
+                                        unit_id,
+                                        first_move: true,
+                                    };
+                                    Self::clear_buf(ui);
+                                    return ModeStatus::Continue;
+                                }
+                                ui.log_message("No other unit awaits orders");
                             } else if c == conf::KEY_SENTRY {
                                 ui.log_message("Going sentry");
                                 game.order_unit_sentry(self.unit_id).await.unwrap();
@@ -200,13 +372,252 @@ impl IMode for GetUnitOrdersMode {
                             } else if c == conf::KEY_DISBAND {
                                 let unit_disbanded =
                                     game.disband_unit_by_id(self.unit_id).await.unwrap();
-                                ui.log_message(format!(
-                                    "Disbanded unit {}",
-                                    unit_disbanded.unit.short_desc()
-                                ));
+                                if unit_disbanded.refund > 0 {
+                                    ui.log_message(format!(
+                                        "Disbanded unit {}, refunding {} production",
+                                        unit_disbanded.unit.short_desc(),
+                                        unit_disbanded.refund
+                                    ));
+                                } else {
+                                    ui.log_message(format!(
+                                        "Disbanded unit {}",
+                                        unit_disbanded.unit.short_desc()
+                                    ));
+                                }
                                 *mode = Mode::GetOrders;
                                 Self::clear_buf(ui);
                                 return ModeStatus::Continue;
+                            } else if c == conf::KEY_LOAD {
+                                // Board the first adjacent unit that will carry this one
+                                let mut loaded = None;
+                                for dir in Direction::values() {
+                                    if let Some(loc) =
+                                        unit_loc.shift_wrapped(dir, game.dims(), game.wrapping())
+                                    {
+                                        if let Some(carrier) =
+                                            game.player_toplevel_unit_by_loc(loc).await
+                                        {
+                                            if let Ok(move_) =
+                                                game.load_unit(self.unit_id, carrier.id).await
+                                            {
+                                                loaded = Some(move_);
+                                                break;
+                                            }
+                                        }
+                                    }
+                                }
+
+                                match loaded {
+                                    Some(move_) => {
+                                        ui.animate_move(game, &move_).await.unwrap();
+                                        *mode = self.mode_after_move(game, &move_).await;
+                                        Self::clear_buf(ui);
+                                        return ModeStatus::Continue;
+                                    }
+                                    None => {
+                                        ui.log_message("No adjacent unit can carry this unit");
+                                    }
+                                }
+                            } else if c == conf::KEY_UNLOAD {
+                                ui.log_message("Disembark in which direction?");
+                                ui.draw_log(game).await.unwrap();
+
+                                let dest = match self.get_key(game, ui, mode).await {
+                                    Ok(KeyStatus::Unhandled(key)) => match key.code {
+                                        KeyCode::Char(c) => {
+                                            Direction::try_from(c).ok().and_then(|dir| {
+                                                unit_loc.shift_wrapped(
+                                                    dir,
+                                                    game.dims(),
+                                                    game.wrapping(),
+                                                )
+                                            })
+                                        }
+                                        _ => None,
+                                    },
+                                    Ok(KeyStatus::Clicked(viewport_loc)) => {
+                                        ui.viewport_to_map_coords(game, viewport_loc).await
+                                    }
+                                    Ok(KeyStatus::Handled(state_disposition)) => {
+                                        match state_disposition {
+                                            StateDisposition::Quit => return ModeStatus::Quit,
+                                            StateDisposition::Next => return ModeStatus::Continue,
+                                            StateDisposition::Stay => None,
+                                        }
+                                    }
+                                    Err(_err) => return ModeStatus::Quit,
+                                };
+
+                                match dest {
+                                    Some(dest) => {
+                                        match game.unload_unit(self.unit_id, dest).await {
+                                            Ok(move_) => {
+                                                ui.animate_move(game, &move_).await.unwrap();
+                                                *mode = self.mode_after_move(game, &move_).await;
+                                                Self::clear_buf(ui);
+                                                return ModeStatus::Continue;
+                                            }
+                                            Err(err) => {
+                                                ui.log_message(format!("Error: {}", err));
+                                            }
+                                        }
+                                    }
+                                    None => {
+                                        ui.log_message("Not disembarking");
+                                    }
+                                }
+                            } else if c == conf::KEY_REBASE {
+                                // Fly to the nearest city or carrier that can take this aircraft
+                                let base = match game.player_air_bases(self.unit_id).await {
+                                    Ok(bases) => bases.first().copied(),
+                                    Err(err) => {
+                                        ui.log_message(format!("Error: {}", err));
+                                        continue;
+                                    }
+                                };
+
+                                match base {
+                                    Some(base) => {
+                                        match game.rebase_unit(self.unit_id, base).await {
+                                            Ok(move_) => {
+                                                ui.animate_move(game, &move_).await.unwrap();
+                                                *mode = self.mode_after_move(game, &move_).await;
+                                                Self::clear_buf(ui);
+                                                return ModeStatus::Continue;
+                                            }
+                                            Err(err) => {
+                                                ui.log_message(format!("Error: {}", err));
+                                            }
+                                        }
+                                    }
+                                    None => {
+                                        ui.log_message("No city or carrier within range");
+                                    }
+                                }
+                            } else if c == conf::KEY_BOMBARD {
+                                // Fire on the nearest enemy within range
+                                let target =
+                                    match game.player_unit_bombard_targets(self.unit_id).await {
+                                        Ok(targets) => targets.first().copied(),
+                                        Err(err) => {
+                                            ui.log_message(format!("Error: {}", err));
+                                            continue;
+                                        }
+                                    };
+
+                                match target {
+                                    Some(target) => {
+                                        match game.bombard(self.unit_id, target).await {
+                                            Ok(bombardment) => {
+                                                let defender = bombardment.combat.defender();
+                                                ui.log_message(
+                                                    if bombardment.combat.victorious() {
+                                                        format!("Destroyed {}", defender)
+                                                    } else {
+                                                        format!(
+                                                            "{} withstood the bombardment",
+                                                            defender
+                                                        )
+                                                    },
+                                                );
+                                                *mode = Mode::GetOrders;
+                                                Self::clear_buf(ui);
+                                                return ModeStatus::Continue;
+                                            }
+                                            Err(err) => {
+                                                ui.log_message(format!("Error: {}", err));
+                                            }
+                                        }
+                                    }
+                                    None => {
+                                        ui.log_message("No enemy within range");
+                                    }
+                                }
+                            } else if c == conf::KEY_PARADROP {
+                                // Pick the drop zone with the examine cursor
+                                let zones = match game.player_unit_drop_zones(self.unit_id).await {
+                                    Ok(zones) => zones,
+                                    Err(err) => {
+                                        ui.log_message(format!("Error: {}", err));
+                                        continue;
+                                    }
+                                };
+
+                                if zones.is_empty() {
+                                    ui.log_message("No drop zones within range");
+                                } else {
+                                    ui.log_message("Choose where to drop the unit (Enter)");
+
+                                    *mode = Mode::Examine {
+                                        cursor_viewport_loc: ui
+                                            .map_to_viewport_coords(unit_loc)
+                                            .unwrap(),
+                                        most_recently_active_unit_id: Some(self.unit_id),
+                                        group: None,
+                                        paradrop: Some(self.unit_id),
+                                        waypoints: Waypoints::new(),
+                                        first: true,
+                                    };
+                                    Self::clear_buf(ui);
+                                    return ModeStatus::Continue;
+                                }
+                            } else if c == conf::KEY_BUILD_ROAD
+                                || c == conf::KEY_BUILD_FORTIFICATION
+                                || c == conf::KEY_BUILD_RADAR
+                            {
+                                let improvement = if c == conf::KEY_BUILD_ROAD {
+                                    Improvement::Road
+                                } else if c == conf::KEY_BUILD_FORTIFICATION {
+                                    Improvement::Fortification
+                                } else {
+                                    Improvement::Radar
+                                };
+
+                                match game
+                                    .propose_order_unit_build(self.unit_id, improvement)
+                                    .await
+                                {
+                                    Ok(proposed_orders_result) => {
+                                        ui.log_message(format!(
+                                            "Building a {}",
+                                            improvement.to_string().to_lowercase()
+                                        ));
+                                        game.take_action(proposed_orders_result.action)
+                                            .await
+                                            .unwrap();
+
+                                        *mode = Mode::GetOrders;
+                                        Self::clear_buf(ui);
+                                        return ModeStatus::Continue;
+                                    }
+                                    Err(err) => {
+                                        ui.log_message(format!("Error: {}", err));
+                                    }
+                                }
+                            } else if c == conf::KEY_LAY_MINES {
+                                match game.lay_mines(self.unit_id).await {
+                                    Ok(loc) => {
+                                        ui.log_message(format!("Laid mines at {}", loc));
+                                        *mode = Mode::GetOrders;
+                                        Self::clear_buf(ui);
+                                        return ModeStatus::Continue;
+                                    }
+                                    Err(err) => {
+                                        ui.log_message(format!("Error: {}", err));
+                                    }
+                                }
+                            } else if c == conf::KEY_SWEEP_MINES {
+                                match game.sweep_mines(self.unit_id).await {
+                                    Ok(swept) => {
+                                        ui.log_message(format!("Swept {} minefields", swept.len()));
+                                        *mode = Mode::GetOrders;
+                                        Self::clear_buf(ui);
+                                        return ModeStatus::Continue;
+                                    }
+                                    Err(err) => {
+                                        ui.log_message(format!("Error: {}", err));
+                                    }
+                                }
                             } else if c == conf::KEY_EXPLORE {
                                 let proposed_orders_result =
                                     game.propose_order_unit_explore(self.unit_id).await.unwrap();
@@ -227,6 +638,35 @@ impl IMode for GetUnitOrdersMode {
                             }
                         }
                     }
+                    KeyStatus::Clicked(viewport_loc) => {
+                        // Send the unit to the clicked tile
+                        let dest = match ui.viewport_to_map_coords(game, viewport_loc).await {
+                            Some(dest) if dest != unit_loc => dest,
+                            _ => continue,
+                        };
+
+                        match game.propose_order_unit_go_to(self.unit_id, dest).await {
+                            Ok(proposed_orders_result) => {
+                                if let Some(ref proposed_move) =
+                                    proposed_orders_result.outcome.move_
+                                {
+                                    ui.animate_move(game, proposed_move).await.unwrap();
+                                }
+                                ui.log_message(format!("Ordered unit to go to {}", dest));
+
+                                game.take_action(proposed_orders_result.action)
+                                    .await
+                                    .unwrap();
+
+                                *mode = Mode::GetOrders;
+                                Self::clear_buf(ui);
+                                return ModeStatus::Continue;
+                            }
+                            Err(err) => {
+                                ui.log_message(format!("Error: {}", err));
+                            }
+                        }
+                    }
                     KeyStatus::Handled(state_disposition) => match state_disposition {
                         StateDisposition::Quit => return ModeStatus::Quit,
                         StateDisposition::Next => return ModeStatus::Continue,
@@ -0,0 +1,215 @@
+use crossterm::event::KeyCode;
+
+use common::{
+    conf,
+    game::{
+        player::PlayerTurn,
+        unit::{orders::Waypoints, UnitID},
+    },
+    log::{Message, MessageSource},
+    util::Rect,
+};
+
+use crate::ui::UI;
+
+use super::{cols, IMode, IVisibleMode, KeyStatus, Mode, ModeStatus, StateDisposition};
+
+/// Organize units into named groups and pick a group to give orders to
+///
+/// Number keys select a group. `unit_id` is the unit that was active on entering the mode, which can be put into the
+/// selected group or a new one.
+pub(in crate::ui) struct GroupsMode {
+    pub rect: Rect,
+    pub unit_id: Option<UnitID>,
+    pub selected: Option<usize>,
+}
+impl GroupsMode {
+    fn write_buf<U: UI>(&self, groups: &[(String, Vec<UnitID>)], ui: &mut U) {
+        ui.clear_sidebar();
+        ui.set_sidebar_row(0, String::from("Unit Groups"));
+
+        let mut y = 2;
+        if groups.is_empty() {
+            ui.set_sidebar_row(y, String::from(" No groups yet"));
+            y += 1;
+        }
+        for (idx, (name, members)) in groups.iter().enumerate() {
+            let marker = if self.selected == Some(idx) { '>' } else { ' ' };
+
+            let units = if members.len() == 1 { "unit" } else { "units" };
+
+            ui.set_sidebar_row(
+                y,
+                cols(
+                    format!("{}[{}] {}", marker, idx + 1, name),
+                    format!("{} {}", members.len(), units),
+                ),
+            );
+            y += 1;
+        }
+
+        y += 1;
+        if self.unit_id.is_some() {
+            ui.set_sidebar_row(y, cols("Add unit to group:", conf::KEY_GROUP_ADD));
+            ui.set_sidebar_row(y + 2, cols("New group of unit:", conf::KEY_GROUP_NEW));
+            y += 4;
+        }
+        ui.set_sidebar_row(y, cols("Order group:", "Enter"));
+        ui.set_sidebar_row(y + 2, cols("Disband group:", conf::KEY_GROUP_DISBAND));
+        ui.set_sidebar_row(y + 4, cols("Done:", "Esc"));
+    }
+
+    /// Carry out the group management bound to `key`, describing the result
+    async fn manage(
+        &self,
+        game: &mut PlayerTurn<'_>,
+        groups: &[(String, Vec<UnitID>)],
+        key: char,
+    ) -> Option<String> {
+        let selected = self.selected.map(|idx| &groups[idx]);
+
+        match (key, self.unit_id, selected) {
+            (conf::KEY_GROUP_ADD, Some(unit_id), Some((name, members))) => {
+                let mut members = members.clone();
+                if !members.contains(&unit_id) {
+                    members.push(unit_id);
+                }
+                Some(match game.form_unit_group(name.clone(), members).await {
+                    Ok(()) => format!("Added unit to {}", name),
+                    Err(err) => format!("Couldn't add unit to {}: {}", name, err),
+                })
+            }
+            (conf::KEY_GROUP_NEW, Some(unit_id), _) => {
+                // The first name of the form "Group N" not already taken
+                let name = (1..)
+                    .map(|n| format!("Group {}", n))
+                    .find(|name| groups.iter().all(|(taken, _)| taken != name))
+                    .unwrap();
+
+                Some(
+                    match game.form_unit_group(name.clone(), vec![unit_id]).await {
+                        Ok(()) => format!("Formed {}", name),
+                        Err(err) => format!("Couldn't form group: {}", err),
+                    },
+                )
+            }
+            (conf::KEY_GROUP_DISBAND, _, Some((name, _))) => {
+                Some(match game.disband_unit_group(name.clone()).await {
+                    Ok(_members) => format!("Disbanded {}", name),
+                    Err(err) => format!("Couldn't disband {}: {}", name, err),
+                })
+            }
+            _ => None,
+        }
+    }
+}
+
+impl IMode for GroupsMode {
+    async fn run<U: UI + Send + Sync>(
+        &self,
+        game: &mut PlayerTurn<'_>,
+        ui: &mut U,
+        mode: &mut Mode,
+        _prev_mode: &Option<Mode>,
+    ) -> ModeStatus {
+        let groups = game.unit_groups().await;
+
+        self.write_buf(&groups, ui);
+        ui.draw_no_flush(game).await.unwrap();
+
+        loop {
+            match self.get_key(game, ui, mode).await {
+                Ok(key) => match key {
+                    KeyStatus::Unhandled(key) => {
+                        if key.code == KeyCode::Esc {
+                            Self::clear_buf(ui);
+                            *mode = Mode::TurnResume;
+                            return ModeStatus::Continue;
+                        }
+
+                        if key.code == KeyCode::Enter {
+                            if let Some(group) = self.selected {
+                                Self::clear_buf(ui);
+
+                                let cursor_viewport_loc = ui
+                                    .cursor_viewport_loc(mode, game)
+                                    .await
+                                    .unwrap_or(ui.viewport_rect().center());
+
+                                ui.log_message(format!(
+                                    "Choose where {} goes (Enter) or attacks ({})",
+                                    groups[group].0,
+                                    conf::KEY_GROUP_ATTACK
+                                ));
+
+                                *mode = Mode::Examine {
+                                    cursor_viewport_loc,
+                                    most_recently_active_unit_id: None,
+                                    group: Some(group),
+                                    paradrop: None,
+                                    waypoints: Waypoints::new(),
+                                    first: true,
+                                };
+                                return ModeStatus::Continue;
+                            }
+                        }
+
+                        if let KeyCode::Char(c) = key.code {
+                            if let Some(n) = c.to_digit(10) {
+                                let n = n as usize;
+                                if n >= 1 && n <= groups.len() {
+                                    *mode = Mode::Groups {
+                                        unit_id: self.unit_id,
+                                        selected: Some(n - 1),
+                                    };
+                                    return ModeStatus::Continue;
+                                }
+                            } else if let Some(text) = self.manage(game, &groups, c).await {
+                                ui.log_message(Message {
+                                    text,
+                                    mark: Some('·'),
+                                    bg_color: None,
+                                    fg_color: None,
+                                    source: Some(MessageSource::Mode),
+                                });
+                                ui.draw_log(game).await.unwrap();
+
+                                // Redraw with the groups as they now stand
+                                *mode = Mode::Groups {
+                                    unit_id: self.unit_id,
+                                    selected: None,
+                                };
+                                return ModeStatus::Continue;
+                            }
+                        }
+                    }
+                    KeyStatus::Clicked(viewport_loc) => {
+                        Self::clear_buf(ui);
+                        self.examine(game, ui, mode, viewport_loc).await;
+                        return ModeStatus::Continue;
+                    }
+                    KeyStatus::Handled(state_disposition) => match state_disposition {
+                        StateDisposition::Quit => return ModeStatus::Quit,
+                        StateDisposition::Next => return ModeStatus::Continue,
+                        StateDisposition::Stay => {}
+                    },
+                },
+                Err(_err) => {
+                    // RecvError comes from the input thread exiting before the UI itself.
+                    // So, just quit the app, we're probably already trying to do so.
+                    return ModeStatus::Quit;
+                }
+            }
+        }
+    }
+}
+
+impl IVisibleMode for GroupsMode {
+    fn clear_buf<U: UI>(ui: &mut U) {
+        ui.clear_sidebar();
+    }
+
+    fn rect(&self) -> Rect {
+        self.rect
+    }
+}
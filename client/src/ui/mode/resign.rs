@@ -0,0 +1,77 @@
+use crossterm::event::KeyCode;
+
+use common::{
+    colors::Colors,
+    conf,
+    game::player::PlayerTurn,
+    log::{Message, MessageSource},
+};
+
+use crate::ui::UI;
+
+use super::{IMode, KeyStatus, Mode, ModeStatus, StateDisposition};
+
+/// Confirm that the player means to resign rather than just disconnect
+pub(in crate::ui) struct ResignMode;
+
+impl IMode for ResignMode {
+    async fn run<U: UI + Send + Sync>(
+        &self,
+        game: &mut PlayerTurn<'_>,
+        ui: &mut U,
+        mode: &mut Mode,
+        _prev_mode: &Option<Mode>,
+    ) -> ModeStatus {
+        ui.log_message(Message {
+            text: format!(
+                "Resign? Your cities go neutral and your units disband. {} resigns and quits, {} just quits, anything else plays on.",
+                conf::KEY_CONFIRM_RESIGN,
+                conf::KEY_QUIT
+            ),
+            mark: Some('?'),
+            fg_color: Some(Colors::Text),
+            bg_color: None,
+            source: Some(MessageSource::Mode),
+        });
+        ui.draw_log(game).await.unwrap();
+
+        loop {
+            match self.get_key(game, ui, mode).await {
+                Ok(key) => match key {
+                    KeyStatus::Unhandled(key) => {
+                        if key.code == KeyCode::Char(conf::KEY_CONFIRM_RESIGN) {
+                            match game.resign().await {
+                                Ok(_resigned) => {
+                                    ui.log_message("Resigned");
+                                    ui.draw_log(game).await.unwrap();
+                                    return ModeStatus::Quit;
+                                }
+                                Err(err) => {
+                                    ui.log_message(format!("Couldn't resign: {}", err));
+                                    ui.draw_log(game).await.unwrap();
+                                }
+                            }
+                        }
+
+                        *mode = Mode::TurnResume;
+                        return ModeStatus::Continue;
+                    }
+                    KeyStatus::Clicked(viewport_loc) => {
+                        self.examine(game, ui, mode, viewport_loc).await;
+                        return ModeStatus::Continue;
+                    }
+                    KeyStatus::Handled(state_disposition) => match state_disposition {
+                        StateDisposition::Quit => return ModeStatus::Quit,
+                        StateDisposition::Next => return ModeStatus::Continue,
+                        StateDisposition::Stay => {}
+                    },
+                },
+                Err(_err) => {
+                    // RecvError comes from the input thread exiting before the UI itself.
+                    // So, just quit the app, we're probably already trying to do so.
+                    return ModeStatus::Quit;
+                }
+            }
+        }
+    }
+}
@@ -2,7 +2,7 @@ use crossterm::event::KeyCode;
 
 use common::{
     conf,
-    game::{player::PlayerTurn, unit::UnitType},
+    game::{city::City, map::Improvement, player::PlayerTurn, unit::UnitType},
     log::{Message, MessageSource},
     util::{Location, Rect},
 };
@@ -12,9 +12,19 @@ use crate::ui::{audio::Sounds, UI};
 
 use super::{IMode, IVisibleMode, KeyStatus, Mode, ModeStatus, StateDisposition, COL_WIDTH};
 
+/// Sidebar rows not given over to the menu of unit types
+const NON_MENU_ROWS: usize = 11;
+
+/// Choose what a city produces from a menu of the unit types it can produce
+///
+/// The arrow keys move the selection and Enter picks it. Each unit type's key picks it directly, as before.
 pub(in crate::ui) struct SetProductionMode {
     pub loc: Location,
     pub rect: Rect,
+
+    /// Index of the selected unit type among those the city can produce
+    pub selected: usize,
+
     pub unicode: bool,
 }
 impl SetProductionMode {
@@ -36,29 +46,110 @@ impl SetProductionMode {
         row
     }
 
-    async fn write_buf<U: UI>(&self, game: &PlayerTurn<'_>, ui: &mut U) {
+    /// The menu entry for `unit_type`, in a city `progress` turns into its production
+    fn unit_type_row(&self, unit_type: UnitType, selected: bool, progress: u16) -> String {
+        let marker = if selected { '>' } else { ' ' };
+
+        // Progress carries over to whatever the city is set to produce
+        let turns = unit_type.cost().saturating_sub(progress).max(1);
+
+        format!(
+            "{}[{}] {} {:<11}{:>4}{:>4} turn{}",
+            marker,
+            unit_type.key(),
+            unit_type.sym(self.unicode),
+            unit_type.name(),
+            unit_type.cost(),
+            turns,
+            if turns == 1 { "" } else { "s" }
+        )
+    }
+
+    /// What a unit of type `unit_type` is like, in a row
+    fn stats_row(unit_type: UnitType) -> String {
+        format!(
+            "  {} hp, {} moves, sees {}, goes by {}",
+            unit_type.max_hp(),
+            unit_type.movement_per_turn(),
+            unit_type.sight_distance(),
+            format!("{:?}", unit_type.transport_mode()).to_lowercase()
+        )
+    }
+
+    async fn write_buf<U: UI>(
+        &self,
+        game: &PlayerTurn<'_>,
+        ui: &mut U,
+        productions: &[UnitType],
+        selected: usize,
+    ) {
         let tile = game.tile(self.loc).unwrap();
         let city = tile.city.as_ref().unwrap();
 
         ui.clear_sidebar();
         ui.set_sidebar_row(0, format!("Set Production for {}", city));
+        ui.set_sidebar_row(2, format!("{:<18}{:>4}{:>10}", "     Unit", "Cost", "Time"));
 
-        let mut highest_y = 0;
+        let menu_rows = (self.rect.height as usize)
+            .saturating_sub(NON_MENU_ROWS)
+            .max(1);
 
-        for (i, unit_type) in game.valid_productions(self.loc).await.iter().enumerate() {
-            let y = i + 2;
-            let row = self.row(
-                unit_type.key(),
-                unit_type.sym(self.unicode),
-                unit_type.name(),
-                Some(unit_type.cost()),
-            );
+        // Scroll just far enough to keep the selected unit type in view
+        let first = (selected + 1).saturating_sub(menu_rows);
+
+        let mut y = 3;
+        for (i, unit_type) in productions.iter().enumerate().skip(first).take(menu_rows) {
+            let row = self.unit_type_row(*unit_type, i == selected, city.production_progress);
             ui.set_sidebar_row(y, row);
-            highest_y = y;
+            y += 1;
+        }
+
+        if let Some(unit_type) = productions.get(selected) {
+            ui.set_sidebar_row(y + 1, Self::stats_row(*unit_type));
         }
+        y += 3;
 
         let row = self.row(conf::KEY_NO_PRODUCTION, " ", "None", None);
-        ui.set_sidebar_row(highest_y + 2, row);
+        ui.set_sidebar_row(y, row);
+
+        if city.walls() < conf::CITY_MAX_WALLS {
+            let row = self.row(
+                conf::KEY_BUILD_WALLS,
+                " ",
+                "Walls",
+                Some(conf::CITY_WALL_COST),
+            );
+            ui.set_sidebar_row(y + 1, row);
+        }
+
+        if !tile.has_improvement(Improvement::Radar) {
+            let row = self.row(
+                conf::KEY_BUILD_RADAR,
+                " ",
+                "Radar",
+                Some(conf::CITY_RADAR_COST),
+            );
+            ui.set_sidebar_row(y + 2, row);
+        }
+
+        ui.set_sidebar_row(y + 4, String::from("Up/Down: select   Enter: produce"));
+    }
+
+    /// Draw the city as the one being asked after, and flush
+    async fn draw_city<U: UI>(&self, game: &PlayerTurn<'_>, ui: &mut U, city: &City) {
+        let city_viewport_loc = ui.map_to_viewport_coords(city.loc).unwrap();
+        ui.draw_map_tile_and_flush(
+            game,
+            city_viewport_loc,
+            false,
+            true,
+            Some(Some(city)),
+            None,
+            None,
+            None,
+        )
+        .await
+        .unwrap();
     }
 }
 
@@ -74,7 +165,10 @@ impl IMode for SetProductionMode {
 
         ui.play_sound(Sounds::Silence);
 
-        self.write_buf(game, ui).await;
+        let productions = game.valid_productions_conservative(self.loc).await;
+        let mut selected = self.selected.min(productions.len().saturating_sub(1));
+
+        self.write_buf(game, ui, &productions, selected).await;
         ui.draw_no_flush(game).await.unwrap();
 
         let city = {
@@ -87,69 +181,122 @@ impl IMode for SetProductionMode {
 
             city
         };
-        // let city_viewport_loc = ui.map_scroller.scrollable.map_to_viewport_coords(city.loc).unwrap();
-        let city_viewport_loc = ui.map_to_viewport_coords(city.loc).unwrap();
-        ui.draw_map_tile_and_flush(
-            game,
-            city_viewport_loc,
-            false,
-            true,
-            Some(Some(&city)),
-            None,
-            None,
-            None,
-        )
-        .await
-        .unwrap();
+        self.draw_city(game, ui, &city).await;
 
         loop {
             match self.get_key(game, ui, mode).await {
                 Ok(key) => {
                     match key {
                         KeyStatus::Unhandled(key) => {
-                            if let KeyCode::Char(c) = key.code {
-                                if let Ok(unit_type) = UnitType::try_from_key(c) {
-                                    game.set_production_by_loc(self.loc, unit_type)
-                                        .await
-                                        .unwrap();
-
-                                    let city = game.player_city_by_loc(self.loc).await.unwrap();
-                                    ui.log_message(Message {
-                                        text: format!(
-                                            "Set {}'s production to {}",
-                                            city.short_desc(),
-                                            unit_type
-                                        ),
-                                        mark: Some('·'),
-                                        bg_color: None,
-                                        fg_color: None,
-                                        source: Some(MessageSource::Mode),
-                                    });
-                                    ui.draw_log(game).await.unwrap();
-
-                                    Self::clear_buf(ui);
-
-                                    *mode = Mode::TurnResume;
-                                    return ModeStatus::Continue;
-                                } else if c == conf::KEY_NO_PRODUCTION {
-                                    if game.player_cities_producing_or_not_ignored().await <= 1 {
-                                        game.clear_production(self.loc, false).await.unwrap();
-                                        // let cursor_viewport_loc = ui.cursor_viewport_loc(mode, game).unwrap();
-
-                                        // *mode = Mode::Examine {
-                                        //     cursor_viewport_loc,
-                                        //     first: true,
-                                        //     most_recently_active_unit_id: None,
-                                        // };
-                                    } else {
-                                        // game.set_production(self.loc, None).unwrap();
-                                        game.clear_production(self.loc, true).await.unwrap();
-                                    }
-
-                                    *mode = Mode::TurnResume;
-                                    return ModeStatus::Continue;
+                            let moved = match key.code {
+                                KeyCode::Up if selected > 0 => Some(selected - 1),
+                                KeyCode::Down if selected + 1 < productions.len() => {
+                                    Some(selected + 1)
                                 }
+                                _ => None,
+                            };
+                            if let Some(moved) = moved {
+                                selected = moved;
+
+                                // Remember the selection should this mode be run again
+                                *mode = Mode::SetProduction {
+                                    city_loc: self.loc,
+                                    selected,
+                                };
+
+                                self.write_buf(game, ui, &productions, selected).await;
+                                ui.draw_no_flush(game).await.unwrap();
+                                self.draw_city(game, ui, &city).await;
+                                continue;
                             }
+
+                            let chosen = match key.code {
+                                KeyCode::Enter => productions.get(selected).copied(),
+                                KeyCode::Char(c) => UnitType::try_from_key(c).ok(),
+                                _ => None,
+                            };
+
+                            if let Some(unit_type) = chosen {
+                                game.set_production_by_loc(self.loc, unit_type)
+                                    .await
+                                    .unwrap();
+
+                                let city = game.player_city_by_loc(self.loc).await.unwrap();
+                                ui.log_message(Message {
+                                    text: format!(
+                                        "Set {}'s production to {}",
+                                        city.short_desc(),
+                                        unit_type
+                                    ),
+                                    mark: Some('·'),
+                                    bg_color: None,
+                                    fg_color: None,
+                                    source: Some(MessageSource::Mode),
+                                });
+                                ui.draw_log(game).await.unwrap();
+
+                                Self::clear_buf(ui);
+
+                                *mode = Mode::TurnResume;
+                                return ModeStatus::Continue;
+                            } else if key.code == KeyCode::Char(conf::KEY_NO_PRODUCTION) {
+                                if game.player_cities_producing_or_not_ignored().await <= 1 {
+                                    game.clear_production(self.loc, false).await.unwrap();
+                                    // let cursor_viewport_loc = ui.cursor_viewport_loc(mode, game).unwrap();
+
+                                    // *mode = Mode::Examine {
+                                    //     cursor_viewport_loc,
+                                    //     first: true,
+                                    //     most_recently_active_unit_id: None,
+                                    // };
+                                } else {
+                                    // game.set_production(self.loc, None).unwrap();
+                                    game.clear_production(self.loc, true).await.unwrap();
+                                }
+
+                                *mode = Mode::TurnResume;
+                                return ModeStatus::Continue;
+                            } else if key.code == KeyCode::Char(conf::KEY_BUILD_WALLS) {
+                                let text = match game.build_city_walls(city.id).await {
+                                    Ok(walls_built) => format!(
+                                        "Built {}'s walls to level {}",
+                                        city.short_desc(),
+                                        walls_built.walls
+                                    ),
+                                    Err(err) => format!("Couldn't build walls: {}", err),
+                                };
+                                ui.log_message(Message {
+                                    text,
+                                    mark: Some('·'),
+                                    bg_color: None,
+                                    fg_color: None,
+                                    source: Some(MessageSource::Mode),
+                                });
+                                ui.draw_log(game).await.unwrap();
+
+                                // Redraw with the walls as they now stand; production is still to be chosen
+                                return ModeStatus::Continue;
+                            } else if key.code == KeyCode::Char(conf::KEY_BUILD_RADAR) {
+                                let text = match game.build_city_radar(city.id).await {
+                                    Ok(_) => format!("Built a radar in {}", city.short_desc()),
+                                    Err(err) => format!("Couldn't build radar: {}", err),
+                                };
+                                ui.log_message(Message {
+                                    text,
+                                    mark: Some('·'),
+                                    bg_color: None,
+                                    fg_color: None,
+                                    source: Some(MessageSource::Mode),
+                                });
+                                ui.draw_log(game).await.unwrap();
+
+                                return ModeStatus::Continue;
+                            }
+                        }
+                        KeyStatus::Clicked(viewport_loc) => {
+                            Self::clear_buf(ui);
+                            self.examine(game, ui, mode, viewport_loc).await;
+                            return ModeStatus::Continue;
                         }
                         KeyStatus::Handled(state_disposition) => match state_disposition {
                             StateDisposition::Quit => return ModeStatus::Quit,
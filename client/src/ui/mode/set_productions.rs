@@ -28,7 +28,10 @@ impl IMode for SetProductionsMode {
             .next()
             .unwrap();
 
-        *mode = Mode::SetProduction { city_loc };
+        *mode = Mode::SetProduction {
+            city_loc,
+            selected: 0,
+        };
         ModeStatus::Continue
     }
 }
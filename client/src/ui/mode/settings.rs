@@ -0,0 +1,167 @@
+use crossterm::event::KeyCode;
+
+use common::{
+    conf,
+    game::player::PlayerTurn,
+    log::{Message, MessageSource},
+    util::Rect,
+};
+
+use crate::ui::{settings::Settings, UI};
+
+use super::{cols, IMode, IVisibleMode, KeyStatus, Mode, ModeStatus, StateDisposition};
+
+fn on_off(on: bool) -> &'static str {
+    if on {
+        "on"
+    } else {
+        "off"
+    }
+}
+
+/// Change the client's settings mid-game
+///
+/// Number keys change the setting they're listed by. Changes take effect right away and are saved for later runs.
+pub(in crate::ui) struct SettingsMode {
+    pub rect: Rect,
+}
+impl SettingsMode {
+    fn write_buf<U: UI>(&self, ui: &mut U) {
+        let settings = ui.settings();
+
+        ui.clear_sidebar();
+        ui.set_sidebar_row(0, String::from("Settings"));
+
+        let rows = [
+            ("Unicode symbols", on_off(settings.unicode).to_string()),
+            ("Animation speed", settings.animation_speed.to_string()),
+            (
+                "Auto end turn",
+                on_off(!settings.confirm_turn_end).to_string(),
+            ),
+            ("Sound", on_off(settings.sound).to_string()),
+            (
+                "Confirm attacks",
+                on_off(settings.confirm_attacks).to_string(),
+            ),
+            ("Music in game", on_off(settings.music).to_string()),
+            ("Music volume", format!("{}%", settings.music_volume)),
+        ];
+        for (i, (desc, value)) in rows.into_iter().enumerate() {
+            ui.set_sidebar_row(2 + i, cols(format!("[{}] {}", i + 1, desc), value));
+        }
+
+        ui.set_sidebar_row(10, cols("Done:", "Esc"));
+    }
+
+    /// The settings after changing the one listed by `key`, with a description of the change
+    fn change(settings: Settings, key: char) -> Option<(Settings, String)> {
+        let mut settings = settings;
+        let desc = match key {
+            '1' => {
+                settings.unicode = !settings.unicode;
+                format!("Unicode symbols {}", on_off(settings.unicode))
+            }
+            '2' => {
+                settings.animation_speed = settings.animation_speed.next();
+                format!("Animation speed {}", settings.animation_speed)
+            }
+            '3' => {
+                settings.confirm_turn_end = !settings.confirm_turn_end;
+                format!("Auto end turn {}", on_off(!settings.confirm_turn_end))
+            }
+            '4' => {
+                settings.sound = !settings.sound;
+                format!("Sound {}", on_off(settings.sound))
+            }
+            '5' => {
+                settings.confirm_attacks = !settings.confirm_attacks;
+                format!("Confirm attacks {}", on_off(settings.confirm_attacks))
+            }
+            '6' => {
+                settings.music = !settings.music;
+                format!("Music in game {}", on_off(settings.music))
+            }
+            '7' => {
+                settings.music_volume = settings.music_volume % 100 + 25;
+                format!("Music volume {}%", settings.music_volume)
+            }
+            _ => return None,
+        };
+        Some((settings, desc))
+    }
+}
+
+impl IMode for SettingsMode {
+    async fn run<U: UI + Send + Sync>(
+        &self,
+        game: &mut PlayerTurn<'_>,
+        ui: &mut U,
+        mode: &mut Mode,
+        _prev_mode: &Option<Mode>,
+    ) -> ModeStatus {
+        self.write_buf(ui);
+        ui.draw_no_flush(game).await.unwrap();
+
+        loop {
+            match self.get_key(game, ui, mode).await {
+                Ok(key) => match key {
+                    KeyStatus::Unhandled(key) => {
+                        if key.code == KeyCode::Esc {
+                            Self::clear_buf(ui);
+                            *mode = Mode::TurnResume;
+                            return ModeStatus::Continue;
+                        }
+
+                        if let KeyCode::Char(c) = key.code {
+                            if let Some((settings, desc)) = Self::change(ui.settings(), c) {
+                                ui.set_settings(settings);
+
+                                let text = match settings.save(conf::SETTINGS_PATH) {
+                                    Ok(()) => desc,
+                                    Err(err) => format!("{}, but couldn't save it: {}", desc, err),
+                                };
+                                ui.log_message(Message {
+                                    text,
+                                    mark: Some('·'),
+                                    bg_color: None,
+                                    fg_color: None,
+                                    source: Some(MessageSource::Mode),
+                                });
+
+                                // Redraw everything, as the symbols may have changed
+                                ui.draw(game).await.unwrap();
+                                return ModeStatus::Continue;
+                            }
+                        }
+                    }
+                    KeyStatus::Clicked(viewport_loc) => {
+                        Self::clear_buf(ui);
+                        self.examine(game, ui, mode, viewport_loc).await;
+                        return ModeStatus::Continue;
+                    }
+                    KeyStatus::Handled(state_disposition) => match state_disposition {
+                        StateDisposition::Quit => return ModeStatus::Quit,
+                        StateDisposition::Next => return ModeStatus::Continue,
+                        StateDisposition::Stay => {}
+                    },
+                },
+                Err(_err) => {
+                    // RecvError comes from the input thread exiting before the UI itself.
+                    // So, just quit the app, we're probably already trying to do so.
+                    return ModeStatus::Quit;
+                }
+            }
+        }
+    }
+}
+
+impl IVisibleMode for SettingsMode {
+    fn clear_buf<U: UI>(ui: &mut U) {
+        ui.clear_sidebar();
+    }
+
+    fn rect(&self) -> Rect {
+        self.rect
+    }
+}
@@ -82,6 +82,10 @@ impl IMode for TurnOverMode {
                                 return ModeStatus::Continue;
                             }
                         }
+                        KeyStatus::Clicked(viewport_loc) => {
+                            self.examine(game, ui, mode, viewport_loc).await;
+                            return ModeStatus::TurnOver;
+                        }
                         KeyStatus::Handled(state_disposition) => match state_disposition {
                             StateDisposition::Quit => return ModeStatus::Quit,
                             StateDisposition::Next => return ModeStatus::TurnOver,
@@ -96,6 +100,19 @@ impl IMode for TurnOverMode {
                 }
             }
         } else {
+            // Say so, as the turn ends without a keypress to show for it
+            ui.log_message(Message {
+                text: format!(
+                    "Nothing left to do; ending turn {} for player {}",
+                    turn, over_for
+                ),
+                mark: Some('X'),
+                fg_color: Some(Colors::Text),
+                bg_color: None,
+                source: None,
+            });
+            ui.draw_log(game).await.unwrap();
+
             // We shouldn't be in the TurnOverMode state unless game.turn_is_done() is true
             // so this unwrap should always succeed
             // game.end_turn().await.unwrap();
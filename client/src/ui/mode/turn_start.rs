@@ -2,11 +2,18 @@ use std::io::Result as IoResult;
 
 use common::{
     colors::Colors,
-    game::{player::PlayerTurn, unit::orders::OrdersOutcome, UnitProductionOutcome},
+    game::{
+        player::PlayerTurn,
+        unit::orders::{OrdersOutcome, OrdersStatus},
+        UnitProductionOutcome,
+    },
     log::{Message, MessageSource},
 };
 
-use crate::ui::UI;
+use crate::ui::{
+    audio::{Effect, Sounds},
+    UI,
+};
 
 use super::{IMode, Mode, ModeStatus};
 
@@ -75,6 +82,16 @@ impl TurnStartMode {
             ui.animate_move(game, move_).await?;
         }
 
+        if orders_outcome.status == OrdersStatus::Interrupted {
+            ui.log_message(Message::new(
+                format!("Unit {} stopped to await orders", ordered_unit),
+                Some('@'),
+                Some(Colors::Notice),
+                None,
+                Some(MessageSource::Game),
+            ));
+        }
+
         Ok(())
     }
 
@@ -100,6 +117,7 @@ impl TurnStartMode {
                         city.short_desc(),
                         unit.medium_desc()
                     ));
+                    ui.play_sound(Sounds::Effect(Effect::UnitProduced));
                 }
                 UnitProductionOutcome::UnitAlreadyPresent {
                     prior_unit,
@@ -121,5 +139,29 @@ impl TurnStartMode {
                 }
             }
         }
+
+        for woken in game.start().units_woken.iter() {
+            ui.log_message(Message {
+                text: format!(
+                    "{} woke up after spotting an enemy at {}",
+                    woken.unit, woken.sighted
+                ),
+                mark: Some('!'),
+                fg_color: Some(Colors::Notice),
+                bg_color: None,
+                source: Some(MessageSource::Game),
+            });
+        }
+
+        for alert in game.start().alerts.iter() {
+            ui.log_message(Message {
+                text: alert.to_string(),
+                mark: Some('!'),
+                fg_color: Some(Colors::Notice),
+                bg_color: None,
+                source: Some(MessageSource::Game),
+            });
+        }
+        ui.set_alerts(game.start().alerts.clone());
     }
 }
@@ -0,0 +1,220 @@
+use crossterm::event::KeyCode;
+
+use common::{
+    conf::{self, key_desc},
+    game::{
+        combat::CombatCapable,
+        player::PlayerTurn,
+        unit::{orders::Orders, Unit, UnitType},
+    },
+    log::{Message, MessageSource},
+};
+
+use crate::ui::UI;
+
+use super::{IMode, KeyStatus, Mode, ModeStatus, StateDisposition};
+
+/// Rows of the full screen not given over to the roster itself
+const NON_ROSTER_ROWS: usize = 4;
+
+/// Every unit the player has on one screen, grouped by type
+///
+/// The arrow keys select a unit, which can then be woken, put on sentry, or jumped to on the map. All sentries can be
+/// woken at once.
+pub(in crate::ui) struct UnitRosterMode {
+    pub selected: usize,
+}
+impl UnitRosterMode {
+    /// The player's units in roster order: by type, then by name
+    async fn units(game: &PlayerTurn<'_>) -> Vec<Unit> {
+        let mut units = game.player_units().await;
+        units.sort_by_key(|unit| {
+            let type_idx = UnitType::values()
+                .iter()
+                .position(|type_| *type_ == unit.type_);
+            (type_idx, unit.short_desc())
+        });
+        units
+    }
+
+    fn write_buf<U: UI>(&self, ui: &mut U, units: &[Unit]) {
+        // Each line is either a type heading or a unit, by its index in `units`
+        let mut lines: Vec<(String, Option<usize>)> = Vec::new();
+        for type_ in UnitType::values() {
+            let count = units.iter().filter(|unit| unit.type_ == type_).count();
+            if count == 0 {
+                continue;
+            }
+
+            lines.push((format!("{} ({})", type_, count), None));
+            for (idx, unit) in units.iter().enumerate().filter(|(_, u)| u.type_ == type_) {
+                let marker = if idx == self.selected { '>' } else { ' ' };
+                let orders = unit.orders.map_or_else(
+                    || String::from("awaiting orders"),
+                    Orders::present_progressive_description,
+                );
+                let row = format!(
+                    "{} {:<32}{:<8}{:<12}{}",
+                    marker,
+                    unit.short_desc(),
+                    format!("{}/{}", unit.hp(), unit.max_hp()),
+                    unit.loc.to_string(),
+                    orders
+                );
+                lines.push((row, Some(idx)));
+            }
+        }
+
+        let list_rows = ui.full_screen_height().saturating_sub(NON_ROSTER_ROWS);
+
+        // Scroll just far enough to keep the selected unit in view
+        let selected_line = lines
+            .iter()
+            .position(|(_, idx)| *idx == Some(self.selected))
+            .unwrap_or(0);
+        let first = (selected_line + 1).saturating_sub(list_rows);
+
+        ui.set_full_screen_row(0, format!("Units ({})", units.len()));
+        for row_idx in 0..list_rows {
+            let row = lines
+                .get(first + row_idx)
+                .map_or_else(String::new, |(row, _)| row.clone());
+            ui.set_full_screen_row(2 + row_idx, row);
+        }
+
+        ui.set_full_screen_row(
+            2 + list_rows + 1,
+            format!(
+                "Up/Down: select   {}: wake   {}: sentry   {}: wake all sentries   Enter: go to unit   Esc: done",
+                key_desc(conf::KEY_ROSTER_WAKE),
+                key_desc(conf::KEY_SENTRY),
+                key_desc(conf::KEY_ROSTER_WAKE_SENTRIES),
+            ),
+        );
+    }
+
+    /// Carry out the order bound to `key`, describing the result
+    ///
+    /// Returns None if `key` isn't an order key.
+    async fn order(
+        game: &mut PlayerTurn<'_>,
+        units: &[Unit],
+        selected: Option<&Unit>,
+        key: char,
+    ) -> Option<String> {
+        Some(match (key, selected) {
+            (conf::KEY_ROSTER_WAKE, Some(unit)) => match game.clear_orders(unit.id).await {
+                Ok(_) => format!("Woke {}", unit.short_desc()),
+                Err(err) => format!("Couldn't wake {}: {}", unit.short_desc(), err),
+            },
+            (conf::KEY_SENTRY, Some(unit)) => match game.order_unit_sentry(unit.id).await {
+                Ok(_) => format!("{} is standing sentry", unit.short_desc()),
+                Err(err) => format!("Couldn't put {} on sentry: {}", unit.short_desc(), err),
+            },
+            (conf::KEY_ROSTER_WAKE_SENTRIES, _) => {
+                let mut woken = 0;
+                for unit in units
+                    .iter()
+                    .filter(|unit| unit.orders == Some(Orders::Sentry))
+                {
+                    if game.clear_orders(unit.id).await.is_ok() {
+                        woken += 1;
+                    }
+                }
+                format!("Woke {} sentries", woken)
+            }
+            _ => return None,
+        })
+    }
+}
+
+impl IMode for UnitRosterMode {
+    async fn run<U: UI + Send + Sync>(
+        &self,
+        game: &mut PlayerTurn<'_>,
+        ui: &mut U,
+        mode: &mut Mode,
+        _prev_mode: &Option<Mode>,
+    ) -> ModeStatus {
+        let units = Self::units(game).await;
+
+        ui.open_full_screen();
+        self.write_buf(ui, &units);
+        ui.draw(game).await.unwrap();
+
+        loop {
+            match self.get_key(game, ui, mode).await {
+                Ok(key) => match key {
+                    KeyStatus::Unhandled(key) => match key.code {
+                        KeyCode::Esc => {
+                            ui.close_full_screen();
+                            ui.draw(game).await.unwrap();
+                            *mode = Mode::TurnResume;
+                            return ModeStatus::Continue;
+                        }
+                        KeyCode::Up if self.selected > 0 => {
+                            *mode = Mode::UnitRoster {
+                                selected: self.selected - 1,
+                            };
+                            return ModeStatus::Continue;
+                        }
+                        KeyCode::Down if self.selected + 1 < units.len() => {
+                            *mode = Mode::UnitRoster {
+                                selected: self.selected + 1,
+                            };
+                            return ModeStatus::Continue;
+                        }
+                        KeyCode::Enter => {
+                            if let Some(unit) = units.get(self.selected) {
+                                ui.close_full_screen();
+                                ui.center_map(unit.loc);
+                                ui.draw(game).await.unwrap();
+
+                                let viewport_loc = ui
+                                    .map_to_viewport_coords(unit.loc)
+                                    .unwrap_or(ui.viewport_rect().center());
+                                self.examine(game, ui, mode, viewport_loc).await;
+                                return ModeStatus::Continue;
+                            }
+                        }
+                        KeyCode::Char(c) => {
+                            let selected = units.get(self.selected);
+                            if let Some(text) = Self::order(game, &units, selected, c).await {
+                                ui.log_message(Message {
+                                    text,
+                                    mark: Some('·'),
+                                    bg_color: None,
+                                    fg_color: None,
+                                    source: Some(MessageSource::Mode),
+                                });
+
+                                // List the units' new orders
+                                return ModeStatus::Continue;
+                            }
+                        }
+                        _ => {}
+                    },
+                    // The map is covered, so there's nothing to click on
+                    KeyStatus::Clicked(_) => {}
+                    KeyStatus::Handled(state_disposition) => match state_disposition {
+                        StateDisposition::Quit => {
+                            ui.close_full_screen();
+                            return ModeStatus::Quit;
+                        }
+                        StateDisposition::Next => {
+                            ui.close_full_screen();
+                            ui.draw(game).await.unwrap();
+                            return ModeStatus::Continue;
+                        }
+                        StateDisposition::Stay => {}
+                    },
+                },
+                Err(_err) => {
+                    // RecvError comes from the input thread exiting before the UI itself.
+                    // So, just quit the app, we're probably already trying to do so.
+                    return ModeStatus::Quit;
+                }
+            }
+        }
+    }
+}
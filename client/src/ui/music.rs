@@ -0,0 +1,268 @@
+//! Theme music
+//!
+//! The theme plays over the splash screen and, if the settings call for it, on through the game, looping until the
+//! program ends. The built-in theme is a short march played on the same square-wave synth as the sound effects; a WAV
+//! file can be played in its place.
+
+use std::{
+    path::Path,
+    sync::mpsc::{channel, Receiver, Sender},
+    thread::{self, JoinHandle},
+};
+
+use cpal::traits::{DeviceTrait, EventLoopTrait, HostTrait};
+
+use pitch_calc::{Letter, LetterOctave};
+
+use synth::{oscillator, Envelope, Oscillator, Point, Synth};
+
+use thiserror::Error;
+
+use super::audio::{AudioError, SAMPLE_HZ};
+
+/// Beats per minute of the built-in theme
+const THEME_TEMPO: f64 = 112.0;
+
+const THEME_VOLUME: f32 = 0.2;
+
+/// The built-in theme, as notes and their lengths in beats; a note of `None` is a rest
+const THEME: &[(Option<(Letter, i32)>, f64)] = &[
+    (Some((Letter::G, 3)), 0.5),
+    (Some((Letter::C, 4)), 0.5),
+    (Some((Letter::C, 4)), 0.75),
+    (Some((Letter::C, 4)), 0.25),
+    (Some((Letter::C, 4)), 1.0),
+    (Some((Letter::E, 4)), 1.0),
+    (Some((Letter::D, 4)), 0.5),
+    (Some((Letter::C, 4)), 0.5),
+    (Some((Letter::D, 4)), 0.5),
+    (Some((Letter::E, 4)), 0.5),
+    (Some((Letter::C, 4)), 2.0),
+    (Some((Letter::G, 3)), 0.5),
+    (Some((Letter::C, 4)), 0.5),
+    (Some((Letter::E, 4)), 0.75),
+    (Some((Letter::E, 4)), 0.25),
+    (Some((Letter::E, 4)), 1.0),
+    (Some((Letter::G, 4)), 1.0),
+    (Some((Letter::F, 4)), 0.5),
+    (Some((Letter::E, 4)), 0.5),
+    (Some((Letter::D, 4)), 0.5),
+    (Some((Letter::B, 3)), 0.5),
+    (Some((Letter::C, 4)), 2.0),
+    (None, 2.0),
+];
+
+#[derive(Debug, Error)]
+pub enum MusicError {
+    #[error("Error reading music file: {0}")]
+    Wav(#[from] hound::Error),
+
+    #[error("The music file has no sound in it")]
+    Empty,
+}
+
+/// A piece of music, decoded and ready to loop
+#[derive(Clone)]
+pub struct Tune {
+    /// Stereo frames, left then right
+    frames: Vec<[f32; 2]>,
+
+    sample_hz: f64,
+}
+
+impl Tune {
+    /// The built-in theme
+    pub fn theme() -> Self {
+        let beat_ms = 60_000.0 / THEME_TEMPO;
+
+        let mut frames = Vec::new();
+        for (note, beats) in THEME {
+            let ms = beats * beat_ms;
+            let mut note_frames = vec![[0.0; 2]; (ms / 1000.0 * SAMPLE_HZ) as usize];
+
+            if let Some((letter, octave)) = note {
+                // Each note dies away over its length, so repeated notes are heard as separate
+                let amp_env = Envelope::from(vec![
+                    Point::new(0.0, THEME_VOLUME.into(), 0.0),
+                    Point::new(1.0, 0.0, 0.0),
+                ]);
+                let freq_env =
+                    Envelope::from(vec![Point::new(0.0, 0.0, 0.0), Point::new(1.0, 0.0, 0.0)]);
+                let oscillator =
+                    Oscillator::new(oscillator::waveform::Square, amp_env, freq_env, ());
+
+                let mut synth = Synth::poly(())
+                    .oscillator(oscillator)
+                    .duration(ms)
+                    .volume(THEME_VOLUME);
+                synth.note_on(LetterOctave(*letter, *octave).hz(), 1.0);
+                synth.fill_slice(&mut note_frames, SAMPLE_HZ);
+            }
+
+            frames.extend(note_frames);
+        }
+
+        Self {
+            frames,
+            sample_hz: SAMPLE_HZ,
+        }
+    }
+
+    /// Decode a WAV file
+    ///
+    /// Mono files are played on both sides; of files with more than two channels, only the first two are played.
+    pub fn load_wav<P: AsRef<Path>>(path: P) -> Result<Self, MusicError> {
+        let reader = hound::WavReader::open(path)?;
+        let spec = reader.spec();
+
+        let samples: Vec<f32> = match spec.sample_format {
+            hound::SampleFormat::Float => reader.into_samples::<f32>().collect::<Result<_, _>>()?,
+            hound::SampleFormat::Int => {
+                let scale = (1_i64 << (spec.bits_per_sample - 1)) as f32;
+                reader
+                    .into_samples::<i32>()
+                    .map(|sample| sample.map(|sample| sample as f32 / scale))
+                    .collect::<Result<_, _>>()?
+            }
+        };
+
+        let channels = spec.channels as usize;
+        let frames: Vec<[f32; 2]> = samples
+            .chunks_exact(channels.max(1))
+            .map(|frame| [frame[0], frame[channels.min(2) - 1]])
+            .collect();
+
+        if frames.is_empty() {
+            return Err(MusicError::Empty);
+        }
+
+        Ok(Self {
+            frames,
+            sample_hz: spec.sample_rate as f64,
+        })
+    }
+}
+
+/// Where playback is in a tune, and how loud
+struct Playback {
+    tune: Tune,
+
+    /// The position in the tune's frames, fractional when the output's sample rate differs from the tune's
+    pos: f64,
+
+    /// How far to advance through the tune's frames per output frame
+    step: f64,
+
+    volume: f32,
+}
+
+impl Playback {
+    fn next_frame(&mut self) -> [f32; 2] {
+        let frame = self.tune.frames[self.pos as usize];
+        self.pos = (self.pos + self.step) % self.tune.frames.len() as f64;
+        [frame[0] * self.volume, frame[1] * self.volume]
+    }
+
+    /// Fill an interleaved output buffer of `channels` channels, converting samples with `convert`
+    fn fill<S>(&mut self, buffer: &mut [S], channels: usize, convert: impl Fn(f32) -> S) {
+        for out in buffer.chunks_mut(channels) {
+            let frame = self.next_frame();
+            for (channel, sample) in out.iter_mut().enumerate() {
+                *sample = convert(frame[channel.min(1)]);
+            }
+        }
+    }
+}
+
+/// Plays a tune on a loop in a thread of its own
+pub struct MusicPlayer {
+    /// Sender by which to pass new volumes to the music thread
+    tx: Sender<f32>,
+
+    /// We need to keep the thread handle because the thread is killed when it goes out of scope.
+    _handle: JoinHandle<()>,
+}
+
+impl MusicPlayer {
+    /// Start looping `tune` at `volume`, from 0 (silent) to 1 (as loud as it was recorded)
+    pub fn play(tune: Tune, volume: f32) -> Self {
+        let (tx, rx) = channel();
+        let handle = thread::Builder::new()
+            .name("music".to_string())
+            .spawn(move || {
+                if let Err(err) = loop_tune(rx, tune, volume) {
+                    eprintln!("Error playing music: {}", err);
+                }
+            })
+            .unwrap();
+
+        Self {
+            tx,
+            _handle: handle,
+        }
+    }
+
+    pub fn set_volume(&self, volume: f32) {
+        // If the music thread has died, it has already said why
+        self.tx.send(volume).ok();
+    }
+}
+
+fn loop_tune(rx: Receiver<f32>, tune: Tune, volume: f32) -> anyhow::Result<()> {
+    let host = cpal::default_host();
+    let device = host
+        .default_output_device()
+        .ok_or(AudioError::NoOutputDevice)?;
+    let format = device
+        .default_output_format()
+        .map_err(|_| AudioError::CantGetDefaultOutputFormat)?;
+
+    let event_loop = host.event_loop();
+    let stream_id = event_loop
+        .build_output_stream(&device, &format)
+        .map_err(|_| AudioError::CantBuildOutputStream)?;
+    event_loop
+        .play_stream(stream_id)
+        .map_err(|_| AudioError::CantPlayStream)?;
+
+    let channels = format.channels as usize;
+    let mut playback = Playback {
+        step: tune.sample_hz / format.sample_rate.0 as f64,
+        tune,
+        pos: 0.0,
+        volume,
+    };
+
+    event_loop.run(move |_id, result| {
+        let data = match result {
+            Ok(data) => data,
+            Err(err) => {
+                eprintln!("Error in music stream: {}", err);
+                return;
+            }
+        };
+
+        if let Some(volume) = rx.try_iter().last() {
+            playback.volume = volume;
+        }
+
+        match data {
+            cpal::StreamData::Output {
+                buffer: cpal::UnknownTypeOutputBuffer::U16(mut buffer),
+            } => playback.fill(&mut buffer, channels, |sample| {
+                ((sample.clamp(-1.0, 1.0) + 1.0) / 2.0 * u16::MAX as f32) as u16
+            }),
+            cpal::StreamData::Output {
+                buffer: cpal::UnknownTypeOutputBuffer::I16(mut buffer),
+            } => playback.fill(&mut buffer, channels, |sample| {
+                (sample.clamp(-1.0, 1.0) * i16::MAX as f32) as i16
+            }),
+            cpal::StreamData::Output {
+                buffer: cpal::UnknownTypeOutputBuffer::F32(mut buffer),
+            } => playback.fill(&mut buffer, channels, |sample| sample),
+            _ => {
+                eprintln!("Unsupported output stream format");
+            }
+        }
+    });
+}
@@ -0,0 +1,461 @@
+//! Playback of recorded games
+//!
+//! Each recorded action is taken against a fresh copy of the replay's initial game, and rendered from the
+//! perspective of the player who took it. Seeking backward replays the log from the beginning.
+
+use std::{io::Result as IoResult, sync::mpsc::RecvTimeoutError, sync::Arc, time::Duration};
+
+use crossterm::event::{KeyCode, KeyEvent};
+use tokio::sync::RwLock as RwLockTokio;
+
+use common::{
+    colors::Colors,
+    conf::{self, key_desc},
+    game::{
+        action::{PlayerAction, PlayerActionOutcome},
+        player::{PlayerControl, PlayerTurn},
+        replay::{Replay, ReplayEntry},
+        IGame, PlayerNum, UmpireResult,
+    },
+    log::{Message, MessageSource},
+    util::Direction,
+};
+
+use super::{mode::cols, Input, UI};
+
+/// How long each action stays on screen during playback
+const PLAYBACK_DELAY: Duration = Duration::from_millis(400);
+
+/// Steps through a recorded game, with pause, step, and seek controls
+pub struct ReplayViewer<'a> {
+    replay: &'a Replay,
+
+    /// Controls for every player of the game being replayed
+    ctrls: Vec<PlayerControl>,
+
+    /// How many of the replay's entries have been taken
+    pos: usize,
+
+    /// The player whose view of the game is shown
+    viewer: PlayerNum,
+
+    paused: bool,
+}
+
+impl<'a> ReplayViewer<'a> {
+    pub async fn new(replay: &'a Replay) -> ReplayViewer<'a> {
+        let ctrls = Self::initial_ctrls(replay).await;
+        let viewer = ctrls[0].current_player().await;
+
+        Self {
+            replay,
+            ctrls,
+            pos: 0,
+            viewer,
+            paused: false,
+        }
+    }
+
+    async fn initial_ctrls(replay: &Replay) -> Vec<PlayerControl> {
+        let (game, secrets) = replay.initial_game(None);
+        let game = Arc::new(RwLockTokio::new(game)) as Arc<RwLockTokio<dyn IGame>>;
+
+        let mut ctrls = Vec::with_capacity(secrets.len());
+        for (player, secret) in secrets.into_iter().enumerate() {
+            ctrls.push(PlayerControl::new(Arc::clone(&game), player, secret).await);
+        }
+        ctrls
+    }
+
+    /// How many of the replay's entries have been taken
+    pub fn pos(&self) -> usize {
+        self.pos
+    }
+
+    pub fn at_end(&self) -> bool {
+        self.pos >= self.replay.len()
+    }
+
+    /// Take the next recorded action
+    ///
+    /// Returns the entry along with the outcome of taking it, or None if the replay is over.
+    pub async fn step(&mut self) -> Option<(ReplayEntry, UmpireResult<PlayerActionOutcome>)> {
+        let entry = *self.replay.entries().get(self.pos)?;
+
+        let outcome = self.ctrls[entry.player].take_action(entry.action).await;
+
+        self.pos += 1;
+        self.viewer = entry.player;
+
+        Some((entry, outcome))
+    }
+
+    /// Move to the point at which `pos` of the replay's entries have been taken
+    pub async fn seek(&mut self, pos: usize) {
+        let pos = pos.min(self.replay.len());
+
+        if pos < self.pos {
+            self.ctrls = Self::initial_ctrls(self.replay).await;
+            self.pos = 0;
+            self.viewer = self.ctrls[0].current_player().await;
+        }
+
+        while self.pos < pos {
+            self.step().await;
+        }
+    }
+
+    /// The position just before the next turn begins, or the end of the replay
+    fn next_turn_pos(&self) -> usize {
+        self.replay.entries()[self.pos..]
+            .iter()
+            .skip(1)
+            .position(|entry| matches!(entry.action, PlayerAction::BeginTurn { .. }))
+            .map_or(self.replay.len(), |offset| self.pos + offset + 1)
+    }
+
+    /// The position just before the current turn began, or the start of the replay
+    fn prev_turn_pos(&self) -> usize {
+        self.replay.entries()[..self.pos.saturating_sub(1)]
+            .iter()
+            .rposition(|entry| matches!(entry.action, PlayerAction::BeginTurn { .. }))
+            .unwrap_or(0)
+    }
+
+    /// Play the replay back until the user quits
+    pub async fn run<U: UI + Send + Sync>(&mut self, ui: &mut U) -> IoResult<()> {
+        ui.log_message(format!(
+            "Replaying {} actions. Press {} to pause.",
+            self.replay.len(),
+            key_desc(conf::KEY_REPLAY_PAUSE)
+        ));
+        self.draw(ui).await?;
+
+        loop {
+            let key = if self.paused || self.at_end() {
+                match ui.get_input() {
+                    Ok(Input::Key(key)) => Some(key),
+                    Ok(Input::Click(_)) => continue,
+                    Ok(Input::Resize(term_dims)) => {
+                        ui.set_term_dims(term_dims);
+                        self.draw(ui).await?;
+                        continue;
+                    }
+                    // The input thread has exited, so we're probably already quitting
+                    Err(_err) => return Ok(()),
+                }
+            } else {
+                match ui.get_key_timeout(PLAYBACK_DELAY) {
+                    Ok(key) => Some(key),
+                    Err(RecvTimeoutError::Timeout) => None,
+                    Err(RecvTimeoutError::Disconnected) => return Ok(()),
+                }
+            };
+
+            match key {
+                Some(key) => {
+                    if !self.handle_key(ui, key).await? {
+                        return Ok(());
+                    }
+                }
+                None => self.step_and_show(ui).await?,
+            }
+        }
+    }
+
+    /// Return false if the user asked to quit
+    async fn handle_key<U: UI + Send + Sync>(
+        &mut self,
+        ui: &mut U,
+        key: KeyEvent,
+    ) -> IoResult<bool> {
+        match key.code {
+            KeyCode::Char(conf::KEY_QUIT) => return Ok(false),
+            KeyCode::Char(conf::KEY_REPLAY_PAUSE) => {
+                self.paused = !self.paused;
+                self.draw(ui).await?;
+            }
+            KeyCode::Char(conf::KEY_REPLAY_STEP_FORWARD) | KeyCode::Right => {
+                self.paused = true;
+                self.step_and_show(ui).await?;
+            }
+            KeyCode::Char(conf::KEY_REPLAY_STEP_BACK) | KeyCode::Left => {
+                self.paused = true;
+                self.seek(self.pos.saturating_sub(1)).await;
+                self.draw(ui).await?;
+            }
+            KeyCode::Char(conf::KEY_REPLAY_NEXT_TURN) => {
+                self.seek(self.next_turn_pos()).await;
+                self.draw(ui).await?;
+            }
+            KeyCode::Char(conf::KEY_REPLAY_PREV_TURN) => {
+                self.seek(self.prev_turn_pos()).await;
+                self.draw(ui).await?;
+            }
+            KeyCode::Home => {
+                self.seek(0).await;
+                self.draw(ui).await?;
+            }
+            KeyCode::End => {
+                self.seek(self.replay.len()).await;
+                self.draw(ui).await?;
+            }
+            KeyCode::Char(c) => {
+                if let Ok(dir) = Direction::try_from_viewport_shift(c) {
+                    ui.scroll_map_relative(dir);
+                    self.draw(ui).await?;
+                }
+            }
+            _ => {}
+        }
+
+        Ok(true)
+    }
+
+    /// Take the next action and render its outcome, animating any movement
+    async fn step_and_show<U: UI + Send + Sync>(&mut self, ui: &mut U) -> IoResult<()> {
+        let (entry, outcome) = match self.step().await {
+            Some(step) => step,
+            None => {
+                self.paused = true;
+                return self.draw(ui).await;
+            }
+        };
+
+        self.write_sidebar(ui);
+
+        let turn = PlayerTurn::spectate(&mut self.ctrls[entry.player]).await;
+
+        match outcome {
+            Ok(outcome) => show_outcome(ui, &turn, &entry, &outcome).await?,
+            Err(err) => ui.log_message(Message {
+                text: format!("Replay diverged at action {}: {}", self.pos, err),
+                mark: None,
+                fg_color: Some(Colors::Notice),
+                bg_color: None,
+                source: Some(MessageSource::Game),
+            }),
+        }
+
+        ui.draw(&turn).await
+    }
+
+    async fn draw<U: UI + Send + Sync>(&mut self, ui: &mut U) -> IoResult<()> {
+        self.write_sidebar(ui);
+
+        let turn = PlayerTurn::spectate(&mut self.ctrls[self.viewer]).await;
+        ui.draw(&turn).await
+    }
+
+    fn write_sidebar<U: UI>(&self, ui: &mut U) {
+        ui.clear_sidebar();
+
+        ui.set_sidebar_row(
+            0,
+            format!(
+                "Replay: {}",
+                if self.at_end() {
+                    "ended"
+                } else if self.paused {
+                    "paused"
+                } else {
+                    "playing"
+                }
+            ),
+        );
+        ui.set_sidebar_row(1, format!("  Action {} / {}", self.pos, self.replay.len()));
+        ui.set_sidebar_row(2, format!("  Viewing player {}", self.viewer));
+
+        let controls = [
+            ("Pause / resume", key_desc(conf::KEY_REPLAY_PAUSE)),
+            (
+                "Step forward",
+                key_desc(conf::KEY_REPLAY_STEP_FORWARD) + " or →",
+            ),
+            ("Step back", key_desc(conf::KEY_REPLAY_STEP_BACK) + " or ←"),
+            ("Next turn", key_desc(conf::KEY_REPLAY_NEXT_TURN)),
+            ("Previous turn", key_desc(conf::KEY_REPLAY_PREV_TURN)),
+            ("Start / end", String::from("Home / End")),
+            ("Quit", key_desc(conf::KEY_QUIT)),
+        ];
+        for (i, (desc, key)) in controls.into_iter().enumerate() {
+            ui.set_sidebar_row(4 + i, cols(desc, key));
+        }
+    }
+}
+
+async fn show_outcome<U: UI + Send + Sync>(
+    ui: &mut U,
+    turn: &PlayerTurn<'_>,
+    entry: &ReplayEntry,
+    outcome: &PlayerActionOutcome,
+) -> IoResult<()> {
+    match outcome {
+        PlayerActionOutcome::TurnStarted(turn_start) => {
+            ui.log_message(Message {
+                text: format!("Turn {}, player {} go!", entry.turn, entry.player),
+                mark: Some('_'),
+                fg_color: None,
+                bg_color: None,
+                source: Some(MessageSource::Mode),
+            });
+
+            for orders_outcome in turn_start.orders_results.iter().flatten() {
+                if let Some(move_) = orders_outcome.move_() {
+                    ui.animate_move(turn, move_).await?;
+                }
+            }
+        }
+        PlayerActionOutcome::TurnEnded => {
+            ui.log_message(format!("Player {} ended turn {}", entry.player, entry.turn));
+        }
+        PlayerActionOutcome::MoveUnit { move_, .. } => {
+            ui.animate_move(turn, move_).await?;
+        }
+        PlayerActionOutcome::OrderUnit { orders_outcome, .. } => {
+            ui.log_message(format!(
+                "Unit {} is {}",
+                orders_outcome.ordered_unit,
+                orders_outcome.orders.present_progressive_description()
+            ));
+
+            if let Some(move_) = orders_outcome.move_() {
+                ui.animate_move(turn, move_).await?;
+            }
+        }
+        PlayerActionOutcome::UnitDisbanded(disbanded) => {
+            if disbanded.refund > 0 {
+                ui.log_message(format!(
+                    "Unit {} disbanded, refunding {} production",
+                    disbanded.unit, disbanded.refund
+                ));
+            } else {
+                ui.log_message(format!("Unit {} disbanded", disbanded.unit));
+            }
+        }
+        PlayerActionOutcome::UnitBombarded(bombardment) => {
+            ui.log_message(format!(
+                "Unit {} bombarded {} at {}",
+                bombardment.combat.attacker(),
+                bombardment.combat.defender(),
+                bombardment.target
+            ));
+        }
+        PlayerActionOutcome::WallsBuilt(walls_built) => {
+            ui.log_message(format!(
+                "City at {} built its walls to level {}",
+                walls_built.obs.loc, walls_built.walls
+            ));
+        }
+        PlayerActionOutcome::RadarBuilt(radar_built) => {
+            ui.log_message(format!("City at {} built a radar", radar_built.obs.loc));
+        }
+        PlayerActionOutcome::MinesLaid { loc, .. } => {
+            ui.log_message(format!("Player {} laid mines at {}", entry.player, loc));
+        }
+        PlayerActionOutcome::MinesSwept { swept, .. } => {
+            ui.log_message(format!(
+                "Player {} swept {} minefields",
+                entry.player,
+                swept.len()
+            ));
+        }
+        PlayerActionOutcome::ProductionSet(production_set) => {
+            if let PlayerAction::SetCityProduction { production, .. } = entry.action {
+                ui.log_message(format!(
+                    "City at {} set to produce {}",
+                    production_set.obs.loc, production
+                ));
+            }
+        }
+        PlayerActionOutcome::TreatyProposed { other, status } => {
+            ui.log_message(format!(
+                "Player {} proposed {} to player {}",
+                entry.player, status, other
+            ));
+        }
+        PlayerActionOutcome::DiplomaticStatusChanged { other, status, .. } => {
+            ui.log_message(format!(
+                "Players {} and {} are now at {}",
+                entry.player, other, status
+            ));
+        }
+        PlayerActionOutcome::Resigned(_) => {
+            ui.log_message(format!("Player {} resigned", entry.player));
+        }
+        PlayerActionOutcome::UnitSkipped { .. }
+        | PlayerActionOutcome::ProductionCleared(_)
+        | PlayerActionOutcome::UnitOrdersSet { .. }
+        | PlayerActionOutcome::UnitOrdersCleared { .. }
+        | PlayerActionOutcome::UnitActivated { .. } => {
+            // nothing worth showing
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use common::game::{action::PlayerAction, replay::Replay, test_support::game1, unit::UnitType};
+
+    use super::ReplayViewer;
+
+    /// Record a couple of turns of a game
+    fn recorded_replay() -> Replay {
+        let (mut game, secrets) = game1();
+        game.record_replay();
+
+        for _ in 0..4 {
+            let player = game.current_player();
+            let secret = secrets[player];
+
+            game.begin_turn(secret, false).unwrap();
+
+            let requests: Vec<_> = game
+                .player_production_set_requests(secret)
+                .unwrap()
+                .collect();
+            for loc in requests {
+                game.set_production_by_loc(secret, loc, UnitType::Infantry)
+                    .unwrap();
+            }
+
+            game.force_end_turn(secret).unwrap();
+        }
+
+        game.take_replay().unwrap()
+    }
+
+    #[tokio::test]
+    pub async fn test_replay_viewer_seek() {
+        let replay = recorded_replay();
+        assert!(!replay.is_empty());
+
+        let mut viewer = ReplayViewer::new(&replay).await;
+        assert_eq!(viewer.pos(), 0);
+
+        let mut steps = 0;
+        while let Some((_entry, outcome)) = viewer.step().await {
+            outcome.unwrap();
+            steps += 1;
+        }
+        assert_eq!(steps, replay.len());
+        assert!(viewer.at_end());
+        assert_eq!(viewer.ctrls[0].turn().await, 2);
+
+        viewer.seek(1).await;
+        assert_eq!(viewer.pos(), 1);
+        assert_eq!(viewer.ctrls[0].turn().await, 0);
+
+        viewer.seek(viewer.next_turn_pos()).await;
+        assert!(matches!(
+            replay.entries()[viewer.pos()].action,
+            PlayerAction::BeginTurn { .. }
+        ));
+        assert_eq!(viewer.ctrls[0].current_player().await, 1);
+
+        viewer.seek(replay.len() + 10).await;
+        assert!(viewer.at_end());
+    }
+}
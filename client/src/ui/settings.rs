@@ -0,0 +1,184 @@
+//! Preferences the player can change while playing
+//!
+//! Settings are kept in a TOML file at `conf::SETTINGS_PATH` so they carry over from one game to the next. The command
+//! line flags for the same preferences take precedence over the file for the run they're given in.
+
+use std::{fmt, fs, io, path::Path};
+
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use common::util::sleep_millis;
+
+/// How quickly moves and combat play out on the map
+#[derive(Clone, Copy, Debug, Default, Deserialize, PartialEq, Serialize)]
+pub enum AnimationSpeed {
+    Slow,
+    #[default]
+    Normal,
+    Fast,
+    Off,
+}
+
+impl AnimationSpeed {
+    /// How long to hold a frame that lasts `millis` at normal speed
+    pub fn scale(self, millis: u64) -> u64 {
+        match self {
+            Self::Slow => millis * 2,
+            Self::Normal => millis,
+            Self::Fast => millis / 4,
+            Self::Off => 0,
+        }
+    }
+
+    /// Hold a frame that lasts `millis` at normal speed
+    pub fn pause(self, millis: u64) {
+        let millis = self.scale(millis);
+        if millis > 0 {
+            sleep_millis(millis);
+        }
+    }
+
+    /// The speed after this one, wrapping around
+    pub fn next(self) -> Self {
+        match self {
+            Self::Slow => Self::Normal,
+            Self::Normal => Self::Fast,
+            Self::Fast => Self::Off,
+            Self::Off => Self::Slow,
+        }
+    }
+}
+
+impl fmt::Display for AnimationSpeed {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{}",
+            match self {
+                Self::Slow => "slow",
+                Self::Normal => "normal",
+                Self::Fast => "fast",
+                Self::Off => "off",
+            }
+        )
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum SettingsError {
+    #[error("Error accessing settings file: {0}")]
+    Io(#[from] io::Error),
+
+    #[error("Error parsing settings: {0}")]
+    Parse(#[from] toml::de::Error),
+
+    #[error("Error writing settings: {0}")]
+    Serialize(#[from] toml::ser::Error),
+}
+
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq, Serialize)]
+#[serde(default)]
+pub struct Settings {
+    /// Whether to draw with Unicode symbols rather than plain ASCII
+    pub unicode: bool,
+
+    pub animation_speed: AnimationSpeed,
+
+    /// Whether to wait for Enter before handing the turn over
+    ///
+    /// When off, the turn ends on its own as soon as every production and orders request is satisfied.
+    pub confirm_turn_end: bool,
+
+    /// Whether to ask before a move that would start a fight
+    pub confirm_attacks: bool,
+
+    pub sound: bool,
+
+    /// Whether the theme music keeps playing once the splash screen is gone
+    pub music: bool,
+
+    /// How loud the theme music plays, from 0 to 100
+    pub music_volume: u8,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            unicode: false,
+            animation_speed: AnimationSpeed::default(),
+            confirm_turn_end: false,
+            confirm_attacks: false,
+            sound: true,
+            music: false,
+            music_volume: 50,
+        }
+    }
+}
+
+impl Settings {
+    /// The settings saved at `path`, or the defaults if none have been saved yet
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self, SettingsError> {
+        match fs::read_to_string(path) {
+            Ok(text) => Ok(toml::from_str(&text)?),
+            Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(Self::default()),
+            Err(err) => Err(err.into()),
+        }
+    }
+
+    /// The volume to play the theme music at over the splash screen, from 0 to 1
+    pub fn splash_music_volume(&self) -> f32 {
+        if self.sound {
+            self.music_volume.min(100) as f32 / 100.0
+        } else {
+            0.0
+        }
+    }
+
+    /// The volume to play the theme music at during play, from 0 to 1
+    pub fn game_music_volume(&self) -> f32 {
+        if self.music {
+            self.splash_music_volume()
+        } else {
+            0.0
+        }
+    }
+
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<(), SettingsError> {
+        fs::write(path, toml::to_string(self)?)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{AnimationSpeed, Settings};
+
+    #[test]
+    fn test_settings() {
+        let settings = Settings {
+            unicode: true,
+            animation_speed: AnimationSpeed::Fast,
+            confirm_turn_end: true,
+            confirm_attacks: true,
+            sound: false,
+            music: true,
+            music_volume: 75,
+        };
+        let text = toml::to_string(&settings).unwrap();
+        assert_eq!(toml::from_str::<Settings>(&text).unwrap(), settings);
+
+        // Settings left out of the file keep their defaults
+        let partial: Settings = toml::from_str("unicode = true\n").unwrap();
+        assert!(partial.unicode);
+        assert!(partial.sound);
+        assert_eq!(partial.animation_speed, AnimationSpeed::Normal);
+
+        assert_eq!(settings.splash_music_volume(), 0.0);
+        assert_eq!(partial.splash_music_volume(), 0.5);
+        assert_eq!(partial.game_music_volume(), 0.0);
+
+        assert_eq!(AnimationSpeed::Off.scale(100), 0);
+        assert_eq!(AnimationSpeed::Off.next(), AnimationSpeed::Slow);
+    }
+}
@@ -0,0 +1,195 @@
+//! Watching a game without playing
+//!
+//! A spectator controls no player and holds no secrets. Every so often it fetches what the chosen viewer has
+//! seen---or, with no viewer chosen, the whole map as it stands---and renders that, so the game can be followed as
+//! the players take their turns.
+
+use std::{
+    io::{self, Result as IoResult},
+    sync::mpsc::RecvTimeoutError,
+    sync::Arc,
+    time::Duration,
+};
+
+use crossterm::event::{KeyCode, KeyEvent};
+use tokio::sync::RwLock as RwLockTokio;
+
+use common::{
+    conf::{self, key_desc},
+    game::{
+        error::GameError,
+        player::{PlayerControl, PlayerTurn},
+        IGame, PlayerNum, PlayerSecret, TurnNum,
+    },
+    util::Direction,
+};
+
+use super::{mode::cols, UI};
+
+/// How often the view is brought up to date with the game
+const REFRESH_INTERVAL: Duration = Duration::from_millis(500);
+
+/// Shows a game in progress without taking part in it
+pub struct Spectator {
+    game: Arc<RwLockTokio<dyn IGame>>,
+
+    num_players: PlayerNum,
+
+    /// The player whose view of the game is shown; the whole map is shown if `None`
+    viewer: Option<PlayerNum>,
+
+    /// The turn and player to move as of the last refresh
+    progress: (TurnNum, PlayerNum),
+}
+
+impl Spectator {
+    pub async fn new(game: Arc<RwLockTokio<dyn IGame>>) -> Self {
+        let (num_players, progress) = {
+            let g = game.read().await;
+            (
+                g.num_players().await.unwrap(),
+                (g.turn().await.unwrap(), g.current_player().await.unwrap()),
+            )
+        };
+
+        Self {
+            game,
+            num_players,
+            viewer: None,
+            progress,
+        }
+    }
+
+    /// Control for rendering the game as the viewer sees it
+    ///
+    /// No secret is held, so the control is only good for looking.
+    pub async fn view_ctrl(&self) -> PlayerControl {
+        let g = self.game.read().await;
+
+        let player = match self.viewer {
+            Some(viewer) => viewer,
+            None => g.current_player().await.unwrap(),
+        };
+        let observations = g.spectator_observations(self.viewer).await.unwrap();
+
+        PlayerControl::from_observations(
+            Arc::clone(&self.game),
+            player,
+            PlayerSecret::nil(),
+            g.dims().await.unwrap(),
+            observations,
+            g.wrapping().await.unwrap(),
+        )
+    }
+
+    /// Follow the game until it's decided or the user quits
+    pub async fn run<U: UI + Send + Sync>(&mut self, ui: &mut U) -> IoResult<()> {
+        ui.log_message(format!(
+            "Spectating. Press {} to switch views.",
+            key_desc(conf::KEY_SPECTATE_NEXT_VIEW)
+        ));
+        ui.log_message(format!(
+            "Turn {}, player {} to move",
+            self.progress.0, self.progress.1
+        ));
+        self.draw(ui).await?;
+
+        loop {
+            match ui.get_key_timeout(REFRESH_INTERVAL) {
+                Ok(key) => {
+                    if !self.handle_key(ui, key) {
+                        return Ok(());
+                    }
+                }
+                Err(RecvTimeoutError::Timeout) => {}
+                // The input thread has exited, so we're probably already quitting
+                Err(RecvTimeoutError::Disconnected) => return Ok(()),
+            }
+
+            let (progress, game_over) = {
+                let g = self.game.read().await;
+                let lost = |err: GameError| io::Error::new(io::ErrorKind::ConnectionAborted, err);
+                (
+                    (
+                        g.turn().await.map_err(lost)?,
+                        g.current_player().await.map_err(lost)?,
+                    ),
+                    g.game_over().await.map_err(lost)?,
+                )
+            };
+            if progress != self.progress {
+                self.progress = progress;
+                ui.log_message(format!(
+                    "Turn {}, player {} to move",
+                    progress.0, progress.1
+                ));
+            }
+
+            self.draw(ui).await?;
+
+            if let Some(game_over) = game_over {
+                ui.log_message(format!("Player {} has won", game_over.victor));
+                return Ok(());
+            }
+        }
+    }
+
+    /// Return false if the user asked to quit
+    fn handle_key<U: UI>(&mut self, ui: &mut U, key: KeyEvent) -> bool {
+        match key.code {
+            KeyCode::Char(conf::KEY_QUIT) => return false,
+            KeyCode::Char(conf::KEY_SPECTATE_NEXT_VIEW) => {
+                // Cycle through each player's view and then the view of everything
+                self.viewer = match self.viewer {
+                    None => Some(0),
+                    Some(viewer) if viewer + 1 < self.num_players => Some(viewer + 1),
+                    Some(_) => None,
+                };
+            }
+            KeyCode::Char(c) => {
+                if let Ok(dir) = Direction::try_from_viewport_shift(c) {
+                    ui.scroll_map_relative(dir);
+                }
+            }
+            _ => {}
+        }
+
+        true
+    }
+
+    async fn draw<U: UI + Send + Sync>(&self, ui: &mut U) -> IoResult<()> {
+        self.write_sidebar(ui);
+
+        let mut ctrl = self.view_ctrl().await;
+        let turn = PlayerTurn::spectate(&mut ctrl).await;
+        ui.draw(&turn).await
+    }
+
+    fn write_sidebar<U: UI>(&self, ui: &mut U) {
+        ui.clear_sidebar();
+
+        ui.set_sidebar_row(0, String::from("Spectating"));
+        ui.set_sidebar_row(
+            1,
+            format!(
+                "  Turn {}, player {} to move",
+                self.progress.0, self.progress.1
+            ),
+        );
+        ui.set_sidebar_row(
+            2,
+            match self.viewer {
+                Some(viewer) => format!("  Viewing player {}", viewer),
+                None => String::from("  Viewing everything"),
+            },
+        );
+
+        let controls = [
+            ("Switch views", key_desc(conf::KEY_SPECTATE_NEXT_VIEW)),
+            ("Quit", key_desc(conf::KEY_QUIT)),
+        ];
+        for (i, (desc, key)) in controls.into_iter().enumerate() {
+            ui.set_sidebar_row(4 + i, cols(desc, key));
+        }
+    }
+}
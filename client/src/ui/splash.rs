@@ -0,0 +1,241 @@
+//! The splash screen shown while the game loads
+//!
+//! The art is kept as text colored with ANSI escape codes. It's parsed into cells of the sixteen standard terminal
+//! colors, which are then drawn in the nearest colors of the palette in use, shrunk as need be to fit the terminal.
+
+use ratatui::{
+    buffer::Buffer,
+    layout::{Alignment, Rect as Area},
+    style::Style,
+    widgets::{Paragraph, Widget},
+};
+
+use common::{colors::Colors, conf};
+
+use umpire_tui::color::Palette;
+
+const ART: &str = include_str!("../../../images/1945_Baseball_Umpire.txt");
+
+/// One character of the art, with its foreground and background as standard terminal color numbers, 0 through 15
+#[derive(Clone, Copy, Debug, PartialEq)]
+struct Cell {
+    c: char,
+    fg: u8,
+    bg: u8,
+}
+
+const BLANK: Cell = Cell {
+    c: ' ',
+    fg: 7,
+    bg: 0,
+};
+
+/// Split text colored with ANSI escape codes into rows of cells
+///
+/// Only the color codes are understood; any other code is skipped over.
+fn parse_ansi(text: &str) -> Vec<Vec<Cell>> {
+    let mut rows = Vec::new();
+
+    for line in text.lines() {
+        let mut row = Vec::new();
+        let (mut fg, mut bg, mut bold) = (BLANK.fg, BLANK.bg, false);
+
+        let mut chars = line.chars();
+        while let Some(c) = chars.next() {
+            if c != '\x1b' {
+                row.push(Cell { c, fg, bg });
+                continue;
+            }
+
+            if chars.next() != Some('[') {
+                continue;
+            }
+            let code: String = chars.by_ref().take_while(|c| *c != 'm').collect();
+
+            for param in code.split(';').filter_map(|param| param.parse::<u8>().ok()) {
+                match param {
+                    0 => (fg, bg, bold) = (BLANK.fg, BLANK.bg, false),
+                    1 => {
+                        bold = true;
+                        fg |= 8;
+                    }
+                    30..=37 => fg = param - 30 + if bold { 8 } else { 0 },
+                    39 => fg = BLANK.fg,
+                    40..=47 => bg = param - 40,
+                    49 => bg = BLANK.bg,
+                    90..=97 => fg = param - 90 + 8,
+                    100..=107 => bg = param - 100 + 8,
+                    _ => {}
+                }
+            }
+        }
+
+        rows.push(row);
+    }
+
+    rows
+}
+
+/// The palette's stand-in for a standard terminal color
+fn palette_color(palette: &Palette, ansi: u8) -> crossterm::style::Color {
+    match ansi {
+        0 => palette.get(Colors::Background, true),
+        1 | 9 => palette.get(Colors::Combat, true),
+        2 | 10 => palette.get(Colors::Land, true),
+        3 | 11 => palette.get(Colors::Notice, true),
+        4 | 6 | 12 | 14 => palette.get(Colors::Ocean, true),
+        5 | 13 => palette.get(Colors::Cursor, true),
+        7 => palette.get(Colors::Neutral, true),
+        8 => palette.get(Colors::Neutral, false),
+        _ => palette.get(Colors::Text, true),
+    }
+}
+
+pub struct Splash {
+    art: Vec<Vec<Cell>>,
+}
+
+impl Splash {
+    pub fn new() -> Self {
+        Self {
+            art: parse_ansi(ART),
+        }
+    }
+
+    /// The art shrunk evenly to fit `width` by `height`, if it doesn't already
+    ///
+    /// Shrinking keeps every nth cell in each direction, so the proportions of the picture hold.
+    fn fitted(&self, width: u16, height: u16) -> Vec<Vec<Cell>> {
+        let art_width = self.art.iter().map(Vec::len).max().unwrap_or(0);
+        let art_height = self.art.len();
+        if width == 0 || height == 0 || art_width == 0 {
+            return Vec::new();
+        }
+
+        let scale = (art_width as f64 / width as f64)
+            .max(art_height as f64 / height as f64)
+            .max(1.0);
+        let fitted_width = (art_width as f64 / scale) as usize;
+        let fitted_height = (art_height as f64 / scale) as usize;
+
+        (0..fitted_height)
+            .map(|y| {
+                let row = &self.art[(y as f64 * scale) as usize];
+                (0..fitted_width)
+                    .map(|x| {
+                        row.get((x as f64 * scale) as usize)
+                            .copied()
+                            .unwrap_or(BLANK)
+                    })
+                    .collect()
+            })
+            .collect()
+    }
+
+    /// The splash screen as a widget, in the colors of `palette`
+    pub fn widget<'a>(&'a self, palette: &'a Palette) -> SplashScreen<'a> {
+        SplashScreen {
+            splash: self,
+            palette,
+        }
+    }
+}
+
+impl Default for Splash {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// The art centered above a line for the title
+pub struct SplashScreen<'a> {
+    splash: &'a Splash,
+    palette: &'a Palette,
+}
+
+impl Widget for SplashScreen<'_> {
+    fn render(self, area: Area, buf: &mut Buffer) {
+        let background = palette_color(self.palette, BLANK.bg).into();
+        buf.set_style(area, Style::default().bg(background));
+
+        let art_height = area.height.saturating_sub(2);
+        let art = self.splash.fitted(area.width, art_height);
+
+        let top = (art_height as usize - art.len()) / 2;
+        for (y, row) in art.iter().enumerate() {
+            let left = (area.width as usize - row.len()) / 2;
+            for (x, cell) in row.iter().enumerate() {
+                buf.get_mut(area.x + (left + x) as u16, area.y + (top + y) as u16)
+                    .set_char(cell.c)
+                    .set_fg(palette_color(self.palette, cell.fg).into())
+                    .set_bg(palette_color(self.palette, cell.bg).into());
+            }
+        }
+
+        let title = format!("{}: {}", conf::APP_NAME, conf::APP_SUBTITLE);
+        Paragraph::new(title)
+            .alignment(Alignment::Center)
+            .style(
+                Style::default()
+                    .fg(self.palette.get(Colors::Text, true).into())
+                    .bg(background),
+            )
+            .render(
+                Area::new(
+                    area.x,
+                    area.bottom().saturating_sub(1),
+                    area.width,
+                    1.min(area.height),
+                ),
+                buf,
+            );
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{parse_ansi, Cell, Splash};
+
+    #[test]
+    fn test_splash() {
+        let rows = parse_ansi("\x1b[0;1;30;47mab\x1b[0mc\n\x1b[34;100md");
+        assert_eq!(
+            rows[0],
+            vec![
+                Cell {
+                    c: 'a',
+                    fg: 8,
+                    bg: 7
+                },
+                Cell {
+                    c: 'b',
+                    fg: 8,
+                    bg: 7
+                },
+                Cell {
+                    c: 'c',
+                    fg: 7,
+                    bg: 0
+                },
+            ]
+        );
+        assert_eq!(
+            rows[1],
+            vec![Cell {
+                c: 'd',
+                fg: 4,
+                bg: 8
+            }]
+        );
+
+        let splash = Splash::new();
+        let fitted = splash.fitted(80, 22);
+        assert!(!fitted.is_empty());
+        assert!(fitted.len() <= 22);
+        assert!(fitted.iter().all(|row| row.len() <= 80));
+
+        // Art that already fits is left as it is
+        let fitted = splash.fitted(u16::MAX, u16::MAX);
+        assert_eq!(fitted.len(), splash.art.len());
+    }
+}
@@ -0,0 +1,141 @@
+//! The statistics screen shown once a game is decided
+//!
+//! Each player's final score and battle totals are tabulated, and their city and unit counts are charted over the
+//! course of the game, one character per turn---or per several turns, when the game ran longer than the screen is wide.
+
+use common::game::{history::PlayerHistory, GameOver, GameOverReason};
+
+const UNICODE_LEVELS: [char; 8] = ['▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+const ASCII_LEVELS: [char; 8] = ['_', '.', '-', '~', '=', '+', '*', '#'];
+
+/// Width of the label column before each chart
+const LABEL_WIDTH: usize = 12;
+
+/// Chart `values` as a line of bars at most `width` characters long, scaled so that `max` is a full bar
+///
+/// Zero is left blank, so stretches where a player had nothing stand out.
+fn sparkline(values: &[usize], max: usize, width: usize, unicode: bool) -> String {
+    let levels = if unicode {
+        &UNICODE_LEVELS
+    } else {
+        &ASCII_LEVELS
+    };
+
+    let len = values.len().min(width);
+    (0..len)
+        .map(|i| {
+            let value = values[i * values.len() / len];
+            if value == 0 || max == 0 {
+                ' '
+            } else {
+                levels[((value * levels.len()).div_ceil(max) - 1).min(levels.len() - 1)]
+            }
+        })
+        .collect()
+}
+
+/// Lines of charts, one per player, of some count taken from each player's standings
+fn charts(
+    title: &str,
+    histories: &[PlayerHistory],
+    count: impl Fn(&PlayerHistory) -> Vec<usize>,
+    width: usize,
+    unicode: bool,
+) -> Vec<String> {
+    let counts: Vec<Vec<usize>> = histories.iter().map(count).collect();
+    let max = counts.iter().flatten().copied().max().unwrap_or(0);
+
+    let mut rows = vec![format!("{} (most: {})", title, max)];
+    for (player, counts) in counts.iter().enumerate() {
+        rows.push(format!(
+            "{:<label_width$}{}",
+            format!("  Player {}", player),
+            sparkline(counts, max, width.saturating_sub(LABEL_WIDTH), unicode),
+            label_width = LABEL_WIDTH
+        ));
+    }
+    rows
+}
+
+/// The lines of the statistics screen, fit to `width` columns
+pub(in crate::ui) fn stats_rows(
+    game_over: GameOver,
+    scores: &[f64],
+    histories: &[PlayerHistory],
+    width: usize,
+    unicode: bool,
+) -> Vec<String> {
+    let mut rows = vec![match game_over.reason {
+        GameOverReason::Conquest => {
+            format!("Game over: player {} won by conquest", game_over.victor)
+        }
+        GameOverReason::TurnLimit => format!(
+            "Game over: player {} won with the highest score at the turn limit",
+            game_over.victor
+        ),
+    }];
+
+    rows.push(String::new());
+    rows.push(format!(
+        "{:<8}{:>10}{:>8}{:>12}{:>20}",
+        "", "", "Units", "Battles", "Cities"
+    ));
+    rows.push(format!(
+        "{:<8}{:>10}{:>8}{:>6}{:>6}{:>8}{:>6}{:>6}",
+        "Player", "Score", "built", "won", "lost", "taken", "lost", "held"
+    ));
+    for (player, history) in histories.iter().enumerate() {
+        let totals = &history.totals;
+        rows.push(format!(
+            "{:<8}{:>10.1}{:>8}{:>6}{:>6}{:>8}{:>6}{:>6}",
+            player,
+            scores.get(player).copied().unwrap_or_default(),
+            totals.units_produced,
+            totals.battles_won,
+            totals.battles_lost,
+            totals.cities_gained,
+            totals.cities_lost,
+            history.standings.last().map_or(0, |s| s.cities)
+        ));
+    }
+
+    rows.push(String::new());
+    rows.extend(charts(
+        "Cities over time",
+        histories,
+        |history| history.standings.iter().map(|s| s.cities).collect(),
+        width,
+        unicode,
+    ));
+
+    rows.push(String::new());
+    rows.extend(charts(
+        "Units over time",
+        histories,
+        |history| history.standings.iter().map(|s| s.units).collect(),
+        width,
+        unicode,
+    ));
+
+    rows.push(String::new());
+    rows.push(String::from("Press any key to finish"));
+
+    rows
+}
+
+#[cfg(test)]
+mod test {
+    use super::sparkline;
+
+    #[test]
+    fn test_sparkline() {
+        assert_eq!(sparkline(&[0, 1, 2, 4, 8], 8, 80, true), " ▁▂▄█");
+        assert_eq!(sparkline(&[0, 1, 2, 4, 8], 8, 80, false), " _.~#");
+
+        // Long histories are sampled down to the width
+        assert_eq!(sparkline(&[8; 100], 8, 10, true).chars().count(), 10);
+
+        assert_eq!(sparkline(&[], 0, 80, true), "");
+        assert_eq!(sparkline(&[0, 0], 0, 80, true), "  ");
+    }
+}
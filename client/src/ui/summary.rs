@@ -0,0 +1,74 @@
+use ratatui::{
+    style::Style,
+    symbols::border,
+    text::Line,
+    widgets::{Block, Borders, Padding, Paragraph, Widget},
+};
+
+use common::{
+    colors::Colors,
+    game::{PlayerNum, TurnNum, TurnSummary},
+};
+
+use umpire_tui::color::Palette;
+
+pub(in crate::ui) const SUMMARY_WIDTH: u16 = 34;
+pub(in crate::ui) const SUMMARY_HEIGHT: u16 = 8;
+
+/// Box drawn in plain ASCII so it shows on any terminal
+const BORDER: border::Set = border::Set {
+    top_left: "+",
+    top_right: "+",
+    bottom_left: "+",
+    bottom_right: "+",
+    vertical_left: "|",
+    vertical_right: "|",
+    horizontal_top: "-",
+    horizontal_bottom: "-",
+};
+
+/// A boxed report of what happened to a player over its turn, drawn over the map
+pub(in crate::ui) struct TurnSummaryPanel {
+    player: PlayerNum,
+    turn: TurnNum,
+    summary: TurnSummary,
+}
+
+impl TurnSummaryPanel {
+    pub(in crate::ui) fn new(player: PlayerNum, turn: TurnNum, summary: TurnSummary) -> Self {
+        Self {
+            player,
+            turn,
+            summary,
+        }
+    }
+
+    fn rows(&self) -> Vec<String> {
+        let s = &self.summary;
+        vec![
+            format!("Player {}, turn {}", self.player, self.turn),
+            String::new(),
+            format!("Units produced: {}", s.units_produced),
+            format!("Battles: {} won, {} lost", s.battles_won, s.battles_lost),
+            format!("Cities: {} gained, {} lost", s.cities_gained, s.cities_lost),
+            format!("Tiles explored: {}", s.tiles_explored),
+        ]
+    }
+
+    pub(in crate::ui) fn widget(&self, palette: &Palette) -> impl Widget {
+        let lines: Vec<Line> = self.rows().into_iter().map(Line::raw).collect();
+
+        Paragraph::new(lines)
+            .block(
+                Block::default()
+                    .borders(Borders::ALL)
+                    .border_set(BORDER)
+                    .padding(Padding::horizontal(1)),
+            )
+            .style(
+                Style::default()
+                    .fg(palette.get_single(Colors::Text).into())
+                    .bg(palette.get_single(Colors::Background).into()),
+            )
+    }
+}
@@ -0,0 +1,57 @@
+use ratatui::{
+    style::Style,
+    widgets::{Paragraph, Widget},
+};
+
+use common::{
+    colors::Colors,
+    conf::{self, key_desc},
+    game::alert::Alert,
+    util::Rect,
+};
+
+use umpire_tui::color::Palette;
+
+/// A line popped up over the top of the map to make sure an alert is seen
+pub(in crate::ui) struct Toast {
+    rect: Rect,
+    text: String,
+}
+
+impl Toast {
+    /// A toast for `alert`, with `pending` more alerts waiting behind it, fit within `viewport_rect`
+    pub(in crate::ui) fn new(viewport_rect: Rect, alert: &Alert, pending: usize) -> Self {
+        let mut text = format!(
+            "! {} ({} to jump there)",
+            alert,
+            key_desc(conf::KEY_JUMP_TO_ALERT)
+        );
+        if pending > 0 {
+            text.push_str(&format!(" +{} more", pending));
+        }
+
+        let width = (text.chars().count() as u16 + 2).min(viewport_rect.width);
+        Self {
+            rect: Rect::new(
+                viewport_rect.left + (viewport_rect.width - width) / 2,
+                viewport_rect.top,
+                width,
+                1,
+            ),
+            text,
+        }
+    }
+    pub(in crate::ui) fn rect(&self) -> Rect {
+        self.rect
+    }
+
+    pub(in crate::ui) fn widget(&self, palette: &Palette) -> impl Widget + '_ {
+        let width = (self.rect.width as usize).saturating_sub(2);
+        let text: String = self.text.chars().take(width).collect();
+        Paragraph::new(format!(" {} ", text)).style(
+            Style::default()
+                .fg(palette.get_single(Colors::Notice).into())
+                .bg(palette.get_single(Colors::Background).into()),
+        )
+    }
+}
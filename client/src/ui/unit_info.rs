@@ -0,0 +1,142 @@
+use common::game::unit::{Fuel, Unit};
+
+/// How many cells wide the HP bar is
+const HP_BAR_WIDTH: usize = 10;
+
+/// What the sidebar shows of a unit, gathered from the unit up front rather than read off its `Display`
+pub(in crate::ui) struct UnitInfo {
+    desc: String,
+    veterancy: String,
+    hp: u16,
+    max_hp: u16,
+    moves_remaining: u16,
+    movement_per_turn: u16,
+
+    /// Fuel remaining and the most the unit can hold, if its fuel is limited
+    fuel: Option<(u16, u16)>,
+
+    /// Descriptions of the units being carried
+    carried: Vec<String>,
+
+    /// What the unit's orders have it doing, if it has any
+    orders: Option<String>,
+}
+
+impl UnitInfo {
+    pub(in crate::ui) fn new(unit: &Unit) -> Self {
+        Self {
+            desc: unit.short_desc(),
+            veterancy: unit.veterancy().to_string(),
+            hp: unit.hp(),
+            max_hp: unit.max_hp(),
+            moves_remaining: unit.moves_remaining(),
+            movement_per_turn: unit.movement_per_turn(),
+            fuel: match unit.fuel {
+                Fuel::Limited { max, remaining } => Some((remaining, max)),
+                Fuel::Unlimited => None,
+            },
+            carried: unit.carried_units().map(Unit::medium_desc).collect(),
+            orders: unit
+                .orders
+                .map(|orders| orders.present_progressive_description()),
+        }
+    }
+
+    /// The unit's hitpoints as a bar filled in proportion to its maximum, followed by the count
+    pub(in crate::ui) fn hp_row(&self, unicode: bool) -> String {
+        let (full, empty) = if unicode { ('█', '░') } else { ('#', '-') };
+
+        // Round up, so a unit hanging on by a thread still shows a sliver
+        let filled = (self.hp as usize * HP_BAR_WIDTH)
+            .div_ceil(self.max_hp.max(1) as usize)
+            .min(HP_BAR_WIDTH);
+
+        format!(
+            "  HP:     [{}{}] {}/{}",
+            full.to_string().repeat(filled),
+            empty.to_string().repeat(HP_BAR_WIDTH - filled),
+            self.hp,
+            self.max_hp
+        )
+    }
+
+    /// Moves remaining this turn, and fuel if it's limited
+    pub(in crate::ui) fn moves_row(&self) -> String {
+        let mut row = format!(
+            "  Moves:  {} / {}",
+            self.moves_remaining, self.movement_per_turn
+        );
+
+        if let Some((remaining, max)) = self.fuel {
+            // Right pad
+            while row.len() < 20 {
+                row.push(' ');
+            }
+
+            row.push_str(format!("Fuel: {} / {}", remaining, max).as_str());
+        }
+
+        row
+    }
+
+    /// What anyone who sees the unit can tell of it, one sidebar row apiece
+    pub(in crate::ui) fn outward_rows(&self, unicode: bool) -> Vec<String> {
+        vec![
+            format!("{} ({})", self.desc, self.veterancy),
+            String::new(),
+            self.hp_row(unicode),
+        ]
+    }
+
+    /// Everything known of the unit, as its owner knows it, one sidebar row apiece
+    pub(in crate::ui) fn rows(&self, unicode: bool) -> Vec<String> {
+        let mut rows = self.outward_rows(unicode);
+        rows.push(self.moves_row());
+        rows.push(format!(
+            "  Orders: {}",
+            self.orders.as_deref().unwrap_or("awaiting orders")
+        ));
+
+        if !self.carried.is_empty() {
+            rows.push(String::new());
+            rows.push(format!("  Carrying {}:", self.carried.len()));
+            rows.extend(self.carried.iter().map(|desc| format!("    {}", desc)));
+        }
+
+        rows
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use common::{
+        game::{
+            alignment::Alignment,
+            unit::{Unit, UnitID, UnitType},
+        },
+        util::Location,
+    };
+
+    use super::UnitInfo;
+
+    #[test]
+    fn test_unit_info() {
+        let unit = Unit::new(
+            UnitID::new(0),
+            Location::new(0, 0),
+            UnitType::Fighter,
+            Alignment::Belligerent { player: 0 },
+            "Icarus",
+        );
+        let info = UnitInfo::new(&unit);
+
+        assert_eq!(info.hp_row(false), "  HP:     [##########] 1/1");
+        assert!(info.moves_row().contains("Fuel: "));
+
+        let rows = info.rows(false);
+        assert!(rows[0].starts_with("Fighter \"Icarus\""));
+        assert_eq!(rows[4], "  Orders: awaiting orders");
+        assert_eq!(rows.len(), 5);
+        assert_eq!(info.outward_rows(false), rows[..3]);
+    }
+}
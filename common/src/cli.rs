@@ -2,7 +2,18 @@ use clap::{builder::Str, value_parser, Arg, ArgAction, Command};
 
 use crate::{
     conf::{FOG_OF_WAR, MAP_HEIGHT, MAP_WIDTH},
-    game::{ai::AISpec, map::gen::MapType, player::PlayerType},
+    game::{
+        ai::{AISpec, AiBackendKind},
+        map::{
+            file as map_file,
+            gen::{MapType, Symmetry},
+            MapData,
+        },
+        obs::FogOfWar,
+        player::PlayerType,
+        unit::stats::UnitStatsRegistry,
+        TurnNum,
+    },
     util::Wrap2d,
 };
 
@@ -40,9 +51,9 @@ pub fn app(name: impl Into<Str>, included_flags: &'static str) -> Command {
             'f' => Arg::new("fog")
                 .short('f')
                 .long("fog")
-                .help("Enable or disable fog of war")
+                .help("Fog of war: 'on' (or 'full'), 'partial' to keep terrain known once seen, or 'off'")
                 .default_value(FOG_OF_WAR)
-                .value_parser(clap::builder::BoolishValueParser::new()),
+                .value_parser(|s: &str| FogOfWar::try_from(s)),
 
             'g' => Arg::new("gpu")
                 .short('g')
@@ -57,6 +68,12 @@ pub fn app(name: impl Into<Str>, included_flags: &'static str) -> Command {
             //     .takes_value(true),
             //     .multiple(true),// 
 
+            'k' => Arg::new("backend")
+                .long("backend")
+                .help("Backend to run the AIs' neural networks on: 'wgpu', 'ndarray' (CPU only), or where built in, 'tch' or 'candle'")
+                .default_value("wgpu")
+                .value_parser(|s: &str| AiBackendKind::try_from(s)),
+
             'v' => Arg::new("verbose")
                 .short('v')
                 .long("verbose")
@@ -90,7 +107,7 @@ pub fn app(name: impl Into<Str>, included_flags: &'static str) -> Command {
             'M' => Arg::new("map_type")
                 .short('M')
                 .long("map-type")
-                .help("Type of map: c[ontinents], t[ransport req'd], r[andom]")
+                .help("Type of map: c[ontinents], t[ransport req'd], r[andom], a[rchipelago], p[angaea], i[nland sea], n[oise]")
                 .default_value("c")
                 .action(ArgAction::Append)// Multiple so the AI trainer can specify multiple dimensions to train in sequence
                 .value_parser(|s:&str| MapType::try_from(s)),
@@ -109,6 +126,39 @@ pub fn app(name: impl Into<Str>, included_flags: &'static str) -> Command {
                 .action(ArgAction::Set)
                 .value_parser(value_parser!(u64)),
 
+            'L' => Arg::new("turn_limit")
+                .short('L')
+                .long("turn-limit")
+                .help("End the game after this many turns, the player with the highest score winning")
+                .value_parser(value_parser!(TurnNum)),
+
+            'U' => Arg::new("unit_stats")
+                .short('U')
+                .long("units")
+                .help("TOML file of unit stats to play with in place of the built-in ones, as in data/units.toml")
+                .value_parser(|s: &str| UnitStatsRegistry::load(s).map_err(|err| format!("Error loading unit stats {}: {}", s, err))),
+
+            'Y' => Arg::new("symmetry")
+                .short('Y')
+                .long("symmetric")
+                .help("Make the map symmetric so all players start on equal footing: 'mirror' (the default) or 'rotational'")
+                .num_args(0..=1)
+                .default_missing_value("mirror")
+                .value_parser(|s: &str| Symmetry::try_from(s)),
+
+            'm' => Arg::new("map_file")
+                .short('m')
+                .long("map-file")
+                .visible_alias("map")
+                .help("Play on the map in this file, plain or layered, rather than generating one; map type, dimensions, and symmetry are then ignored")
+                .value_parser(|s: &str| map_file::load(s).map_err(|err| format!("Error loading map {}: {}", s, err))),
+
+            'b' => Arg::new("fair_starts")
+                .short('b')
+                .long("fair-starts")
+                .help("Regenerate the map until the players' starts are balanced, warning if they never are")
+                .action(ArgAction::SetTrue),
+
             c => panic!("Tried to build CLI with unrecognized flag '{}'", c)
         });
     }
@@ -167,8 +217,8 @@ pub fn parse_ai_spec<S: AsRef<str>>(spec: S) -> Result<Vec<AISpec>, String> {
 
 #[cfg(test)]
 mod test {
-    use super::parse_ai_spec;
-    use crate::game::ai::AISpec;
+    use super::{parse_ai_spec, Specified};
+    use crate::game::ai::{AISpec, DEFAULT_PLAYOUTS};
 
     #[test]
     fn test_parse_ai_spec() {
@@ -187,5 +237,55 @@ mod test {
                 AISpec::Random { seed: None }
             ])
         );
+
+        assert_eq!(
+            parse_ai_spec("0123"),
+            Ok((0..4)
+                .map(|level| AISpec::FromLevel {
+                    level,
+                    device: Default::default()
+                })
+                .collect())
+        );
+
+        assert_eq!(
+            parse_ai_spec("rH,heuristic"),
+            Ok(vec![
+                AISpec::Random { seed: None },
+                AISpec::Heuristic,
+                AISpec::Heuristic
+            ])
+        );
+    }
+
+    #[test]
+    fn test_parse_mcts_spec() {
+        let level = |level| {
+            Box::new(AISpec::FromLevel {
+                level,
+                device: Default::default(),
+            })
+        };
+
+        assert_eq!(
+            parse_ai_spec("m:0,m400:1"),
+            Ok(vec![
+                AISpec::Mcts {
+                    model: level(0),
+                    playouts: DEFAULT_PLAYOUTS
+                },
+                AISpec::Mcts {
+                    model: level(1),
+                    playouts: 400
+                }
+            ])
+        );
+
+        assert_eq!(parse_ai_spec("m400:1").unwrap()[0].spec(), "m400:1");
+
+        // Only models can guide the search
+        assert!(parse_ai_spec("m:r").is_err());
+        assert!(parse_ai_spec("m0:1").is_err());
+        assert!(parse_ai_spec("m99999999999999999999999:1").is_err());
     }
 }
@@ -31,6 +31,9 @@ pub enum Colors {
 
     /// Scroll percentage indicators
     ScrollMarks,
+
+    /// The background of map tiles the selected unit can move to
+    Reachable,
 }
 
 pub trait Colorized {
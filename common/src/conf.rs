@@ -4,6 +4,10 @@
 //! sophisticated that allows configuration to be set through a combination of defaults, command
 //! line arguments, and configuration files.
 
+use std::time::Duration;
+
+use crate::game::{unit::UnitType, TurnNum};
+
 /// The name of this application
 pub const APP_NAME: &str = "umpire";
 
@@ -38,17 +42,114 @@ pub const GROWTH_CARDINAL_LAMBDA: f32 = 2_f32;
 /// The degree to which diagonal landmass growth should be discouraged
 pub const GROWTH_DIAGONAL_LAMBDA: f32 = 5_f32;
 
+/// The number of islands to seed when generating an archipelago
+pub const ARCHIPELAGO_LANDMASSES: u16 = 400;
+
+/// The number of iterations to grow the islands of an archipelago
+pub const ARCHIPELAGO_GROWTH_ITERATIONS: u16 = 2;
+
+/// The size of a pangaea's core, as a proportion of the distance from the center of the map to its edges
+pub const PANGAEA_RADIUS: f64 = 0.6;
+
+/// The size of an inland sea's core, as a proportion of the distance from the center of the map to its edges
+pub const INLAND_SEA_RADIUS: f64 = 0.5;
+
+/// The span in tiles of the broadest features of noise-generated terrain
+pub const NOISE_SCALE: f64 = 24.0;
+
+/// The number of layers of successively finer detail in noise-generated terrain
+pub const NOISE_OCTAVES: u16 = 5;
+
+/// How much each layer of detail in noise-generated terrain is weighted relative to the last
+pub const NOISE_PERSISTENCE: f64 = 0.5;
+
+/// The proportion of noise-generated terrain that's land, mountains included
+pub const NOISE_LAND_PROPORTION: f64 = 0.45;
+
+/// The proportion of noise-generated terrain that's mountains, before any more are added at random
+pub const NOISE_MOUNTAINS_PROPORTION: f64 = 0.03;
+
 pub const NEUTRAL_CITY_DENSITY: f64 = 0.05;
 
+/// The most maps to generate in search of balanced starts before settling for the least unbalanced
+pub const FAIR_START_ATTEMPTS: usize = 25;
+
+/// Starts are unbalanced if the least land reachable from one is under this proportion of the most from another
+pub const FAIR_START_MIN_LAND_RATIO: f64 = 0.6;
+
+/// Starts are unbalanced if their distances to the nearest neutral city differ by more than this many steps
+pub const FAIR_START_MAX_NEUTRAL_CITY_GAP: u16 = 6;
+
+/// Starts are unbalanced if the nearest any is to a rival is under this proportion of the farthest any is
+pub const FAIR_START_MIN_ISOLATION_RATIO: f64 = 0.5;
+
+/// The type of unit garrisoned in each neutral city at map generation, if any
+///
+/// Sets how much of a fight it takes to capture a neutral city.
+pub const NEUTRAL_GARRISON_TYPE: Option<UnitType> = Some(UnitType::Infantry);
+
+/// The proportion of land to turn into forest during map generation
+pub const FOREST_DENSITY: f64 = 0.15;
+
+/// The proportion of land to turn into mountains during map generation
+pub const MOUNTAINS_DENSITY: f64 = 0.05;
+
+/// The number of rivers to run from the mountains during map generation
+pub const RIVERS: u16 = 20;
+
+/// The longest a river can grow during map generation
+pub const RIVER_MAX_LENGTH: u16 = 25;
+
+/// A ford is placed every this many tiles along a river
+pub const RIVER_FORD_INTERVAL: u16 = 4;
+
+/// The probability of a river bending sideways at each step
+pub const RIVER_MEANDER_PROB: f64 = 0.3;
+
+/// The multiplier on the combat strength of units defending a fortified tile
+pub const FORTIFICATION_DEFENSE_BONUS: f64 = 1.5;
+
+/// The multiplier on the combat strength of a city and its garrison, before any walls
+pub const CITY_DEFENSE_BONUS: f64 = 1.25;
+
+/// How much each level of city walls adds to the city's defense bonus
+pub const CITY_WALL_DEFENSE_BONUS: f64 = 0.25;
+
+/// The production spent on each level of city walls
+pub const CITY_WALL_COST: u16 = 12;
+
+/// The most levels of walls a city can have
+pub const CITY_MAX_WALLS: u16 = 3;
+
+/// The production a city spends on a radar
+pub const CITY_RADAR_COST: u16 = 20;
+
+/// How far a radar sees, well beyond any unit
+pub const RADAR_SIGHT_DISTANCE: u16 = 10;
+
+/// The most tiles whose passability can change mid-move before a unit's shortest paths are searched anew rather than
+/// repaired
+pub const SHORTEST_PATHS_MAX_REPAIRED_CHANGES: usize = 8;
+
+/// The fraction of a unit's cost credited to the production of the friendly city it's disbanded in
+pub const DISBAND_REFUND_FRACTION: f64 = 0.5;
+
+/// The hitpoints a unit loses when it strays into an enemy minefield
+pub const MINE_DAMAGE: u16 = 1;
+
 /// The number of teams playing, including humans and AIs
 pub const NUM_PLAYERS: &str = "4";
 
 pub const FOG_OF_WAR: &str = "on";
 
+/// How many turns old an observation can be before the map shows it as stale rather than merely remembered
+pub const STALE_OBS_TURNS: TurnNum = 10;
+
 // pub const HUMAN_PLAYER: PlayerNum = 0;
 
 // Key mappings
 pub const KEY_VIEWPORT_SIZE_ROTATE: char = 'v';
+pub const KEY_VIEWPORT_SWITCH: char = '|';
 pub const KEY_LEFT: char = 'h';
 pub const KEY_RIGHT: char = 'l';
 pub const KEY_UP: char = 'k';
@@ -77,18 +178,112 @@ pub const KEY_NUMPAD_DOWN_RIGHT: char = '3';
 
 pub const KEY_EXPLORE: char = 'o';
 
+pub const KEY_ESCORT: char = 'f';
+
 pub const KEY_SENTRY: char = 's';
 
 pub const KEY_SKIP: char = ' ';
 
+/// Leave the unit getting orders be for now, and give orders to the next unit awaiting them
+pub const KEY_NEXT_UNIT: char = 'i';
+
 pub const KEY_DISBAND: char = 'd';
 
+pub const KEY_LOAD: char = 'e';
+
+pub const KEY_UNLOAD: char = 'E';
+
+pub const KEY_REBASE: char = 'R';
+
+pub const KEY_BOMBARD: char = 'F';
+
+pub const KEY_PARADROP: char = 'P';
+
+pub const KEY_BUILD_ROAD: char = 'r';
+
+pub const KEY_BUILD_FORTIFICATION: char = 'f';
+
+pub const KEY_BUILD_RADAR: char = 'V';
+
+pub const KEY_LAY_MINES: char = 'M';
+
+pub const KEY_SWEEP_MINES: char = 'S';
+
 pub const KEY_QUIT: char = 'q';
 
+pub const KEY_RESIGN: char = 'Q';
+
+pub const KEY_CONFIRM_RESIGN: char = 'y';
+
 pub const KEY_EXAMINE: char = 'x';
 
+/// While examining, queue the location under the cursor as a stop on the way to wherever is picked with Enter
+pub const KEY_ADD_WAYPOINT: char = '+';
+
 pub const KEY_NO_PRODUCTION: char = 'n';
 
+pub const KEY_BUILD_WALLS: char = 'W';
+
+pub const KEY_SAVE: char = 'w';
+
+pub const KEY_DIPLOMACY: char = 'D';
+
+pub const KEY_PROPOSE_PEACE: char = 'p';
+
+pub const KEY_PROPOSE_ALLIANCE: char = 'a';
+
+pub const KEY_ACCEPT_TREATY: char = 'y';
+
+pub const KEY_DECLARE_WAR: char = 'W';
+
+pub const KEY_GROUPS: char = 'g';
+
+pub const KEY_GROUP_ADD: char = 'a';
+
+pub const KEY_GROUP_NEW: char = 'c';
+
+pub const KEY_GROUP_DISBAND: char = 'r';
+
+pub const KEY_GROUP_ATTACK: char = 'A';
+
+pub const KEY_SETTINGS: char = 'O';
+
+pub const KEY_CITY_LIST: char = 'C';
+
+pub const KEY_UNIT_ROSTER: char = 'T';
+
+pub const KEY_CHAT: char = '/';
+
+pub const KEY_JUMP_TO_ALERT: char = 'z';
+
+pub const KEY_ROSTER_WAKE: char = 'a';
+
+pub const KEY_ROSTER_WAKE_SENTRIES: char = 'A';
+
+pub const KEY_REPLAY_PAUSE: char = ' ';
+
+pub const KEY_REPLAY_STEP_FORWARD: char = '.';
+
+pub const KEY_REPLAY_STEP_BACK: char = ',';
+
+pub const KEY_REPLAY_NEXT_TURN: char = '>';
+
+pub const KEY_REPLAY_PREV_TURN: char = '<';
+
+pub const KEY_EDITOR_PAINT: char = ' ';
+
+pub const KEY_EDITOR_TERRAIN: char = 't';
+
+pub const KEY_EDITOR_CITY: char = 'c';
+
+pub const KEY_EDITOR_UNIT: char = 'a';
+
+pub const KEY_EDITOR_PLAYER: char = 'p';
+
+pub const KEY_EDITOR_ERASE: char = 'x';
+
+pub const KEY_SPECTATE_NEXT_VIEW: char = 'p';
+
 pub fn key_desc(key: char) -> String {
     match key {
         ' ' => String::from("Space"),
@@ -98,3 +293,31 @@ pub fn key_desc(key: char) -> String {
 }
 
 pub const PORT: u16 = 21131;
+
+/// How many times a client tries to reconnect to the server after losing its connection, before giving up
+pub const RECONNECT_ATTEMPTS: u32 = 8;
+
+/// How long a client waits before its first attempt to reconnect; the wait doubles with each attempt after
+pub const RECONNECT_INITIAL_BACKOFF: Duration = Duration::from_millis(250);
+
+/// How often a client lets the server know it's still there, so its connection isn't given up on as lost
+pub const HEARTBEAT_INTERVAL: Duration = Duration::from_secs(5);
+
+/// How long a client waits on the server for something to happen before asking again, well within the deadline of a
+/// request
+pub const LONG_POLL_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// How many new connections the server settles compression with at once
+pub const MAX_PENDING_HANDSHAKES: usize = 16;
+
+/// Where games are saved to, and loaded from if no other path is given
+pub const SAVE_PATH: &str = "umpire.sav";
+
+/// Where replays are recorded to, and played back from if no other path is given
+pub const REPLAY_PATH: &str = "umpire.replay";
+
+/// Where maps are edited and loaded from if no other path is given
+pub const MAP_PATH: &str = "umpire.map";
+
+/// Where the client's settings are kept between runs
+pub const SETTINGS_PATH: &str = "umpire.toml";
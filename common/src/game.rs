@@ -5,16 +5,24 @@
 
 pub mod action;
 pub mod ai;
+pub mod alert;
 pub mod alignment;
+pub mod chat;
 pub mod city;
 pub mod combat;
+pub mod delta;
+pub mod diplomacy;
 pub mod error;
+pub mod group;
+pub mod history;
 mod igameimpl;
 pub mod map;
 pub mod move_;
 pub mod obs;
 pub mod player;
 pub mod proposed;
+pub mod replay;
+pub mod save;
 pub mod traits;
 pub mod turn;
 pub mod turn_async;
@@ -28,32 +36,34 @@ use std::{
 
 use rand::{rngs::StdRng, Rng};
 use serde::{Deserialize, Serialize};
-use tokio::sync::RwLock as RwLockTokio;
+use tokio::sync::{watch, RwLock as RwLockTokio};
 use uuid::Uuid;
 
 use crate::{
+    conf,
     game::{
         action::NextCityAction,
-        ai::{
-            ADDED_WIDE_FEATURES, BASE_CONV_FEATS, DEEP_HEIGHT, DEEP_HEIGHT_REL_MAX,
-            DEEP_HEIGHT_REL_MIN, DEEP_IN_LEN, DEEP_WIDTH, DEEP_WIDTH_REL_MAX, DEEP_WIDTH_REL_MIN,
-        },
+        ai::{deep_in_len, ADDED_WIDE_FEATURES, BASE_CONV_FEATS},
+        alert::Alert,
         city::{City, CityID},
-        combat::CombatCapable,
+        combat::{CombatCapable, CombatOdds, CombatOutcome},
         error::GameError,
         map::{
             dijkstra::{
                 self, directions_unit_could_move_iter, neighbors_terrain_only,
                 neighbors_unit_could_move_to_iter, AndFilter, Filter, NoCitiesButOursFilter,
-                NoUnitsFilter, ShortestPaths, Source, UnitMovementFilter,
+                NoUnitsFilter, ShortestPaths, Source, UnitMovementCost, UnitMovementFilter,
                 UnitMovementFilterXenophile,
             },
-            LocationGridI, MapData, NewUnitError, Tile,
+            Improvement, LocationGridI, MapData, NewUnitError, Tile,
+        },
+        obs::{
+            visible_coords_iter, CompactObsTracker, FogOfWar, Obs, ObsTracker, ObsTrackerI,
+            Observer, PlayerObsTracker,
         },
-        obs::{Obs, ObsTracker, Observer, PlayerObsTracker},
         unit::{
-            orders::{Orders, OrdersOutcome, OrdersResult, OrdersStatus},
-            Unit, UnitID, UnitType,
+            orders::{Orders, OrdersOutcome, OrdersResult, OrdersStatus, PlannedRoute, Waypoints},
+            TransportMode, Unit, UnitID, UnitType, POSSIBLE_UNIT_TYPES,
         },
     },
     name::{IntNamer, Namer},
@@ -68,19 +78,26 @@ pub use self::player::{PlayerNum, PlayerType};
 
 use self::{
     action::{Actionable, NextUnitAction, PlayerAction, PlayerActionOutcome},
-    ai::{fX, TrainingFocus, FEATS_LEN},
+    ai::{fX, feats_len, TrainingFocus},
     alignment::{Aligned, AlignedMaybe},
-    map::gen::MapType,
+    chat::{ChatMessage, MAX_CHAT_MESSAGE_LEN, MAX_PLAYER_NAME_LEN},
+    delta::{SyncPoint, TurnDelta, TurnSnapshot},
+    diplomacy::{Diplomacy, DiplomaticStatus},
+    group::UnitGroups,
+    history::{PlayerHistory, Standing},
+    map::gen::{MapType, Symmetry},
     move_::{Move, MoveComponent, MoveError},
     obs::{LocatedObs, LocatedObsLite},
     player::PlayerControl,
     proposed::Proposed2,
+    replay::{Replay, ReplayEntry},
+    save::SavedGame,
     unit::Fuel,
 };
 
 pub use self::traits::IGame;
 
-static UNIT_TYPES: [UnitType; 10] = UnitType::values();
+static UNIT_TYPES: [UnitType; POSSIBLE_UNIT_TYPES] = UnitType::values();
 
 /// How important is a city in and of itself?
 const CITY_INTRINSIC_SCORE: f64 = 1000.0;
@@ -128,9 +145,83 @@ pub struct ProductionSet {
     pub obs: LocatedObsLite,
 }
 
+/// Another level of walls went up around a city
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+pub struct WallsBuilt {
+    /// The city's levels of walls now
+    pub walls: u16,
+    pub obs: LocatedObsLite,
+}
+
+/// A city put up a radar
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+pub struct RadarBuilt {
+    pub obs: LocatedObsLite,
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct TurnEnded {
     pub observations: Vec<LocatedObsLite>,
+
+    /// Set if the game had been decided by the time the turn ended
+    pub game_over: Option<GameOver>,
+
+    /// What befell the player since its previous turn ended
+    pub summary: TurnSummary,
+}
+
+/// Tallies of what befell a player between the end of one of its turns and the end of the next
+///
+/// Battles and cities count for both sides, so fights on other players' turns are included as well as the player's
+/// own.
+#[derive(Clone, Debug, Default, Deserialize, PartialEq, Eq, Serialize)]
+pub struct TurnSummary {
+    pub units_produced: usize,
+    pub battles_won: usize,
+    pub battles_lost: usize,
+    pub cities_gained: usize,
+    pub cities_lost: usize,
+
+    /// Tiles seen for the first time
+    pub tiles_explored: usize,
+}
+
+impl std::ops::AddAssign<&TurnSummary> for TurnSummary {
+    fn add_assign(&mut self, rhs: &TurnSummary) {
+        self.units_produced += rhs.units_produced;
+        self.battles_won += rhs.battles_won;
+        self.battles_lost += rhs.battles_lost;
+        self.cities_gained += rhs.cities_gained;
+        self.cities_lost += rhs.cities_lost;
+        self.tiles_explored += rhs.tiles_explored;
+    }
+}
+
+/// Why a game came to an end
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq, Eq, Serialize)]
+pub enum GameOverReason {
+    /// One player defeated all the others
+    Conquest,
+
+    /// The turn limit was reached; the player with the highest score won
+    TurnLimit,
+}
+
+/// A player's withdrawal from the game
+#[derive(Debug, Deserialize, PartialEq, Serialize)]
+pub struct Resigned {
+    /// How the locations of the player's former units and cities look now
+    pub observations: Vec<LocatedObsLite>,
+
+    /// Set if the resignation decided the game
+    pub game_over: Option<GameOver>,
+}
+
+/// The outcome of a game that has been decided
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq, Eq, Serialize)]
+pub struct GameOver {
+    pub victor: PlayerNum,
+    pub reason: GameOverReason,
 }
 
 #[derive(Debug, Deserialize, PartialEq, Serialize)]
@@ -140,12 +231,45 @@ pub struct TurnStart {
     pub orders_results: Vec<OrdersResult>,
     pub production_outcomes: Vec<UnitProductionOutcome>,
     pub observations: Vec<LocatedObs>,
+
+    /// Sentried units that spotted an enemy and now await orders
+    pub units_woken: Vec<UnitWoken>,
+
+    /// What demands the player's attention, raised since its last turn and while this one began
+    pub alerts: Vec<Alert>,
+}
+
+/// A sentried unit's orders were cleared because an enemy came into sight
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct UnitWoken {
+    /// A copy of the unit that was woken, sans orders
+    pub unit: Unit,
+
+    /// Where the enemy was spotted
+    pub sighted: Location,
 }
 
 #[derive(Debug, PartialEq, Serialize, Deserialize)]
 pub struct UnitDisbanded {
     pub unit: Unit,
     pub obs: LocatedObsLite,
+
+    /// Production credited to the friendly city the unit was disbanded in, if any
+    pub refund: u16,
+}
+
+/// A unit's attack on a location within its range, made without moving
+#[derive(Debug, Deserialize, PartialEq, Serialize)]
+pub struct Bombardment {
+    pub unit_id: UnitID,
+    pub target: Location,
+
+    /// The fight with the target's best defender. If the defender wins, the target simply survives; the attacking
+    /// unit isn't harmed.
+    pub combat: CombatOutcome<Unit, Unit>,
+
+    /// What can be seen of the target afterward
+    pub obs: LocatedObsLite,
 }
 
 #[derive(Debug, Deserialize, PartialEq, Serialize)]
@@ -228,7 +352,10 @@ pub struct Game {
     unit_namer: Arc<RwLock<dyn Namer>>,
 
     /// Whether players have full information about the map, or have their knowledge obscured by the "fog of war".
-    fog_of_war: bool,
+    fog_of_war: FogOfWar,
+
+    /// The turn on which the game ends, if nobody has won outright before then
+    turn_limit: Option<TurnNum>,
 
     /// The total number of actions taken during the game, by all players
     action_count: ActionNum,
@@ -244,31 +371,96 @@ pub struct Game {
     ///
     /// Stored for use in the score calculation.
     defeated_unit_hitpoints: Vec<u64>,
+
+    /// War, peace, and alliances between the players
+    diplomacy: Diplomacy,
+
+    /// Each player's named unit groups
+    unit_groups: Vec<UnitGroups>,
+
+    /// Each player's sentried units woken since its turn last began, to be reported when its next turn begins
+    units_woken: Vec<Vec<UnitWoken>>,
+
+    /// Each player's alerts raised since its turn last began, to be handed over when its next turn begins
+    alerts: Vec<Vec<Alert>>,
+
+    /// What has befallen each player since its last turn ended, to be reported when its current turn ends
+    turn_summaries: Vec<TurnSummary>,
+
+    /// How many tiles each player had observed when its last turn ended, for counting the tiles explored since
+    observed_at_turn_end: Vec<usize>,
+
+    /// Each player's standings and running totals, turn by turn
+    histories: Vec<PlayerHistory>,
+
+    /// Every chat message sent so far, oldest first
+    chat: Vec<ChatMessage>,
+
+    /// The names players have gone by, if they've given one
+    player_names: Vec<Option<String>>,
+
+    /// The players who have resigned from the game
+    resigned: BTreeSet<PlayerNum>,
+
+    /// The actions taken since recording began, if recording
+    replay: Option<Replay>,
+
+    /// How deeply nested we are in recordable actions; only the outermost gets recorded
+    replay_depth: usize,
+
+    /// The routes of units under go-to orders, as planned on earlier turns
+    ///
+    /// Not saved; after loading, routes are planned afresh.
+    go_to_routes: BTreeMap<UnitID, PlannedRoute>,
+
+    /// Whether play is held, so no new turn can begin
+    ///
+    /// Not saved; a loaded game is never paused.
+    paused: bool,
+
+    /// Announces the turn and the player to play whenever play passes to the next player
+    ///
+    /// Shared by clones, so those waiting on the turn to come around should check the game they're waiting on.
+    turn_watch: Arc<watch::Sender<(TurnNum, PlayerNum)>>,
+
+    /// Announces how many chat messages have been sent whenever another one is
+    ///
+    /// Shared by clones, like `turn_watch`.
+    chat_watch: Arc<watch::Sender<usize>>,
 }
 impl Game {
     /// Creates a new game instance
     ///
     /// The Game that is returned will already have begun with the first player's turn.
     ///
-    /// A map with the specified dimensions will be generated. City names are taken from `city_namer`
+    /// A map with the specified dimensions will be generated, made symmetric if `symmetry` is given. City names are
+    /// taken from `city_namer`
     ///
-    /// If `fog_of_war` is `true` then players' view of the map will be limited to what they have previously
-    /// observed, with observations growing stale over time.
+    /// If `fog_of_war` is `FogOfWar::Full` (or `true`) then players' view of the map will be limited to what they have
+    /// previously observed, with observations growing stale over time. Under `FogOfWar::Partial` the terrain stays
+    /// accurate once observed, but units and cities are only seen while in sight.
     ///
     /// Also returns the player secrets used for access control
-    pub fn new<N: Namer>(
+    pub fn new<N: Namer, F: Into<FogOfWar>>(
         rng: Option<StdRng>,
         deterministic_secrets: bool,
         map_dims: Dims,
         map_type: MapType,
+        symmetry: Option<Symmetry>,
         mut city_namer: N,
         num_players: PlayerNum,
-        fog_of_war: bool,
+        fog_of_war: F,
         unit_namer: Option<Arc<RwLock<dyn Namer>>>,
         wrapping: Wrap2d,
     ) -> (Self, Vec<PlayerSecret>) {
         let mut rng = rng.unwrap_or_else(|| init_rng(None));
-        let map = map_type.generate(&mut rng, map_dims, num_players, &mut city_namer);
+        let map = map_type.generate_with_symmetry(
+            &mut rng,
+            map_dims,
+            num_players,
+            &mut city_namer,
+            symmetry,
+        );
         Self::new_with_map(
             Some(rng),
             deterministic_secrets,
@@ -283,16 +475,21 @@ impl Game {
     /// Creates a new game instance from a pre-generated map
     ///
     /// Also returns the player secrets used for access control
-    pub fn new_with_map(
+    pub fn new_with_map<F: Into<FogOfWar>>(
         rng: Option<StdRng>,
         deterministic_secrets: bool,
         map: MapData,
         num_players: PlayerNum,
-        fog_of_war: bool,
+        fog_of_war: F,
         unit_namer: Option<Arc<RwLock<dyn Namer>>>,
         wrapping: Wrap2d,
     ) -> (Self, Vec<PlayerSecret>) {
-        let player_observations = PlayerObsTracker::new(num_players, map.dims());
+        let fog_of_war = fog_of_war.into();
+        let player_observations = if fog_of_war == FogOfWar::Off {
+            PlayerObsTracker::universal(num_players, map.dims())
+        } else {
+            PlayerObsTracker::new(num_players, map.dims())
+        };
         let player_pending_observations = (0..num_players).map(|_| Vec::new()).collect();
 
         let rng = rng.unwrap_or_else(|| init_rng(None));
@@ -311,9 +508,26 @@ impl Game {
             wrapping,
             unit_namer: unit_namer.unwrap_or(Arc::new(RwLock::new(IntNamer::new("unit")))),
             fog_of_war,
+            turn_limit: None,
             action_count: 0,
             action_counts: vec![0; num_players],
             defeated_unit_hitpoints: vec![0; num_players],
+            diplomacy: Diplomacy::new(num_players),
+            unit_groups: vec![UnitGroups::default(); num_players],
+            units_woken: (0..num_players).map(|_| Vec::new()).collect(),
+            alerts: (0..num_players).map(|_| Vec::new()).collect(),
+            turn_summaries: vec![TurnSummary::default(); num_players],
+            observed_at_turn_end: vec![0; num_players],
+            histories: vec![PlayerHistory::default(); num_players],
+            chat: Vec::new(),
+            player_names: vec![None; num_players],
+            resigned: BTreeSet::new(),
+            replay: None,
+            replay_depth: 0,
+            go_to_routes: BTreeMap::new(),
+            paused: false,
+            turn_watch: Arc::new(watch::channel((0, 0)).0),
+            chat_watch: Arc::new(watch::channel(0).0),
         };
 
         let secrets: Vec<PlayerSecret> = (0..num_players)
@@ -344,12 +558,12 @@ impl Game {
     }
 
     /// Set up a sharable game instance and return it and controls for each player
-    pub async fn setup_with_map(
+    pub async fn setup_with_map<F: Into<FogOfWar>>(
         rng: Option<StdRng>,
         deterministic_secrets: bool,
         map: MapData,
         num_players: PlayerNum,
-        fog_of_war: bool,
+        fog_of_war: F,
         unit_namer: Option<Arc<RwLock<dyn Namer>>>,
         wrapping: Wrap2d,
     ) -> (Arc<RwLockTokio<Self>>, Vec<PlayerControl>) {
@@ -569,6 +783,154 @@ impl Game {
         self.action_counts[player] += 1;
     }
 
+    /// Tally a fight in the turn summaries of the attacker and of the defender, if it belongs to a player
+    fn record_battle(&mut self, attacker: PlayerNum, defender: Alignment, attacker_won: bool) {
+        let summary = &mut self.turn_summaries[attacker];
+        if attacker_won {
+            summary.battles_won += 1;
+        } else {
+            summary.battles_lost += 1;
+        }
+
+        if let Alignment::Belligerent { player: defender } = defender {
+            let summary = &mut self.turn_summaries[defender];
+            if attacker_won {
+                summary.battles_lost += 1;
+            } else {
+                summary.battles_won += 1;
+            }
+        }
+    }
+
+    /// Raise an alert for `player`, replacing any earlier sighting of the same enemy unit
+    fn alert(&mut self, player: PlayerNum, alert: Alert) {
+        let alerts = &mut self.alerts[player];
+        alerts.retain(|prior| !alert.resights(prior));
+        alerts.push(alert);
+    }
+
+    /// Raise an alert for whoever owns the city or unit it concerns, if a player does
+    fn alert_owner(&mut self, owner: Alignment, alert: Alert) {
+        if let Alignment::Belligerent { player } = owner {
+            self.alert(player, alert);
+        }
+    }
+
+    /// Tally a city taken by `captor` from whoever held it before
+    fn record_city_captured(&mut self, captor: PlayerNum, former: Alignment) {
+        self.turn_summaries[captor].cities_gained += 1;
+
+        if let Alignment::Belligerent { player: former } = former {
+            self.turn_summaries[former].cities_lost += 1;
+        }
+    }
+
+    /// Begin recording a replay of all actions taken from here on
+    ///
+    /// Any replay already being recorded is discarded. The random number generator is reseeded so the replay can
+    /// reproduce the game exactly.
+    pub fn record_replay(&mut self) {
+        let seed: u64 = self.rng.gen();
+        self.rng = init_rng(Some(seed));
+        self.replay = Some(Replay::new(SavedGame::new(self, seed)));
+    }
+
+    /// Draw future random outcomes, such as those of combat, from a fresh generator seeded by `seed`
+    ///
+    /// A copy of the game otherwise rolls the same dice the game itself is going to, giving away how fights will turn
+    /// out before they happen.
+    pub fn reseed(&mut self, seed: Option<u64>) {
+        self.rng = init_rng(seed);
+    }
+
+    /// The replay being recorded, if any
+    pub fn replay(&self) -> Option<&Replay> {
+        self.replay.as_ref()
+    }
+
+    /// Stop recording, returning the replay recorded so far
+    pub fn take_replay(&mut self) -> Option<Replay> {
+        self.replay.take()
+    }
+
+    /// A copy of the game to try actions out on, as for proposals and tree search
+    ///
+    /// Nothing done to the copy is ever recorded or shown to a player, so the replay, the chat, the players' histories
+    /// and what's waiting to be reported to them are left behind. The map's tiles and the players' observations are
+    /// shared with the game until the copy changes them.
+    pub fn sandbox(&self) -> Self {
+        Self {
+            rng: self.rng.clone(),
+            deterministic_secrets: self.deterministic_secrets,
+            map: self.map.clone(),
+            player_observations: self.player_observations.clone(),
+            player_pending_observations: vec![Vec::new(); self.player_pending_observations.len()],
+            turn: self.turn,
+            turn_phase: self.turn_phase,
+            num_players: self.num_players,
+            player_secrets: self.player_secrets.clone(),
+            current_player: self.current_player,
+            wrapping: self.wrapping,
+            unit_namer: Arc::clone(&self.unit_namer),
+            fog_of_war: self.fog_of_war,
+            turn_limit: self.turn_limit,
+            action_count: self.action_count,
+            action_counts: self.action_counts.clone(),
+            defeated_unit_hitpoints: self.defeated_unit_hitpoints.clone(),
+            diplomacy: self.diplomacy.clone(),
+            unit_groups: self.unit_groups.clone(),
+            units_woken: vec![Vec::new(); self.num_players],
+            alerts: vec![Vec::new(); self.num_players],
+            turn_summaries: vec![TurnSummary::default(); self.num_players],
+            observed_at_turn_end: self.observed_at_turn_end.clone(),
+            histories: vec![PlayerHistory::default(); self.num_players],
+            chat: Vec::new(),
+            player_names: self.player_names.clone(),
+            resigned: self.resigned.clone(),
+            replay: None,
+            replay_depth: 0,
+            go_to_routes: self.go_to_routes.clone(),
+            paused: self.paused,
+            // Nobody waits on turns or chat in the sandbox
+            turn_watch: Arc::new(watch::channel((self.turn, self.current_player)).0),
+            chat_watch: Arc::new(watch::channel(0).0),
+        }
+    }
+
+    /// Run `f`, recording `action` in the replay if it succeeds
+    ///
+    /// Actions taken in the course of another action (as when orders are carried out) aren't recorded; taking the
+    /// outer action again reproduces them.
+    fn recorded<T>(
+        &mut self,
+        player_secret: PlayerSecret,
+        action: PlayerAction,
+        f: impl FnOnce(&mut Self) -> UmpireResult<T>,
+    ) -> UmpireResult<T> {
+        if self.replay.is_none() {
+            return f(self);
+        }
+
+        let turn = self.turn;
+
+        self.replay_depth += 1;
+        let result = f(self);
+        self.replay_depth -= 1;
+
+        if result.is_ok() && self.replay_depth == 0 {
+            let player = self.player_with_secret(player_secret)?;
+            if let Some(replay) = self.replay.as_mut() {
+                replay.record(ReplayEntry {
+                    turn,
+                    player,
+                    action,
+                });
+            }
+        }
+
+        result
+    }
+
     pub fn current_turn_begun(&self) -> bool {
         self.turn_phase == TurnPhase::Main
     }
@@ -583,13 +945,35 @@ impl Game {
         &mut self,
         player_secret: PlayerSecret,
         clear_after_unit_production: bool,
+    ) -> UmpireResult<TurnStart> {
+        self.recorded(
+            player_secret,
+            PlayerAction::BeginTurn {
+                clear_after_unit_production,
+            },
+            |game| game._begin_turn(player_secret, clear_after_unit_production),
+        )
+    }
+
+    fn _begin_turn(
+        &mut self,
+        player_secret: PlayerSecret,
+        clear_after_unit_production: bool,
     ) -> UmpireResult<TurnStart> {
         let player = self.validate_is_player_turn_pre_phase(player_secret)?;
 
+        if self.paused {
+            return Err(GameError::GamePaused);
+        }
+
         // "Beginning" a turn is what moves us from Pre to Main phase
         self.turn_phase = TurnPhase::Main;
 
         let production_outcomes = self.produce_units(player_secret)?;
+        self.turn_summaries[player].units_produced += production_outcomes
+            .iter()
+            .filter(|prod| matches!(prod, UnitProductionOutcome::UnitProduced { .. }))
+            .count();
 
         if clear_after_unit_production {
             for prod in production_outcomes.iter() {
@@ -604,6 +988,21 @@ impl Game {
 
         let observations = self.update_player_observations(player);
 
+        let sightings: BTreeSet<Location> = observations
+            .iter()
+            .filter(|located_obs| located_obs.reveals_enemy_of(player))
+            .map(|located_obs| located_obs.loc)
+            .collect();
+        self.wake_sentries(player, &sightings);
+        let units_woken = std::mem::take(&mut self.units_woken[player]);
+
+        for located_obs in observations.iter() {
+            if let Some(alert) = Alert::sighting(located_obs, player) {
+                self.alert(player, alert);
+            }
+        }
+        let alerts = std::mem::take(&mut self.alerts[player]);
+
         let orders_results = self.follow_pending_orders(player_secret)?;
 
         Ok(TurnStart {
@@ -612,6 +1011,8 @@ impl Game {
             orders_results,
             production_outcomes,
             observations,
+            units_woken,
+            alerts,
         })
     }
 
@@ -695,8 +1096,125 @@ impl Game {
         None
     }
 
+    pub fn turn_limit(&self) -> Option<TurnNum> {
+        self.turn_limit
+    }
+
+    /// Set the turn on which the game ends if nobody has won outright by then, or `None` to play on indefinitely
+    pub fn set_turn_limit(&mut self, turn_limit: Option<TurnNum>) {
+        self.turn_limit = turn_limit;
+    }
+
+    /// How the game was decided, if it has been
+    ///
+    /// A player who defeats all the others wins by conquest. Failing that, once the turn limit (if any) is reached,
+    /// the player with the highest score wins, ties going to the lowest-numbered player. Players who resigned can't
+    /// win on score.
+    ///
+    /// As with `victor`, the game will continue to function after it's over.
+    pub fn game_over(&self) -> Option<GameOver> {
+        if let Some(victor) = self.victor() {
+            return Some(GameOver {
+                victor,
+                reason: GameOverReason::Conquest,
+            });
+        }
+
+        if self.turn < self.turn_limit? {
+            return None;
+        }
+
+        let scores = self.player_scores();
+        let victor = (0..self.num_players)
+            .filter(|player| !self.resigned.contains(player))
+            .reduce(|best, player| {
+                if scores[player] > scores[best] {
+                    player
+                } else {
+                    best
+                }
+            })?;
+
+        Some(GameOver {
+            victor,
+            reason: GameOverReason::TurnLimit,
+        })
+    }
+
+    /// Withdraw the player from the game
+    ///
+    /// The player's cities become neutral and its units are disbanded, which leaves it out of contention. Whether
+    /// somebody has now won is re-evaluated.
+    ///
+    /// Must be player's turn
+    pub fn resign(&mut self, player_secret: PlayerSecret) -> UmpireResult<Resigned> {
+        self.recorded(player_secret, PlayerAction::Resign, |game| {
+            game._resign(player_secret)
+        })
+    }
+
+    fn _resign(&mut self, player_secret: PlayerSecret) -> UmpireResult<Resigned> {
+        let player = self.validate_is_player_turn(player_secret)?;
+
+        if !self.resigned.insert(player) {
+            return Err(GameError::AlreadyResigned { player });
+        }
+
+        let mut locs: BTreeSet<Location> = BTreeSet::new();
+
+        let units: Vec<(UnitID, Location)> = self
+            .map
+            .player_units(player)
+            .map(|unit| (unit.id, unit.loc))
+            .collect();
+        for (unit_id, loc) in units {
+            // Carried units go down with their carriers, so may already be gone
+            self.map.pop_player_unit_by_id(player, unit_id);
+            locs.insert(loc);
+        }
+
+        let city_locs: Vec<Location> = self
+            .map
+            .player_cities(player)
+            .map(|city| city.loc)
+            .collect();
+        for loc in city_locs {
+            self.map.clear_city_production_by_loc(loc, false).unwrap();
+            self.map.clear_city_production_progress_by_loc(loc).unwrap();
+            self.map
+                .set_city_alignment_by_loc(loc, Alignment::Neutral)
+                .unwrap();
+            locs.insert(loc);
+        }
+
+        self.unit_groups[player] = UnitGroups::default();
+
+        self.action_taken(player);
+
+        let observations = locs
+            .into_iter()
+            .map(|loc| self._observable_event(loc, true).unwrap().lite())
+            .collect();
+
+        Ok(Resigned {
+            observations,
+            game_over: self.game_over(),
+        })
+    }
+
+    /// Whether the player has resigned from the game
+    pub fn player_resigned(&self, player: PlayerNum) -> bool {
+        self.resigned.contains(&player)
+    }
+
     /// Ends the turn but doesn't check if requests are completed
     pub fn force_end_turn(&mut self, player_secret: PlayerSecret) -> UmpireResult<TurnEnded> {
+        self.recorded(player_secret, PlayerAction::ForceEndTurn, |game| {
+            game._force_end_turn(player_secret)
+        })
+    }
+
+    fn _force_end_turn(&mut self, player_secret: PlayerSecret) -> UmpireResult<TurnEnded> {
         let player = self.validate_is_player_turn_main_phase(player_secret)?;
 
         let observations = {
@@ -723,17 +1241,45 @@ impl Game {
             observations
         };
 
-        self.player_observations_mut(player_secret)?.archive();
+        let observed = self
+            .player_observations
+            .tracker(player)
+            .unwrap()
+            .num_observed();
+        let mut summary = std::mem::take(&mut self.turn_summaries[player]);
+        let previously_observed =
+            std::mem::replace(&mut self.observed_at_turn_end[player], observed);
+        summary.tiles_explored = observed.saturating_sub(previously_observed);
+
+        let standing = Standing {
+            turn: self.turn,
+            score: self.player_score_by_idx(player)?,
+            cities: self.player_cities_by_idx(player)?.count(),
+            units: self.player_units_by_idx(player).count(),
+        };
+        self.histories[player].record(standing, &summary);
+
+        self.player_observations.archive(player).unwrap();
 
         self._inc_current_player();
 
         // The next player's turn starts out in the Pre phase
         self.turn_phase = TurnPhase::Pre;
 
-        Ok(TurnEnded { observations })
+        Ok(TurnEnded {
+            observations,
+            game_over: self.game_over(),
+            summary,
+        })
     }
 
     pub fn end_turn(&mut self, player_secret: PlayerSecret) -> UmpireResult<TurnEnded> {
+        self.recorded(player_secret, PlayerAction::EndTurn, |game| {
+            game._end_turn(player_secret)
+        })
+    }
+
+    fn _end_turn(&mut self, player_secret: PlayerSecret) -> UmpireResult<TurnEnded> {
         self.validate_is_player_turn(player_secret)?;
 
         if self.current_turn_is_done() {
@@ -786,6 +1332,16 @@ impl Game {
         if self.current_player == 0 {
             self.turn += 1;
         }
+
+        self.turn_watch
+            .send_replace((self.turn, self.current_player));
+    }
+
+    /// Be told the turn and the player to play each time play passes to the next player
+    ///
+    /// Lets those waiting for a player's turn sleep until it could have come, rather than checking over and over.
+    pub fn watch_turns(&self) -> watch::Receiver<(TurnNum, PlayerNum)> {
+        self.turn_watch.subscribe()
     }
 
     /// End the turn without checking that the player has filled all production and orders requests.
@@ -800,13 +1356,15 @@ impl Game {
         self.begin_turn(next_player_secret, clear_after_unit_production)
     }
 
-    /// Register the current observations of player units
+    /// Register the current observations of player cities, units, and radars
     ///
     /// This applies only to top-level units. Carried units (e.g. units in a transport or carrier) make no observations
+    ///
+    /// Under partial fog of war, what the player remembers of locations now out of sight is reduced to their terrain.
     fn update_player_observations(&mut self, player: PlayerNum) -> Vec<LocatedObs> {
         let obs_tracker = self.player_observations.tracker_mut(player).unwrap();
 
-        if self.fog_of_war {
+        if self.fog_of_war != FogOfWar::Off {
             let mut observations: Vec<LocatedObs> = Vec::new();
             for city in self.map.player_cities(player) {
                 observations.extend(city.observe(
@@ -828,23 +1386,75 @@ impl Game {
                 ));
             }
 
+            for radar in self.map.player_radars(player) {
+                observations.extend(radar.observe(
+                    &self.map,
+                    self.turn,
+                    self.action_count,
+                    self.wrapping,
+                    obs_tracker,
+                ));
+            }
+
+            if self.fog_of_war == FogOfWar::Partial {
+                observations.extend(obs_tracker.refresh_terrain(&self.map));
+            }
+
             observations
         } else {
-            //FIXME when fog of war is disabled we shouldn't need to track observations at all
+            // All players share the same observations, which moves and the like keep up to date as they happen. Only
+            // tiles changed some other way, as by production, need tracking here.
             let mut observations: Vec<LocatedObs> = Vec::new();
             for loc in self.map.dims().iter_locs() {
                 let tile = self.map.tile(loc).unwrap();
-                observations.push(obs_tracker.track_observation(
-                    loc,
-                    tile,
-                    self.turn,
-                    self.action_count,
-                ));
+                let up_to_date = matches!(
+                    obs_tracker.get(loc),
+                    Some(Obs::Observed { tile: observed, .. }) if observed == tile
+                );
+                if !up_to_date {
+                    observations.push(obs_tracker.track_observation(
+                        loc,
+                        tile,
+                        self.turn,
+                        self.action_count,
+                    ));
+                }
             }
             observations
         }
     }
 
+    /// Clear the orders of the player's sentried units within sight of any of the `sightings` of enemies
+    ///
+    /// Having no orders, the woken units will request orders again. They're reported at the start of the player's
+    /// next turn.
+    fn wake_sentries(&mut self, player: PlayerNum, sightings: &BTreeSet<Location>) {
+        if sightings.is_empty() {
+            return;
+        }
+
+        let dims = self.dims();
+        let wake: Vec<(UnitID, Location)> = self
+            .map
+            .player_units(player)
+            .filter(|unit| unit.orders == Some(Orders::Sentry))
+            .filter_map(|unit| {
+                visible_coords_iter(unit.sight_distance())
+                    .filter_map(|inc| self.wrapping.wrapped_add(dims, unit.loc, inc))
+                    .find(|loc| sightings.contains(loc))
+                    .map(|sighted| (unit.id, sighted))
+            })
+            .collect();
+
+        for (unit_id, sighted) in wake {
+            self.map.clear_player_unit_orders(player, unit_id).unwrap();
+            self.units_woken[player].push(UnitWoken {
+                unit: self.map.unit_by_id(unit_id).cloned().unwrap(),
+                sighted,
+            });
+        }
+    }
+
     fn observable_event(&mut self, loc: Location) -> UmpireResult<LocatedObs> {
         self._observable_event(loc, false)
     }
@@ -881,10 +1491,22 @@ impl Game {
         let obs_lite = LocatedObsLite::new(loc, obs.clone());
         let mut old_obs = Obs::Unobserved;
 
+        // When all players share their observations, what was there before has to be learned before the first of them
+        // tracks the new observation
+        let universal_old_obs = if self.player_observations.is_universal() {
+            Some(
+                self.player_observations
+                    .track(self.current_player, loc, obs.clone())
+                    .unwrap(),
+            )
+        } else {
+            None
+        };
+
         for player in 0..self.num_players {
             // Make the observation available to the player if at least one of its top-level units or cities
             // can see it, or if fog of war is off, or (for the current player) force_current_player_visibility is true
-            let include = !self.fog_of_war
+            let include = self.fog_of_war == FogOfWar::Off
                 || (force_current_player_visibility && player == self.current_player)
                 || self
                     .player_active_observers_by_idx(player)?
@@ -897,15 +1519,29 @@ impl Game {
                 }
 
                 // Also keep track on our side
-                let old_obs_incoming = self
-                    .player_observations
-                    .track(player, loc, obs.clone())
-                    .unwrap();
+                let old_obs_incoming = match universal_old_obs.as_ref() {
+                    Some(universal_old_obs) => universal_old_obs.clone(),
+                    None => self
+                        .player_observations
+                        .track(player, loc, obs.clone())
+                        .unwrap(),
+                };
 
                 if player == self.current_player {
                     if let Some(old_obs_incoming) = old_obs_incoming {
                         old_obs = old_obs_incoming;
                     }
+                } else {
+                    // Other players' sentries react to what they see happen, such as an enemy moving into view
+                    let seen = LocatedObs::new(
+                        loc,
+                        obs.clone(),
+                        old_obs_incoming.unwrap_or(Obs::Unobserved),
+                    );
+                    if let Some(alert) = Alert::sighting(&seen, player) {
+                        self.wake_sentries(player, &BTreeSet::from([loc]));
+                        self.alert(player, alert);
+                    }
                 }
             }
         }
@@ -963,20 +1599,58 @@ impl Game {
         )
     }
 
-    pub fn current_player_unit_legal_directions(
-        &self,
-        unit_id: UnitID,
-    ) -> UmpireResult<impl Iterator<Item = Direction> + '_> {
-        let player_secret = self.player_secrets[self.current_player];
-        self.player_unit_legal_directions(player_secret, unit_id)
-    }
-
-    pub fn player_unit_legal_directions(
+    /// The locations the specified unit could get to with the moves it has left this turn, as far as its player
+    /// knows
+    ///
+    /// Paths run over what the player has observed, at the movement cost of each tile, and an aircraft's are limited
+    /// by its fuel too. This excludes the unit's original location.
+    pub fn player_unit_reachable_this_turn(
         &self,
         player_secret: PlayerSecret,
         unit_id: UnitID,
-    ) -> UmpireResult<impl Iterator<Item = Direction> + '_> {
-        let unit = self
+    ) -> UmpireResult<BTreeSet<Location>> {
+        let player = self.player_with_secret(player_secret)?;
+        let unit = self
+            .player_unit_by_id(player_secret, unit_id)?
+            .ok_or(GameError::NoSuchUnit { id: unit_id })?;
+
+        let range = match unit.fuel {
+            Fuel::Limited { remaining, .. } => remaining.min(unit.moves_remaining()),
+            Fuel::Unlimited => unit.moves_remaining(),
+        };
+
+        let paths = dijkstra::shortest_paths_weighted(
+            self.player_observations_by_idx(player),
+            unit.loc,
+            &UnitMovementFilter::new(unit),
+            &UnitMovementCost {
+                unit_type: unit.type_,
+            },
+            self.wrapping,
+            range,
+        );
+
+        Ok(paths
+            .dist
+            .iter_locs()
+            .filter(|loc| *loc != unit.loc)
+            .collect())
+    }
+
+    pub fn current_player_unit_legal_directions(
+        &self,
+        unit_id: UnitID,
+    ) -> UmpireResult<impl Iterator<Item = Direction> + '_> {
+        let player_secret = self.player_secrets[self.current_player];
+        self.player_unit_legal_directions(player_secret, unit_id)
+    }
+
+    pub fn player_unit_legal_directions(
+        &self,
+        player_secret: PlayerSecret,
+        unit_id: UnitID,
+    ) -> UmpireResult<impl Iterator<Item = Direction> + '_> {
+        let unit = self
             .player_unit_by_id(player_secret, unit_id)?
             .ok_or(GameError::NoSuchUnit { id: unit_id })?;
 
@@ -1002,6 +1676,14 @@ impl Game {
             }
             a.insert(NextUnitAction::Disband);
             a.insert(NextUnitAction::Skip);
+
+            if !self
+                .player_unit_bombard_targets(player_secret, unit_id)
+                .unwrap()
+                .is_empty()
+            {
+                a.insert(NextUnitAction::Bombard);
+            }
         }
 
         // Units can always skip
@@ -1066,7 +1748,7 @@ impl Game {
         self.player_observations_by_idx(player).get(loc)
     }
 
-    fn current_player_observations(&self) -> &ObsTracker {
+    fn current_player_observations(&self) -> &CompactObsTracker {
         let secret = self.player_secrets[self.current_player];
         self.player_observations(secret).unwrap()
     }
@@ -1078,24 +1760,46 @@ impl Game {
             .get(loc)
     }
 
-    pub fn player_observations(&self, player_secret: PlayerSecret) -> UmpireResult<&ObsTracker> {
+    pub fn player_observations(
+        &self,
+        player_secret: PlayerSecret,
+    ) -> UmpireResult<&CompactObsTracker> {
         self.player_with_secret(player_secret)
             .map(|player| self.player_observations_by_idx(player))
     }
 
-    fn player_observations_by_idx(&self, player: PlayerNum) -> &ObsTracker {
+    fn player_observations_by_idx(&self, player: PlayerNum) -> &CompactObsTracker {
         self.player_observations.tracker(player).unwrap()
     }
 
+    /// What a spectator sees: `viewer`'s observations, or if `None`, every tile as it is right now
+    ///
+    /// No secret is needed, so clients that control no player can watch the game.
+    pub fn spectator_observations(&self, viewer: Option<PlayerNum>) -> UmpireResult<ObsTracker> {
+        if let Some(player) = viewer {
+            return self
+                .player_observations
+                .tracker(player)
+                .map(ObsTracker::from)
+                .ok_or(GameError::NoSuchPlayer { player });
+        }
+
+        let mut observations = ObsTracker::new(self.dims());
+        for loc in self.dims().iter_locs() {
+            observations.track_observation(loc, self.map.tile(loc).unwrap(), self.turn, 0);
+        }
+        Ok(observations)
+    }
+
     pub fn player_observations_mut(
         &mut self,
         player_secret: PlayerSecret,
-    ) -> UmpireResult<&mut ObsTracker> {
+    ) -> UmpireResult<&mut CompactObsTracker> {
         self.player_with_secret(player_secret)
             .map(|player| self.player_observations_by_idx_mut(player))
     }
 
-    fn player_observations_by_idx_mut(&mut self, player: PlayerNum) -> &mut ObsTracker {
+    fn player_observations_by_idx_mut(&mut self, player: PlayerNum) -> &mut CompactObsTracker {
         self.player_observations.tracker_mut(player).unwrap()
     }
 
@@ -1495,6 +2199,19 @@ impl Game {
         player_secret: PlayerSecret,
         unit_id: UnitID,
         direction: Direction,
+    ) -> UmpireResult<Move> {
+        self.recorded(
+            player_secret,
+            PlayerAction::MoveUnitInDirection { unit_id, direction },
+            |game| game._move_unit_by_id_in_direction(player_secret, unit_id, direction),
+        )
+    }
+
+    fn _move_unit_by_id_in_direction(
+        &mut self,
+        player_secret: PlayerSecret,
+        unit_id: UnitID,
+        direction: Direction,
     ) -> UmpireResult<Move> {
         // let unit_loc = self.map.unit_by_id(id)
         // .ok_or_else(|| MoveError::SourceUnitDoesNotExist {id})?.loc;
@@ -1522,6 +2239,19 @@ impl Game {
         player_secret: PlayerSecret,
         unit_id: UnitID,
         dest: Location,
+    ) -> UmpireResult<Move> {
+        self.recorded(
+            player_secret,
+            PlayerAction::MoveUnit { unit_id, dest },
+            |game| game._move_unit_by_id(player_secret, unit_id, dest),
+        )
+    }
+
+    fn _move_unit_by_id(
+        &mut self,
+        player_secret: PlayerSecret,
+        unit_id: UnitID,
+        dest: Location,
     ) -> UmpireResult<Move> {
         // self.propose_move_unit_by_id(unit_id, dest).map(|proposed_move| proposed_move.take(self))
         // let unit = self.current_player_unit_by_id(unit_id).unwrap().clone();
@@ -1536,13 +2266,372 @@ impl Game {
         self.move_unit_by_id_using_filter(player_secret, unit_id, dest, &filter)
     }
 
+    /// Move a unit onto an adjacent friendly unit that will carry it, such as a land unit boarding a transport
+    ///
+    /// Must be player's turn
+    pub fn load_unit(
+        &mut self,
+        player_secret: PlayerSecret,
+        unit_id: UnitID,
+        carrier_id: UnitID,
+    ) -> UmpireResult<Move> {
+        self.recorded(
+            player_secret,
+            PlayerAction::LoadUnit {
+                unit_id,
+                carrier_id,
+            },
+            |game| game._load_unit(player_secret, unit_id, carrier_id),
+        )
+    }
+
+    fn _load_unit(
+        &mut self,
+        player_secret: PlayerSecret,
+        unit_id: UnitID,
+        carrier_id: UnitID,
+    ) -> UmpireResult<Move> {
+        let unit = self
+            .player_unit_by_id(player_secret, unit_id)?
+            .ok_or(GameError::MoveError(MoveError::SourceUnitDoesNotExist {
+                id: unit_id,
+            }))?
+            .clone();
+
+        let carrier = self
+            .player_unit_by_id(player_secret, carrier_id)?
+            .ok_or(GameError::NoSuchUnit { id: carrier_id })?;
+
+        carrier.carry_status(&unit)?;
+
+        let dest = carrier.loc;
+        if !self.adjacent(unit.loc, dest) {
+            return Err(GameError::NotAdjacent {
+                src: unit.loc,
+                dest,
+            });
+        }
+
+        let filter = UnitMovementFilter::new(&unit);
+        self.move_unit_by_id_using_filter(player_secret, unit_id, dest, &filter)
+    }
+
+    /// Move a carried unit off of its carrier onto an adjacent tile, such as a land unit disembarking from a transport
+    ///
+    /// Must be player's turn
+    pub fn unload_unit(
+        &mut self,
+        player_secret: PlayerSecret,
+        unit_id: UnitID,
+        dest: Location,
+    ) -> UmpireResult<Move> {
+        self.recorded(
+            player_secret,
+            PlayerAction::UnloadUnit { unit_id, dest },
+            |game| game._unload_unit(player_secret, unit_id, dest),
+        )
+    }
+
+    fn _unload_unit(
+        &mut self,
+        player_secret: PlayerSecret,
+        unit_id: UnitID,
+        dest: Location,
+    ) -> UmpireResult<Move> {
+        let unit = self
+            .player_unit_by_id(player_secret, unit_id)?
+            .ok_or(GameError::MoveError(MoveError::SourceUnitDoesNotExist {
+                id: unit_id,
+            }))?
+            .clone();
+
+        if self.map.unit_carrier_id(unit_id).is_none() {
+            return Err(GameError::UnitNotCarried { id: unit_id });
+        }
+
+        if !self.adjacent(unit.loc, dest) {
+            return Err(GameError::NotAdjacent {
+                src: unit.loc,
+                dest,
+            });
+        }
+
+        let filter = UnitMovementFilter::new(&unit);
+        self.move_unit_by_id_using_filter(player_secret, unit_id, dest, &filter)
+    }
+
+    /// Fly an aircraft to a friendly city, or to a friendly carrier with room for it, and land there
+    ///
+    /// This is how aircraft rebase, whether from a city to a carrier, between carriers, or any other way around. The
+    /// base must be within reach of the aircraft's remaining moves and fuel.
+    ///
+    /// Must be player's turn
+    pub fn rebase_unit(
+        &mut self,
+        player_secret: PlayerSecret,
+        unit_id: UnitID,
+        base: Location,
+    ) -> UmpireResult<Move> {
+        self.recorded(
+            player_secret,
+            PlayerAction::RebaseUnit { unit_id, base },
+            |game| game._rebase_unit(player_secret, unit_id, base),
+        )
+    }
+
+    fn _rebase_unit(
+        &mut self,
+        player_secret: PlayerSecret,
+        unit_id: UnitID,
+        base: Location,
+    ) -> UmpireResult<Move> {
+        let player = self.player_with_secret(player_secret)?;
+
+        let unit = self
+            .player_unit_by_id_by_idx(player, unit_id)
+            .ok_or(GameError::MoveError(MoveError::SourceUnitDoesNotExist {
+                id: unit_id,
+            }))?
+            .clone();
+
+        if unit.type_.transport_mode() != TransportMode::Air {
+            return Err(GameError::UnitCannotFly { id: unit_id });
+        }
+
+        if !self._is_air_base_for(&unit, base) {
+            return Err(GameError::NoAirBase { loc: base });
+        }
+
+        if self._flight_paths(player, &unit).dist.get(base).is_none() {
+            return Err(GameError::MoveError(MoveError::NoRoute {
+                id: unit_id,
+                src: unit.loc,
+                dest: base,
+            }));
+        }
+
+        let filter = UnitMovementFilter::new(&unit);
+        self.move_unit_by_id_using_filter(player_secret, unit_id, base, &filter)
+    }
+
+    /// Where the specified aircraft could land this turn, nearest first
+    ///
+    /// These are the friendly cities and the friendly carriers with room to spare that are within reach of the
+    /// aircraft's remaining moves and fuel.
+    pub fn player_air_bases(
+        &self,
+        player_secret: PlayerSecret,
+        unit_id: UnitID,
+    ) -> UmpireResult<Vec<Location>> {
+        let player = self.player_with_secret(player_secret)?;
+
+        let unit = self
+            .player_unit_by_id_by_idx(player, unit_id)
+            .ok_or(GameError::NoSuchUnit { id: unit_id })?;
+
+        if unit.type_.transport_mode() != TransportMode::Air {
+            return Err(GameError::UnitCannotFly { id: unit_id });
+        }
+
+        let flight_paths = self._flight_paths(player, unit);
+
+        let mut bases: Vec<Location> = flight_paths
+            .dist
+            .iter_locs()
+            .filter(|loc| *loc != unit.loc && self._is_air_base_for(unit, *loc))
+            .collect();
+        bases.sort_by_key(|loc| flight_paths.dist[*loc]);
+
+        Ok(bases)
+    }
+
+    /// Could the aircraft `unit` land at `loc`?
+    fn _is_air_base_for(&self, unit: &Unit, loc: Location) -> bool {
+        self.map.tile(loc).map_or(false, |tile| {
+            if let Some(ref other) = tile.unit {
+                other.is_friendly_to(unit) && other.can_carry_unit(unit)
+            } else {
+                tile.city
+                    .as_ref()
+                    .map_or(false, |city| city.is_friendly_to(unit))
+            }
+        })
+    }
+
+    /// The shortest flights `unit` could take over what `player` has observed, given its remaining moves and fuel
+    fn _flight_paths(&self, player: PlayerNum, unit: &Unit) -> ShortestPaths {
+        let range = match unit.fuel {
+            Fuel::Limited { remaining, .. } => remaining.min(unit.moves_remaining()),
+            Fuel::Unlimited => unit.moves_remaining(),
+        };
+
+        dijkstra::shortest_paths_weighted(
+            self.player_observations_by_idx(player),
+            unit.loc,
+            &UnitMovementFilter::new(unit),
+            &UnitMovementCost {
+                unit_type: unit.type_,
+            },
+            self.wrapping,
+            range,
+        )
+    }
+
+    /// The diplomatic status between the player and `other`
+    pub fn diplomatic_status(
+        &self,
+        player_secret: PlayerSecret,
+        other: PlayerNum,
+    ) -> UmpireResult<DiplomaticStatus> {
+        let player = self.player_with_secret(player_secret)?;
+        self.validate_player_num(other)?;
+
+        Ok(self.diplomacy.status(player, other))
+    }
+
+    /// Treaties other players have offered the player, which it may accept
+    pub fn treaty_proposals(
+        &self,
+        player_secret: PlayerSecret,
+    ) -> UmpireResult<Vec<(PlayerNum, DiplomaticStatus)>> {
+        let player = self.player_with_secret(player_secret)?;
+
+        Ok(self.diplomacy.proposals_to(player))
+    }
+
+    /// Offer `other` peace or an alliance
+    ///
+    /// The treaty takes effect only once `other` accepts it. Diplomacy can be conducted at any time, not just on the
+    /// player's turn.
+    ///
+    /// ## Errors
+    /// * GameError::NoSuchPlayer
+    /// * GameError::CannotNegotiateWithSelf
+    /// * GameError::StatusNotProposable if `status` is war, which can only be declared
+    pub fn propose_treaty(
+        &mut self,
+        player_secret: PlayerSecret,
+        other: PlayerNum,
+        status: DiplomaticStatus,
+    ) -> UmpireResult<()> {
+        self.recorded(
+            player_secret,
+            PlayerAction::ProposeTreaty { other, status },
+            |game| game._propose_treaty(player_secret, other, status),
+        )
+    }
+
+    fn _propose_treaty(
+        &mut self,
+        player_secret: PlayerSecret,
+        other: PlayerNum,
+        status: DiplomaticStatus,
+    ) -> UmpireResult<()> {
+        let player = self.validate_negotiating_partner(player_secret, other)?;
+
+        if !status.is_proposable() {
+            return Err(GameError::StatusNotProposable { status });
+        }
+
+        self.diplomacy.propose(player, other, status);
+
+        Ok(())
+    }
+
+    /// Accept the treaty `other` has offered the player
+    ///
+    /// Returns the status now in effect between the two.
+    ///
+    /// ## Errors
+    /// * GameError::NoSuchPlayer
+    /// * GameError::CannotNegotiateWithSelf
+    /// * GameError::NoTreatyProposed if `other` hasn't offered anything
+    pub fn accept_treaty(
+        &mut self,
+        player_secret: PlayerSecret,
+        other: PlayerNum,
+    ) -> UmpireResult<DiplomaticStatus> {
+        self.recorded(
+            player_secret,
+            PlayerAction::AcceptTreaty { other },
+            |game| game._accept_treaty(player_secret, other),
+        )
+    }
+
+    fn _accept_treaty(
+        &mut self,
+        player_secret: PlayerSecret,
+        other: PlayerNum,
+    ) -> UmpireResult<DiplomaticStatus> {
+        let player = self.validate_negotiating_partner(player_secret, other)?;
+
+        self.diplomacy
+            .accept(other, player)
+            .ok_or(GameError::NoTreatyProposed {
+                from: other,
+                to: player,
+            })
+    }
+
+    /// End any peace or alliance with `other`, resuming hostilities
+    ///
+    /// Returns the status that was in effect before.
+    ///
+    /// ## Errors
+    /// * GameError::NoSuchPlayer
+    /// * GameError::CannotNegotiateWithSelf
+    pub fn declare_war(
+        &mut self,
+        player_secret: PlayerSecret,
+        other: PlayerNum,
+    ) -> UmpireResult<DiplomaticStatus> {
+        self.recorded(player_secret, PlayerAction::DeclareWar { other }, |game| {
+            game._declare_war(player_secret, other)
+        })
+    }
+
+    fn _declare_war(
+        &mut self,
+        player_secret: PlayerSecret,
+        other: PlayerNum,
+    ) -> UmpireResult<DiplomaticStatus> {
+        let player = self.validate_negotiating_partner(player_secret, other)?;
+
+        Ok(self.diplomacy.declare_war(player, other))
+    }
+
+    /// Ensure that `other` is a player the player identified by `player_secret` can conduct diplomacy with
+    ///
+    /// Returns the PlayerNum of the player identified by `player_secret` on success
+    fn validate_negotiating_partner(
+        &self,
+        player_secret: PlayerSecret,
+        other: PlayerNum,
+    ) -> UmpireResult<PlayerNum> {
+        let player = self.player_with_secret(player_secret)?;
+        self.validate_player_num(other)?;
+
+        if player == other {
+            return Err(GameError::CannotNegotiateWithSelf { player });
+        }
+
+        Ok(player)
+    }
+
+    /// Whether `dest` is one step away from `src`, taking wrapping into account
+    fn adjacent(&self, src: Location, dest: Location) -> bool {
+        Direction::values()
+            .iter()
+            .any(|dir| src.shift_wrapped(*dir, self.dims(), self.wrapping) == Some(dest))
+    }
+
     pub fn propose_move_unit_by_id(
         &self,
         player_secret: PlayerSecret,
         id: UnitID,
         dest: Location,
     ) -> ProposedResult<Move, GameError> {
-        let mut new = self.clone();
+        let mut new = self.sandbox();
         let move_ = new.move_unit_by_id(player_secret, id, dest)?;
         Ok(Proposed2 {
             action: PlayerAction::MoveUnit { unit_id: id, dest },
@@ -1576,7 +2665,7 @@ impl Game {
         id: UnitID,
         dest: Location,
     ) -> ProposedResult<Move, GameError> {
-        self.clone()
+        self.sandbox()
             .move_unit_by_id_avoiding_combat(player_secret, id, dest)
             .map(|move_| Proposed2 {
                 action: PlayerAction::MoveUnit { unit_id: id, dest },
@@ -1584,19 +2673,591 @@ impl Game {
             })
     }
 
-    /// Make a best-effort attempt to move the given unit to the destination, generating shortest paths repeatedly using
-    /// the given tile filter. This is necessary because, as the unit advances, it observes tiles which may have been
-    /// previously observed but are now stale. If the tile state changes, then the shortest path will change and
-    /// potentially other behaviors like unit carrying and combat.
+    /// The odds of the specified unit prevailing should it attack `defender_loc`, as far as the player knows
     ///
-    /// *player_secret* is the secret of the player attempting to make the move; they must control the specified unit.
-    ///
-    /// Must be player's turn
-    fn move_unit_by_id_using_filter<F: Filter<Obs>>(
-        &mut self,
+    /// The defender is the enemy unit the player last observed defending `defender_loc`, or failing that the enemy
+    /// city there. An attacker must beat a city's garrison before fighting the city itself, so these odds are against the
+    /// garrison alone. Nothing is changed by asking.
+    pub fn combat_odds(
+        &self,
         player_secret: PlayerSecret,
-        unit_id: UnitID,
-        dest: Location,
+        attacker_id: UnitID,
+        defender_loc: Location,
+    ) -> UmpireResult<CombatOdds> {
+        let player = self.player_with_secret(player_secret)?;
+
+        let attacker = self
+            .player_unit_by_id_by_idx(player, attacker_id)
+            .ok_or(GameError::NoSuchUnit { id: attacker_id })?;
+
+        let tile = self
+            .player_tile_by_idx(player, defender_loc)
+            .ok_or(GameError::NoTileAtLocation { loc: defender_loc })?;
+
+        if let Some(unit) = tile.defender() {
+            if unit.is_enemy_of(attacker) {
+                return Ok(attacker.odds_with_defense_bonus(unit, tile.defense_bonus()));
+            }
+        }
+
+        if let Some(city) = tile.city.as_ref() {
+            if city.is_enemy_of(attacker) {
+                return Ok(attacker.odds_with_defense_bonus(city, city.defense_bonus()));
+            }
+        }
+
+        Err(GameError::NoEnemyAtLocation { loc: defender_loc })
+    }
+
+    /// Lay mines where the specified unit stands
+    ///
+    /// Only some units can lay mines (see `UnitType::can_lay_mines`), and not in cities. The minefield stays hidden
+    /// from other players until one of their units blunders into it. Laying mines uses up the unit's moves for the
+    /// turn.
+    ///
+    /// Returns the location mined.
+    pub fn lay_mines(
+        &mut self,
+        player_secret: PlayerSecret,
+        unit_id: UnitID,
+    ) -> UmpireResult<Location> {
+        self.recorded(player_secret, PlayerAction::LayMines { unit_id }, |game| {
+            game._lay_mines(player_secret, unit_id)
+        })
+    }
+
+    fn _lay_mines(
+        &mut self,
+        player_secret: PlayerSecret,
+        unit_id: UnitID,
+    ) -> UmpireResult<Location> {
+        let player = self.validate_is_player_turn_main_phase(player_secret)?;
+
+        let unit = self
+            .player_unit_by_id_by_idx(player, unit_id)
+            .ok_or(GameError::NoSuchUnit { id: unit_id })?;
+
+        if !unit.type_.can_lay_mines() {
+            return Err(GameError::CannotLayMines { id: unit_id });
+        }
+
+        if unit.moves_remaining() == 0 {
+            return Err(GameError::NoMovesRemaining { id: unit_id });
+        }
+
+        let loc = unit.loc;
+        if self.map.city_by_loc(loc).is_some() {
+            return Err(GameError::CannotMine { loc });
+        }
+
+        self.map.lay_minefield(loc, player)?;
+
+        self.map.mark_unit_movement_complete(unit_id).unwrap();
+
+        self.action_taken(player);
+
+        Ok(loc)
+    }
+
+    /// Clear all mines from the specified unit's location and the locations surrounding it
+    ///
+    /// Only minesweepers (see `UnitType::can_sweep_mines`) can do this. Sweeping turns up hidden minefields as well as
+    /// known ones, and uses up the unit's moves for the turn.
+    ///
+    /// Returns the locations cleared.
+    pub fn sweep_mines(
+        &mut self,
+        player_secret: PlayerSecret,
+        unit_id: UnitID,
+    ) -> UmpireResult<Vec<Location>> {
+        self.recorded(
+            player_secret,
+            PlayerAction::SweepMines { unit_id },
+            |game| game._sweep_mines(player_secret, unit_id),
+        )
+    }
+
+    fn _sweep_mines(
+        &mut self,
+        player_secret: PlayerSecret,
+        unit_id: UnitID,
+    ) -> UmpireResult<Vec<Location>> {
+        let player = self.validate_is_player_turn_main_phase(player_secret)?;
+
+        let unit = self
+            .player_unit_by_id_by_idx(player, unit_id)
+            .ok_or(GameError::NoSuchUnit { id: unit_id })?;
+
+        if !unit.type_.can_sweep_mines() {
+            return Err(GameError::CannotSweepMines { id: unit_id });
+        }
+
+        if unit.moves_remaining() == 0 {
+            return Err(GameError::NoMovesRemaining { id: unit_id });
+        }
+
+        let loc = unit.loc;
+        let swept: Vec<Location> = self
+            .map
+            .minefields()
+            .map(|(mined_loc, _)| *mined_loc)
+            .filter(|mined_loc| self._within_range(loc, *mined_loc, 1).unwrap())
+            .collect();
+
+        for mined_loc in swept.iter() {
+            self.map.clear_minefield(*mined_loc);
+        }
+
+        self.map.mark_unit_movement_complete(unit_id).unwrap();
+
+        self.action_taken(player);
+
+        Ok(swept)
+    }
+
+    /// The locations of the minefields the player knows about: their own, and any others' they've run into
+    pub fn player_known_minefields(
+        &self,
+        player_secret: PlayerSecret,
+    ) -> UmpireResult<Vec<Location>> {
+        let player = self.player_with_secret(player_secret)?;
+
+        Ok(self
+            .map
+            .minefields()
+            .filter(|(_, minefield)| minefield.known_to(player))
+            .map(|(loc, _)| *loc)
+            .collect())
+    }
+
+    /// Attack the enemy at `target` from where the unit stands, without moving
+    ///
+    /// Only units with an attack range (see `UnitType::attack_range`) can do this. Firing uses up the unit's moves
+    /// for the turn.
+    ///
+    /// Must be player's turn
+    pub fn bombard(
+        &mut self,
+        player_secret: PlayerSecret,
+        unit_id: UnitID,
+        target: Location,
+    ) -> UmpireResult<Bombardment> {
+        self.recorded(
+            player_secret,
+            PlayerAction::Bombard { unit_id, target },
+            |game| game._bombard(player_secret, unit_id, target),
+        )
+    }
+
+    fn _bombard(
+        &mut self,
+        player_secret: PlayerSecret,
+        unit_id: UnitID,
+        target: Location,
+    ) -> UmpireResult<Bombardment> {
+        let player = self.validate_is_player_turn_main_phase(player_secret)?;
+
+        let unit = self
+            .player_unit_by_id_by_idx(player, unit_id)
+            .ok_or(GameError::NoSuchUnit { id: unit_id })?
+            .clone();
+
+        let range = unit
+            .type_
+            .attack_range()
+            .ok_or(GameError::NoRangedAttack { id: unit_id })?;
+
+        if !self._within_range(unit.loc, target, range)? {
+            return Err(GameError::OutOfRange {
+                id: unit_id,
+                loc: target,
+            });
+        }
+
+        if unit.moves_remaining() == 0 {
+            return Err(GameError::NoMovesRemaining { id: unit_id });
+        }
+
+        let defender = self
+            .map
+            .tile(target)
+            .and_then(|tile| tile.defender())
+            .filter(|defender| defender.is_enemy_of(&unit))
+            .ok_or(GameError::NoEnemyAtLocation { loc: target })?
+            .clone();
+
+        // Refuse to attack players we aren't at war with
+        if let Alignment::Belligerent { player: other } = defender.alignment {
+            if !self.diplomacy.status(player, other).permits_combat() {
+                return Err(GameError::NotAtWar { player, other });
+            }
+        }
+
+        let defense_bonus = self.map.tile(target).unwrap().defense_bonus();
+
+        let combat = unit.fight_with_defense_bonus(&mut self.rng, &defender, defense_bonus);
+        self.record_battle(player, defender.alignment, combat.victorious());
+
+        if combat.victorious() {
+            self.defeated_unit_hitpoints[player] += defender.max_hp() as u64;
+
+            let destroyed = self
+                .map
+                .pop_unit_by_loc_and_id(target, defender.id)
+                .unwrap();
+            self.alert_owner(
+                defender.alignment,
+                Alert::UnitDestroyed {
+                    unit: destroyed,
+                    attacker: player,
+                },
+            );
+
+            self.map.record_unit_victory(unit_id).unwrap();
+        }
+
+        self.map.mark_unit_movement_complete(unit_id).unwrap();
+
+        self.action_taken(player);
+
+        let obs = self._observable_event(target, true).unwrap().lite();
+
+        Ok(Bombardment {
+            unit_id,
+            target,
+            combat,
+            obs,
+        })
+    }
+
+    /// Where the specified unit could bombard: the locations within its range where the player knows of an enemy
+    /// it is at war with
+    ///
+    /// Nearest targets come first. Empty for units that have no ranged attack.
+    pub fn player_unit_bombard_targets(
+        &self,
+        player_secret: PlayerSecret,
+        unit_id: UnitID,
+    ) -> UmpireResult<Vec<Location>> {
+        let player = self.player_with_secret(player_secret)?;
+
+        let unit = self
+            .player_unit_by_id_by_idx(player, unit_id)
+            .ok_or(GameError::NoSuchUnit { id: unit_id })?;
+
+        let range = match unit.type_.attack_range() {
+            Some(range) => range as i32,
+            None => return Ok(Vec::new()),
+        };
+
+        let mut targets: Vec<(i32, Location)> = Vec::new();
+        for inc_x in -range..=range {
+            for inc_y in -range..=range {
+                let loc =
+                    match self
+                        .wrapping
+                        .wrapped_add(self.dims(), unit.loc, Vec2d::new(inc_x, inc_y))
+                    {
+                        Some(loc) => loc,
+                        None => continue,
+                    };
+
+                let enemy = self
+                    .player_tile_by_idx(player, loc)
+                    .and_then(|tile| tile.defender())
+                    .filter(|defender| defender.is_enemy_of(unit));
+
+                if let Some(enemy) = enemy {
+                    let at_war = match enemy.alignment {
+                        Alignment::Belligerent { player: other } => {
+                            self.diplomacy.status(player, other).permits_combat()
+                        }
+                        _ => true,
+                    };
+
+                    // On small wrapping maps the same location can be reached by more than one offset
+                    if at_war && targets.iter().all(|(_dist, other)| *other != loc) {
+                        targets.push((inc_x.abs().max(inc_y.abs()), loc));
+                    }
+                }
+            }
+        }
+        targets.sort();
+
+        Ok(targets.into_iter().map(|(_dist, loc)| loc).collect())
+    }
+
+    /// Air-drop a unit from a friendly city onto land within its drop range
+    ///
+    /// Only units with a drop range (see `UnitType::paradrop_range`) can do this. The drop zone must be land the player
+    /// has observed, and not already held by a friendly unit. Any enemy unit or city there is fought on landing, as
+    /// though the unit had moved in. Dropping ends the unit's movement for the turn.
+    ///
+    /// Must be player's turn
+    pub fn paradrop(
+        &mut self,
+        player_secret: PlayerSecret,
+        unit_id: UnitID,
+        dest: Location,
+    ) -> UmpireResult<Move> {
+        self.recorded(
+            player_secret,
+            PlayerAction::Paradrop { unit_id, dest },
+            |game| game._paradrop(player_secret, unit_id, dest),
+        )
+    }
+
+    fn _paradrop(
+        &mut self,
+        player_secret: PlayerSecret,
+        unit_id: UnitID,
+        dest: Location,
+    ) -> UmpireResult<Move> {
+        let player = self.validate_is_player_turn_main_phase(player_secret)?;
+
+        let mut unit = self
+            .player_unit_by_id_by_idx(player, unit_id)
+            .ok_or(GameError::NoSuchUnit { id: unit_id })?
+            .clone();
+
+        let range = unit
+            .type_
+            .paradrop_range()
+            .ok_or(GameError::CannotParadrop { id: unit_id })?;
+
+        let src = unit.loc;
+
+        let in_friendly_city = self
+            .map
+            .city_by_loc(src)
+            .map_or(false, |city| city.is_friendly_to(&unit));
+        if !in_friendly_city {
+            return Err(GameError::NoAirfield { id: unit_id });
+        }
+
+        if !self._within_range(src, dest, range)? {
+            return Err(GameError::OutOfRange {
+                id: unit_id,
+                loc: dest,
+            });
+        }
+
+        if unit.moves_remaining() == 0 {
+            return Err(GameError::NoMovesRemaining { id: unit_id });
+        }
+
+        // The drop zone must be land the player knows about
+        let known_land = self
+            .player_tile_by_idx(player, dest)
+            .map_or(false, |tile| tile.terrain.is_land());
+        let friendly_occupied = self
+            .map
+            .toplevel_unit_by_loc(dest)
+            .map_or(false, |other| other.is_friendly_to(&unit));
+        if dest == src || !known_land || friendly_occupied {
+            return Err(GameError::UnsuitableDropZone { loc: dest });
+        }
+
+        // Refuse to attack players we aren't at war with
+        let defender_alignment = self
+            .map
+            .toplevel_unit_by_loc(dest)
+            .map(|other_unit| other_unit.alignment)
+            .or_else(|| self.map.city_by_loc(dest).map(|city| city.alignment));
+        if let Some(Alignment::Belligerent { player: other }) = defender_alignment {
+            if other != player && !self.diplomacy.status(player, other).permits_combat() {
+                return Err(GameError::NotAtWar { player, other });
+            }
+        }
+
+        let mut component = MoveComponent::new(src, dest);
+
+        // Fight whatever is waiting at the drop zone
+        let mut landed = true;
+        if let Some(defender) = self
+            .map
+            .tile(dest)
+            .and_then(|tile| tile.defender())
+            .cloned()
+        {
+            let defense_bonus = self.map.tile(dest).unwrap().defense_bonus();
+            component.unit_combat =
+                Some(unit.fight_with_defense_bonus(&mut self.rng, &defender, defense_bonus));
+            self.record_battle(
+                player,
+                defender.alignment,
+                component.unit_combat.as_ref().unwrap().victorious(),
+            );
+
+            if component.unit_combat.as_ref().unwrap().victorious() {
+                self.defeated_unit_hitpoints[player] += defender.max_hp() as u64;
+                let destroyed = self.map.pop_unit_by_loc_and_id(dest, defender.id).unwrap();
+                self.alert_owner(
+                    defender.alignment,
+                    Alert::UnitDestroyed {
+                        unit: destroyed,
+                        attacker: player,
+                    },
+                );
+                self.map.record_unit_victory(unit_id).unwrap();
+                unit.record_victory();
+
+                // If the rest of the enemy's stack still holds the tile, there's nowhere to land
+                if self.map.toplevel_unit_by_loc(dest).is_some() {
+                    component.loc = src;
+                    landed = false;
+                }
+            } else {
+                self.map.pop_unit_by_id(unit_id).unwrap();
+                self.map.record_unit_victory(defender.id).unwrap();
+                landed = false;
+            }
+        }
+
+        if landed {
+            match self.map.city_by_loc(dest).cloned() {
+                Some(city) if !city.is_friendly_to(&unit) => {
+                    component.city_combat = Some(unit.fight_with_defense_bonus(
+                        &mut self.rng,
+                        &city,
+                        city.defense_bonus(),
+                    ));
+
+                    let victorious = component.city_combat.as_ref().unwrap().victorious();
+                    self.record_battle(player, city.alignment, victorious);
+                    self.alert_owner(
+                        city.alignment,
+                        Alert::CityAttacked {
+                            city: city.clone(),
+                            attacker: player,
+                            captured: victorious,
+                        },
+                    );
+
+                    if victorious {
+                        self.record_city_captured(player, city.alignment);
+                        self.map.occupy_city(unit_id, dest).unwrap();
+                        self.map.record_unit_victory(unit_id).unwrap();
+                        unit.record_victory();
+                        unit.loc = dest;
+                    } else {
+                        self.map.pop_unit_by_id(unit_id).unwrap();
+                    }
+                }
+                _ => {
+                    let prior_unit = self.map.relocate_unit_by_id(unit_id, dest).unwrap();
+                    debug_assert!(prior_unit.is_none());
+                    unit.loc = dest;
+                }
+            }
+        }
+
+        // Observe the takeoff and landing from both ends
+        component.observations_after_move = vec![
+            self._observable_event(src, true).unwrap(),
+            self._observable_event(dest, true).unwrap(),
+        ];
+
+        if component.moved_successfully() {
+            let obs_tracker = self.player_observations.tracker_mut(player).unwrap();
+            component.observations_after_move.extend(unit.observe(
+                &self.map,
+                self.turn,
+                self.action_count,
+                self.wrapping,
+                obs_tracker,
+            ));
+
+            self.map.mark_unit_movement_complete(unit_id).unwrap();
+            unit.movement_complete();
+        }
+
+        self.action_taken(player);
+
+        Move::new(unit, src, vec![component]).map_err(GameError::MoveError)
+    }
+
+    /// Where the specified unit could be air-dropped this turn, nearest first
+    ///
+    /// These are the observed land tiles within the unit's drop range that aren't held by a friendly unit. Empty for
+    /// units that can't be dropped, or that aren't in a friendly city to take off from.
+    pub fn player_unit_drop_zones(
+        &self,
+        player_secret: PlayerSecret,
+        unit_id: UnitID,
+    ) -> UmpireResult<Vec<Location>> {
+        let player = self.player_with_secret(player_secret)?;
+
+        let unit = self
+            .player_unit_by_id_by_idx(player, unit_id)
+            .ok_or(GameError::NoSuchUnit { id: unit_id })?;
+
+        let range = match unit.type_.paradrop_range() {
+            Some(range) => range as i32,
+            None => return Ok(Vec::new()),
+        };
+
+        let in_friendly_city = self
+            .map
+            .city_by_loc(unit.loc)
+            .map_or(false, |city| city.is_friendly_to(unit));
+        if !in_friendly_city {
+            return Ok(Vec::new());
+        }
+
+        let mut zones: Vec<(i32, Location)> = Vec::new();
+        for inc_x in -range..=range {
+            for inc_y in -range..=range {
+                let loc =
+                    match self
+                        .wrapping
+                        .wrapped_add(self.dims(), unit.loc, Vec2d::new(inc_x, inc_y))
+                    {
+                        Some(loc) => loc,
+                        None => continue,
+                    };
+
+                let suitable = self.player_tile_by_idx(player, loc).map_or(false, |tile| {
+                    tile.terrain.is_land()
+                        && tile
+                            .unit
+                            .as_ref()
+                            .map_or(true, |other| !other.is_friendly_to(unit))
+                });
+
+                if loc != unit.loc && suitable && zones.iter().all(|(_dist, other)| *other != loc) {
+                    zones.push((inc_x.abs().max(inc_y.abs()), loc));
+                }
+            }
+        }
+        zones.sort();
+
+        Ok(zones.into_iter().map(|(_dist, loc)| loc).collect())
+    }
+
+    /// Is `target` no more than `range` steps from `loc`, counting diagonal steps as one?
+    fn _within_range(&self, loc: Location, target: Location, range: u16) -> UmpireResult<bool> {
+        let inc = self
+            .wrapping
+            .wrapped_sub(self.dims(), loc, target)
+            .map_err(|_| GameError::NoTileAtLocation { loc: target })?;
+
+        Ok(inc.x.unsigned_abs().max(inc.y.unsigned_abs()) <= range as u32)
+    }
+
+    /// Make a best-effort attempt to move the given unit to the destination, generating shortest paths repeatedly using
+    /// the given tile filter. This is necessary because, as the unit advances, it observes tiles which may have been
+    /// previously observed but are now stale. If the tile state changes, then the shortest path will change and
+    /// potentially other behaviors like unit carrying and combat.
+    ///
+    /// *player_secret* is the secret of the player attempting to make the move; they must control the specified unit.
+    ///
+    /// Must be player's turn
+    fn move_unit_by_id_using_filter<F: Filter<Obs>>(
+        &mut self,
+        player_secret: PlayerSecret,
+        unit_id: UnitID,
+        dest: Location,
         tile_filter: &F,
     ) -> UmpireResult<Move> {
         let player = self.validate_is_player_turn_main_phase(player_secret)?;
@@ -1628,31 +3289,41 @@ impl Game {
         let mut movement_complete = false;
 
         let mut shortest_paths: Option<ShortestPaths> = None;
-        let mut moves_remaining_on_last_shortest_paths_calculation = unit.moves_remaining();
+        let mut steps_since_last_shortest_paths_calculation = 0usize;
+
+        // The most movement the baseline shortest paths were searched out to
+        let mut shortest_paths_max_dist = 0u16;
+
+        let initial_moves_remaining = unit.moves_remaining();
+        let movement_cost = UnitMovementCost {
+            unit_type: unit.type_,
+        };
 
         while unit.loc != dest {
             if shortest_paths.is_none() {
                 let obs_tracker = self.player_observations.tracker_mut(player).unwrap();
                 // Establish a new "baseline"---calculation of shortest paths from the unit's current location
-                shortest_paths = Some(dijkstra::shortest_paths(
+                shortest_paths_max_dist = unit.moves_remaining();
+                shortest_paths = Some(dijkstra::shortest_paths_weighted(
                     obs_tracker,
                     unit.loc,
                     tile_filter,
+                    &movement_cost,
                     self.wrapping,
-                    unit.moves_remaining(),
+                    shortest_paths_max_dist,
                 ));
-                moves_remaining_on_last_shortest_paths_calculation = unit.moves_remaining();
+                steps_since_last_shortest_paths_calculation = 0;
             }
 
             if let Some(distance_to_dest_on_last_shortest_paths_calculation) =
                 shortest_paths.as_ref().unwrap().dist.get(dest).cloned()
             {
-                let movement_since_last_shortest_paths_calculation =
-                    moves_remaining_on_last_shortest_paths_calculation - unit.moves_remaining();
+                // The unit is somewhere along the shortest path, so its distance from the baseline is known too
+                let distance_from_baseline = shortest_paths.as_ref().unwrap().dist[unit.loc];
 
                 // Distance to destination from unit's current location
-                let distance = distance_to_dest_on_last_shortest_paths_calculation
-                    - movement_since_last_shortest_paths_calculation;
+                let distance =
+                    distance_to_dest_on_last_shortest_paths_calculation - distance_from_baseline;
 
                 // if distance == 0 {// We might be able to just assert this
                 //     return Err(MoveError::ZeroLengthMove);
@@ -1682,11 +3353,34 @@ impl Game {
                     .unwrap();
 
                 // skip the source location and steps we've taken since "baseline"
-                let loc =
-                    shortest_path[1 + movement_since_last_shortest_paths_calculation as usize];
+                let loc = shortest_path[1 + steps_since_last_shortest_paths_calculation];
+                steps_since_last_shortest_paths_calculation += 1;
 
                 let prev_loc = unit.loc;
 
+                // Refuse to attack players we aren't at war with
+                let defender_alignment = self
+                    .map
+                    .toplevel_unit_by_loc(loc)
+                    .map(|other_unit| other_unit.alignment)
+                    .or_else(|| self.map.city_by_loc(loc).map(|city| city.alignment));
+                if let Some(Alignment::Belligerent { player: other }) = defender_alignment {
+                    if other != player && !self.diplomacy.status(player, other).permits_combat() {
+                        if moves.is_empty() {
+                            return Err(GameError::NotAtWar { player, other });
+                        }
+
+                        // Stop short, ending the move where the unit already is
+                        break;
+                    }
+                }
+
+                // The movement points spent entering the next location
+                let step_cost = self
+                    .map
+                    .tile(loc)
+                    .map_or(1, |tile| unit.type_.tile_movement_cost(tile));
+
                 // Move our simulated unit along the path
                 unit.loc = loc;
 
@@ -1698,11 +3392,15 @@ impl Game {
                 //     If it has carrying capacity
                 //       Have it carry this unit
                 //     else
-                //       This doesn't happen---the search algorithm won't consider the location of there's no capacity
+                //       Stack this unit beneath it---the search algorithm won't consider the location if there's
+                //       neither carrying capacity nor room in the stack
                 //   else
-                //     It is an enemy unit.
-                //     Fight it.
+                //     It is an enemy unit, maybe one of a stack.
+                //     Fight the stack's best defender.
                 //     If victorious:
+                //       If any of the stack remains:
+                //         Stay put
+                //         END THE OVERALL MOVE
                 //       If there is a city at the destination:
                 //         It must be an enemy or there wouldn't have been an enemy unit there
                 //         If this unit can occupy cities:
@@ -1734,43 +3432,107 @@ impl Game {
                     // If it is a friendly unit:
                     if unit.is_friendly_to(other_unit) {
                         debug_assert_ne!(unit.id, other_unit.id);
-                        debug_assert!(other_unit.can_carry_unit(&unit));
 
-                        // the friendly unit must have space for us in its carrying capacity or else the
-                        // path search wouldn't have included it
-                        move_.carrier = Some(other_unit.id);
+                        // the friendly unit must have space for us in its carrying capacity or its stack, or else
+                        // the path search wouldn't have included it
+                        if other_unit.can_carry_unit(&unit) {
+                            move_.carrier = Some(other_unit.id);
 
-                        self.map
-                            .carry_unit_by_id(other_unit.id, unit_id)
-                            .expect("Could not carry unit for some weird reason");
+                            self.map
+                                .carry_unit_by_id(other_unit.id, unit_id)
+                                .expect("Could not carry unit for some weird reason");
+                        } else {
+                            self.map
+                                .stack_unit_by_id(unit_id, loc)
+                                .expect("Could not stack unit for some weird reason");
+                        }
 
-                        unit.record_movement(1).unwrap();
+                        unit.record_movement(step_cost).unwrap();
                     } else {
-                        // It is an enemy unit.
-                        // Fight it.
-                        move_.unit_combat = Some(unit.fight(&mut self.rng, other_unit));
+                        // It is an enemy unit---possibly the garrison of a neutral city, or one of a stack.
+                        // Fight whichever defends the tile best.
+                        let tile = self.map.tile(loc).unwrap();
+                        let defender = tile.defender().unwrap();
+                        let defender_alignment = defender.alignment;
+
+                        move_.unit_combat = Some(unit.fight_with_defense_bonus(
+                            &mut self.rng,
+                            defender,
+                            tile.defense_bonus(),
+                        ));
                         if move_.unit_combat.as_ref().unwrap().victorious() {
                             // We were victorious over the unit
 
                             // Record the victory for score calculation purposes
                             self.defeated_unit_hitpoints[self.current_player] +=
-                                other_unit.max_hp() as u64;
+                                defender.max_hp() as u64;
 
                             // Destroy the conquered unit
-                            self.map.pop_unit_by_loc_and_id(loc, other_unit.id).unwrap();
+                            let destroyed =
+                                self.map.pop_unit_by_loc_and_id(loc, defender.id).unwrap();
+                            self.record_battle(self.current_player, defender_alignment, true);
+                            self.alert_owner(
+                                defender_alignment,
+                                Alert::UnitDestroyed {
+                                    unit: destroyed,
+                                    attacker: self.current_player,
+                                },
+                            );
+
+                            if unit.type_.is_expendable() {
+                                // The attack used this unit up
+                                self.map.pop_unit_by_id(unit_id).unwrap();
+                                move_.expended = true;
+
+                                // END THE OVERALL MOVE
+                                break;
+                            }
+
+                            // Credit the victory toward this unit's veterancy
+                            self.map.record_unit_victory(unit_id).unwrap();
+                            unit.record_victory();
 
                             // Deal with any city
                             if let Some(city) = self.map.city_by_loc(loc) {
-                                // It must be an enemy city or there wouldn't have been an enemy unit there
+                                // It must be an enemy city or there wouldn't have been an enemy unit there. If
+                                // it's neutral, the unit we defeated was its garrison.
 
                                 // If this unit can occupy cities
                                 if unit.can_occupy_cities() {
                                     // Fight the enemy city
-                                    move_.city_combat = Some(unit.fight(&mut self.rng, city));
+                                    let city_alignment = city.alignment;
+                                    let attacked_city = city.clone();
+                                    move_.city_combat = Some(unit.fight_with_defense_bonus(
+                                        &mut self.rng,
+                                        city,
+                                        city.defense_bonus(),
+                                    ));
+
+                                    let victorious =
+                                        move_.city_combat.as_ref().unwrap().victorious();
+                                    self.record_battle(
+                                        self.current_player,
+                                        city_alignment,
+                                        victorious,
+                                    );
+                                    self.alert_owner(
+                                        city_alignment,
+                                        Alert::CityAttacked {
+                                            city: attacked_city,
+                                            attacker: self.current_player,
+                                            captured: victorious,
+                                        },
+                                    );
 
                                     // If victorious
-                                    if move_.city_combat.as_ref().unwrap().victorious() {
+                                    if victorious {
+                                        self.record_city_captured(
+                                            self.current_player,
+                                            city_alignment,
+                                        );
                                         self.map.occupy_city(unit_id, loc).unwrap();
+                                        self.map.record_unit_victory(unit_id).unwrap();
+                                        unit.record_victory();
 
                                         movement_complete = true;
                                     } else {
@@ -1789,19 +3551,33 @@ impl Game {
                                 // We either occupied an enemy city (thus ending movement), or were destroyed fighting
                                 // a city, or had to stop because this unit cannot occupy cities
                                 break;
+                            } else if self.map.toplevel_unit_by_loc(loc).is_some() {
+                                // There was no city, but the rest of the enemy's stack still holds the tile
+                                // Nerf this move since we didn't actually go anywhere and end the overall move
+                                move_.loc = prev_loc;
+                                unit.loc = prev_loc;
+
+                                // END THE OVERALL MOVE
+                                break;
                             } else {
                                 // There was no city, we just defeated an enemy, move to the destination
                                 let prior_unit =
                                     self.map.relocate_unit_by_id(unit_id, loc).unwrap();
                                 debug_assert!(prior_unit.is_none());
 
-                                unit.record_movement(1).unwrap();
+                                unit.record_movement(step_cost).unwrap();
                             }
                         } else {
                             // We were not victorious against the enemy unit
+                            let defender_id = defender.id;
+                            self.record_battle(self.current_player, defender_alignment, false);
+
                             // Destroy this unit and end the overall move
                             self.map.pop_unit_by_id(unit_id).unwrap();
 
+                            // The defender earns experience for surviving
+                            self.map.record_unit_victory(defender_id).unwrap();
+
                             break;
                         }
                     }
@@ -1811,17 +3587,37 @@ impl Game {
                         // Move this unit to the destination
                         self.map.relocate_unit_by_id(unit_id, loc).unwrap();
 
-                        unit.record_movement(1).unwrap();
+                        unit.record_movement(step_cost).unwrap();
                     } else {
                         // If the unit couldn't occupy cities then this location wouldn't be in the path, but let's
                         // check the assumption
                         debug_assert!(unit.can_occupy_cities());
 
-                        move_.city_combat = Some(unit.fight(&mut self.rng, city));
+                        let city_alignment = city.alignment;
+                        let attacked_city = city.clone();
+                        move_.city_combat = Some(unit.fight_with_defense_bonus(
+                            &mut self.rng,
+                            city,
+                            city.defense_bonus(),
+                        ));
+
+                        let victorious = move_.city_combat.as_ref().unwrap().victorious();
+                        self.record_battle(self.current_player, city_alignment, victorious);
+                        self.alert_owner(
+                            city_alignment,
+                            Alert::CityAttacked {
+                                city: attacked_city,
+                                attacker: self.current_player,
+                                captured: victorious,
+                            },
+                        );
 
                         // If victorious
-                        if move_.city_combat.as_ref().unwrap().victorious() {
+                        if victorious {
+                            self.record_city_captured(self.current_player, city_alignment);
                             self.map.occupy_city(unit_id, loc).unwrap();
+                            self.map.record_unit_victory(unit_id).unwrap();
+                            unit.record_victory();
 
                             movement_complete = true;
                         } else {
@@ -1841,7 +3637,34 @@ impl Game {
                     let prior_unit = self.map.relocate_unit_by_id(unit_id, loc).unwrap();
                     debug_assert!(prior_unit.is_none());
 
-                    unit.record_movement(1).unwrap();
+                    unit.record_movement(step_cost).unwrap();
+                }
+
+                // Set off any enemy minefield the unit has entered
+                if unit.type_.triggers_mines()
+                    && move_.carrier.is_none()
+                    && self
+                        .map
+                        .minefield(loc)
+                        .map_or(false, |minefield| minefield.owner != player)
+                {
+                    move_.struck_mine = true;
+                    self.map.reveal_minefield(loc, player);
+
+                    unit.take_damage(conf::MINE_DAMAGE);
+                    if self
+                        .map
+                        .damage_unit_by_id(unit_id, conf::MINE_DAMAGE)
+                        .unwrap()
+                    {
+                        // The blast stops the unit where it is
+                        movement_complete = true;
+                    } else {
+                        move_.destroyed_by_mine = true;
+                    }
+
+                    // END THE OVERALL MOVE
+                    break;
                 }
 
                 if let Fuel::Limited { remaining, .. } = unit.fuel {
@@ -1933,17 +3756,38 @@ impl Game {
 
                 // Inspect all observations besides at the unit's previous and current location to see if any changes in
                 // passability have occurred relevant to the unit's future moves.
-                // If so, request shortest_paths to be recalculated
-                let passability_changed = move_
+                // If so, bring shortest_paths up to date
+                let passability_changed: Vec<Location> = move_
                     .observations_after_move
                     .iter()
                     .filter(|located_obs| {
                         located_obs.loc != unit.loc && located_obs.loc != prev_loc
                     })
-                    .any(|located_obs| located_obs.passability_changed(tile_filter));
-                if passability_changed {
+                    .filter(|located_obs| located_obs.passability_changed(tile_filter))
+                    .map(|located_obs| located_obs.loc)
+                    .collect();
+                if passability_changed.len() > conf::SHORTEST_PATHS_MAX_REPAIRED_CHANGES {
                     // Mark the shortest_paths stale so it gets recomputed
                     shortest_paths = None;
+                } else if !passability_changed.is_empty() {
+                    let paths = shortest_paths.as_mut().unwrap();
+                    paths.repair(
+                        self.player_observations.tracker(player).unwrap(),
+                        &passability_changed,
+                        tile_filter,
+                        &movement_cost,
+                        self.wrapping,
+                        shortest_paths_max_dist,
+                    );
+
+                    // The rest of the move relies on the unit's path so far still being the shortest; if a better one
+                    // turned up, or the destination was cut off, start over from where the unit stands
+                    let still_on_path = paths.shortest_path(dest).map_or(false, |path| {
+                        path.get(steps_since_last_shortest_paths_calculation) == Some(&unit.loc)
+                    });
+                    if !still_on_path {
+                        shortest_paths = None;
+                    }
                 }
             } else {
                 return Err(GameError::MoveError(MoveError::NoRoute {
@@ -1980,12 +3824,9 @@ impl Game {
                 self.map.mark_unit_movement_complete(unit_id).unwrap();
                 unit.movement_complete();
             } else {
-                let distance_moved = moves
-                    .iter()
-                    .map(|move_| move_.distance_moved() as u16)
-                    .sum();
+                let movement_spent = initial_moves_remaining - unit.moves_remaining();
                 self.map
-                    .record_unit_movement(unit_id, distance_moved)
+                    .record_unit_movement(unit_id, movement_spent)
                     .unwrap();
             }
         }
@@ -1997,11 +3838,26 @@ impl Game {
 
     /// Disbands a unit
     ///
+    /// A unit disbanded inside one of the player's cities credits `conf::DISBAND_REFUND_FRACTION` of its cost toward
+    /// the city's production.
+    ///
     /// Must be main phase of player's turn
     pub fn disband_unit_by_id(
         &mut self,
         player_secret: PlayerSecret,
         unit_id: UnitID,
+    ) -> UmpireResult<UnitDisbanded> {
+        self.recorded(
+            player_secret,
+            PlayerAction::DisbandUnit { unit_id },
+            |game| game._disband_unit_by_id(player_secret, unit_id),
+        )
+    }
+
+    fn _disband_unit_by_id(
+        &mut self,
+        player_secret: PlayerSecret,
+        unit_id: UnitID,
     ) -> UmpireResult<UnitDisbanded> {
         let player = self.validate_is_player_turn_main_phase(player_secret)?;
 
@@ -2010,13 +3866,18 @@ impl Game {
             .pop_player_unit_by_id(player, unit_id)
             .ok_or(GameError::NoSuchUnit { id: unit_id })?;
 
+        let refund = (unit.type_.cost() as f64 * conf::DISBAND_REFUND_FRACTION) as u16;
+        let refund = self
+            .map
+            .credit_player_city_production(player, unit.loc, refund);
+
         // Mark the action as taken so the change shows up in the observation
         self.action_taken(player);
 
         // Let everyone in line of sight know the unit is gone
         let obs = self._observable_event(unit.loc, true).unwrap().lite();
 
-        Ok(UnitDisbanded { unit, obs })
+        Ok(UnitDisbanded { unit, obs, refund })
     }
 
     /// Sets the production of the current player's city at location `loc` to `production`, returning the prior setting.
@@ -2027,6 +3888,30 @@ impl Game {
         player_secret: PlayerSecret,
         loc: Location,
         production: UnitType,
+    ) -> UmpireResult<ProductionSet> {
+        let city_id = self
+            .player_city_by_loc(player_secret, loc)?
+            .map(|city| city.id);
+
+        if let Some(city_id) = city_id {
+            self.recorded(
+                player_secret,
+                PlayerAction::SetCityProduction {
+                    city_id,
+                    production,
+                },
+                |game| game._set_production_by_loc(player_secret, loc, production),
+            )
+        } else {
+            self._set_production_by_loc(player_secret, loc, production)
+        }
+    }
+
+    fn _set_production_by_loc(
+        &mut self,
+        player_secret: PlayerSecret,
+        loc: Location,
+        production: UnitType,
     ) -> UmpireResult<ProductionSet> {
         let player = self.player_with_secret(player_secret)?;
 
@@ -2044,10 +3929,26 @@ impl Game {
         })
     }
 
-    /// Sets the production of the current player's city with ID `city_id` to `production`.
-    ///
-    /// Returns GameError::NoCityAtLocation if no city with the given ID belongs to the current player.
-    pub fn set_production_by_id(
+    /// Sets the production of the current player's city with ID `city_id` to `production`.
+    ///
+    /// Returns GameError::NoCityAtLocation if no city with the given ID belongs to the current player.
+    pub fn set_production_by_id(
+        &mut self,
+        player_secret: PlayerSecret,
+        city_id: CityID,
+        production: UnitType,
+    ) -> UmpireResult<ProductionSet> {
+        self.recorded(
+            player_secret,
+            PlayerAction::SetCityProduction {
+                city_id,
+                production,
+            },
+            |game| game._set_production_by_id(player_secret, city_id, production),
+        )
+    }
+
+    fn _set_production_by_id(
         &mut self,
         player_secret: PlayerSecret,
         city_id: CityID,
@@ -2074,6 +3975,75 @@ impl Game {
         })
     }
 
+    /// Spend some of the accumulated production of the city with ID `city_id` on another level of walls
+    ///
+    /// Each level costs `conf::CITY_WALL_COST` of the city's production progress, up to `conf::CITY_MAX_WALLS`
+    /// levels. Walls add to the defense bonus the city lends itself and its garrison.
+    ///
+    /// Must be player's turn
+    pub fn build_city_walls(
+        &mut self,
+        player_secret: PlayerSecret,
+        city_id: CityID,
+    ) -> UmpireResult<WallsBuilt> {
+        self.recorded(
+            player_secret,
+            PlayerAction::BuildCityWalls { city_id },
+            |game| game._build_city_walls(player_secret, city_id),
+        )
+    }
+
+    fn _build_city_walls(
+        &mut self,
+        player_secret: PlayerSecret,
+        city_id: CityID,
+    ) -> UmpireResult<WallsBuilt> {
+        let player = self.validate_is_player_turn(player_secret)?;
+
+        let walls = self.map.build_player_city_walls(player, city_id)?;
+
+        self.action_taken(player);
+
+        let loc = self.map.city_by_id(city_id).unwrap().loc;
+        let obs = self.observable_event(loc).unwrap().lite();
+
+        Ok(WallsBuilt { walls, obs })
+    }
+
+    /// Spend some of the accumulated production of the city with ID `city_id` on a radar
+    ///
+    /// The radar costs `conf::CITY_RADAR_COST` of the city's production progress. It watches out to
+    /// `conf::RADAR_SIGHT_DISTANCE` for whoever holds the city, starting with their next turn.
+    ///
+    /// Must be player's turn
+    pub fn build_city_radar(
+        &mut self,
+        player_secret: PlayerSecret,
+        city_id: CityID,
+    ) -> UmpireResult<RadarBuilt> {
+        self.recorded(
+            player_secret,
+            PlayerAction::BuildCityRadar { city_id },
+            |game| game._build_city_radar(player_secret, city_id),
+        )
+    }
+
+    fn _build_city_radar(
+        &mut self,
+        player_secret: PlayerSecret,
+        city_id: CityID,
+    ) -> UmpireResult<RadarBuilt> {
+        let player = self.validate_is_player_turn(player_secret)?;
+
+        let loc = self.map.build_player_city_radar(player, city_id)?;
+
+        self.action_taken(player);
+
+        let obs = self.observable_event(loc).unwrap().lite();
+
+        Ok(RadarBuilt { obs })
+    }
+
     /// Clears the production of a city at location `loc` if one exists and is controlled by the
     /// specified player.
     ///
@@ -2083,6 +4053,22 @@ impl Game {
         player_secret: PlayerSecret,
         loc: Location,
         ignore_cleared_production: bool,
+    ) -> UmpireResult<ProductionCleared> {
+        self.recorded(
+            player_secret,
+            PlayerAction::ClearProduction {
+                loc,
+                ignore_cleared_production,
+            },
+            |game| game._clear_production(player_secret, loc, ignore_cleared_production),
+        )
+    }
+
+    fn _clear_production(
+        &mut self,
+        player_secret: PlayerSecret,
+        loc: Location,
+        ignore_cleared_production: bool,
     ) -> UmpireResult<ProductionCleared> {
         let city = self.player_city_by_loc(player_secret, loc)?;
         if city.is_none() {
@@ -2139,6 +4125,25 @@ impl Game {
         self.current_player
     }
 
+    /// The secret of the player whose turn it is
+    ///
+    /// For acting on the current player's behalf in a game one has full access to anyway, such as a sandboxed copy.
+    pub fn current_player_secret(&self) -> PlayerSecret {
+        self.player_secrets[self.current_player]
+    }
+
+    /// Whether play is held, so no new turn can begin
+    pub fn paused(&self) -> bool {
+        self.paused
+    }
+
+    /// Hold play, or let it carry on
+    ///
+    /// A turn already underway can be played out while paused; it's the next one that has to wait.
+    pub fn set_paused(&mut self, paused: bool) {
+        self.paused = paused;
+    }
+
     /// The logical dimensions of the game map
     pub fn dims(&self) -> Dims {
         self.map.dims()
@@ -2228,7 +4233,9 @@ impl Game {
         player_secret: PlayerSecret,
         unit_id: UnitID,
     ) -> UmpireResult<OrdersSet> {
-        self.set_orders(player_secret, unit_id, Orders::Skip)
+        self.recorded(player_secret, PlayerAction::SkipUnit { unit_id }, |game| {
+            game.set_orders(player_secret, unit_id, Orders::Skip)
+        })
     }
 
     pub fn order_unit_go_to(
@@ -2237,7 +4244,14 @@ impl Game {
         unit_id: UnitID,
         dest: Location,
     ) -> OrdersResult {
-        self.set_and_follow_orders(player_secret, unit_id, Orders::GoTo { dest })
+        self.set_and_follow_orders(
+            player_secret,
+            unit_id,
+            Orders::GoTo {
+                dest,
+                waypoints: Waypoints::new(),
+            },
+        )
     }
 
     /// Simulate ordering the specified unit to go to the given location
@@ -2247,7 +4261,14 @@ impl Game {
         unit_id: UnitID,
         dest: Location,
     ) -> ProposedOrdersResult {
-        self.propose_set_and_follow_orders(player_secret, unit_id, Orders::GoTo { dest })
+        self.propose_set_and_follow_orders(
+            player_secret,
+            unit_id,
+            Orders::GoTo {
+                dest,
+                waypoints: Waypoints::new(),
+            },
+        )
     }
 
     pub fn order_unit_explore(
@@ -2267,6 +4288,192 @@ impl Game {
         self.propose_set_and_follow_orders(player_secret, unit_id, Orders::Explore)
     }
 
+    /// Order the specified unit to stay alongside the unit `target`
+    pub fn order_unit_escort(
+        &mut self,
+        player_secret: PlayerSecret,
+        unit_id: UnitID,
+        target: UnitID,
+    ) -> OrdersResult {
+        self.set_and_follow_orders(player_secret, unit_id, Orders::Escort { target })
+    }
+
+    /// Simulate ordering the specified unit to escort the unit `target`
+    pub fn propose_order_unit_escort(
+        &self,
+        player_secret: PlayerSecret,
+        unit_id: UnitID,
+        target: UnitID,
+    ) -> ProposedOrdersResult {
+        self.propose_set_and_follow_orders(player_secret, unit_id, Orders::Escort { target })
+    }
+
+    /// Order the specified unit to build `improvement` where it stands
+    ///
+    /// The work takes `improvement.build_turns()` full turns of the unit's movement.
+    pub fn order_unit_build(
+        &mut self,
+        player_secret: PlayerSecret,
+        unit_id: UnitID,
+        improvement: Improvement,
+    ) -> OrdersResult {
+        let player = self.player_with_secret(player_secret)?;
+        self.check_build_site(player, unit_id, improvement)?;
+
+        self.set_and_follow_orders(
+            player_secret,
+            unit_id,
+            Orders::Build {
+                improvement,
+                turns_remaining: improvement.build_turns(),
+            },
+        )
+    }
+
+    /// Simulate ordering the specified unit to build `improvement` where it stands
+    pub fn propose_order_unit_build(
+        &self,
+        player_secret: PlayerSecret,
+        unit_id: UnitID,
+        improvement: Improvement,
+    ) -> ProposedOrdersResult {
+        let player = self.player_with_secret(player_secret)?;
+        self.check_build_site(player, unit_id, improvement)?;
+
+        self.propose_set_and_follow_orders(
+            player_secret,
+            unit_id,
+            Orders::Build {
+                improvement,
+                turns_remaining: improvement.build_turns(),
+            },
+        )
+    }
+
+    /// Make sure the player's unit can build `improvement` on its tile
+    ///
+    /// Improvements go on open land only: not in cities, and not twice on the same tile.
+    fn check_build_site(
+        &self,
+        player: PlayerNum,
+        unit_id: UnitID,
+        improvement: Improvement,
+    ) -> UmpireResult<()> {
+        let unit = self
+            .map
+            .player_unit_by_id(player, unit_id)
+            .ok_or(GameError::NoSuchUnit { id: unit_id })?;
+
+        if !unit.type_.can_build() {
+            return Err(GameError::CannotBuild { id: unit_id });
+        }
+
+        let tile = self.map.tile(unit.loc).unwrap();
+        if tile.city.is_some() || !tile.terrain.is_land() || tile.has_improvement(improvement) {
+            return Err(GameError::CannotImprove {
+                loc: unit.loc,
+                improvement,
+            });
+        }
+
+        Ok(())
+    }
+
+    /// The player's unit groups, by name
+    ///
+    /// Only members that still exist and belong to the player are included.
+    pub fn unit_groups(
+        &self,
+        player_secret: PlayerSecret,
+    ) -> UmpireResult<Vec<(String, Vec<UnitID>)>> {
+        let player = self.player_with_secret(player_secret)?;
+
+        Ok(self.unit_groups[player]
+            .iter()
+            .map(|(name, members)| (name.clone(), self.live_group_members(player, members)))
+            .collect())
+    }
+
+    /// Group the given units under `name`, replacing any group of that name
+    ///
+    /// A unit can only be in one group, so the units leave any groups they were previously in. This doesn't affect
+    /// the game itself, so it needn't be the player's turn.
+    ///
+    /// ## Errors
+    /// * GameError::NoSuchUnit if the player doesn't control one of the units
+    pub fn form_unit_group(
+        &mut self,
+        player_secret: PlayerSecret,
+        name: String,
+        unit_ids: Vec<UnitID>,
+    ) -> UmpireResult<()> {
+        let player = self.player_with_secret(player_secret)?;
+
+        if let Some(id) = unit_ids
+            .iter()
+            .find(|id| self.player_unit_by_id_by_idx(player, **id).is_none())
+        {
+            return Err(GameError::NoSuchUnit { id: *id });
+        }
+
+        self.unit_groups[player].form(name, unit_ids);
+
+        Ok(())
+    }
+
+    /// Break up the player's group called `name`, returning its members
+    ///
+    /// ## Errors
+    /// * GameError::NoSuchUnitGroup
+    pub fn disband_unit_group(
+        &mut self,
+        player_secret: PlayerSecret,
+        name: String,
+    ) -> UmpireResult<Vec<UnitID>> {
+        let player = self.player_with_secret(player_secret)?;
+
+        self.unit_groups[player]
+            .disband(&name)
+            .map(|members| self.live_group_members(player, &members))
+            .ok_or(GameError::NoSuchUnitGroup { name })
+    }
+
+    /// Give every member of the player's group called `name` the same orders, each following them individually
+    ///
+    /// The result of each member's orders is returned in member order.
+    ///
+    /// Must be player's turn
+    ///
+    /// ## Errors
+    /// * GameError::NoSuchUnitGroup
+    pub fn order_unit_group(
+        &mut self,
+        player_secret: PlayerSecret,
+        name: String,
+        orders: Orders,
+    ) -> UmpireResult<Vec<OrdersResult>> {
+        let player = self.validate_is_player_turn(player_secret)?;
+
+        let members = self.unit_groups[player]
+            .members(&name)
+            .map(|members| self.live_group_members(player, members))
+            .ok_or(GameError::NoSuchUnitGroup { name })?;
+
+        Ok(members
+            .into_iter()
+            .map(|unit_id| self.set_and_follow_orders(player_secret, unit_id, orders))
+            .collect())
+    }
+
+    /// Those of `members` that still exist and belong to `player`
+    fn live_group_members(&self, player: PlayerNum, members: &[UnitID]) -> Vec<UnitID> {
+        members
+            .iter()
+            .copied()
+            .filter(|id| self.player_unit_by_id_by_idx(player, *id).is_some())
+            .collect()
+    }
+
     /// If a unit at the location owned by the current player exists, activate it and any units it carries
     ///
     /// Returns a fresh observation of the location
@@ -2274,6 +4481,16 @@ impl Game {
         &mut self,
         player_secret: PlayerSecret,
         loc: Location,
+    ) -> UmpireResult<LocatedObsLite> {
+        self.recorded(player_secret, PlayerAction::ActivateUnit { loc }, |game| {
+            game._activate_unit_by_loc(player_secret, loc)
+        })
+    }
+
+    fn _activate_unit_by_loc(
+        &mut self,
+        player_secret: PlayerSecret,
+        loc: Location,
     ) -> UmpireResult<LocatedObsLite> {
         let player = self.validate_is_player_turn(player_secret)?;
 
@@ -2303,6 +4520,22 @@ impl Game {
         player_secret: PlayerSecret,
         id: UnitID,
         orders: Orders,
+    ) -> UmpireResult<OrdersSet> {
+        self.recorded(
+            player_secret,
+            PlayerAction::SetUnitOrders {
+                unit_id: id,
+                orders,
+            },
+            |game| game._set_orders(player_secret, id, orders),
+        )
+    }
+
+    fn _set_orders(
+        &mut self,
+        player_secret: PlayerSecret,
+        id: UnitID,
+        orders: Orders,
     ) -> UmpireResult<OrdersSet> {
         let player = self.player_with_secret(player_secret)?;
 
@@ -2324,6 +4557,18 @@ impl Game {
         &mut self,
         player_secret: PlayerSecret,
         id: UnitID,
+    ) -> UmpireResult<Option<Orders>> {
+        self.recorded(
+            player_secret,
+            PlayerAction::ClearUnitOrders { unit_id: id },
+            |game| game._clear_orders(player_secret, id),
+        )
+    }
+
+    fn _clear_orders(
+        &mut self,
+        player_secret: PlayerSecret,
+        id: UnitID,
     ) -> UmpireResult<Option<Orders>> {
         let player = self.player_with_secret(player_secret)?;
 
@@ -2363,13 +4608,16 @@ impl Game {
 
         let result = orders.carry_out(id, self, player_secret);
 
-        // If the orders are already complete, clear them out
+        // If the orders are already complete, or were cut short, clear them out
         if let Ok(OrdersOutcome {
-            status: OrdersStatus::Completed,
+            status: OrdersStatus::Completed | OrdersStatus::Interrupted,
             ..
         }) = result
         {
-            self.map.clear_player_unit_orders(player, id)?;
+            // An attacking unit may not have survived its orders
+            if self.map.player_unit_by_id(player, id).is_some() {
+                self.map.clear_player_unit_orders(player, id)?;
+            }
         }
 
         result
@@ -2382,7 +4630,7 @@ impl Game {
         id: UnitID,
         orders: Orders,
     ) -> ProposedOrdersResult {
-        self.clone()
+        self.sandbox()
             .set_and_follow_orders(player_secret, id, orders)
             .map(|orders_outcome| Proposed2 {
                 action: PlayerAction::OrderUnit {
@@ -2398,6 +4646,22 @@ impl Game {
         player_secret: PlayerSecret,
         id: UnitID,
         orders: Orders,
+    ) -> OrdersResult {
+        self.recorded(
+            player_secret,
+            PlayerAction::OrderUnit {
+                unit_id: id,
+                orders,
+            },
+            |game| game._set_and_follow_orders(player_secret, id, orders),
+        )
+    }
+
+    fn _set_and_follow_orders(
+        &mut self,
+        player_secret: PlayerSecret,
+        id: UnitID,
+        orders: Orders,
     ) -> OrdersResult {
         let player = self.validate_is_player_turn(player_secret)?;
 
@@ -2473,6 +4737,123 @@ impl Game {
             .collect()
     }
 
+    /// How the game has gone for each player so far, indexed by player number
+    pub fn player_histories(&self) -> Vec<PlayerHistory> {
+        self.histories.clone()
+    }
+
+    /// Send a chat message to all the other players
+    ///
+    /// Leading and trailing whitespace is trimmed. Chat can happen at any time, not just on the player's turn.
+    ///
+    /// ## Errors
+    /// * GameError::NoPlayerIdentifiedBySecret
+    /// * GameError::EmptyChatMessage if nothing is left after trimming
+    /// * GameError::ChatMessageTooLong
+    pub fn send_chat_message(
+        &mut self,
+        player_secret: PlayerSecret,
+        text: String,
+    ) -> UmpireResult<()> {
+        let sender = self.player_with_secret(player_secret)?;
+
+        let text = text.trim();
+        if text.is_empty() {
+            return Err(GameError::EmptyChatMessage);
+        }
+
+        let len = text.chars().count();
+        if len > MAX_CHAT_MESSAGE_LEN {
+            return Err(GameError::ChatMessageTooLong {
+                len,
+                max: MAX_CHAT_MESSAGE_LEN,
+            });
+        }
+
+        self.chat.push(ChatMessage {
+            sender,
+            sender_name: self.player_names[sender].clone(),
+            turn: self.turn,
+            text: text.to_string(),
+        });
+        self.chat_watch.send_replace(self.chat.len());
+
+        Ok(())
+    }
+
+    /// The chat messages sent after the first `since` of them, the player's own included
+    pub fn chat_messages_since(
+        &self,
+        player_secret: PlayerSecret,
+        since: usize,
+    ) -> UmpireResult<Vec<ChatMessage>> {
+        self.player_with_secret(player_secret)?;
+
+        Ok(self.chat.get(since..).unwrap_or_default().to_vec())
+    }
+
+    /// What changed for the player since `since`, or everything if `since` is `None`
+    ///
+    /// Which units and cities changed is worked out against `snapshot`, which is then brought up to date. If `since`
+    /// doesn't go with `snapshot`, all of the player's units and cities are given.
+    pub fn player_turn_delta(
+        &self,
+        player_secret: PlayerSecret,
+        since: Option<SyncPoint>,
+        snapshot: &mut TurnSnapshot,
+    ) -> UmpireResult<TurnDelta> {
+        let player = self.player_with_secret(player_secret)?;
+
+        let (revision, chat_seen) = since.map_or((0, 0), |since| (since.revision, since.chat));
+
+        Ok(snapshot.advance(
+            since,
+            self.player_observations_by_idx(player).since(revision),
+            self.player_units_by_idx(player).cloned().collect(),
+            self.player_cities_by_idx(player)?.cloned().collect(),
+            self.chat.get(chat_seen..).unwrap_or_default().to_vec(),
+            self.chat.len(),
+        ))
+    }
+
+    /// Be told how many chat messages have been sent each time another one is
+    pub fn watch_chat(&self) -> watch::Receiver<usize> {
+        self.chat_watch.subscribe()
+    }
+
+    /// Go by `name` in the chat from here on, or by player number alone if `name` is blank
+    ///
+    /// Leading and trailing whitespace is trimmed. Messages already sent keep the name they were sent under.
+    ///
+    /// ## Errors
+    /// * GameError::NoPlayerIdentifiedBySecret
+    /// * GameError::PlayerNameTooLong
+    pub fn set_player_name(
+        &mut self,
+        player_secret: PlayerSecret,
+        name: String,
+    ) -> UmpireResult<()> {
+        let player = self.player_with_secret(player_secret)?;
+
+        let name = name.trim();
+        let len = name.chars().count();
+        if len > MAX_PLAYER_NAME_LEN {
+            return Err(GameError::PlayerNameTooLong {
+                len,
+                max: MAX_PLAYER_NAME_LEN,
+            });
+        }
+
+        self.player_names[player] = Some(name.to_string()).filter(|name| !name.is_empty());
+
+        Ok(())
+    }
+
+    /// The names players go by, indexed by player number; `None` for those who haven't given one
+    pub fn player_names(&self) -> &[Option<String>] {
+        &self.player_names
+    }
+
     pub fn take_action<A: Actionable>(
         &mut self,
         player_secret: PlayerSecret,
@@ -2488,7 +4869,7 @@ impl Game {
         player_secret: PlayerSecret,
         action: PlayerAction,
     ) -> ProposedActionResult {
-        let mut game = self.clone();
+        let mut game = self.sandbox();
         let outcome = game.take_action(player_secret, action)?;
 
         Ok(Proposed2 { action, outcome })
@@ -2518,12 +4899,18 @@ impl Game {
     /// * 11: the type of unit being represented, where "city" is also a type of unit (one hot encoded)
     /// * 10: number of units controlled by current player (infantry, armor, fighters, bombers, transports, destroyers
     ///                                                     submarines, cruisers, battleships, carriers)
-    /// # 4275: 2d features, 15x15, 19 channels
+    /// # 6300: 2d features, 15x15, 28 channels (see `Obs::features`)
+    ///
+    /// The 2d features cover a window of `window` by `window` tiles, 15 above being the default, `DEEP_WIDTH`. The
+    /// window must be odd in width, to center it on the city or unit in focus.
     pub fn player_features(
         &self,
         player_secret: PlayerSecret,
         focus: TrainingFocus,
+        window: usize,
     ) -> UmpireResult<Vec<fX>> {
+        debug_assert_eq!(window % 2, 1, "Feature window must be odd in width");
+
         // For every tile we add these f64's:
         // is the tile observed or not?
         // which player controls the tile (one hot encoded)
@@ -2564,11 +4951,12 @@ impl Game {
         };
 
         // We also add a context around the currently active unit (if any)
-        let mut x: Vec<fX> = Vec::with_capacity(FEATS_LEN);
+        let mut x: Vec<fX> = Vec::with_capacity(feats_len(window));
 
         // General statistics
 
         // NOTE Update WIDE_LEN to reflect the number of generic features added here
+        // NOTE Keep umpire_ai's augment module in step with where the orientation-dependent features are found
 
         let dims = self.dims();
         let observations = self.player_observations(player_secret).unwrap();
@@ -2592,15 +4980,17 @@ impl Game {
         let player = self.player_with_secret(player_secret)?;
 
         // Init 0's for deep features
-        let mut x2d = [0.0; DEEP_IN_LEN];
+        let mut x2d = vec![0.0; deep_in_len(window)];
 
         let mut observed_cities = 0usize;
 
         // 2d features
-        for inc_x in DEEP_WIDTH_REL_MIN..=DEEP_WIDTH_REL_MAX {
-            let x_idx = (inc_x - DEEP_WIDTH_REL_MIN) as usize;
-            for inc_y in DEEP_HEIGHT_REL_MIN..=DEEP_HEIGHT_REL_MAX {
-                let y_idx = (inc_y - DEEP_HEIGHT_REL_MIN) as usize;
+        let rel_min = window as i32 / -2;
+        let rel_max = window as i32 / 2;
+        for inc_x in rel_min..=rel_max {
+            let x_idx = (inc_x - rel_min) as usize;
+            for inc_y in rel_min..=rel_max {
+                let y_idx = (inc_y - rel_min) as usize;
 
                 let inc: Vec2d<i32> = Vec2d::new(inc_x, inc_y);
 
@@ -2637,7 +5027,7 @@ impl Game {
                 // 10           1,1,1
                 // 11           1,1,2
                 for c in 0..BASE_CONV_FEATS {
-                    x2d[c * DEEP_HEIGHT * DEEP_WIDTH + y_idx * DEEP_HEIGHT + x_idx] = channels[c];
+                    x2d[c * window * window + y_idx * window + x_idx] = channels[c];
                 }
             }
         }
@@ -2675,6 +5065,10 @@ impl Game {
             } else {
                 0.0 as fX
             },
+            // - veterancy level of the active unit, if any
+            unit_id
+                .and_then(|unit_id| self.map.unit_by_id(unit_id))
+                .map_or(0.0 as fX, |unit| unit.veterancy().level() as fX),
         ];
 
         x.extend(x_1d_extra);
@@ -2682,7 +5076,7 @@ impl Game {
         x.extend(counts_vec);
         x.extend(x2d);
 
-        debug_assert_eq!(x.len(), FEATS_LEN);
+        debug_assert_eq!(x.len(), feats_len(window));
 
         Ok(x)
     }
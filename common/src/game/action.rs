@@ -9,14 +9,16 @@ use crate::util::{Direction, Location};
 use super::{
     ai::POSSIBLE_ACTIONS,
     city::CityID,
+    diplomacy::DiplomaticStatus,
     move_::Move,
-    player::PlayerTurn,
+    obs::LocatedObsLite,
+    player::{PlayerNum, PlayerTurn},
     unit::{
         orders::{Orders, OrdersOutcome},
         UnitID, UnitType,
     },
-    Game, GameError, OrdersSet, PlayerSecret, ProductionSet, TurnStart, UmpireResult,
-    UnitDisbanded,
+    Bombardment, Game, GameError, OrdersSet, PlayerSecret, ProductionCleared, ProductionSet,
+    RadarBuilt, Resigned, TurnStart, UmpireResult, UnitDisbanded, WallsBuilt,
 };
 
 /// Something that can be converted into a PlayerAction
@@ -55,15 +57,22 @@ impl AiPlayerAction {
     // UnitType::Cruiser,     7
     // UnitType::Battleship,  8
     // UnitType::Carrier      9
-    // Direction::Up,         10
-    // Direction::Down,       11
-    // Direction::Left,       12
-    // Direction::Right,      13
-    // Direction::UpLeft,     14
-    // Direction::UpRight,    15
-    // Direction::DownLeft,   16
-    // Direction::DownRight,  17
-    // SkipNextTurn           18
+    // UnitType::Artillery    10
+    // UnitType::Helicopter   11
+    // UnitType::Paratrooper  12
+    // UnitType::Missile      13
+    // UnitType::Engineer     14
+    // Direction::Up,         15
+    // Direction::Down,       16
+    // Direction::Left,       17
+    // Direction::Right,      18
+    // Direction::UpLeft,     19
+    // Direction::UpRight,    20
+    // Direction::DownLeft,   21
+    // Direction::DownRight,  22
+    // Disband                23
+    // SkipNextTurn           24
+    // Bombard                25
     pub const POSSIBLE: [Self; POSSIBLE_ACTIONS] = [
         AiPlayerAction::City(NextCityAction::SetProduction {
             unit_type: UnitType::Infantry,
@@ -95,6 +104,21 @@ impl AiPlayerAction {
         AiPlayerAction::City(NextCityAction::SetProduction {
             unit_type: UnitType::Carrier,
         }),
+        AiPlayerAction::City(NextCityAction::SetProduction {
+            unit_type: UnitType::Artillery,
+        }),
+        AiPlayerAction::City(NextCityAction::SetProduction {
+            unit_type: UnitType::Helicopter,
+        }),
+        AiPlayerAction::City(NextCityAction::SetProduction {
+            unit_type: UnitType::Paratrooper,
+        }),
+        AiPlayerAction::City(NextCityAction::SetProduction {
+            unit_type: UnitType::Missile,
+        }),
+        AiPlayerAction::City(NextCityAction::SetProduction {
+            unit_type: UnitType::Engineer,
+        }),
         AiPlayerAction::Unit(NextUnitAction::Move {
             direction: Direction::Up,
         }),
@@ -121,6 +145,7 @@ impl AiPlayerAction {
         }),
         AiPlayerAction::Unit(NextUnitAction::Disband),
         AiPlayerAction::Unit(NextUnitAction::Skip),
+        AiPlayerAction::Unit(NextUnitAction::Bombard),
     ];
 }
 
@@ -138,6 +163,7 @@ impl Display for AiPlayerAction {
                 }
                 NextUnitAction::Disband => f.write_str("D"),
                 NextUnitAction::Skip => f.write_str("S"),
+                NextUnitAction::Bombard => f.write_str("B"),
             },
         }
     }
@@ -198,6 +224,11 @@ impl Actionable for AiPlayerAction {
                     let unit_id = game.player_unit_orders_requests(secret)?.next().unwrap();
                     PlayerAction::SkipUnit { unit_id }
                 }
+                NextUnitAction::Bombard => {
+                    let unit_id = game.player_unit_orders_requests(secret)?.next().unwrap();
+                    let target = game.player_unit_bombard_targets(secret, unit_id)?[0];
+                    PlayerAction::Bombard { unit_id, target }
+                }
             },
         })
     }
@@ -298,17 +329,21 @@ impl PartialOrd for NextCityAction {
 
 #[derive(Copy, Clone, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, Deserialize, Serialize)]
 pub enum NextUnitAction {
-    Move { direction: Direction },
+    Move {
+        direction: Direction,
+    },
     Disband,
     Skip,
+    /// Fire on the nearest enemy within range
+    Bombard,
 }
 
 impl NextUnitAction {
     /// Currently possible actions
     pub async fn legal(turn: &PlayerTurn<'_>) -> Vec<Self> {
         if let Some(unit_id) = turn.player_unit_orders_requests().await.first() {
-            // disband, skip, then any move actions
-            [Self::Disband, Self::Skip]
+            // disband, skip, then any move actions, then bombardment if there's anything to fire on
+            let mut legal: Vec<Self> = [Self::Disband, Self::Skip]
                 .into_iter()
                 .chain(
                     turn.player_unit_legal_directions(*unit_id)
@@ -317,14 +352,25 @@ impl NextUnitAction {
                         .into_iter()
                         .map(|direction| Self::Move { direction }),
                 )
-                .collect()
+                .collect();
+
+            if !turn
+                .player_unit_bombard_targets(*unit_id)
+                .await
+                .unwrap()
+                .is_empty()
+            {
+                legal.push(Self::Bombard);
+            }
+
+            legal
         } else {
             Vec::new() // no legal actions because there's no next unit
         }
     }
 
     pub const fn possible() -> usize {
-        Direction::values().len() + 2
+        Direction::values().len() + 3
     }
 }
 
@@ -359,6 +405,11 @@ impl Actionable for NextUnitAction {
                     orders: Orders::Skip,
                 }
             }
+            Self::Bombard => {
+                let unit_id = game.player_unit_orders_requests(secret)?.next().unwrap();
+                let target = game.player_unit_bombard_targets(secret, unit_id)?[0];
+                PlayerAction::Bombard { unit_id, target }
+            }
         })
     }
 }
@@ -374,6 +425,7 @@ impl From<usize> for NextUnitAction {
         match idx {
             0 => Self::Disband,
             1 => Self::Skip,
+            x if x == Direction::values().len() + 2 => Self::Bombard,
             x => Self::Move {
                 direction: Direction::values()[x - 2],
             },
@@ -396,6 +448,7 @@ impl Into<usize> for NextUnitAction {
         match self {
             Self::Disband => 0,
             Self::Skip => 1,
+            Self::Bombard => Direction::values().len() + 2,
             Self::Move { direction } => {
                 Direction::values()
                     .iter()
@@ -407,16 +460,22 @@ impl Into<usize> for NextUnitAction {
     }
 }
 
-#[derive(Clone, Copy, Debug, Deserialize, Serialize)]
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq, Serialize)]
 pub enum PlayerAction {
     BeginTurn {
         clear_after_unit_production: bool,
     },
     EndTurn,
+    /// End the turn without checking that all production and orders requests were fulfilled
+    ForceEndTurn,
     SetCityProduction {
         city_id: CityID,
         production: UnitType,
     },
+    ClearProduction {
+        loc: Location,
+        ignore_cleared_production: bool,
+    },
     MoveUnit {
         unit_id: UnitID,
         dest: Location,
@@ -435,6 +494,72 @@ pub enum PlayerAction {
     SkipUnit {
         unit_id: UnitID,
     },
+    /// Set a unit's orders without following them
+    SetUnitOrders {
+        unit_id: UnitID,
+        orders: Orders,
+    },
+    ClearUnitOrders {
+        unit_id: UnitID,
+    },
+    ActivateUnit {
+        loc: Location,
+    },
+    /// Board an adjacent friendly carrier, such as a transport
+    LoadUnit {
+        unit_id: UnitID,
+        carrier_id: UnitID,
+    },
+    /// Disembark a carried unit onto an adjacent tile
+    UnloadUnit {
+        unit_id: UnitID,
+        dest: Location,
+    },
+    /// Fly an aircraft to a friendly city or carrier and land there
+    RebaseUnit {
+        unit_id: UnitID,
+        base: Location,
+    },
+    /// Attack the enemy at `target` from a distance, without moving
+    Bombard {
+        unit_id: UnitID,
+        target: Location,
+    },
+    /// Air-drop a unit from a friendly city onto land within range
+    Paradrop {
+        unit_id: UnitID,
+        dest: Location,
+    },
+    /// Spend a city's accumulated production on another level of walls
+    BuildCityWalls {
+        city_id: CityID,
+    },
+    /// Spend a city's accumulated production on a radar
+    BuildCityRadar {
+        city_id: CityID,
+    },
+    /// Lay mines where the unit stands
+    LayMines {
+        unit_id: UnitID,
+    },
+    /// Clear mines from the unit's location and its surroundings
+    SweepMines {
+        unit_id: UnitID,
+    },
+    /// Offer another player peace or an alliance
+    ProposeTreaty {
+        other: PlayerNum,
+        status: DiplomaticStatus,
+    },
+    /// Accept the treaty another player has offered
+    AcceptTreaty {
+        other: PlayerNum,
+    },
+    DeclareWar {
+        other: PlayerNum,
+    },
+    /// Withdraw from the game, giving up all cities and units
+    Resign,
 }
 
 impl Actionable for PlayerAction {
@@ -448,6 +573,8 @@ pub enum PlayerActionOutcome {
     TurnStarted(TurnStart),
     TurnEnded,
     ProductionSet(ProductionSet),
+    WallsBuilt(WallsBuilt),
+    RadarBuilt(RadarBuilt),
     MoveUnit {
         unit_id: UnitID,
         /// When moving by direction, this could be None
@@ -460,10 +587,42 @@ pub enum PlayerActionOutcome {
         orders_outcome: OrdersOutcome,
     },
     UnitDisbanded(UnitDisbanded),
+    UnitBombarded(Bombardment),
+    MinesLaid {
+        unit_id: UnitID,
+        loc: Location,
+    },
+    MinesSwept {
+        unit_id: UnitID,
+        swept: Vec<Location>,
+    },
     UnitSkipped {
         unit_id: UnitID,
         orders_outcome: OrdersSet,
     },
+    ProductionCleared(ProductionCleared),
+    UnitOrdersSet {
+        unit_id: UnitID,
+        orders: Orders,
+        orders_set: OrdersSet,
+    },
+    UnitOrdersCleared {
+        unit_id: UnitID,
+        prior_orders: Option<Orders>,
+    },
+    UnitActivated {
+        obs: LocatedObsLite,
+    },
+    TreatyProposed {
+        other: PlayerNum,
+        status: DiplomaticStatus,
+    },
+    DiplomaticStatusChanged {
+        other: PlayerNum,
+        status: DiplomaticStatus,
+        prior_status: DiplomaticStatus,
+    },
+    Resigned(Resigned),
 }
 
 impl PlayerAction {
@@ -481,12 +640,21 @@ impl PlayerAction {
             Self::EndTurn => game
                 .end_turn(player_secret)
                 .map(|_| PlayerActionOutcome::TurnEnded),
+            Self::ForceEndTurn => game
+                .force_end_turn(player_secret)
+                .map(|_| PlayerActionOutcome::TurnEnded),
             Self::SetCityProduction {
                 city_id,
                 production,
             } => game
                 .set_production_by_id(player_secret, city_id, production)
                 .map(PlayerActionOutcome::ProductionSet),
+            Self::ClearProduction {
+                loc,
+                ignore_cleared_production,
+            } => game
+                .clear_production(player_secret, loc, ignore_cleared_production)
+                .map(PlayerActionOutcome::ProductionCleared),
             Self::MoveUnit { unit_id, dest } => game
                 .move_unit_by_id(player_secret, unit_id, dest)
                 .map(|move_| PlayerActionOutcome::MoveUnit {
@@ -524,6 +692,96 @@ impl PlayerAction {
                         orders_outcome,
                     })
             }
+            Self::SetUnitOrders { unit_id, orders } => game
+                .set_orders(player_secret, unit_id, orders)
+                .map(|orders_set| PlayerActionOutcome::UnitOrdersSet {
+                    unit_id,
+                    orders,
+                    orders_set,
+                }),
+            Self::ClearUnitOrders { unit_id } => {
+                game.clear_orders(player_secret, unit_id)
+                    .map(|prior_orders| PlayerActionOutcome::UnitOrdersCleared {
+                        unit_id,
+                        prior_orders,
+                    })
+            }
+            Self::ActivateUnit { loc } => game
+                .activate_unit_by_loc(player_secret, loc)
+                .map(|obs| PlayerActionOutcome::UnitActivated { obs }),
+            Self::LoadUnit {
+                unit_id,
+                carrier_id,
+            } => game
+                .load_unit(player_secret, unit_id, carrier_id)
+                .map(|move_| PlayerActionOutcome::MoveUnit {
+                    unit_id,
+                    dest: move_.ending_loc(),
+                    move_,
+                }),
+            Self::UnloadUnit { unit_id, dest } => game
+                .unload_unit(player_secret, unit_id, dest)
+                .map(|move_| PlayerActionOutcome::MoveUnit {
+                    unit_id,
+                    dest: Some(dest),
+                    move_,
+                }),
+            Self::RebaseUnit { unit_id, base } => game
+                .rebase_unit(player_secret, unit_id, base)
+                .map(|move_| PlayerActionOutcome::MoveUnit {
+                    unit_id,
+                    dest: move_.ending_loc(),
+                    move_,
+                }),
+            Self::Bombard { unit_id, target } => game
+                .bombard(player_secret, unit_id, target)
+                .map(PlayerActionOutcome::UnitBombarded),
+            Self::Paradrop { unit_id, dest } => {
+                game.paradrop(player_secret, unit_id, dest).map(|move_| {
+                    PlayerActionOutcome::MoveUnit {
+                        unit_id,
+                        dest: Some(dest),
+                        move_,
+                    }
+                })
+            }
+            Self::BuildCityWalls { city_id } => game
+                .build_city_walls(player_secret, city_id)
+                .map(PlayerActionOutcome::WallsBuilt),
+            Self::BuildCityRadar { city_id } => game
+                .build_city_radar(player_secret, city_id)
+                .map(PlayerActionOutcome::RadarBuilt),
+            Self::LayMines { unit_id } => game
+                .lay_mines(player_secret, unit_id)
+                .map(|loc| PlayerActionOutcome::MinesLaid { unit_id, loc }),
+            Self::SweepMines { unit_id } => game
+                .sweep_mines(player_secret, unit_id)
+                .map(|swept| PlayerActionOutcome::MinesSwept { unit_id, swept }),
+            Self::ProposeTreaty { other, status } => game
+                .propose_treaty(player_secret, other, status)
+                .map(|_| PlayerActionOutcome::TreatyProposed { other, status }),
+            Self::AcceptTreaty { other } => {
+                let prior_status = game.diplomatic_status(player_secret, other)?;
+                game.accept_treaty(player_secret, other).map(|status| {
+                    PlayerActionOutcome::DiplomaticStatusChanged {
+                        other,
+                        status,
+                        prior_status,
+                    }
+                })
+            }
+            Self::DeclareWar { other } => {
+                game.declare_war(player_secret, other).map(|prior_status| {
+                    PlayerActionOutcome::DiplomaticStatusChanged {
+                        other,
+                        status: DiplomaticStatus::War,
+                        prior_status,
+                    }
+                })
+            }
+            Self::Resign => game
+                .resign(player_secret)
+                .map(PlayerActionOutcome::Resigned),
         }
     }
 }
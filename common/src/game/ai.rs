@@ -4,10 +4,11 @@ use std::{
     path::Path,
 };
 
-use burn::{
-    backend::{wgpu::WgpuDevice, Autodiff, Wgpu},
-    tensor::backend::Backend,
-};
+#[cfg(feature = "candle")]
+use burn::backend::candle::CandleDevice;
+#[cfg(feature = "tch")]
+use burn::backend::libtorch::LibTorchDevice;
+use burn::backend::{ndarray::NdArrayDevice, wgpu::WgpuDevice};
 use regex::Regex;
 use serde::{Deserialize, Serialize};
 
@@ -18,10 +19,6 @@ use super::{
     ActionNum, PlayerNum, PlayerType, TurnNum,
 };
 
-pub type AiBackend = Wgpu;
-pub type AiBackendTrain = Autodiff<AiBackend>;
-pub type AiBackendDevice = <AiBackend as Backend>::Device;
-
 #[derive(
     Clone, Copy, Debug, Default, PartialEq, Eq, Hash, Ord, PartialOrd, Serialize, Deserialize,
 )]
@@ -40,6 +37,95 @@ impl From<AiDevice> for WgpuDevice {
         }
     }
 }
+impl From<AiDevice> for NdArrayDevice {
+    fn from(_value: AiDevice) -> Self {
+        // It's the CPU or nothing
+        Self::Cpu
+    }
+}
+// LibTorch and Candle can't be asked what hardware they have to work with, so a GPU has to be asked for by number
+#[cfg(feature = "tch")]
+impl From<AiDevice> for LibTorchDevice {
+    fn from(value: AiDevice) -> Self {
+        match value {
+            AiDevice::Best | AiDevice::Cpu => Self::Cpu,
+            AiDevice::DiscreteGpu(x) => Self::Cuda(x),
+        }
+    }
+}
+#[cfg(feature = "candle")]
+impl From<AiDevice> for CandleDevice {
+    fn from(value: AiDevice) -> Self {
+        match value {
+            AiDevice::Best | AiDevice::Cpu => Self::Cpu,
+            AiDevice::DiscreteGpu(x) => Self::Cuda(x),
+        }
+    }
+}
+
+/// Which of burn's backends the AIs' neural networks run on
+#[derive(
+    Clone, Copy, Debug, Default, PartialEq, Eq, Hash, Ord, PartialOrd, Serialize, Deserialize,
+)]
+pub enum AiBackendKind {
+    /// WebGPU, on whatever graphics hardware there is
+    #[default]
+    Wgpu,
+
+    /// Pure Rust on the CPU, for machines without a usable GPU
+    NdArray,
+
+    /// LibTorch, if built with the "tch" feature
+    Tch,
+
+    /// Candle, if built with the "candle" feature
+    Candle,
+}
+
+impl AiBackendKind {
+    /// Whether support for the backend was built in
+    pub const fn available(self) -> bool {
+        match self {
+            Self::Wgpu | Self::NdArray => true,
+            Self::Tch => cfg!(feature = "tch"),
+            Self::Candle => cfg!(feature = "candle"),
+        }
+    }
+}
+
+impl TryFrom<&str> for AiBackendKind {
+    type Error = String;
+
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        let backend = match value {
+            "wgpu" => Self::Wgpu,
+            "ndarray" | "cpu" => Self::NdArray,
+            "tch" | "libtorch" => Self::Tch,
+            "candle" => Self::Candle,
+            x => return Err(format!("Unrecognized backend {}", x)),
+        };
+
+        if backend.available() {
+            Ok(backend)
+        } else {
+            Err(format!(
+                "Support for the {} backend wasn't built in; build with the \"{}\" feature",
+                backend, backend
+            ))
+        }
+    }
+}
+
+impl fmt::Display for AiBackendKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Wgpu => "wgpu",
+            Self::NdArray => "ndarray",
+            Self::Tch => "tch",
+            Self::Candle => "candle",
+        })
+    }
+}
 
 #[allow(non_camel_case_types)]
 pub type fX = f32;
@@ -48,16 +134,21 @@ pub const P_DROPOUT: f64 = 0.4;
 
 pub const POSSIBLE_CITY_ACTIONS: usize = POSSIBLE_UNIT_TYPES; // all possible productions
 
-pub const POSSIBLE_UNIT_ACTIONS: usize = POSSIBLE_DIRECTIONS + 2; // plus skip and disband
+pub const POSSIBLE_UNIT_ACTIONS: usize = POSSIBLE_DIRECTIONS + 3; // plus skip, disband, and bombard
 
 pub const POSSIBLE_ACTIONS: usize = POSSIBLE_CITY_ACTIONS + POSSIBLE_UNIT_ACTIONS;
 
-pub const ADDED_WIDE_FEATURES: usize = 13;
+pub const ADDED_WIDE_FEATURES: usize = 14;
 
 /// Number of 1d (wide) features
 /// Includes `POSSIBLE_UNIT_TYPES` twice: once for the unit type one-hot-encoded, once for the overall unit counts, plus one for city
 pub const WIDE_LEN: usize =
     POSSIBLE_UNIT_TYPES_WRIT_LARGE + POSSIBLE_UNIT_TYPES + ADDED_WIDE_FEATURES;
+
+/// The width and height of the window of tiles around the city or unit in focus that the 2d features cover, unless
+/// told otherwise
+///
+/// Windows are square, and odd in width so the focus is at the center.
 pub const DEEP_WIDTH: usize = 15;
 pub const DEEP_HEIGHT: usize = 15;
 pub const DEEP_TILES: usize = DEEP_WIDTH * DEEP_HEIGHT;
@@ -66,23 +157,40 @@ pub const DEEP_OUT_WIDTH: usize = 3;
 pub const DEEP_OUT_HEIGHT: usize = 3;
 pub const DEEP_OUT_TILES: usize = DEEP_OUT_WIDTH * DEEP_OUT_HEIGHT;
 
-pub const DEEP_WIDTH_REL_MIN: i32 = DEEP_WIDTH as i32 / -2;
-pub const DEEP_WIDTH_REL_MAX: i32 = DEEP_WIDTH as i32 / 2;
-pub const DEEP_HEIGHT_REL_MIN: i32 = DEEP_HEIGHT as i32 / -2;
-pub const DEEP_HEIGHT_REL_MAX: i32 = DEEP_HEIGHT as i32 / 2;
-
 /// Number of "channels" in convolution output
-pub const BASE_CONV_FEATS: usize = 20;
+pub const BASE_CONV_FEATS: usize = 28;
 
-pub const DEEP_IN_LEN: usize = DEEP_TILES * BASE_CONV_FEATS;
+/// Length of the 2d features over a window `window` tiles wide
+pub const fn deep_in_len(window: usize) -> usize {
+    window * window * BASE_CONV_FEATS
+}
+
+pub const DEEP_IN_LEN: usize = deep_in_len(DEEP_WIDTH);
 
 pub const PER_ACTION_CHANNELS: usize = 1;
 
 /// Total length of convolution output after reducing
 pub const DEEP_OUT_LEN: usize = DEEP_OUT_TILES * POSSIBLE_ACTIONS * PER_ACTION_CHANNELS;
 
-/// Total length of the feature vectors that are input to the dnn
-pub const FEATS_LEN: usize = WIDE_LEN + DEEP_IN_LEN;
+/// Total length of the feature vectors whose 2d features cover a window `window` tiles wide
+pub const fn feats_len(window: usize) -> usize {
+    WIDE_LEN + deep_in_len(window)
+}
+
+/// Total length of the feature vectors that are input to the dnn, with the default window
+pub const FEATS_LEN: usize = feats_len(DEEP_WIDTH);
+
+/// The version of the layout of the feature vectors and of the actions, bumped whenever features or actions are added,
+/// removed, or reordered
+///
+/// Training data is tagged with the version it was generated under, so it isn't read as though laid out otherwise.
+/// Data from before there were versions is untagged, and could be in any of the several layouts that came before, so
+/// it's rejected outright. Models are tagged with the version they were trained under likewise.
+///
+/// 1: the first version tagged. Since the original layout: veterancy among the wide features; artillery, helicopters,
+///    paratroopers, missiles, and engineers among the unit types, and so among the wide features and the productions;
+///    the bombard action; and forest, mountain, river, terrain, and city ownership channels in each tile's features
+pub const FEATURES_VERSION: u32 = 1;
 
 /// We customize the feature vector depending on if we're training a model for city actions or unit actions
 /// This just lets us specify which.
@@ -234,8 +342,15 @@ lazy_static! {
     static ref RANDOM_RGX: Regex = Regex::new(r"^r(?:and(?:om)?)?(?:(?P<seed>\d+))?$").unwrap();
     static ref RANDOM_PLUS_RGX: Regex =
         Regex::new(r"^R(?:and(?:om)?)?(?:(?P<seed>\d+))?$").unwrap();
+    static ref MCTS_RGX: Regex = Regex::new(r"^m(?P<playouts>\d+)?:(?P<model>.+)$").unwrap();
 }
 
+/// How many playouts a tree search runs before each action unless told otherwise
+pub const DEFAULT_PLAYOUTS: usize = 100;
+
+/// How many preset AI levels there are, numbered from 0
+pub const AI_LEVELS: usize = 4;
+
 /// A user specification of an AI
 ///
 /// Used as a lightweight description of an AI to be passed around. Also to validate AIs given at the command line.
@@ -250,13 +365,22 @@ pub enum AISpec {
     /// An even worse AI that only ever skips unit actions; first possible production for cities
     Skip,
 
+    /// A hand-written AI that expands to the nearest cities, garrisons its own, and takes the fights it's likely to win
+    Heuristic,
+
     /// AI loaded from a path.
     ///
     /// See the Loadable impl for `AI` for more information.
     FromPath { path: String, device: AiDevice },
 
-    /// AI loaded from a preset AI level, beginning at 1
+    /// AI loaded from a preset AI level, from 0, the weakest, up to `AI_LEVELS - 1`
+    ///
+    /// Levels 2 and 3 are level 1 strengthened by tree search, rather than models of their own.
     FromLevel { level: usize, device: AiDevice },
+
+    /// Monte Carlo Tree Search guided by a model, itself from a path or a preset level, running `playouts` playouts
+    /// before each action
+    Mcts { model: Box<AISpec>, playouts: usize },
 }
 
 impl fmt::Display for AISpec {
@@ -279,9 +403,38 @@ impl TryFrom<String> for AISpec {
             return Ok(Self::RandomPlus { seed });
         }
 
+        if let Some(m) = MCTS_RGX.captures(value.as_str()) {
+            let playouts: usize = match m.name("playouts") {
+                Some(playouts) => playouts
+                    .as_str()
+                    .parse()
+                    .map_err(|_| format!("Too many playouts for a tree search in '{}'", value))?,
+                None => DEFAULT_PLAYOUTS,
+            };
+            if playouts == 0 {
+                return Err(format!(
+                    "A tree search needs at least one playout in '{}'",
+                    value
+                ));
+            }
+
+            let model = Self::try_from(m.name("model").unwrap().as_str().to_string())?;
+            return match model {
+                Self::FromPath { .. } | Self::FromLevel { .. } => Ok(Self::Mcts {
+                    model: Box::new(model),
+                    playouts,
+                }),
+                _ => Err(format!(
+                    "A tree search needs a model to guide it, not {}, in '{}'",
+                    model, value
+                )),
+            };
+        }
+
         match value.as_str() {
             "s" => Ok(Self::Skip),
-            "0" | "1" => Ok(Self::FromLevel {
+            "H" | "heuristic" => Ok(Self::Heuristic),
+            "0" | "1" | "2" | "3" => Ok(Self::FromLevel {
                 level: value.chars().next().unwrap().to_digit(10).unwrap() as usize,
                 device: Default::default(),
             }),
@@ -323,8 +476,16 @@ impl Specified for AISpec {
                 s
             }
             Self::Skip => String::from("skip"),
+            Self::Heuristic => String::from("heuristic"),
             Self::FromPath { path, .. } => format!("AI from path {}", path),
             Self::FromLevel { level, .. } => format!("level {} AI", level),
+            Self::Mcts { model, playouts } => {
+                format!(
+                    "tree search guided by {} ({} playouts)",
+                    model.desc(),
+                    playouts
+                )
+            }
         }
     }
 
@@ -350,8 +511,10 @@ impl Specified for AISpec {
                 s
             }
             Self::Skip => String::from("s"),
+            Self::Heuristic => String::from("H"),
             Self::FromPath { path, .. } => path.clone(),
             Self::FromLevel { level, .. } => format!("{}", level),
+            Self::Mcts { model, playouts } => format!("m{}:{}", playouts, model.spec()),
         }
     }
 }
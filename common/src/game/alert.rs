@@ -0,0 +1,132 @@
+//! Urgent news for a player
+//!
+//! Alerts are raised for what happens to a player while others move---its cities attacked, its units destroyed, and
+//! enemies coming into view---and are handed over when the player's next turn begins, so a UI can make sure they
+//! aren't missed among everything else that gets logged.
+
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+use crate::util::Location;
+
+use super::{
+    city::City,
+    obs::{LocatedObs, Obs},
+    unit::Unit,
+    PlayerNum,
+};
+
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub enum Alert {
+    /// One of the player's cities was attacked, as it stood before the attack
+    CityAttacked {
+        city: City,
+        attacker: PlayerNum,
+        captured: bool,
+    },
+
+    /// One of the player's units was destroyed, as it stood before the fight
+    UnitDestroyed { unit: Unit, attacker: PlayerNum },
+
+    /// An enemy unit or city came into sight
+    ///
+    /// A unit seen more than once before the alert is handed over is reported only where it was seen last.
+    EnemySighted {
+        loc: Location,
+        unit: Option<Unit>,
+        city: Option<City>,
+    },
+}
+
+impl Alert {
+    /// Sight of whatever enemy of `player` the observation reveals, if it reveals one
+    pub(in crate::game) fn sighting(located_obs: &LocatedObs, player: PlayerNum) -> Option<Self> {
+        if !located_obs.reveals_enemy_of(player) {
+            return None;
+        }
+
+        match &located_obs.obs {
+            Obs::Observed { tile, .. } => Some(Self::EnemySighted {
+                loc: located_obs.loc,
+                unit: tile
+                    .unit
+                    .clone()
+                    .filter(|unit| unit.alignment.is_enemy_of_player(player)),
+                city: tile
+                    .city
+                    .clone()
+                    .filter(|city| city.alignment.is_enemy_of_player(player)),
+            }),
+            Obs::Unobserved => None,
+        }
+    }
+
+    /// Where it happened
+    pub fn loc(&self) -> Location {
+        match self {
+            Self::CityAttacked { city, .. } => city.loc,
+            Self::UnitDestroyed { unit, .. } => unit.loc,
+            Self::EnemySighted { loc, .. } => *loc,
+        }
+    }
+
+    /// Whether this is a later sighting of the same enemy unit as `other`
+    pub(in crate::game) fn resights(&self, other: &Self) -> bool {
+        match (self, other) {
+            (
+                Self::EnemySighted {
+                    unit: Some(unit), ..
+                },
+                Self::EnemySighted {
+                    unit: Some(other_unit),
+                    ..
+                },
+            ) => unit.id == other_unit.id,
+            _ => false,
+        }
+    }
+}
+
+impl fmt::Display for Alert {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::CityAttacked {
+                city,
+                attacker,
+                captured: true,
+            } => write!(
+                f,
+                "{} was captured by player {}",
+                city.short_desc(),
+                attacker
+            ),
+            Self::CityAttacked {
+                city,
+                attacker,
+                captured: false,
+            } => write!(
+                f,
+                "{} held off an attack by player {}",
+                city.short_desc(),
+                attacker
+            ),
+            Self::UnitDestroyed { unit, attacker } => write!(
+                f,
+                "{} was destroyed at {} by player {}",
+                unit.short_desc(),
+                unit.loc,
+                attacker
+            ),
+            Self::EnemySighted {
+                loc,
+                unit: Some(unit),
+                ..
+            } => write!(f, "Enemy {} sighted at {}", unit.short_desc(), loc),
+            Self::EnemySighted { loc, city, .. } => match city {
+                Some(city) => write!(f, "Enemy {} sighted at {}", city.short_desc(), loc),
+                None => write!(f, "Enemy sighted at {}", loc),
+            },
+        }
+    }
+}
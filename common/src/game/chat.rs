@@ -0,0 +1,27 @@
+//! Messages players send one another
+//!
+//! Chat is kept by the game itself, so everyone connected to a server sees the same conversation, and it survives
+//! saving and loading along with everything else. It isn't recorded in replays, having no bearing on play.
+
+use serde::{Deserialize, Serialize};
+
+use super::{PlayerNum, TurnNum};
+
+/// The most characters a chat message can have
+pub const MAX_CHAT_MESSAGE_LEN: usize = 280;
+
+/// The most characters a player's name can have
+pub const MAX_PLAYER_NAME_LEN: usize = 24;
+
+#[derive(Clone, Debug, Deserialize, PartialEq, Eq, Serialize)]
+pub struct ChatMessage {
+    pub sender: PlayerNum,
+
+    /// The name the sender went by when sending the message, if they'd given one
+    pub sender_name: Option<String>,
+
+    /// The turn during which the message was sent
+    pub turn: TurnNum,
+
+    pub text: String,
+}
@@ -3,11 +3,13 @@ use std::fmt;
 use serde::{Deserialize, Serialize};
 
 use crate::{
+    conf,
     game::{
         alignment::{Aligned, Alignment},
         combat::CombatCapable,
         obs::Observer,
         unit::UnitType,
+        GameError,
     },
     util::{Located, Location},
 };
@@ -42,6 +44,9 @@ pub struct City {
     pub production_progress: u16,
     name: String,
 
+    /// Levels of walls built up around the city
+    walls: u16,
+
     /// When set to true, even a unit_under_production of None will not bring this city's production menu up
     ignore_cleared_production: bool,
 }
@@ -55,6 +60,7 @@ impl City {
             production: None,
             production_progress: 0,
             name: name.into(),
+            walls: 0,
             ignore_cleared_production: false,
         }
     }
@@ -92,6 +98,42 @@ impl City {
     pub fn ignore_cleared_production(&self) -> bool {
         self.ignore_cleared_production
     }
+
+    pub fn walls(&self) -> u16 {
+        self.walls
+    }
+
+    /// Multiplier on the combat strength of the city and the units garrisoning it
+    pub fn defense_bonus(&self) -> f64 {
+        conf::CITY_DEFENSE_BONUS + self.walls as f64 * conf::CITY_WALL_DEFENSE_BONUS
+    }
+
+    /// Spend `conf::CITY_WALL_COST` of the city's production progress on another level of walls
+    ///
+    /// Returns the new number of levels.
+    pub(in crate::game) fn build_walls(&mut self) -> Result<u16, GameError> {
+        if self.walls >= conf::CITY_MAX_WALLS {
+            return Err(GameError::CityWallsComplete { id: self.id });
+        }
+
+        self.spend_production(conf::CITY_WALL_COST)?;
+        self.walls += 1;
+        Ok(self.walls)
+    }
+
+    /// Take `cost` out of the city's production progress, to be spent on something other than a unit
+    pub(in crate::game) fn spend_production(&mut self, cost: u16) -> Result<(), GameError> {
+        if self.production_progress < cost {
+            return Err(GameError::InsufficientProduction {
+                id: self.id,
+                needed: cost,
+                available: self.production_progress,
+            });
+        }
+
+        self.production_progress -= cost;
+        Ok(())
+    }
 }
 
 impl CombatCapable for City {
@@ -115,6 +157,9 @@ impl fmt::Display for City {
                 produced_unit.cost()
             ));
         }
+        if self.walls > 0 {
+            result = result.and(write!(f, ", walls {}", self.walls));
+        }
         result
     }
 }
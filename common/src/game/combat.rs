@@ -57,15 +57,116 @@ impl<A: CombatCapable, D: CombatCapable> CombatOutcome<A, D> {
     }
 }
 
+/// How a fight between two combatants is likely to go, worked out without fighting it
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct CombatOdds {
+    /// Probability that the attacker is victorious
+    pub victory_prob: f64,
+
+    /// `attacker_damage[n]` is the probability that the attacker loses `n` hitpoints
+    pub attacker_damage: Vec<f64>,
+
+    /// `defender_damage[n]` is the probability that the defender loses `n` hitpoints
+    pub defender_damage: Vec<f64>,
+}
+
+impl CombatOdds {
+    /// Hitpoints the attacker can expect to lose
+    pub fn expected_attacker_damage(&self) -> f64 {
+        expectation(&self.attacker_damage)
+    }
+
+    /// Hitpoints the defender can expect to lose
+    pub fn expected_defender_damage(&self) -> f64 {
+        expectation(&self.defender_damage)
+    }
+}
+
+fn expectation(dist: &[f64]) -> f64 {
+    dist.iter().enumerate().map(|(n, p)| n as f64 * p).sum()
+}
+
+/// Probability that any one blow lands on the attacker rather than the defender
+fn attacker_damage_prob(attacker_strength: f64, defender_strength: f64) -> f64 {
+    defender_strength / (attacker_strength + defender_strength)
+}
+
 pub trait CombatCapable {
     fn hp(&self) -> u16;
     fn max_hp(&self) -> u16;
 
+    /// Relative likelihood of landing each blow in combat
+    fn strength(&self) -> f64 {
+        1.0
+    }
+
+    /// The odds of each outcome were this to attack `defender`
+    ///
+    /// Each blow is independent, so this is exact rather than sampled.
+    fn odds<D: CombatCapable>(&self, defender: &D) -> CombatOdds {
+        self.odds_with_defense_bonus(defender, 1.0)
+    }
+
+    /// The odds of each outcome were this to attack `defender`, whose strength is multiplied by `defense_bonus`
+    fn odds_with_defense_bonus<D: CombatCapable>(
+        &self,
+        defender: &D,
+        defense_bonus: f64,
+    ) -> CombatOdds {
+        let attacker_hp = self.hp() as usize;
+        let defender_hp = defender.hp() as usize;
+
+        let p = attacker_damage_prob(self.strength(), defender.strength() * defense_bonus);
+
+        // reach[a][d]: probability the fight ever stands with the attacker down `a` hitpoints and the defender `d`
+        let mut reach = vec![vec![0.0; defender_hp + 1]; attacker_hp + 1];
+        reach[0][0] = 1.0;
+
+        let mut odds = CombatOdds {
+            victory_prob: 0.0,
+            attacker_damage: vec![0.0; attacker_hp + 1],
+            defender_damage: vec![0.0; defender_hp + 1],
+        };
+
+        for a in 0..=attacker_hp {
+            for d in 0..=defender_hp {
+                let prob = reach[a][d];
+
+                if a == attacker_hp || d == defender_hp {
+                    // The fight ends here
+                    odds.attacker_damage[a] += prob;
+                    odds.defender_damage[d] += prob;
+                    if a < attacker_hp {
+                        odds.victory_prob += prob;
+                    }
+                } else {
+                    reach[a + 1][d] += prob * p;
+                    reach[a][d + 1] += prob * (1.0 - p);
+                }
+            }
+        }
+
+        odds
+    }
+
     fn fight<D: CombatCapable + Clone, R: RngCore>(
         &self,
         rng: &mut R,
         defender: &D,
     ) -> CombatOutcome<Self, D>
+    where
+        Self: Clone + Sized,
+    {
+        self.fight_with_defense_bonus(rng, defender, 1.0)
+    }
+
+    /// Fight `defender`, whose strength is multiplied by `defense_bonus`, as when it holds a fortified position
+    fn fight_with_defense_bonus<D: CombatCapable + Clone, R: RngCore>(
+        &self,
+        rng: &mut R,
+        defender: &D,
+        defense_bonus: f64,
+    ) -> CombatOutcome<Self, D>
     where
         Self: Clone + Sized,
     {
@@ -77,8 +178,11 @@ pub trait CombatCapable {
         let mut attacker_hp = attacker_initial_hp;
         let mut defender_hp = defender_initial_hp;
 
+        let attacker_damage_prob =
+            attacker_damage_prob(self.strength(), defender.strength() * defense_bonus);
+
         while attacker_hp > 0 && defender_hp > 0 {
-            let attacker_received_damage = rng.gen::<bool>();
+            let attacker_received_damage = rng.gen_bool(attacker_damage_prob);
             if attacker_received_damage {
                 damage_received.push(CombatParticipant::Attacker);
                 attacker_hp -= 1;
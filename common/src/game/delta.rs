@@ -0,0 +1,297 @@
+//! What changed for a player between one sync and the next
+//!
+//! Rather than fetch all of a player's observations, units, and cities each turn, a remote client fetches a
+//! `TurnDelta` carrying only what changed since the state it last acknowledged. The server keeps a `TurnSnapshot` of
+//! what it last sent, to tell what's changed since; a client whose sync point doesn't match the snapshot, as after
+//! reconnecting, is sent everything afresh.
+
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+
+use super::{
+    chat::ChatMessage,
+    city::{City, CityID},
+    obs::{ObsDiff, ObsRevision},
+    unit::{Unit, UnitID},
+};
+
+/// The state a player's copy was last brought up to; ask for the changes since it next time
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq, Eq, Serialize)]
+pub struct SyncPoint {
+    /// Identifies the snapshot of units and cities the copy was synced with; never 0
+    pub snapshot: u64,
+
+    /// The revision of the player's observations the copy was synced with
+    pub revision: ObsRevision,
+
+    /// How many chat messages the copy has seen
+    pub chat: usize,
+}
+
+impl SyncPoint {
+    /// For a copy that has observations up to `revision` but no units, cities, or chat yet
+    pub fn unsnapshotted(revision: ObsRevision) -> Self {
+        Self {
+            snapshot: 0,
+            revision,
+            chat: 0,
+        }
+    }
+}
+
+/// The changes to a player's state since a `SyncPoint`
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct TurnDelta {
+    /// The sync point the changes bring a copy up to
+    pub sync: SyncPoint,
+
+    /// Whether `units` and `cities` are the player's complete collections, replacing rather than updating a copy
+    pub complete: bool,
+
+    pub observations: ObsDiff,
+
+    /// Units that are new or changed
+    pub units: Vec<Unit>,
+
+    /// Units lost, disbanded, or otherwise gone
+    pub units_gone: Vec<UnitID>,
+
+    /// Cities that are new or changed
+    pub cities: Vec<City>,
+
+    /// Cities lost to other players
+    pub cities_gone: Vec<CityID>,
+
+    /// Chat messages sent since
+    pub chat: Vec<ChatMessage>,
+}
+
+impl TurnDelta {
+    /// Everything the player has, to replace any copy with
+    pub fn complete(
+        snapshot: u64,
+        observations: ObsDiff,
+        units: Vec<Unit>,
+        cities: Vec<City>,
+        chat: Vec<ChatMessage>,
+        chat_len: usize,
+    ) -> Self {
+        Self {
+            sync: SyncPoint {
+                snapshot,
+                revision: observations.revision,
+                chat: chat_len,
+            },
+            complete: true,
+            observations,
+            units,
+            units_gone: Vec::new(),
+            cities,
+            cities_gone: Vec::new(),
+            chat,
+        }
+    }
+}
+
+/// A player's units and cities as last sent, for working out what changed since
+#[derive(Clone, Debug, Default)]
+pub struct TurnSnapshot {
+    id: u64,
+    units: BTreeMap<UnitID, Unit>,
+    cities: BTreeMap<CityID, City>,
+}
+
+impl TurnSnapshot {
+    /// The changes from this snapshot to `units` and `cities`, after which the snapshot is of them
+    ///
+    /// Only if `since` was taken from this snapshot are just the changes given; otherwise everything is.
+    ///
+    /// The observations and chat messages are expected to already be those since `since`, or all of them if `since`
+    /// is `None`.
+    pub fn advance(
+        &mut self,
+        since: Option<SyncPoint>,
+        observations: ObsDiff,
+        units: Vec<Unit>,
+        cities: Vec<City>,
+        chat: Vec<ChatMessage>,
+        chat_len: usize,
+    ) -> TurnDelta {
+        let complete = self.id == 0 || since.map_or(true, |since| since.snapshot != self.id);
+
+        let units: BTreeMap<UnitID, Unit> = units.into_iter().map(|unit| (unit.id, unit)).collect();
+        let cities: BTreeMap<CityID, City> =
+            cities.into_iter().map(|city| (city.id, city)).collect();
+
+        let prior_units = std::mem::replace(&mut self.units, units);
+        let prior_cities = std::mem::replace(&mut self.cities, cities);
+        // Zero is left for sync points that were never snapshotted
+        self.id = rand::random::<u64>().max(1);
+
+        if complete {
+            return TurnDelta::complete(
+                self.id,
+                observations,
+                self.units.values().cloned().collect(),
+                self.cities.values().cloned().collect(),
+                chat,
+                chat_len,
+            );
+        }
+
+        TurnDelta {
+            sync: SyncPoint {
+                snapshot: self.id,
+                revision: observations.revision,
+                chat: chat_len,
+            },
+            complete: false,
+            observations,
+            units: changed(&prior_units, &self.units),
+            units_gone: gone(&prior_units, &self.units),
+            cities: changed(&prior_cities, &self.cities),
+            cities_gone: gone(&prior_cities, &self.cities),
+            chat,
+        }
+    }
+}
+
+fn changed<K: Ord, V: Clone + PartialEq>(prior: &BTreeMap<K, V>, now: &BTreeMap<K, V>) -> Vec<V> {
+    now.iter()
+        .filter(|(k, v)| prior.get(k) != Some(v))
+        .map(|(_, v)| v.clone())
+        .collect()
+}
+
+fn gone<K: Copy + Ord, V>(prior: &BTreeMap<K, V>, now: &BTreeMap<K, V>) -> Vec<K> {
+    prior
+        .keys()
+        .filter(|k| !now.contains_key(k))
+        .copied()
+        .collect()
+}
+
+/// A copy of a player's units and cities, kept up to date by applying deltas
+#[derive(Clone, Debug, Default)]
+pub struct SyncedState {
+    pub units: BTreeMap<UnitID, Unit>,
+    pub cities: BTreeMap<CityID, City>,
+}
+
+impl SyncedState {
+    /// Bring the copy up to date with `delta`
+    ///
+    /// The delta's observations and chat messages are left to the caller.
+    pub fn apply(&mut self, delta: &TurnDelta) {
+        if delta.complete {
+            self.units.clear();
+            self.cities.clear();
+        }
+
+        for id in &delta.units_gone {
+            self.units.remove(id);
+        }
+        for id in &delta.cities_gone {
+            self.cities.remove(id);
+        }
+
+        self.units
+            .extend(delta.units.iter().map(|unit| (unit.id, unit.clone())));
+        self.cities
+            .extend(delta.cities.iter().map(|city| (city.id, city.clone())));
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        game::{
+            obs::ObsDiff,
+            unit::{Unit, UnitID, UnitType},
+            Alignment,
+        },
+        util::Location,
+    };
+
+    use super::{SyncedState, TurnSnapshot};
+
+    fn no_obs() -> ObsDiff {
+        ObsDiff {
+            revision: 0,
+            archived: false,
+            observations: Vec::new(),
+        }
+    }
+
+    fn unit(id: u64, x: u16) -> Unit {
+        Unit::new(
+            UnitID::new(id),
+            Location::new(x, 0),
+            UnitType::Infantry,
+            Alignment::Belligerent { player: 0 },
+            "Unit",
+        )
+    }
+
+    #[test]
+    fn test_advance() {
+        let mut snapshot = TurnSnapshot::default();
+        let mut synced = SyncedState::default();
+
+        let delta = snapshot.advance(
+            None,
+            no_obs(),
+            vec![unit(0, 0), unit(1, 0)],
+            Vec::new(),
+            Vec::new(),
+            0,
+        );
+        assert!(delta.complete);
+        assert_eq!(delta.units.len(), 2);
+        synced.apply(&delta);
+
+        // One unit moves and the other is lost; only that comes across
+        let delta = snapshot.advance(
+            Some(delta.sync),
+            no_obs(),
+            vec![unit(0, 1)],
+            Vec::new(),
+            Vec::new(),
+            0,
+        );
+        assert!(!delta.complete);
+        assert_eq!(delta.units, vec![unit(0, 1)]);
+        assert_eq!(delta.units_gone, vec![UnitID::new(1)]);
+        synced.apply(&delta);
+        assert_eq!(
+            synced.units.values().cloned().collect::<Vec<_>>(),
+            vec![unit(0, 1)]
+        );
+
+        // Nothing changed
+        let sync = delta.sync;
+        let delta = snapshot.advance(
+            Some(sync),
+            no_obs(),
+            vec![unit(0, 1)],
+            Vec::new(),
+            Vec::new(),
+            0,
+        );
+        assert!(delta.units.is_empty());
+        assert!(delta.units_gone.is_empty());
+
+        // A sync point from an older snapshot gets everything
+        let delta = snapshot.advance(
+            Some(sync),
+            no_obs(),
+            vec![unit(0, 1)],
+            Vec::new(),
+            Vec::new(),
+            0,
+        );
+        assert!(delta.complete);
+        assert_eq!(delta.units, vec![unit(0, 1)]);
+    }
+}
@@ -0,0 +1,145 @@
+//! Relations between players
+//!
+//! Every pair of players is at war, at peace, or allied. Players begin the game at war with everyone. Peace or an
+//! alliance comes about when one player proposes it and the other accepts; either party can return the pair to war
+//! unilaterally at any time. Units of players who aren't at war can't attack each other.
+
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+use super::PlayerNum;
+
+#[derive(Clone, Copy, Debug, Deserialize, Eq, Hash, PartialEq, Serialize)]
+pub enum DiplomaticStatus {
+    War,
+    Peace,
+    Alliance,
+}
+
+impl DiplomaticStatus {
+    /// Whether units of players with this status may attack each other
+    pub fn permits_combat(self) -> bool {
+        self == Self::War
+    }
+
+    /// Whether this is something a player can propose to another
+    ///
+    /// War needs no agreement, so it can't be proposed, only declared.
+    pub fn is_proposable(self) -> bool {
+        self != Self::War
+    }
+}
+
+impl fmt::Display for DiplomaticStatus {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::War => write!(f, "War"),
+            Self::Peace => write!(f, "Peace"),
+            Self::Alliance => write!(f, "Alliance"),
+        }
+    }
+}
+
+/// The diplomatic status between each pair of players, and the treaties proposed but not yet accepted
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct Diplomacy {
+    /// Symmetric; `statuses[a][b] == statuses[b][a]`
+    statuses: Vec<Vec<DiplomaticStatus>>,
+
+    /// `proposals[from][to]` is the status `from` has offered `to`, if any
+    proposals: Vec<Vec<Option<DiplomaticStatus>>>,
+}
+
+impl Diplomacy {
+    /// Every player at war with every other
+    pub fn new(num_players: PlayerNum) -> Self {
+        Self {
+            statuses: vec![vec![DiplomaticStatus::War; num_players]; num_players],
+            proposals: vec![vec![None; num_players]; num_players],
+        }
+    }
+
+    pub fn status(&self, a: PlayerNum, b: PlayerNum) -> DiplomaticStatus {
+        self.statuses[a][b]
+    }
+
+    /// Record `from`'s offer of `status` to `to`, replacing any earlier offer between them
+    pub fn propose(&mut self, from: PlayerNum, to: PlayerNum, status: DiplomaticStatus) {
+        self.proposals[from][to] = Some(status);
+    }
+
+    /// The status `from` has offered `to`, if any
+    pub fn proposal(&self, from: PlayerNum, to: PlayerNum) -> Option<DiplomaticStatus> {
+        self.proposals[from][to]
+    }
+
+    /// Treaties offered to `to` and awaiting its response, by proposing player
+    pub fn proposals_to(&self, to: PlayerNum) -> Vec<(PlayerNum, DiplomaticStatus)> {
+        self.proposals
+            .iter()
+            .enumerate()
+            .filter_map(|(from, row)| row[to].map(|status| (from, status)))
+            .collect()
+    }
+
+    /// `to` accepts the treaty offered by `from`, which takes effect immediately
+    ///
+    /// Returns the new status, or `None` if nothing was offered.
+    pub fn accept(&mut self, from: PlayerNum, to: PlayerNum) -> Option<DiplomaticStatus> {
+        let status = self.proposals[from][to].take()?;
+        self.set_status(from, to, status);
+        Some(status)
+    }
+
+    /// Return `a` and `b` to war, withdrawing any offers between them
+    ///
+    /// Returns the status that was in effect before.
+    pub fn declare_war(&mut self, a: PlayerNum, b: PlayerNum) -> DiplomaticStatus {
+        let prior = self.status(a, b);
+        self.set_status(a, b, DiplomaticStatus::War);
+        prior
+    }
+
+    fn set_status(&mut self, a: PlayerNum, b: PlayerNum, status: DiplomaticStatus) {
+        self.statuses[a][b] = status;
+        self.statuses[b][a] = status;
+        self.proposals[a][b] = None;
+        self.proposals[b][a] = None;
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{Diplomacy, DiplomaticStatus};
+
+    #[test]
+    fn test_diplomacy() {
+        let mut diplomacy = Diplomacy::new(3);
+
+        assert_eq!(diplomacy.status(0, 1), DiplomaticStatus::War);
+        assert!(diplomacy.proposals_to(1).is_empty());
+        assert_eq!(diplomacy.accept(0, 1), None);
+
+        diplomacy.propose(0, 1, DiplomaticStatus::Alliance);
+        assert_eq!(
+            diplomacy.proposals_to(1),
+            vec![(0, DiplomaticStatus::Alliance)]
+        );
+        assert!(diplomacy.proposals_to(0).is_empty());
+        assert_eq!(diplomacy.status(0, 1), DiplomaticStatus::War);
+
+        assert_eq!(diplomacy.accept(0, 1), Some(DiplomaticStatus::Alliance));
+        assert_eq!(diplomacy.status(0, 1), DiplomaticStatus::Alliance);
+        assert_eq!(diplomacy.status(1, 0), DiplomaticStatus::Alliance);
+        assert_eq!(diplomacy.status(0, 2), DiplomaticStatus::War);
+        assert!(diplomacy.proposals_to(1).is_empty());
+
+        diplomacy.propose(2, 1, DiplomaticStatus::Peace);
+        assert_eq!(diplomacy.declare_war(1, 2), DiplomaticStatus::War);
+        assert!(diplomacy.proposals_to(1).is_empty());
+
+        assert_eq!(diplomacy.declare_war(1, 0), DiplomaticStatus::Alliance);
+        assert_eq!(diplomacy.status(0, 1), DiplomaticStatus::War);
+    }
+}
@@ -5,14 +5,16 @@ use thiserror::Error;
 use crate::{
     game::{
         city::CityID,
+        map::Improvement,
         move_::MoveError,
         player::PlayerNum,
-        unit::{TransportMode, UnitID},
+        unit::{TransportMode, UnitID, UnitType},
     },
+    lobby::GameID,
     util::Location,
 };
 
-use super::{alignment::Alignment, TurnNum, TurnPhase};
+use super::{alignment::Alignment, diplomacy::DiplomaticStatus, TurnNum, TurnPhase};
 
 #[derive(Debug, Deserialize, Error, PartialEq, Serialize)]
 pub enum GameError {
@@ -26,12 +28,51 @@ pub enum GameError {
     #[error("No player slots available; the game is full")]
     NoPlayerSlotsAvailable,
 
+    #[error("No spectator seats available; the server has as many spectators as it allows")]
+    NoSpectatorSeatsAvailable,
+
+    #[error("Player {player}'s slot is taken or isn't for a human")]
+    PlayerSlotUnavailable { player: PlayerNum },
+
+    #[error("Player {player}'s slot hasn't been claimed")]
+    PlayerSlotUnclaimed { player: PlayerNum },
+
+    #[error("The game hasn't started; players are still gathering in the lobby")]
+    GameNotStarted,
+
+    #[error("The game is paused; no turn can begin until it's resumed")]
+    GamePaused,
+
+    #[error("No game with ID {id} exists")]
+    NoSuchGame { id: GameID },
+
+    #[error("Players already claimed can't be taken to another game")]
+    AlreadyPlaying,
+
+    #[error("Spectators can't play")]
+    Spectating,
+
+    #[error("Only spectators are shown the game this way")]
+    NotSpectating,
+
+    #[error("Error calling the server: {reason}")]
+    Rpc { reason: String },
+
+    #[error("Lost the connection to the server and couldn't reconnect: {reason}")]
+    ServerUnreachable { reason: String },
+
+    #[error("The connection to the server was lost before it answered; the call may or may not have taken effect")]
+    CallInterrupted,
+
     #[error("There is no player {player}")]
     NoSuchPlayer { player: PlayerNum },
 
     #[error("It isn't player {player}'s turn")]
     NotPlayersTurn { player: PlayerNum },
 
+    #[error("Player {player} has already resigned")]
+    AlreadyResigned { player: PlayerNum },
+
     #[error("There is no player identified by the given secret")]
     NoPlayerIdentifiedBySecret,
 
@@ -47,9 +88,22 @@ pub enum GameError {
     #[error("No city at location {loc} exists")]
     NoCityAtLocation { loc: Location },
 
+    #[error("The walls of the city with ID {id:?} can't be built any higher")]
+    CityWallsComplete { id: CityID },
+
+    #[error("The city with ID {id:?} needs {needed} production but has only {available}")]
+    InsufficientProduction {
+        id: CityID,
+        needed: u16,
+        available: u16,
+    },
+
     #[error("No tile at location {loc} exists")]
     NoTileAtLocation { loc: Location },
 
+    #[error("No enemy is known to be at location {loc}")]
+    NoEnemyAtLocation { loc: Location },
+
     #[error("Specified unit is not controlled by the current player")]
     UnitNotControlledByCurrentPlayer,
 
@@ -65,6 +119,13 @@ pub enum GameError {
         carried_transport_mode: TransportMode,
     },
 
+    #[error("The relevant carrying space only carries units of type {accepted_type}, not the {carried_type} with ID {carried_id:?}")]
+    WrongUnitType {
+        carried_id: UnitID,
+        accepted_type: UnitType,
+        carried_type: UnitType,
+    },
+
     #[error("The relevant carrying space cannot carry the unit with ID {carried_id:?} due insufficient space.")]
     InsufficientCarryingSpace { carried_id: UnitID },
 
@@ -84,9 +145,86 @@ pub enum GameError {
         garrisoned_unit_id: UnitID,
     },
 
+    #[error("The unit with ID {id:?} cannot stack with the units at location {loc}")]
+    CannotStackUnit { id: UnitID, loc: Location },
+
+    #[error("The unit with ID {id:?} is not an aircraft")]
+    UnitCannotFly { id: UnitID },
+
+    #[error("There is no friendly city or carrier with room to land at location {loc}")]
+    NoAirBase { loc: Location },
+
+    #[error("The unit with ID {id:?} cannot attack from a distance")]
+    NoRangedAttack { id: UnitID },
+
+    #[error("Location {loc} is beyond the attack range of the unit with ID {id:?}")]
+    OutOfRange { id: UnitID, loc: Location },
+
+    #[error("The unit with ID {id:?} has no moves remaining this turn")]
+    NoMovesRemaining { id: UnitID },
+
+    #[error("The unit with ID {id:?} cannot build tile improvements")]
+    CannotBuild { id: UnitID },
+
+    #[error("A {improvement} cannot be built at location {loc}")]
+    CannotImprove {
+        loc: Location,
+        improvement: Improvement,
+    },
+
+    #[error("The unit with ID {id:?} cannot lay mines")]
+    CannotLayMines { id: UnitID },
+
+    #[error("Mines cannot be laid at location {loc}")]
+    CannotMine { loc: Location },
+
+    #[error("The unit with ID {id:?} cannot sweep for mines")]
+    CannotSweepMines { id: UnitID },
+
+    #[error("The unit with ID {id:?} cannot be air-dropped")]
+    CannotParadrop { id: UnitID },
+
+    #[error("The unit with ID {id:?} must be in a friendly city to take off")]
+    NoAirfield { id: UnitID },
+
+    #[error(
+        "Location {loc} is not known land clear of friendly units, so it can't be a drop zone"
+    )]
+    UnsuitableDropZone { loc: Location },
+
+    #[error("The unit with ID {id:?} is not being carried")]
+    UnitNotCarried { id: UnitID },
+
+    #[error("Location {dest} is not adjacent to location {src}")]
+    NotAdjacent { src: Location, dest: Location },
+
     #[error("There was a problem moving the unit: {0}")]
     MoveError(MoveError),
 
     #[error("Requirements for ending turn not met for player {player}")]
     TurnEndRequirementsNotMet { player: PlayerNum },
+
+    #[error("Player {player} cannot attack player {other} without first declaring war")]
+    NotAtWar { player: PlayerNum, other: PlayerNum },
+
+    #[error("Player {player} cannot conduct diplomacy with itself")]
+    CannotNegotiateWithSelf { player: PlayerNum },
+
+    #[error("{status} cannot be proposed, only declared")]
+    StatusNotProposable { status: DiplomaticStatus },
+
+    #[error("Player {from} has not proposed a treaty to player {to}")]
+    NoTreatyProposed { from: PlayerNum, to: PlayerNum },
+
+    #[error("There is no unit group named {name}")]
+    NoSuchUnitGroup { name: String },
+
+    #[error("A chat message can't be empty")]
+    EmptyChatMessage,
+
+    #[error("A chat message can be at most {max} characters long, not {len}")]
+    ChatMessageTooLong { len: usize, max: usize },
+
+    #[error("A player's name can be at most {max} characters long, not {len}")]
+    PlayerNameTooLong { len: usize, max: usize },
 }
@@ -0,0 +1,84 @@
+//! Named groups of units, such as armies and fleets
+//!
+//! Grouping lets a player give one order to many units at once; each member still follows the order individually.
+//! A unit belongs to at most one group at a time.
+
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+
+use super::unit::UnitID;
+
+/// One player's unit groups, by name
+#[derive(Clone, Debug, Default, Deserialize, PartialEq, Serialize)]
+pub struct UnitGroups {
+    groups: BTreeMap<String, Vec<UnitID>>,
+}
+
+impl UnitGroups {
+    /// Make `members` the group called `name`, replacing any group already by that name
+    ///
+    /// The members leave whatever groups they were in before; groups left empty are dropped.
+    pub fn form(&mut self, name: String, members: Vec<UnitID>) {
+        for group in self.groups.values_mut() {
+            group.retain(|id| !members.contains(id));
+        }
+        self.groups.retain(|_name, group| !group.is_empty());
+
+        self.groups.insert(name, members);
+    }
+
+    /// Remove the group called `name`, returning its members
+    pub fn disband(&mut self, name: &str) -> Option<Vec<UnitID>> {
+        self.groups.remove(name)
+    }
+
+    pub fn members(&self, name: &str) -> Option<&Vec<UnitID>> {
+        self.groups.get(name)
+    }
+
+    /// Each group's name and members, in order of name
+    pub fn iter(&self) -> impl Iterator<Item = (&String, &Vec<UnitID>)> {
+        self.groups.iter()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::game::unit::UnitID;
+
+    use super::UnitGroups;
+
+    #[test]
+    fn test_unit_groups() {
+        let mut groups = UnitGroups::default();
+
+        let a = UnitID::new(0);
+        let b = UnitID::new(1);
+        let c = UnitID::new(2);
+
+        groups.form(String::from("Army"), vec![a, b]);
+        groups.form(String::from("Fleet"), vec![c]);
+        assert_eq!(groups.members("Army"), Some(&vec![a, b]));
+        assert_eq!(groups.members("Navy"), None);
+
+        // Joining the fleet takes `b` out of the army
+        groups.form(String::from("Fleet"), vec![b, c]);
+        assert_eq!(groups.members("Army"), Some(&vec![a]));
+        assert_eq!(groups.members("Fleet"), Some(&vec![b, c]));
+
+        // Groups left empty are dropped
+        groups.form(String::from("Reserve"), vec![a]);
+        assert_eq!(groups.members("Army"), None);
+        assert_eq!(
+            groups
+                .iter()
+                .map(|(name, _)| name.as_str())
+                .collect::<Vec<_>>(),
+            vec!["Fleet", "Reserve"]
+        );
+
+        assert_eq!(groups.disband("Fleet"), Some(vec![b, c]));
+        assert_eq!(groups.disband("Fleet"), None);
+    }
+}
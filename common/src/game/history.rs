@@ -0,0 +1,44 @@
+//! The course of a game, player by player
+//!
+//! Whenever a player's turn ends, the engine notes where the player stood and adds that turn's summary to the
+//! player's running totals. Once the game is decided, this is what's looked back on to see how it went.
+
+use serde::{Deserialize, Serialize};
+
+use super::{TurnNum, TurnSummary};
+
+/// Where a player stood as one of its turns ended
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct Standing {
+    pub turn: TurnNum,
+    pub score: f64,
+    pub cities: usize,
+    pub units: usize,
+}
+
+/// How the game has gone for one player
+#[derive(Clone, Debug, Default, Deserialize, PartialEq, Serialize)]
+pub struct PlayerHistory {
+    /// The player's standing at the end of each of its turns, oldest first
+    pub standings: Vec<Standing>,
+
+    /// All of the player's turn summaries added together
+    pub totals: TurnSummary,
+}
+
+impl PlayerHistory {
+    pub(in crate::game) fn record(&mut self, standing: Standing, summary: &TurnSummary) {
+        self.standings.push(standing);
+        self.totals += summary;
+    }
+
+    /// The most cities the player held at the end of any of its turns
+    pub fn peak_cities(&self) -> usize {
+        self.standings.iter().map(|s| s.cities).max().unwrap_or(0)
+    }
+
+    /// The most units the player had at the end of any of its turns
+    pub fn peak_units(&self) -> usize {
+        self.standings.iter().map(|s| s.units).max().unwrap_or(0)
+    }
+}
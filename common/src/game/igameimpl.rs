@@ -1,16 +1,21 @@
 use std::{
     borrow::Cow,
     collections::{BTreeMap, BTreeSet},
+    time::Duration,
 };
 
 use async_trait::async_trait;
 
 use crate::{
     game::{
+        chat::ChatMessage,
         city::{City, CityID},
+        combat::CombatOdds,
+        delta::{SyncPoint, TurnDelta, TurnSnapshot},
         error::GameError,
-        map::Tile,
-        obs::{Obs, ObsTracker},
+        history::PlayerHistory,
+        map::{Improvement, Tile},
+        obs::{Obs, ObsDiff, ObsRevision, ObsTracker, ObsTrackerI},
         unit::{
             orders::{Orders, OrdersResult},
             Unit, UnitID, UnitType,
@@ -25,26 +30,39 @@ use super::{
         PlayerActionOutcome,
     },
     ai::{fX, TrainingFocus},
+    diplomacy::DiplomaticStatus,
     move_::Move,
     obs::LocatedObsLite,
     player::PlayerNum,
-    ActionNum, Game, OrdersSet, PlayerSecret, ProductionCleared, ProductionSet,
-    ProposedActionResult, ProposedOrdersResult, ProposedResult, TurnEnded, TurnNum, TurnPhase,
-    TurnStart, UmpireResult, UnitDisbanded,
+    ActionNum, Bombardment, Game, GameOver, OrdersSet, PlayerSecret, ProductionCleared,
+    ProductionSet, ProposedActionResult, ProposedOrdersResult, ProposedResult, RadarBuilt,
+    Resigned, TurnEnded, TurnNum, TurnPhase, TurnStart, UmpireResult, UnitDisbanded, WallsBuilt,
 };
 
 pub use super::traits::IGame;
 
 #[async_trait]
 impl IGame for Game {
-    async fn num_players(&self) -> PlayerNum {
-        self.num_players()
+    async fn num_players(&self) -> UmpireResult<PlayerNum> {
+        Ok(self.num_players())
     }
 
     async fn is_player_turn(&self, secret: PlayerSecret) -> UmpireResult<bool> {
         self.is_player_turn(secret)
     }
 
+    async fn turn_time_remaining(
+        &self,
+        player_secret: PlayerSecret,
+    ) -> UmpireResult<Option<Duration>> {
+        // Only the server times turns
+        self.validate_is_player_turn(player_secret).map(|_| None)
+    }
+
+    async fn wait_my_turn(&self, _timeout: Duration) -> UmpireResult<Option<PlayerNum>> {
+        Ok(Some(self.current_player()))
+    }
+
     async fn begin_turn(
         &mut self,
         player_secret: PlayerSecret,
@@ -57,12 +75,20 @@ impl IGame for Game {
         self.turn_is_done(player, turn)
     }
 
-    async fn current_turn_is_done(&self) -> bool {
-        self.current_turn_is_done()
+    async fn current_turn_is_done(&self) -> UmpireResult<bool> {
+        Ok(self.current_turn_is_done())
     }
 
-    async fn victor(&self) -> Option<PlayerNum> {
-        self.victor()
+    async fn victor(&self) -> UmpireResult<Option<PlayerNum>> {
+        Ok(self.victor())
+    }
+
+    async fn game_over(&self) -> UmpireResult<Option<GameOver>> {
+        Ok(self.game_over())
+    }
+
+    async fn turn_limit(&self) -> UmpireResult<Option<TurnNum>> {
+        Ok(self.turn_limit())
     }
 
     async fn end_turn(&mut self, player_secret: PlayerSecret) -> UmpireResult<TurnEnded> {
@@ -107,6 +133,14 @@ impl IGame for Game {
         self.player_unit_legal_one_step_destinations(player_secret, unit_id)
     }
 
+    async fn player_unit_reachable_this_turn(
+        &self,
+        player_secret: PlayerSecret,
+        unit_id: UnitID,
+    ) -> UmpireResult<BTreeSet<Location>> {
+        self.player_unit_reachable_this_turn(player_secret, unit_id)
+    }
+
     async fn player_unit_legal_directions(
         &self,
         player_secret: PlayerSecret,
@@ -135,7 +169,29 @@ impl IGame for Game {
 
     async fn player_observations(&self, player_secret: PlayerSecret) -> UmpireResult<ObsTracker> {
         self.player_observations(player_secret)
-            .map(|tracker| tracker.clone())
+            .map(ObsTracker::from)
+    }
+
+    async fn spectator_observations(&self, viewer: Option<PlayerNum>) -> UmpireResult<ObsTracker> {
+        self.spectator_observations(viewer)
+    }
+
+    async fn player_observations_since(
+        &self,
+        player_secret: PlayerSecret,
+        revision: ObsRevision,
+    ) -> UmpireResult<ObsDiff> {
+        self.player_observations(player_secret)
+            .map(|tracker| tracker.since(revision))
+    }
+
+    /// A game held locally has no bandwidth to save, so keeps no snapshot; everything is given each time
+    async fn player_turn_delta(
+        &self,
+        player_secret: PlayerSecret,
+        since: Option<SyncPoint>,
+    ) -> UmpireResult<TurnDelta> {
+        self.player_turn_delta(player_secret, since, &mut TurnSnapshot::default())
     }
 
     async fn player_cities(&self, player_secret: PlayerSecret) -> UmpireResult<Vec<City>> {
@@ -346,6 +402,151 @@ impl IGame for Game {
         self.propose_move_unit_by_id_avoiding_combat(player_secret, id, dest)
     }
 
+    async fn combat_odds(
+        &self,
+        player_secret: PlayerSecret,
+        attacker_id: UnitID,
+        defender_loc: Location,
+    ) -> UmpireResult<CombatOdds> {
+        Game::combat_odds(self, player_secret, attacker_id, defender_loc)
+    }
+
+    async fn load_unit(
+        &mut self,
+        player_secret: PlayerSecret,
+        unit_id: UnitID,
+        carrier_id: UnitID,
+    ) -> UmpireResult<Move> {
+        Game::load_unit(self, player_secret, unit_id, carrier_id)
+    }
+
+    async fn unload_unit(
+        &mut self,
+        player_secret: PlayerSecret,
+        unit_id: UnitID,
+        dest: Location,
+    ) -> UmpireResult<Move> {
+        Game::unload_unit(self, player_secret, unit_id, dest)
+    }
+
+    async fn rebase_unit(
+        &mut self,
+        player_secret: PlayerSecret,
+        unit_id: UnitID,
+        base: Location,
+    ) -> UmpireResult<Move> {
+        Game::rebase_unit(self, player_secret, unit_id, base)
+    }
+
+    async fn player_air_bases(
+        &self,
+        player_secret: PlayerSecret,
+        unit_id: UnitID,
+    ) -> UmpireResult<Vec<Location>> {
+        Game::player_air_bases(self, player_secret, unit_id)
+    }
+
+    async fn bombard(
+        &mut self,
+        player_secret: PlayerSecret,
+        unit_id: UnitID,
+        target: Location,
+    ) -> UmpireResult<Bombardment> {
+        Game::bombard(self, player_secret, unit_id, target)
+    }
+
+    async fn player_unit_bombard_targets(
+        &self,
+        player_secret: PlayerSecret,
+        unit_id: UnitID,
+    ) -> UmpireResult<Vec<Location>> {
+        Game::player_unit_bombard_targets(self, player_secret, unit_id)
+    }
+
+    async fn paradrop(
+        &mut self,
+        player_secret: PlayerSecret,
+        unit_id: UnitID,
+        dest: Location,
+    ) -> UmpireResult<Move> {
+        Game::paradrop(self, player_secret, unit_id, dest)
+    }
+
+    async fn player_unit_drop_zones(
+        &self,
+        player_secret: PlayerSecret,
+        unit_id: UnitID,
+    ) -> UmpireResult<Vec<Location>> {
+        Game::player_unit_drop_zones(self, player_secret, unit_id)
+    }
+
+    async fn lay_mines(
+        &mut self,
+        player_secret: PlayerSecret,
+        unit_id: UnitID,
+    ) -> UmpireResult<Location> {
+        Game::lay_mines(self, player_secret, unit_id)
+    }
+
+    async fn sweep_mines(
+        &mut self,
+        player_secret: PlayerSecret,
+        unit_id: UnitID,
+    ) -> UmpireResult<Vec<Location>> {
+        Game::sweep_mines(self, player_secret, unit_id)
+    }
+
+    async fn player_known_minefields(
+        &self,
+        player_secret: PlayerSecret,
+    ) -> UmpireResult<Vec<Location>> {
+        Game::player_known_minefields(self, player_secret)
+    }
+
+    async fn diplomatic_status(
+        &self,
+        player_secret: PlayerSecret,
+        other: PlayerNum,
+    ) -> UmpireResult<DiplomaticStatus> {
+        Game::diplomatic_status(self, player_secret, other)
+    }
+
+    async fn treaty_proposals(
+        &self,
+        player_secret: PlayerSecret,
+    ) -> UmpireResult<Vec<(PlayerNum, DiplomaticStatus)>> {
+        Game::treaty_proposals(self, player_secret)
+    }
+
+    async fn propose_treaty(
+        &mut self,
+        player_secret: PlayerSecret,
+        other: PlayerNum,
+        status: DiplomaticStatus,
+    ) -> UmpireResult<()> {
+        Game::propose_treaty(self, player_secret, other, status)
+    }
+
+    async fn accept_treaty(
+        &mut self,
+        player_secret: PlayerSecret,
+        other: PlayerNum,
+    ) -> UmpireResult<DiplomaticStatus> {
+        Game::accept_treaty(self, player_secret, other)
+    }
+
+    async fn declare_war(
+        &mut self,
+        player_secret: PlayerSecret,
+        other: PlayerNum,
+    ) -> UmpireResult<DiplomaticStatus> {
+        Game::declare_war(self, player_secret, other)
+    }
+
+    async fn resign(&mut self, player_secret: PlayerSecret) -> UmpireResult<Resigned> {
+        Game::resign(self, player_secret)
+    }
+
     async fn disband_unit_by_id(
         &mut self,
         player_secret: PlayerSecret,
@@ -372,6 +573,22 @@ impl IGame for Game {
         self.set_production_by_id(player_secret, city_id, production)
     }
 
+    async fn build_city_walls(
+        &mut self,
+        player_secret: PlayerSecret,
+        city_id: CityID,
+    ) -> UmpireResult<WallsBuilt> {
+        self.build_city_walls(player_secret, city_id)
+    }
+
+    async fn build_city_radar(
+        &mut self,
+        player_secret: PlayerSecret,
+        city_id: CityID,
+    ) -> UmpireResult<RadarBuilt> {
+        self.build_city_radar(player_secret, city_id)
+    }
+
     async fn clear_production(
         &mut self,
         player_secret: PlayerSecret,
@@ -390,28 +607,32 @@ impl IGame for Game {
             .map(|prods_cleared| prods_cleared.collect())
     }
 
-    async fn turn(&self) -> TurnNum {
-        self.turn()
+    async fn turn(&self) -> UmpireResult<TurnNum> {
+        Ok(self.turn())
     }
 
     async fn player_action(&self, player_secret: PlayerSecret) -> UmpireResult<ActionNum> {
         self.player_action(player_secret)
     }
 
-    async fn turn_phase(&self) -> TurnPhase {
-        self.turn_phase()
+    async fn turn_phase(&self) -> UmpireResult<TurnPhase> {
+        Ok(self.turn_phase())
     }
 
-    async fn current_player(&self) -> PlayerNum {
-        self.current_player()
+    async fn current_player(&self) -> UmpireResult<PlayerNum> {
+        Ok(self.current_player())
     }
 
-    async fn dims(&self) -> Dims {
-        self.dims()
+    async fn paused(&self) -> UmpireResult<bool> {
+        Ok(self.paused())
     }
 
-    async fn wrapping(&self) -> Wrap2d {
-        self.wrapping()
+    async fn dims(&self) -> UmpireResult<Dims> {
+        Ok(self.dims())
+    }
+
+    async fn wrapping(&self) -> UmpireResult<Wrap2d> {
+        Ok(self.wrapping())
     }
 
     async fn valid_productions(
@@ -482,6 +703,75 @@ impl IGame for Game {
         self.propose_order_unit_explore(player_secret, unit_id)
     }
 
+    async fn order_unit_escort(
+        &mut self,
+        player_secret: PlayerSecret,
+        unit_id: UnitID,
+        target: UnitID,
+    ) -> OrdersResult {
+        self.order_unit_escort(player_secret, unit_id, target)
+    }
+
+    async fn propose_order_unit_escort(
+        &self,
+        player_secret: PlayerSecret,
+        unit_id: UnitID,
+        target: UnitID,
+    ) -> ProposedOrdersResult {
+        self.propose_order_unit_escort(player_secret, unit_id, target)
+    }
+
+    async fn order_unit_build(
+        &mut self,
+        player_secret: PlayerSecret,
+        unit_id: UnitID,
+        improvement: Improvement,
+    ) -> OrdersResult {
+        self.order_unit_build(player_secret, unit_id, improvement)
+    }
+
+    async fn propose_order_unit_build(
+        &self,
+        player_secret: PlayerSecret,
+        unit_id: UnitID,
+        improvement: Improvement,
+    ) -> ProposedOrdersResult {
+        self.propose_order_unit_build(player_secret, unit_id, improvement)
+    }
+
+    async fn unit_groups(
+        &self,
+        player_secret: PlayerSecret,
+    ) -> UmpireResult<Vec<(String, Vec<UnitID>)>> {
+        Game::unit_groups(self, player_secret)
+    }
+
+    async fn form_unit_group(
+        &mut self,
+        player_secret: PlayerSecret,
+        name: String,
+        unit_ids: Vec<UnitID>,
+    ) -> UmpireResult<()> {
+        Game::form_unit_group(self, player_secret, name, unit_ids)
+    }
+
+    async fn disband_unit_group(
+        &mut self,
+        player_secret: PlayerSecret,
+        name: String,
+    ) -> UmpireResult<Vec<UnitID>> {
+        Game::disband_unit_group(self, player_secret, name)
+    }
+
+    async fn order_unit_group(
+        &mut self,
+        player_secret: PlayerSecret,
+        name: String,
+        orders: Orders,
+    ) -> UmpireResult<Vec<OrdersResult>> {
+        Game::order_unit_group(self, player_secret, name, orders)
+    }
+
     async fn activate_unit_by_loc(
         &mut self,
         player_secret: PlayerSecret,
@@ -526,8 +816,8 @@ impl IGame for Game {
         self.set_and_follow_orders(player_secret, id, orders)
     }
 
-    async fn current_player_score(&self) -> f64 {
-        self.current_player_score()
+    async fn current_player_score(&self) -> UmpireResult<f64> {
+        Ok(self.current_player_score())
     }
 
     async fn player_score(&self, player_secret: PlayerSecret) -> UmpireResult<f64> {
@@ -538,16 +828,46 @@ impl IGame for Game {
         self.player_score_by_idx(player)
     }
 
-    async fn player_scores(&self) -> Vec<f64> {
-        self.player_scores()
+    async fn player_scores(&self) -> UmpireResult<Vec<f64>> {
+        Ok(self.player_scores())
+    }
+
+    async fn player_histories(&self) -> UmpireResult<Vec<PlayerHistory>> {
+        Ok(self.player_histories())
+    }
+
+    async fn send_chat_message(
+        &mut self,
+        player_secret: PlayerSecret,
+        text: String,
+    ) -> UmpireResult<()> {
+        self.send_chat_message(player_secret, text)
+    }
+
+    async fn chat_messages_since(
+        &self,
+        player_secret: PlayerSecret,
+        since: usize,
+    ) -> UmpireResult<Vec<ChatMessage>> {
+        self.chat_messages_since(player_secret, since)
+    }
+
+    async fn wait_chat_messages(
+        &self,
+        player_secret: PlayerSecret,
+        since: usize,
+        _timeout: Duration,
+    ) -> UmpireResult<Vec<ChatMessage>> {
+        self.chat_messages_since(player_secret, since)
     }
 
     async fn player_features(
         &self,
         player_secret: PlayerSecret,
         focus: TrainingFocus,
+        window: usize,
     ) -> UmpireResult<Vec<fX>> {
-        self.player_features(player_secret, focus)
+        self.player_features(player_secret, focus, window)
     }
 
     async fn take_simple_action(
@@ -3,28 +3,34 @@
 //! Data structures and algorithms for representing and working with the game map.
 
 pub mod dijkstra;
+pub mod fairness;
+pub mod file;
 pub mod gen;
 pub(in crate::game) mod grid;
 pub mod terrain;
 pub(in crate::game) mod tile;
 
 pub use self::grid::{LocationGrid, LocationGridI, SparseLocationGrid};
-pub use self::terrain::Terrain;
-pub use self::tile::Tile;
+pub use self::terrain::{Improvement, River, Terrain};
+pub use self::tile::{Tile, STACK_CAPACITY};
 
 use std::collections::{BTreeMap, BTreeSet};
 use std::fmt::{Debug, Formatter, Result as FmtResult};
+use std::sync::Arc;
 
+use serde::{Deserialize, Serialize};
 use thiserror::Error;
 
 use crate::{
+    conf,
     game::{
         alignment::{AlignedMaybe, Alignment},
         city::{City, CityID},
-        unit::{Unit, UnitID, UnitType},
+        obs::Observer,
+        unit::{Unit, UnitID, UnitType, Veterancy},
         GameError, PlayerNum,
     },
-    util::{Dimensioned, Dims, Location},
+    util::{Dimensioned, Dims, Located, Location},
 };
 
 use self::dijkstra::Source;
@@ -44,6 +50,47 @@ pub enum NewUnitError {
     },
 }
 
+/// Mines laid by a player, unseen by the other players until one of their units runs into them
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct Minefield {
+    /// The player who laid the mines
+    pub owner: PlayerNum,
+
+    /// Other players who have found out about the minefield the hard way
+    pub revealed_to: BTreeSet<PlayerNum>,
+}
+
+impl Minefield {
+    pub fn new(owner: PlayerNum) -> Self {
+        Self {
+            owner,
+            revealed_to: BTreeSet::new(),
+        }
+    }
+
+    /// Whether the given player knows the minefield is there
+    pub fn known_to(&self, player: PlayerNum) -> bool {
+        self.owner == player || self.revealed_to.contains(&player)
+    }
+}
+
+/// A radar installation, keeping watch for its owner
+pub struct Radar {
+    pub loc: Location,
+}
+
+impl Located for Radar {
+    fn loc(&self) -> Location {
+        self.loc
+    }
+}
+
+impl Observer for Radar {
+    fn sight_distance(&self) -> u16 {
+        conf::RADAR_SIGHT_DISTANCE
+    }
+}
+
 /// An abstract, indexed representation of the map data.
 ///
 /// The main role of this structure is to tracker the IDs, locations, and carried status of all
@@ -57,10 +104,12 @@ pub enum NewUnitError {
 ///
 /// Reasonable constraints on tiles, cities, and units are enforced. For example, if a carrier unit is destroyed,
 /// all of its carried units will also be destroyed.
-#[derive(Clone)]
+#[derive(Clone, Deserialize, Serialize)]
 pub struct MapData {
     /// A grid of map tiles. All cities and units are owned by the tiles that contain them.
-    tiles: LocationGrid<Tile>,
+    ///
+    /// Tiles are shared between clones until one of them changes, so cloning the map copies only the grid of pointers.
+    tiles: LocationGrid<Arc<Tile>>,
 
     /// where are all the units located?
     ///
@@ -93,6 +142,16 @@ pub struct MapData {
 
     /// The number of each type of unit controlled by each alignment
     alignment_unit_type_counts: BTreeMap<Alignment, BTreeMap<UnitType, usize>>,
+
+    /// Where mines have been laid
+    ///
+    /// Kept off the tiles themselves so that observing a tile doesn't give them away.
+    minefields: BTreeMap<Location, Minefield>,
+
+    /// Who built each radar
+    ///
+    /// A radar in a city watches for whoever holds the city instead, so this matters only for radars in the open.
+    radars: BTreeMap<Location, PlayerNum>,
 }
 
 impl MapData {
@@ -120,14 +179,14 @@ impl MapData {
 
         let next_unit_id: UnitID = tiles
             .iter()
-            .filter_map(|tile| tile.unit.as_ref())
+            .flat_map(|tile| tile.unit.iter().chain(tile.stack.iter()))
             .map(|unit| unit.id)
             .max()
             .map(|id| id.next())
             .unwrap_or_else(UnitID::default);
 
         let mut map_data = Self {
-            tiles,
+            tiles: tiles.map(Arc::new),
             unit_locs: BTreeSet::new(),
             unit_loc_by_id: BTreeMap::new(),
             unit_carrier_by_id: BTreeMap::new(),
@@ -136,6 +195,8 @@ impl MapData {
             next_unit_id,
             alignment_city_counts: BTreeMap::new(),
             alignment_unit_type_counts: BTreeMap::new(),
+            minefields: BTreeMap::new(),
+            radars: BTreeMap::new(),
         };
 
         map_data.index();
@@ -153,7 +214,7 @@ impl MapData {
     }
 
     fn index_tile(&mut self, loc: Location) {
-        let tile = LocationGridI::get(&self.tiles, loc).cloned().unwrap(); //CLONE
+        let tile = Arc::clone(LocationGridI::get(&self.tiles, loc).unwrap());
         if let Some(city) = tile.city.as_ref() {
             self.index_city(city);
         }
@@ -163,6 +224,9 @@ impl MapData {
                 self.index_carried_unit(carried_unit, unit);
             }
         }
+        for stacked_unit in tile.stack.iter() {
+            self.index_uncarried_unit(stacked_unit);
+        }
     }
 
     /// Add a carried unit to the relevant indices
@@ -227,6 +291,14 @@ impl MapData {
         let added = self.unit_locs.insert(unit.loc);
         debug_assert!(added);
 
+        self.index_uncarried_unit(unit);
+    }
+
+    /// Add a unit that isn't carried (and all units it carries) to the relevant indices, except for `unit_locs`
+    ///
+    /// Units stacked beneath the top-level unit are indexed this way alone, as the top-level unit already accounts for
+    /// their location in `unit_locs`.
+    fn index_uncarried_unit(&mut self, unit: &Unit) {
         let overwritten_loc: Option<Location> = self.unit_loc_by_id.insert(unit.id, unit.loc);
         debug_assert_eq!(
             overwritten_loc,
@@ -253,6 +325,11 @@ impl MapData {
         let was_present = self.unit_locs.remove(&unit.loc);
         debug_assert!(was_present);
 
+        self.unindex_uncarried_unit(unit);
+    }
+
+    /// Remove a unit that isn't carried (and all units it carries) from the relevant indices, except for `unit_locs`
+    fn unindex_uncarried_unit(&mut self, unit: &Unit) {
         let removed_loc: Option<Location> = self.unit_loc_by_id.remove(&unit.id);
         debug_assert_eq!(removed_loc.unwrap(), unit.loc);
 
@@ -301,14 +378,118 @@ impl MapData {
     /// Set the terrain of a tile at the given location, returning the prior setting.
     pub fn set_terrain(&mut self, loc: Location, terrain: Terrain) -> Result<Terrain, GameError> {
         let tile = self
-            .tiles
-            .get_mut(loc)
+            .tile_mut(loc)
             .ok_or(GameError::NoTileAtLocation { loc })?;
         let old = tile.terrain;
         tile.terrain = terrain;
         Ok(old)
     }
 
+    /// Set the river running through the tile at the given location, returning the prior setting.
+    pub fn set_river(
+        &mut self,
+        loc: Location,
+        river: Option<River>,
+    ) -> Result<Option<River>, GameError> {
+        let tile = self
+            .tile_mut(loc)
+            .ok_or(GameError::NoTileAtLocation { loc })?;
+        Ok(std::mem::replace(&mut tile.river, river))
+    }
+
+    /// Build `improvement` on the tile at the given location
+    ///
+    /// Returns whether the improvement is new; a tile holds at most one of each kind.
+    pub fn improve_tile(
+        &mut self,
+        loc: Location,
+        improvement: Improvement,
+    ) -> Result<bool, GameError> {
+        let tile = self
+            .tile_mut(loc)
+            .ok_or(GameError::NoTileAtLocation { loc })?;
+
+        if tile.has_improvement(improvement) {
+            return Ok(false);
+        }
+
+        tile.improvements.push(improvement);
+        Ok(true)
+    }
+
+    /// Build a radar for `owner` at the given location
+    ///
+    /// Returns whether the radar is new.
+    pub fn build_radar(&mut self, loc: Location, owner: PlayerNum) -> Result<bool, GameError> {
+        let built = self.improve_tile(loc, Improvement::Radar)?;
+        if built {
+            self.radars.insert(loc, owner);
+        }
+        Ok(built)
+    }
+
+    /// The player the radar at the given location watches for, if there is a radar and anyone to watch for
+    pub fn radar_owner(&self, loc: Location) -> Option<PlayerNum> {
+        let tile = self.tile(loc)?;
+        if !tile.has_improvement(Improvement::Radar) {
+            return None;
+        }
+
+        match tile.city.as_ref() {
+            Some(city) => match city.alignment {
+                Alignment::Belligerent { player } => Some(player),
+                Alignment::Neutral => None,
+            },
+            None => self.radars.get(&loc).copied(),
+        }
+    }
+
+    /// The radars watching for the given player
+    pub fn player_radars(&self, player: PlayerNum) -> impl Iterator<Item = Radar> + '_ {
+        self.radars
+            .keys()
+            .filter(move |loc| self.radar_owner(**loc) == Some(player))
+            .map(|loc| Radar { loc: *loc })
+    }
+
+    /// Lay mines belonging to `owner` at the given location
+    ///
+    /// Errors if the location is off the map or already mined.
+    pub fn lay_minefield(&mut self, loc: Location, owner: PlayerNum) -> Result<(), GameError> {
+        if !self.in_bounds(loc) {
+            return Err(GameError::NoTileAtLocation { loc });
+        }
+        if self.minefields.contains_key(&loc) {
+            return Err(GameError::CannotMine { loc });
+        }
+
+        self.minefields.insert(loc, Minefield::new(owner));
+        Ok(())
+    }
+
+    pub fn minefield(&self, loc: Location) -> Option<&Minefield> {
+        self.minefields.get(&loc)
+    }
+
+    /// Every minefield on the map, whoever knows of it
+    pub fn minefields(&self) -> impl Iterator<Item = (&Location, &Minefield)> {
+        self.minefields.iter()
+    }
+
+    /// Let `player` know of the minefield at the given location, if any
+    pub fn reveal_minefield(&mut self, loc: Location, player: PlayerNum) {
+        if let Some(minefield) = self.minefields.get_mut(&loc) {
+            if minefield.owner != player {
+                minefield.revealed_to.insert(player);
+            }
+        }
+    }
+
+    /// Remove the minefield at the given location, returning it
+    pub fn clear_minefield(&mut self, loc: Location) -> Option<Minefield> {
+        self.minefields.remove(&loc)
+    }
+
     /// Create a new unit properly indexed and managed
     ///
     /// Returns the ID of the new unit.
@@ -341,7 +522,7 @@ impl MapData {
 
         self.index_toplevel_unit(&unit);
 
-        self.tiles[loc].unit = Some(unit);
+        Arc::make_mut(&mut self.tiles[loc]).unit = Some(unit);
 
         Ok(unit_id)
     }
@@ -355,53 +536,39 @@ impl MapData {
         }
     }
 
-    /// Get the top-level unit at the given location, if any exists; mutably
-    fn toplevel_unit_by_loc_mut(&mut self, loc: Location) -> Option<&mut Unit> {
-        if let Some(tile) = self.tiles.get_mut(loc) {
-            tile.unit.as_mut()
-        } else {
-            None
-        }
-    }
-
-    /// Get the top-level unit or carried unit at `loc` which has ID `id`, if any
+    /// Get the top-level, stacked, or carried unit at `loc` which has ID `id`, if any
     pub fn unit_by_loc_and_id(&self, loc: Location, id: UnitID) -> Option<&Unit> {
-        if let Some(toplevel_unit) = self.toplevel_unit_by_loc(loc) {
-            if toplevel_unit.id == id {
-                return Some(toplevel_unit);
-            }
-
-            toplevel_unit
-                .carried_units()
-                .find(|carried_unit| carried_unit.id == id)
-        } else {
-            None
-        }
+        LocationGridI::get(&self.tiles, loc)
+            .and_then(|tile| tile.all_units().into_iter().find(|unit| unit.id == id))
     }
 
-    /// Get the top-level unit or carried unit at `loc` which has ID `id`, if any; mutably
+    /// Get the top-level, stacked, or carried unit at `loc` which has ID `id`, if any; mutably
     fn unit_by_loc_and_id_mut(&mut self, loc: Location, id: UnitID) -> Option<&mut Unit> {
-        if let Some(toplevel_unit) = self.toplevel_unit_by_loc_mut(loc) {
-            if toplevel_unit.id == id {
-                return Some(toplevel_unit);
-            }
-
-            toplevel_unit
-                .carried_units_mut()
-                .find(|carried_unit| carried_unit.id == id)
-        } else {
-            None
-        }
+        let tile = self.tile_mut(loc)?;
+
+        tile.unit
+            .iter_mut()
+            .chain(tile.stack.iter_mut())
+            .find_map(|unit| {
+                if unit.id == id {
+                    Some(unit)
+                } else {
+                    unit.carried_units_mut()
+                        .find(|carried_unit| carried_unit.id == id)
+                }
+            })
     }
 
     pub fn pop_unit_by_loc_and_id(&mut self, loc: Location, id: UnitID) -> Option<Unit> {
         self.pop_toplevel_unit_by_loc_and_id(loc, id)
             .or_else(|| self.pop_carried_unit_by_loc_and_id(loc, id))
+            .or_else(|| self.pop_stacked_unit_by_loc_and_id(loc, id))
     }
 
     pub fn pop_unit_by_id(&mut self, id: UnitID) -> Option<Unit> {
         self.pop_toplevel_unit_by_id(id)
             .or_else(|| self.pop_carried_unit_by_id(id))
+            .or_else(|| self.pop_stacked_unit_by_id(id))
     }
 
     pub fn pop_player_unit_by_id(&mut self, player: PlayerNum, id: UnitID) -> Option<Unit> {
@@ -412,18 +579,21 @@ impl MapData {
         }
     }
 
-    /// Get a mutable reference to the top-level unit at the given location, if any exists
-    fn unit_by_loc_mut(&mut self, loc: Location) -> Option<&mut Unit> {
-        if let Some(tile) = self.tiles.get_mut(loc) {
-            tile.unit.as_mut()
-        } else {
-            None
+    /// Remove the top-level unit from the given location (if any exists) and return it
+    ///
+    /// The next unit in the stack, if any, takes its place.
+    pub fn pop_toplevel_unit_by_loc(&mut self, loc: Location) -> Option<Unit> {
+        let popped_unit = self.take_toplevel_unit_by_loc(loc);
+        if popped_unit.is_some() {
+            self.promote_stacked_unit(loc);
         }
+        popped_unit
     }
 
-    /// Remove the top-level unit from the given location (if any exists) and return it
-    pub fn pop_toplevel_unit_by_loc(&mut self, loc: Location) -> Option<Unit> {
-        if let Some(tile) = self.tiles.get_mut(loc) {
+    /// Remove the top-level unit from the given location (if any exists) and return it, leaving any stacked units
+    /// without a top
+    fn take_toplevel_unit_by_loc(&mut self, loc: Location) -> Option<Unit> {
+        if let Some(tile) = self.tile_mut(loc) {
             let popped_unit = tile.unit.take();
             if let Some(ref popped_unit) = popped_unit {
                 self.unindex_toplevel_unit(popped_unit);
@@ -445,7 +615,7 @@ impl MapData {
 
     /// Remove the top-evel unit at location `loc` if it has ID `id`
     fn pop_toplevel_unit_by_loc_and_id(&mut self, loc: Location, id: UnitID) -> Option<Unit> {
-        if let Some(tile) = self.tiles.get_mut(loc) {
+        if let Some(tile) = self.tile_mut(loc) {
             let matches_id = if let Some(unit) = tile.unit.as_ref() {
                 unit.id == id
             } else {
@@ -455,6 +625,7 @@ impl MapData {
             if matches_id {
                 let popped_unit = tile.unit.take().unwrap();
                 self.unindex_toplevel_unit(&popped_unit);
+                self.promote_stacked_unit(loc);
                 Some(popped_unit)
             } else {
                 None
@@ -477,7 +648,8 @@ impl MapData {
         carried_unit_loc: Location,
         carried_unit_id: UnitID,
     ) -> Option<Unit> {
-        let carrier_unit = self.unit_by_loc_mut(carried_unit_loc).unwrap();
+        let carrier_unit_id = self.unit_carrier_by_id.get(&carried_unit_id).cloned()?;
+        let carrier_unit = self.unit_by_loc_and_id_mut(carried_unit_loc, carrier_unit_id)?;
         if let Some(carried_unit) = carrier_unit.release_by_id(carried_unit_id) {
             self.unindex_carried_unit(&carried_unit);
             Some(carried_unit)
@@ -486,20 +658,51 @@ impl MapData {
         }
     }
 
+    fn pop_stacked_unit_by_id(&mut self, stacked_unit_id: UnitID) -> Option<Unit> {
+        if let Some(stacked_unit_loc) = self.unit_loc_by_id.get(&stacked_unit_id).cloned() {
+            self.pop_stacked_unit_by_loc_and_id(stacked_unit_loc, stacked_unit_id)
+        } else {
+            None
+        }
+    }
+
+    /// Remove the unit with ID `id` from beneath the top-level unit at `loc`, if it's stacked there
+    fn pop_stacked_unit_by_loc_and_id(&mut self, loc: Location, id: UnitID) -> Option<Unit> {
+        let tile = self.tile_mut(loc)?;
+        let idx = tile.stack.iter().position(|unit| unit.id == id)?;
+
+        let stacked_unit = tile.stack.remove(idx);
+        self.unindex_uncarried_unit(&stacked_unit);
+        Some(stacked_unit)
+    }
+
+    /// Raise the first unit stacked at `loc` (if any) to the top, after the top-level unit leaves
+    fn promote_stacked_unit(&mut self, loc: Location) {
+        let tile = self.tile_mut(loc).unwrap();
+        debug_assert!(tile.unit.is_none());
+
+        if !tile.stack.is_empty() {
+            tile.unit = Some(tile.stack.remove(0));
+
+            // The promoted unit is already indexed, save for the location it now accounts for
+            self.unit_locs.insert(loc);
+        }
+    }
+
     /// Set the top-level unit at the given location to the one provided
     ///
-    /// Returns the previous unit, if any
+    /// Returns the previous unit, if any. Units stacked beneath it stay put.
     pub fn set_unit(&mut self, loc: Location, mut unit: Unit) -> Option<Unit> {
         unit.loc = loc;
         for carried_unit in unit.carried_units_mut() {
             carried_unit.loc = loc;
         }
 
-        let old_unit = self.pop_toplevel_unit_by_loc(loc);
+        let old_unit = self.take_toplevel_unit_by_loc(loc);
 
         self.index_toplevel_unit(&unit);
 
-        self.tiles.get_mut(loc).unwrap().unit = Some(unit);
+        self.tile_mut(loc).unwrap().unit = Some(unit);
         old_unit
     }
 
@@ -520,6 +723,35 @@ impl MapData {
         Ok(self.set_unit(dest, unit))
     }
 
+    /// Move a unit on the map onto the tile at `loc`, stacking it beneath the top-level unit there
+    ///
+    /// Returns the number of units now stacked on the tile
+    pub fn stack_unit_by_id(&mut self, unit_id: UnitID, loc: Location) -> Result<usize, GameError> {
+        {
+            let unit = self
+                .unit_by_id(unit_id)
+                .ok_or(GameError::NoSuchUnit { id: unit_id })?;
+
+            let tile = self.tile(loc).ok_or(GameError::NoTileAtLocation { loc })?;
+
+            if !tile.can_stack(unit) {
+                return Err(GameError::CannotStackUnit { id: unit_id, loc });
+            }
+        }
+
+        let mut unit = self.pop_unit_by_id(unit_id).unwrap();
+        unit.loc = loc;
+        for carried_unit in unit.carried_units_mut() {
+            carried_unit.loc = loc;
+        }
+
+        self.index_uncarried_unit(&unit);
+
+        let tile = self.tile_mut(loc).unwrap();
+        tile.stack.push(unit);
+        Ok(tile.stack_size())
+    }
+
     /// Occupy the city at the given location using the unit with the given ID.
     ///
     /// This will update the city's alignment to match the occupier unit.
@@ -609,6 +841,33 @@ impl MapData {
         unit.record_movement(moves)
     }
 
+    /// Credit the unit with ID `id` with a combat victory
+    ///
+    /// Returns the unit's new veterancy if it was promoted.
+    pub fn record_unit_victory(&mut self, id: UnitID) -> UmpireResult<Option<Veterancy>> {
+        let unit = self
+            .unit_by_id_mut(id)
+            .ok_or(GameError::NoSuchUnit { id })?;
+
+        Ok(unit.record_victory())
+    }
+
+    /// Inflict `damage` hitpoints of damage on the unit with the given ID
+    ///
+    /// A unit left without hitpoints is removed from the map. Returns whether the unit survived.
+    pub fn damage_unit_by_id(&mut self, id: UnitID, damage: u16) -> UmpireResult<bool> {
+        let unit = self
+            .unit_by_id_mut(id)
+            .ok_or(GameError::NoSuchUnit { id })?;
+
+        if unit.take_damage(damage) {
+            return Ok(true);
+        }
+
+        self.pop_unit_by_id(id).unwrap();
+        Ok(false)
+    }
+
     pub fn unit_loc(&self, id: UnitID) -> Option<Location> {
         self.unit_by_id(id).map(|unit| unit.loc)
     }
@@ -617,6 +876,11 @@ impl MapData {
         self.toplevel_unit_by_loc(loc).map(|unit| unit.id)
     }
 
+    /// The ID of the unit carrying the unit with ID `id`, if it is being carried
+    pub fn unit_carrier_id(&self, id: UnitID) -> Option<UnitID> {
+        self.unit_carrier_by_id.get(&id).cloned()
+    }
+
     /// Check for any errors we would encounter were we to try having the carrier carry the given unit
     fn carry_status(&self, carrier_unit_id: UnitID, carried_unit: &Unit) -> Result<(), GameError> {
         self.unit_by_id(carrier_unit_id)
@@ -791,7 +1055,7 @@ impl MapData {
 
         self.index_city(&city);
 
-        self.tiles[loc].city = Some(city);
+        Arc::make_mut(&mut self.tiles[loc]).city = Some(city);
 
         Ok(self.tiles[loc].city.as_ref().unwrap())
     }
@@ -805,7 +1069,7 @@ impl MapData {
     }
 
     fn city_by_loc_mut(&mut self, loc: Location) -> Option<&mut City> {
-        if let Some(tile) = self.tiles.get_mut(loc) {
+        if let Some(tile) = self.tile_mut(loc) {
             tile.city.as_mut()
         } else {
             None
@@ -818,7 +1082,7 @@ impl MapData {
     }
 
     pub fn pop_city_by_loc(&mut self, loc: Location) -> Option<City> {
-        if let Some(tile) = self.tiles.get_mut(loc) {
+        if let Some(tile) = self.tile_mut(loc) {
             let old_city = tile.city.take();
             if let Some(old_city) = old_city.as_ref() {
                 self.unindex_city(old_city);
@@ -838,11 +1102,12 @@ impl MapData {
     }
 
     pub fn tile(&self, loc: Location) -> Option<&Tile> {
-        LocationGridI::get(&self.tiles, loc)
+        LocationGridI::get(&self.tiles, loc).map(Arc::as_ref)
     }
 
+    /// The tile at `loc`, copied first if it's shared with another clone of the map
     fn tile_mut(&mut self, loc: Location) -> Option<&mut Tile> {
-        self.tiles.get_mut(loc)
+        self.tiles.get_mut(loc).map(Arc::make_mut)
     }
 
     // /// Destroy the top-level unit at `loc`
@@ -865,15 +1130,27 @@ impl MapData {
 
     //FIXME Use the `city_locs` index instead of scanning every tile in search of cities
     fn cities_mut(&mut self) -> impl Iterator<Item = &mut City> {
-        self.tiles.iter_mut().filter_map(|tile| tile.city.as_mut())
+        self.tiles
+            .iter_mut()
+            .filter(|tile| tile.city.is_some())
+            .filter_map(|tile| Arc::make_mut(tile).city.as_mut())
     }
 
+    /// All units not carried by another unit, whether at the top of a stack or beneath
     pub(crate) fn toplevel_units(&self) -> impl Iterator<Item = &Unit> {
-        self.tiles.iter().filter_map(|tile| tile.unit.as_ref())
+        self.tiles
+            .iter()
+            .flat_map(|tile| tile.unit.iter().chain(tile.stack.iter()))
     }
 
     fn toplevel_units_mut(&mut self) -> impl Iterator<Item = &mut Unit> {
-        self.tiles.iter_mut().filter_map(|tile| tile.unit.as_mut())
+        self.tiles
+            .iter_mut()
+            .filter(|tile| tile.unit.is_some() || !tile.stack.is_empty())
+            .flat_map(|tile| {
+                let tile = Arc::make_mut(tile);
+                tile.unit.iter_mut().chain(tile.stack.iter_mut())
+            })
     }
 
     pub(crate) fn units(&self) -> impl Iterator<Item = &Unit> {
@@ -1018,6 +1295,24 @@ impl MapData {
             .ok_or(GameError::NoSuchCity { id })
     }
 
+    /// Credit `amount` toward the production of the player's city at `loc`, if there is one
+    ///
+    /// Returns the amount actually credited.
+    pub fn credit_player_city_production(
+        &mut self,
+        player: PlayerNum,
+        loc: Location,
+        amount: u16,
+    ) -> u16 {
+        match self.player_city_by_loc_mut(player, loc) {
+            Some(city) => {
+                city.production_progress += amount;
+                amount
+            }
+            None => 0,
+        }
+    }
+
     pub fn clear_city_production_by_loc(
         &mut self,
         loc: Location,
@@ -1072,6 +1367,43 @@ impl MapData {
             .map(|city| city.set_production(production))
             .ok_or(GameError::NoSuchCity { id: city_id })
     }
+
+    /// Build another level of walls around the player's city, returning the new number of levels
+    pub fn build_player_city_walls(
+        &mut self,
+        player: PlayerNum,
+        city_id: CityID,
+    ) -> Result<u16, GameError> {
+        self.player_city_by_id_mut(player, city_id)
+            .ok_or(GameError::NoSuchCity { id: city_id })?
+            .build_walls()
+    }
+
+    /// Build a radar in the player's city out of the city's production, returning the city's location
+    pub fn build_player_city_radar(
+        &mut self,
+        player: PlayerNum,
+        city_id: CityID,
+    ) -> Result<Location, GameError> {
+        let loc = self
+            .player_city_by_id_mut(player, city_id)
+            .ok_or(GameError::NoSuchCity { id: city_id })?
+            .loc;
+
+        if self.tile(loc).unwrap().has_improvement(Improvement::Radar) {
+            return Err(GameError::CannotImprove {
+                loc,
+                improvement: Improvement::Radar,
+            });
+        }
+
+        self.player_city_by_id_mut(player, city_id)
+            .unwrap()
+            .spend_production(conf::CITY_RADAR_COST)?;
+        self.build_radar(loc, player)?;
+
+        Ok(loc)
+    }
 }
 
 impl Dimensioned for MapData {
@@ -1084,6 +1416,51 @@ impl Source<Tile> for MapData {
     fn get(&self, loc: Location) -> Option<&Tile> {
         self.tile(loc)
     }
+
+    /// Render the map in the text format read by `MapData::try_from`
+    ///
+    /// The format can't express everything a map holds, so some detail is lost. Each tile keeps at most its city,
+    /// if that belongs to players 0 through 9, or else its top-level unit, if that belongs to player 0 or 1.
+    /// Neutral cities, other units, improvements, minefields, names, and production are dropped.
+    pub fn to_text(&self) -> String {
+        let dims = self.dims();
+        let mut text = String::with_capacity((dims.width as usize + 1) * dims.height as usize);
+
+        for y in 0..dims.height {
+            for x in 0..dims.width {
+                let tile = self.get(Location { x, y }).unwrap();
+
+                let city_char = tile.city.as_ref().and_then(|city| match city.alignment {
+                    Alignment::Belligerent { player } => char::from_digit(player as u32, 10),
+                    Alignment::Neutral => None,
+                });
+
+                let unit_char = tile.unit.as_ref().and_then(|unit| match unit.alignment {
+                    Alignment::Belligerent { player: 0 } => Some(unit.type_.key()),
+                    Alignment::Belligerent { player: 1 } => {
+                        Some(unit.type_.key().to_ascii_uppercase())
+                    }
+                    _ => None,
+                });
+
+                text.push(
+                    city_char
+                        .or(unit_char)
+                        .unwrap_or(match (tile.river, tile.terrain) {
+                            (Some(River::Channel), _) => '=',
+                            (Some(River::Ford), _) => '+',
+                            (None, Terrain::Water) => ' ',
+                            (None, Terrain::Land) => '-',
+                            (None, Terrain::Forest) => '%',
+                            (None, Terrain::Mountains) => '^',
+                        }),
+                );
+            }
+            text.push('\n');
+        }
+
+        text
+    }
 }
 
 impl Debug for MapData {
@@ -1135,9 +1512,14 @@ impl TryFrom<String> for MapData {
 
 #[cfg(test)]
 mod test {
+    use std::sync::Arc;
+
     use rand::distributions::Distribution;
 
-    use super::{gen::MapType, MapData};
+    use super::{
+        gen::{MapType, Symmetry},
+        MapData,
+    };
     use crate::{
         game::{
             map::{terrain::Terrain, CityID, LocationGridI},
@@ -1177,6 +1559,18 @@ mod test {
         assert_eq!(map.next_unit_id, UnitID::new(6));
     }
 
+    #[test]
+    pub fn test_to_text() {
+        let text = "i-% 0\nA=+^1\n";
+        let mut map = MapData::try_from(text).unwrap();
+        assert_eq!(map.to_text(), text);
+
+        // Neutral cities can't be written, leaving only the land beneath
+        map.set_city_alignment_by_loc(Location::new(4, 0), Alignment::Neutral)
+            .unwrap();
+        assert_eq!(map.to_text(), "i-% -\nA=+^1\n");
+    }
+
     #[test]
     pub fn test_map_data() {
         let mut map = MapData::new(Dims::new(10, 10), |_| Terrain::Land);
@@ -1201,6 +1595,39 @@ mod test {
         assert!(!map.player_units(0).any(|unit| unit.id == unit_id));
     }
 
+    #[test]
+    pub fn test_stacking() {
+        let mut map = MapData::try_from("iaI-").unwrap();
+        let infantry_id = map.toplevel_unit_id_by_loc(Location::new(0, 0)).unwrap();
+        let armor_loc = Location::new(1, 0);
+        let armor_id = map.toplevel_unit_id_by_loc(armor_loc).unwrap();
+        let enemy_id = map.toplevel_unit_id_by_loc(Location::new(2, 0)).unwrap();
+
+        assert_eq!(map.stack_unit_by_id(infantry_id, armor_loc), Ok(2));
+        assert_eq!(map.toplevel_unit_id_by_loc(armor_loc), Some(armor_id));
+        assert_eq!(map.toplevel_unit_by_loc(Location::new(0, 0)), None);
+        assert_eq!(map.unit_loc(infantry_id), Some(armor_loc));
+        assert_eq!(map.player_units(0).count(), 2);
+
+        assert_eq!(
+            map.stack_unit_by_id(enemy_id, armor_loc),
+            Err(GameError::CannotStackUnit {
+                id: enemy_id,
+                loc: armor_loc
+            })
+        );
+
+        // The infantry rises to the top when the armor leaves
+        map.relocate_unit_by_id(armor_id, Location::new(3, 0))
+            .unwrap();
+        assert_eq!(map.toplevel_unit_id_by_loc(armor_loc), Some(infantry_id));
+        assert!(map.tile(armor_loc).unwrap().stack.is_empty());
+
+        map.pop_unit_by_id(infantry_id).unwrap();
+        assert_eq!(map.toplevel_unit_by_loc(armor_loc), None);
+        assert_eq!(map.units().count(), 2);
+    }
+
     #[test]
     pub fn test_new_and_pop() {
         let mut rng = init_rng(None);
@@ -1211,7 +1638,13 @@ mod test {
                 MapType::Continents.generate(&mut rng, Dims::new(180, 90), 1, &mut city_namer);
 
             for i in 0..100 {
-                let loc = map.dims().sample(&mut rng);
+                // Neutral cities come garrisoned, so find somewhere that's free
+                let loc = loop {
+                    let loc = map.dims().sample(&mut rng);
+                    if map.toplevel_unit_by_loc(loc).is_none() {
+                        break loc;
+                    }
+                };
 
                 let name = format!("Unit {}", i);
 
@@ -1247,6 +1680,112 @@ mod test {
         }
     }
 
+    #[test]
+    pub fn test_map_types() {
+        let mut rng = init_rng(Some(8));
+        let mut city_namer = IntNamer::new("city");
+        let dims = Dims::new(60, 40);
+        let center = Location::new(30, 20);
+        let corners = [
+            Location::new(0, 0),
+            Location::new(59, 0),
+            Location::new(0, 39),
+            Location::new(59, 39),
+        ];
+
+        for spec in ["a", "p", "i", "n"] {
+            let map_type = MapType::try_from(spec).unwrap();
+            assert_eq!(map_type.to_string(), spec);
+
+            let map = map_type.generate(&mut rng, dims, 2, &mut city_namer);
+            assert_eq!(map.player_cities(0).count(), 1);
+            assert_eq!(map.player_cities(1).count(), 1);
+
+            let land = map
+                .iter_locs()
+                .filter(|loc| map.terrain(*loc).unwrap().is_land())
+                .count();
+            assert!(land > 0 && land < dims.area() as usize);
+
+            let center_is_land = map.terrain(center).unwrap().is_land();
+            match map_type {
+                MapType::Pangaea => assert!(center_is_land),
+                MapType::InlandSea => {
+                    assert!(!center_is_land);
+                    for corner in corners {
+                        assert!(map.terrain(corner).unwrap().is_land());
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+
+    #[test]
+    pub fn test_noise_map_deterministic() {
+        let generate = |seed| {
+            MapType::Noise.generate(
+                &mut init_rng(Some(seed)),
+                Dims::new(40, 30),
+                2,
+                &mut IntNamer::new("city"),
+            )
+        };
+
+        assert_eq!(generate(3).to_text(), generate(3).to_text());
+        assert_ne!(generate(3).to_text(), generate(4).to_text());
+    }
+
+    #[test]
+    pub fn test_symmetric_map() {
+        let mut rng = init_rng(Some(21));
+        let mut city_namer = IntNamer::new("city");
+
+        // Mirrored left to right for two players
+        let dims = Dims::new(30, 20);
+        let map = MapType::Continents.generate_with_symmetry(
+            &mut rng,
+            dims,
+            2,
+            &mut city_namer,
+            Some(Symmetry::Mirror),
+        );
+        let mirror = |loc: Location| Location::new(dims.width - 1 - loc.x, loc.y);
+        for loc in map.iter_locs() {
+            assert_eq!(map.terrain(loc), map.terrain(mirror(loc)));
+            assert_eq!(
+                map.city_by_loc(loc).is_some(),
+                map.city_by_loc(mirror(loc)).is_some()
+            );
+        }
+        let start0 = map.player_cities(0).next().unwrap().loc;
+        let start1 = map.player_cities(1).next().unwrap().loc;
+        assert_eq!(mirror(start0), start1);
+
+        // Quarter turns for four players
+        let dims = Dims::new(24, 24);
+        let map = MapType::Pangaea.generate_with_symmetry(
+            &mut rng,
+            dims,
+            4,
+            &mut city_namer,
+            Some(Symmetry::Rotational),
+        );
+        let turn = |loc: Location| Location::new(dims.width - 1 - loc.y, loc.x);
+        for loc in map.iter_locs() {
+            assert_eq!(map.terrain(loc), map.terrain(turn(loc)));
+        }
+        for player in 0..3 {
+            let start = map.player_cities(player).next().unwrap().loc;
+            let next_start = map.player_cities(player + 1).next().unwrap().loc;
+            assert_eq!(turn(start), next_start);
+        }
+
+        assert!(Symmetry::Mirror.check(Dims::new(30, 20), 5).is_err());
+        assert!(Symmetry::Rotational.check(Dims::new(30, 20), 3).is_err());
+        assert!(Symmetry::Rotational.check(Dims::new(30, 20), 2).is_ok());
+    }
+
     #[test]
     pub fn test_relocate() {
         let mut map = MapData::new(Dims::new(10, 10), |_| Terrain::Land);
@@ -1464,4 +2003,28 @@ mod test {
         // Now pop it
         assert_eq!(map.pop_toplevel_unit_by_id(infantry_id), None);
     }
+
+    #[test]
+    fn test_clone_copies_tiles_on_write() {
+        let map = MapData::try_from("it").unwrap();
+        let infantry_loc = Location::new(0, 0);
+        let transport_loc = Location::new(1, 0);
+        let infantry_id = map.toplevel_unit_id_by_loc(infantry_loc).unwrap();
+
+        let mut copy = map.clone();
+        copy.pop_unit_by_id(infantry_id).unwrap();
+
+        assert_eq!(map.toplevel_unit_id_by_loc(infantry_loc), Some(infantry_id));
+        assert_eq!(copy.toplevel_unit_id_by_loc(infantry_loc), None);
+
+        // Only the tile changed was copied
+        assert!(!Arc::ptr_eq(
+            &map.tiles[infantry_loc],
+            &copy.tiles[infantry_loc]
+        ));
+        assert!(Arc::ptr_eq(
+            &map.tiles[transport_loc],
+            &copy.tiles[transport_loc]
+        ));
+    }
 }
@@ -2,7 +2,7 @@
 
 use std::{
     cmp::Ordering,
-    collections::{BTreeSet, VecDeque},
+    collections::{BTreeMap, BTreeSet, BinaryHeap, VecDeque},
     fmt,
     marker::PhantomData,
     ops::{Index, IndexMut},
@@ -89,6 +89,76 @@ impl ShortestPaths {
             dir
         })
     }
+
+    /// Bring the paths up to date after the tiles at `changed` were altered in `tiles`
+    ///
+    /// Locations whose shortest paths ran through a changed tile lose their distances, and are then searched again
+    /// from the locations around them whose distances still hold, as are the changed tiles themselves in case they
+    /// opened up a shorter way. The rest of the paths are left alone, which is much cheaper than starting over when
+    /// only a few tiles changed. `filter`, `cost`, `wrapping`, and `max_dist` must be those the paths were found with.
+    ///
+    /// Distances come out as a fresh search would find them, though where paths tie a different one may be kept.
+    pub fn repair<T, F: Filter<T>, C: Cost<T>, S: Source<T>>(
+        &mut self,
+        tiles: &S,
+        changed: &[Location],
+        filter: &F,
+        cost: &C,
+        wrapping: Wrap2d,
+        max_dist: u16,
+    ) {
+        // Where each location's shortest path leads on to next
+        let mut next: BTreeMap<Location, Vec<Location>> = BTreeMap::new();
+        for loc in self.prev.iter_locs() {
+            next.entry(self.prev[loc]).or_default().push(loc);
+        }
+
+        // The changed tiles and every location reached through them
+        let mut invalidated: BTreeSet<Location> = BTreeSet::new();
+        let mut stack: Vec<Location> = changed
+            .iter()
+            .copied()
+            .filter(|loc| *loc != self.start_loc && self.dist.get(*loc).is_some())
+            .collect();
+        while let Some(loc) = stack.pop() {
+            if invalidated.insert(loc) {
+                stack.extend(next.get(&loc).into_iter().flatten().copied());
+            }
+        }
+
+        for loc in invalidated.iter() {
+            self.dist.remove(*loc);
+            self.prev.remove(*loc);
+        }
+
+        // Search onward from every location with a known distance that borders the changes
+        let mut q = VecDeque::new();
+        let mut seeded: BTreeSet<Location> = BTreeSet::new();
+        for loc in invalidated.iter().chain(changed.iter()) {
+            for neighb_loc in neighbors_iter(tiles, *loc, RELATIVE_NEIGHBORS.iter(), &All, wrapping)
+            {
+                if let Some(dist_) = self.dist.get(neighb_loc).cloned() {
+                    if seeded.insert(neighb_loc) {
+                        q.push_back(State {
+                            dist_,
+                            loc: neighb_loc,
+                        });
+                    }
+                }
+            }
+        }
+
+        relax(
+            tiles,
+            q,
+            &mut self.dist,
+            &mut self.prev,
+            filter,
+            cost,
+            wrapping,
+            max_dist,
+        );
+    }
 }
 
 static DIRECTIONS: [Direction; 8] = Direction::values();
@@ -150,6 +220,21 @@ impl<'a> Filter<Obs> for UnitMovementFilter<'a> {
     }
 }
 
+/// Like `UnitMovementFilter`, but also excludes tiles that would cost more movement than the unit has remaining
+pub struct UnitMovementAffordableFilter<'a> {
+    pub unit: &'a Unit,
+}
+impl<'a> Filter<Obs> for UnitMovementAffordableFilter<'a> {
+    fn include(&self, obs: &Obs) -> bool {
+        if let Obs::Observed { tile, .. } = obs {
+            self.unit.can_move_on_tile(tile)
+                && self.unit.type_.tile_movement_cost(tile) <= self.unit.moves_remaining()
+        } else {
+            false
+        }
+    }
+}
+
 /// Include tiles that a unit could attack if adjacent
 pub struct UnitAttackFilter<'a> {
     pub unit: &'a Unit,
@@ -198,7 +283,7 @@ impl<'a> Filter<Obs> for PacifistXenophileUnitMovementFilter<'a> {
         if let Obs::Observed { tile, .. } = obs {
             if let Some(ref unit) = tile.unit {
                 if unit.is_friendly_to(self.unit) {
-                    return unit.can_carry_unit(self.unit);
+                    return unit.can_carry_unit(self.unit) || tile.can_stack(self.unit);
                 }
 
                 return false;
@@ -255,6 +340,40 @@ impl<'a> Filter<Obs> for ObservedReachableByPacifistUnit<'a> {
     }
 }
 
+/// The cost of stepping onto an item during a weighted path search
+pub trait Cost<T> {
+    fn cost(&self, item: &T) -> u16;
+}
+
+/// Every step costs 1
+pub struct UniformCost;
+impl<T> Cost<T> for UniformCost {
+    fn cost(&self, _item: &T) -> u16 {
+        1
+    }
+}
+
+/// Each step costs the movement points a unit of the given type would spend entering the tile
+///
+/// Unobserved tiles are assumed to cost 1.
+pub struct UnitMovementCost {
+    pub unit_type: UnitType,
+}
+impl Cost<Tile> for UnitMovementCost {
+    fn cost(&self, tile: &Tile) -> u16 {
+        self.unit_type.tile_movement_cost(tile)
+    }
+}
+impl Cost<Obs> for UnitMovementCost {
+    fn cost(&self, obs: &Obs) -> u16 {
+        if let Obs::Observed { tile, .. } = obs {
+            Cost::<Tile>::cost(self, tile)
+        } else {
+            1
+        }
+    }
+}
+
 pub struct TerrainFilter {
     pub terrain: Terrain,
 }
@@ -670,7 +789,7 @@ pub fn directions_unit_could_move_iter<'a, S: Source<Obs>>(
     let loc = unit.loc;
     // let neighb_iter = RELATIVE_NEIGHBORS.iter();
     // let dir_iter = Direction::values().iter();
-    let filter = UnitMovementFilter { unit };
+    let filter = UnitMovementAffordableFilter { unit };
     directions_iter_owned_filter(tiles, loc, DIRECTIONS.iter(), filter, wrapping)
 }
 
@@ -703,6 +822,8 @@ impl PartialOrd for State {
 /// by the filter `filter`. The returned `ShortestPaths` object can then be queried for the shortest path
 /// to any particular destination.
 ///
+/// Every step counts as a distance of 1; see `shortest_paths_weighted` to account for terrain.
+///
 /// The provided wrapping strategy is respected.
 ///
 /// # Arguments
@@ -714,6 +835,18 @@ pub fn shortest_paths<T, F: Filter<T>, S: Source<T>>(
     filter: &F,
     wrapping: Wrap2d,
     max_dist: u16,
+) -> ShortestPaths {
+    shortest_paths_weighted(tiles, source, filter, &UniformCost, wrapping, max_dist)
+}
+
+/// Like `shortest_paths`, but stepping onto each location adds its cost under `cost` to the distance rather than 1
+pub fn shortest_paths_weighted<T, F: Filter<T>, C: Cost<T>, S: Source<T>>(
+    tiles: &S,
+    source: Location,
+    filter: &F,
+    cost: &C,
+    wrapping: Wrap2d,
+    max_dist: u16,
 ) -> ShortestPaths {
     let mut q = VecDeque::new();
 
@@ -727,6 +860,29 @@ pub fn shortest_paths<T, F: Filter<T>, S: Source<T>>(
 
     dist.replace(source, 0);
 
+    relax(
+        tiles, q, &mut dist, &mut prev, filter, cost, wrapping, max_dist,
+    );
+
+    ShortestPaths {
+        start_loc: source,
+        dist,
+        prev,
+    }
+}
+
+/// Improve `dist` and `prev` by searching onward from the locations queued in `q` until no better paths turn up
+#[allow(clippy::too_many_arguments)]
+fn relax<T, F: Filter<T>, C: Cost<T>, S: Source<T>>(
+    tiles: &S,
+    mut q: VecDeque<State>,
+    dist: &mut SparseLocationGrid<u16>,
+    prev: &mut SparseLocationGrid<Location>,
+    filter: &F,
+    cost: &C,
+    wrapping: Wrap2d,
+    max_dist: u16,
+) {
     while let Some(State { dist_, loc }) = q.pop_front() {
         // Quit early since we're already doing worse than the best known route
         if let Some(dist) = dist.get(loc) {
@@ -737,7 +893,7 @@ pub fn shortest_paths<T, F: Filter<T>, S: Source<T>>(
 
         // for neighb_loc in neighbors(tiles, loc, RELATIVE_NEIGHBORS.iter(), filter, wrapping) {
         for neighb_loc in neighbors_iter(tiles, loc, RELATIVE_NEIGHBORS.iter(), filter, wrapping) {
-            let new_dist = dist_ + 1;
+            let new_dist = dist_.saturating_add(cost.cost(tiles.get(neighb_loc).unwrap()));
 
             if new_dist > max_dist {
                 continue; //NOTE we might be able to just return here
@@ -756,6 +912,79 @@ pub fn shortest_paths<T, F: Filter<T>, S: Source<T>>(
             }
         }
     }
+}
+
+/// An implementation of A*, finding the shortest path from `source` to `dest` alone
+///
+/// Like `shortest_paths_weighted`, but the search is steered toward `dest` by the number of steps left to it, and
+/// ends as soon as it gets there. Far fewer locations are visited on a large map as a result. The shortest path to
+/// `dest` is in the returned `ShortestPaths`, but distances to other locations may be missing or overestimated. If
+/// `dest` can't be reached, every reachable location ends up with its true distance, just as from
+/// `shortest_paths_weighted`.
+///
+/// The steps left are only a lower bound on the distance left if every step costs at least 1 under `cost`, as with
+/// `UniformCost` and `UnitMovementCost`.
+pub fn shortest_path_astar<T, F: Filter<T>, C: Cost<T>, S: Source<T>>(
+    tiles: &S,
+    source: Location,
+    dest: Location,
+    filter: &F,
+    cost: &C,
+    wrapping: Wrap2d,
+    max_dist: u16,
+) -> ShortestPaths {
+    let dims = tiles.dims();
+    let steps_to_dest = |loc: Location| {
+        let inc = wrapping.wrapped_sub(dims, loc, dest).unwrap();
+        inc.x.unsigned_abs().max(inc.y.unsigned_abs()) as u16
+    };
+
+    let mut q = BinaryHeap::new();
+
+    let mut dist: SparseLocationGrid<u16> = SparseLocationGrid::new(dims);
+    let mut prev: SparseLocationGrid<Location> = SparseLocationGrid::new(dims);
+
+    // Here each state's distance is the estimated length of the whole path through it
+    q.push(State {
+        dist_: steps_to_dest(source),
+        loc: source,
+    });
+
+    dist.replace(source, 0);
+
+    while let Some(State {
+        dist_: estimate,
+        loc,
+    }) = q.pop()
+    {
+        let dist_ = dist[loc];
+
+        // A better route to this location has been found since this state was queued
+        if estimate > dist_.saturating_add(steps_to_dest(loc)) {
+            continue;
+        }
+
+        if loc == dest {
+            break;
+        }
+
+        for neighb_loc in neighbors_iter(tiles, loc, RELATIVE_NEIGHBORS.iter(), filter, wrapping) {
+            let new_dist = dist_.saturating_add(cost.cost(tiles.get(neighb_loc).unwrap()));
+
+            if new_dist > max_dist {
+                continue;
+            }
+
+            if dist.get(neighb_loc).is_none() || new_dist < dist[neighb_loc] {
+                q.push(State {
+                    dist_: new_dist.saturating_add(steps_to_dest(neighb_loc)),
+                    loc: neighb_loc,
+                });
+                dist.replace(neighb_loc, new_dist);
+                prev.replace(neighb_loc, loc);
+            }
+        }
+    }
 
     ShortestPaths {
         start_loc: source,
@@ -820,7 +1049,7 @@ mod test {
 
     use crate::{
         game::{
-            map::{terrain::Terrain, LocationGrid, LocationGridI, Tile},
+            map::{terrain::Terrain, LocationGrid, LocationGridI, Tile, STACK_CAPACITY},
             obs::Obs,
             unit::{Unit, UnitID, UnitType},
             Alignment,
@@ -830,8 +1059,9 @@ mod test {
 
     use super::{
         nearest_adjacent_unobserved_reachable_without_attacking, neighbors, neighbors_terrain_only,
-        shortest_paths, All, Filter, PacifistXenophileUnitMovementFilter, Source,
-        UnitMovementFilter, Xenophile, RELATIVE_NEIGHBORS,
+        shortest_path_astar, shortest_paths, shortest_paths_weighted, All, Filter,
+        PacifistXenophileUnitMovementFilter, Source, UnitMovementCost, UnitMovementFilter,
+        Xenophile, RELATIVE_NEIGHBORS,
     };
 
     fn neighbors_all_unit<T: Source<Tile>>(
@@ -1126,6 +1356,166 @@ mod test {
         assert_eq!(shortest_both.dist[Location { x: 2, y: 2 }], 1);
     }
 
+    #[test]
+    fn test_dijkstra_rivers() {
+        let map: LocationGrid<Tile> = LocationGrid::try_from(
+            "\
+    *=x\n\
+    x+x\n\
+    x=x",
+        )
+        .unwrap();
+
+        let loc = Location { x: 0, y: 0 };
+
+        for (type_, ford_cost) in [(UnitType::Infantry, 1), (UnitType::Armor, 2)] {
+            let unit = Unit::new(
+                UnitID::new(0),
+                loc,
+                type_,
+                Alignment::Belligerent { player: 0 },
+                "Sacagawea",
+            );
+            let shortest = shortest_paths_weighted(
+                &map,
+                loc,
+                &UnitMovementFilter::new(&unit),
+                &UnitMovementCost { unit_type: type_ },
+                Wrap2d::NEITHER,
+                std::u16::MAX,
+            );
+
+            // The river can only be crossed at the ford
+            assert_eq!(shortest.dist.get(Location { x: 1, y: 0 }), None);
+            assert_eq!(shortest.dist.get(Location { x: 1, y: 2 }), None);
+            assert_eq!(shortest.dist[Location { x: 1, y: 1 }], ford_cost);
+            assert_eq!(shortest.dist[Location { x: 2, y: 0 }], ford_cost + 1);
+            assert_eq!(
+                shortest.shortest_path(Location { x: 2, y: 0 }),
+                Some(vec![loc, Location { x: 1, y: 1 }, Location { x: 2, y: 0 }])
+            );
+        }
+    }
+
+    #[test]
+    fn test_shortest_path_astar() {
+        let dims = Dims::new(30, 30);
+
+        // A forest wall with a gap at the bottom
+        let map: LocationGrid<Tile> = LocationGrid::new(dims, |loc| {
+            let terrain = if loc.x == 10 && loc.y < 25 {
+                Terrain::Forest
+            } else {
+                Terrain::Land
+            };
+            Tile::new(terrain, loc)
+        });
+
+        let src = Location::new(5, 5);
+        let armor = Unit::new(
+            UnitID::new(0),
+            src,
+            UnitType::Armor,
+            Alignment::Belligerent { player: 0 },
+            "Gus Grissom",
+        );
+        let filter = UnitMovementFilter::new(&armor);
+        let cost = UnitMovementCost {
+            unit_type: UnitType::Armor,
+        };
+
+        for wrapping in [Wrap2d::NEITHER, Wrap2d::BOTH] {
+            let dijkstra = shortest_paths_weighted(&map, src, &filter, &cost, wrapping, u16::MAX);
+
+            for dest in [
+                Location::new(7, 5),
+                Location::new(20, 5),
+                Location::new(25, 28),
+                Location::new(29, 0),
+            ] {
+                let astar =
+                    shortest_path_astar(&map, src, dest, &filter, &cost, wrapping, u16::MAX);
+                assert_eq!(astar.dist.get(dest), dijkstra.dist.get(dest));
+                assert_eq!(
+                    astar
+                        .shortest_path(dest)
+                        .map(|path| (path[0], *path.last().unwrap())),
+                    Some((src, dest))
+                );
+            }
+
+            // Nearby destinations are found without searching the whole map
+            let astar = shortest_path_astar(
+                &map,
+                src,
+                Location::new(7, 5),
+                &filter,
+                &cost,
+                wrapping,
+                u16::MAX,
+            );
+            assert!(astar.dist.iter().count() * 4 < dijkstra.dist.iter().count());
+        }
+    }
+
+    #[test]
+    fn test_shortest_paths_repair() {
+        let dims = Dims::new(20, 20);
+
+        // A lake with a gap in its middle
+        let mut map: LocationGrid<Tile> = LocationGrid::new(dims, |loc| {
+            let terrain = if loc.x == 10 && loc.y != 10 {
+                Terrain::Water
+            } else {
+                Terrain::Land
+            };
+            Tile::new(terrain, loc)
+        });
+
+        let src = Location::new(3, 10);
+        let armor = Unit::new(
+            UnitID::new(0),
+            src,
+            UnitType::Armor,
+            Alignment::Belligerent { player: 0 },
+            "Ed White",
+        );
+        let filter = UnitMovementFilter::new(&armor);
+        let cost = UnitMovementCost {
+            unit_type: UnitType::Armor,
+        };
+
+        for wrapping in [Wrap2d::NEITHER, Wrap2d::BOTH] {
+            for max_dist in [12, u16::MAX] {
+                let mut paths =
+                    shortest_paths_weighted(&map, src, &filter, &cost, wrapping, max_dist);
+
+                // Close the gap, fill in part of the lake, and plant a forest on the way
+                let changed = [
+                    Location::new(10, 10),
+                    Location::new(10, 3),
+                    Location::new(6, 10),
+                ];
+                let original: Vec<Tile> = changed.iter().map(|loc| map[*loc].clone()).collect();
+                map.replace(changed[0], Tile::new(Terrain::Water, changed[0]));
+                map.replace(changed[1], Tile::new(Terrain::Land, changed[1]));
+                map.replace(changed[2], Tile::new(Terrain::Forest, changed[2]));
+
+                paths.repair(&map, &changed, &filter, &cost, wrapping, max_dist);
+
+                let fresh = shortest_paths_weighted(&map, src, &filter, &cost, wrapping, max_dist);
+                for loc in dims.iter_locs() {
+                    assert_eq!(paths.dist.get(loc), fresh.dist.get(loc), "at {}", loc);
+                    assert_eq!(paths.prev.get(loc).is_some(), fresh.prev.get(loc).is_some());
+                }
+
+                for (loc, tile) in changed.iter().zip(original) {
+                    map.replace(*loc, tile);
+                }
+            }
+        }
+    }
+
     #[test]
     fn test_shortest_paths() {
         let map: LocationGrid<Obs> = LocationGrid::try_from(
@@ -1222,7 +1612,11 @@ mod test {
         let filter = UnitMovementFilter::new(&u1);
 
         let mut tile = Tile::new(Terrain::Land, l2);
-        tile.unit = Some(u2);
+        tile.unit = Some(u2.clone());
+
+        assert!(filter.include(&tile));
+
+        tile.stack = vec![u2; STACK_CAPACITY - 1];
 
         assert!(!filter.include(&tile));
     }
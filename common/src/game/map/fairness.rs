@@ -0,0 +1,233 @@
+//! Fairness of the players' starting positions
+//!
+//! A start is scored by how much land its city can reach without crossing water, how far it is by land to the
+//! nearest neutral city, and how far it is from the nearest rival start. Starts are unbalanced when any of these
+//! differ too much from one player to the next.
+
+use std::fmt;
+
+use crate::{
+    conf,
+    game::{Alignment, PlayerNum},
+    util::{Location, Wrap2d},
+};
+
+use super::{
+    dijkstra::{shortest_paths, Filter},
+    MapData, Terrain, Tile,
+};
+
+/// Tiles a land unit could walk across
+struct OverLand;
+impl Filter<Tile> for OverLand {
+    fn include(&self, tile: &Tile) -> bool {
+        tile.terrain != Terrain::Water
+    }
+}
+
+/// The scores of one player's starting position
+#[derive(Clone, Debug, PartialEq)]
+pub struct StartScore {
+    pub player: PlayerNum,
+
+    /// Where the player's first city is
+    pub loc: Location,
+
+    /// The number of tiles reachable from the start without crossing water, the start included
+    pub land_area: usize,
+
+    /// The number of steps over land to the nearest neutral city, if any can be reached
+    pub nearest_neutral_city: Option<u16>,
+
+    /// The number of steps to the nearest rival's start as the crow flies, if there are rivals
+    pub isolation: Option<u16>,
+}
+
+impl fmt::Display for StartScore {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "Player {} at {}: {} land tiles",
+            self.player, self.loc, self.land_area
+        )?;
+        if let Some(dist) = self.nearest_neutral_city {
+            write!(f, ", neutral city {} away", dist)?;
+        }
+        if let Some(dist) = self.isolation {
+            write!(f, ", rival {} away", dist)?;
+        }
+        Ok(())
+    }
+}
+
+/// The scores of every player's starting position
+#[derive(Clone, Debug, PartialEq)]
+pub struct StartsReport {
+    pub starts: Vec<StartScore>,
+}
+
+impl StartsReport {
+    /// Descriptions of the ways the starts are unbalanced, if any
+    pub fn imbalances(&self) -> Vec<String> {
+        let mut imbalances = Vec::new();
+
+        let most = |score: fn(&StartScore) -> Option<u16>| {
+            self.starts
+                .iter()
+                .filter_map(|start| score(start).map(|s| (start.player, s)))
+                .max_by_key(|(_, s)| *s)
+        };
+        let least = |score: fn(&StartScore) -> Option<u16>| {
+            self.starts
+                .iter()
+                .filter_map(|start| score(start).map(|s| (start.player, s)))
+                .min_by_key(|(_, s)| *s)
+        };
+
+        let land = |start: &StartScore| Some(start.land_area.min(u16::MAX as usize) as u16);
+        if let (Some((most_player, most_land)), Some((least_player, least_land))) =
+            (most(land), least(land))
+        {
+            if (least_land as f64) < most_land as f64 * conf::FAIR_START_MIN_LAND_RATIO {
+                imbalances.push(format!(
+                    "Player {} can reach {} land tiles but player {} only {}",
+                    most_player, most_land, least_player, least_land
+                ));
+            }
+        }
+
+        if let Some(stranded) = self
+            .starts
+            .iter()
+            .find(|start| start.nearest_neutral_city.is_none())
+        {
+            if self
+                .starts
+                .iter()
+                .any(|start| start.nearest_neutral_city.is_some())
+            {
+                imbalances.push(format!(
+                    "Player {} can reach no neutral city by land but others can",
+                    stranded.player
+                ));
+            }
+        } else if let (Some((far_player, far)), Some((near_player, near))) = (
+            most(|start| start.nearest_neutral_city),
+            least(|start| start.nearest_neutral_city),
+        ) {
+            if far - near > conf::FAIR_START_MAX_NEUTRAL_CITY_GAP {
+                imbalances.push(format!(
+                    "Player {}'s nearest neutral city is {} steps away but player {}'s only {}",
+                    far_player, far, near_player, near
+                ));
+            }
+        }
+
+        if let (Some((far_player, far)), Some((near_player, near))) = (
+            most(|start| start.isolation),
+            least(|start| start.isolation),
+        ) {
+            if (near as f64) < far as f64 * conf::FAIR_START_MIN_ISOLATION_RATIO {
+                imbalances.push(format!(
+                    "Player {} is {} steps from the nearest rival but player {} only {}",
+                    far_player, far, near_player, near
+                ));
+            }
+        }
+
+        imbalances
+    }
+
+    pub fn is_balanced(&self) -> bool {
+        self.imbalances().is_empty()
+    }
+}
+
+/// Score each player's starting position on `map`
+///
+/// A player's start is taken to be their first city; players without cities are left out. Distances to rivals respect
+/// `wrapping`.
+pub fn assess_starts(map: &MapData, wrapping: Wrap2d) -> StartsReport {
+    let dims = map.dims();
+
+    let start_locs: Vec<(PlayerNum, Location)> = (0..map.players())
+        .filter_map(|player| {
+            map.player_cities(player)
+                .next()
+                .map(|city| (player, city.loc))
+        })
+        .collect();
+
+    let starts = start_locs
+        .iter()
+        .map(|(player, loc)| {
+            let paths = shortest_paths(map, *loc, &OverLand, wrapping, u16::MAX);
+
+            let nearest_neutral_city = paths
+                .dist
+                .iter_locs()
+                .filter(|reached| {
+                    map.city_by_loc(*reached)
+                        .map_or(false, |city| city.alignment == Alignment::Neutral)
+                })
+                .map(|reached| paths.dist[reached])
+                .min();
+
+            let isolation = start_locs
+                .iter()
+                .filter(|(rival, _)| rival != player)
+                .filter_map(|(_, rival_loc)| wrapping.wrapped_sub(dims, *loc, *rival_loc).ok())
+                .map(|inc| inc.x.unsigned_abs().max(inc.y.unsigned_abs()) as u16)
+                .min();
+
+            StartScore {
+                player: *player,
+                loc: *loc,
+                land_area: paths.dist.iter().count(),
+                nearest_neutral_city,
+                isolation,
+            }
+        })
+        .collect();
+
+    StartsReport { starts }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        game::{map::MapData, Alignment},
+        util::{Location, Wrap2d},
+    };
+
+    use super::assess_starts;
+
+    #[test]
+    fn test_assess_starts() {
+        let mut map = MapData::try_from("0--2-- -2-1").unwrap();
+        for x in [3, 8] {
+            map.set_city_alignment_by_loc(Location::new(x, 0), Alignment::Neutral)
+                .unwrap();
+        }
+
+        let report = assess_starts(&map, Wrap2d::NEITHER);
+        assert_eq!(report.starts.len(), 2);
+
+        let (start0, start1) = (&report.starts[0], &report.starts[1]);
+        assert_eq!(start0.land_area, 6);
+        assert_eq!(start0.nearest_neutral_city, Some(3));
+        assert_eq!(start0.isolation, Some(10));
+        assert_eq!(start1.land_area, 4);
+        assert_eq!(start1.nearest_neutral_city, Some(2));
+        assert!(report.is_balanced());
+
+        // Wrapping brings the rivals next to each other
+        let report = assess_starts(&map, Wrap2d::BOTH);
+        assert_eq!(report.starts[0].isolation, Some(1));
+
+        let map = MapData::try_from("0------ -1").unwrap();
+        let report = assess_starts(&map, Wrap2d::NEITHER);
+        assert_eq!(report.starts[1].land_area, 2);
+        assert_eq!(report.imbalances().len(), 1);
+    }
+}
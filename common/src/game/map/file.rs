@@ -0,0 +1,318 @@
+//! Map files
+//!
+//! Two formats are read. The plain format is the one parsed by `MapData::try_from`: a single grid of characters in
+//! which a city or unit hides the terrain beneath it. The layered format splits the map into a `[terrain]` grid and
+//! optional `[cities]` and `[units]` grids of the same size, so that cities can stand in forests, units can wait on
+//! rivers, and cities can be neutral. Lines starting with `#` are comments in the layered format, and empty lines, as
+//! between sections, are skipped. A row of water is spaces, so it's never empty.
+//!
+//! In the `[terrain]` grid, ' ' is water, '%' forest, '^' mountains, '=' a river channel, '+' a ford, and anything
+//! else land. In the `[cities]` grid, a digit is a city belonging to that player and 'n' a neutral city. In the
+//! `[units]` grid, a unit type's key is a unit of player 0, and the key in uppercase a unit of player 1. Anything else
+//! in those two grids leaves the tile empty.
+//!
+//! Maps are written in the layered format, as it keeps everything the plain format does and more.
+
+use std::{fs, path::Path};
+
+use thiserror::Error;
+
+use crate::{
+    game::{unit::UnitType, Alignment, PlayerNum},
+    util::{Dims, Location},
+};
+
+use super::{MapData, River, Terrain};
+
+#[derive(Debug, Error)]
+pub enum MapFileError {
+    #[error("Error reading map file: {0}")]
+    Io(#[from] std::io::Error),
+
+    #[error("Error parsing map: {0}")]
+    Parse(String),
+
+    #[error("Error parsing the [{section}] section of map: {msg}")]
+    Section { section: String, msg: String },
+}
+
+/// Read a map file in either the plain or the layered format
+pub fn load<P: AsRef<Path>>(path: P) -> Result<MapData, MapFileError> {
+    parse(fs::read_to_string(path)?.as_str())
+}
+
+/// Parse the contents of a map file in either the plain or the layered format
+pub fn parse(text: &str) -> Result<MapData, MapFileError> {
+    let mut lines = text
+        .lines()
+        .filter(|line| !line.is_empty() && !line.starts_with('#'));
+
+    match lines.next() {
+        Some("[terrain]") => parse_layered(text),
+        _ => MapData::try_from(text).map_err(MapFileError::Parse),
+    }
+}
+
+/// Write `map` in the layered format
+///
+/// Cities of players above 9 and units of players above 1 have no character of their own, and are left out.
+pub fn to_layered_text(map: &MapData) -> String {
+    let dims = map.dims();
+
+    let grid = |text: &mut String, section: &str, tile_char: &dyn Fn(Location) -> char| {
+        text.push_str(&format!("[{}]\n", section));
+        for y in 0..dims.height {
+            text.extend((0..dims.width).map(|x| tile_char(Location::new(x, y))));
+            text.push('\n');
+        }
+    };
+
+    let mut text = String::new();
+
+    grid(&mut text, "terrain", &|loc| {
+        let tile = map.tile(loc).unwrap();
+        match (tile.river, tile.terrain) {
+            (Some(River::Channel), _) => '=',
+            (Some(River::Ford), _) => '+',
+            (None, Terrain::Water) => ' ',
+            (None, Terrain::Land) => '-',
+            (None, Terrain::Forest) => '%',
+            (None, Terrain::Mountains) => '^',
+        }
+    });
+
+    grid(
+        &mut text,
+        "cities",
+        &|loc| match map.city_by_loc(loc).map(|city| city.alignment) {
+            Some(Alignment::Neutral) => 'n',
+            Some(Alignment::Belligerent { player }) => {
+                char::from_digit(player as u32, 10).unwrap_or('.')
+            }
+            None => '.',
+        },
+    );
+
+    grid(&mut text, "units", &|loc| {
+        let unit = map.tile(loc).unwrap().unit.as_ref();
+        match unit.map(|unit| (unit.type_.key(), unit.alignment)) {
+            Some((key, Alignment::Belligerent { player: 0 })) => key,
+            Some((key, Alignment::Belligerent { player: 1 })) => key.to_ascii_uppercase(),
+            _ => '.',
+        }
+    });
+
+    text
+}
+
+/// The grids of a layered map file, by section name
+fn sections(text: &str) -> Result<Vec<(&str, Vec<Vec<char>>)>, MapFileError> {
+    let mut sections: Vec<(&str, Vec<Vec<char>>)> = Vec::new();
+
+    for line in text
+        .lines()
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+    {
+        if let Some(name) = line.strip_prefix('[').and_then(|s| s.strip_suffix(']')) {
+            if sections.iter().any(|(prior, _)| *prior == name) {
+                return Err(MapFileError::Parse(format!(
+                    "The [{}] section appears more than once",
+                    name
+                )));
+            }
+            sections.push((name, Vec::new()));
+        } else if let Some((_, rows)) = sections.last_mut() {
+            rows.push(line.chars().collect());
+        }
+    }
+
+    Ok(sections)
+}
+
+/// Check that every row of `rows` matches `dims`
+fn check_dims(section: &str, rows: &[Vec<char>], dims: Dims) -> Result<(), MapFileError> {
+    let err = |msg: String| MapFileError::Section {
+        section: section.to_string(),
+        msg,
+    };
+
+    if rows.len() != dims.height as usize {
+        return Err(err(format!(
+            "Expected {} rows but found {}",
+            dims.height,
+            rows.len()
+        )));
+    }
+
+    if let Some(row) = rows.iter().find(|row| row.len() != dims.width as usize) {
+        return Err(err(format!(
+            "Expected rows of width {} but found one of width {}",
+            dims.width,
+            row.len()
+        )));
+    }
+
+    Ok(())
+}
+
+fn parse_layered(text: &str) -> Result<MapData, MapFileError> {
+    let sections = sections(text)?;
+
+    let terrain = &sections[0].1;
+    let width = terrain.first().map_or(0, |row| row.len());
+    if width == 0 {
+        return Err(MapFileError::Section {
+            section: String::from("terrain"),
+            msg: String::from("No tiles were provided"),
+        });
+    }
+    let dims = Dims::new(width as u16, terrain.len() as u16);
+    check_dims("terrain", terrain, dims)?;
+
+    let mut map = MapData::new(dims, |loc| match terrain[loc.y as usize][loc.x as usize] {
+        ' ' => Terrain::Water,
+        '%' => Terrain::Forest,
+        '^' => Terrain::Mountains,
+        _ => Terrain::Land,
+    });
+    for loc in dims.iter_locs() {
+        let river = match terrain[loc.y as usize][loc.x as usize] {
+            '=' => Some(River::Channel),
+            '+' => Some(River::Ford),
+            _ => None,
+        };
+        map.set_river(loc, river).unwrap();
+    }
+
+    for (section, rows) in sections.iter().skip(1) {
+        check_dims(section, rows, dims)?;
+
+        let err = |msg: String| MapFileError::Section {
+            section: section.to_string(),
+            msg,
+        };
+
+        for loc in dims.iter_locs() {
+            let c = rows[loc.y as usize][loc.x as usize];
+
+            match *section {
+                "cities" => {
+                    let alignment = if c == 'n' {
+                        Alignment::Neutral
+                    } else if let Some(player) = c.to_digit(10) {
+                        Alignment::Belligerent {
+                            player: player as PlayerNum,
+                        }
+                    } else {
+                        continue;
+                    };
+
+                    map.new_city(loc, alignment, format!("City_{}_{}", loc.x, loc.y))
+                        .map_err(err)?;
+                }
+                "units" => {
+                    let (type_, player) = match UnitType::try_from_key(c) {
+                        Ok(type_) => (type_, 0),
+                        Err(_) => match UnitType::try_from_key(c.to_ascii_lowercase()) {
+                            Ok(type_) => (type_, 1),
+                            Err(_) => continue,
+                        },
+                    };
+
+                    map.new_unit(
+                        loc,
+                        type_,
+                        Alignment::Belligerent { player },
+                        format!("Unit_{}_{}", loc.x, loc.y),
+                    )
+                    .map_err(|e| err(e.to_string()))?;
+                }
+                _ => return Err(err(String::from("Not a known section"))),
+            }
+        }
+    }
+
+    Ok(map)
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        game::{
+            map::{River, Terrain},
+            unit::UnitType,
+            Alignment,
+        },
+        util::Location,
+    };
+
+    use super::{parse, to_layered_text, MapFileError};
+
+    #[test]
+    fn test_parse_layered() {
+        let map = parse(
+            "# A river crossing\n\
+             [terrain]\n\
+             %%=--\n\
+             ^-+  \n\
+             [cities]\n\
+             0...n\n\
+             .....\n\
+             [units]\n\
+             .....\n\
+             ..A..\n",
+        )
+        .unwrap();
+
+        let forest_city = map.city_by_loc(Location::new(0, 0)).unwrap();
+        assert_eq!(forest_city.alignment, Alignment::Belligerent { player: 0 });
+        assert_eq!(map.terrain(Location::new(0, 0)), Some(&Terrain::Forest));
+
+        let neutral_city = map.city_by_loc(Location::new(4, 0)).unwrap();
+        assert_eq!(neutral_city.alignment, Alignment::Neutral);
+
+        let ford = map.tile(Location::new(2, 1)).unwrap();
+        assert_eq!(ford.river, Some(River::Ford));
+        let armor = ford.unit.as_ref().unwrap();
+        assert_eq!(armor.type_, UnitType::Armor);
+        assert_eq!(armor.alignment, Alignment::Belligerent { player: 1 });
+
+        assert_eq!(map.terrain(Location::new(4, 1)), Some(&Terrain::Water));
+        assert_eq!(map.players(), 2);
+
+        let text = to_layered_text(&map);
+        assert_eq!(to_layered_text(&parse(&text).unwrap()), text);
+
+        // The plain format still works
+        let plain = parse("i-% 0\nA=+^1\n").unwrap();
+        assert_eq!(plain.to_text(), "i-% 0\nA=+^1\n");
+
+        assert!(matches!(
+            parse("[terrain]\n---\n---\n[cities]\n0..\n"),
+            Err(MapFileError::Section { section, .. }) if section == "cities"
+        ));
+        assert!(matches!(
+            parse("[terrain]\n---\n[weather]\n...\n"),
+            Err(MapFileError::Section { section, .. }) if section == "weather"
+        ));
+    }
+
+    #[test]
+    fn test_parse_layered_blank_lines() {
+        let map = parse(
+            "\n\
+             [terrain]\n\
+             --\n\
+             \x20 \n\
+             \n\
+             [cities]\n\
+             0.\n\
+             ..\n\
+             \n",
+        )
+        .unwrap();
+
+        assert!(map.city_by_loc(Location::new(0, 0)).is_some());
+        assert_eq!(map.terrain(Location::new(1, 1)), Some(&Terrain::Water));
+    }
+}
@@ -2,7 +2,9 @@
 //! Map generation
 //!
 
-use std::fmt;
+mod noise;
+
+use std::{collections::BTreeSet, fmt};
 
 use rand::{distributions::Distribution, Rng, RngCore};
 
@@ -10,17 +12,20 @@ use crate::{
     conf,
     game::{Alignment, PlayerNum},
     name::Namer,
-    util::{Dims, Location, Wrap2d},
+    util::{Dims, Direction, Location, Wrap2d},
 };
 
 use super::{
     dijkstra::{
         neighbors, Source, TerrainFilter, RELATIVE_NEIGHBORS_CARDINAL, RELATIVE_NEIGHBORS_DIAGONAL,
     },
+    fairness::{self, StartsReport},
     terrain::Terrainous,
-    LocationGrid, MapData, Terrain,
+    LocationGrid, MapData, River, Terrain,
 };
 
+use self::noise::PerlinNoise;
+
 fn land_cardinal_neighbors<T: Terrainous, S: Source<T>>(tiles: &S, loc: Location) -> u16 {
     neighbors(
         tiles,
@@ -51,25 +56,40 @@ fn land_diagonal_neighbors<T: Terrainous, S: Source<T>>(tiles: &S, loc: Location
 //     neighbors(tiles, loc, RELATIVE_NEIGHBORS.iter(), &TerrainFilter{terrain: Terrain::Land}, WRAP_NEITHER).len() as u16
 // }
 
-fn generate_continents<R: RngCore>(rng: &mut R, map_dims: Dims) -> LocationGrid<Terrain> {
+/// Seed `landmasses` bits of land at random and grow them for `growth_iterations`
+fn generate_continents<R: RngCore>(
+    rng: &mut R,
+    map_dims: Dims,
+    landmasses: u16,
+    growth_iterations: u16,
+) -> LocationGrid<Terrain> {
     let mut grid = LocationGrid::new(map_dims, |_| Terrain::Water);
 
     // Seed the continents/islands
-    for _ in 0..conf::LANDMASSES {
+    for _ in 0..landmasses {
         let loc = map_dims.sample(rng);
 
         // This might overwrite an already-set terrain but it doesn't matter
         grid[loc] = Terrain::Land;
     }
 
+    grow_land(rng, &mut grid, growth_iterations);
+
+    grid
+}
+
+/// Grow land outward into the water, water tiles with more land around them being likelier to turn
+fn grow_land<R: RngCore>(rng: &mut R, grid: &mut LocationGrid<Terrain>, growth_iterations: u16) {
+    let map_dims = grid.dims();
+
     //FIXME by keeping an index of land locations and counts of cardinal/diagonal land neighbors this could probably be
     //      sped up substantially
 
     // Grow landmasses
-    for _iteration in 0..conf::GROWTH_ITERATIONS {
+    for _iteration in 0..growth_iterations {
         for loc in map_dims.iter_locs() {
             match grid[loc] {
-                Terrain::Land => {
+                Terrain::Land | Terrain::Forest | Terrain::Mountains => {
 
                     // for x2 in safe_minus_one(x)..(safe_plus_one(x, self.map_dims.width)+1) {
                     //     for y2 in safe_minus_one(y)..(safe_plus_one(y, self.map_dims.height)+1) {
@@ -82,9 +102,9 @@ fn generate_continents<R: RngCore>(rng: &mut R, map_dims: Dims) -> LocationGrid<
                     // }
                 }
                 Terrain::Water => {
-                    let cardinal_growth_prob = f32::from(land_cardinal_neighbors(&grid, loc))
+                    let cardinal_growth_prob = f32::from(land_cardinal_neighbors(grid, loc))
                         / (4_f32 + conf::GROWTH_CARDINAL_LAMBDA);
-                    let diagonal_growth_prob = f32::from(land_diagonal_neighbors(&grid, loc))
+                    let diagonal_growth_prob = f32::from(land_diagonal_neighbors(grid, loc))
                         / (4_f32 + conf::GROWTH_DIAGONAL_LAMBDA);
 
                     if rng.gen::<f32>() <= cardinal_growth_prob
@@ -97,10 +117,91 @@ fn generate_continents<R: RngCore>(rng: &mut R, map_dims: Dims) -> LocationGrid<
             }
         }
     }
+}
+
+/// Is `loc` within the ellipse at the center of the map whose radii are `radius` times half the map's width and height?
+fn in_central_ellipse(map_dims: Dims, loc: Location, radius: f64) -> bool {
+    let dx = (f64::from(loc.x) + 0.5) / f64::from(map_dims.width) * 2.0 - 1.0;
+    let dy = (f64::from(loc.y) + 0.5) / f64::from(map_dims.height) * 2.0 - 1.0;
+    dx * dx + dy * dy <= radius * radius
+}
+
+/// A single great landmass, grown outward from an elliptical core in the middle of the map
+fn generate_pangaea<R: RngCore>(rng: &mut R, map_dims: Dims) -> LocationGrid<Terrain> {
+    let mut grid = LocationGrid::new(map_dims, |loc| {
+        if in_central_ellipse(map_dims, loc, conf::PANGAEA_RADIUS) {
+            Terrain::Land
+        } else {
+            Terrain::Water
+        }
+    });
+
+    grow_land(rng, &mut grid, conf::GROWTH_ITERATIONS);
 
     grid
 }
 
+/// Land surrounding a sea in the middle of the map
+///
+/// The sea is shaped like a pangaea, then land and water are swapped.
+fn generate_inland_sea<R: RngCore>(rng: &mut R, map_dims: Dims) -> LocationGrid<Terrain> {
+    let mut sea = LocationGrid::new(map_dims, |loc| {
+        if in_central_ellipse(map_dims, loc, conf::INLAND_SEA_RADIUS) {
+            Terrain::Land
+        } else {
+            Terrain::Water
+        }
+    });
+
+    grow_land(rng, &mut sea, conf::GROWTH_ITERATIONS);
+
+    LocationGrid::new(map_dims, |loc| {
+        if sea[loc] == Terrain::Land {
+            Terrain::Water
+        } else {
+            Terrain::Land
+        }
+    })
+}
+
+/// Terrain following the contours of fractal noise, taken as elevation
+///
+/// The lowest-lying tiles are water and the highest mountains, with the sea level and tree line set so that fixed
+/// proportions of the map end up as land and mountains.
+fn generate_noise<R: RngCore>(rng: &mut R, map_dims: Dims) -> LocationGrid<Terrain> {
+    let noise = PerlinNoise::new(rng);
+
+    let elevation = LocationGrid::new(map_dims, |loc| {
+        noise.fractal(
+            f64::from(loc.x) / conf::NOISE_SCALE,
+            f64::from(loc.y) / conf::NOISE_SCALE,
+            conf::NOISE_OCTAVES,
+            conf::NOISE_PERSISTENCE,
+        )
+    });
+
+    let mut elevations: Vec<f64> = map_dims.iter_locs().map(|loc| elevation[loc]).collect();
+    elevations.sort_by(|a, b| a.total_cmp(b));
+
+    let quantile = |proportion: f64| {
+        let idx = ((1.0 - proportion) * elevations.len() as f64) as usize;
+        elevations[idx.min(elevations.len() - 1)]
+    };
+    let sea_level = quantile(conf::NOISE_LAND_PROPORTION);
+    let tree_line = quantile(conf::NOISE_MOUNTAINS_PROPORTION);
+
+    LocationGrid::new(map_dims, |loc| {
+        let elevation = elevation[loc];
+        if elevation >= tree_line {
+            Terrain::Mountains
+        } else if elevation >= sea_level {
+            Terrain::Land
+        } else {
+            Terrain::Water
+        }
+    })
+}
+
 fn generate_transport_required(
     map_dims: Dims,
     left_continent_rightmosts: Vec<u16>,
@@ -131,6 +232,78 @@ fn generate_random_terrain<R: RngCore>(
     })
 }
 
+/// Roughen some of the land into forest and mountains
+fn add_rough_terrain<R: RngCore>(rng: &mut R, grid: &mut LocationGrid<Terrain>) {
+    for loc in grid.dims().iter_locs() {
+        if grid[loc] == Terrain::Land {
+            let x = rng.gen::<f64>();
+            if x < conf::MOUNTAINS_DENSITY {
+                grid[loc] = Terrain::Mountains;
+            } else if x < conf::MOUNTAINS_DENSITY + conf::FOREST_DENSITY {
+                grid[loc] = Terrain::Forest;
+            }
+        }
+    }
+}
+
+/// Run rivers down from some of the mountains
+///
+/// Each river flows in a general direction, meandering side to side, until it reaches water or the edge of the map,
+/// runs into another river, or reaches its maximum length. Rivers only ever step in the cardinal directions so that
+/// ground units can't slip between two diagonally-adjacent river tiles; they must instead cross at one of the fords
+/// placed periodically along each river.
+fn add_rivers<R: RngCore>(rng: &mut R, map: &mut MapData) {
+    let sources: Vec<Location> = map
+        .dims()
+        .iter_locs()
+        .filter(|loc| map.terrain(*loc) == Some(&Terrain::Mountains))
+        .collect();
+
+    if sources.is_empty() {
+        return;
+    }
+
+    let cardinals = [
+        Direction::Up,
+        Direction::Down,
+        Direction::Left,
+        Direction::Right,
+    ];
+
+    for _ in 0..conf::RIVERS {
+        let mut loc = sources[rng.gen_range(0..sources.len())];
+        let dir = cardinals[rng.gen_range(0..cardinals.len())];
+        let sideways = match dir {
+            Direction::Up | Direction::Down => [Direction::Left, Direction::Right],
+            _ => [Direction::Up, Direction::Down],
+        };
+
+        for length in 0..conf::RIVER_MAX_LENGTH {
+            if map.tile(loc).unwrap().river.is_some() {
+                break;
+            }
+
+            let river = if length % conf::RIVER_FORD_INTERVAL == conf::RIVER_FORD_INTERVAL - 1 {
+                River::Ford
+            } else {
+                River::Channel
+            };
+            map.set_river(loc, Some(river)).unwrap();
+
+            let step = if rng.gen_bool(conf::RIVER_MEANDER_PROB) {
+                sideways[rng.gen_range(0..sideways.len())]
+            } else {
+                dir
+            };
+
+            match loc.shift_wrapped(step, map.dims(), Wrap2d::NEITHER) {
+                Some(next) if map.terrain(next).unwrap().is_land() => loc = next,
+                _ => break,
+            }
+        }
+    }
+}
+
 fn populate_player_cities<N: Namer, R: RngCore>(
     rng: &mut R,
     map: &mut MapData,
@@ -154,6 +327,8 @@ fn populate_player_cities<N: Namer, R: RngCore>(
     }
 }
 
+/// Populate neutral cities, each garrisoned by a neutral unit of type `conf::NEUTRAL_GARRISON_TYPE`
+///
 /// * land_only: Only place the cities on land
 fn populate_neutral_cities<N: Namer, R: RngCore>(
     rng: &mut R,
@@ -165,8 +340,76 @@ fn populate_neutral_cities<N: Namer, R: RngCore>(
     for loc in map.dims().iter_locs() {
         let land_ok = !land_only || map.terrain(loc).copied().unwrap() == Terrain::Land;
         if land_ok && map.city_by_loc(loc).is_none() && rng.gen_bool(conf::NEUTRAL_CITY_DENSITY) {
-            map.new_city(loc, Alignment::Neutral, city_namer.name())
-                .unwrap();
+            new_neutral_city(map, loc, city_namer);
+        }
+    }
+}
+
+/// Found a neutral city at `loc` with its garrison, if any
+fn new_neutral_city<N: Namer>(map: &mut MapData, loc: Location, city_namer: &mut N) {
+    let city_name = map
+        .new_city(loc, Alignment::Neutral, city_namer.name())
+        .unwrap()
+        .name()
+        .clone();
+
+    if let Some(garrison_type) = conf::NEUTRAL_GARRISON_TYPE {
+        map.new_unit(
+            loc,
+            garrison_type,
+            Alignment::Neutral,
+            format!("{} Garrison", city_name),
+        )
+        .unwrap();
+    }
+}
+
+/// Populate player and neutral cities, each city having a counterpart in every symmetric region of the map
+///
+/// Player cities are placed as images of one another, so each player's start looks the same as every other's.
+fn populate_symmetric_cities<N: Namer, R: RngCore>(
+    rng: &mut R,
+    map: &mut MapData,
+    players: PlayerNum,
+    city_namer: &mut N,
+    symmetry: Symmetry,
+    land_only: bool,
+) {
+    let dims = map.dims();
+
+    loop {
+        let loc = dims.sample(rng);
+        let images = symmetry.images(dims, players, loc);
+
+        // Locations on an axis of symmetry would have to be shared
+        let distinct = images.iter().collect::<BTreeSet<_>>().len() == images.len();
+
+        if distinct
+            && *map.terrain(loc).unwrap() == Terrain::Land
+            && images.iter().all(|image| map.city_by_loc(*image).is_none())
+        {
+            for (player, image) in images.into_iter().take(players).enumerate() {
+                map.new_city(image, Alignment::Belligerent { player }, city_namer.name())
+                    .unwrap();
+            }
+            break;
+        }
+    }
+
+    for loc in dims.iter_locs() {
+        if symmetry.canonical(dims, players, loc) != loc {
+            continue;
+        }
+
+        let land_ok = !land_only || map.terrain(loc).copied().unwrap() == Terrain::Land;
+        if land_ok && map.city_by_loc(loc).is_none() && rng.gen_bool(conf::NEUTRAL_CITY_DENSITY) {
+            let images: BTreeSet<Location> =
+                symmetry.images(dims, players, loc).into_iter().collect();
+            for image in images {
+                if map.city_by_loc(image).is_none() {
+                    new_neutral_city(map, image, city_namer);
+                }
+            }
         }
     }
 }
@@ -233,6 +476,109 @@ fn right_continent_leftmosts(right_continent_width: f64, map_dims: Dims) -> Vec<
         .collect()
 }
 
+/// A way of making a map look the same from every player's starting point
+///
+/// Maps for up to two players are divided into two equivalent regions; maps for three or four players, into four.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Symmetry {
+    /// Reflected left to right, and for more than two players top to bottom as well
+    Mirror,
+
+    /// Turned by a half turn about the center of the map, or for more than two players by quarter turns
+    Rotational,
+}
+
+impl Symmetry {
+    /// The most players a symmetric map can be made for
+    pub const MAX_PLAYERS: PlayerNum = 4;
+
+    /// Check that a map of the given dimensions can be made symmetric for `players`
+    pub fn check(self, dims: Dims, players: PlayerNum) -> Result<(), String> {
+        if players > Self::MAX_PLAYERS {
+            Err(format!(
+                "Symmetric maps can have at most {} players, not {}",
+                Self::MAX_PLAYERS,
+                players
+            ))
+        } else if self == Self::Rotational && players > 2 && dims.width != dims.height {
+            Err(format!(
+                "Rotationally symmetric maps for more than two players must be square, not {}",
+                dims
+            ))
+        } else {
+            Ok(())
+        }
+    }
+
+    /// `loc` followed by its counterparts in each of the map's other regions
+    ///
+    /// Player `i` starts at image `i` of player 0's start. On an axis of symmetry, some images coincide.
+    fn images(self, dims: Dims, players: PlayerNum, loc: Location) -> Vec<Location> {
+        let flip_x = dims.width - 1 - loc.x;
+        let flip_y = dims.height - 1 - loc.y;
+
+        match (self, players <= 2) {
+            (Self::Mirror, true) => vec![loc, Location::new(flip_x, loc.y)],
+            (Self::Mirror, false) => vec![
+                loc,
+                Location::new(flip_x, loc.y),
+                Location::new(loc.x, flip_y),
+                Location::new(flip_x, flip_y),
+            ],
+            (Self::Rotational, true) => vec![loc, Location::new(flip_x, flip_y)],
+            (Self::Rotational, false) => vec![
+                loc,
+                Location::new(dims.width - 1 - loc.y, loc.x),
+                Location::new(flip_x, flip_y),
+                Location::new(loc.y, dims.height - 1 - loc.x),
+            ],
+        }
+    }
+
+    /// The one location standing for `loc` and all its images
+    fn canonical(self, dims: Dims, players: PlayerNum, loc: Location) -> Location {
+        self.images(dims, players, loc).into_iter().min().unwrap()
+    }
+
+    /// Make each location's terrain match that of its canonical location
+    fn symmetrize_terrain(self, grid: &mut LocationGrid<Terrain>, players: PlayerNum) {
+        let dims = grid.dims();
+        for loc in dims.iter_locs() {
+            grid[loc] = grid[self.canonical(dims, players, loc)];
+        }
+    }
+
+    /// Make each location's river match that of its canonical location
+    fn symmetrize_rivers(self, map: &mut MapData, players: PlayerNum) {
+        let dims = map.dims();
+        for loc in dims.iter_locs() {
+            let river = map.tile(self.canonical(dims, players, loc)).unwrap().river;
+            map.set_river(loc, river).unwrap();
+        }
+    }
+}
+
+impl fmt::Display for Symmetry {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Mirror => write!(f, "mirror"),
+            Self::Rotational => write!(f, "rotational"),
+        }
+    }
+}
+
+impl TryFrom<&str> for Symmetry {
+    type Error = String;
+
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        match value {
+            "m" | "mirror" => Ok(Self::Mirror),
+            "r" | "rotational" => Ok(Self::Rotational),
+            x => Err(format!("Unrecognized symmetry {}", x)),
+        }
+    }
+}
+
 #[derive(Copy, Clone)]
 pub enum MapType {
     Continents,
@@ -246,11 +592,25 @@ pub enum MapType {
     RandomTerrain {
         land_prob: f64,
     },
+
+    /// Many small islands
+    Archipelago,
+
+    /// One supercontinent
+    Pangaea,
+
+    /// Land all around a great central sea
+    InlandSea,
+
+    /// Landforms shaped by gradient noise, with mountains on the high ground
+    Noise,
 }
 impl MapType {
     fn generate_terrain<R: RngCore>(&self, rng: &mut R, map_dims: Dims) -> LocationGrid<Terrain> {
         match self {
-            Self::Continents => generate_continents(rng, map_dims),
+            Self::Continents => {
+                generate_continents(rng, map_dims, conf::LANDMASSES, conf::GROWTH_ITERATIONS)
+            }
             Self::TransportRequired {
                 left_continent_width,
                 right_continent_width,
@@ -260,6 +620,15 @@ impl MapType {
                 right_continent_leftmosts(*right_continent_width, map_dims),
             ),
             Self::RandomTerrain { land_prob } => generate_random_terrain(rng, map_dims, *land_prob),
+            Self::Archipelago => generate_continents(
+                rng,
+                map_dims,
+                conf::ARCHIPELAGO_LANDMASSES,
+                conf::ARCHIPELAGO_GROWTH_ITERATIONS,
+            ),
+            Self::Pangaea => generate_pangaea(rng, map_dims),
+            Self::InlandSea => generate_inland_sea(rng, map_dims),
+            Self::Noise => generate_noise(rng, map_dims),
         }
     }
 
@@ -271,7 +640,11 @@ impl MapType {
         city_namer: &mut N,
     ) {
         match self {
-            Self::Continents => {
+            Self::Continents
+            | Self::Archipelago
+            | Self::Pangaea
+            | Self::InlandSea
+            | Self::Noise => {
                 populate_player_cities(rng, map, players, city_namer);
                 populate_neutral_cities(rng, map, city_namer, true);
             }
@@ -302,17 +675,83 @@ impl MapType {
         players: PlayerNum,
         city_namer: &mut N,
     ) -> MapData {
-        let terrain = self.generate_terrain(rng, map_dims);
+        self.generate_with_symmetry(rng, map_dims, players, city_namer, None)
+    }
+
+    /// Generate a map, made symmetric if `symmetry` is given
+    ///
+    /// Panics if the symmetry doesn't pass `Symmetry::check`.
+    pub fn generate_with_symmetry<N: Namer, R: RngCore>(
+        &self,
+        rng: &mut R,
+        map_dims: Dims,
+        players: PlayerNum,
+        city_namer: &mut N,
+        symmetry: Option<Symmetry>,
+    ) -> MapData {
+        let mut terrain = self.generate_terrain(rng, map_dims);
+        add_rough_terrain(rng, &mut terrain);
+
+        if let Some(symmetry) = symmetry {
+            symmetry.check(map_dims, players).unwrap();
+            symmetry.symmetrize_terrain(&mut terrain, players);
+        }
 
         let mut map = MapData::new(map_dims, |loc| terrain[loc]);
 
-        self.initialize_cities(rng, &mut map, players, city_namer);
+        add_rivers(rng, &mut map);
+
+        match symmetry {
+            Some(symmetry) => {
+                symmetry.symmetrize_rivers(&mut map, players);
+
+                let land_only = !matches!(self, Self::RandomTerrain { .. });
+                populate_symmetric_cities(rng, &mut map, players, city_namer, symmetry, land_only);
+            }
+            None => self.initialize_cities(rng, &mut map, players, city_namer),
+        }
 
         map
     }
 }
 
 impl fmt::Debug for MapType {
+    /// Generate maps until one gives the players balanced starts, per `fairness::assess_starts`
+    ///
+    /// Gives up after `conf::FAIR_START_ATTEMPTS` tries, returning the map with the fewest imbalances. The assessment
+    /// of the returned map is returned alongside it so that any remaining imbalance can be reported.
+    pub fn generate_fair<N: Namer, R: RngCore>(
+        &self,
+        rng: &mut R,
+        map_dims: Dims,
+        players: PlayerNum,
+        city_namer: &mut N,
+        symmetry: Option<Symmetry>,
+        wrapping: Wrap2d,
+    ) -> (MapData, StartsReport) {
+        let mut best: Option<(MapData, StartsReport, usize)> = None;
+
+        for _ in 0..conf::FAIR_START_ATTEMPTS {
+            let map = self.generate_with_symmetry(rng, map_dims, players, city_namer, symmetry);
+            let report = fairness::assess_starts(&map, wrapping);
+            let imbalances = report.imbalances().len();
+
+            if imbalances == 0 {
+                return (map, report);
+            }
+
+            if best
+                .as_ref()
+                .map_or(true, |(_, _, fewest)| imbalances < *fewest)
+            {
+                best = Some((map, report, imbalances));
+            }
+        }
+
+        let (map, report, _) = best.unwrap();
+        (map, report)
+    }
+
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         <Self as fmt::Display>::fmt(self, f)
     }
@@ -327,6 +766,10 @@ impl fmt::Display for MapType {
                 right_continent_width,
             } => write!(f, "t({},{})", left_continent_width, right_continent_width),
             Self::RandomTerrain { land_prob } => write!(f, "r({})", land_prob),
+            Self::Archipelago => write!(f, "a"),
+            Self::Pangaea => write!(f, "p"),
+            Self::InlandSea => write!(f, "i"),
+            Self::Noise => write!(f, "n"),
         }
     }
 }
@@ -342,6 +785,10 @@ impl TryFrom<&str> for MapType {
                 right_continent_width: 0.3,
             }),
             "r" => Ok(Self::RandomTerrain { land_prob: 0.4 }),
+            "a" => Ok(Self::Archipelago),
+            "p" => Ok(Self::Pangaea),
+            "i" => Ok(Self::InlandSea),
+            "n" => Ok(Self::Noise),
             x => Err(format!("Unrecognized map type {}", x)),
         }
     }
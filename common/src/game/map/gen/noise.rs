@@ -0,0 +1,118 @@
+//! Gradient noise for natural-looking terrain
+//!
+//! An implementation of Ken Perlin's improved noise in two dimensions. The permutation table is shuffled by the RNG
+//! the noise is made with, so a map generated from a given seed always comes out the same.
+
+use rand::{prelude::SliceRandom, RngCore};
+
+/// Smooth pseudo-random values over the plane, varying on the scale of one unit
+pub(in crate::game::map) struct PerlinNoise {
+    /// A shuffling of 0..256, repeated once so lookups needn't wrap
+    perm: Vec<usize>,
+}
+
+impl PerlinNoise {
+    pub fn new<R: RngCore>(rng: &mut R) -> Self {
+        let mut perm: Vec<usize> = (0..256).collect();
+        perm.shuffle(rng);
+        perm.extend_from_within(..);
+        Self { perm }
+    }
+
+    /// Ease the interpolation so the noise has no visible seams at lattice lines
+    fn fade(t: f64) -> f64 {
+        t * t * t * (t * (t * 6.0 - 15.0) + 10.0)
+    }
+
+    fn lerp(a: f64, b: f64, t: f64) -> f64 {
+        a + t * (b - a)
+    }
+
+    /// The dot product of the offset (x, y) with one of eight gradients picked by `hash`
+    fn grad(hash: usize, x: f64, y: f64) -> f64 {
+        match hash & 7 {
+            0 => x + y,
+            1 => -x + y,
+            2 => x - y,
+            3 => -x - y,
+            4 => x,
+            5 => -x,
+            6 => y,
+            _ => -y,
+        }
+    }
+
+    /// The noise at (x, y), roughly in -1..1 and zero at every lattice point
+    pub fn get(&self, x: f64, y: f64) -> f64 {
+        let x0 = x.floor();
+        let y0 = y.floor();
+        let xf = x - x0;
+        let yf = y - y0;
+
+        let xi = (x0 as i64 & 255) as usize;
+        let yi = (y0 as i64 & 255) as usize;
+
+        let p = &self.perm;
+        let aa = p[p[xi] + yi];
+        let ab = p[p[xi] + yi + 1];
+        let ba = p[p[xi + 1] + yi];
+        let bb = p[p[xi + 1] + yi + 1];
+
+        let u = Self::fade(xf);
+        let v = Self::fade(yf);
+
+        Self::lerp(
+            Self::lerp(Self::grad(aa, xf, yf), Self::grad(ba, xf - 1.0, yf), u),
+            Self::lerp(
+                Self::grad(ab, xf, yf - 1.0),
+                Self::grad(bb, xf - 1.0, yf - 1.0),
+                u,
+            ),
+            v,
+        )
+    }
+
+    /// Several octaves of noise summed, each twice the frequency and `persistence` times the amplitude of the last
+    ///
+    /// Normalized back to roughly -1..1.
+    pub fn fractal(&self, x: f64, y: f64, octaves: u16, persistence: f64) -> f64 {
+        let mut sum = 0.0;
+        let mut total_amplitude = 0.0;
+        let mut amplitude = 1.0;
+        let mut frequency = 1.0;
+
+        for _ in 0..octaves {
+            sum += amplitude * self.get(x * frequency, y * frequency);
+            total_amplitude += amplitude;
+            amplitude *= persistence;
+            frequency *= 2.0;
+        }
+
+        sum / total_amplitude
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::util::init_rng;
+
+    use super::PerlinNoise;
+
+    #[test]
+    fn test_perlin_noise() {
+        let noise = PerlinNoise::new(&mut init_rng(Some(42)));
+        let same = PerlinNoise::new(&mut init_rng(Some(42)));
+
+        for i in 0..100 {
+            let x = i as f64 * 0.37;
+            let y = i as f64 * 0.91 - 20.0;
+
+            assert_eq!(noise.fractal(x, y, 4, 0.5), same.fractal(x, y, 4, 0.5));
+
+            assert_eq!(noise.get(i as f64, -(i as f64)), 0.0);
+        }
+
+        // Nearby points have nearby values
+        assert!((noise.get(3.5, 7.25) - noise.get(3.51, 7.25)).abs() < 0.05);
+    }
+}
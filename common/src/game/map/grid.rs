@@ -16,7 +16,7 @@ use crate::{
     util::{Dimensioned, Dims, Location},
 };
 
-use super::{dijkstra::Source, Terrain, Tile};
+use super::{dijkstra::Source, River, Terrain, Tile};
 
 pub trait LocationGridI<T>: Dimensioned + Index<Location, Output = T> {
     fn get(&self, loc: Location) -> Option<&T>;
@@ -73,6 +73,14 @@ impl<T> LocationGrid<T> {
     pub fn iter_locs(&self) -> impl Iterator<Item = Location> {
         self.dims.iter_locs()
     }
+
+    /// The grid of what `f` makes of each value, in the same places
+    pub fn map<U, F: FnMut(T) -> U>(self, f: F) -> LocationGrid<U> {
+        LocationGrid {
+            grid: self.grid.into_iter().map(f).collect(),
+            dims: self.dims,
+        }
+    }
 }
 
 impl<T> LocationGridI<T> for LocationGrid<T> {
@@ -199,6 +207,8 @@ impl<T: fmt::Debug> fmt::Debug for LocationGrid<T> {
 ///   i.e. character "3" becomes a city belonging to player 3 located on land.
 /// * letters corresponding to a UnitType key represent a unit of that type belonging to player 0
 /// * letters whose lowercase corresponds to a UnitType key represent a unit of that type belonging to player 1
+/// * "%" corresponds to forest and "^" to mountains
+/// * "=" corresponds to land with an impassable river running through it, and "+" to a river ford
 /// * other non-whitespace characters correspond to empty land
 /// * whitespace characters correspond to water
 ///
@@ -231,14 +241,21 @@ impl TryFrom<String> for LocationGrid<Tile> {
 
         let mut grid = LocationGrid::new(Dims::new(width as u16, height), |loc| {
             let c = lines[loc.y as usize][loc.x as usize];
-            Tile::new(
-                if c == ' ' {
-                    Terrain::Water
-                } else {
-                    Terrain::Land
+            let mut tile = Tile::new(
+                match c {
+                    ' ' => Terrain::Water,
+                    '%' => Terrain::Forest,
+                    '^' => Terrain::Mountains,
+                    _ => Terrain::Land,
                 },
                 loc,
-            )
+            );
+            tile.river = match c {
+                '=' => Some(River::Channel),
+                '+' => Some(River::Ford),
+                _ => None,
+            };
+            tile
         });
 
         let mut next_city_id = CityID::default();
@@ -386,6 +403,16 @@ impl<T> SparseLocationGrid<T> {
     pub fn iter_mut(&mut self) -> impl Iterator<Item = &mut T> {
         self.grid.values_mut()
     }
+
+    /// The locations that have values
+    pub fn iter_locs(&self) -> impl Iterator<Item = Location> + '_ {
+        self.grid.keys().copied()
+    }
+
+    /// Clear the value at `loc`, returning it if there was one
+    pub fn remove(&mut self, loc: Location) -> Option<T> {
+        self.grid.remove(&loc)
+    }
 }
 
 impl<T> Dimensioned for SparseLocationGrid<T> {
@@ -490,6 +517,10 @@ mod test {
         assert_eq!(grid.get(Location::new(50, 1000)), None);
 
         assert_eq!(grid.get_mut(Location::new(10, 20)), None);
+
+        assert_eq!(grid.remove(Location::new(5, 6)), Some(100));
+        assert_eq!(grid.get(Location::new(5, 6)), None);
+        assert_eq!(grid.iter().count(), 0);
     }
 
     #[test]
@@ -529,6 +560,9 @@ mod test {
                     map[Location { x: 0, y: 0 }],
                     Tile {
                         terrain: Terrain::Land,
+                        river: None,
+                        improvements: Vec::new(),
+                        stack: Vec::new(),
                         loc: Location { x: 0, y: 0 },
                         city: None,
                         unit: None
@@ -538,6 +572,9 @@ mod test {
                     map[Location { x: 1, y: 0 }],
                     Tile {
                         terrain: Terrain::Land,
+                        river: None,
+                        improvements: Vec::new(),
+                        stack: Vec::new(),
                         loc: Location { x: 1, y: 0 },
                         city: None,
                         unit: None
@@ -547,6 +584,9 @@ mod test {
                     map[Location { x: 2, y: 0 }],
                     Tile {
                         terrain: Terrain::Land,
+                        river: None,
+                        improvements: Vec::new(),
+                        stack: Vec::new(),
                         loc: Location { x: 2, y: 0 },
                         city: None,
                         unit: None
@@ -556,6 +596,9 @@ mod test {
                     map[Location { x: 3, y: 0 }],
                     Tile {
                         terrain: Terrain::Land,
+                        river: None,
+                        improvements: Vec::new(),
+                        stack: Vec::new(),
                         loc: Location { x: 3, y: 0 },
                         city: None,
                         unit: None
@@ -565,6 +608,9 @@ mod test {
                     map[Location { x: 4, y: 0 }],
                     Tile {
                         terrain: Terrain::Water,
+                        river: None,
+                        improvements: Vec::new(),
+                        stack: Vec::new(),
                         loc: Location { x: 4, y: 0 },
                         city: None,
                         unit: None
@@ -574,6 +620,9 @@ mod test {
                     map[Location { x: 5, y: 0 }],
                     Tile {
                         terrain: Terrain::Land,
+                        river: None,
+                        improvements: Vec::new(),
+                        stack: Vec::new(),
                         loc: Location { x: 5, y: 0 },
                         city: None,
                         unit: None
@@ -584,6 +633,9 @@ mod test {
                     map[Location { x: 0, y: 1 }],
                     Tile {
                         terrain: Terrain::Land,
+                        river: None,
+                        improvements: Vec::new(),
+                        stack: Vec::new(),
                         loc: Location { x: 0, y: 1 },
                         city: None,
                         unit: None
@@ -593,6 +645,9 @@ mod test {
                     map[Location { x: 1, y: 1 }],
                     Tile {
                         terrain: Terrain::Land,
+                        river: None,
+                        improvements: Vec::new(),
+                        stack: Vec::new(),
                         loc: Location { x: 1, y: 1 },
                         city: None,
                         unit: None
@@ -602,6 +657,9 @@ mod test {
                     map[Location { x: 2, y: 1 }],
                     Tile {
                         terrain: Terrain::Land,
+                        river: None,
+                        improvements: Vec::new(),
+                        stack: Vec::new(),
                         loc: Location { x: 2, y: 1 },
                         city: None,
                         unit: None
@@ -611,6 +669,9 @@ mod test {
                     map[Location { x: 3, y: 1 }],
                     Tile {
                         terrain: Terrain::Water,
+                        river: None,
+                        improvements: Vec::new(),
+                        stack: Vec::new(),
                         loc: Location { x: 3, y: 1 },
                         city: None,
                         unit: None
@@ -620,6 +681,9 @@ mod test {
                     map[Location { x: 4, y: 1 }],
                     Tile {
                         terrain: Terrain::Land,
+                        river: None,
+                        improvements: Vec::new(),
+                        stack: Vec::new(),
                         loc: Location { x: 4, y: 1 },
                         city: None,
                         unit: None
@@ -629,6 +693,9 @@ mod test {
                     map[Location { x: 5, y: 1 }],
                     Tile {
                         terrain: Terrain::Land,
+                        river: None,
+                        improvements: Vec::new(),
+                        stack: Vec::new(),
                         loc: Location { x: 5, y: 1 },
                         city: None,
                         unit: None
@@ -639,6 +706,9 @@ mod test {
                     map[Location { x: 0, y: 2 }],
                     Tile {
                         terrain: Terrain::Land,
+                        river: None,
+                        improvements: Vec::new(),
+                        stack: Vec::new(),
                         loc: Location { x: 0, y: 2 },
                         city: None,
                         unit: None
@@ -648,6 +718,9 @@ mod test {
                     map[Location { x: 1, y: 2 }],
                     Tile {
                         terrain: Terrain::Land,
+                        river: None,
+                        improvements: Vec::new(),
+                        stack: Vec::new(),
                         loc: Location { x: 1, y: 2 },
                         city: None,
                         unit: None
@@ -657,6 +730,9 @@ mod test {
                     map[Location { x: 2, y: 2 }],
                     Tile {
                         terrain: Terrain::Water,
+                        river: None,
+                        improvements: Vec::new(),
+                        stack: Vec::new(),
                         loc: Location { x: 2, y: 2 },
                         city: None,
                         unit: None
@@ -666,6 +742,9 @@ mod test {
                     map[Location { x: 3, y: 2 }],
                     Tile {
                         terrain: Terrain::Water,
+                        river: None,
+                        improvements: Vec::new(),
+                        stack: Vec::new(),
                         loc: Location { x: 3, y: 2 },
                         city: None,
                         unit: None
@@ -675,6 +754,9 @@ mod test {
                     map[Location { x: 4, y: 2 }],
                     Tile {
                         terrain: Terrain::Water,
+                        river: None,
+                        improvements: Vec::new(),
+                        stack: Vec::new(),
                         loc: Location { x: 4, y: 2 },
                         city: None,
                         unit: None
@@ -684,6 +766,9 @@ mod test {
                     map[Location { x: 5, y: 2 }],
                     Tile {
                         terrain: Terrain::Land,
+                        river: None,
+                        improvements: Vec::new(),
+                        stack: Vec::new(),
                         loc: Location { x: 5, y: 2 },
                         city: None,
                         unit: None
@@ -739,6 +824,9 @@ mod test {
                     Obs::Observed {
                         tile: Tile {
                             terrain: Terrain::Land,
+                            river: None,
+                            improvements: Vec::new(),
+                            stack: Vec::new(),
                             loc: Location { x: 0, y: 0 },
                             city: None,
                             unit: None
@@ -753,6 +841,9 @@ mod test {
                     Obs::Observed {
                         tile: Tile {
                             terrain: Terrain::Land,
+                            river: None,
+                            improvements: Vec::new(),
+                            stack: Vec::new(),
                             loc: Location { x: 1, y: 0 },
                             city: None,
                             unit: None
@@ -767,6 +858,9 @@ mod test {
                     Obs::Observed {
                         tile: Tile {
                             terrain: Terrain::Land,
+                            river: None,
+                            improvements: Vec::new(),
+                            stack: Vec::new(),
                             loc: Location { x: 2, y: 0 },
                             city: None,
                             unit: None
@@ -781,6 +875,9 @@ mod test {
                     Obs::Observed {
                         tile: Tile {
                             terrain: Terrain::Land,
+                            river: None,
+                            improvements: Vec::new(),
+                            stack: Vec::new(),
                             loc: Location { x: 3, y: 0 },
                             city: None,
                             unit: None
@@ -795,6 +892,9 @@ mod test {
                     Obs::Observed {
                         tile: Tile {
                             terrain: Terrain::Water,
+                            river: None,
+                            improvements: Vec::new(),
+                            stack: Vec::new(),
                             loc: Location { x: 4, y: 0 },
                             city: None,
                             unit: None
@@ -809,6 +909,9 @@ mod test {
                     Obs::Observed {
                         tile: Tile {
                             terrain: Terrain::Land,
+                            river: None,
+                            improvements: Vec::new(),
+                            stack: Vec::new(),
                             loc: Location { x: 5, y: 0 },
                             city: None,
                             unit: None
@@ -824,6 +927,9 @@ mod test {
                     Obs::Observed {
                         tile: Tile {
                             terrain: Terrain::Land,
+                            river: None,
+                            improvements: Vec::new(),
+                            stack: Vec::new(),
                             loc: Location { x: 0, y: 1 },
                             city: None,
                             unit: None
@@ -838,6 +944,9 @@ mod test {
                     Obs::Observed {
                         tile: Tile {
                             terrain: Terrain::Land,
+                            river: None,
+                            improvements: Vec::new(),
+                            stack: Vec::new(),
                             loc: Location { x: 1, y: 1 },
                             city: None,
                             unit: None
@@ -853,6 +962,9 @@ mod test {
                     Obs::Observed {
                         tile: Tile {
                             terrain: Terrain::Water,
+                            river: None,
+                            improvements: Vec::new(),
+                            stack: Vec::new(),
                             loc: Location { x: 3, y: 1 },
                             city: None,
                             unit: None
@@ -867,6 +979,9 @@ mod test {
                     Obs::Observed {
                         tile: Tile {
                             terrain: Terrain::Land,
+                            river: None,
+                            improvements: Vec::new(),
+                            stack: Vec::new(),
                             loc: Location { x: 4, y: 1 },
                             city: None,
                             unit: None
@@ -881,6 +996,9 @@ mod test {
                     Obs::Observed {
                         tile: Tile {
                             terrain: Terrain::Land,
+                            river: None,
+                            improvements: Vec::new(),
+                            stack: Vec::new(),
                             loc: Location { x: 5, y: 1 },
                             city: None,
                             unit: None
@@ -896,6 +1014,9 @@ mod test {
                     Obs::Observed {
                         tile: Tile {
                             terrain: Terrain::Land,
+                            river: None,
+                            improvements: Vec::new(),
+                            stack: Vec::new(),
                             loc: Location { x: 0, y: 2 },
                             city: None,
                             unit: None
@@ -910,6 +1031,9 @@ mod test {
                     Obs::Observed {
                         tile: Tile {
                             terrain: Terrain::Land,
+                            river: None,
+                            improvements: Vec::new(),
+                            stack: Vec::new(),
                             loc: Location { x: 1, y: 2 },
                             city: None,
                             unit: None
@@ -924,6 +1048,9 @@ mod test {
                     Obs::Observed {
                         tile: Tile {
                             terrain: Terrain::Water,
+                            river: None,
+                            improvements: Vec::new(),
+                            stack: Vec::new(),
                             loc: Location { x: 2, y: 2 },
                             city: None,
                             unit: None
@@ -939,6 +1066,9 @@ mod test {
                     Obs::Observed {
                         tile: Tile {
                             terrain: Terrain::Water,
+                            river: None,
+                            improvements: Vec::new(),
+                            stack: Vec::new(),
                             loc: Location { x: 4, y: 2 },
                             city: None,
                             unit: None
@@ -953,6 +1083,9 @@ mod test {
                     Obs::Observed {
                         tile: Tile {
                             terrain: Terrain::Land,
+                            river: None,
+                            improvements: Vec::new(),
+                            stack: Vec::new(),
                             loc: Location { x: 5, y: 2 },
                             city: None,
                             unit: None
@@ -13,15 +13,92 @@ pub trait Terrainous {
 pub enum Terrain {
     Water,
     Land,
+    Forest,
+    Mountains,
     // CITY
     //ice, lava, river, deep sea vs shallow, etc.
 }
 
+impl Terrain {
+    /// Is this terrain some kind of land, rough or not?
+    pub fn is_land(self) -> bool {
+        self != Self::Water
+    }
+
+    /// Is this land that slows down ground units?
+    pub fn is_rough(self) -> bool {
+        matches!(self, Self::Forest | Self::Mountains)
+    }
+}
+
+/// A river running through a tile
+///
+/// Rivers flow across land, blocking ground units everywhere but at fords.
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq, Serialize)]
+pub enum River {
+    /// A stretch of river that ground units can't cross
+    Channel,
+
+    /// A shallow stretch where ground units can cross, though slowly
+    Ford,
+}
+
+impl fmt::Display for River {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{}",
+            match *self {
+                River::Channel => "River",
+                River::Ford => "Ford",
+            }
+        )
+    }
+}
+
+/// Works built on a tile by engineers
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq, Serialize)]
+pub enum Improvement {
+    /// Lets ground units cross rough terrain at the usual cost
+    Road,
+
+    /// Strengthens units defending the tile
+    Fortification,
+
+    /// Watches the surroundings for the player who built it, out to `conf::RADAR_SIGHT_DISTANCE`
+    Radar,
+}
+
+impl Improvement {
+    /// The number of turns an engineer must work to build this improvement
+    pub fn build_turns(self) -> u16 {
+        match self {
+            Improvement::Road => 2,
+            Improvement::Fortification => 4,
+            Improvement::Radar => 6,
+        }
+    }
+}
+
+impl fmt::Display for Improvement {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{}",
+            match *self {
+                Improvement::Road => "Road",
+                Improvement::Fortification => "Fortification",
+                Improvement::Radar => "Radar",
+            }
+        )
+    }
+}
+
 impl Colorized for Terrain {
     fn color(&self) -> Option<Colors> {
         Some(match *self {
             Terrain::Water => Colors::Ocean,
-            Terrain::Land => Colors::Land,
+            Terrain::Land | Terrain::Forest | Terrain::Mountains => Colors::Land,
         })
     }
 }
@@ -34,6 +111,8 @@ impl fmt::Display for Terrain {
             match *self {
                 Terrain::Water => "Water",
                 Terrain::Land => "Land",
+                Terrain::Forest => "Forest",
+                Terrain::Mountains => "Mountains",
             }
         )
     }
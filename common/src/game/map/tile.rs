@@ -4,21 +4,41 @@ use serde::{Deserialize, Serialize};
 
 use crate::{
     colors::{Colorized, Colors},
+    conf,
     game::{
         alignment::{Aligned, AlignedMaybe, Alignment},
         city::City,
+        combat::CombatCapable,
         unit::Unit,
     },
     util::Location,
 };
 
-use super::{terrain::Terrainous, Terrain};
+use super::{
+    terrain::{Improvement, River, Terrainous},
+    Terrain,
+};
+
+/// The most units that can share a tile outside of a city, counting the unit on top
+pub const STACK_CAPACITY: usize = 4;
 
 //FIXME Cleaner Debug impl
 #[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
 pub struct Tile {
     pub terrain: Terrain,
+    pub river: Option<River>,
+
+    /// Works engineers have built here, each at most once
+    pub improvements: Vec<Improvement>,
+
+    /// The top-level unit, or the top of the stack if several friendly units share the tile
     pub unit: Option<Unit>,
+
+    /// Friendly units sharing the tile beneath `unit`
+    ///
+    /// Only ever non-empty when `unit` is present.
+    pub stack: Vec<Unit>,
+
     pub city: Option<City>,
     pub loc: Location,
 }
@@ -27,7 +47,10 @@ impl Tile {
     pub fn new(terrain: Terrain, loc: Location) -> Tile {
         Tile {
             terrain,
+            river: None,
+            improvements: Vec::new(),
             unit: None,
+            stack: Vec::new(),
             city: None,
             loc,
         }
@@ -43,20 +66,83 @@ impl Tile {
         self.unit = Some(unit);
     }
 
+    /// Every unit on the tile: the top-level unit, the rest of its stack, and whatever any of them carries
     pub fn all_units(&self) -> Vec<&Unit> {
-        if let Some(unit) = self.unit.as_ref() {
-            let mut units = Vec::with_capacity(1 + unit.type_.carrying_capacity());
-
+        let mut units = Vec::new();
+        for unit in self.unit.iter().chain(self.stack.iter()) {
             units.push(unit);
             units.extend(unit.carried_units());
+        }
+        units
+    }
+
+    /// How many units stand on the tile itself, not counting carried units
+    pub fn stack_size(&self) -> usize {
+        self.unit.iter().count() + self.stack.len()
+    }
+
+    /// Could `unit` join the unit already here, stacking beneath it?
+    ///
+    /// Units stack only with friendly units, never in a city, and no more than `STACK_CAPACITY` to a tile.
+    pub fn can_stack(&self, unit: &Unit) -> bool {
+        self.city.is_none()
+            && self.stack_size() < STACK_CAPACITY
+            && self
+                .unit
+                .as_ref()
+                .map_or(false, |top| top.is_friendly_to(unit))
+            && unit.type_.can_move_on_tile(self)
+    }
+
+    /// The unit that would fight off an attack on the tile: whichever stacked unit fights best
+    ///
+    /// Units that fight best have the most hitpoints, weighted by their strength. Ties go to the higher unit in the
+    /// stack.
+    pub fn defender(&self) -> Option<&Unit> {
+        self.unit
+            .iter()
+            .chain(self.stack.iter())
+            .fold(None, |best, unit| match best {
+                Some(best) if defense(best) >= defense(unit) => Some(best),
+                _ => Some(unit),
+            })
+    }
 
-            units
+    pub fn has_improvement(&self, improvement: Improvement) -> bool {
+        self.improvements.contains(&improvement)
+    }
+
+    /// A copy of the tile with its units and city left out
+    pub fn terrain_only(&self) -> Tile {
+        Tile {
+            terrain: self.terrain,
+            river: self.river,
+            improvements: self.improvements.clone(),
+            unit: None,
+            stack: Vec::new(),
+            city: None,
+            loc: self.loc,
+        }
+    }
+
+    /// Multiplier on the combat strength of units defending the tile
+    ///
+    /// A city shelters its garrison behind its walls; elsewhere a fortification helps.
+    pub fn defense_bonus(&self) -> f64 {
+        if let Some(city) = self.city.as_ref() {
+            city.defense_bonus()
+        } else if self.has_improvement(Improvement::Fortification) {
+            conf::FORTIFICATION_DEFENSE_BONUS
         } else {
-            Vec::new()
+            1.0
         }
     }
 }
 
+fn defense(unit: &Unit) -> f64 {
+    unit.hp() as f64 * unit.strength()
+}
+
 impl Colorized for Tile {
     fn color(&self) -> Option<Colors> {
         if let Some(ref last_unit) = self.unit {
@@ -83,17 +169,34 @@ impl AlignedMaybe for Tile {
 
 impl fmt::Display for Tile {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        if let Some(ref city) = self.city {
+        let mut result = if let Some(ref city) = self.city {
             if let Some(ref unit) = self.unit {
                 write!(f, "{} with {} garrisoned; {}", city, unit, self.terrain)
             } else {
                 write!(f, "{}; {}", city, self.terrain)
             }
         } else if let Some(ref unit) = self.unit {
-            write!(f, "{} on {}", unit, self.terrain)
+            if self.stack.is_empty() {
+                write!(f, "{} on {}", unit, self.terrain)
+            } else {
+                write!(
+                    f,
+                    "{} and {} more on {}",
+                    unit,
+                    self.stack.len(),
+                    self.terrain
+                )
+            }
         } else {
             write!(f, "{}", self.terrain)
+        };
+        if let Some(river) = self.river {
+            result = result.and(write!(f, " ({})", river));
+        }
+        for improvement in self.improvements.iter() {
+            result = result.and(write!(f, " ({})", improvement));
         }
+        result
     }
 }
 
@@ -129,6 +129,15 @@ pub struct MoveComponent {
 
     /// Flag to mark after the fact whether fuel ran out in this move
     pub fuel_ran_out: bool,
+
+    /// Flag to mark after the fact whether the unit was used up attacking, as missiles are
+    pub expended: bool,
+
+    /// Flag to mark after the fact whether the unit set off an enemy minefield, which ends its move
+    pub struck_mine: bool,
+
+    /// Flag to mark after the fact whether the mine blast destroyed the unit
+    pub destroyed_by_mine: bool,
 }
 impl MoveComponent {
     pub fn new(prev_loc: Location, loc: Location) -> Self {
@@ -140,12 +149,16 @@ impl MoveComponent {
             city_combat: None,
             observations_after_move: Vec::with_capacity(0),
             fuel_ran_out: false,
+            expended: false,
+            struck_mine: false,
+            destroyed_by_mine: false,
         }
     }
 
     /// Did the unit survive the move and combat represented by this component?
     ///
-    /// Running out of fuel after moving counts as _not_ surviving.
+    /// Running out of fuel after moving, being expended in an attack, or being destroyed by a mine counts as _not_
+    /// surviving.
     pub fn moved_successfully(&self) -> bool {
         if let Some(ref combat) = self.unit_combat {
             if combat.destroyed() {
@@ -158,7 +171,7 @@ impl MoveComponent {
             }
         }
 
-        if self.fuel_ran_out {
+        if self.fuel_ran_out || self.expended || self.destroyed_by_mine {
             return false;
         }
 
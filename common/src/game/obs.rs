@@ -1,4 +1,11 @@
-use std::{collections::BTreeMap, fmt};
+use std::{
+    collections::BTreeMap,
+    fmt,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    },
+};
 
 use serde::{Deserialize, Serialize};
 use thiserror::Error;
@@ -12,7 +19,7 @@ use crate::{
         map::{
             dijkstra::{Source, SourceMut},
             grid::LocationGridI,
-            LocationGrid, Terrain, Tile,
+            LocationGrid, River, Terrain, Tile,
         },
         unit::UnitType,
         TurnNum,
@@ -20,6 +27,68 @@ use crate::{
     util::{indicator as b, Dimensioned, Dims, Located, LocatedItem, Location, Vec2d, Wrap2d},
 };
 
+/// A point in the history of an `ObsTracker`, advanced by each change to it
+pub type ObsRevision = u64;
+
+/// Tells one `ObsTracker` from another, as revisions only mean anything within the same tracker
+pub type ObsTrackerId = u64;
+
+static NEXT_OBS_TRACKER_ID: AtomicU64 = AtomicU64::new(0);
+
+fn next_obs_tracker_id() -> ObsTrackerId {
+    NEXT_OBS_TRACKER_ID.fetch_add(1, Ordering::Relaxed)
+}
+
+/// How much of the map players can see
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq, Eq, Serialize)]
+pub enum FogOfWar {
+    /// Players see the whole map as it is
+    Off,
+
+    /// Terrain once observed stays accurate, but units and cities are only seen while in sight
+    Partial,
+
+    /// Players know only what they have observed, as of when they observed it
+    Full,
+}
+
+impl From<bool> for FogOfWar {
+    fn from(fog_of_war: bool) -> Self {
+        if fog_of_war {
+            Self::Full
+        } else {
+            Self::Off
+        }
+    }
+}
+
+impl TryFrom<&str> for FogOfWar {
+    type Error = String;
+
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        match value {
+            "on" | "true" | "full" => Ok(Self::Full),
+            "off" | "false" => Ok(Self::Off),
+            "partial" => Ok(Self::Partial),
+            x => Err(format!("Unrecognized fog of war setting {}", x)),
+        }
+    }
+}
+
+impl fmt::Display for FogOfWar {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{}",
+            match self {
+                Self::Off => "off",
+                Self::Partial => "partial",
+                Self::Full => "full",
+            }
+        )
+    }
+}
+
 /// What a particular player knows about a tile
 #[derive(Clone, Deserialize, PartialEq, Serialize)]
 pub enum Obs {
@@ -49,7 +118,7 @@ impl Obs {
     }
 
     /// Observation features:
-    /// - known to be land (0 or 1)
+    /// - known to be land of any kind (0 or 1)
     /// - known to be sea (0 or 1)
     /// - known to have city (0 or 1)
     /// - known to have unit of type - 10 bits (one hot encoded)
@@ -61,6 +130,17 @@ impl Obs {
     /// - city production as % of cost - 1 fX
     /// - is observation in bounds - 1 fX. All Obs are, but Location wrapped_add can yield Option<&Obs> of None
     //    that represent out-of-bounds.
+    /// - known to be forest (0 or 1)
+    /// - known to be mountains (0 or 1)
+    /// - known to have an impassable river (0 or 1)
+    /// - known to have a river ford (0 or 1)
+    /// - known to be plain land (0 or 1); with sea, forest, and mountains, one-hot encodes the terrain
+    /// - known to have a city of the player's own (0 or 1)
+    /// - known to have an enemy city (0 or 1)
+    /// - known to have a neutral city (0 or 1)
+    ///
+    /// New features go at the end, so models trained on fewer can read just the ones they know. Any change here
+    /// calls for a bump of `FEATURES_VERSION`.
     pub fn features(&self, player: PlayerNum) -> [fX; BASE_CONV_FEATS] {
         let none = UnitType::none_features();
         let unit_type_feats = match self {
@@ -71,6 +151,11 @@ impl Obs {
             Self::Unobserved => none,
         };
 
+        let city_alignment = match self {
+            Self::Observed { tile, .. } => tile.city.as_ref().map(|city| city.alignment),
+            Self::Unobserved => None,
+        };
+
         let production_progress = match self {
             Self::Observed { tile, .. } => tile
                 .city
@@ -79,9 +164,9 @@ impl Obs {
             _ => 0.0 as fX,
         };
         [
-            // 0: known to be land (0 or 1)
+            // 0: known to be land of any kind (0 or 1)
             match self {
-                Self::Observed { ref tile, .. } => b(tile.terrain == Terrain::Land),
+                Self::Observed { ref tile, .. } => b(tile.terrain.is_land()),
                 Self::Unobserved => 0.0,
             },
             // 1: known to be sea (0 or 1)
@@ -146,6 +231,39 @@ impl Obs {
             },
             // 19: is in bounds; all Obs are
             1.0 as fX,
+            // 20: known to be forest (0 or 1)
+            match self {
+                Self::Observed { ref tile, .. } => b(tile.terrain == Terrain::Forest),
+                Self::Unobserved => 0.0,
+            },
+            // 21: known to be mountains (0 or 1)
+            match self {
+                Self::Observed { ref tile, .. } => b(tile.terrain == Terrain::Mountains),
+                Self::Unobserved => 0.0,
+            },
+            // 22: known to have an impassable river (0 or 1)
+            match self {
+                Self::Observed { ref tile, .. } => b(tile.river == Some(River::Channel)),
+                Self::Unobserved => 0.0,
+            },
+            // 23: known to have a river ford (0 or 1)
+            match self {
+                Self::Observed { ref tile, .. } => b(tile.river == Some(River::Ford)),
+                Self::Unobserved => 0.0,
+            },
+            // 24: known to be plain land (0 or 1)
+            match self {
+                Self::Observed { ref tile, .. } => b(tile.terrain == Terrain::Land),
+                Self::Unobserved => 0.0,
+            },
+            // 25: known to have a city of the player's own (0 or 1)
+            city_alignment.map_or(0.0, |alignment| b(alignment.is_friendly_to_player(player))),
+            // 26: known to have an enemy city (0 or 1)
+            city_alignment.map_or(0.0, |alignment| {
+                b(alignment.is_belligerent() && !alignment.is_friendly_to_player(player))
+            }),
+            // 27: known to have a neutral city (0 or 1)
+            city_alignment.map_or(0.0, |alignment| b(alignment.is_neutral())),
         ]
     }
 }
@@ -181,6 +299,19 @@ impl LocatedObsLite {
     }
 }
 
+/// The changes to an `ObsTracker` since some earlier revision
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct ObsDiff {
+    /// The revision the changes bring a copy of the tracker up to; ask for changes since this one next time
+    pub revision: ObsRevision,
+
+    /// Whether the observations were archived since the earlier revision
+    pub archived: bool,
+
+    /// The current observation of each location whose observation changed
+    pub observations: Vec<LocatedObsLite>,
+}
+
 #[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
 pub struct LocatedObs {
     pub loc: Location,
@@ -198,6 +329,32 @@ impl LocatedObs {
         was_included != is_included
     }
 
+    /// Whether this observation shows an enemy unit or city that the prior observation of the same location didn't
+    pub fn reveals_enemy_of(&self, player: PlayerNum) -> bool {
+        let enemy_unit = |obs: &Obs| match obs {
+            Obs::Observed { tile, .. } => tile
+                .unit
+                .as_ref()
+                .filter(|unit| unit.alignment.is_enemy_of_player(player))
+                .map(|unit| unit.id),
+            Obs::Unobserved => None,
+        };
+        let enemy_city = |obs: &Obs| match obs {
+            Obs::Observed { tile, .. } => tile
+                .city
+                .as_ref()
+                .filter(|city| city.alignment.is_enemy_of_player(player))
+                .map(|city| city.id),
+            Obs::Unobserved => None,
+        };
+
+        let new_enemy_unit = enemy_unit(&self.obs);
+        let new_enemy_city = enemy_city(&self.obs);
+
+        (new_enemy_unit.is_some() && new_enemy_unit != enemy_unit(&self.old_obs))
+            || (new_enemy_city.is_some() && new_enemy_city != enemy_city(&self.old_obs))
+    }
+
     pub fn lite(self) -> LocatedObsLite {
         LocatedObsLite {
             loc: self.loc,
@@ -266,16 +423,32 @@ impl<'a, S: Source<Tile>> Source<Tile> for UnifiedObsTracker<'a, S> {
     }
 }
 
-#[derive(Clone, Deserialize, Serialize)]
+#[derive(Deserialize, Serialize)]
 pub struct ObsTracker {
+    /// Unique to this instance; copies, whether cloned or deserialized, get their own
+    #[serde(skip, default = "next_obs_tracker_id")]
+    id: ObsTrackerId,
+
     observations: LocationGrid<Obs>,
     num_observed: usize,
+
+    revision: ObsRevision,
+
+    /// The revision at which each location's observation last changed
+    revised: LocationGrid<ObsRevision>,
+
+    /// The revision at which the observations were last archived
+    archived: ObsRevision,
 }
 impl ObsTracker {
     pub fn new(dims: Dims) -> Self {
         Self {
+            id: next_obs_tracker_id(),
             observations: LocationGrid::new(dims, |_loc: Location| Obs::Unobserved),
             num_observed: 0,
+            revision: 0,
+            revised: LocationGrid::new(dims, |_loc: Location| 0),
+            archived: 0,
         }
     }
 
@@ -286,18 +459,102 @@ impl ObsTracker {
                 *current = false;
             }
         }
+
+        self.revision += 1;
+        self.archived = self.revision;
+    }
+
+    pub fn revision(&self) -> ObsRevision {
+        self.revision
+    }
+
+    pub fn id(&self) -> ObsTrackerId {
+        self.id
+    }
+
+    /// Whether the observation of `loc` changed after `revision`, counting the archiving of all observations
+    pub fn changed_since(&self, loc: Location, revision: ObsRevision) -> bool {
+        self.archived > revision
+            || LocationGridI::get(&self.revised, loc).map_or(false, |revised| *revised > revision)
+    }
+
+    /// What changed since `revision`, enough to bring a copy of the tracker as of then up to date using `apply`
+    pub fn since(&self, revision: ObsRevision) -> ObsDiff {
+        ObsDiff {
+            revision: self.revision,
+            archived: self.archived > revision,
+            observations: self
+                .revised
+                .iter_locs()
+                .filter(|loc| self.revised[*loc] > revision)
+                .map(|loc| LocatedObsLite::new(loc, self.observations[loc].clone()))
+                .collect(),
+        }
+    }
+
+    /// Bring the tracker up to date with the changes in `diff`
+    ///
+    /// The tracker should match the one `diff` came from as of the revision the diff was asked for.
+    pub fn apply(&mut self, diff: ObsDiff) {
+        if diff.archived {
+            self.archive();
+        }
+        self.track_many_lite_owned(diff.observations.into_iter());
     }
 
     pub fn iter(&self) -> impl Iterator<Item = &Obs> {
         self.observations.iter()
     }
 
+    /// Bring the remembered terrain of every location out of sight up to date with `truth`
+    ///
+    /// Under partial fog of war, terrain memory stays accurate while memory of units and cities lapses once they're
+    /// out of sight. So out-of-sight observations are replaced by the true terrain, with no units or city, keeping the
+    /// turn and action count they were made at.
+    ///
+    /// Returns the observations that changed as a result
+    pub fn refresh_terrain(&mut self, truth: &dyn Source<Tile>) -> Vec<LocatedObs> {
+        let mut refreshed = Vec::new();
+        for loc in self.dims().iter_locs() {
+            let obs = match self.get(loc) {
+                Some(Obs::Observed {
+                    tile,
+                    turn,
+                    action_count,
+                    current: false,
+                }) => {
+                    let terrain = truth.get(loc).unwrap().terrain_only();
+                    if *tile == terrain {
+                        continue;
+                    }
+                    Obs::Observed {
+                        tile: terrain,
+                        turn: *turn,
+                        action_count: *action_count,
+                        current: false,
+                    }
+                }
+                _ => continue,
+            };
+
+            let old = self._track(loc, obs.clone()).unwrap();
+            refreshed.push(LocatedObs::new(loc, obs, old));
+        }
+        refreshed
+    }
+
     pub fn num_observed(&self) -> usize {
         self.num_observed
     }
 
     fn _track(&mut self, loc: Location, obs: Obs) -> Option<Obs> {
         let new_is_unobserved = obs == Obs::Unobserved;
+
+        if LocationGridI::get(&self.observations, loc) != Some(&obs) {
+            self.revision += 1;
+            self.revised.replace(loc, self.revision);
+        }
+
         let old = self.observations.replace(loc, obs);
 
         // Since we are always replacing with an Obs::Observed, the number observed will go up as long as there was
@@ -359,6 +616,19 @@ impl ObsTracker {
     }
 }
 
+impl Clone for ObsTracker {
+    fn clone(&self) -> Self {
+        Self {
+            id: next_obs_tracker_id(),
+            observations: self.observations.clone(),
+            num_observed: self.num_observed,
+            revision: self.revision,
+            revised: self.revised.clone(),
+            archived: self.archived,
+        }
+    }
+}
+
 impl Dimensioned for ObsTracker {
     fn dims(&self) -> Dims {
         self.observations.dims()
@@ -383,17 +653,300 @@ impl fmt::Debug for ObsTracker {
     }
 }
 
+/// What the game needs of a store of one player's observations
+pub trait ObsTrackerI: Source<Obs> {
+    /// Mark everything as no longer being current
+    fn archive(&mut self);
+
+    /// Record `located_obs`, returning the observation it replaced
+    fn track_lite(&mut self, located_obs: LocatedObsLite) -> Option<Obs>;
+
+    fn track_observation(
+        &mut self,
+        loc: Location,
+        tile: &Tile,
+        turn: TurnNum,
+        action_count: ActionNum,
+    ) -> LocatedObs;
+
+    /// Bring the remembered terrain of every location out of sight up to date with `truth`, as under partial fog of
+    /// war
+    ///
+    /// Returns the observations that changed as a result
+    fn refresh_terrain(&mut self, truth: &dyn Source<Tile>) -> Vec<LocatedObs>;
+
+    fn num_observed(&self) -> usize;
+
+    fn revision(&self) -> ObsRevision;
+
+    /// What changed since `revision`
+    fn since(&self, revision: ObsRevision) -> ObsDiff;
+}
+
+impl ObsTrackerI for ObsTracker {
+    fn archive(&mut self) {
+        ObsTracker::archive(self)
+    }
+
+    fn track_lite(&mut self, located_obs: LocatedObsLite) -> Option<Obs> {
+        ObsTracker::track_lite(self, located_obs)
+    }
+
+    fn track_observation(
+        &mut self,
+        loc: Location,
+        tile: &Tile,
+        turn: TurnNum,
+        action_count: ActionNum,
+    ) -> LocatedObs {
+        ObsTracker::track_observation(self, loc, tile, turn, action_count)
+    }
+
+    fn refresh_terrain(&mut self, truth: &dyn Source<Tile>) -> Vec<LocatedObs> {
+        ObsTracker::refresh_terrain(self, truth)
+    }
+
+    fn num_observed(&self) -> usize {
+        ObsTracker::num_observed(self)
+    }
+
+    fn revision(&self) -> ObsRevision {
+        ObsTracker::revision(self)
+    }
+
+    fn since(&self, revision: ObsRevision) -> ObsDiff {
+        ObsTracker::since(self, revision)
+    }
+}
+
+static UNOBSERVED: Obs = Obs::Unobserved;
+
+/// Observations stored only for the locations that have been observed
+///
+/// The dense `ObsTracker` spends as much room on a location never seen as on one that has been, so a game with many
+/// players on a large map, where each player has seen only part of it, is mostly paying for nothing. The game keeps
+/// every player's observations this way, handing out dense copies to clients, which look observations up far more
+/// often.
+#[derive(Clone, Deserialize, Serialize)]
+pub struct CompactObsTracker {
+    dims: Dims,
+
+    /// Each tracked location's observation, with the revision at which it last changed
+    observations: BTreeMap<Location, (Obs, ObsRevision)>,
+
+    num_observed: usize,
+
+    revision: ObsRevision,
+
+    /// The revision at which the observations were last archived
+    archived: ObsRevision,
+}
+
+impl CompactObsTracker {
+    pub fn new(dims: Dims) -> Self {
+        Self {
+            dims,
+            observations: BTreeMap::new(),
+            num_observed: 0,
+            revision: 0,
+            archived: 0,
+        }
+    }
+
+    /// The observations of every location observed
+    pub fn iter(&self) -> impl Iterator<Item = &Obs> {
+        self.observations
+            .values()
+            .map(|(obs, _)| obs)
+            .filter(|obs| obs.is_observed())
+    }
+
+    fn _track(&mut self, loc: Location, obs: Obs) -> Option<Obs> {
+        if !self.dims.contain(loc) {
+            return None;
+        }
+
+        let old = match self.observations.get_mut(&loc) {
+            Some((old, _)) if *old == obs => return Some(obs),
+            Some((old, revised)) => {
+                self.revision += 1;
+                *revised = self.revision;
+                std::mem::replace(old, obs)
+            }
+            None => {
+                if obs.is_unobserved() {
+                    return Some(obs);
+                }
+                self.revision += 1;
+                self.observations.insert(loc, (obs, self.revision));
+                Obs::Unobserved
+            }
+        };
+
+        let new_is_observed = self.observations[&loc].0.is_observed();
+        match (old.is_observed(), new_is_observed) {
+            (false, true) => self.num_observed += 1,
+            (true, false) => self.num_observed -= 1,
+            _ => {}
+        }
+
+        Some(old)
+    }
+}
+
+impl ObsTrackerI for CompactObsTracker {
+    fn archive(&mut self) {
+        for (obs, _) in self.observations.values_mut() {
+            if let Obs::Observed { current, .. } = obs {
+                *current = false;
+            }
+        }
+
+        self.revision += 1;
+        self.archived = self.revision;
+    }
+
+    fn track_lite(&mut self, located_obs: LocatedObsLite) -> Option<Obs> {
+        self._track(located_obs.loc, located_obs.obs)
+    }
+
+    fn track_observation(
+        &mut self,
+        loc: Location,
+        tile: &Tile,
+        turn: TurnNum,
+        action_count: ActionNum,
+    ) -> LocatedObs {
+        let obs = Obs::Observed {
+            tile: tile.clone(),
+            turn,
+            action_count,
+            current: true,
+        };
+
+        let old = self._track(loc, obs.clone());
+
+        LocatedObs::new(loc, obs, old.unwrap_or(Obs::Unobserved))
+    }
+
+    fn refresh_terrain(&mut self, truth: &dyn Source<Tile>) -> Vec<LocatedObs> {
+        let stale: Vec<(Location, Obs)> = self
+            .observations
+            .iter()
+            .filter_map(|(loc, (obs, _))| match obs {
+                Obs::Observed {
+                    tile,
+                    turn,
+                    action_count,
+                    current: false,
+                } => {
+                    let terrain = truth.get(*loc).unwrap().terrain_only();
+                    (*tile != terrain).then(|| {
+                        (
+                            *loc,
+                            Obs::Observed {
+                                tile: terrain,
+                                turn: *turn,
+                                action_count: *action_count,
+                                current: false,
+                            },
+                        )
+                    })
+                }
+                _ => None,
+            })
+            .collect();
+
+        stale
+            .into_iter()
+            .map(|(loc, obs)| {
+                let old = self._track(loc, obs.clone()).unwrap();
+                LocatedObs::new(loc, obs, old)
+            })
+            .collect()
+    }
+
+    fn num_observed(&self) -> usize {
+        self.num_observed
+    }
+
+    fn revision(&self) -> ObsRevision {
+        self.revision
+    }
+
+    fn since(&self, revision: ObsRevision) -> ObsDiff {
+        ObsDiff {
+            revision: self.revision,
+            archived: self.archived > revision,
+            observations: self
+                .observations
+                .iter()
+                .filter(|(_, (_, revised))| *revised > revision)
+                .map(|(loc, (obs, _))| LocatedObsLite::new(*loc, obs.clone()))
+                .collect(),
+        }
+    }
+}
+
+impl Dimensioned for CompactObsTracker {
+    fn dims(&self) -> Dims {
+        self.dims
+    }
+}
+
+impl Source<Obs> for CompactObsTracker {
+    fn get(&self, loc: Location) -> Option<&Obs> {
+        if !self.dims.contain(loc) {
+            return None;
+        }
+
+        Some(
+            self.observations
+                .get(&loc)
+                .map_or(&UNOBSERVED, |(obs, _)| obs),
+        )
+    }
+}
+
+/// A dense copy, revisions included, so that `since` can later be used to keep the copy up to date
+impl From<&CompactObsTracker> for ObsTracker {
+    fn from(compact: &CompactObsTracker) -> Self {
+        let mut tracker = ObsTracker::new(compact.dims);
+
+        for (loc, (obs, revised)) in compact.observations.iter() {
+            tracker.observations.replace(*loc, obs.clone());
+            tracker.revised.replace(*loc, *revised);
+        }
+
+        tracker.num_observed = compact.num_observed;
+        tracker.revision = compact.revision;
+        tracker.archived = compact.archived;
+
+        tracker
+    }
+}
+
 #[derive(Debug, Error)]
 pub enum ObsTrackerError {
     #[error("No observation tracker present for player {0}")]
     NoTrackerForPlayer(PlayerNum),
 }
 
-/// Convenience struct to track the observations of one or more players
-#[derive(Clone)]
-pub struct PlayerObsTracker {
+/// Convenience type to track the observations of one or more players
+///
+/// Trackers are shared between clones until one of them changes, so cloning is cheap.
+#[derive(Clone, Deserialize, Serialize)]
+pub enum PlayerObsTracker {
     /// The information that each player has about the state of the game
-    player_observations: BTreeMap<PlayerNum, ObsTracker>,
+    PerPlayer(BTreeMap<PlayerNum, Arc<CompactObsTracker>>),
+
+    /// With fog of war off, every player sees the whole map all the time, so one set of observations serves them all
+    ///
+    /// It's never archived, since nothing is ever out of sight.
+    UniversalVisibility {
+        players: PlayerNum,
+        observations: Arc<CompactObsTracker>,
+    },
 }
 
 impl PlayerObsTracker {
@@ -401,11 +954,40 @@ impl PlayerObsTracker {
         let mut player_observations = BTreeMap::new();
 
         for p in 0..players {
-            player_observations.insert(p, ObsTracker::new(dims));
+            player_observations.insert(p, Arc::new(CompactObsTracker::new(dims)));
         }
 
-        Self {
-            player_observations,
+        Self::PerPlayer(player_observations)
+    }
+
+    /// Observations shared by all `players`, for games without fog of war
+    pub fn universal(players: PlayerNum, dims: Dims) -> Self {
+        Self::UniversalVisibility {
+            players,
+            observations: Arc::new(CompactObsTracker::new(dims)),
+        }
+    }
+
+    pub fn is_universal(&self) -> bool {
+        matches!(self, Self::UniversalVisibility { .. })
+    }
+
+    /// Mark the given player's observations as no longer current, unless everything is always in view
+    pub fn archive(&mut self, player: PlayerNum) -> Result<(), ObsTrackerError> {
+        match self {
+            Self::PerPlayer(_) => {
+                self.tracker_mut(player)
+                    .ok_or(ObsTrackerError::NoTrackerForPlayer(player))?
+                    .archive();
+                Ok(())
+            }
+            Self::UniversalVisibility { players, .. } => {
+                if player < *players {
+                    Ok(())
+                } else {
+                    Err(ObsTrackerError::NoTrackerForPlayer(player))
+                }
+            }
         }
     }
 
@@ -419,19 +1001,34 @@ impl PlayerObsTracker {
         obs: Obs,
     ) -> Result<Option<Obs>, ObsTrackerError> {
         let observations = self
-            .player_observations
-            .get_mut(&player)
+            .tracker_mut(player)
             .ok_or(ObsTrackerError::NoTrackerForPlayer(player))?;
 
         Ok(observations._track(loc, obs))
     }
 
-    pub fn tracker(&self, player: PlayerNum) -> Option<&ObsTracker> {
-        self.player_observations.get(&player)
+    pub fn tracker(&self, player: PlayerNum) -> Option<&CompactObsTracker> {
+        match self {
+            Self::PerPlayer(player_observations) => {
+                player_observations.get(&player).map(Arc::as_ref)
+            }
+            Self::UniversalVisibility {
+                players,
+                observations,
+            } => (player < *players).then_some(observations.as_ref()),
+        }
     }
 
-    pub fn tracker_mut(&mut self, player: PlayerNum) -> Option<&mut ObsTracker> {
-        self.player_observations.get_mut(&player)
+    pub fn tracker_mut(&mut self, player: PlayerNum) -> Option<&mut CompactObsTracker> {
+        match self {
+            Self::PerPlayer(player_observations) => {
+                player_observations.get_mut(&player).map(Arc::make_mut)
+            }
+            Self::UniversalVisibility {
+                players,
+                observations,
+            } => (player < *players).then(|| Arc::make_mut(observations)),
+        }
     }
 }
 
@@ -454,7 +1051,7 @@ pub trait Observer: Located {
         turn: TurnNum,
         action: ActionNum,
         wrapping: Wrap2d,
-        obs_tracker: &mut ObsTracker,
+        obs_tracker: &mut dyn ObsTrackerI,
     ) -> Vec<LocatedObs> {
         visible_coords_iter(self.sight_distance())
             .filter_map(|inc| wrapping.wrapped_add(tiles.dims(), self.loc(), inc))
@@ -475,8 +1072,11 @@ pub trait Observer: Located {
 mod test {
     use crate::{
         game::{
-            map::{dijkstra::Source, LocationGrid, Terrain, Tile},
-            obs::{Obs, ObsTracker, Observer},
+            ai::BASE_CONV_FEATS,
+            city::{City, CityID},
+            fX,
+            map::{dijkstra::Source, Improvement, LocationGrid, Terrain, Tile},
+            obs::{CompactObsTracker, FogOfWar, Obs, ObsTracker, ObsTrackerI, Observer},
             unit::{Unit, UnitID, UnitType},
             Alignment,
         },
@@ -523,6 +1123,161 @@ mod test {
         infantry.observe(&map, turn, action_count, Wrap2d::BOTH, &mut tracker);
     }
 
+    #[test]
+    fn test_refresh_terrain() {
+        let dims = Dims::new(2, 1);
+        let road_loc = Location::new(0, 0);
+        let other_loc = Location::new(1, 0);
+
+        // A road has gone in since the player last looked
+        let truth: LocationGrid<Tile> = LocationGrid::new(dims, |loc| -> Tile {
+            let mut tile = Tile::new(Terrain::Land, loc);
+            if loc == road_loc {
+                tile.improvements.push(Improvement::Road);
+            }
+            tile
+        });
+
+        let mut remembered = Tile::new(Terrain::Land, road_loc);
+        remembered.unit = Some(Unit::new(
+            UnitID::new(0),
+            road_loc,
+            UnitType::Armor,
+            Alignment::Belligerent { player: 1 },
+            "Erwin",
+        ));
+
+        let mut tracker = ObsTracker::new(dims);
+        tracker.track_observation(road_loc, &remembered, 3, 7);
+        tracker.track_observation(other_loc, truth.get(other_loc).unwrap(), 3, 8);
+        tracker.archive();
+
+        let refreshed = tracker.refresh_terrain(&truth);
+        assert_eq!(refreshed.len(), 1);
+        assert_eq!(refreshed[0].loc, road_loc);
+
+        // The enemy armor is forgotten and the road known, but the observation is as old as it was
+        assert_eq!(
+            tracker.get(road_loc).cloned(),
+            Some(Obs::Observed {
+                tile: truth.get(road_loc).cloned().unwrap(),
+                turn: 3,
+                action_count: 7,
+                current: false
+            })
+        );
+
+        assert!(tracker.refresh_terrain(&truth).is_empty());
+
+        assert_eq!(FogOfWar::try_from("partial"), Ok(FogOfWar::Partial));
+        assert_eq!(FogOfWar::try_from("on"), Ok(FogOfWar::Full));
+        assert_eq!(FogOfWar::from(false), FogOfWar::Off);
+    }
+
+    #[test]
+    fn test_obs_diff() {
+        let dims = Dims::new(4, 1);
+        let tile = |x: u16| Tile::new(Terrain::Land, Location::new(x, 0));
+
+        let mut tracker = ObsTracker::new(dims);
+        let mut copy = tracker.clone();
+
+        tracker.track_observation(Location::new(0, 0), &tile(0), 0, 0);
+        tracker.track_observation(Location::new(1, 0), &tile(1), 0, 1);
+
+        let diff = tracker.since(copy.revision());
+        assert!(!diff.archived);
+        assert_eq!(diff.observations.len(), 2);
+        let revision = diff.revision;
+        copy.apply(diff);
+
+        assert!(tracker.since(revision).observations.is_empty());
+
+        // Observing the same thing again changes nothing
+        tracker.track_observation(Location::new(0, 0), &tile(0), 0, 0);
+        assert_eq!(tracker.revision(), revision);
+
+        tracker.archive();
+        tracker.track_observation(Location::new(2, 0), &tile(2), 1, 2);
+
+        let diff = tracker.since(revision);
+        assert!(diff.archived);
+        assert_eq!(diff.observations.len(), 1);
+        copy.apply(diff);
+
+        for loc in dims.iter_locs() {
+            assert_eq!(copy.get(loc), tracker.get(loc));
+        }
+    }
+
+    #[test]
+    fn test_changed_since() {
+        let dims = Dims::new(3, 1);
+        let tile = |x: u16| Tile::new(Terrain::Land, Location::new(x, 0));
+
+        let mut tracker = ObsTracker::new(dims);
+        let revision = tracker.revision();
+
+        tracker.track_observation(Location::new(1, 0), &tile(1), 0, 0);
+        assert!(!tracker.changed_since(Location::new(0, 0), revision));
+        assert!(tracker.changed_since(Location::new(1, 0), revision));
+        assert!(!tracker.changed_since(Location::new(1, 0), tracker.revision()));
+
+        // Archiving changes everything
+        let revision = tracker.revision();
+        tracker.archive();
+        assert!(tracker.changed_since(Location::new(0, 0), revision));
+
+        // Copies can't be mistaken for the original
+        assert_ne!(tracker.clone().id(), tracker.id());
+    }
+
+    #[test]
+    fn test_compact_obs_tracker() {
+        let dims = Dims::new(5, 3);
+        let map: LocationGrid<Tile> = LocationGrid::new(dims, |loc| Tile::new(Terrain::Land, loc));
+
+        let mut dense = ObsTracker::new(dims);
+        let mut compact = CompactObsTracker::new(dims);
+
+        assert_eq!(compact.get(Location::new(4, 2)), Some(&Obs::Unobserved));
+        assert_eq!(compact.get(Location::new(5, 0)), None);
+
+        let infantry = Unit::new(
+            UnitID::new(0),
+            Location::new(1, 1),
+            UnitType::Infantry,
+            Alignment::Belligerent { player: 0 },
+            "Jane Drew",
+        );
+        let dense_seen = infantry.observe(&map, 0, 0, Wrap2d::NEITHER, &mut dense);
+        let compact_seen = infantry.observe(&map, 0, 0, Wrap2d::NEITHER, &mut compact);
+        assert_eq!(dense_seen, compact_seen);
+
+        dense.archive();
+        compact.archive();
+
+        for loc in dims.iter_locs() {
+            assert_eq!(compact.get(loc), dense.get(loc));
+        }
+        assert_eq!(compact.num_observed(), dense.num_observed());
+        assert_eq!(compact.iter().count(), compact.num_observed());
+
+        // Only what was observed takes up room
+        assert_eq!(compact.observations.len(), compact.num_observed());
+
+        // The dense copy picks up where the compact tracker left off
+        let mut copy = ObsTracker::from(&compact);
+        let revision = copy.revision();
+        compact.track_observation(Location::new(4, 2), &map[Location::new(4, 2)], 1, 1);
+        copy.apply(compact.since(revision));
+
+        for loc in dims.iter_locs() {
+            assert_eq!(copy.get(loc), compact.get(loc));
+        }
+        assert_eq!(copy.num_observed(), compact.num_observed());
+    }
+
     #[test]
     pub fn test_num_observed() {
         let mut tracker = ObsTracker::new(Dims::new(10, 1));
@@ -552,4 +1307,51 @@ mod test {
 
         assert_eq!(tracker.num_observed(), 1);
     }
+
+    #[test]
+    fn test_terrain_and_city_features() {
+        let loc = Location { x: 0, y: 0 };
+        let observed = |tile: Tile| Obs::Observed {
+            tile,
+            turn: 0,
+            action_count: 0,
+            current: true,
+        };
+        let with_city = |alignment: Alignment| {
+            let mut tile = Tile::new(Terrain::Land, loc);
+            tile.city = Some(City::new(CityID::default(), alignment, loc, "Featureville"));
+            observed(tile)
+        };
+
+        // Sea, plain land, forest, mountains
+        let terrain = |feats: [fX; BASE_CONV_FEATS]| [feats[1], feats[24], feats[20], feats[21]];
+        assert_eq!(
+            terrain(observed(Tile::new(Terrain::Land, loc)).features(0)),
+            [0.0, 1.0, 0.0, 0.0]
+        );
+        assert_eq!(
+            terrain(observed(Tile::new(Terrain::Forest, loc)).features(0)),
+            [0.0, 0.0, 1.0, 0.0]
+        );
+        assert_eq!(terrain(Obs::Unobserved.features(0)), [0.0; 4]);
+
+        // Own, enemy, neutral
+        let cities = |feats: [fX; BASE_CONV_FEATS]| [feats[25], feats[26], feats[27]];
+        assert_eq!(
+            cities(with_city(Alignment::Belligerent { player: 0 }).features(0)),
+            [1.0, 0.0, 0.0]
+        );
+        assert_eq!(
+            cities(with_city(Alignment::Belligerent { player: 1 }).features(0)),
+            [0.0, 1.0, 0.0]
+        );
+        assert_eq!(
+            cities(with_city(Alignment::Neutral).features(0)),
+            [0.0, 0.0, 1.0]
+        );
+        assert_eq!(
+            cities(observed(Tile::new(Terrain::Land, loc)).features(0)),
+            [0.0; 3]
+        );
+    }
 }
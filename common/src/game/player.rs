@@ -1,4 +1,4 @@
-use std::{borrow::Cow, cmp::Ordering, collections::BTreeSet, sync::Arc};
+use std::{borrow::Cow, cmp::Ordering, collections::BTreeSet, sync::Arc, time::Duration};
 
 use delegate::delegate;
 use serde::{Deserialize, Serialize};
@@ -6,14 +6,18 @@ use tokio::sync::RwLock as RwLockTokio;
 
 use super::{
     action::{AiPlayerAction, NextCityAction, NextUnitAction, PlayerAction, PlayerActionOutcome},
-    ai::{fX, AISpec, TrainingFocus},
+    ai::{fX, AISpec, TrainingFocus, AI_LEVELS, DEEP_WIDTH},
+    chat::ChatMessage,
+    combat::CombatOdds,
+    delta::{SyncPoint, SyncedState},
+    diplomacy::DiplomaticStatus,
     error::GameError,
-    map::dijkstra::Source,
+    map::{dijkstra::Source, Improvement},
     move_::Move,
     obs::{LocatedObsLite, ObsTracker},
-    ActionNum, IGame, OrdersSet, PlayerSecret, ProductionCleared, ProductionSet,
-    ProposedOrdersResult, ProposedUmpireResult, TurnEnded, TurnPhase, TurnStart, UmpireResult,
-    UnitDisbanded,
+    ActionNum, Bombardment, GameOver, IGame, OrdersSet, PlayerSecret, ProductionCleared,
+    ProductionSet, ProposedOrdersResult, ProposedUmpireResult, RadarBuilt, Resigned, TurnEnded,
+    TurnPhase, TurnStart, UmpireResult, UnitDisbanded, WallsBuilt,
 };
 use crate::{
     cli::Specified,
@@ -21,7 +25,10 @@ use crate::{
         city::City,
         map::tile::Tile,
         obs::Obs,
-        unit::{Unit, UnitID, UnitType},
+        unit::{
+            orders::{Orders, OrdersResult},
+            Unit, UnitID, UnitType,
+        },
         Game, TurnNum,
     },
     util::{Dims, Direction, Location, Wrap2d},
@@ -36,15 +43,15 @@ pub enum PlayerType {
 }
 
 impl PlayerType {
-    pub fn values() -> [Self; 3] {
-        [
-            Self::Human,
-            Self::AI(AISpec::Random { seed: None }),
+    pub fn values() -> Vec<Self> {
+        let mut values = vec![Self::Human, Self::AI(AISpec::Random { seed: None })];
+        values.extend((0..AI_LEVELS).map(|level| {
             Self::AI(AISpec::FromLevel {
-                level: 0,
+                level,
                 device: Default::default(),
-            }),
-        ]
+            })
+        }));
+        values
     }
 }
 
@@ -117,8 +124,19 @@ pub struct PlayerControl {
     /// A local copy of the player's observations
     observations: ObsTracker,
 
+    /// What the local copies of the player's state were last brought up to date with
+    ///
+    /// The observations are kept up to date on their own as well, so the revision may be ahead of the rest.
+    sync: SyncPoint,
+
+    /// A local copy of the player's units and cities as of the last turn delta
+    synced: SyncedState,
+
     /// Wrapping never changes; cache it
     wrapping: Wrap2d,
+
+    /// The width of the window covered by the features recorded when generating training data
+    feature_window: usize,
 }
 
 impl PlayerControl {
@@ -132,9 +150,9 @@ impl PlayerControl {
             let g = game.read().await;
 
             (
-                g.dims().await,
+                g.dims().await.unwrap(),
                 g.player_observations(secret).await.unwrap(),
-                g.wrapping().await,
+                g.wrapping().await.unwrap(),
             )
         };
 
@@ -154,13 +172,57 @@ impl PlayerControl {
             player,
             secret,
             dims,
+            sync: SyncPoint::unsnapshotted(observations.revision()),
+            synced: SyncedState::default(),
             observations,
             wrapping,
+            feature_window: DEEP_WIDTH,
         }
     }
 
     // Mutable
 
+    /// Have the features recorded when generating training data cover a window `window` tiles wide
+    pub fn set_feature_window(&mut self, window: usize) {
+        self.feature_window = window;
+    }
+
+    /// Bring the local copy of the player's observations up to date with the game's
+    ///
+    /// Only what changed since the last time is fetched.
+    pub async fn sync_observations(&mut self) -> UmpireResult<()> {
+        let diff = self
+            .game
+            .read()
+            .await
+            .player_observations_since(self.secret, self.sync.revision)
+            .await?;
+
+        self.sync.revision = diff.revision;
+        self.observations.apply(diff);
+
+        Ok(())
+    }
+
+    /// Bring the local copies of the player's observations, units, and cities up to date with the game's
+    ///
+    /// Only what changed since the last time is fetched. Gives the chat messages sent since the last time, which
+    /// bring the count to `chat_synced`.
+    pub async fn sync_turn(&mut self) -> UmpireResult<Vec<ChatMessage>> {
+        let delta = self
+            .game
+            .read()
+            .await
+            .player_turn_delta(self.secret, Some(self.sync))
+            .await?;
+
+        self.sync = delta.sync;
+        self.synced.apply(&delta);
+        self.observations.apply(delta.observations);
+
+        Ok(delta.chat)
+    }
+
     pub async fn activate_unit_by_loc(&mut self, loc: Location) -> UmpireResult<LocatedObsLite> {
         let result = self
             .game
@@ -185,8 +247,10 @@ impl PlayerControl {
             .begin_turn(self.secret, clear_after_unit_production)
             .await;
 
-        if let Ok(ref turn_start) = result {
-            self.observations.track_many(turn_start.observations.iter());
+        // The turn start's observations only cover what the player's units and cities see now; the game's copy also
+        // knows what changed elsewhere since the player's last turn, as when there's no fog of war
+        if result.is_ok() {
+            self.sync_observations().await?;
         }
 
         result
@@ -245,25 +309,42 @@ impl PlayerControl {
         result
     }
 
+    pub async fn bombard(
+        &mut self,
+        unit_id: UnitID,
+        target: Location,
+    ) -> UmpireResult<Bombardment> {
+        let result = self
+            .game
+            .write()
+            .await
+            .bombard(self.secret, unit_id, target)
+            .await;
+
+        if let Ok(ref outcome) = result {
+            self.observations.track_lite(outcome.obs.clone());
+        }
+
+        result
+    }
+
     pub async fn end_turn(&mut self) -> UmpireResult<TurnEnded> {
-        let mut result = self.game.write().await.end_turn(self.secret).await;
+        let result = self.game.write().await.end_turn(self.secret).await;
 
-        if let Ok(ref mut outcome) = result {
-            self.observations
-                .track_many_lite(outcome.observations.iter());
-            self.observations.archive();
+        // Syncing archives the local observations only if the game archived its own
+        if result.is_ok() {
+            self.sync_observations().await?;
         }
 
         result
     }
 
     pub async fn force_end_turn(&mut self) -> UmpireResult<TurnEnded> {
-        let mut result = self.game.write().await.force_end_turn(self.secret).await;
+        let result = self.game.write().await.force_end_turn(self.secret).await;
 
-        if let Ok(ref mut outcome) = result {
-            self.observations
-                .track_many_lite(outcome.observations.iter());
-            self.observations.archive();
+        // Syncing archives the local observations only if the game archived its own
+        if result.is_ok() {
+            self.sync_observations().await?;
         }
 
         result
@@ -331,19 +412,42 @@ impl PlayerControl {
             PlayerActionOutcome::ProductionSet(ps) => {
                 self.observations.track_lite(ps.obs.clone());
             }
-            PlayerActionOutcome::TurnEnded => {
-                self.observations.archive();
+            PlayerActionOutcome::WallsBuilt(wb) => {
+                self.observations.track_lite(wb.obs.clone());
+            }
+            PlayerActionOutcome::RadarBuilt(rb) => {
+                self.observations.track_lite(rb.obs.clone());
             }
 
             PlayerActionOutcome::UnitDisbanded(ud) => {
                 self.observations.track_lite(ud.obs.clone());
             }
-            PlayerActionOutcome::TurnStarted(ts) => {
-                self.observations.track_many(ts.observations.iter());
+            PlayerActionOutcome::UnitBombarded(bombardment) => {
+                self.observations.track_lite(bombardment.obs.clone());
             }
+            // Synced with the game's copy instead
+            PlayerActionOutcome::TurnEnded | PlayerActionOutcome::TurnStarted(_) => {}
             PlayerActionOutcome::UnitSkipped { orders_outcome, .. } => {
                 self.observations.track_lite(orders_outcome.obs.clone());
             }
+            PlayerActionOutcome::ProductionCleared(pc) => {
+                self.observations.track_lite(pc.obs.clone());
+            }
+            PlayerActionOutcome::UnitOrdersSet { orders_set, .. } => {
+                self.observations.track_lite(orders_set.obs.clone());
+            }
+            PlayerActionOutcome::UnitOrdersCleared { .. }
+            | PlayerActionOutcome::MinesLaid { .. }
+            | PlayerActionOutcome::MinesSwept { .. } => {}
+            PlayerActionOutcome::UnitActivated { obs } => {
+                self.observations.track_lite(obs.clone());
+            }
+            PlayerActionOutcome::TreatyProposed { .. }
+            | PlayerActionOutcome::DiplomaticStatusChanged { .. } => {}
+            PlayerActionOutcome::Resigned(resigned) => {
+                self.observations
+                    .track_many_lite(resigned.observations.iter());
+            }
         }
     }
 
@@ -357,6 +461,13 @@ impl PlayerControl {
 
         if let Ok(ref outcome) = result {
             self.update_action_observations(outcome);
+
+            if matches!(
+                outcome,
+                PlayerActionOutcome::TurnEnded | PlayerActionOutcome::TurnStarted(_)
+            ) {
+                self.sync_observations().await?;
+            }
         }
 
         result
@@ -375,6 +486,13 @@ impl PlayerControl {
 
         if let Ok(ref outcome) = result {
             self.update_action_observations(outcome);
+
+            if matches!(
+                outcome,
+                PlayerActionOutcome::TurnEnded | PlayerActionOutcome::TurnStarted(_)
+            ) {
+                self.sync_observations().await?;
+            }
         }
 
         result
@@ -386,6 +504,10 @@ impl PlayerControl {
         self.dims
     }
 
+    pub fn feature_window(&self) -> usize {
+        self.feature_window
+    }
+
     pub fn wrapping(&self) -> Wrap2d {
         self.wrapping
     }
@@ -410,15 +532,28 @@ impl PlayerControl {
 
     delegate! {
         to self.game.read().await {
+            #[unwrap]
             pub async fn current_player(&self) -> PlayerNum;
 
+            #[unwrap]
+            pub async fn paused(&self) -> bool;
+
             #[unwrap]
             pub async fn is_player_turn(&self, [self.secret]) -> bool;
 
+            pub async fn turn_time_remaining(&self, [self.secret]) -> UmpireResult<Option<Duration>>;
+
+            #[unwrap]
+            pub async fn num_players(&self) -> PlayerNum;
+
             pub async fn propose_move_unit_by_id(&self, [self.secret], id: UnitID, dest: Location) -> ProposedUmpireResult<Move>;
 
             pub async fn propose_order_unit_explore(&self, [self.secret], unit_id: UnitID) -> ProposedOrdersResult;
 
+            pub async fn propose_order_unit_escort(&self, [self.secret], unit_id: UnitID, target: UnitID) -> ProposedOrdersResult;
+
+            pub async fn propose_order_unit_build(&self, [self.secret], unit_id: UnitID, improvement: Improvement) -> ProposedOrdersResult;
+
             pub async fn propose_order_unit_go_to(
                 &self,
                 [self.secret],
@@ -426,6 +561,20 @@ impl PlayerControl {
                 dest: Location,
             ) -> ProposedOrdersResult;
 
+            pub async fn propose_set_and_follow_orders(&self, [self.secret], id: UnitID, orders: Orders) -> ProposedOrdersResult;
+
+            pub async fn diplomatic_status(&self, [self.secret], other: PlayerNum) -> UmpireResult<DiplomaticStatus>;
+
+            pub async fn combat_odds(&self, [self.secret], attacker_id: UnitID, defender_loc: Location) -> UmpireResult<CombatOdds>;
+
+            pub async fn player_air_bases(&self, [self.secret], unit_id: UnitID) -> UmpireResult<Vec<Location>>;
+
+            pub async fn player_unit_bombard_targets(&self, [self.secret], unit_id: UnitID) -> UmpireResult<Vec<Location>>;
+
+            pub async fn player_unit_drop_zones(&self, [self.secret], unit_id: UnitID) -> UmpireResult<Vec<Location>>;
+
+            pub async fn player_known_minefields(&self, [self.secret]) -> UmpireResult<Vec<Location>>;
+
             #[unwrap]
             pub async fn player_cities(&self, [self.secret]) -> Vec<City>;
 
@@ -440,6 +589,7 @@ impl PlayerControl {
 
             pub async fn player_score(&self, [self.secret]) -> UmpireResult<f64>;
 
+            #[unwrap]
             pub async fn current_player_score(&self) -> f64;
 
             #[unwrap]
@@ -450,6 +600,10 @@ impl PlayerControl {
 
             pub async fn player_unit_legal_directions(&self, [self.secret], unit_id: UnitID) -> UmpireResult<Vec<Direction>>;
 
+            pub async fn player_unit_legal_one_step_destinations(&self, [self.secret], unit_id: UnitID) -> UmpireResult<BTreeSet<Location>>;
+
+            pub async fn player_unit_reachable_this_turn(&self, [self.secret], unit_id: UnitID) -> UmpireResult<BTreeSet<Location>>;
+
             #[unwrap]
             pub async fn player_unit_orders_requests(&self, [self.secret]) -> Vec<UnitID>;
 
@@ -459,14 +613,25 @@ impl PlayerControl {
             #[unwrap]
             pub async fn player_units(&self, [self.secret]) -> Vec<Unit>;
 
+            #[unwrap]
+            pub async fn treaty_proposals(&self, [self.secret]) -> Vec<(PlayerNum, DiplomaticStatus)>;
+
+            #[unwrap]
+            pub async fn unit_groups(&self, [self.secret]) -> Vec<(String, Vec<UnitID>)>;
+
+            pub async fn chat_messages_since(&self, [self.secret], since: usize) -> UmpireResult<Vec<ChatMessage>>;
+
+            #[unwrap]
             pub async fn turn(&self) -> TurnNum;
 
             #[unwrap]
             pub async fn player_action(&self, [self.secret]) -> ActionNum;
 
             pub async fn turn_is_done(&self, [self.player], turn: TurnNum) -> UmpireResult<bool>;
+            #[unwrap]
             pub async fn current_turn_is_done(&self) -> bool;
 
+            #[unwrap]
             pub async fn turn_phase(&self) -> TurnPhase;
 
             #[unwrap]
@@ -475,7 +640,14 @@ impl PlayerControl {
             #[unwrap]
             pub async fn valid_productions_conservative(&self, [self.secret], loc: Location) -> Vec<UnitType>;
 
+            #[unwrap]
             pub async fn victor(&self) -> Option<PlayerNum>;
+
+            #[unwrap]
+            pub async fn game_over(&self) -> Option<GameOver>;
+
+            #[unwrap]
+            pub async fn turn_limit(&self) -> Option<TurnNum>;
         }
     }
 
@@ -499,6 +671,195 @@ impl PlayerControl {
             })
     }
 
+    pub async fn load_unit(&mut self, unit_id: UnitID, carrier_id: UnitID) -> UmpireResult<Move> {
+        self.game
+            .write()
+            .await
+            .load_unit(self.secret, unit_id, carrier_id)
+            .await
+            .map(|move_| {
+                self.observations.track_many(move_.observations());
+                move_
+            })
+    }
+
+    pub async fn unload_unit(&mut self, unit_id: UnitID, dest: Location) -> UmpireResult<Move> {
+        self.game
+            .write()
+            .await
+            .unload_unit(self.secret, unit_id, dest)
+            .await
+            .map(|move_| {
+                self.observations.track_many(move_.observations());
+                move_
+            })
+    }
+
+    pub async fn rebase_unit(&mut self, unit_id: UnitID, base: Location) -> UmpireResult<Move> {
+        self.game
+            .write()
+            .await
+            .rebase_unit(self.secret, unit_id, base)
+            .await
+            .map(|move_| {
+                self.observations.track_many(move_.observations());
+                move_
+            })
+    }
+
+    pub async fn paradrop(&mut self, unit_id: UnitID, dest: Location) -> UmpireResult<Move> {
+        self.game
+            .write()
+            .await
+            .paradrop(self.secret, unit_id, dest)
+            .await
+            .map(|move_| {
+                self.observations.track_many(move_.observations());
+                move_
+            })
+    }
+
+    pub async fn build_city_walls(&mut self, city_id: CityID) -> UmpireResult<WallsBuilt> {
+        let result = self
+            .game
+            .write()
+            .await
+            .build_city_walls(self.secret, city_id)
+            .await;
+
+        if let Ok(ref outcome) = result {
+            self.observations.track_lite(outcome.obs.clone());
+        }
+
+        result
+    }
+
+    pub async fn build_city_radar(&mut self, city_id: CityID) -> UmpireResult<RadarBuilt> {
+        let result = self
+            .game
+            .write()
+            .await
+            .build_city_radar(self.secret, city_id)
+            .await;
+
+        if let Ok(ref outcome) = result {
+            self.observations.track_lite(outcome.obs.clone());
+        }
+
+        result
+    }
+
+    pub async fn lay_mines(&mut self, unit_id: UnitID) -> UmpireResult<Location> {
+        self.game
+            .write()
+            .await
+            .lay_mines(self.secret, unit_id)
+            .await
+    }
+
+    pub async fn sweep_mines(&mut self, unit_id: UnitID) -> UmpireResult<Vec<Location>> {
+        self.game
+            .write()
+            .await
+            .sweep_mines(self.secret, unit_id)
+            .await
+    }
+
+    pub async fn propose_treaty(
+        &mut self,
+        other: PlayerNum,
+        status: DiplomaticStatus,
+    ) -> UmpireResult<()> {
+        self.game
+            .write()
+            .await
+            .propose_treaty(self.secret, other, status)
+            .await
+    }
+
+    pub async fn accept_treaty(&mut self, other: PlayerNum) -> UmpireResult<DiplomaticStatus> {
+        self.game
+            .write()
+            .await
+            .accept_treaty(self.secret, other)
+            .await
+    }
+
+    pub async fn send_chat_message(&mut self, text: String) -> UmpireResult<()> {
+        self.game
+            .write()
+            .await
+            .send_chat_message(self.secret, text)
+            .await
+    }
+
+    pub async fn declare_war(&mut self, other: PlayerNum) -> UmpireResult<DiplomaticStatus> {
+        self.game
+            .write()
+            .await
+            .declare_war(self.secret, other)
+            .await
+    }
+
+    pub async fn resign(&mut self) -> UmpireResult<Resigned> {
+        let result = self.game.write().await.resign(self.secret).await;
+
+        if let Ok(ref outcome) = result {
+            self.observations
+                .track_many_lite(outcome.observations.iter());
+        }
+
+        result
+    }
+
+    /// Clear the orders of the player's unit `id`, waking it if it was standing sentry
+    pub async fn clear_orders(&mut self, id: UnitID) -> UmpireResult<Option<Orders>> {
+        self.game.write().await.clear_orders(self.secret, id).await
+    }
+
+    pub async fn form_unit_group(
+        &mut self,
+        name: String,
+        unit_ids: Vec<UnitID>,
+    ) -> UmpireResult<()> {
+        self.game
+            .write()
+            .await
+            .form_unit_group(self.secret, name, unit_ids)
+            .await
+    }
+
+    pub async fn disband_unit_group(&mut self, name: String) -> UmpireResult<Vec<UnitID>> {
+        self.game
+            .write()
+            .await
+            .disband_unit_group(self.secret, name)
+            .await
+    }
+
+    pub async fn order_unit_group(
+        &mut self,
+        name: String,
+        orders: Orders,
+    ) -> UmpireResult<Vec<OrdersResult>> {
+        let result = self
+            .game
+            .write()
+            .await
+            .order_unit_group(self.secret, name, orders)
+            .await;
+
+        if let Ok(ref orders_results) = result {
+            for orders_outcome in orders_results.iter().flatten() {
+                if let Some(move_) = orders_outcome.move_.as_ref() {
+                    self.observations.track_many(move_.observations());
+                }
+            }
+        }
+
+        result
+    }
+
     /// The player's most recent observation at the given location
     pub fn obs(&self, loc: Location) -> Option<Obs> {
         self.observations.get(loc).cloned()
@@ -508,6 +869,21 @@ impl PlayerControl {
         &self.observations
     }
 
+    /// The player's units as of the last `sync_turn`
+    pub fn synced_units(&self) -> impl Iterator<Item = &Unit> {
+        self.synced.units.values()
+    }
+
+    /// The player's cities as of the last `sync_turn`
+    pub fn synced_cities(&self) -> impl Iterator<Item = &City> {
+        self.synced.cities.values()
+    }
+
+    /// How many chat messages had been sent as of the last `sync_turn`
+    pub fn chat_synced(&self) -> usize {
+        self.sync.chat
+    }
+
     /// The tile at the given location, as present in the player's observations (or not)
     pub fn tile(&self, loc: Location) -> Option<Cow<Tile>> {
         match self.observations.get(loc) {
@@ -517,11 +893,11 @@ impl PlayerControl {
     }
 
     /// FIXME Maintain this vector in the client, incrementally
-    pub async fn player_features(&self, focus: TrainingFocus) -> Vec<fX> {
+    pub async fn player_features(&self, focus: TrainingFocus, window: usize) -> Vec<fX> {
         self.game
             .read()
             .await
-            .player_features(self.secret, focus)
+            .player_features(self.secret, focus, window)
             .await
             .unwrap()
     }
@@ -541,11 +917,30 @@ pub struct PlayerTurn<'a> {
 }
 
 impl<'a> PlayerTurn<'a> {
+    /// Begin the player's turn
+    ///
+    /// If the turn was already begun---as when resuming a game saved mid-turn---it is picked up where it left off
+    /// rather than begun anew.
     pub async fn new(
         ctrl: &'a mut PlayerControl,
         clear_after_unit_production: bool,
     ) -> PlayerTurn<'a> {
-        let turn_start = ctrl.begin_turn(clear_after_unit_production).await.unwrap();
+        let resuming = ctrl.turn_phase().await == TurnPhase::Main
+            && ctrl.current_player().await == ctrl.player;
+
+        let turn_start = if resuming {
+            TurnStart {
+                turn: ctrl.turn().await,
+                current_player: ctrl.player,
+                orders_results: Vec::new(),
+                production_outcomes: Vec::new(),
+                observations: Vec::new(),
+                units_woken: Vec::new(),
+                alerts: Vec::new(),
+            }
+        } else {
+            ctrl.begin_turn(clear_after_unit_production).await.unwrap()
+        };
         Self {
             ctrl,
             turn_start,
@@ -553,6 +948,27 @@ impl<'a> PlayerTurn<'a> {
         }
     }
 
+    /// View the game through the player's eyes without beginning a turn
+    ///
+    /// For rendering the game outside the player's own turn, as when watching a replay. The turn counts as already
+    /// ended, so it can be dropped freely but not ended again.
+    pub async fn spectate(ctrl: &'a mut PlayerControl) -> PlayerTurn<'a> {
+        let turn_start = TurnStart {
+            turn: ctrl.turn().await,
+            current_player: ctrl.current_player().await,
+            orders_results: Vec::new(),
+            production_outcomes: Vec::new(),
+            observations: Vec::new(),
+            units_woken: Vec::new(),
+            alerts: Vec::new(),
+        };
+        Self {
+            ctrl,
+            turn_start,
+            ended: true,
+        }
+    }
+
     pub fn start(&self) -> &TurnStart {
         &self.turn_start
     }
@@ -593,6 +1009,21 @@ impl<'a> PlayerTurn<'a> {
         self.ended
     }
 
+    /// Whether the turn was ended for the player, their time having run out
+    ///
+    /// The turn then counts as ended, so it can be dropped but not ended again.
+    pub async fn out_of_time(&mut self) -> bool {
+        if self.ended {
+            return false;
+        }
+
+        self.ended = matches!(
+            self.ctrl.turn_time_remaining().await,
+            Err(GameError::NotPlayersTurn { .. })
+        );
+        self.ended
+    }
+
     delegate! {
         to self.ctrl {
             // Mutable
@@ -600,10 +1031,14 @@ impl<'a> PlayerTurn<'a> {
 
             // pub async fn begin_turn(&mut self) -> UmpireResult<TurnStart>;
 
+            pub async fn sync_turn(&mut self) -> UmpireResult<Vec<ChatMessage>>;
+
             pub async fn clear_production(&mut self, loc: Location, ignore_cleared_production: bool) -> UmpireResult<ProductionCleared>;
 
             pub async fn clear_productions(&mut self, ignore_cleared_production: bool) -> UmpireResult<Vec<ProductionCleared>>;
 
+            pub async fn clear_orders(&mut self, id: UnitID) -> UmpireResult<Option<Orders>>;
+
             pub async fn disband_unit_by_id(&mut self, id: UnitID) -> UmpireResult<UnitDisbanded>;
 
             // pub async fn end_turn(&mut self) -> UmpireResult<()>;
@@ -612,12 +1047,46 @@ impl<'a> PlayerTurn<'a> {
 
             pub async fn move_unit_by_id_in_direction(&mut self, id: UnitID, direction: Direction) -> UmpireResult<Move>;
 
+            pub async fn load_unit(&mut self, unit_id: UnitID, carrier_id: UnitID) -> UmpireResult<Move>;
+
+            pub async fn unload_unit(&mut self, unit_id: UnitID, dest: Location) -> UmpireResult<Move>;
+
+            pub async fn rebase_unit(&mut self, unit_id: UnitID, base: Location) -> UmpireResult<Move>;
+
+            pub async fn bombard(&mut self, unit_id: UnitID, target: Location) -> UmpireResult<Bombardment>;
+
+            pub async fn paradrop(&mut self, unit_id: UnitID, dest: Location) -> UmpireResult<Move>;
+
+            pub async fn lay_mines(&mut self, unit_id: UnitID) -> UmpireResult<Location>;
+
+            pub async fn sweep_mines(&mut self, unit_id: UnitID) -> UmpireResult<Vec<Location>>;
+
+            pub async fn propose_treaty(&mut self, other: PlayerNum, status: DiplomaticStatus) -> UmpireResult<()>;
+
+            pub async fn accept_treaty(&mut self, other: PlayerNum) -> UmpireResult<DiplomaticStatus>;
+
+            pub async fn declare_war(&mut self, other: PlayerNum) -> UmpireResult<DiplomaticStatus>;
+
+            pub async fn resign(&mut self) -> UmpireResult<Resigned>;
+
+            pub async fn send_chat_message(&mut self, text: String) -> UmpireResult<()>;
+
+            pub async fn form_unit_group(&mut self, name: String, unit_ids: Vec<UnitID>) -> UmpireResult<()>;
+
+            pub async fn disband_unit_group(&mut self, name: String) -> UmpireResult<Vec<UnitID>>;
+
+            pub async fn order_unit_group(&mut self, name: String, orders: Orders) -> UmpireResult<Vec<OrdersResult>>;
+
             pub async fn order_unit_sentry(&mut self, unit_id: UnitID) -> UmpireResult<OrdersSet>;
 
             pub async fn order_unit_skip(&mut self,  unit_id: UnitID) -> UmpireResult<OrdersSet>;
 
             pub async fn set_production_by_loc(&mut self, loc: Location, production: UnitType) -> UmpireResult<ProductionSet>;
 
+            pub async fn build_city_walls(&mut self, city_id: CityID) -> UmpireResult<WallsBuilt>;
+
+            pub async fn build_city_radar(&mut self, city_id: CityID) -> UmpireResult<RadarBuilt>;
+
             pub async fn take_action(&mut self, action: PlayerAction) -> UmpireResult<PlayerActionOutcome>;
 
             pub async fn take_simple_action(&mut self, action: AiPlayerAction) -> UmpireResult<PlayerActionOutcome>;
@@ -627,27 +1096,51 @@ impl<'a> PlayerTurn<'a> {
 
             pub async fn current_player(&self) -> PlayerNum;
 
+            pub async fn paused(&self) -> bool;
+
+            pub async fn num_players(&self) -> PlayerNum;
+
             pub fn dims(&self) -> Dims;
 
+            pub fn feature_window(&self) -> usize;
+
             pub async fn is_player_turn(&self) -> bool;
 
+            pub async fn turn_time_remaining(&self) -> UmpireResult<Option<Duration>>;
+
             pub async fn propose_move_unit_by_id(&self, id: UnitID, dest: Location) -> ProposedUmpireResult<Move>;
 
             pub async fn propose_order_unit_explore(&self, unit_id: UnitID) -> ProposedOrdersResult;
 
+            pub async fn propose_order_unit_escort(&self, unit_id: UnitID, target: UnitID) -> ProposedOrdersResult;
+
+            pub async fn propose_order_unit_build(&self, unit_id: UnitID, improvement: Improvement) -> ProposedOrdersResult;
+
             pub async fn propose_order_unit_go_to(
                 &self,
                 unit_id: UnitID,
                 dest: Location,
             ) -> ProposedOrdersResult;
 
+            pub async fn propose_set_and_follow_orders(&self, id: UnitID, orders: Orders) -> ProposedOrdersResult;
+
             pub fn obs(&self, loc: Location) -> Option<Obs>;
 
+            pub fn observations(&self) -> &ObsTracker;
+
+            pub fn synced_units(&self) -> impl Iterator<Item = &Unit>;
+
+            pub fn synced_cities(&self) -> impl Iterator<Item = &City>;
+
+            pub fn chat_synced(&self) -> usize;
+
+            pub async fn player_cities(&self) -> Vec<City>;
+
             pub async fn player_cities_producing_or_not_ignored(&self) -> usize;
 
             pub async fn player_city_by_loc(&self, loc: Location) -> Option<City>;
 
-            pub async fn player_features(&self, focus: TrainingFocus) -> Vec<fX>;
+            pub async fn player_features(&self, focus: TrainingFocus, window: usize) -> Vec<fX>;
 
             pub async fn player_production_set_requests(&self) -> Vec<Location>;
 
@@ -657,7 +1150,11 @@ impl<'a> PlayerTurn<'a> {
 
             pub async fn player_unit_by_id(&self, id: UnitID) -> Option<Unit>;
 
+            pub async fn player_units(&self) -> Vec<Unit>;
+
             pub async fn player_unit_legal_directions(&self, unit_id: UnitID) -> UmpireResult<Vec<Direction>>;
+            pub async fn player_unit_legal_one_step_destinations(&self, unit_id: UnitID) -> UmpireResult<BTreeSet<Location>>;
+            pub async fn player_unit_reachable_this_turn(&self, unit_id: UnitID) -> UmpireResult<BTreeSet<Location>>;
             pub async fn player_next_unit_legal_actions(
                 &self,
             ) -> BTreeSet<NextUnitAction>;
@@ -670,6 +1167,24 @@ impl<'a> PlayerTurn<'a> {
 
             pub async fn player_unit_loc(&self, id: UnitID) -> Option<Location>;
 
+            pub async fn diplomatic_status(&self, other: PlayerNum) -> UmpireResult<DiplomaticStatus>;
+
+            pub async fn combat_odds(&self, attacker_id: UnitID, defender_loc: Location) -> UmpireResult<CombatOdds>;
+
+            pub async fn player_air_bases(&self, unit_id: UnitID) -> UmpireResult<Vec<Location>>;
+
+            pub async fn player_unit_bombard_targets(&self, unit_id: UnitID) -> UmpireResult<Vec<Location>>;
+
+            pub async fn player_unit_drop_zones(&self, unit_id: UnitID) -> UmpireResult<Vec<Location>>;
+
+            pub async fn player_known_minefields(&self) -> UmpireResult<Vec<Location>>;
+
+            pub async fn treaty_proposals(&self) -> Vec<(PlayerNum, DiplomaticStatus)>;
+
+            pub async fn chat_messages_since(&self, since: usize) -> UmpireResult<Vec<ChatMessage>>;
+
+            pub async fn unit_groups(&self) -> Vec<(String, Vec<UnitID>)>;
+
             pub fn tile(&self, loc: Location) -> Option<Cow<Tile>>;
 
             pub async fn turn(&self) -> TurnNum;
@@ -685,6 +1200,10 @@ impl<'a> PlayerTurn<'a> {
 
             pub async fn victor(&self) -> Option<PlayerNum>;
 
+            pub async fn game_over(&self) -> Option<GameOver>;
+
+            pub async fn turn_limit(&self) -> Option<TurnNum>;
+
             pub fn wrapping(&self) -> Wrap2d;
         }
     }
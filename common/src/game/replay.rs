@@ -0,0 +1,87 @@
+//! Recording of the actions taken during a game
+//!
+//! A `Replay` holds the state of the game at the moment recording began, followed by every action the engine
+//! accepted from then on, in order. Recording reseeds the game's random number generator so that taking the recorded
+//! actions against the initial state reproduces the game exactly, combat outcomes included.
+//!
+//! Only actions expressible as a `PlayerAction` are recorded. Moves made through the `move_toplevel_*` and
+//! `*_avoiding_combat` methods are not, and a replay containing them will diverge from the original game.
+
+use std::{
+    path::Path,
+    sync::{Arc, RwLock},
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::name::Namer;
+
+use super::{
+    action::PlayerAction,
+    save::{read_compressed, write_compressed, SaveError, SavedGame},
+    Game, PlayerNum, PlayerSecret, TurnNum,
+};
+
+/// A single action as recorded in a replay
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq, Serialize)]
+pub struct ReplayEntry {
+    /// The turn during which the action was taken
+    pub turn: TurnNum,
+
+    /// The player that took the action
+    pub player: PlayerNum,
+
+    pub action: PlayerAction,
+}
+
+/// An append-only log of the actions taken in a game, along with the game's initial state
+#[derive(Clone, Deserialize, Serialize)]
+pub struct Replay {
+    initial: SavedGame,
+    entries: Vec<ReplayEntry>,
+}
+
+impl Replay {
+    pub(in crate::game) fn new(initial: SavedGame) -> Self {
+        Self {
+            initial,
+            entries: Vec::new(),
+        }
+    }
+
+    pub(in crate::game) fn record(&mut self, entry: ReplayEntry) {
+        self.entries.push(entry);
+    }
+
+    pub fn entries(&self) -> &[ReplayEntry] {
+        &self.entries
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// The game as it stood when recording began, and its player secrets
+    ///
+    /// Taking each entry's action as the corresponding player reproduces the recorded game.
+    pub fn initial_game(
+        &self,
+        unit_namer: Option<Arc<RwLock<dyn Namer>>>,
+    ) -> (Game, Vec<PlayerSecret>) {
+        self.initial.clone().into_game(unit_namer)
+    }
+
+    /// Write the replay to the file at `path`
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<(), SaveError> {
+        write_compressed(path, self)
+    }
+
+    /// Read a replay previously written by `save`
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self, SaveError> {
+        read_compressed(path)
+    }
+}
@@ -0,0 +1,216 @@
+//! Saving and loading of games
+//!
+//! The full engine state is written as gzip-compressed bincode. Two pieces of state can't be serialized directly:
+//! the random number generator, which is reseeded from a value drawn from it at save time; and the unit namer,
+//! which must be supplied again when loading.
+
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fs::File,
+    io::{BufReader, BufWriter, Write},
+    path::Path,
+    sync::{Arc, RwLock},
+};
+
+use flate2::{read::GzDecoder, write::GzEncoder, Compression};
+use rand::Rng;
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use thiserror::Error;
+use tokio::sync::watch;
+
+use crate::{
+    name::{IntNamer, Namer},
+    util::{init_rng, Wrap2d},
+};
+
+use super::{
+    alert::Alert,
+    chat::ChatMessage,
+    diplomacy::Diplomacy,
+    group::UnitGroups,
+    history::PlayerHistory,
+    map::MapData,
+    obs::{FogOfWar, LocatedObsLite, PlayerObsTracker},
+    ActionNum, Game, PlayerNum, PlayerSecret, TurnNum, TurnPhase, TurnSummary, UnitWoken,
+};
+
+/// Bumped whenever the saved game format changes incompatibly
+pub const SAVE_FORMAT_VERSION: u32 = 23;
+
+#[derive(Debug, Error)]
+pub enum SaveError {
+    #[error("Error accessing saved game file: {0}")]
+    Io(#[from] std::io::Error),
+
+    #[error("Error encoding or decoding saved game: {0}")]
+    Encoding(#[from] bincode::Error),
+
+    #[error("Saved game has format version {found} but only version {expected} is supported")]
+    UnsupportedVersion { found: u32, expected: u32 },
+}
+
+/// Everything in `Game` that gets persisted
+#[derive(Clone, Deserialize, Serialize)]
+pub(in crate::game) struct SavedGame {
+    rng_seed: u64,
+    deterministic_secrets: bool,
+    map: MapData,
+    player_observations: PlayerObsTracker,
+    player_pending_observations: Vec<Vec<LocatedObsLite>>,
+    turn: TurnNum,
+    turn_phase: TurnPhase,
+    num_players: PlayerNum,
+    player_secrets: Vec<PlayerSecret>,
+    current_player: PlayerNum,
+    wrapping: Wrap2d,
+    fog_of_war: FogOfWar,
+    turn_limit: Option<TurnNum>,
+    action_count: ActionNum,
+    action_counts: Vec<ActionNum>,
+    defeated_unit_hitpoints: Vec<u64>,
+    diplomacy: Diplomacy,
+    unit_groups: Vec<UnitGroups>,
+    units_woken: Vec<Vec<UnitWoken>>,
+    alerts: Vec<Vec<Alert>>,
+    turn_summaries: Vec<TurnSummary>,
+    observed_at_turn_end: Vec<usize>,
+    histories: Vec<PlayerHistory>,
+    chat: Vec<ChatMessage>,
+    player_names: Vec<Option<String>>,
+    resigned: BTreeSet<PlayerNum>,
+}
+
+impl SavedGame {
+    /// Snapshot `game`, to be restored with an RNG seeded by `rng_seed`
+    pub(in crate::game) fn new(game: &Game, rng_seed: u64) -> Self {
+        Self {
+            rng_seed,
+            deterministic_secrets: game.deterministic_secrets,
+            map: game.map.clone(),
+            player_observations: game.player_observations.clone(),
+            player_pending_observations: game.player_pending_observations.clone(),
+            turn: game.turn,
+            turn_phase: game.turn_phase,
+            num_players: game.num_players,
+            player_secrets: game.player_secrets.clone(),
+            current_player: game.current_player,
+            wrapping: game.wrapping,
+            fog_of_war: game.fog_of_war,
+            turn_limit: game.turn_limit,
+            action_count: game.action_count,
+            action_counts: game.action_counts.clone(),
+            defeated_unit_hitpoints: game.defeated_unit_hitpoints.clone(),
+            diplomacy: game.diplomacy.clone(),
+            unit_groups: game.unit_groups.clone(),
+            units_woken: game.units_woken.clone(),
+            alerts: game.alerts.clone(),
+            turn_summaries: game.turn_summaries.clone(),
+            observed_at_turn_end: game.observed_at_turn_end.clone(),
+            histories: game.histories.clone(),
+            chat: game.chat.clone(),
+            player_names: game.player_names.clone(),
+            resigned: game.resigned.clone(),
+        }
+    }
+
+    /// Restore the snapshotted game, along with its player secrets
+    pub(in crate::game) fn into_game(
+        self,
+        unit_namer: Option<Arc<RwLock<dyn Namer>>>,
+    ) -> (Game, Vec<PlayerSecret>) {
+        let secrets = self.player_secrets.clone();
+        let chat_watch = Arc::new(watch::channel(self.chat.len()).0);
+
+        let game = Game {
+            rng: init_rng(Some(self.rng_seed)),
+            deterministic_secrets: self.deterministic_secrets,
+            map: self.map,
+            player_observations: self.player_observations,
+            player_pending_observations: self.player_pending_observations,
+            turn: self.turn,
+            turn_phase: self.turn_phase,
+            num_players: self.num_players,
+            player_secrets: self.player_secrets,
+            current_player: self.current_player,
+            wrapping: self.wrapping,
+            unit_namer: unit_namer.unwrap_or(Arc::new(RwLock::new(IntNamer::new("unit")))),
+            fog_of_war: self.fog_of_war,
+            turn_limit: self.turn_limit,
+            action_count: self.action_count,
+            action_counts: self.action_counts,
+            defeated_unit_hitpoints: self.defeated_unit_hitpoints,
+            diplomacy: self.diplomacy,
+            unit_groups: self.unit_groups,
+            units_woken: self.units_woken,
+            alerts: self.alerts,
+            turn_summaries: self.turn_summaries,
+            observed_at_turn_end: self.observed_at_turn_end,
+            histories: self.histories,
+            chat: self.chat,
+            player_names: self.player_names,
+            resigned: self.resigned,
+            replay: None,
+            replay_depth: 0,
+            go_to_routes: BTreeMap::new(),
+            paused: false,
+            turn_watch: Arc::new(watch::channel((self.turn, self.current_player)).0),
+            chat_watch,
+        };
+
+        (game, secrets)
+    }
+}
+
+/// Write `value` to the file at `path` as versioned, gzip-compressed bincode
+pub(in crate::game) fn write_compressed<P: AsRef<Path>, T: Serialize>(
+    path: P,
+    value: &T,
+) -> Result<(), SaveError> {
+    let mut w = GzEncoder::new(BufWriter::new(File::create(path)?), Compression::default());
+
+    bincode::serialize_into(&mut w, &SAVE_FORMAT_VERSION)?;
+    bincode::serialize_into(&mut w, value)?;
+
+    w.finish()?.flush()?;
+
+    Ok(())
+}
+
+/// Read a value written by `write_compressed`
+pub(in crate::game) fn read_compressed<P: AsRef<Path>, T: DeserializeOwned>(
+    path: P,
+) -> Result<T, SaveError> {
+    let mut r = GzDecoder::new(BufReader::new(File::open(path)?));
+
+    let version: u32 = bincode::deserialize_from(&mut r)?;
+    if version != SAVE_FORMAT_VERSION {
+        return Err(SaveError::UnsupportedVersion {
+            found: version,
+            expected: SAVE_FORMAT_VERSION,
+        });
+    }
+
+    Ok(bincode::deserialize_from(&mut r)?)
+}
+
+impl Game {
+    /// Write the full state of the game to the file at `path`
+    ///
+    /// The game may be saved mid-turn; loading it will resume the turn where it left off. Any replay being recorded
+    /// is not included.
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<(), SaveError> {
+        write_compressed(path, &SavedGame::new(self, self.rng.clone().gen()))
+    }
+
+    /// Load a game previously written by `save`
+    ///
+    /// Unit names are generated by `unit_namer`, as in `Game::new`.
+    ///
+    /// Also returns the player secrets used for access control
+    pub fn load<P: AsRef<Path>>(
+        path: P,
+        unit_namer: Option<Arc<RwLock<dyn Namer>>>,
+    ) -> Result<(Self, Vec<PlayerSecret>), SaveError> {
+        read_compressed(path).map(|saved: SavedGame| saved.into_game(unit_namer))
+    }
+}
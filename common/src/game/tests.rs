@@ -4,24 +4,33 @@ use std::{
 };
 
 use rand::Rng;
+use uuid::Uuid;
 
 use crate::{
+    conf,
     game::{
-        map::{MapData, Terrain},
+        action::{PlayerAction, PlayerActionOutcome},
+        alert::Alert,
+        chat::{ChatMessage, MAX_CHAT_MESSAGE_LEN, MAX_PLAYER_NAME_LEN},
+        combat::CombatCapable,
+        diplomacy::DiplomaticStatus,
+        map::{Improvement, MapData, Terrain},
         move_::MoveError,
-        obs::Obs,
-        test_support::game_two_cities_two_infantry,
+        obs::{Obs, ObsTrackerI},
+        replay::Replay,
+        save::SaveError,
+        test_support::{game1, game_two_cities_two_infantry},
         unit::{
-            orders::{Orders, OrdersStatus},
+            orders::{Orders, OrdersResult, OrdersStatus},
             Fuel, TransportMode, Unit, UnitID, UnitType,
         },
-        Alignment, Game, GameError, TurnNum,
+        Alignment, Game, GameError, GameOver, GameOverReason, TurnNum, TurnPhase, TurnSummary,
     },
     name::{unit_namer, Named},
-    util::{Dims, Direction, Location, Vec2d, Wrap2d},
+    util::{init_rng, Dims, Direction, Location, Vec2d, Wrap2d},
 };
 
-use super::ai::TrainingFocus;
+use super::ai::{TrainingFocus, DEEP_WIDTH};
 
 #[test]
 fn test_game() {
@@ -336,6 +345,230 @@ fn test_unit_moves_onto_transport() {
     assert_eq!(move_result.ending_carrier(), Some(transport_id));
 }
 
+#[test]
+fn test_load_and_unload_unit() {
+    let map = MapData::try_from("---it   ").unwrap();
+    let infantry_loc = Location::new(3, 0);
+    let transport_loc = Location::new(4, 0);
+
+    let infantry_id: UnitID = map.toplevel_unit_id_by_loc(infantry_loc).unwrap();
+    let transport_id: UnitID = map.toplevel_unit_id_by_loc(transport_loc).unwrap();
+
+    let (mut game, secrets) = Game::new_with_map(None, false, map, 1, false, None, Wrap2d::NEITHER);
+
+    game.begin_turn(secrets[0], false).unwrap();
+
+    assert_eq!(
+        game.unload_unit(secrets[0], infantry_id, Location::new(2, 0)),
+        Err(GameError::UnitNotCarried { id: infantry_id })
+    );
+
+    let move_ = game
+        .load_unit(secrets[0], infantry_id, transport_id)
+        .unwrap();
+    assert!(move_.moved_successfully());
+    assert_eq!(move_.ending_loc(), Some(transport_loc));
+    assert_eq!(move_.ending_carrier(), Some(transport_id));
+
+    game.force_end_turn(secrets[0]).unwrap();
+    game.begin_turn(secrets[0], false).unwrap();
+
+    assert_eq!(
+        game.unload_unit(secrets[0], infantry_id, Location::new(1, 0)),
+        Err(GameError::NotAdjacent {
+            src: transport_loc,
+            dest: Location::new(1, 0),
+        })
+    );
+
+    let move_ = game
+        .unload_unit(secrets[0], infantry_id, infantry_loc)
+        .unwrap();
+    assert!(move_.moved_successfully());
+    assert_eq!(move_.ending_loc(), Some(infantry_loc));
+    assert_eq!(move_.ending_carrier(), None);
+
+    assert_eq!(
+        game.current_player_toplevel_unit_by_loc(infantry_loc)
+            .unwrap()
+            .id,
+        infantry_id
+    );
+}
+
+#[test]
+fn test_rough_terrain_movement_cost() {
+    let map = MapData::try_from(
+        "\
+A%-
+---",
+    )
+    .unwrap();
+    let armor_id: UnitID = map.toplevel_unit_id_by_loc(Location::new(0, 0)).unwrap();
+    let forest_loc = Location::new(1, 0);
+
+    let (mut game, secrets) = Game::new_with_map(None, false, map, 1, false, None, Wrap2d::NEITHER);
+
+    game.begin_turn(secrets[0], false).unwrap();
+
+    // Going straight through the forest would cost 3, so the armor goes around it
+    let move_ = game
+        .move_unit_by_id(secrets[0], armor_id, Location::new(2, 0))
+        .unwrap();
+    assert_eq!(move_.components[0].loc, Location::new(1, 1));
+    assert_eq!(move_.ending_loc(), Some(Location::new(2, 0)));
+
+    game.force_end_turn(secrets[0]).unwrap();
+    game.begin_turn(secrets[0], false).unwrap();
+
+    // Entering the forest takes the armor's full movement
+    game.move_unit_by_id(secrets[0], armor_id, forest_loc)
+        .unwrap();
+    assert_eq!(
+        game.current_player_unit_by_id(armor_id)
+            .unwrap()
+            .moves_remaining(),
+        0
+    );
+
+    game.force_end_turn(secrets[0]).unwrap();
+    game.begin_turn(secrets[0], false).unwrap();
+
+    // After stepping out, there isn't enough movement left to step back in
+    game.move_unit_by_id(secrets[0], armor_id, Location::new(0, 0))
+        .unwrap();
+    assert!(game
+        .move_unit_by_id(secrets[0], armor_id, forest_loc)
+        .is_err());
+}
+
+#[test]
+fn test_diplomacy() {
+    let map = MapData::try_from("iI-").unwrap();
+    let infantry_id: UnitID = map.toplevel_unit_id_by_loc(Location::new(0, 0)).unwrap();
+    let enemy_loc = Location::new(1, 0);
+
+    let (mut game, secrets) = Game::new_with_map(None, false, map, 2, false, None, Wrap2d::NEITHER);
+
+    game.begin_turn(secrets[0], false).unwrap();
+
+    assert_eq!(
+        game.diplomatic_status(secrets[0], 1),
+        Ok(DiplomaticStatus::War)
+    );
+    assert_eq!(
+        game.propose_treaty(secrets[0], 1, DiplomaticStatus::War),
+        Err(GameError::StatusNotProposable {
+            status: DiplomaticStatus::War
+        })
+    );
+    assert_eq!(
+        game.propose_treaty(secrets[0], 0, DiplomaticStatus::Peace),
+        Err(GameError::CannotNegotiateWithSelf { player: 0 })
+    );
+    assert_eq!(
+        game.accept_treaty(secrets[1], 0),
+        Err(GameError::NoTreatyProposed { from: 0, to: 1 })
+    );
+
+    // Player 1 accepts the alliance even though it isn't their turn
+    game.propose_treaty(secrets[0], 1, DiplomaticStatus::Alliance)
+        .unwrap();
+    assert_eq!(
+        game.treaty_proposals(secrets[1]),
+        Ok(vec![(0, DiplomaticStatus::Alliance)])
+    );
+    assert_eq!(
+        game.accept_treaty(secrets[1], 0),
+        Ok(DiplomaticStatus::Alliance)
+    );
+    assert_eq!(
+        game.diplomatic_status(secrets[0], 1),
+        Ok(DiplomaticStatus::Alliance)
+    );
+
+    // Allies can't attack each other
+    assert_eq!(
+        game.move_unit_by_id(secrets[0], infantry_id, enemy_loc),
+        Err(GameError::NotAtWar {
+            player: 0,
+            other: 1
+        })
+    );
+    assert!(game
+        .player_toplevel_unit_by_loc(secrets[1], enemy_loc)
+        .unwrap()
+        .is_some());
+
+    assert_eq!(
+        game.declare_war(secrets[1], 0),
+        Ok(DiplomaticStatus::Alliance)
+    );
+
+    let move_ = game
+        .move_unit_by_id(secrets[0], infantry_id, enemy_loc)
+        .unwrap();
+    assert!(move_.components[0].unit_combat.is_some());
+}
+
+#[test]
+fn test_capture_garrisoned_neutral_city() {
+    let mut captured = false;
+    let mut repelled = false;
+    while !captured || !repelled {
+        let mut map = MapData::try_from("A-").unwrap();
+        let armor_id: UnitID = map.toplevel_unit_id_by_loc(Location::new(0, 0)).unwrap();
+
+        let city_loc = Location::new(1, 0);
+        map.new_city(city_loc, Alignment::Neutral, "Neutralia")
+            .unwrap();
+        let garrison_id = map
+            .new_unit(
+                city_loc,
+                UnitType::Infantry,
+                Alignment::Neutral,
+                "Neutralia Garrison",
+            )
+            .unwrap();
+
+        let (mut game, secrets) =
+            Game::new_with_map(None, false, map, 1, false, None, Wrap2d::NEITHER);
+
+        game.begin_turn(secrets[0], false).unwrap();
+
+        let move_ = game
+            .move_unit_by_id(secrets[0], armor_id, city_loc)
+            .unwrap();
+
+        // The garrison has to be dealt with before the city itself
+        let unit_combat = move_.components[0].unit_combat.as_ref().unwrap();
+        if unit_combat.victorious() {
+            assert!(game.map.unit_by_id(garrison_id).is_none());
+
+            let city_combat = move_.components[0].city_combat.as_ref().unwrap();
+            if city_combat.victorious() {
+                assert_eq!(
+                    game.map.city_by_loc(city_loc).unwrap().alignment,
+                    Alignment::Belligerent { player: 0 }
+                );
+                captured = true;
+            }
+        } else {
+            assert!(move_.components[0].city_combat.is_none());
+            assert!(game.current_player_unit_by_id(armor_id).is_none());
+            assert_eq!(
+                game.map.toplevel_unit_id_by_loc(city_loc),
+                Some(garrison_id)
+            );
+            assert_eq!(
+                game.map.city_by_loc(city_loc).unwrap().alignment,
+                Alignment::Neutral
+            );
+            repelled = true;
+        }
+    }
+}
+
 #[test]
 fn test_loaded_transport_attack() {
     let mut victorious = false;
@@ -497,6 +730,259 @@ fn test_set_orders() {
     );
 }
 
+#[test]
+fn test_wake_sentry() {
+    let map = MapData::try_from("i--I------").unwrap();
+    let unit_id: UnitID = map.toplevel_unit_id_by_loc(Location::new(0, 0)).unwrap();
+    let enemy_id: UnitID = map.toplevel_unit_id_by_loc(Location::new(3, 0)).unwrap();
+
+    let (mut game, secrets) = Game::new_with_map(None, false, map, 2, true, None, Wrap2d::NEITHER);
+
+    game.begin_turn(secrets[0], false).unwrap();
+    game.order_unit_sentry(secrets[0], unit_id).unwrap();
+    game.end_turn(secrets[0]).unwrap();
+
+    game.begin_turn(secrets[1], false).unwrap();
+    game.force_end_turn(secrets[1]).unwrap();
+
+    // The enemy is out of sight, so the unit stays on sentry
+    let turn_start = game.begin_turn(secrets[0], false).unwrap();
+    assert!(turn_start.units_woken.is_empty());
+    assert_eq!(
+        game.current_player_unit_by_id(unit_id).unwrap().orders,
+        Some(Orders::Sentry)
+    );
+    game.end_turn(secrets[0]).unwrap();
+
+    // The enemy comes into view
+    let enemy_loc = Location::new(2, 0);
+    game.begin_turn(secrets[1], false).unwrap();
+    game.move_unit_by_id(secrets[1], enemy_id, enemy_loc)
+        .unwrap();
+    game.force_end_turn(secrets[1]).unwrap();
+
+    let turn_start = game.begin_turn(secrets[0], false).unwrap();
+    assert_eq!(turn_start.units_woken.len(), 1);
+    assert_eq!(turn_start.units_woken[0].unit.id, unit_id);
+    assert_eq!(turn_start.units_woken[0].sighted, enemy_loc);
+    assert_eq!(
+        game.current_player_unit_by_id(unit_id).unwrap().orders,
+        None
+    );
+    assert_eq!(
+        game.current_player_unit_orders_requests()
+            .collect::<Vec<UnitID>>(),
+        vec![unit_id]
+    );
+}
+
+#[test]
+fn test_stack_combat() {
+    let mut map = MapData::try_from("aII").unwrap();
+    let armor_id: UnitID = map.toplevel_unit_id_by_loc(Location::new(0, 0)).unwrap();
+    let stack_loc = Location::new(1, 0);
+    let stacked_id: UnitID = map.toplevel_unit_id_by_loc(Location::new(2, 0)).unwrap();
+    map.stack_unit_by_id(stacked_id, stack_loc).unwrap();
+
+    let (mut game, secrets) = Game::new_with_map(None, false, map, 2, false, None, Wrap2d::NEITHER);
+
+    game.begin_turn(secrets[0], false).unwrap();
+
+    let move_ = game
+        .move_unit_by_id(secrets[0], armor_id, stack_loc)
+        .unwrap();
+    let victorious = move_.components[0]
+        .unit_combat
+        .as_ref()
+        .unwrap()
+        .victorious();
+
+    // Win or lose, one of the stack is left holding the tile, so a victorious armor stays where it was
+    assert_eq!(
+        game.map.tile(stack_loc).unwrap().stack_size(),
+        if victorious { 1 } else { 2 }
+    );
+    assert_eq!(
+        game.current_player_unit_loc(armor_id),
+        if victorious {
+            Some(Location::new(0, 0))
+        } else {
+            None
+        }
+    );
+}
+
+#[test]
+fn test_combat_odds() {
+    let map = MapData::try_from("aI-").unwrap();
+    let armor_id: UnitID = map.toplevel_unit_id_by_loc(Location::new(0, 0)).unwrap();
+    let infantry_loc = Location::new(1, 0);
+
+    let (mut game, secrets) = Game::new_with_map(None, false, map, 2, false, None, Wrap2d::NEITHER);
+
+    game.begin_turn(secrets[0], false).unwrap();
+
+    // The armor has two hitpoints to the infantry's one, and each blow is a coin flip, so the armor only loses if the
+    // first two blows both land on it
+    let odds = game
+        .combat_odds(secrets[0], armor_id, infantry_loc)
+        .unwrap();
+    assert_eq!(odds.victory_prob, 0.75);
+    assert_eq!(odds.attacker_damage, vec![0.5, 0.25, 0.25]);
+    assert_eq!(odds.defender_damage, vec![0.25, 0.75]);
+    assert_eq!(odds.expected_attacker_damage(), 0.75);
+    assert_eq!(odds.expected_defender_damage(), 0.75);
+
+    // Asking doesn't fight
+    assert!(game.map.toplevel_unit_by_loc(infantry_loc).is_some());
+
+    assert_eq!(
+        game.combat_odds(secrets[0], armor_id, Location::new(2, 0)),
+        Err(GameError::NoEnemyAtLocation {
+            loc: Location::new(2, 0)
+        })
+    );
+    assert_eq!(
+        game.combat_odds(secrets[1], armor_id, infantry_loc),
+        Err(GameError::NoSuchUnit { id: armor_id })
+    );
+}
+
+#[test]
+fn test_bombard() {
+    let map = MapData::try_from("ar-I-I").unwrap();
+    let armor_id = map.toplevel_unit_id_by_loc(Location::new(0, 0)).unwrap();
+    let artillery_id = map.toplevel_unit_id_by_loc(Location::new(1, 0)).unwrap();
+    let near = Location::new(3, 0);
+    let far = Location::new(5, 0);
+
+    let (mut game, secrets) = Game::new_with_map(None, false, map, 2, false, None, Wrap2d::NEITHER);
+
+    game.begin_turn(secrets[0], false).unwrap();
+
+    assert_eq!(
+        game.player_unit_bombard_targets(secrets[0], artillery_id),
+        Ok(vec![near])
+    );
+    assert_eq!(
+        game.player_unit_bombard_targets(secrets[0], armor_id),
+        Ok(Vec::new())
+    );
+
+    assert_eq!(
+        game.bombard(secrets[0], armor_id, Location::new(2, 0)),
+        Err(GameError::NoRangedAttack { id: armor_id })
+    );
+    assert_eq!(
+        game.bombard(secrets[0], artillery_id, far),
+        Err(GameError::OutOfRange {
+            id: artillery_id,
+            loc: far
+        })
+    );
+    assert_eq!(
+        game.bombard(secrets[0], artillery_id, Location::new(2, 0)),
+        Err(GameError::NoEnemyAtLocation {
+            loc: Location::new(2, 0)
+        })
+    );
+
+    let bombardment = game.bombard(secrets[0], artillery_id, near).unwrap();
+
+    // The target falls only if the artillery wins, but the artillery survives either way
+    assert_eq!(
+        bombardment.combat.victorious(),
+        game.map.toplevel_unit_by_loc(near).is_none()
+    );
+    let artillery = game
+        .player_unit_by_id(secrets[0], artillery_id)
+        .unwrap()
+        .unwrap();
+    assert_eq!(artillery.loc, Location::new(1, 0));
+    assert_eq!(artillery.moves_remaining(), 0);
+
+    assert_eq!(
+        game.bombard(secrets[0], artillery_id, near),
+        Err(GameError::NoMovesRemaining { id: artillery_id })
+    );
+}
+
+#[test]
+fn test_unit_groups() {
+    let map = MapData::try_from("i--I\ni---").unwrap();
+    let a: UnitID = map.toplevel_unit_id_by_loc(Location::new(0, 0)).unwrap();
+    let b: UnitID = map.toplevel_unit_id_by_loc(Location::new(0, 1)).unwrap();
+    let enemy_loc = Location::new(3, 0);
+    let enemy_id: UnitID = map.toplevel_unit_id_by_loc(enemy_loc).unwrap();
+
+    let (mut game, secrets) = Game::new_with_map(None, false, map, 2, false, None, Wrap2d::NEITHER);
+
+    game.begin_turn(secrets[0], false).unwrap();
+
+    assert_eq!(
+        game.form_unit_group(secrets[0], String::from("Army"), vec![a, enemy_id]),
+        Err(GameError::NoSuchUnit { id: enemy_id })
+    );
+    game.form_unit_group(secrets[0], String::from("Army"), vec![a, b])
+        .unwrap();
+    assert_eq!(
+        game.unit_groups(secrets[0]),
+        Ok(vec![(String::from("Army"), vec![a, b])])
+    );
+    assert_eq!(game.unit_groups(secrets[1]), Ok(Vec::new()));
+
+    assert_eq!(
+        game.order_unit_group(secrets[0], String::from("Navy"), Orders::Sentry),
+        Err(GameError::NoSuchUnitGroup {
+            name: String::from("Navy")
+        })
+    );
+
+    let had_combat = |orders_results: &[OrdersResult]| {
+        orders_results
+            .iter()
+            .filter_map(|result| result.as_ref().ok())
+            .filter_map(|outcome| outcome.move_.as_ref())
+            .any(|move_| move_.components.iter().any(|c| c.unit_combat.is_some()))
+    };
+
+    // Both members close in on the enemy and attack it
+    let orders = Orders::Attack { target: enemy_loc };
+    let orders_results = game
+        .order_unit_group(secrets[0], String::from("Army"), orders)
+        .unwrap();
+    assert_eq!(orders_results.len(), 2);
+    for orders_result in orders_results.iter() {
+        let outcome = orders_result.as_ref().unwrap();
+        assert_eq!(outcome.orders, orders);
+        assert_eq!(outcome.status, OrdersStatus::InProgress);
+        assert!(outcome.move_.is_some());
+    }
+
+    let mut combat = had_combat(&orders_results);
+    while game.current_player_units().any(|unit| unit.has_orders()) {
+        game.force_end_then_begin_turn(secrets[0], secrets[1], false)
+            .unwrap();
+        let turn_start = game
+            .force_end_then_begin_turn(secrets[1], secrets[0], false)
+            .unwrap();
+        combat |= had_combat(&turn_start.orders_results);
+
+        // Make sure we don't go on too long
+        assert!(game.turn() < 10);
+    }
+    assert!(combat);
+
+    let mut survivors: Vec<UnitID> = game.current_player_units().map(|unit| unit.id).collect();
+    survivors.sort();
+    let mut disbanded = game
+        .disband_unit_group(secrets[0], String::from("Army"))
+        .unwrap();
+    disbanded.sort();
+    assert_eq!(disbanded, survivors);
+    assert_eq!(game.unit_groups(secrets[0]), Ok(Vec::new()));
+}
+
 #[test]
 pub fn test_order_unit_explore() {
     let map = MapData::try_from("i--------------------").unwrap();
@@ -517,6 +1003,51 @@ pub fn test_propose_move_unit_by_id() {
     super::test_support::test_propose_move_unit_by_id();
 }
 
+#[test]
+pub fn test_propose_leaves_game_alone() {
+    let (mut game, secrets) = game_two_cities_two_infantry();
+    game.record_replay();
+
+    let unit_id: UnitID = game.current_player_unit_orders_requests().next().unwrap();
+    let src = game.current_player_unit_by_id(unit_id).unwrap().loc;
+    let dest = Location::new(src.x + 1, src.y);
+    let obs_before = game.player_obs(secrets[0], dest).unwrap().cloned();
+
+    let proposed = game
+        .propose_action(secrets[0], PlayerAction::MoveUnit { unit_id, dest })
+        .unwrap();
+    match &proposed.outcome {
+        PlayerActionOutcome::MoveUnit { move_, .. } => assert_eq!(move_.unit.loc, dest),
+        outcome => panic!("Unexpected outcome {:?}", outcome),
+    }
+
+    // Neither the unit, the player's observations, nor the replay reflect the proposal
+    assert_eq!(game.current_player_unit_by_id(unit_id).unwrap().loc, src);
+    assert_eq!(
+        game.player_obs(secrets[0], dest).unwrap().cloned(),
+        obs_before
+    );
+    assert!(game.replay().unwrap().is_empty());
+
+    game.take_action(secrets[0], proposed.action).unwrap();
+    assert_eq!(game.current_player_unit_by_id(unit_id).unwrap().loc, dest);
+    assert_eq!(game.replay().unwrap().len(), 1);
+}
+
+#[test]
+pub fn test_sandbox_leaves_chat_behind() {
+    let (mut game, secrets) = game_two_cities_two_infantry();
+    game.send_chat_message(secrets[1], String::from("Hello"))
+        .unwrap();
+
+    let sandbox = game.sandbox();
+    assert!(sandbox
+        .chat_messages_since(secrets[0], 0)
+        .unwrap()
+        .is_empty());
+    assert_eq!(game.chat_messages_since(secrets[0], 0).unwrap().len(), 1);
+}
+
 #[test]
 pub fn test_current_player_unit_legal_one_step_destinations() {
     let dirs = [
@@ -890,9 +1421,12 @@ pub fn test_movement_matches_carry_status() {
 
             game.begin_turn(secrets[0], false).unwrap();
 
+            // Where it can't be carried, the unit may yet stack with the other
+            let can_stack = game.map.tile(l2).unwrap().can_stack(&u1);
+
             let result = game.move_unit_by_id_in_direction(secrets[0], u1.id, Direction::Right);
 
-            if u2.can_carry_unit(&u1) {
+            if u2.can_carry_unit(&u1) || can_stack {
                 assert!(result.is_ok());
             } else {
                 assert!(result.is_err());
@@ -1139,13 +1673,18 @@ fn test_valid_productions() {
     for t in UnitType::values().iter().cloned() {
         if match t {
             UnitType::Armor => true,
+            UnitType::Artillery => true,
             UnitType::Battleship => false,
             UnitType::Bomber => true,
             UnitType::Carrier => false,
             UnitType::Cruiser => false,
             UnitType::Destroyer => false,
+            UnitType::Engineer => true,
             UnitType::Fighter => true,
+            UnitType::Helicopter => true,
             UnitType::Infantry => true,
+            UnitType::Missile => true,
+            UnitType::Paratrooper => true,
             UnitType::Submarine => false,
             UnitType::Transport => false,
         } {
@@ -1174,13 +1713,18 @@ fn test_valid_productions_conservative() {
     for t in UnitType::values().iter().cloned() {
         if match t {
             UnitType::Armor => true,
+            UnitType::Artillery => true,
             UnitType::Battleship => false,
             UnitType::Bomber => true,
             UnitType::Carrier => false,
             UnitType::Cruiser => false,
             UnitType::Destroyer => false,
+            UnitType::Engineer => true,
             UnitType::Fighter => true,
+            UnitType::Helicopter => true,
             UnitType::Infantry => true,
+            UnitType::Missile => true,
+            UnitType::Paratrooper => true,
             UnitType::Submarine => false,
             UnitType::Transport => false,
         } {
@@ -1216,24 +1760,113 @@ fn test_move_fighter_over_water() {
 }
 
 #[test]
-fn test_disband_unit_by_id() {
-    {
-        let map = MapData::try_from("i").unwrap();
-        let (mut game, secrets) =
-            Game::new_with_map(None, false, map, 1, true, None, Wrap2d::NEITHER);
-        let id = UnitID::new(0);
+fn test_player_unit_reachable_this_turn() {
+    let map = MapData::try_from("-a--- -").unwrap();
+    let armor_id = map.toplevel_unit_id_by_loc(Location::new(1, 0)).unwrap();
 
-        game.begin_turn(secrets[0], false).unwrap();
+    let (mut game, secrets) = Game::new_with_map(None, false, map, 1, false, None, Wrap2d::NEITHER);
 
-        let unit = game.current_player_unit_by_id(id).cloned().unwrap();
+    game.begin_turn(secrets[0], false).unwrap();
 
-        assert!(game
-            .current_player_unit_orders_requests()
-            .any(|unit_id| unit_id == id));
+    // Armor gets two moves, and can't cross the water
+    assert_eq!(
+        game.player_unit_reachable_this_turn(secrets[0], armor_id),
+        Ok(BTreeSet::from([
+            Location::new(0, 0),
+            Location::new(2, 0),
+            Location::new(3, 0),
+        ]))
+    );
 
-        assert_eq!(
-            game.disband_unit_by_id(secrets[0], id)
-                .map(|disbanded| disbanded.unit),
+    game.move_unit_by_id(secrets[0], armor_id, Location::new(2, 0))
+        .unwrap();
+
+    assert_eq!(
+        game.player_unit_reachable_this_turn(secrets[0], armor_id),
+        Ok(BTreeSet::from([Location::new(1, 0), Location::new(3, 0)]))
+    );
+}
+
+#[test]
+fn test_rebase_unit() {
+    let map = MapData::try_from("0f- k-a").unwrap();
+    let fighter_id = map.toplevel_unit_id_by_loc(Location::new(1, 0)).unwrap();
+    let carrier_id = map.toplevel_unit_id_by_loc(Location::new(4, 0)).unwrap();
+    let armor_id = map.toplevel_unit_id_by_loc(Location::new(6, 0)).unwrap();
+
+    let city_loc = Location::new(0, 0);
+    let carrier_loc = Location::new(4, 0);
+
+    let (mut game, secrets) = Game::new_with_map(None, false, map, 1, false, None, Wrap2d::NEITHER);
+
+    game.begin_turn(secrets[0], false).unwrap();
+
+    assert_eq!(
+        game.player_air_bases(secrets[0], fighter_id),
+        Ok(vec![city_loc, carrier_loc])
+    );
+
+    assert_eq!(
+        game.rebase_unit(secrets[0], armor_id, city_loc),
+        Err(GameError::UnitCannotFly { id: armor_id })
+    );
+    assert_eq!(
+        game.rebase_unit(secrets[0], fighter_id, Location::new(2, 0)),
+        Err(GameError::NoAirBase {
+            loc: Location::new(2, 0)
+        })
+    );
+
+    // Land on the carrier...
+    let move_ = game
+        .rebase_unit(secrets[0], fighter_id, carrier_loc)
+        .unwrap();
+    assert_eq!(move_.ending_loc(), Some(carrier_loc));
+    assert_eq!(
+        game.player_toplevel_unit_by_loc(secrets[0], carrier_loc)
+            .unwrap()
+            .unwrap()
+            .id,
+        carrier_id
+    );
+    assert_eq!(
+        game.player_unit_by_id(secrets[0], fighter_id)
+            .unwrap()
+            .unwrap()
+            .loc,
+        carrier_loc
+    );
+
+    // ...then launch from it and land back in the city
+    game.rebase_unit(secrets[0], fighter_id, city_loc).unwrap();
+    assert_eq!(
+        game.player_unit_by_id(secrets[0], fighter_id)
+            .unwrap()
+            .unwrap()
+            .loc,
+        city_loc
+    );
+}
+
+#[test]
+fn test_disband_unit_by_id() {
+    {
+        let map = MapData::try_from("i").unwrap();
+        let (mut game, secrets) =
+            Game::new_with_map(None, false, map, 1, true, None, Wrap2d::NEITHER);
+        let id = UnitID::new(0);
+
+        game.begin_turn(secrets[0], false).unwrap();
+
+        let unit = game.current_player_unit_by_id(id).cloned().unwrap();
+
+        assert!(game
+            .current_player_unit_orders_requests()
+            .any(|unit_id| unit_id == id));
+
+        assert_eq!(
+            game.disband_unit_by_id(secrets[0], id)
+                .map(|disbanded| disbanded.unit),
             Ok(unit)
         );
 
@@ -1416,6 +2049,47 @@ fn test_disband_unit_by_id() {
             }
         }
     }
+
+    // Units disbanded in a friendly city go toward its production
+    {
+        let mut map = MapData::try_from("0i").unwrap();
+        let city_loc = Location::new(0, 0);
+        let garrison_id = map
+            .new_unit(
+                city_loc,
+                UnitType::Armor,
+                Alignment::Belligerent { player: 0 },
+                "Garrison",
+            )
+            .unwrap();
+        let infantry_id = map.toplevel_unit_id_by_loc(Location::new(1, 0)).unwrap();
+
+        let (mut game, secrets) =
+            Game::new_with_map(None, false, map, 1, true, None, Wrap2d::NEITHER);
+
+        game.begin_turn(secrets[0], false).unwrap();
+
+        let progress = |game: &Game| game.map.city_by_loc(city_loc).unwrap().production_progress;
+        let prior_progress = progress(&game);
+
+        let refund = (UnitType::Armor.cost() as f64 * conf::DISBAND_REFUND_FRACTION) as u16;
+        assert!(refund > 0);
+
+        assert_eq!(
+            game.disband_unit_by_id(secrets[0], garrison_id)
+                .map(|disbanded| disbanded.refund),
+            Ok(refund)
+        );
+        assert_eq!(progress(&game), prior_progress + refund);
+
+        // Nothing is refunded out in the field
+        assert_eq!(
+            game.disband_unit_by_id(secrets[0], infantry_id)
+                .map(|disbanded| disbanded.refund),
+            Ok(0)
+        );
+        assert_eq!(progress(&game), prior_progress + refund);
+    }
 }
 
 #[test]
@@ -1704,11 +2378,11 @@ pub fn test_player_feature_playernum_invariance() {
         );
 
         let v0 = game0
-            .player_features(secrets0[0], TrainingFocus::Unit)
+            .player_features(secrets0[0], TrainingFocus::Unit, DEEP_WIDTH)
             .unwrap();
 
         let v1 = game1
-            .player_features(secrets1[1], TrainingFocus::Unit)
+            .player_features(secrets1[1], TrainingFocus::Unit, DEEP_WIDTH)
             .unwrap();
 
         assert_eq!(v0, v1);
@@ -1720,3 +2394,1051 @@ pub fn test_player_feature_playernum_invariance() {
     //     let game3 = Game::try_from("1   0").unwrap();
     // }
 }
+
+#[test]
+pub fn test_save_load() {
+    let (mut game, secrets) = game_two_cities_two_infantry();
+
+    // Save mid-turn, after the current player has taken an action
+    let unit_id: UnitID = game.current_player_unit_orders_requests().next().unwrap();
+    game.order_unit_skip(secrets[0], unit_id).unwrap();
+
+    let path = std::env::temp_dir().join(format!("umpire_test_save_load_{}.sav", secrets[0]));
+
+    game.save(&path).unwrap();
+
+    let (mut loaded, loaded_secrets) = Game::load(&path, None).unwrap();
+
+    std::fs::remove_file(&path).unwrap();
+
+    assert_eq!(loaded_secrets, secrets);
+    assert_eq!(loaded.turn(), game.turn());
+    assert_eq!(loaded.turn_phase(), game.turn_phase());
+    assert_eq!(loaded.current_player(), game.current_player());
+
+    for secret in secrets.iter().copied() {
+        assert_eq!(
+            loaded
+                .player_observations(secret)
+                .unwrap()
+                .iter()
+                .collect::<Vec<&Obs>>(),
+            game.player_observations(secret)
+                .unwrap()
+                .iter()
+                .collect::<Vec<&Obs>>()
+        );
+
+        assert_eq!(
+            loaded
+                .player_units(secret)
+                .unwrap()
+                .cloned()
+                .collect::<Vec<Unit>>(),
+            game.player_units(secret)
+                .unwrap()
+                .cloned()
+                .collect::<Vec<Unit>>()
+        );
+
+        assert_eq!(
+            loaded.player_score(secret).unwrap(),
+            game.player_score(secret).unwrap()
+        );
+    }
+
+    // The loaded game picks up mid-turn
+    assert_eq!(
+        loaded.begin_turn(secrets[0], false).map(|_| ()),
+        Err(GameError::WrongPhase {
+            player: 0,
+            turn: game.turn(),
+            phase: TurnPhase::Main,
+        })
+    );
+
+    let result = loaded.end_then_begin_turn(secrets[0], secrets[1], false);
+    assert_eq!(result.unwrap().current_player, 1);
+}
+
+#[test]
+pub fn test_load_missing() {
+    let path = std::env::temp_dir().join("umpire_test_load_missing_nonexistent.sav");
+    assert!(matches!(Game::load(path, None), Err(SaveError::Io(_))));
+}
+
+#[test]
+pub fn test_replay() {
+    let (mut game, secrets) = game1();
+
+    assert!(game.replay().is_none());
+
+    game.record_replay();
+
+    // Play a handful of turns with random moves, exercising production, movement, combat, and turn ending
+    let mut rng = init_rng(Some(4_294_967_291));
+    for _ in 0..30 {
+        let player = game.current_player();
+        let secret = secrets[player];
+
+        game.begin_turn(secret, false).unwrap();
+
+        let city_locs: Vec<Location> = game
+            .player_production_set_requests(secret)
+            .unwrap()
+            .collect();
+        for loc in city_locs {
+            let production = UnitType::values()[rng.gen_range(0..2)];
+            game.set_production_by_loc(secret, loc, production).unwrap();
+        }
+
+        loop {
+            let unit_id = match game.player_unit_orders_requests(secret).unwrap().next() {
+                Some(unit_id) => unit_id,
+                None => break,
+            };
+
+            let directions: Vec<Direction> = game
+                .player_unit_legal_directions(secret, unit_id)
+                .unwrap()
+                .collect();
+
+            if directions.is_empty() {
+                game.order_unit_skip(secret, unit_id).unwrap();
+            } else {
+                let direction = directions[rng.gen_range(0..directions.len())];
+                game.move_unit_by_id_in_direction(secret, unit_id, direction)
+                    .unwrap();
+            }
+        }
+
+        game.force_end_turn(secret).unwrap();
+    }
+
+    let replay = game.take_replay().unwrap();
+    assert!(game.replay().is_none());
+
+    // Each turn ended is recorded exactly once
+    assert_eq!(
+        replay
+            .entries()
+            .iter()
+            .filter(|entry| entry.action == PlayerAction::ForceEndTurn)
+            .count(),
+        30
+    );
+    assert_eq!(replay.entries()[0].turn, 0);
+    assert_eq!(replay.entries()[0].player, 0);
+    assert_eq!(
+        replay.entries()[0].action,
+        PlayerAction::BeginTurn {
+            clear_after_unit_production: false
+        }
+    );
+
+    let path = std::env::temp_dir().join(format!("umpire_test_replay_{}.replay", secrets[0]));
+    replay.save(&path).unwrap();
+    let loaded = Replay::load(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+
+    assert_eq!(loaded.entries(), replay.entries());
+
+    // Taking the recorded actions against the initial state reproduces the game
+    let (mut replayed, replayed_secrets) = loaded.initial_game(None);
+    assert_eq!(replayed_secrets, secrets);
+
+    for entry in loaded.entries() {
+        replayed
+            .take_action(replayed_secrets[entry.player], entry.action)
+            .unwrap();
+    }
+
+    assert_eq!(replayed.turn(), game.turn());
+    assert_eq!(replayed.current_player(), game.current_player());
+    for secret in secrets.iter().copied() {
+        assert_eq!(
+            replayed
+                .player_units(secret)
+                .unwrap()
+                .cloned()
+                .collect::<Vec<Unit>>(),
+            game.player_units(secret)
+                .unwrap()
+                .cloned()
+                .collect::<Vec<Unit>>()
+        );
+        assert_eq!(
+            replayed.player_score(secret).unwrap(),
+            game.player_score(secret).unwrap()
+        );
+    }
+}
+
+#[test]
+fn test_paradrop() {
+    let mut map = MapData::try_from("0i -I-1").unwrap();
+    let infantry_id = map.toplevel_unit_id_by_loc(Location::new(1, 0)).unwrap();
+
+    let city_loc = Location::new(0, 0);
+    let paratrooper_id = map
+        .new_unit(
+            city_loc,
+            UnitType::Paratrooper,
+            Alignment::Belligerent { player: 0 },
+            "Geronimo",
+        )
+        .unwrap();
+
+    let (mut game, secrets) = Game::new_with_map(None, false, map, 2, false, None, Wrap2d::NEITHER);
+
+    game.begin_turn(secrets[0], false).unwrap();
+
+    let zones = game
+        .player_unit_drop_zones(secrets[0], paratrooper_id)
+        .unwrap();
+    assert!(zones.contains(&Location::new(5, 0)));
+    assert!(!zones.contains(&Location::new(1, 0)));
+    assert!(!zones.contains(&Location::new(2, 0)));
+
+    assert_eq!(
+        game.paradrop(secrets[0], infantry_id, Location::new(3, 0)),
+        Err(GameError::CannotParadrop { id: infantry_id })
+    );
+
+    // Not into the sea, nor on top of a friendly unit
+    assert_eq!(
+        game.paradrop(secrets[0], paratrooper_id, Location::new(2, 0)),
+        Err(GameError::UnsuitableDropZone {
+            loc: Location::new(2, 0)
+        })
+    );
+    assert_eq!(
+        game.paradrop(secrets[0], paratrooper_id, Location::new(1, 0)),
+        Err(GameError::UnsuitableDropZone {
+            loc: Location::new(1, 0)
+        })
+    );
+
+    // Drop behind the enemy lines
+    let dest = Location::new(5, 0);
+    let move_ = game.paradrop(secrets[0], paratrooper_id, dest).unwrap();
+    assert_eq!(move_.ending_loc(), Some(dest));
+
+    let paratrooper = game
+        .player_unit_by_id(secrets[0], paratrooper_id)
+        .unwrap()
+        .unwrap();
+    assert_eq!(paratrooper.loc, dest);
+    assert_eq!(paratrooper.moves_remaining(), 0);
+
+    // Once on the ground it can't take off again
+    assert_eq!(
+        game.paradrop(secrets[0], paratrooper_id, Location::new(3, 0)),
+        Err(GameError::NoAirfield { id: paratrooper_id })
+    );
+}
+
+#[test]
+fn test_missile() {
+    // Submarines carry missiles, but not other aircraft
+    {
+        let map = MapData::try_from("msf").unwrap();
+        let missile_id = map.toplevel_unit_id_by_loc(Location::new(0, 0)).unwrap();
+        let sub_id = map.toplevel_unit_id_by_loc(Location::new(1, 0)).unwrap();
+        let fighter_id = map.toplevel_unit_id_by_loc(Location::new(2, 0)).unwrap();
+
+        let (mut game, secrets) =
+            Game::new_with_map(None, false, map, 1, false, None, Wrap2d::NEITHER);
+
+        game.begin_turn(secrets[0], false).unwrap();
+
+        let sub = game.player_unit_by_id(secrets[0], sub_id).unwrap().unwrap();
+        let fighter = game
+            .player_unit_by_id(secrets[0], fighter_id)
+            .unwrap()
+            .unwrap();
+        assert!(!sub.can_carry_unit(fighter));
+
+        game.move_unit_by_id_in_direction(secrets[0], missile_id, Direction::Right)
+            .unwrap();
+
+        let sub = game.player_unit_by_id(secrets[0], sub_id).unwrap().unwrap();
+        assert_eq!(
+            sub.carried_units().map(|unit| unit.id).collect::<Vec<_>>(),
+            vec![missile_id]
+        );
+    }
+
+    // The missile is spent striking its target, whether or not the target survives
+    for _ in 0..10 {
+        let map = MapData::try_from("mD").unwrap();
+        let missile_id = map.toplevel_unit_id_by_loc(Location::new(0, 0)).unwrap();
+        let destroyer_id = map.toplevel_unit_id_by_loc(Location::new(1, 0)).unwrap();
+
+        let (mut game, secrets) =
+            Game::new_with_map(None, false, map, 2, false, None, Wrap2d::NEITHER);
+
+        game.begin_turn(secrets[0], false).unwrap();
+
+        let move_ = game
+            .move_unit_by_id_in_direction(secrets[0], missile_id, Direction::Right)
+            .unwrap();
+
+        assert!(!move_.moved_successfully());
+        assert!(game
+            .player_unit_by_id(secrets[0], missile_id)
+            .unwrap()
+            .is_none());
+
+        let destroyed = move_.components[0]
+            .unit_combat
+            .as_ref()
+            .unwrap()
+            .victorious();
+        assert_eq!(move_.components[0].expended, destroyed);
+        assert_eq!(
+            game.map.toplevel_unit_id_by_loc(Location::new(1, 0)),
+            if destroyed { None } else { Some(destroyer_id) }
+        );
+    }
+}
+
+#[test]
+fn test_engineer() {
+    let mut map = MapData::try_from("%%i").unwrap();
+    let loc = Location::new(0, 0);
+    let engineer_id = map
+        .new_unit(
+            loc,
+            UnitType::Engineer,
+            Alignment::Belligerent { player: 0 },
+            "Sapper",
+        )
+        .unwrap();
+    let infantry_id = map.toplevel_unit_id_by_loc(Location::new(2, 0)).unwrap();
+
+    let (mut game, secrets) = Game::new_with_map(None, false, map, 1, false, None, Wrap2d::NEITHER);
+
+    game.begin_turn(secrets[0], false).unwrap();
+
+    assert_eq!(
+        game.order_unit_build(secrets[0], infantry_id, Improvement::Road),
+        Err(GameError::CannotBuild { id: infantry_id })
+    );
+
+    assert!(
+        UnitType::Armor.tile_movement_cost(game.map.tile(loc).unwrap())
+            > UnitType::Armor.movement_cost(Terrain::Land)
+    );
+
+    // Two turns of work make a road, which lets ground units cross the forest as though it were open land
+    let outcome = game
+        .order_unit_build(secrets[0], engineer_id, Improvement::Road)
+        .unwrap();
+    assert_eq!(outcome.status, OrdersStatus::InProgress);
+    assert!(!game
+        .map
+        .tile(loc)
+        .unwrap()
+        .has_improvement(Improvement::Road));
+
+    game.force_end_then_begin_turn(secrets[0], secrets[0], false)
+        .unwrap();
+
+    assert!(game
+        .map
+        .tile(loc)
+        .unwrap()
+        .has_improvement(Improvement::Road));
+    assert_eq!(
+        UnitType::Armor.tile_movement_cost(game.map.tile(loc).unwrap()),
+        UnitType::Armor.movement_cost(Terrain::Land)
+    );
+    assert_eq!(
+        game.order_unit_build(secrets[0], engineer_id, Improvement::Road),
+        Err(GameError::CannotImprove {
+            loc,
+            improvement: Improvement::Road
+        })
+    );
+
+    // The road used up this turn's work, so the fortification starts next turn and takes four more
+    game.order_unit_build(secrets[0], engineer_id, Improvement::Fortification)
+        .unwrap();
+    for _ in 0..Improvement::Fortification.build_turns() {
+        assert_eq!(game.map.tile(loc).unwrap().defense_bonus(), 1.0);
+        game.force_end_then_begin_turn(secrets[0], secrets[0], false)
+            .unwrap();
+    }
+    assert_eq!(
+        game.map.tile(loc).unwrap().defense_bonus(),
+        conf::FORTIFICATION_DEFENSE_BONUS
+    );
+    assert_eq!(
+        game.player_unit_by_id(secrets[0], engineer_id)
+            .unwrap()
+            .unwrap()
+            .orders,
+        None
+    );
+}
+
+#[test]
+fn test_minefields() {
+    // Laying mines takes the rest of the unit's turn, and only the layer knows of them
+    {
+        let map = MapData::try_from("e-i").unwrap();
+        let engineer_id = map.toplevel_unit_id_by_loc(Location::new(0, 0)).unwrap();
+        let infantry_id = map.toplevel_unit_id_by_loc(Location::new(2, 0)).unwrap();
+
+        let (mut game, secrets) =
+            Game::new_with_map(None, false, map, 2, false, None, Wrap2d::NEITHER);
+
+        game.begin_turn(secrets[0], false).unwrap();
+
+        assert_eq!(
+            game.lay_mines(secrets[0], infantry_id),
+            Err(GameError::CannotLayMines { id: infantry_id })
+        );
+
+        assert_eq!(
+            game.lay_mines(secrets[0], engineer_id),
+            Ok(Location::new(0, 0))
+        );
+        assert_eq!(
+            game.lay_mines(secrets[0], engineer_id),
+            Err(GameError::NoMovesRemaining { id: engineer_id })
+        );
+
+        assert_eq!(
+            game.player_known_minefields(secrets[0]),
+            Ok(vec![Location::new(0, 0)])
+        );
+        assert_eq!(game.player_known_minefields(secrets[1]), Ok(Vec::new()));
+    }
+
+    // Entering an enemy minefield damages the unit, stops it short, and reveals the mines
+    {
+        let mut map = MapData::try_from("a--").unwrap();
+        map.lay_minefield(Location::new(1, 0), 1).unwrap();
+        let armor_id = map.toplevel_unit_id_by_loc(Location::new(0, 0)).unwrap();
+
+        let (mut game, secrets) =
+            Game::new_with_map(None, false, map, 2, false, None, Wrap2d::NEITHER);
+
+        game.begin_turn(secrets[0], false).unwrap();
+
+        assert_eq!(game.player_known_minefields(secrets[0]), Ok(Vec::new()));
+
+        let move_ = game
+            .move_unit_by_id(secrets[0], armor_id, Location::new(2, 0))
+            .unwrap();
+        assert_eq!(move_.components.len(), 1);
+        assert!(move_.components[0].struck_mine);
+        assert!(move_.moved_successfully());
+
+        let armor = game
+            .player_unit_by_id(secrets[0], armor_id)
+            .unwrap()
+            .unwrap();
+        assert_eq!(armor.loc, Location::new(1, 0));
+        assert_eq!(armor.hp(), armor.max_hp() - conf::MINE_DAMAGE);
+        assert_eq!(armor.moves_remaining(), 0);
+
+        assert_eq!(
+            game.player_known_minefields(secrets[0]),
+            Ok(vec![Location::new(1, 0)])
+        );
+    }
+
+    // A unit with too few hitpoints is destroyed outright
+    {
+        let mut map = MapData::try_from("i-").unwrap();
+        map.lay_minefield(Location::new(1, 0), 1).unwrap();
+        let infantry_id = map.toplevel_unit_id_by_loc(Location::new(0, 0)).unwrap();
+
+        let (mut game, secrets) =
+            Game::new_with_map(None, false, map, 2, false, None, Wrap2d::NEITHER);
+
+        game.begin_turn(secrets[0], false).unwrap();
+
+        let move_ = game
+            .move_unit_by_id_in_direction(secrets[0], infantry_id, Direction::Right)
+            .unwrap();
+        assert!(move_.components[0].destroyed_by_mine);
+        assert!(!move_.moved_successfully());
+        assert!(game
+            .player_unit_by_id(secrets[0], infantry_id)
+            .unwrap()
+            .is_none());
+    }
+
+    // Destroyers sweep the mines around them, hidden or not
+    {
+        let mut map = MapData::try_from("d  ").unwrap();
+        map.lay_minefield(Location::new(1, 0), 1).unwrap();
+        map.lay_minefield(Location::new(2, 0), 1).unwrap();
+        let destroyer_id = map.toplevel_unit_id_by_loc(Location::new(0, 0)).unwrap();
+
+        let (mut game, secrets) =
+            Game::new_with_map(None, false, map, 2, false, None, Wrap2d::NEITHER);
+
+        game.begin_turn(secrets[0], false).unwrap();
+
+        assert_eq!(
+            game.sweep_mines(secrets[0], destroyer_id),
+            Ok(vec![Location::new(1, 0)])
+        );
+        assert!(game.map.minefield(Location::new(1, 0)).is_none());
+        assert!(game.map.minefield(Location::new(2, 0)).is_some());
+    }
+}
+
+#[test]
+fn test_city_walls() {
+    let map = MapData::try_from("0").unwrap();
+    let loc = Location::new(0, 0);
+    let city_id = map.city_by_loc(loc).unwrap().id;
+
+    let (mut game, secrets) = Game::new_with_map(None, false, map, 1, false, None, Wrap2d::NEITHER);
+
+    game.begin_turn(secrets[0], false).unwrap();
+    game.set_production_by_id(secrets[0], city_id, UnitType::Battleship)
+        .unwrap();
+
+    assert_eq!(
+        game.map.tile(loc).unwrap().defense_bonus(),
+        conf::CITY_DEFENSE_BONUS
+    );
+    assert_eq!(
+        game.build_city_walls(secrets[0], city_id),
+        Err(GameError::InsufficientProduction {
+            id: city_id,
+            needed: conf::CITY_WALL_COST,
+            available: 0
+        })
+    );
+
+    let progress = |game: &Game| game.map.city_by_id(city_id).unwrap().production_progress;
+
+    for level in 1..=conf::CITY_MAX_WALLS {
+        while progress(&game) < conf::CITY_WALL_COST {
+            game.force_end_then_begin_turn(secrets[0], secrets[0], false)
+                .unwrap();
+        }
+
+        // The walls come out of the battleship's progress
+        let before = progress(&game);
+        assert_eq!(
+            game.build_city_walls(secrets[0], city_id).unwrap().walls,
+            level
+        );
+        assert_eq!(progress(&game), before - conf::CITY_WALL_COST);
+        assert_eq!(
+            game.map.tile(loc).unwrap().defense_bonus(),
+            conf::CITY_DEFENSE_BONUS + level as f64 * conf::CITY_WALL_DEFENSE_BONUS
+        );
+    }
+
+    while progress(&game) < conf::CITY_WALL_COST {
+        game.force_end_then_begin_turn(secrets[0], secrets[0], false)
+            .unwrap();
+    }
+    assert_eq!(
+        game.build_city_walls(secrets[0], city_id),
+        Err(GameError::CityWallsComplete { id: city_id })
+    );
+}
+
+#[test]
+fn test_radar() {
+    let mut map = MapData::try_from("0--------------------").unwrap();
+    let loc = Location::new(0, 0);
+    let city_id = map.city_by_loc(loc).unwrap().id;
+    let engineer_loc = Location::new(20, 0);
+    let engineer_id = map
+        .new_unit(
+            engineer_loc,
+            UnitType::Engineer,
+            Alignment::Belligerent { player: 0 },
+            "Watchman",
+        )
+        .unwrap();
+
+    let (mut game, secrets) = Game::new_with_map(None, false, map, 1, true, None, Wrap2d::NEITHER);
+
+    game.begin_turn(secrets[0], false).unwrap();
+    game.set_production_by_id(secrets[0], city_id, UnitType::Battleship)
+        .unwrap();
+
+    let far = Location::new(conf::RADAR_SIGHT_DISTANCE, 0);
+    let too_far = Location::new(conf::RADAR_SIGHT_DISTANCE + 1, 0);
+    assert_eq!(
+        game.player_obs(secrets[0], far).unwrap(),
+        Some(&Obs::Unobserved)
+    );
+
+    assert_eq!(
+        game.build_city_radar(secrets[0], city_id),
+        Err(GameError::InsufficientProduction {
+            id: city_id,
+            needed: conf::CITY_RADAR_COST,
+            available: 0
+        })
+    );
+
+    while game.map.city_by_id(city_id).unwrap().production_progress < conf::CITY_RADAR_COST {
+        game.force_end_then_begin_turn(secrets[0], secrets[0], false)
+            .unwrap();
+    }
+
+    game.build_city_radar(secrets[0], city_id).unwrap();
+    assert_eq!(game.map.radar_owner(loc), Some(0));
+    assert_eq!(
+        game.build_city_radar(secrets[0], city_id),
+        Err(GameError::CannotImprove {
+            loc,
+            improvement: Improvement::Radar
+        })
+    );
+
+    // The radar sees far beyond the city, starting next turn
+    game.force_end_then_begin_turn(secrets[0], secrets[0], false)
+        .unwrap();
+    assert!(game
+        .player_obs(secrets[0], far)
+        .unwrap()
+        .unwrap()
+        .is_observed());
+    assert_eq!(
+        game.player_obs(secrets[0], too_far).unwrap(),
+        Some(&Obs::Unobserved)
+    );
+
+    // A radar in the open belongs to whoever built it
+    game.order_unit_build(secrets[0], engineer_id, Improvement::Radar)
+        .unwrap();
+    for _ in 0..Improvement::Radar.build_turns() {
+        game.force_end_then_begin_turn(secrets[0], secrets[0], false)
+            .unwrap();
+    }
+    assert_eq!(game.map.radar_owner(engineer_loc), Some(0));
+    assert_eq!(game.map.player_radars(0).count(), 2);
+
+    // A radar in a city watches for whoever holds the city
+    game.map
+        .set_city_alignment_by_loc(loc, Alignment::Neutral)
+        .unwrap();
+    assert_eq!(game.map.radar_owner(loc), None);
+    assert_eq!(game.map.player_radars(0).count(), 1);
+}
+
+#[test]
+fn test_watch_turns() {
+    let map = MapData::try_from("0-1").unwrap();
+    let (mut game, secrets) = Game::new_with_map(None, false, map, 2, false, None, Wrap2d::NEITHER);
+
+    let mut turns = game.watch_turns();
+    assert!(!turns.has_changed().unwrap());
+
+    game.begin_turn(secrets[0], false).unwrap();
+    assert!(!turns.has_changed().unwrap());
+
+    game.force_end_turn(secrets[0]).unwrap();
+    assert!(turns.has_changed().unwrap());
+    assert_eq!(*turns.borrow_and_update(), (0, 1));
+
+    game.begin_turn(secrets[1], false).unwrap();
+    game.force_end_turn(secrets[1]).unwrap();
+    assert_eq!(*turns.borrow_and_update(), (1, 0));
+}
+
+#[test]
+fn test_pause() {
+    let map = MapData::try_from("0-1").unwrap();
+    let (mut game, secrets) = Game::new_with_map(None, false, map, 2, false, None, Wrap2d::NEITHER);
+
+    game.begin_turn(secrets[0], false).unwrap();
+    game.set_paused(true);
+    assert!(game.paused());
+
+    // The turn underway plays out, but the next can't begin
+    game.force_end_turn(secrets[0]).unwrap();
+    assert_eq!(
+        game.begin_turn(secrets[1], false),
+        Err(GameError::GamePaused)
+    );
+    assert_eq!(game.current_player(), 1);
+
+    game.set_paused(false);
+    game.begin_turn(secrets[1], false).unwrap();
+}
+
+#[test]
+fn test_turn_limit() {
+    // Player 1's infantry gives it the higher score
+    let map = MapData::try_from("0-1I").unwrap();
+    let (mut game, secrets) = Game::new_with_map(None, false, map, 2, false, None, Wrap2d::NEITHER);
+
+    game.set_turn_limit(Some(1));
+    assert_eq!(game.turn_limit(), Some(1));
+
+    game.begin_turn(secrets[0], false).unwrap();
+    assert_eq!(game.force_end_turn(secrets[0]).unwrap().game_over, None);
+
+    game.begin_turn(secrets[1], false).unwrap();
+    assert_eq!(game.game_over(), None);
+
+    let game_over = Some(GameOver {
+        victor: 1,
+        reason: GameOverReason::TurnLimit,
+    });
+    assert_eq!(
+        game.force_end_turn(secrets[1]).unwrap().game_over,
+        game_over
+    );
+    assert_eq!(game.turn(), 1);
+    assert_eq!(game.victor(), None);
+    assert_eq!(game.game_over(), game_over);
+}
+
+#[test]
+fn test_universal_visibility() {
+    let map = MapData::try_from("0i  1").unwrap();
+    let dims = map.dims();
+    let (mut game, secrets) = Game::new_with_map(None, false, map, 2, false, None, Wrap2d::NEITHER);
+    assert!(game.player_observations.is_universal());
+
+    // The whole map is seen at the outset, by both players alike
+    let turn_start = game.begin_turn(secrets[0], false).unwrap();
+    assert_eq!(turn_start.observations.len(), dims.area() as usize);
+    for loc in dims.iter_locs() {
+        assert!(matches!(
+            game.player_obs(secrets[1], loc).unwrap(),
+            Some(Obs::Observed { current: true, .. })
+        ));
+        assert_eq!(
+            game.player_obs(secrets[0], loc).unwrap(),
+            game.player_obs(secrets[1], loc).unwrap()
+        );
+    }
+
+    // Nothing has changed since, so there's nothing new to track, and everything is still current
+    game.force_end_turn(secrets[0]).unwrap();
+    let turn_start = game.begin_turn(secrets[1], false).unwrap();
+    assert!(turn_start.observations.is_empty());
+    assert!(game
+        .player_observations(secrets[0])
+        .unwrap()
+        .iter()
+        .all(|obs| matches!(obs, Obs::Observed { current: true, .. })));
+}
+
+#[test]
+fn test_resign() {
+    let map = MapData::try_from("0i 1I").unwrap();
+    let infantry_id = map.toplevel_unit_id_by_loc(Location::new(1, 0)).unwrap();
+    let (mut game, secrets) = Game::new_with_map(None, false, map, 2, false, None, Wrap2d::NEITHER);
+
+    game.begin_turn(secrets[0], false).unwrap();
+    assert_eq!(game.game_over(), None);
+
+    let resigned = game.resign(secrets[0]).unwrap();
+
+    // Player 1 is the last one standing
+    assert_eq!(
+        resigned.game_over,
+        Some(GameOver {
+            victor: 1,
+            reason: GameOverReason::Conquest,
+        })
+    );
+    assert!(game.player_resigned(0));
+    assert!(!game.player_resigned(1));
+
+    assert_eq!(
+        game.map.city_by_loc(Location::new(0, 0)).unwrap().alignment,
+        Alignment::Neutral
+    );
+    assert!(game.map.unit_by_id(infantry_id).is_none());
+    assert_eq!(resigned.observations.len(), 2);
+
+    assert_eq!(
+        game.resign(secrets[0]),
+        Err(GameError::AlreadyResigned { player: 0 })
+    );
+}
+
+#[test]
+fn test_turn_summary() {
+    let mut captured = false;
+    let mut repelled = false;
+    while !captured || !repelled {
+        let map = MapData::try_from("A1").unwrap();
+        let armor_id = map.toplevel_unit_id_by_loc(Location::new(0, 0)).unwrap();
+        let city_loc = Location::new(1, 0);
+        let (mut game, secrets) =
+            Game::new_with_map(None, false, map, 2, false, None, Wrap2d::NEITHER);
+
+        game.begin_turn(secrets[0], false).unwrap();
+
+        let move_ = game
+            .move_unit_by_id(secrets[0], armor_id, city_loc)
+            .unwrap();
+        let won = move_.components[0]
+            .city_combat
+            .as_ref()
+            .unwrap()
+            .victorious();
+
+        let summary = game.force_end_turn(secrets[0]).unwrap().summary;
+        assert!(summary.tiles_explored > 0);
+        assert_eq!(summary.units_produced, 0);
+
+        if won {
+            assert_eq!(summary.battles_won, 1);
+            assert_eq!(summary.cities_gained, 1);
+            assert_eq!(game.turn_summaries[1].battles_lost, 1);
+            assert_eq!(game.turn_summaries[1].cities_lost, 1);
+            captured = true;
+        } else {
+            assert_eq!(summary.battles_lost, 1);
+            assert_eq!(summary.cities_gained, 0);
+            assert_eq!(game.turn_summaries[1].battles_won, 1);
+            repelled = true;
+        }
+
+        // The tallies start over once reported
+        assert_eq!(game.turn_summaries[0], TurnSummary::default());
+    }
+}
+
+#[test]
+fn test_alerts() {
+    let mut captured = false;
+    let mut repelled = false;
+    while !captured || !repelled {
+        let map = MapData::try_from("A1-I").unwrap();
+        let armor_id = map.toplevel_unit_id_by_loc(Location::new(0, 0)).unwrap();
+        let city_loc = Location::new(1, 0);
+        let (mut game, secrets) =
+            Game::new_with_map(None, false, map, 2, false, None, Wrap2d::NEITHER);
+
+        game.begin_turn(secrets[0], false).unwrap();
+        let won = game
+            .move_unit_by_id(secrets[0], armor_id, city_loc)
+            .unwrap()
+            .components[0]
+            .city_combat
+            .as_ref()
+            .unwrap()
+            .victorious();
+        game.force_end_turn(secrets[0]).unwrap();
+
+        let attacks: Vec<Alert> = game
+            .begin_turn(secrets[1], false)
+            .unwrap()
+            .alerts
+            .into_iter()
+            .filter(|alert| matches!(alert, Alert::CityAttacked { .. }))
+            .collect();
+        assert_eq!(attacks.len(), 1);
+        assert_eq!(attacks[0].loc(), city_loc);
+        if let Alert::CityAttacked {
+            attacker,
+            captured: city_captured,
+            ..
+        } = attacks[0]
+        {
+            assert_eq!(attacker, 0);
+            assert_eq!(city_captured, won);
+        }
+
+        if won {
+            captured = true;
+        } else {
+            repelled = true;
+        }
+
+        // Alerts are handed over only once
+        game.force_end_turn(secrets[1]).unwrap();
+        game.begin_turn(secrets[0], false).unwrap();
+        game.force_end_turn(secrets[0]).unwrap();
+        assert!(!game
+            .begin_turn(secrets[1], false)
+            .unwrap()
+            .alerts
+            .iter()
+            .any(|alert| matches!(alert, Alert::CityAttacked { .. })));
+    }
+
+    // An enemy moving into view is sighted
+    let map = MapData::try_from("i--I------").unwrap();
+    let enemy_id: UnitID = map.toplevel_unit_id_by_loc(Location::new(3, 0)).unwrap();
+    let (mut game, secrets) = Game::new_with_map(None, false, map, 2, true, None, Wrap2d::NEITHER);
+
+    let turn_start = game.begin_turn(secrets[0], false).unwrap();
+    assert!(turn_start.alerts.is_empty());
+    game.force_end_turn(secrets[0]).unwrap();
+
+    let enemy_loc = Location::new(2, 0);
+    game.begin_turn(secrets[1], false).unwrap();
+    game.move_unit_by_id(secrets[1], enemy_id, enemy_loc)
+        .unwrap();
+    game.force_end_turn(secrets[1]).unwrap();
+
+    let turn_start = game.begin_turn(secrets[0], false).unwrap();
+    assert_eq!(turn_start.alerts.len(), 1);
+    assert_eq!(turn_start.alerts[0].loc(), enemy_loc);
+    assert!(matches!(
+        &turn_start.alerts[0],
+        Alert::EnemySighted { unit: Some(unit), .. } if unit.id == enemy_id
+    ));
+}
+
+#[test]
+fn test_player_histories() {
+    let map = MapData::try_from("A1").unwrap();
+    let (mut game, secrets) = Game::new_with_map(None, false, map, 2, false, None, Wrap2d::NEITHER);
+
+    let mut explored = 0;
+    for _ in 0..2 {
+        for secret in &secrets {
+            game.begin_turn(*secret, false).unwrap();
+            let summary = game.force_end_turn(*secret).unwrap().summary;
+            if *secret == secrets[0] {
+                explored += summary.tiles_explored;
+            }
+        }
+    }
+
+    let histories = game.player_histories();
+    assert_eq!(histories.len(), 2);
+    for history in &histories {
+        let turns: Vec<TurnNum> = history.standings.iter().map(|s| s.turn).collect();
+        assert_eq!(turns, vec![0, 1]);
+    }
+
+    assert_eq!(histories[0].standings[0].cities, 0);
+    assert_eq!(histories[0].standings[0].units, 1);
+    assert_eq!(histories[0].peak_units(), 1);
+    assert_eq!(histories[1].standings[1].cities, 1);
+    assert_eq!(histories[1].peak_cities(), 1);
+
+    // The totals add up every turn's summary
+    assert!(explored > 0);
+    assert_eq!(histories[0].totals.tiles_explored, explored);
+}
+
+#[test]
+fn test_spectator_observations() {
+    let map = MapData::try_from("A1          ").unwrap();
+    let dims = map.dims();
+    let (game, secrets) = Game::new_with_map(None, false, map, 2, true, None, Wrap2d::NEITHER);
+
+    // With no viewer, everything is seen as it currently is
+    let everything = game.spectator_observations(None).unwrap();
+    assert_eq!(everything.num_observed(), dims.area() as usize);
+    for loc in dims.iter_locs() {
+        match everything.get(loc) {
+            Some(Obs::Observed { tile, current, .. }) => {
+                assert_eq!(tile, game.map.tile(loc).unwrap());
+                assert!(current);
+            }
+            obs => panic!("Expected {} to be observed but got {:?}", loc, obs),
+        }
+    }
+
+    // A viewer's observations are the same as the player gets with its secret
+    for (player, secret) in secrets.iter().enumerate() {
+        let seen = game.spectator_observations(Some(player)).unwrap();
+        let observations = game.player_observations(*secret).unwrap();
+        assert!(seen.num_observed() < everything.num_observed());
+        for loc in dims.iter_locs() {
+            assert_eq!(seen.get(loc), observations.get(loc));
+        }
+    }
+
+    assert!(matches!(
+        game.spectator_observations(Some(2)),
+        Err(GameError::NoSuchPlayer { player: 2 })
+    ));
+}
+
+#[test]
+fn test_chat() {
+    let map = MapData::try_from("A1").unwrap();
+    let (mut game, secrets) = Game::new_with_map(None, false, map, 2, false, None, Wrap2d::NEITHER);
+
+    game.set_player_name(secrets[1], String::from(" Ada "))
+        .unwrap();
+    let mut chat = game.watch_chat();
+
+    // Chat doesn't wait for anyone's turn
+    game.send_chat_message(secrets[1], String::from("  Hello  "))
+        .unwrap();
+    assert!(chat.has_changed().unwrap());
+    assert_eq!(*chat.borrow_and_update(), 1);
+    game.send_chat_message(secrets[0], String::from("Hi"))
+        .unwrap();
+
+    let messages = game.chat_messages_since(secrets[0], 0).unwrap();
+    assert_eq!(
+        messages,
+        vec![
+            ChatMessage {
+                sender: 1,
+                sender_name: Some(String::from("Ada")),
+                turn: 0,
+                text: String::from("Hello"),
+            },
+            ChatMessage {
+                sender: 0,
+                sender_name: None,
+                turn: 0,
+                text: String::from("Hi"),
+            },
+        ]
+    );
+    assert_eq!(
+        game.chat_messages_since(secrets[1], 1).unwrap(),
+        messages[1..]
+    );
+    assert!(game.chat_messages_since(secrets[1], 2).unwrap().is_empty());
+    assert!(game.chat_messages_since(secrets[1], 10).unwrap().is_empty());
+
+    assert_eq!(
+        game.send_chat_message(secrets[0], String::from("   ")),
+        Err(GameError::EmptyChatMessage)
+    );
+    assert_eq!(
+        game.send_chat_message(secrets[0], "a".repeat(MAX_CHAT_MESSAGE_LEN + 1)),
+        Err(GameError::ChatMessageTooLong {
+            len: MAX_CHAT_MESSAGE_LEN + 1,
+            max: MAX_CHAT_MESSAGE_LEN
+        })
+    );
+    assert_eq!(game.chat_messages_since(secrets[0], 0).unwrap().len(), 2);
+    assert_eq!(*chat.borrow(), 2);
+
+    // Renaming doesn't touch what was already said
+    game.set_player_name(secrets[1], String::from("  "))
+        .unwrap();
+    assert_eq!(game.player_names(), &[None, None]);
+    assert_eq!(
+        game.chat_messages_since(secrets[0], 0).unwrap()[0].sender_name,
+        Some(String::from("Ada"))
+    );
+    assert_eq!(
+        game.set_player_name(secrets[0], "a".repeat(MAX_PLAYER_NAME_LEN + 1)),
+        Err(GameError::PlayerNameTooLong {
+            len: MAX_PLAYER_NAME_LEN + 1,
+            max: MAX_PLAYER_NAME_LEN
+        })
+    );
+
+    assert_eq!(
+        game.chat_messages_since(Uuid::new_v4(), 0),
+        Err(GameError::NoPlayerIdentifiedBySecret)
+    );
+}
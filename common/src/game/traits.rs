@@ -1,16 +1,21 @@
 use std::{
     borrow::Cow,
     collections::{BTreeMap, BTreeSet},
+    time::Duration,
 };
 
 use async_trait::async_trait;
 
 use crate::{
     game::{
+        chat::ChatMessage,
         city::{City, CityID},
+        combat::CombatOdds,
+        delta::{SyncPoint, TurnDelta},
         error::GameError,
-        map::Tile,
-        obs::{Obs, ObsTracker},
+        history::PlayerHistory,
+        map::{Improvement, Tile},
+        obs::{Obs, ObsDiff, ObsRevision, ObsTracker},
         unit::{
             orders::{Orders, OrdersResult},
             Unit, UnitID, UnitType,
@@ -22,20 +27,34 @@ use crate::{
 use super::{
     action::{AiPlayerAction, NextCityAction, NextUnitAction, PlayerAction, PlayerActionOutcome},
     ai::{fX, TrainingFocus},
+    diplomacy::DiplomaticStatus,
     move_::Move,
     obs::LocatedObsLite,
     player::PlayerNum,
-    ActionNum, Game, OrdersSet, PlayerSecret, ProductionCleared, ProductionSet,
-    ProposedActionResult, ProposedOrdersResult, ProposedResult, TurnEnded, TurnNum, TurnPhase,
-    TurnStart, UmpireResult, UnitDisbanded,
+    ActionNum, Bombardment, Game, GameOver, OrdersSet, PlayerSecret, ProductionCleared,
+    ProductionSet, ProposedActionResult, ProposedOrdersResult, ProposedResult, RadarBuilt,
+    Resigned, TurnEnded, TurnNum, TurnPhase, TurnStart, UmpireResult, UnitDisbanded, WallsBuilt,
 };
 
 #[async_trait]
 pub trait IGame: Send + Sync {
-    async fn num_players(&self) -> PlayerNum;
+    async fn num_players(&self) -> UmpireResult<PlayerNum>;
 
     async fn is_player_turn(&self, secret: PlayerSecret) -> UmpireResult<bool>;
 
+    /// How long the player has left to finish their turn, if turns are timed
+    ///
+    /// Errs if it isn't the player's turn---as when it ran out of time and was ended for them.
+    async fn turn_time_remaining(
+        &self,
+        player_secret: PlayerSecret,
+    ) -> UmpireResult<Option<Duration>>;
+
+    /// Wait up to `timeout` for the turn of a player we control, giving the player---or `None` if no such turn came
+    ///
+    /// A game in this process controls every player, and can't change while it's waited on, so it answers at once.
+    async fn wait_my_turn(&self, timeout: Duration) -> UmpireResult<Option<PlayerNum>>;
+
     async fn begin_turn(
         &mut self,
         player_secret: PlayerSecret,
@@ -47,9 +66,9 @@ pub trait IGame: Send + Sync {
     /// This is public information.
     async fn turn_is_done(&self, player: PlayerNum, turn: TurnNum) -> UmpireResult<bool>;
 
-    async fn turn_phase(&self) -> TurnPhase;
+    async fn turn_phase(&self) -> UmpireResult<TurnPhase>;
 
-    async fn current_turn_is_done(&self) -> bool;
+    async fn current_turn_is_done(&self) -> UmpireResult<bool>;
 
     /// The victor---if any---meaning the player who has defeated all other players.
     ///
@@ -57,7 +76,13 @@ pub trait IGame: Send + Sync {
     /// has won.
     ///
     /// Defeat is defined as having no cities and having no units that can capture cities
-    async fn victor(&self) -> Option<PlayerNum>;
+    async fn victor(&self) -> UmpireResult<Option<PlayerNum>>;
+
+    /// How the game was decided---by conquest, or by score once the turn limit was reached---if it has been
+    async fn game_over(&self) -> UmpireResult<Option<GameOver>>;
+
+    /// The turn on which the game ends if nobody has won outright by then, if any
+    async fn turn_limit(&self) -> UmpireResult<Option<TurnNum>>;
 
     async fn end_turn(&mut self, player_secret: PlayerSecret) -> UmpireResult<TurnEnded>;
 
@@ -101,6 +126,14 @@ pub trait IGame: Send + Sync {
         unit_id: UnitID,
     ) -> UmpireResult<BTreeSet<Location>>;
 
+    /// The locations the specified unit could get to with the moves it has left this turn, given what its player has
+    /// observed. This excludes the unit's original location
+    async fn player_unit_reachable_this_turn(
+        &self,
+        player_secret: PlayerSecret,
+        unit_id: UnitID,
+    ) -> UmpireResult<BTreeSet<Location>>;
+
     async fn player_unit_legal_directions(
         &self,
         player_secret: PlayerSecret,
@@ -123,6 +156,29 @@ pub trait IGame: Send + Sync {
 
     async fn player_observations(&self, player_secret: PlayerSecret) -> UmpireResult<ObsTracker>;
 
+    /// What a spectator sees: `viewer`'s observations, or if `None`, every tile as it is right now
+    async fn spectator_observations(&self, viewer: Option<PlayerNum>) -> UmpireResult<ObsTracker>;
+
+    /// The changes to the player's observations since `revision` of them
+    ///
+    /// Much cheaper than `player_observations` for keeping a copy of the observations up to date, as only the
+    /// locations whose observations changed are sent.
+    async fn player_observations_since(
+        &self,
+        player_secret: PlayerSecret,
+        revision: ObsRevision,
+    ) -> UmpireResult<ObsDiff>;
+
+    /// What changed for the player since `since`---observations, units, cities, and chat---or everything if `since`
+    /// is `None`
+    ///
+    /// Lets a remote copy of the player's state be kept up to date without downloading the whole of it each turn.
+    async fn player_turn_delta(
+        &self,
+        player_secret: PlayerSecret,
+        since: Option<SyncPoint>,
+    ) -> UmpireResult<TurnDelta>;
+
     /// Every city controlled by the player whose secret is provided
     async fn player_cities(&self, player_secret: PlayerSecret) -> UmpireResult<Vec<City>>;
 
@@ -305,6 +361,155 @@ pub trait IGame: Send + Sync {
         dest: Location,
     ) -> ProposedResult<Move, GameError>;
 
+    /// The odds of the specified unit prevailing should it attack `defender_loc`, as far as the player knows
+    async fn combat_odds(
+        &self,
+        player_secret: PlayerSecret,
+        attacker_id: UnitID,
+        defender_loc: Location,
+    ) -> UmpireResult<CombatOdds>;
+
+    /// Move a unit onto an adjacent friendly carrier, such as a transport
+    ///
+    /// Must be player's turn
+    async fn load_unit(
+        &mut self,
+        player_secret: PlayerSecret,
+        unit_id: UnitID,
+        carrier_id: UnitID,
+    ) -> UmpireResult<Move>;
+
+    /// Move a carried unit off of its carrier onto an adjacent tile
+    ///
+    /// Must be player's turn
+    async fn unload_unit(
+        &mut self,
+        player_secret: PlayerSecret,
+        unit_id: UnitID,
+        dest: Location,
+    ) -> UmpireResult<Move>;
+
+    /// Fly an aircraft to a friendly city or carrier and land there
+    ///
+    /// Must be player's turn
+    async fn rebase_unit(
+        &mut self,
+        player_secret: PlayerSecret,
+        unit_id: UnitID,
+        base: Location,
+    ) -> UmpireResult<Move>;
+
+    /// Where the specified aircraft could land this turn, nearest first
+    async fn player_air_bases(
+        &self,
+        player_secret: PlayerSecret,
+        unit_id: UnitID,
+    ) -> UmpireResult<Vec<Location>>;
+
+    /// Attack the enemy at `target` from a distance, without moving
+    ///
+    /// Must be player's turn
+    async fn bombard(
+        &mut self,
+        player_secret: PlayerSecret,
+        unit_id: UnitID,
+        target: Location,
+    ) -> UmpireResult<Bombardment>;
+
+    /// Where the specified unit could bombard, nearest first
+    async fn player_unit_bombard_targets(
+        &self,
+        player_secret: PlayerSecret,
+        unit_id: UnitID,
+    ) -> UmpireResult<Vec<Location>>;
+
+    /// Air-drop a unit from a friendly city onto land within range, fighting whatever is there
+    ///
+    /// Must be player's turn
+    async fn paradrop(
+        &mut self,
+        player_secret: PlayerSecret,
+        unit_id: UnitID,
+        dest: Location,
+    ) -> UmpireResult<Move>;
+
+    /// Where the specified unit could be air-dropped this turn, nearest first
+    async fn player_unit_drop_zones(
+        &self,
+        player_secret: PlayerSecret,
+        unit_id: UnitID,
+    ) -> UmpireResult<Vec<Location>>;
+
+    /// Lay mines where the specified unit stands, returning the location mined
+    ///
+    /// Must be player's turn
+    async fn lay_mines(
+        &mut self,
+        player_secret: PlayerSecret,
+        unit_id: UnitID,
+    ) -> UmpireResult<Location>;
+
+    /// Clear all mines from the specified unit's location and its surroundings, returning the locations cleared
+    ///
+    /// Must be player's turn
+    async fn sweep_mines(
+        &mut self,
+        player_secret: PlayerSecret,
+        unit_id: UnitID,
+    ) -> UmpireResult<Vec<Location>>;
+
+    /// The minefields the player knows about
+    async fn player_known_minefields(
+        &self,
+        player_secret: PlayerSecret,
+    ) -> UmpireResult<Vec<Location>>;
+
+    /// The diplomatic status between the player and `other`
+    async fn diplomatic_status(
+        &self,
+        player_secret: PlayerSecret,
+        other: PlayerNum,
+    ) -> UmpireResult<DiplomaticStatus>;
+
+    /// Treaties other players have offered the player
+    async fn treaty_proposals(
+        &self,
+        player_secret: PlayerSecret,
+    ) -> UmpireResult<Vec<(PlayerNum, DiplomaticStatus)>>;
+
+    /// Offer `other` peace or an alliance
+    ///
+    /// Needn't be player's turn
+    async fn propose_treaty(
+        &mut self,
+        player_secret: PlayerSecret,
+        other: PlayerNum,
+        status: DiplomaticStatus,
+    ) -> UmpireResult<()>;
+
+    /// Accept the treaty `other` has offered, returning the new status
+    ///
+    /// Needn't be player's turn
+    async fn accept_treaty(
+        &mut self,
+        player_secret: PlayerSecret,
+        other: PlayerNum,
+    ) -> UmpireResult<DiplomaticStatus>;
+
+    /// Return to war with `other`, returning the prior status
+    ///
+    /// Needn't be player's turn
+    async fn declare_war(
+        &mut self,
+        player_secret: PlayerSecret,
+        other: PlayerNum,
+    ) -> UmpireResult<DiplomaticStatus>;
+
+    /// Withdraw from the game: the player's cities become neutral and its units are disbanded
+    ///
+    /// Must be player's turn
+    async fn resign(&mut self, player_secret: PlayerSecret) -> UmpireResult<Resigned>;
+
     /// Disbands
     ///
     /// Must be player's turn
@@ -334,6 +539,21 @@ pub trait IGame: Send + Sync {
         production: UnitType,
     ) -> UmpireResult<ProductionSet>;
 
+    /// Spends some of the accumulated production of the current player's city with ID `city_id` on another level of
+    /// walls.
+    async fn build_city_walls(
+        &mut self,
+        player_secret: PlayerSecret,
+        city_id: CityID,
+    ) -> UmpireResult<WallsBuilt>;
+
+    /// Spends some of the accumulated production of the current player's city with ID `city_id` on a radar.
+    async fn build_city_radar(
+        &mut self,
+        player_secret: PlayerSecret,
+        city_id: CityID,
+    ) -> UmpireResult<RadarBuilt>;
+
     /// Clears the production of a city at location `loc` if one exists and is controlled by the
     /// specified player.
     ///
@@ -351,16 +571,19 @@ pub trait IGame: Send + Sync {
         ignore_cleared_production: bool,
     ) -> UmpireResult<Vec<ProductionCleared>>;
 
-    async fn turn(&self) -> TurnNum;
+    async fn turn(&self) -> UmpireResult<TurnNum>;
 
     async fn player_action(&self, player_secret: PlayerSecret) -> UmpireResult<ActionNum>;
 
-    async fn current_player(&self) -> PlayerNum;
+    async fn current_player(&self) -> UmpireResult<PlayerNum>;
+
+    /// Whether play is held, so no new turn can begin
+    async fn paused(&self) -> UmpireResult<bool>;
 
     /// The logical dimensions of the game map
-    async fn dims(&self) -> Dims;
+    async fn dims(&self) -> UmpireResult<Dims>;
 
-    async fn wrapping(&self) -> Wrap2d;
+    async fn wrapping(&self) -> UmpireResult<Wrap2d>;
 
     async fn valid_productions(
         &self,
@@ -415,6 +638,73 @@ pub trait IGame: Send + Sync {
         unit_id: UnitID,
     ) -> ProposedOrdersResult;
 
+    /// Order the specified unit to stay alongside the unit `target`
+    async fn order_unit_escort(
+        &mut self,
+        player_secret: PlayerSecret,
+        unit_id: UnitID,
+        target: UnitID,
+    ) -> OrdersResult;
+
+    /// Simulate ordering the specified unit to escort the unit `target`
+    async fn propose_order_unit_escort(
+        &self,
+        player_secret: PlayerSecret,
+        unit_id: UnitID,
+        target: UnitID,
+    ) -> ProposedOrdersResult;
+
+    /// Order the specified unit to build `improvement` where it stands
+    async fn order_unit_build(
+        &mut self,
+        player_secret: PlayerSecret,
+        unit_id: UnitID,
+        improvement: Improvement,
+    ) -> OrdersResult;
+
+    /// Simulate ordering the specified unit to build `improvement` where it stands
+    async fn propose_order_unit_build(
+        &self,
+        player_secret: PlayerSecret,
+        unit_id: UnitID,
+        improvement: Improvement,
+    ) -> ProposedOrdersResult;
+
+    /// The player's unit groups, by name
+    async fn unit_groups(
+        &self,
+        player_secret: PlayerSecret,
+    ) -> UmpireResult<Vec<(String, Vec<UnitID>)>>;
+
+    /// Group the given units under `name`, replacing any group of that name
+    ///
+    /// Needn't be player's turn
+    async fn form_unit_group(
+        &mut self,
+        player_secret: PlayerSecret,
+        name: String,
+        unit_ids: Vec<UnitID>,
+    ) -> UmpireResult<()>;
+
+    /// Break up the player's group called `name`, returning its members
+    ///
+    /// Needn't be player's turn
+    async fn disband_unit_group(
+        &mut self,
+        player_secret: PlayerSecret,
+        name: String,
+    ) -> UmpireResult<Vec<UnitID>>;
+
+    /// Give every member of the player's group called `name` the same orders
+    ///
+    /// Must be player's turn
+    async fn order_unit_group(
+        &mut self,
+        player_secret: PlayerSecret,
+        name: String,
+        orders: Orders,
+    ) -> UmpireResult<Vec<OrdersResult>>;
+
     /// If a unit at the location owned by the current player exists, activate it and any units it carries
     async fn activate_unit_by_loc(
         &mut self,
@@ -457,12 +747,14 @@ pub trait IGame: Send + Sync {
         orders: Orders,
     ) -> OrdersResult;
 
-    async fn current_player_score(&self) -> f64;
+    async fn current_player_score(&self) -> UmpireResult<f64>;
 
+    /// The player's feature vector, its 2d features covering a window `window` tiles wide
     async fn player_features(
         &self,
         player_secret: PlayerSecret,
         focus: TrainingFocus,
+        window: usize,
     ) -> UmpireResult<Vec<fX>>;
 
     async fn player_score(&self, player_secret: PlayerSecret) -> UmpireResult<f64>;
@@ -470,7 +762,36 @@ pub trait IGame: Send + Sync {
     async fn player_score_by_idx(&self, player: PlayerNum) -> UmpireResult<f64>;
 
     /// Each player's current score, indexed by player number
-    async fn player_scores(&self) -> Vec<f64>;
+    async fn player_scores(&self) -> UmpireResult<Vec<f64>>;
+
+    /// How the game has gone for each player so far, indexed by player number
+    async fn player_histories(&self) -> UmpireResult<Vec<PlayerHistory>>;
+
+    /// Send a chat message to all the other players
+    ///
+    /// Needn't be player's turn
+    async fn send_chat_message(
+        &mut self,
+        player_secret: PlayerSecret,
+        text: String,
+    ) -> UmpireResult<()>;
+
+    /// The chat messages sent after the first `since` of them
+    async fn chat_messages_since(
+        &self,
+        player_secret: PlayerSecret,
+        since: usize,
+    ) -> UmpireResult<Vec<ChatMessage>>;
+
+    /// Wait up to `timeout` for chat messages after the first `since` of them, giving them as soon as there are any
+    ///
+    /// Like `wait_my_turn`, a game in this process answers at once.
+    async fn wait_chat_messages(
+        &self,
+        player_secret: PlayerSecret,
+        since: usize,
+        timeout: Duration,
+    ) -> UmpireResult<Vec<ChatMessage>>;
 
     async fn take_simple_action(
         &mut self,
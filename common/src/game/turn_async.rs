@@ -109,7 +109,8 @@ impl<T: ActionwiseTurnTaker + Send> TurnTaker for T {
                         TrainingFocus::Unit
                     };
 
-                    let (num_features, features) = sparsify(turn.player_features(focus).await);
+                    let (num_features, features) =
+                        sparsify(turn.player_features(focus, turn.feature_window()).await);
                     (Some(num_features), Some(features))
                 } else {
                     (None, None)
@@ -1,6 +1,7 @@
 //! Abstract representation of units and cities and their interactions.
 
 pub mod orders;
+pub mod stats;
 
 use std::cmp::Ordering;
 use std::fmt;
@@ -12,7 +13,7 @@ use crate::{
     game::{
         alignment::{Aligned, Alignment},
         combat::CombatCapable,
-        map::{Terrain, Tile},
+        map::{Improvement, River, Terrain, Tile},
         obs::Observer,
         GameError,
     },
@@ -20,7 +21,7 @@ use crate::{
     util::{Located, Location},
 };
 
-use self::orders::Orders;
+use self::{orders::Orders, stats::UnitStats};
 
 use super::{ai::fX, move_::MoveError, UmpireResult};
 
@@ -53,12 +54,17 @@ impl TransportMode {
     /// Determine whether a unit with this transport mode can operate on terrain of the given type
     pub fn can_traverse(&self, terrain: Terrain) -> bool {
         match self {
-            TransportMode::Land => terrain == Terrain::Land,
+            TransportMode::Land => terrain.is_land(),
             TransportMode::Sea => terrain == Terrain::Water,
-            TransportMode::Air => terrain == Terrain::Land || terrain == Terrain::Water,
+            TransportMode::Air => true,
         }
     }
 
+    /// Determine whether a unit with this transport mode can cross the given river, if any
+    pub fn can_cross(&self, river: Option<River>) -> bool {
+        *self != TransportMode::Land || river != Some(River::Channel)
+    }
+
     pub fn default_terrain(&self) -> Terrain {
         match self {
             TransportMode::Land => Terrain::Land,
@@ -72,14 +78,22 @@ impl TransportMode {
 struct CarryingSpace {
     owner: Alignment,
     accepted_transport_mode: TransportMode,
+    /// If set, only units of this type are accepted
+    accepted_type: Option<UnitType>,
     capacity: usize,
     space: Vec<Unit>,
 }
 impl CarryingSpace {
-    fn new(owner: Alignment, accepted_transport_mode: TransportMode, capacity: usize) -> Self {
+    fn new(
+        owner: Alignment,
+        accepted_transport_mode: TransportMode,
+        accepted_type: Option<UnitType>,
+        capacity: usize,
+    ) -> Self {
         Self {
             owner,
             accepted_transport_mode,
+            accepted_type,
             capacity,
             space: Vec::with_capacity(capacity),
         }
@@ -103,6 +117,16 @@ impl CarryingSpace {
             });
         }
 
+        if let Some(accepted_type) = self.accepted_type {
+            if unit.type_ != accepted_type {
+                return Err(GameError::WrongUnitType {
+                    carried_id: unit.id,
+                    accepted_type,
+                    carried_type: unit.type_,
+                });
+            }
+        }
+
         debug_assert!(self.space.len() <= self.capacity);
 
         if self.space.len() == self.capacity {
@@ -150,7 +174,7 @@ impl CarryingSpace {
     }
 }
 
-pub const POSSIBLE_UNIT_TYPES: usize = 10;
+pub const POSSIBLE_UNIT_TYPES: usize = 15;
 
 /// How many unit types there are, counting city as a unit type
 pub const POSSIBLE_UNIT_TYPES_WRIT_LARGE: usize = POSSIBLE_UNIT_TYPES + 1;
@@ -167,6 +191,11 @@ pub enum UnitType {
     Cruiser,
     Battleship,
     Carrier,
+    Artillery,
+    Helicopter,
+    Paratrooper,
+    Missile,
+    Engineer,
 }
 
 impl UnitType {
@@ -182,33 +211,26 @@ impl UnitType {
             UnitType::Cruiser,
             UnitType::Battleship,
             UnitType::Carrier,
+            UnitType::Artillery,
+            UnitType::Helicopter,
+            UnitType::Paratrooper,
+            UnitType::Missile,
+            UnitType::Engineer,
         ]
     }
 
+    /// This unit type's stats, as loaded into the stats registry
+    pub fn stats(self) -> &'static UnitStats {
+        stats::registry().get(self)
+    }
+
     pub fn max_hp(self) -> u16 {
-        match self {
-            UnitType::Infantry | UnitType::Fighter => 1,
-            UnitType::Armor | UnitType::Bomber | UnitType::Destroyer | UnitType::Submarine => 2,
-            UnitType::Transport => 3,
-            UnitType::Cruiser => 4,
-            UnitType::Battleship => 8,
-            UnitType::Carrier => 6,
-        }
+        self.stats().max_hp
     }
 
     /// The number of turns a city must dedicate its production to the unit type to produce a single unit of that type
     pub fn cost(self) -> u16 {
-        match self {
-            UnitType::Infantry => 6,
-            UnitType::Armor => 11, // Cheaper per HP than infantry - trade first-mover advantage for long-term efficiency
-            UnitType::Fighter => 12,
-            UnitType::Bomber => 18, // Longer range AND tougher than fighters
-            UnitType::Destroyer | UnitType::Submarine => 24,
-            UnitType::Transport => 30,
-            UnitType::Cruiser => 36,
-            UnitType::Carrier => 48,
-            UnitType::Battleship => 60,
-        }
+        self.stats().cost
     }
 
     pub fn key(self) -> char {
@@ -223,15 +245,16 @@ impl UnitType {
             UnitType::Cruiser => 'c',
             UnitType::Battleship => 'p',
             UnitType::Carrier => 'k',
+            UnitType::Artillery => 'r',
+            UnitType::Helicopter => 'h',
+            UnitType::Paratrooper => 'j',
+            UnitType::Missile => 'm',
+            UnitType::Engineer => 'e',
         }
     }
 
     pub fn sight_distance(self) -> u16 {
-        match self {
-            UnitType::Infantry | UnitType::Armor | UnitType::Transport => 2,
-            UnitType::Destroyer | UnitType::Submarine | UnitType::Cruiser => 3,
-            UnitType::Fighter | UnitType::Bomber | UnitType::Battleship | UnitType::Carrier => 4,
-        }
+        self.stats().sight_distance
     }
 
     //TODO Replace with impl From<char>
@@ -250,7 +273,7 @@ impl UnitType {
     /// If a city is present, this will always be true. Otherwise, it will be determined by the match between
     /// the unit's capabilities and the terrain (e.g. planes over water, but not tanks over water).
     pub fn can_move_on_tile(self, tile: &Tile) -> bool {
-        tile.city.is_some() || self.transport_mode().can_traverse(tile.terrain)
+        tile.city.is_some() || self.can_traverse_tile(tile)
     }
 
     /// Determine whether a unit of this type could actually occupy a particular tile (maybe requiring combat to do so).
@@ -258,10 +281,16 @@ impl UnitType {
         if tile.city.is_some() {
             self.can_occupy_cities()
         } else {
-            self.transport_mode().can_traverse(tile.terrain)
+            self.can_traverse_tile(tile)
         }
     }
 
+    /// Can a unit of this type cross the tile's terrain and any river on it?
+    fn can_traverse_tile(self, tile: &Tile) -> bool {
+        let mode = self.transport_mode();
+        mode.can_traverse(tile.terrain) && mode.can_cross(tile.river)
+    }
+
     pub fn name(self) -> &'static str {
         match self {
             UnitType::Infantry => "Infantry",
@@ -274,41 +303,124 @@ impl UnitType {
             UnitType::Cruiser => "Cruiser",
             UnitType::Battleship => "Battleship",
             UnitType::Carrier => "Carrier",
+            UnitType::Artillery => "Artillery",
+            UnitType::Helicopter => "Helicopter",
+            UnitType::Paratrooper => "Paratrooper",
+            UnitType::Missile => "Missile",
+            UnitType::Engineer => "Engineer",
         }
     }
 
     pub fn transport_mode(self) -> TransportMode {
-        match self {
-            UnitType::Infantry | UnitType::Armor => TransportMode::Land,
-            UnitType::Fighter | UnitType::Bomber => TransportMode::Air,
-            UnitType::Transport
-            | UnitType::Destroyer
-            | UnitType::Submarine
-            | UnitType::Cruiser
-            | UnitType::Battleship
-            | UnitType::Carrier => TransportMode::Sea,
-        }
+        self.stats().transport_mode
     }
 
     pub fn carrying_capacity(self) -> usize {
         match self {
             UnitType::Carrier => 5,
             UnitType::Transport => 4,
+            UnitType::Submarine | UnitType::Cruiser => 2,
             _ => 0,
         }
     }
 
+    /// How far away a unit of this type can attack without moving, if it can at all
+    ///
+    /// Range is measured in steps in any of the eight directions, so a range of 2 covers the 5x5 square around the
+    /// unit.
+    pub fn attack_range(self) -> Option<u16> {
+        match self {
+            UnitType::Artillery => Some(2),
+            _ => None,
+        }
+    }
+
+    /// How far a unit of this type can be air-dropped from a friendly city, if it can be at all
+    ///
+    /// Measured like `attack_range`.
+    pub fn paradrop_range(self) -> Option<u16> {
+        match self {
+            UnitType::Paratrooper => Some(8),
+            _ => None,
+        }
+    }
+
+    /// Can a unit of this type build tile improvements such as roads?
+    pub fn can_build(self) -> bool {
+        self == UnitType::Engineer
+    }
+
+    /// Can a unit of this type lay mines where it stands?
+    pub fn can_lay_mines(self) -> bool {
+        matches!(
+            self,
+            UnitType::Engineer | UnitType::Destroyer | UnitType::Submarine | UnitType::Cruiser
+        )
+    }
+
+    /// Can a unit of this type clear mines from its surroundings?
+    pub fn can_sweep_mines(self) -> bool {
+        self == UnitType::Destroyer
+    }
+
+    /// Does a unit of this type set off mines? Aircraft pass over them unharmed.
+    pub fn triggers_mines(self) -> bool {
+        self.transport_mode() != TransportMode::Air
+    }
+
+    /// Is a unit of this type used up when it attacks, whatever the outcome?
+    pub fn is_expendable(self) -> bool {
+        self == UnitType::Missile
+    }
+
+    /// Can a unit of this type attack a unit of type `target`?
+    ///
+    /// Helicopters can strike at ground and naval units but can't engage other aircraft.
+    pub fn can_attack(self, target: UnitType) -> bool {
+        !matches!(
+            (self, target.transport_mode()),
+            (UnitType::Helicopter, TransportMode::Air)
+        )
+    }
+
     /// Can this type of unit occupy cities?
     pub fn can_occupy_cities(self) -> bool {
         self.transport_mode() == TransportMode::Land
     }
 
     pub fn movement_per_turn(&self) -> u16 {
-        match self {
-            UnitType::Infantry | UnitType::Battleship | UnitType::Carrier => 1,
-            UnitType::Armor | UnitType::Transport | UnitType::Submarine | UnitType::Cruiser => 2,
-            UnitType::Bomber | UnitType::Destroyer => 3,
-            UnitType::Fighter => 5,
+        self.stats().movement_per_turn
+    }
+
+    /// The movement points spent by a unit of this type entering terrain of the given type
+    ///
+    /// Forest and mountains cost what the unit type's stats say, but never more than its full movement for the turn,
+    /// so it can always make some headway. By default that leaves only armor slowed by rough terrain, the other ground
+    /// units moving just one tile a turn anyway. Cities are always entered at a cost of 1 regardless of their terrain;
+    /// see `tile_movement_cost`.
+    pub fn movement_cost(self, terrain: Terrain) -> u16 {
+        let cost = match terrain {
+            Terrain::Forest => self.stats().forest_cost,
+            Terrain::Mountains => self.stats().mountains_cost,
+            Terrain::Water | Terrain::Land => 1,
+        };
+        cost.min(self.movement_per_turn())
+    }
+
+    /// The movement points spent by a unit of this type entering the given tile
+    ///
+    /// Fording a river takes a ground unit's full movement for the turn. Elsewhere, a road lets ground units cross
+    /// rough terrain at a cost of 1.
+    pub fn tile_movement_cost(self, tile: &Tile) -> u16 {
+        let ground = self.transport_mode() == TransportMode::Land;
+        if tile.city.is_some() {
+            1
+        } else if tile.river == Some(River::Ford) && ground {
+            self.movement_per_turn()
+        } else if tile.has_improvement(Improvement::Road) && ground {
+            1
+        } else {
+            self.movement_cost(tile.terrain)
         }
     }
 
@@ -317,6 +429,8 @@ impl UnitType {
         match self {
             UnitType::Fighter => Fuel::limited(20),
             UnitType::Bomber => Fuel::limited(30),
+            UnitType::Helicopter => Fuel::limited(12),
+            UnitType::Missile => Fuel::limited(8),
             _ => Fuel::Unlimited,
         }
     }
@@ -334,11 +448,20 @@ impl UnitType {
             UnitType::Transport => Some(CarryingSpace::new(
                 alignment,
                 TransportMode::Land,
+                None,
                 self.carrying_capacity(),
             )),
             UnitType::Carrier => Some(CarryingSpace::new(
                 alignment,
                 TransportMode::Air,
+                None,
+                self.carrying_capacity(),
+            )),
+            // Submarines and cruisers serve as missile launchers
+            UnitType::Submarine | UnitType::Cruiser => Some(CarryingSpace::new(
+                alignment,
+                TransportMode::Air,
+                Some(UnitType::Missile),
                 self.carrying_capacity(),
             )),
             _ => None,
@@ -375,11 +498,11 @@ impl UnitType {
     }
 
     pub fn none_features_writ_large(is_city: bool) -> [fX; POSSIBLE_UNIT_TYPES_WRIT_LARGE] {
+        let mut feats = [0.0; POSSIBLE_UNIT_TYPES_WRIT_LARGE];
         if is_city {
-            [0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 1.0]
-        } else {
-            [0.0; POSSIBLE_UNIT_TYPES_WRIT_LARGE]
+            feats[POSSIBLE_UNIT_TYPES] = 1.0;
         }
+        feats
     }
 }
 
@@ -420,6 +543,68 @@ impl Fuel {
     }
 }
 
+/// A unit's standing as earned through combat experience
+#[derive(Clone, Copy, Debug, Deserialize, Eq, Hash, Ord, PartialEq, PartialOrd, Serialize)]
+pub enum Veterancy {
+    Green,
+    Veteran,
+    Elite,
+}
+impl Veterancy {
+    /// Experience at which a unit becomes a veteran
+    pub const VETERAN_XP: u16 = 2;
+
+    /// Experience at which a unit becomes elite
+    pub const ELITE_XP: u16 = 5;
+
+    pub fn from_experience(experience: u16) -> Self {
+        if experience >= Self::ELITE_XP {
+            Self::Elite
+        } else if experience >= Self::VETERAN_XP {
+            Self::Veteran
+        } else {
+            Self::Green
+        }
+    }
+
+    /// Hitpoints added to the unit type's base maximum
+    pub fn hp_bonus(self) -> u16 {
+        match self {
+            Self::Green => 0,
+            Self::Veteran => 1,
+            Self::Elite => 2,
+        }
+    }
+
+    /// Multiplier on the unit's odds of landing each blow in combat
+    pub fn strength(self) -> f64 {
+        match self {
+            Self::Green => 1.0,
+            Self::Veteran => 1.25,
+            Self::Elite => 1.5,
+        }
+    }
+
+    /// 0 for green, 1 for veteran, 2 for elite
+    pub fn level(self) -> u16 {
+        self as u16
+    }
+}
+
+impl fmt::Display for Veterancy {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{}",
+            match self {
+                Self::Green => "Green",
+                Self::Veteran => "Veteran",
+                Self::Elite => "Elite",
+            }
+        )
+    }
+}
+
 #[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
 pub struct Unit {
     pub id: UnitID,
@@ -433,6 +618,9 @@ pub struct Unit {
     pub orders: Option<Orders>,
     carrying_space: Option<CarryingSpace>,
     pub fuel: Fuel,
+
+    /// Combat victories won by this unit
+    experience: u16,
 }
 
 impl Unit {
@@ -456,6 +644,7 @@ impl Unit {
             orders: None,
             carrying_space: type_.new_carrying_space_for(alignment),
             fuel: type_.fuel(),
+            experience: 0,
         }
     }
 
@@ -468,7 +657,8 @@ impl Unit {
     /// This is determined as follows:
     ///
     /// If the tile contains a unit:
-    ///    if the unit is unfriendly, then defer to terrain features / city presence
+    ///    if the unit is unfriendly, then defer to terrain features / city presence, provided this unit can attack
+    ///        the tile's defender
     ///    if the unit is friendly:
     ///        if the unit has appropriate carrying space for this unit, then we can move on the tile
     ///        otherwise, we cannot move on the tile
@@ -480,8 +670,11 @@ impl Unit {
         if let Some(ref unit) = tile.unit {
             if !unit.is_friendly_to(self) {
                 self.type_.can_move_on_tile(tile)
+                    && tile
+                        .defender()
+                        .map_or(true, |defender| self.type_.can_attack(defender.type_))
             } else {
-                unit.can_carry_unit(self)
+                unit.can_carry_unit(self) || tile.can_stack(self)
             }
         } else if let Some(ref city) = tile.city {
             if city.is_friendly_to(self) {
@@ -496,11 +689,11 @@ impl Unit {
 
     /// Could this unit attack the given tile if it were adjacent?
     ///
-    /// This basically amounts to whether there is an enemy city or unit on the tile
+    /// This basically amounts to whether there is an enemy city or unit on the tile, and whether this unit is able to
+    /// attack the unit
     pub fn can_attack_tile(&self, tile: &Tile) -> bool {
-        tile.unit
-            .as_ref()
-            .map(|_| true)
+        tile.defender()
+            .map(|defender| self.type_.can_attack(defender.type_))
             .or_else(|| tile.city.as_ref().map(|_| true))
             .unwrap_or(false)
     }
@@ -634,10 +827,48 @@ impl Unit {
         self.type_.transport_mode()
     }
 
+    pub fn hp(&self) -> u16 {
+        self.hp
+    }
+
     pub fn max_hp(&self) -> u16 {
         self.max_hp
     }
 
+    /// Lose up to `damage` hitpoints, returning whether any remain
+    pub(in crate::game) fn take_damage(&mut self, damage: u16) -> bool {
+        self.hp = self.hp.saturating_sub(damage);
+        self.hp > 0
+    }
+
+    pub fn experience(&self) -> u16 {
+        self.experience
+    }
+
+    pub fn veterancy(&self) -> Veterancy {
+        Veterancy::from_experience(self.experience)
+    }
+
+    /// Credit the unit with a combat victory
+    ///
+    /// If this earns a promotion, the unit's maximum hitpoints grow to match its new veterancy, and the new veterancy
+    /// is returned.
+    pub(in crate::game) fn record_victory(&mut self) -> Option<Veterancy> {
+        let prior = self.veterancy();
+        self.experience = self.experience.saturating_add(1);
+        let veterancy = self.veterancy();
+
+        if veterancy == prior {
+            return None;
+        }
+
+        let added_hp = veterancy.hp_bonus() - prior.hp_bonus();
+        self.max_hp += added_hp;
+        self.hp += added_hp;
+
+        Some(veterancy)
+    }
+
     /// If the unit's fuel is limited, refill it. Otherwise, do nothing.
     ///
     /// Returns the quantity of fuel refilled.
@@ -665,6 +896,9 @@ impl CombatCapable for Unit {
     fn max_hp(&self) -> u16 {
         self.max_hp
     }
+    fn strength(&self) -> f64 {
+        self.veterancy().strength()
+    }
 }
 
 impl Colorized for Unit {
@@ -693,7 +927,12 @@ impl Observer for Unit {
 
 impl fmt::Display for Unit {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        let mut result = write!(f, "{} {}", self.alignment, self.medium_desc());
+        let mut result = write!(f, "{}", self.alignment);
+        let veterancy = self.veterancy();
+        if veterancy != Veterancy::Green {
+            result = result.and(write!(f, " {}", veterancy));
+        }
+        result = result.and(write!(f, " {}", self.medium_desc()));
         if let Some(ref carrying_space) = self.carrying_space {
             result = result.and(write!(f, " carrying {} units", carrying_space.units_held()));
         }
@@ -708,9 +947,9 @@ mod test {
     use crate::{
         game::{
             city::{City, CityID},
-            map::{dijkstra::Source, LocationGrid, Terrain, Tile},
+            map::{dijkstra::Source, LocationGrid, Terrain, Tile, STACK_CAPACITY},
             obs::{Obs, ObsTracker},
-            unit::{Alignment, Observer, Unit, UnitID, UnitType},
+            unit::{Alignment, Observer, Unit, UnitID, UnitType, Veterancy},
         },
         util::{Dims, Location, Wrap2d},
     };
@@ -898,8 +1137,12 @@ x   o    x";
         let tile2 = Tile::new(Terrain::Water, loc);
         assert!(!infantry.can_move_on_tile(&tile2));
 
+        // Friendly units stack, up to a point
         let mut tile3 = Tile::new(Terrain::Land, loc);
-        tile3.unit = Some(friendly_unit);
+        tile3.unit = Some(friendly_unit.clone());
+        assert!(infantry.can_move_on_tile(&tile3));
+
+        tile3.stack = vec![friendly_unit; STACK_CAPACITY - 1];
         assert!(!infantry.can_move_on_tile(&tile3));
 
         let mut tile4 = Tile::new(Terrain::Land, loc);
@@ -958,6 +1201,54 @@ x   o    x";
         }
     }
 
+    #[test]
+    fn test_helicopter_targets() {
+        let loc = Location::new(0, 0);
+
+        let helicopter = Unit::new(
+            UnitID::new(0),
+            loc,
+            UnitType::Helicopter,
+            Alignment::Belligerent { player: 0 },
+            "Huey",
+        );
+
+        let enemy_tile = |type_: UnitType, terrain: Terrain| {
+            let mut tile = Tile::new(terrain, loc);
+            tile.unit = Some(Unit::new(
+                UnitID::new(1),
+                loc,
+                type_,
+                Alignment::Belligerent { player: 1 },
+                "Enemy",
+            ));
+            tile
+        };
+
+        // Ground and naval units are fair game
+        assert!(helicopter.can_move_on_tile(&enemy_tile(UnitType::Armor, Terrain::Land)));
+        assert!(helicopter.can_move_on_tile(&enemy_tile(UnitType::Destroyer, Terrain::Water)));
+
+        // Aircraft are not
+        let fighter_tile = enemy_tile(UnitType::Fighter, Terrain::Water);
+        assert!(!helicopter.can_move_on_tile(&fighter_tile));
+        assert!(!helicopter.can_attack_tile(&fighter_tile));
+
+        // Nor can it capture cities
+        let mut city_tile = Tile::new(Terrain::Land, loc);
+        city_tile.city = Some(City::new(
+            CityID::new(0),
+            Alignment::Belligerent { player: 1 },
+            loc,
+            "Hue",
+        ));
+        assert!(!helicopter.can_move_on_tile(&city_tile));
+
+        // It flies over any terrain
+        assert!(helicopter.can_move_on_tile(&Tile::new(Terrain::Water, loc)));
+        assert!(helicopter.can_move_on_tile(&Tile::new(Terrain::Mountains, loc)));
+    }
+
     #[test]
     pub fn test_can_carry_unit() {
         let l1 = Location::new(0, 0);
@@ -979,4 +1270,36 @@ x   o    x";
 
         assert!(!t1.can_carry_unit(&t2));
     }
+
+    #[test]
+    fn test_veterancy() {
+        let mut unit = Unit::new(
+            UnitID::new(0),
+            Location::new(0, 0),
+            UnitType::Armor,
+            Alignment::Belligerent { player: 0 },
+            "Sherman",
+        );
+        let base_hp = unit.max_hp();
+
+        assert_eq!(unit.veterancy(), Veterancy::Green);
+        assert!(!unit.to_string().contains("Veteran"));
+
+        for xp in 1..=Veterancy::ELITE_XP {
+            let promotion = unit.record_victory();
+            assert_eq!(unit.experience(), xp);
+
+            match xp {
+                x if x == Veterancy::VETERAN_XP => assert_eq!(promotion, Some(Veterancy::Veteran)),
+                x if x == Veterancy::ELITE_XP => assert_eq!(promotion, Some(Veterancy::Elite)),
+                _ => assert_eq!(promotion, None),
+            }
+
+            assert_eq!(unit.max_hp(), base_hp + unit.veterancy().hp_bonus());
+        }
+
+        assert_eq!(unit.veterancy(), Veterancy::Elite);
+        assert!(unit.to_string().contains("Elite"));
+        assert_eq!(unit.record_victory(), None);
+    }
 }
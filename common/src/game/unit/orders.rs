@@ -3,16 +3,19 @@ use serde::{Deserialize, Serialize};
 use super::Unit;
 use crate::{
     game::{
+        alignment::Aligned,
         map::{
             dijkstra::{
-                nearest_adjacent_unobserved_reachable_without_attacking, shortest_paths,
-                ObservedReachableByPacifistUnit, PacifistXenophileUnitMovementFilter,
+                nearest_adjacent_unobserved_reachable_without_attacking, shortest_path_astar,
+                shortest_paths_weighted, Cost, Filter, ObservedReachableByPacifistUnit,
+                PacifistXenophileUnitMovementFilter, Source, UnitMovementCost, RELATIVE_NEIGHBORS,
             },
-            LocationGridI,
+            Improvement, LocationGridI,
         },
         move_::{Move, MoveComponent, MoveError},
+        obs::{LocatedObs, Obs},
         unit::UnitID,
-        Game, GameError, PlayerSecret,
+        Game, GameError, PlayerNum, PlayerSecret,
     },
     util::Location,
 };
@@ -21,6 +24,9 @@ use crate::{
 pub enum OrdersStatus {
     InProgress,
     Completed,
+
+    /// The unit stopped short of completing its orders, which have been cleared so it can be given new ones
+    Interrupted,
 }
 
 /// The outcome of a unit following its orders
@@ -75,6 +81,15 @@ impl OrdersOutcome {
         }
     }
 
+    pub fn interrupted_with_move(ordered_unit: Unit, orders: Orders, move_: Move) -> Self {
+        Self {
+            ordered_unit,
+            orders,
+            move_: Some(move_),
+            status: OrdersStatus::Interrupted,
+        }
+    }
+
     pub fn move_(&self) -> Option<&Move> {
         self.move_.as_ref()
     }
@@ -86,12 +101,78 @@ impl OrdersOutcome {
 
 pub type OrdersResult = Result<OrdersOutcome, GameError>;
 
+/// The most waypoints a go-to order can queue up beyond its destination
+pub const MAX_WAYPOINTS: usize = 8;
+
+/// Where a unit under go-to orders heads next, in turn, once it reaches its destination
+///
+/// Kept inline rather than in a `Vec` so that orders stay `Copy`.
+#[derive(Copy, Clone, Debug, Default, Deserialize, PartialEq, Serialize)]
+pub struct Waypoints {
+    locs: [Option<Location>; MAX_WAYPOINTS],
+}
+
+impl Waypoints {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.locs[0].is_none()
+    }
+
+    pub fn len(&self) -> usize {
+        self.iter().count()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = Location> + '_ {
+        self.locs.iter().map_while(|loc| *loc)
+    }
+
+    /// Queue `loc` after the waypoints already held
+    ///
+    /// Returns false, leaving the waypoints as they were, if there's no room for another.
+    pub fn push(&mut self, loc: Location) -> bool {
+        match self.locs.iter_mut().find(|slot| slot.is_none()) {
+            Some(slot) => {
+                *slot = Some(loc);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// The first waypoint and the waypoints after it, or `None` if there are none
+    pub fn split_first(self) -> Option<(Location, Self)> {
+        let first = self.locs[0]?;
+        let mut rest = Self::default();
+        rest.locs[..MAX_WAYPOINTS - 1].copy_from_slice(&self.locs[1..]);
+        Some((first, rest))
+    }
+}
+
 #[derive(Copy, Clone, Debug, Deserialize, PartialEq, Serialize)]
 pub enum Orders {
     Skip,
     Sentry,
-    GoTo { dest: Location },
+    GoTo {
+        dest: Location,
+
+        /// Where to go on to once `dest` is reached
+        #[serde(default)]
+        waypoints: Waypoints,
+    },
     Explore,
+    Escort {
+        target: UnitID,
+    },
+    Attack {
+        target: Location,
+    },
+    Build {
+        improvement: Improvement,
+        turns_remaining: u16,
+    },
 }
 
 impl Orders {
@@ -112,8 +193,22 @@ impl Orders {
                 let unit = game.map.unit_by_id(unit_id).unwrap().clone();
                 Ok(OrdersOutcome::in_progress_without_move(unit, self))
             }
-            Orders::GoTo { dest } => go_to(self, game, player_secret, unit_id, dest),
-            Orders::Explore => explore(self, game, player_secret, unit_id),
+            Orders::GoTo { dest, waypoints } => {
+                go_to(self, game, player_secret, unit_id, dest, waypoints)
+            }
+            Orders::Explore => explore(
+                self,
+                game,
+                player_secret,
+                unit_id,
+                &EnemiesAndUnclaimedCities,
+            ),
+            Orders::Escort { target } => escort(self, game, player_secret, unit_id, target),
+            Orders::Attack { target } => attack(self, game, player_secret, unit_id, target),
+            Orders::Build {
+                improvement,
+                turns_remaining,
+            } => build(game, unit_id, improvement, turns_remaining),
         }
     }
 
@@ -123,23 +218,55 @@ impl Orders {
         match self {
             Orders::Skip => String::from("skipping its turn"),
             Orders::Sentry => String::from("standing sentry"),
-            Orders::GoTo { dest } => {
-                format!("going to {}", dest)
-            }
+            Orders::GoTo { dest, waypoints } => match waypoints.len() {
+                0 => format!("going to {}", dest),
+                1 => format!("going to {}, then one place more", dest),
+                n => format!("going to {}, then {} places more", dest, n),
+            },
             Orders::Explore => String::from("exploring"),
+            Orders::Escort { target } => format!("escorting unit {:?}", target),
+            Orders::Attack { target } => format!("attacking {}", target),
+            Orders::Build {
+                improvement,
+                turns_remaining,
+            } => format!(
+                "building a {} ({} turns left)",
+                improvement.to_string().to_lowercase(),
+                turns_remaining
+            ),
         }
     }
 }
 
+/// Decides which observations made while exploring are worth stopping for
+pub trait InterestingDiscovery {
+    fn is_interesting(&self, explorer: PlayerNum, located_obs: &LocatedObs) -> bool;
+}
+
+/// Newly-seen enemy units and cities, including unclaimed cities
+pub struct EnemiesAndUnclaimedCities;
+impl InterestingDiscovery for EnemiesAndUnclaimedCities {
+    fn is_interesting(&self, explorer: PlayerNum, located_obs: &LocatedObs) -> bool {
+        // Neutral cities count as enemies here
+        located_obs.reveals_enemy_of(explorer)
+    }
+}
+
 /// Keep moving toward the nearest unobserved tile we can see a path
 /// to, until either there is no such tile or we run out of moves
 /// If there are no such tiles then set the unit's orders to None
-pub fn explore(
+///
+/// If a move reveals something `discovery` deems interesting, exploration is interrupted so the unit can be given
+/// new orders.
+pub fn explore<D: InterestingDiscovery>(
     orders: Orders,
     game: &mut Game,
     player_secret: PlayerSecret,
     unit_id: UnitID,
+    discovery: &D,
 ) -> OrdersResult {
+    let player = game.current_player();
+
     // Clone the unit and simulate exploration using the clone
     let mut unit: Unit = game
         .current_player_unit_by_id(unit_id)
@@ -167,28 +294,55 @@ pub fn explore(
             game.wrapping(),
         ) {
             let filter = ObservedReachableByPacifistUnit { unit: &unit };
-            let shortest_paths = shortest_paths(
+            let shortest_paths = shortest_path_astar(
                 observations,
                 unit.loc,
+                goal,
                 &filter,
+                &UnitMovementCost {
+                    unit_type: unit.type_,
+                },
                 game.wrapping(),
                 std::u16::MAX,
             );
 
             // Find the proximate goal that the unit can reach on this turn
-            let mut dist_to_real_goal = shortest_paths.dist[goal];
-            while dist_to_real_goal > unit.moves_remaining() {
+            while shortest_paths.dist[goal] > unit.moves_remaining() {
                 goal = shortest_paths.prev[goal];
-                dist_to_real_goal -= 1;
+            }
+
+            if goal == unit.loc {
+                // The next step costs more movement than the unit has left this turn
+                return Ok(if move_components.is_empty() {
+                    OrdersOutcome::in_progress_without_move(unit, orders)
+                } else {
+                    OrdersOutcome::in_progress_with_move(
+                        unit.clone(),
+                        orders,
+                        Move::new(unit, starting_loc, move_components).unwrap(),
+                    )
+                });
             }
 
             let mut move_ =
                 game.move_unit_by_id_using_filter(player_secret, unit.id, goal, &filter)?;
 
             if move_.moved_successfully() {
+                let interrupted = move_
+                    .observations()
+                    .any(|located_obs| discovery.is_interesting(player, located_obs));
+
                 unit = move_.unit;
 
                 move_components.append(&mut move_.components);
+
+                if interrupted {
+                    return Ok(OrdersOutcome::interrupted_with_move(
+                        unit.clone(),
+                        orders,
+                        Move::new(unit, starting_loc, move_components).unwrap(),
+                    ));
+                }
             } else {
                 panic!("Unit was unexpectedly destroyed during exploration");
             }
@@ -218,66 +372,90 @@ pub fn explore(
 /// So, in all cases, the right thing to do is to go to the observed, accessible tile nearest the
 /// target, going there by way of the shortest route we know of. Once we're there, clear the unit's
 /// orders.
+///
+/// Unobserved tiles are optimistically assumed passable at the lowest cost, so a route through them
+/// counts as known until observations prove otherwise. A route to the destination is kept from turn
+/// to turn, and planned afresh only once what the unit's player observes along the rest of it
+/// changes in a way that bears on the route. So as the unit reveals the terrain ahead it adjusts
+/// course, falling back to the nearest reachable tile if the destination turns out to be out of
+/// reach.
+///
+/// Once the destination is reached, the unit's orders become to go to the first of the `waypoints`, by way of the
+/// rest, and it carries on with whatever moves it has left.
 pub fn go_to(
     orders: Orders,
     game: &mut Game,
     player_secret: PlayerSecret,
     unit_id: UnitID,
     dest: Location,
+    waypoints: Waypoints,
 ) -> OrdersResult {
     if !game.dims().contain(dest) {
         return Err(GameError::MoveError(MoveError::DestinationOutOfBounds {}));
     }
 
-    let (moves_remaining, shortest_paths, src) = {
+    let (moves_remaining, route, src) = {
         let unit = game
             .current_player_unit_by_id(unit_id)
-            .ok_or(GameError::NoSuchUnit { id: unit_id })?;
-
-        let moves_remaining = unit.moves_remaining;
-
-        let filter = PacifistXenophileUnitMovementFilter { unit };
+            .ok_or(GameError::NoSuchUnit { id: unit_id })?
+            .clone();
+
+        let planned = game
+            .go_to_routes
+            .get(&unit_id)
+            .filter(|route| route.dest == dest)
+            .and_then(|route| route.remaining(game, &unit));
+
+        let route = match planned {
+            Some(route) => route,
+            None => {
+                let route = plan_route(game, &unit, dest);
+
+                if route.last().map(|(loc, _)| *loc) == Some(dest) {
+                    game.go_to_routes
+                        .retain(|id, _| game.map.unit_by_id(*id).is_some());
+                    game.go_to_routes.insert(
+                        unit_id,
+                        PlannedRoute {
+                            dest,
+                            steps: route.clone(),
+                        },
+                    );
+                }
 
-        // Shortest paths emanating from the unit's location, allowing inclusion of unobserved tiles.
-        let shortest_paths =
-            shortest_paths(game, unit.loc, &filter, game.wrapping(), std::u16::MAX);
+                route
+            }
+        };
 
-        (moves_remaining, shortest_paths, unit.loc)
+        (unit.moves_remaining, route, unit.loc)
     };
 
     if src == dest {
         return Err(GameError::MoveError(MoveError::ZeroLengthMove));
     }
 
-    // Find the observed tile on the path from source to destination that is nearest to the
-    // destination but also within reach of this unit's limited moves
-    let mut dest2 = dest;
-    loop {
-        if game.current_player_tile(dest2).is_some() {
-            if let Some(dist) = shortest_paths.dist.get(dest2).cloned() {
-                if dist <= moves_remaining {
-                    break;
-                }
-            }
-        }
-
-        dest2 = shortest_paths
-            .prev
-            .get(dest2)
-            .cloned()
-            .ok_or(GameError::MoveError(MoveError::NoRoute {
-                id: unit_id,
-                src,
-                dest,
-            }))?;
+    if route.len() < 2 {
+        // We're as close as we know how to get
+        game.go_to_routes.remove(&unit_id);
+        let unit = game.current_player_unit_by_id(unit_id).unwrap().clone();
+        return Ok(OrdersOutcome::completed_without_move(unit, orders));
     }
-    let dest2 = dest2;
+
+    // Find the observed tile on the route that is nearest to its end but also within reach of this
+    // unit's limited moves
+    let dest2 = route
+        .iter()
+        .rev()
+        .find(|(loc, dist)| *dist <= moves_remaining && game.current_player_tile(*loc).is_some())
+        .map(|(loc, _)| *loc)
+        .unwrap(); // the unit's own location starts the route
 
     if dest2 == src {
         // We aren't going anywhere---the hypothetical route to the destination isn't coming to pass
         //FIXME I'm not sure why this situation arises---why does following the shortest path
         //     not actually lead us to the destination sometimes?
 
+        game.go_to_routes.remove(&unit_id);
         return Err(GameError::MoveError(MoveError::NoRoute {
             id: unit_id,
             src,
@@ -285,7 +463,8 @@ pub fn go_to(
         }));
     }
 
-    game.move_unit_by_id(player_secret, unit_id, dest2)
+    let mut outcome = game
+        .move_unit_by_id(player_secret, unit_id, dest2)
         .map(|move_| {
             let status = if let Some(ending_loc) = move_.ending_loc() {
                 // survived the immediate move
@@ -301,13 +480,297 @@ pub fn go_to(
                 OrdersStatus::InProgress
             };
 
+            if status == OrdersStatus::Completed {
+                game.go_to_routes.remove(&unit_id);
+            }
+
             OrdersOutcome {
                 ordered_unit: game.current_player_unit_by_id(unit_id).unwrap().clone(),
                 orders,
                 move_: Some(move_),
                 status,
             }
+        })?;
+
+    if outcome.status == OrdersStatus::Completed {
+        if let Some((next_dest, rest)) = waypoints.split_first() {
+            let next_orders = Orders::GoTo {
+                dest: next_dest,
+                waypoints: rest,
+            };
+            let player = game.current_player();
+            game.map
+                .set_player_unit_orders(player, unit_id, next_orders)?;
+
+            outcome.orders = next_orders;
+            outcome.status = OrdersStatus::InProgress;
+
+            if outcome.ordered_unit.moves_remaining() > 0 && next_dest != dest {
+                if let Ok(next_outcome) =
+                    go_to(next_orders, game, player_secret, unit_id, next_dest, rest)
+                {
+                    // Show both legs as one move
+                    let mut components = outcome.move_.take().unwrap().components;
+                    if let Some(next_move) = next_outcome.move_ {
+                        components.extend(next_move.components);
+                    }
+                    outcome = OrdersOutcome {
+                        move_: Some(
+                            Move::new(next_outcome.ordered_unit.clone(), src, components).unwrap(),
+                        ),
+                        ..next_outcome
+                    };
+                }
+            }
+        }
+    }
+
+    Ok(outcome)
+}
+
+/// The route the unit should take toward `dest`, each step with its distance from the unit
+///
+/// If no route to the destination is known, even passing through unobserved tiles, the route leads to the reachable
+/// observed tile nearest to it instead. The route starts with the unit's own location.
+fn plan_route(game: &Game, unit: &Unit, dest: Location) -> Vec<(Location, u16)> {
+    let filter = PacifistXenophileUnitMovementFilter { unit };
+
+    // The shortest path from the unit's location to the destination, allowing inclusion of unobserved tiles. If
+    // there's none, this holds the shortest paths to everywhere the unit can reach.
+    let shortest_paths = shortest_path_astar(
+        game,
+        unit.loc,
+        dest,
+        &filter,
+        &UnitMovementCost {
+            unit_type: unit.type_,
+        },
+        game.wrapping(),
+        std::u16::MAX,
+    );
+
+    let target = if shortest_paths.dist.get(dest).is_some() {
+        dest
+    } else {
+        let steps_to_dest = |loc: Location| {
+            let inc = game.wrapping().wrapped_sub(game.dims(), loc, dest).unwrap();
+            inc.x.unsigned_abs().max(inc.y.unsigned_abs())
+        };
+
+        shortest_paths
+            .dist
+            .iter_locs()
+            .filter(|loc| game.current_player_tile(*loc).is_some())
+            .min_by_key(|loc| (steps_to_dest(*loc), shortest_paths.dist[*loc]))
+            .unwrap() // the unit's own location is always present
+    };
+
+    shortest_paths
+        .shortest_path(target)
+        .unwrap_or_else(|| vec![unit.loc])
+        .into_iter()
+        .map(|loc| (loc, shortest_paths.dist[loc]))
+        .collect()
+}
+
+/// A go-to route planned on an earlier turn, kept until observations along it change
+#[derive(Clone, Debug)]
+pub struct PlannedRoute {
+    dest: Location,
+
+    /// The locations along the route, each with its distance from the start of the route
+    steps: Vec<(Location, u16)>,
+}
+
+impl PlannedRoute {
+    /// The rest of the route from where `unit` now stands, with distances from there
+    ///
+    /// None if the unit has strayed from the route, or if what its player now observes of any step yet to be taken
+    /// would bar the unit from it or change what it costs to enter.
+    fn remaining(&self, game: &Game, unit: &Unit) -> Option<Vec<(Location, u16)>> {
+        let start = self.steps.iter().position(|(loc, _)| *loc == unit.loc)?;
+        let steps = &self.steps[start..];
+
+        let filter = PacifistXenophileUnitMovementFilter { unit };
+        let cost = UnitMovementCost {
+            unit_type: unit.type_,
+        };
+
+        let unchanged = steps.windows(2).all(|pair| {
+            let ((_, prev_dist), (loc, dist)) = (pair[0], pair[1]);
+            Source::<Obs>::get(game, loc).map_or(false, |obs| {
+                filter.include(obs) && prev_dist.saturating_add(cost.cost(obs)) == dist
+            })
+        });
+
+        let base = steps[0].1;
+        unchanged.then(|| {
+            steps
+                .iter()
+                .map(|(loc, dist)| (*loc, dist - base))
+                .collect()
         })
+    }
+}
+
+/// Move to stay adjacent to the unit `target`, wherever it goes
+///
+/// A carried target is escorted by staying next to its carrier. If the target can't be found, it is presumed
+/// destroyed and the orders are complete.
+pub fn escort(
+    orders: Orders,
+    game: &mut Game,
+    player_secret: PlayerSecret,
+    unit_id: UnitID,
+    target: UnitID,
+) -> OrdersResult {
+    let unit = game
+        .current_player_unit_by_id(unit_id)
+        .ok_or(GameError::NoSuchUnit { id: unit_id })?
+        .clone();
+
+    let target_loc = match game.current_player_unit_by_id(target) {
+        Some(target_unit) if target != unit_id => target_unit.loc,
+        _ => return Ok(OrdersOutcome::completed_without_move(unit, orders)),
+    };
+
+    if adjacent(game, unit.loc, target_loc) {
+        // Already alongside
+        return Ok(OrdersOutcome::in_progress_without_move(unit, orders));
+    }
+
+    let wrapping = game.wrapping();
+    let dims = game.dims();
+
+    let shortest_paths = shortest_paths_weighted(
+        game,
+        unit.loc,
+        &PacifistXenophileUnitMovementFilter { unit: &unit },
+        &UnitMovementCost {
+            unit_type: unit.type_,
+        },
+        wrapping,
+        std::u16::MAX,
+    );
+
+    // The observed tile next to the target that the unit can get to soonest
+    let dest = RELATIVE_NEIGHBORS
+        .iter()
+        .filter_map(|inc| wrapping.wrapped_add(dims, target_loc, *inc))
+        .filter(|loc| game.current_player_tile(*loc).is_some())
+        .filter_map(|loc| shortest_paths.dist.get(loc).map(|dist| (loc, *dist)))
+        .min_by_key(|(_loc, dist)| *dist)
+        .map(|(loc, _dist)| loc);
+
+    match dest {
+        Some(dest) => go_to(orders, game, player_secret, unit_id, dest).map(|mut outcome| {
+            // Arriving alongside the target doesn't end the escort
+            outcome.status = OrdersStatus::InProgress;
+            outcome
+        }),
+        None => {
+            // No way to the target is known; wait for it to come closer
+            Ok(OrdersOutcome::in_progress_without_move(unit, orders))
+        }
+    }
+}
+
+/// Close with and attack the enemy unit or city at `target`
+///
+/// The unit goes toward the target until it's adjacent, then attacks. The orders are complete after the attack, or
+/// once the target is observed to hold nothing to attack, or when the unit can get no closer to it.
+pub fn attack(
+    orders: Orders,
+    game: &mut Game,
+    player_secret: PlayerSecret,
+    unit_id: UnitID,
+    target: Location,
+) -> OrdersResult {
+    if !game.dims().contain(target) {
+        return Err(GameError::MoveError(MoveError::DestinationOutOfBounds {}));
+    }
+
+    let unit = game
+        .current_player_unit_by_id(unit_id)
+        .ok_or(GameError::NoSuchUnit { id: unit_id })?
+        .clone();
+
+    if let Some(tile) = game.current_player_tile(target) {
+        let enemy_unit = tile.unit.as_ref().map_or(false, |x| x.is_enemy_of(&unit));
+        let enemy_city = tile.city.as_ref().map_or(false, |x| x.is_enemy_of(&unit));
+        if !enemy_unit && !enemy_city {
+            return Ok(OrdersOutcome::completed_without_move(unit, orders));
+        }
+    }
+
+    if adjacent(game, unit.loc, target) {
+        return game
+            .move_unit_by_id(player_secret, unit_id, target)
+            .map(|move_| {
+                let ordered_unit = move_.unit.clone();
+                OrdersOutcome::completed_with_move(ordered_unit, orders, move_)
+            });
+    }
+
+    // The target itself is off limits to the pacifist route planning of a go-to, so this approaches as near as
+    // it can
+    go_to(orders, game, player_secret, unit_id, target)
+}
+
+/// Put a turn's work into building `improvement` where the unit stands
+///
+/// Work takes the unit's whole turn, so a unit that has already moved this turn starts the following turn. The
+/// improvement is finished once no turns of work remain.
+fn build(
+    game: &mut Game,
+    unit_id: UnitID,
+    improvement: Improvement,
+    turns_remaining: u16,
+) -> OrdersResult {
+    let player = game.current_player();
+
+    let unit = game
+        .current_player_unit_by_id(unit_id)
+        .ok_or(GameError::NoSuchUnit { id: unit_id })?
+        .clone();
+
+    let orders = Orders::Build {
+        improvement,
+        turns_remaining,
+    };
+
+    if unit.moves_remaining() < unit.movement_per_turn() {
+        return Ok(OrdersOutcome::in_progress_without_move(unit, orders));
+    }
+
+    game.map.mark_unit_movement_complete(unit_id)?;
+
+    if turns_remaining > 1 {
+        let orders = Orders::Build {
+            improvement,
+            turns_remaining: turns_remaining - 1,
+        };
+        game.map.set_player_unit_orders(player, unit_id, orders)?;
+        return Ok(OrdersOutcome::in_progress_without_move(unit, orders));
+    }
+
+    if improvement == Improvement::Radar {
+        game.map.build_radar(unit.loc, player)?;
+    } else {
+        game.map.improve_tile(unit.loc, improvement)?;
+    }
+    game.observable_event(unit.loc)?;
+
+    Ok(OrdersOutcome::completed_without_move(unit, orders))
+}
+
+/// Whether `loc` is `other` or one of the locations surrounding it
+fn adjacent(game: &Game, loc: Location, other: Location) -> bool {
+    let inc = game
+        .wrapping()
+        .wrapped_sub(game.dims(), loc, other)
+        .unwrap();
+    inc.x.abs() <= 1 && inc.y.abs() <= 1
 }
 
 pub mod test_support {
@@ -382,10 +845,10 @@ pub mod test {
 
     use crate::{
         game::{
-            alignment::AlignedMaybe,
+            alignment::{AlignedMaybe, Alignment},
             map::MapData,
             unit::{
-                orders::{test_support, Orders},
+                orders::{test_support, Orders, Waypoints, MAX_WAYPOINTS},
                 UnitID,
             },
             Game, GameError, MoveError,
@@ -435,6 +898,9 @@ pub mod test {
         assert!(result3.is_ok());
         assert_eq!(result3.unwrap().status, OrdersStatus::InProgress);
 
+        let route = game.go_to_routes[&id].steps.clone();
+        assert_eq!(route.len(), 6);
+
         // Wait while the go-to order is carried out
         while game.current_player_unit_orders_requests().next().is_none() {
             let turn_start = game
@@ -442,6 +908,11 @@ pub mod test {
                 .unwrap();
             assert_eq!(turn_start.current_player, 0);
 
+            // Nothing along the way changes, so the route planned at first is followed throughout
+            if let Some(planned) = game.go_to_routes.get(&id) {
+                assert_eq!(planned.steps, route);
+            }
+
             match turn_start.orders_results.len() {
                 0 | 1 => { /* do nothing */ }
                 _ => panic!("Infantry shouldn't move more than 1 per turn"),
@@ -452,6 +923,7 @@ pub mod test {
         }
 
         assert_eq!(game.turn(), 5);
+        assert!(!game.go_to_routes.contains_key(&id));
 
         let unit = game.current_player_unit_by_id(id).unwrap();
         assert!(!unit.has_orders());
@@ -468,6 +940,174 @@ pub mod test {
             .any(|x| x == unit.id));
     }
 
+    #[test]
+    fn test_go_to_waypoints() {
+        let map = MapData::try_from("i----------").unwrap();
+        let (mut game, secrets) =
+            Game::new_with_map(None, false, map, 1, false, None, Wrap2d::NEITHER);
+
+        game.begin_turn(secrets[0], false).unwrap();
+
+        let id: UnitID = game.current_player_unit_orders_requests().next().unwrap();
+
+        // Out to the third tile, then back home
+        let mut waypoints = Waypoints::new();
+        assert!(waypoints.push(Location::new(0, 0)));
+        assert_eq!(waypoints.len(), 1);
+
+        let outcome = game
+            .set_and_follow_orders(
+                secrets[0],
+                id,
+                Orders::GoTo {
+                    dest: Location::new(2, 0),
+                    waypoints,
+                },
+            )
+            .unwrap();
+        assert_eq!(outcome.status, OrdersStatus::InProgress);
+        assert_eq!(outcome.ordered_unit.loc, Location::new(1, 0));
+
+        let mut headed_home = false;
+        while game.current_player_unit_orders_requests().next().is_none() {
+            game.end_then_begin_turn(secrets[0], secrets[0], false)
+                .unwrap();
+
+            let unit = game.current_player_unit_by_id(id).unwrap();
+            if unit.orders
+                == Some(Orders::GoTo {
+                    dest: Location::new(0, 0),
+                    waypoints: Waypoints::new(),
+                })
+            {
+                headed_home = true;
+            }
+
+            // Make sure we don't go on too long
+            assert!(game.turn() < 10);
+        }
+
+        assert!(headed_home);
+
+        let unit = game.current_player_unit_by_id(id).unwrap();
+        assert_eq!(unit.loc, Location::new(0, 0));
+        assert!(!unit.has_orders());
+    }
+
+    #[test]
+    fn test_waypoints() {
+        let mut waypoints = Waypoints::new();
+        assert!(waypoints.is_empty());
+        assert_eq!(waypoints.split_first(), None);
+
+        for x in 0..MAX_WAYPOINTS as u16 {
+            assert!(waypoints.push(Location::new(x, 0)));
+        }
+        assert!(!waypoints.push(Location::new(99, 0)));
+        assert_eq!(waypoints.len(), MAX_WAYPOINTS);
+
+        let (first, rest) = waypoints.split_first().unwrap();
+        assert_eq!(first, Location::new(0, 0));
+        assert_eq!(rest.len(), MAX_WAYPOINTS - 1);
+        assert_eq!(rest.iter().next(), Some(Location::new(1, 0)));
+    }
+
+    #[test]
+    fn test_go_to_unobserved() {
+        // The destination lies across water the infantry can't yet see
+        let map = MapData::try_from("i----   --").unwrap();
+        let (mut game, secrets) =
+            Game::new_with_map(None, false, map, 1, true, None, Wrap2d::NEITHER);
+
+        game.begin_turn(secrets[0], false).unwrap();
+
+        let id: UnitID = game.current_player_unit_orders_requests().next().unwrap();
+
+        let dest = Location::new(8, 0);
+        assert!(game.current_player_tile(dest).is_none());
+
+        let outcome = game.order_unit_go_to(secrets[0], id, dest).unwrap();
+        assert_eq!(outcome.status, OrdersStatus::InProgress);
+        assert_eq!(outcome.ordered_unit.loc, Location::new(1, 0));
+
+        // Once the water comes into view the infantry settles for the shore
+        let mut outcome = outcome;
+        while game.current_player_unit_orders_requests().next().is_none() {
+            let mut turn_start = game
+                .end_then_begin_turn(secrets[0], secrets[0], false)
+                .unwrap();
+            outcome = turn_start.orders_results.pop().unwrap().unwrap();
+
+            // Make sure we don't go on too long
+            assert!(game.turn() < 10);
+        }
+
+        assert_eq!(outcome.status, OrdersStatus::Completed);
+
+        let unit = game.current_player_unit_by_id(id).unwrap();
+        assert_eq!(unit.loc, Location::new(4, 0));
+        assert!(!unit.has_orders());
+    }
+
+    #[test]
+    fn test_escort() {
+        let map = MapData::try_from("i----i----").unwrap();
+        let (mut game, secrets) =
+            Game::new_with_map(None, false, map, 1, false, None, Wrap2d::NEITHER);
+
+        game.begin_turn(secrets[0], false).unwrap();
+
+        let escort_id = game
+            .current_player_toplevel_unit_by_loc(Location::new(0, 0))
+            .unwrap()
+            .id;
+        let target_id = game
+            .current_player_toplevel_unit_by_loc(Location::new(5, 0))
+            .unwrap()
+            .id;
+
+        game.order_unit_sentry(secrets[0], target_id).unwrap();
+
+        let outcome = game
+            .order_unit_escort(secrets[0], escort_id, target_id)
+            .unwrap();
+        assert_eq!(outcome.status, OrdersStatus::InProgress);
+        assert_eq!(outcome.ordered_unit.loc, Location::new(1, 0));
+
+        // The escort catches up with the target, then stays put alongside it
+        for _ in 0..5 {
+            let turn_start = game
+                .end_then_begin_turn(secrets[0], secrets[0], false)
+                .unwrap();
+            for orders_result in turn_start.orders_results {
+                assert_eq!(orders_result.unwrap().status, OrdersStatus::InProgress);
+            }
+        }
+
+        let escort = game.current_player_unit_by_id(escort_id).unwrap();
+        assert_eq!(escort.loc, Location::new(4, 0));
+        assert_eq!(escort.orders, Some(Orders::Escort { target: target_id }));
+
+        // With the target gone, the escort awaits new orders
+        game.disband_unit_by_id(secrets[0], target_id).unwrap();
+
+        let turn_start = game
+            .end_then_begin_turn(secrets[0], secrets[0], false)
+            .unwrap();
+        assert_eq!(turn_start.orders_results.len(), 1);
+        let outcome = turn_start.orders_results[0].as_ref().unwrap();
+        assert_eq!(outcome.status, OrdersStatus::Completed);
+        assert!(outcome.move_.is_none());
+
+        assert!(!game
+            .current_player_unit_by_id(escort_id)
+            .unwrap()
+            .has_orders());
+        assert!(game
+            .current_player_unit_orders_requests()
+            .any(|id| id == escort_id));
+    }
+
     #[test]
     pub fn test_explore() {
         test_support::test_explore(Dims::new(10, 10));
@@ -475,6 +1115,42 @@ pub mod test {
         test_support::test_explore(Dims::new(100, 100));
     }
 
+    #[test]
+    fn test_explore_interrupted_by_discovery() {
+        let mut map = MapData::try_from("i---------").unwrap();
+        map.new_city(Location::new(8, 0), Alignment::Neutral, "Zanzibar")
+            .unwrap();
+
+        let (mut game, secrets) =
+            Game::new_with_map(None, false, map, 1, true, None, Wrap2d::NEITHER);
+
+        game.begin_turn(secrets[0], false).unwrap();
+
+        let unit_id: UnitID = game.current_player_unit_orders_requests().next().unwrap();
+
+        let mut outcome = game.order_unit_explore(secrets[0], unit_id).unwrap();
+        while outcome.status == OrdersStatus::InProgress {
+            let mut turn_start = game
+                .end_then_begin_turn(secrets[0], secrets[0], false)
+                .unwrap();
+            outcome = turn_start.orders_results.pop().unwrap().unwrap();
+
+            // Make sure we don't go on too long
+            assert!(game.turn() < 10);
+        }
+
+        // The city comes into the infantry's sight range of 2 once it reaches x=6
+        assert_eq!(outcome.status, OrdersStatus::Interrupted);
+        assert_eq!(outcome.ordered_unit.loc, Location::new(6, 0));
+
+        let unit = game.current_player_unit_by_id(unit_id).unwrap();
+        assert_eq!(unit.loc, Location::new(6, 0));
+        assert!(!unit.has_orders());
+        assert!(game
+            .current_player_unit_orders_requests()
+            .any(|id| id == unit_id));
+    }
+
     #[test]
     fn test_propose_exploration() {
         let map = MapData::try_from("i--------------------").unwrap();
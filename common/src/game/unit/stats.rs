@@ -0,0 +1,149 @@
+//! Unit type stats, loadable from TOML
+//!
+//! The stats built into the game are those in `data/units.toml`. A modded stats file need only list the unit types
+//! and fields it changes; everything it leaves out keeps its built-in value.
+
+use std::{collections::HashMap, fs, path::Path, sync::OnceLock};
+
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use super::{TransportMode, UnitType};
+
+const DEFAULT_UNIT_STATS: &str = include_str!("../../../../data/units.toml");
+
+static UNIT_STATS: OnceLock<UnitStatsRegistry> = OnceLock::new();
+
+/// The rebalanceable characteristics of a unit type
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct UnitStats {
+    pub max_hp: u16,
+
+    /// The number of turns a city must dedicate its production to the unit type to produce a single unit of that type
+    pub cost: u16,
+
+    pub movement_per_turn: u16,
+
+    pub sight_distance: u16,
+
+    /// Whether the unit type goes by land, sea, or air, which decides the terrain it can cross
+    pub transport_mode: TransportMode,
+
+    /// The movement points spent entering forest
+    pub forest_cost: u16,
+
+    /// The movement points spent entering mountains
+    pub mountains_cost: u16,
+}
+
+#[derive(Debug, Error)]
+pub enum UnitStatsError {
+    #[error("Error reading unit stats file: {0}")]
+    Io(#[from] std::io::Error),
+
+    #[error("Error parsing unit stats: {0}")]
+    Parse(#[from] toml::de::Error),
+
+    #[error("Stats were given for {type_name}, which isn't a unit type")]
+    UnknownUnitType { type_name: String },
+}
+
+/// The stats of every unit type
+#[derive(Clone, Debug, PartialEq)]
+pub struct UnitStatsRegistry {
+    stats: HashMap<UnitType, UnitStats>,
+}
+
+impl UnitStatsRegistry {
+    /// The built-in stats, as modified by the TOML `overrides`
+    pub fn from_toml(overrides: &str) -> Result<Self, UnitStatsError> {
+        let mut table: toml::Table = DEFAULT_UNIT_STATS.parse()?;
+        let overrides: toml::Table = overrides.parse()?;
+
+        for (type_name, fields) in overrides {
+            match (table.get_mut(&type_name), fields) {
+                (Some(toml::Value::Table(stats)), toml::Value::Table(fields)) => {
+                    stats.extend(fields);
+                }
+                _ => return Err(UnitStatsError::UnknownUnitType { type_name }),
+            }
+        }
+
+        Ok(Self {
+            stats: toml::Value::Table(table).try_into()?,
+        })
+    }
+
+    /// Read a stats file in the format of `data/units.toml`
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self, UnitStatsError> {
+        Self::from_toml(fs::read_to_string(path)?.as_str())
+    }
+
+    pub fn get(&self, type_: UnitType) -> &UnitStats {
+        &self.stats[&type_]
+    }
+}
+
+impl Default for UnitStatsRegistry {
+    fn default() -> Self {
+        Self::from_toml("").unwrap()
+    }
+}
+
+/// Make `registry` the source of unit stats for the rest of the run
+///
+/// Must come before any stats are looked up; otherwise the registry is handed back.
+pub fn install(registry: UnitStatsRegistry) -> Result<(), UnitStatsRegistry> {
+    UNIT_STATS.set(registry)
+}
+
+/// The unit stats in force: the built-in ones unless others were installed
+pub fn registry() -> &'static UnitStatsRegistry {
+    UNIT_STATS.get_or_init(UnitStatsRegistry::default)
+}
+
+#[cfg(test)]
+mod test {
+    use crate::game::unit::{TransportMode, UnitType};
+
+    use super::{UnitStatsError, UnitStatsRegistry};
+
+    #[test]
+    fn test_unit_stats_registry() {
+        let defaults = UnitStatsRegistry::default();
+        for type_ in UnitType::values() {
+            assert!(defaults.get(type_).max_hp > 0);
+        }
+        assert_eq!(defaults.get(UnitType::Armor).cost, 11);
+        assert_eq!(defaults.get(UnitType::Armor).forest_cost, 2);
+        assert_eq!(defaults.get(UnitType::Armor).mountains_cost, 2);
+
+        // Fields left out keep their built-in values
+        let modded = UnitStatsRegistry::from_toml(
+            "[Armor]\n\
+             cost = 9\n\
+             [Fighter]\n\
+             transport_mode = \"Land\"\n",
+        )
+        .unwrap();
+        assert_eq!(modded.get(UnitType::Armor).cost, 9);
+        assert_eq!(modded.get(UnitType::Armor).max_hp, 2);
+        assert_eq!(
+            modded.get(UnitType::Fighter).transport_mode,
+            TransportMode::Land
+        );
+        assert_eq!(
+            modded.get(UnitType::Infantry),
+            defaults.get(UnitType::Infantry)
+        );
+
+        assert!(matches!(
+            UnitStatsRegistry::from_toml("[Dragon]\ncost = 1\n"),
+            Err(UnitStatsError::UnknownUnitType { type_name }) if type_name == "Dragon"
+        ));
+        assert!(matches!(
+            UnitStatsRegistry::from_toml("[Armor]\ncost = \"cheap\"\n"),
+            Err(UnitStatsError::Parse(_))
+        ));
+    }
+}
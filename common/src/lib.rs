@@ -10,6 +10,7 @@ pub mod cli;
 pub mod colors;
 pub mod conf;
 pub mod game;
+pub mod lobby;
 pub mod log;
 pub mod name;
 pub mod rpc;
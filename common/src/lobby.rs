@@ -0,0 +1,231 @@
+//! Where players gather before a networked game starts
+//!
+//! Each human player's slot must be claimed by a client and readied before the game begins. The AIs, played on the
+//! server, are always ready.
+
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    cli::Specified,
+    game::{error::GameError, PlayerNum, PlayerType, UmpireResult},
+};
+
+/// Identifies one of the games hosted by a server
+pub type GameID = u64;
+
+/// A player's place in a game yet to start
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct LobbySlot {
+    pub player_type: PlayerType,
+
+    /// Whether a client controls the player; AI players count as claimed by the server
+    pub claimed: bool,
+
+    pub ready: bool,
+}
+
+impl fmt::Display for LobbySlot {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.player_type {
+            PlayerType::Human => {
+                let status = if self.ready {
+                    "ready"
+                } else if self.claimed {
+                    "not ready"
+                } else {
+                    "open"
+                };
+                write!(f, "Human ({})", status)
+            }
+            PlayerType::AI(ref ai_type) => write!(f, "AI {}", ai_type.spec()),
+        }
+    }
+}
+
+/// A game waiting on its human players to join
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct Lobby {
+    slots: Vec<LobbySlot>,
+    started: bool,
+}
+
+impl Lobby {
+    pub fn new(player_types: &[PlayerType]) -> Self {
+        let slots: Vec<LobbySlot> = player_types
+            .iter()
+            .map(|player_type| {
+                let ai = *player_type != PlayerType::Human;
+                LobbySlot {
+                    player_type: player_type.clone(),
+                    claimed: ai,
+                    ready: ai,
+                }
+            })
+            .collect();
+
+        // A game with no humans has nobody to wait for
+        let started = slots.iter().all(|slot| slot.ready);
+
+        Self { slots, started }
+    }
+
+    pub fn slots(&self) -> &[LobbySlot] {
+        &self.slots
+    }
+
+    /// Whether every player was ready, starting the game
+    ///
+    /// Once started, a game stays started.
+    pub fn started(&self) -> bool {
+        self.started
+    }
+
+    /// The players whose slots nobody has claimed yet
+    pub fn open_slots(&self) -> impl Iterator<Item = PlayerNum> + '_ {
+        self.slots
+            .iter()
+            .enumerate()
+            .filter(|(_, slot)| !slot.claimed)
+            .map(|(player, _)| player)
+    }
+
+    /// How many players the game is still waiting on
+    pub fn waiting_on(&self) -> usize {
+        self.slots.iter().filter(|slot| !slot.ready).count()
+    }
+
+    /// Claim `player`'s slot, which must be an open human one
+    pub fn claim(&mut self, player: PlayerNum) -> UmpireResult<()> {
+        let slot = self
+            .slots
+            .get_mut(player)
+            .ok_or(GameError::NoSuchPlayer { player })?;
+
+        if slot.claimed {
+            return Err(GameError::PlayerSlotUnavailable { player });
+        }
+
+        slot.claimed = true;
+        Ok(())
+    }
+
+    /// Give up `player`'s claimed human slot, as when the client holding it is lost, leaving it open to be claimed again
+    ///
+    /// The player is no longer ready, though a game already started carries on regardless.
+    pub fn release(&mut self, player: PlayerNum) -> UmpireResult<()> {
+        let slot = self
+            .slots
+            .get_mut(player)
+            .ok_or(GameError::NoSuchPlayer { player })?;
+
+        if slot.player_type != PlayerType::Human {
+            return Err(GameError::PlayerSlotUnavailable { player });
+        }
+        if !slot.claimed {
+            return Err(GameError::PlayerSlotUnclaimed { player });
+        }
+
+        slot.claimed = false;
+        slot.ready = false;
+        Ok(())
+    }
+
+    /// Mark `player`, already claimed, as ready to play
+    ///
+    /// Gives whether this started the game.
+    pub fn ready(&mut self, player: PlayerNum) -> UmpireResult<bool> {
+        let slot = self
+            .slots
+            .get_mut(player)
+            .ok_or(GameError::NoSuchPlayer { player })?;
+
+        if !slot.claimed {
+            return Err(GameError::PlayerSlotUnclaimed { player });
+        }
+
+        slot.ready = true;
+
+        let starting = !self.started && self.waiting_on() == 0;
+        self.started |= starting;
+        Ok(starting)
+    }
+}
+
+impl fmt::Display for Lobby {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for (player, slot) in self.slots.iter().enumerate() {
+            writeln!(f, "Player {}: {}", player, slot)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::game::{ai::AISpec, error::GameError, PlayerType};
+
+    use super::Lobby;
+
+    #[test]
+    fn test_lobby() {
+        let mut lobby = Lobby::new(&[
+            PlayerType::Human,
+            PlayerType::AI(AISpec::Random { seed: None }),
+            PlayerType::Human,
+        ]);
+
+        assert!(!lobby.started());
+        assert_eq!(lobby.open_slots().collect::<Vec<_>>(), vec![0, 2]);
+        assert_eq!(lobby.waiting_on(), 2);
+
+        assert_eq!(
+            lobby.claim(1),
+            Err(GameError::PlayerSlotUnavailable { player: 1 })
+        );
+        assert_eq!(lobby.claim(3), Err(GameError::NoSuchPlayer { player: 3 }));
+        assert_eq!(
+            lobby.ready(0),
+            Err(GameError::PlayerSlotUnclaimed { player: 0 })
+        );
+
+        lobby.claim(0).unwrap();
+        assert_eq!(
+            lobby.claim(0),
+            Err(GameError::PlayerSlotUnavailable { player: 0 })
+        );
+        assert_eq!(lobby.ready(0), Ok(false));
+        assert!(!lobby.started());
+        assert_eq!(lobby.waiting_on(), 1);
+
+        lobby.claim(2).unwrap();
+        assert_eq!(lobby.open_slots().count(), 0);
+        assert_eq!(lobby.ready(2), Ok(true));
+        assert!(lobby.started());
+
+        // Starting happens just once
+        assert_eq!(lobby.ready(2), Ok(false));
+        assert!(lobby.started());
+
+        // Slots given up can be claimed again, even once the game is underway
+        assert_eq!(
+            lobby.release(1),
+            Err(GameError::PlayerSlotUnavailable { player: 1 })
+        );
+        lobby.release(2).unwrap();
+        assert_eq!(
+            lobby.release(2),
+            Err(GameError::PlayerSlotUnclaimed { player: 2 })
+        );
+        assert_eq!(lobby.open_slots().collect::<Vec<_>>(), vec![2]);
+        assert!(lobby.started());
+        lobby.claim(2).unwrap();
+    }
+
+    #[test]
+    fn test_lobby_without_humans() {
+        let lobby = Lobby::new(&[PlayerType::AI(AISpec::Random { seed: None })]);
+        assert!(lobby.started());
+    }
+}
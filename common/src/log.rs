@@ -10,6 +10,7 @@ pub enum MessageSource {
     Game,
     UI,
     Mode,
+    Chat,
 }
 
 /// A loggable message, along with some presentation details such as foreground and background
@@ -1,41 +1,103 @@
 use std::{
     borrow::Cow,
     collections::{BTreeMap, BTreeSet},
+    io::Result as IoResult,
+    net::SocketAddr,
+    time::Duration,
 };
 
 use async_trait::async_trait;
-use tarpc::context;
+use tarpc::{
+    client::{self, RpcError},
+    context,
+};
+use tokio::sync::RwLock as RwLockTokio;
 
 use crate::{
+    conf,
     game::{
         action::{
             AiPlayerAction, NextCityAction, NextUnitAction, PlayerAction, PlayerActionOutcome,
         },
         ai::{fX, TrainingFocus},
+        chat::ChatMessage,
         city::{City, CityID},
+        combat::CombatOdds,
+        delta::{SyncPoint, TurnDelta},
+        diplomacy::DiplomaticStatus,
         error::GameError,
-        map::Tile,
+        history::PlayerHistory,
+        map::{Improvement, Tile},
         move_::Move,
-        obs::{LocatedObsLite, Obs, ObsTracker},
+        obs::{LocatedObsLite, Obs, ObsDiff, ObsRevision, ObsTracker},
         unit::{
             orders::{Orders, OrdersResult},
             Unit, UnitID, UnitType,
         },
-        ActionNum, Game, IGame, OrdersSet, PlayerNum, PlayerSecret, PlayerType, ProductionCleared,
-        ProductionSet, ProposedActionResult, ProposedOrdersResult, ProposedResult, TurnEnded,
-        TurnNum, TurnPhase, TurnStart, UmpireResult, UnitDisbanded,
+        ActionNum, Bombardment, Game, GameOver, IGame, OrdersSet, PlayerNum, PlayerSecret,
+        PlayerType, ProductionCleared, ProductionSet, ProposedActionResult, ProposedOrdersResult,
+        ProposedResult, RadarBuilt, Resigned, TurnEnded, TurnNum, TurnPhase, TurnStart,
+        UmpireResult, UnitDisbanded, WallsBuilt,
     },
+    lobby::{GameID, Lobby},
     util::{Dims, Direction, Location, Wrap2d},
 };
 
+use self::transport::Compression;
+
+pub mod transport;
+
 /// The Umpire RPC interface. The macro generates a client impl called `UmpireRpcClient`.
 #[tarpc::service]
 pub trait UmpireRpc {
-    async fn wait_my_turn() -> PlayerNum;
+    /// Wait for the turn of a player controlled by this connection, giving the player once play isn't held
+    ///
+    /// Gives up after `timeout`, giving `None`, so the call can be made again before the request's deadline passes.
+    async fn wait_my_turn(timeout: Duration) -> Option<PlayerNum>;
+
+    /// Let the server know this connection is still alive
+    ///
+    /// Connections the server doesn't hear from for too long are dropped, giving up the players they controlled.
+    async fn heartbeat();
 
     /// For each player in the game, gives the player secret if the player is controlled by this connection
     async fn player_secrets_known() -> Vec<Option<PlayerSecret>>;
 
+    /// Take control of the human player identified by `player_secret` on this connection, as when resuming a game
+    /// after losing an earlier connection
+    ///
+    /// The player's secret serves as the token proving the player was ours. Gives the player's number.
+    async fn reconnect(player_secret: PlayerSecret) -> UmpireResult<PlayerNum>;
+
+    /// Every game on the server, with who's playing in each and who's ready
+    async fn games() -> Vec<(GameID, Lobby)>;
+
+    /// Make this connection's requests be about game `game_id` from now on
+    ///
+    /// Connections start out in the first game. A connection can't change games once it controls any players.
+    async fn join_game(game_id: GameID) -> UmpireResult<()>;
+
+    /// Register this connection as a spectator of the joined game, so it can watch using `spectator_observations`
+    ///
+    /// Spectators hold no player secrets and can't claim any. A connection already controlling players can't become a
+    /// spectator.
+    async fn spectate() -> UmpireResult<()>;
+
+    /// Who's playing in the joined game and who's ready, as players gather before it starts
+    async fn lobby() -> Lobby;
+
+    /// Wait for the joined game's lobby to differ from `seen`, giving it as it then stands
+    ///
+    /// Gives up after `timeout`, giving the lobby unchanged, so the call can be made again before the request's
+    /// deadline passes.
+    async fn wait_lobby(seen: Lobby, timeout: Duration) -> Lobby;
+
+    /// Take control of human player `player` on this connection, giving the player's secret
+    async fn claim_slot(player: PlayerNum) -> UmpireResult<PlayerSecret>;
+
+    /// Mark every player controlled by this connection as ready to play, giving whether the game has started
+    async fn ready() -> UmpireResult<bool>;
+
     async fn player_types() -> Vec<PlayerType>;
 
     /// The number of players in the game
@@ -56,6 +118,11 @@ pub trait UmpireRpc {
 
     async fn is_player_turn(secret: PlayerSecret) -> UmpireResult<bool>;
 
+    /// How long the player has left to finish their turn, if the server times turns
+    ///
+    /// Once the time runs out the server ends the turn itself, after which this errs.
+    async fn turn_time_remaining(player_secret: PlayerSecret) -> UmpireResult<Option<Duration>>;
+
     async fn end_then_begin_turn(
         player_secret: PlayerSecret,
         next_player_secret: PlayerSecret,
@@ -74,11 +141,22 @@ pub trait UmpireRpc {
     /// has won.
     async fn victor() -> Option<PlayerNum>;
 
+    /// How the game was decided---by conquest, or by score once the turn limit was reached---if it has been
+    async fn game_over() -> Option<GameOver>;
+
+    /// The turn on which the game ends if nobody has won outright by then, if any
+    async fn turn_limit() -> Option<TurnNum>;
+
     async fn player_unit_legal_one_step_destinations(
         player_secret: PlayerSecret,
         unit_id: UnitID,
     ) -> UmpireResult<BTreeSet<Location>>;
 
+    async fn player_unit_reachable_this_turn(
+        player_secret: PlayerSecret,
+        unit_id: UnitID,
+    ) -> UmpireResult<BTreeSet<Location>>;
+
     async fn player_unit_legal_directions(
         player_secret: PlayerSecret,
         unit_id: UnitID,
@@ -90,6 +168,26 @@ pub trait UmpireRpc {
 
     async fn player_observations(player_secret: PlayerSecret) -> UmpireResult<ObsTracker>;
 
+    /// What a spectator sees of the game from `viewer`'s point of view, or the whole map if `None`
+    ///
+    /// Only for connections registered using `spectate`. The server may hold the view some turns behind the game.
+    async fn spectator_observations(viewer: Option<PlayerNum>) -> UmpireResult<ObsTracker>;
+
+    /// The changes to the player's observations since `revision` of them
+    async fn player_observations_since(
+        player_secret: PlayerSecret,
+        revision: ObsRevision,
+    ) -> UmpireResult<ObsDiff>;
+
+    /// What changed for the player since `since`, or everything if `since` is `None`
+    ///
+    /// The changes are worked out against what was last sent on this connection; a `since` from another connection
+    /// gets everything.
+    async fn player_turn_delta(
+        player_secret: PlayerSecret,
+        since: Option<SyncPoint>,
+    ) -> UmpireResult<TurnDelta>;
+
     /// Every city controlled by the player whose secret is provided
     async fn player_cities(player_secret: PlayerSecret) -> UmpireResult<Vec<City>>;
 
@@ -213,6 +311,96 @@ pub trait UmpireRpc {
         dest: Location,
     ) -> ProposedResult<Move, GameError>;
 
+    async fn combat_odds(
+        player_secret: PlayerSecret,
+        attacker_id: UnitID,
+        defender_loc: Location,
+    ) -> UmpireResult<CombatOdds>;
+
+    async fn load_unit(
+        player_secret: PlayerSecret,
+        unit_id: UnitID,
+        carrier_id: UnitID,
+    ) -> UmpireResult<Move>;
+
+    async fn unload_unit(
+        player_secret: PlayerSecret,
+        unit_id: UnitID,
+        dest: Location,
+    ) -> UmpireResult<Move>;
+
+    async fn rebase_unit(
+        player_secret: PlayerSecret,
+        unit_id: UnitID,
+        base: Location,
+    ) -> UmpireResult<Move>;
+
+    async fn player_air_bases(
+        player_secret: PlayerSecret,
+        unit_id: UnitID,
+    ) -> UmpireResult<Vec<Location>>;
+
+    async fn bombard(
+        player_secret: PlayerSecret,
+        unit_id: UnitID,
+        target: Location,
+    ) -> UmpireResult<Bombardment>;
+
+    async fn player_unit_bombard_targets(
+        player_secret: PlayerSecret,
+        unit_id: UnitID,
+    ) -> UmpireResult<Vec<Location>>;
+
+    async fn paradrop(
+        player_secret: PlayerSecret,
+        unit_id: UnitID,
+        dest: Location,
+    ) -> UmpireResult<Move>;
+
+    async fn player_unit_drop_zones(
+        player_secret: PlayerSecret,
+        unit_id: UnitID,
+    ) -> UmpireResult<Vec<Location>>;
+
+    async fn lay_mines(player_secret: PlayerSecret, unit_id: UnitID) -> UmpireResult<Location>;
+
+    async fn sweep_mines(
+        player_secret: PlayerSecret,
+        unit_id: UnitID,
+    ) -> UmpireResult<Vec<Location>>;
+
+    async fn player_known_minefields(player_secret: PlayerSecret) -> UmpireResult<Vec<Location>>;
+
+    async fn diplomatic_status(
+        player_secret: PlayerSecret,
+        other: PlayerNum,
+    ) -> UmpireResult<DiplomaticStatus>;
+
+    async fn treaty_proposals(
+        player_secret: PlayerSecret,
+    ) -> UmpireResult<Vec<(PlayerNum, DiplomaticStatus)>>;
+
+    async fn propose_treaty(
+        player_secret: PlayerSecret,
+        other: PlayerNum,
+        status: DiplomaticStatus,
+    ) -> UmpireResult<()>;
+
+    async fn accept_treaty(
+        player_secret: PlayerSecret,
+        other: PlayerNum,
+    ) -> UmpireResult<DiplomaticStatus>;
+
+    async fn declare_war(
+        player_secret: PlayerSecret,
+        other: PlayerNum,
+    ) -> UmpireResult<DiplomaticStatus>;
+
+    /// Withdraw from the game: the player's cities become neutral and its units are disbanded
+    ///
+    /// Must be player's turn
+    async fn resign(player_secret: PlayerSecret) -> UmpireResult<Resigned>;
+
     async fn disband_unit_by_id(
         player_secret: PlayerSecret,
         id: UnitID,
@@ -236,6 +424,19 @@ pub trait UmpireRpc {
         production: UnitType,
     ) -> UmpireResult<ProductionSet>;
 
+    /// Spends some of the accumulated production of the current player's city with ID `city_id` on another level of
+    /// walls.
+    async fn build_city_walls(
+        player_secret: PlayerSecret,
+        city_id: CityID,
+    ) -> UmpireResult<WallsBuilt>;
+
+    /// Spends some of the accumulated production of the current player's city with ID `city_id` on a radar.
+    async fn build_city_radar(
+        player_secret: PlayerSecret,
+        city_id: CityID,
+    ) -> UmpireResult<RadarBuilt>;
+
     async fn clear_production(
         player_secret: PlayerSecret,
         loc: Location,
@@ -255,6 +456,9 @@ pub trait UmpireRpc {
 
     async fn current_player() -> PlayerNum;
 
+    /// Whether play is held, so no new turn can begin
+    async fn paused() -> bool;
+
     /// The logical dimensions of the game map
     async fn dims() -> Dims;
 
@@ -305,6 +509,53 @@ pub trait UmpireRpc {
         unit_id: UnitID,
     ) -> ProposedOrdersResult;
 
+    /// Order the specified unit to stay alongside the unit `target`
+    async fn order_unit_escort(
+        player_secret: PlayerSecret,
+        unit_id: UnitID,
+        target: UnitID,
+    ) -> OrdersResult;
+
+    /// Simulate ordering the specified unit to escort the unit `target`
+    async fn propose_order_unit_escort(
+        player_secret: PlayerSecret,
+        unit_id: UnitID,
+        target: UnitID,
+    ) -> ProposedOrdersResult;
+
+    /// Order the specified unit to build `improvement` where it stands
+    async fn order_unit_build(
+        player_secret: PlayerSecret,
+        unit_id: UnitID,
+        improvement: Improvement,
+    ) -> OrdersResult;
+
+    /// Simulate ordering the specified unit to build `improvement` where it stands
+    async fn propose_order_unit_build(
+        player_secret: PlayerSecret,
+        unit_id: UnitID,
+        improvement: Improvement,
+    ) -> ProposedOrdersResult;
+
+    async fn unit_groups(player_secret: PlayerSecret) -> UmpireResult<Vec<(String, Vec<UnitID>)>>;
+
+    async fn form_unit_group(
+        player_secret: PlayerSecret,
+        name: String,
+        unit_ids: Vec<UnitID>,
+    ) -> UmpireResult<()>;
+
+    async fn disband_unit_group(
+        player_secret: PlayerSecret,
+        name: String,
+    ) -> UmpireResult<Vec<UnitID>>;
+
+    async fn order_unit_group(
+        player_secret: PlayerSecret,
+        name: String,
+        orders: Orders,
+    ) -> UmpireResult<Vec<OrdersResult>>;
+
     /// If a unit at the location owned by the current player exists, activate it and any units it carries
     async fn activate_unit_by_loc(
         player_secret: PlayerSecret,
@@ -339,6 +590,28 @@ pub trait UmpireRpc {
 
     async fn player_scores() -> Vec<f64>;
 
+    async fn player_histories() -> Vec<PlayerHistory>;
+
+    async fn send_chat_message(player_secret: PlayerSecret, text: String) -> UmpireResult<()>;
+
+    async fn chat_messages_since(
+        player_secret: PlayerSecret,
+        since: usize,
+    ) -> UmpireResult<Vec<ChatMessage>>;
+
+    /// Wait for chat messages beyond the first `since` of them, giving them as soon as there are any
+    ///
+    /// Gives up after `timeout`, giving no messages, so the call can be made again before the request's deadline
+    /// passes.
+    async fn wait_chat_messages(
+        player_secret: PlayerSecret,
+        since: usize,
+        timeout: Duration,
+    ) -> UmpireResult<Vec<ChatMessage>>;
+
+    /// Go by `name` in the chat, or by player number alone if `name` is blank
+    async fn set_player_name(player_secret: PlayerSecret, name: String) -> UmpireResult<()>;
+
     async fn take_simple_action(
         player_secret: PlayerSecret,
         action: AiPlayerAction,
@@ -357,30 +630,202 @@ pub trait UmpireRpc {
     async fn player_features(
         player_secret: PlayerSecret,
         focus: TrainingFocus,
+        window: usize,
     ) -> UmpireResult<Vec<fX>>;
 }
 
-pub struct RpcGame {
+/// Connect to the server at `server_addr`, asking for payloads to be compressed as `compression` says
+///
+/// Heartbeats are sent on the connection in the background until it's lost.
+pub async fn connect(
+    server_addr: SocketAddr,
+    compression: Compression,
+) -> IoResult<UmpireRpcClient> {
+    let transport = transport::connect(server_addr, compression).await?;
+    let client = UmpireRpcClient::new(client::Config::default(), transport).spawn();
+
+    tokio::spawn(keep_alive(client.clone()));
+
+    Ok(client)
+}
+
+/// Send a heartbeat to the server every `conf::HEARTBEAT_INTERVAL` until one fails to go through
+async fn keep_alive(client: UmpireRpcClient) {
+    loop {
+        tokio::time::sleep(conf::HEARTBEAT_INTERVAL).await;
+
+        if client.heartbeat(context::current()).await.is_err() {
+            break;
+        }
+    }
+}
+
+/// Call `$method` on the server, reconnecting and trying again if the connection is lost
+///
+/// Only for reads: the call may have reached the server before the connection dropped, so it may be made twice. The
+/// arguments are evaluated anew for each attempt. Returns from the enclosing function if we can't reconnect.
+macro_rules! call {
+    ($self:ident, $method:ident ( $($arg:expr),* $(,)? )) => {
+        loop {
+            let (game, generation) = $self.connection().await;
+            match game.$method(context::current(), $($arg),*).await {
+                Ok(result) => break result,
+                Err(err) => $self.reconnect(generation, err).await?,
+            }
+        }
+    };
+}
+
+/// Call `$method` on the server once, reconnecting but not trying again if the connection is lost
+///
+/// For calls that change the game: the server may have made the change before the connection dropped, so making the
+/// call again could make it twice. The caller gets `GameError::CallInterrupted` and can check the game to find out.
+macro_rules! call_once {
+    ($self:ident, $method:ident ( $($arg:expr),* $(,)? )) => {{
+        let (game, generation) = $self.connection().await;
+        match game.$method(context::current(), $($arg),*).await {
+            Ok(result) => result,
+            Err(err) => {
+                $self.reconnect(generation, err).await?;
+                Err(GameError::CallInterrupted)
+            }
+        }
+    }};
+}
+
+/// A connection to the server, and how many connections came before it
+struct Connection {
     game: UmpireRpcClient,
+    generation: u64,
+}
+
+/// A game played on a server
+///
+/// If the connection drops, a new one is made and our players are reclaimed on it, so a blip in the network doesn't
+/// end the game. The game state, observations included, stays on the server throughout, so nothing is lost. Reads
+/// interrupted by the drop are made again; changes to the game are not, as they may already have been made, and fail
+/// with `GameError::CallInterrupted` instead.
+pub struct RpcGame {
+    connection: RwLockTokio<Connection>,
+    server_addr: SocketAddr,
+
+    /// The compression asked for when reconnecting
+    compression: Compression,
+
+    /// The game on the server we're playing
+    game_id: GameID,
+
+    /// The secrets of the players we control, as given by `player_secrets_known`
+    secrets: Vec<Option<PlayerSecret>>,
 }
 
 impl RpcGame {
-    pub fn new(game: UmpireRpcClient) -> Self {
-        Self { game }
+    pub fn new(
+        game: UmpireRpcClient,
+        server_addr: SocketAddr,
+        compression: Compression,
+        game_id: GameID,
+        secrets: Vec<Option<PlayerSecret>>,
+    ) -> Self {
+        Self {
+            connection: RwLockTokio::new(Connection {
+                game,
+                generation: 0,
+            }),
+            server_addr,
+            compression,
+            game_id,
+            secrets,
+        }
+    }
+
+    async fn connection(&self) -> (UmpireRpcClient, u64) {
+        let connection = self.connection.read().await;
+        (connection.game.clone(), connection.generation)
+    }
+
+    /// Replace connection number `generation`, lost with error `err`, by a new one controlling the same players
+    ///
+    /// Attempts are made with exponential backoff. If some other call already replaced the connection, nothing more
+    /// is done.
+    ///
+    /// Fails if `err` isn't one a new connection would fix, or if the server can't be reached again.
+    async fn reconnect(&self, generation: u64, err: RpcError) -> UmpireResult<()> {
+        if !matches!(
+            err,
+            RpcError::Shutdown | RpcError::Send(_) | RpcError::Receive(_)
+        ) {
+            return Err(GameError::Rpc {
+                reason: format!("{} (server {})", err, self.server_addr),
+            });
+        }
+
+        let mut connection = self.connection.write().await;
+        if connection.generation != generation {
+            return Ok(());
+        }
+
+        let mut backoff = conf::RECONNECT_INITIAL_BACKOFF;
+        for _ in 0..conf::RECONNECT_ATTEMPTS {
+            tokio::time::sleep(backoff).await;
+            backoff *= 2;
+
+            if let Ok(game) = connect(self.server_addr, self.compression).await {
+                if self.reclaim_players(&game).await {
+                    connection.game = game;
+                    connection.generation += 1;
+                    return Ok(());
+                }
+            }
+        }
+
+        Err(GameError::ServerUnreachable {
+            reason: format!("{} (server {})", err, self.server_addr),
+        })
+    }
+
+    /// Take control of our players on the new connection `game`, returning whether all of them were reclaimed
+    async fn reclaim_players(&self, game: &UmpireRpcClient) -> bool {
+        if !matches!(
+            game.join_game(context::current(), self.game_id).await,
+            Ok(Ok(()))
+        ) {
+            return false;
+        }
+
+        // Controlling no players, we must be watching
+        if self.secrets.iter().all(Option::is_none) {
+            return matches!(game.spectate(context::current()).await, Ok(Ok(())));
+        }
+
+        for secret in self.secrets.iter().flatten() {
+            if !matches!(game.reconnect(context::current(), *secret).await, Ok(Ok(_))) {
+                return false;
+            }
+        }
+        true
     }
 }
 
 #[async_trait]
 impl IGame for RpcGame {
     async fn is_player_turn(&self, secret: PlayerSecret) -> UmpireResult<bool> {
-        self.game
-            .is_player_turn(context::current(), secret)
-            .await
-            .unwrap()
+        call!(self, is_player_turn(secret))
+    }
+
+    async fn turn_time_remaining(
+        &self,
+        player_secret: PlayerSecret,
+    ) -> UmpireResult<Option<Duration>> {
+        call!(self, turn_time_remaining(player_secret))
+    }
+
+    async fn wait_my_turn(&self, timeout: Duration) -> UmpireResult<Option<PlayerNum>> {
+        Ok(call!(self, wait_my_turn(timeout)))
     }
 
-    async fn num_players(&self) -> PlayerNum {
-        self.game.num_players(context::current()).await.unwrap()
+    async fn num_players(&self) -> UmpireResult<PlayerNum> {
+        Ok(call!(self, num_players()))
     }
 
     async fn begin_turn(
@@ -388,46 +833,35 @@ impl IGame for RpcGame {
         player_secret: PlayerSecret,
         clear_after_unit_production: bool,
     ) -> UmpireResult<TurnStart> {
-        self.game
-            .begin_turn(
-                context::current(),
-                player_secret,
-                clear_after_unit_production,
-            )
-            .await
-            .unwrap()
+        call_once!(self, begin_turn(player_secret, clear_after_unit_production))
     }
 
     async fn turn_is_done(&self, player: PlayerNum, turn: TurnNum) -> UmpireResult<bool> {
-        self.game
-            .turn_is_done(context::current(), player, turn)
-            .await
-            .unwrap()
+        call!(self, turn_is_done(player, turn))
     }
 
-    async fn current_turn_is_done(&self) -> bool {
-        self.game
-            .current_turn_is_done(context::current())
-            .await
-            .unwrap()
+    async fn current_turn_is_done(&self) -> UmpireResult<bool> {
+        Ok(call!(self, current_turn_is_done()))
     }
 
-    async fn victor(&self) -> Option<PlayerNum> {
-        self.game.victor(context::current()).await.unwrap()
+    async fn victor(&self) -> UmpireResult<Option<PlayerNum>> {
+        Ok(call!(self, victor()))
+    }
+
+    async fn game_over(&self) -> UmpireResult<Option<GameOver>> {
+        Ok(call!(self, game_over()))
+    }
+
+    async fn turn_limit(&self) -> UmpireResult<Option<TurnNum>> {
+        Ok(call!(self, turn_limit()))
     }
 
     async fn end_turn(&mut self, player_secret: PlayerSecret) -> UmpireResult<TurnEnded> {
-        self.game
-            .end_turn(context::current(), player_secret)
-            .await
-            .unwrap()
+        call_once!(self, end_turn(player_secret))
     }
 
     async fn force_end_turn(&mut self, player_secret: PlayerSecret) -> UmpireResult<TurnEnded> {
-        self.game
-            .force_end_turn(context::current(), player_secret)
-            .await
-            .unwrap()
+        call_once!(self, force_end_turn(player_secret))
     }
 
     async fn end_then_begin_turn(
@@ -436,15 +870,14 @@ impl IGame for RpcGame {
         next_player_secret: PlayerSecret,
         clear_after_unit_production: bool,
     ) -> UmpireResult<TurnStart> {
-        self.game
-            .end_then_begin_turn(
-                context::current(),
+        call_once!(
+            self,
+            end_then_begin_turn(
                 player_secret,
                 next_player_secret,
-                clear_after_unit_production,
+                clear_after_unit_production
             )
-            .await
-            .unwrap()
+        )
     }
 
     async fn force_end_then_begin_turn(
@@ -453,15 +886,14 @@ impl IGame for RpcGame {
         next_player_secret: PlayerSecret,
         clear_after_unit_production: bool,
     ) -> UmpireResult<TurnStart> {
-        self.game
-            .force_end_then_begin_turn(
-                context::current(),
+        call_once!(
+            self,
+            force_end_then_begin_turn(
                 player_secret,
                 next_player_secret,
-                clear_after_unit_production,
+                clear_after_unit_production
             )
-            .await
-            .unwrap()
+        )
     }
 
     async fn player_unit_legal_one_step_destinations(
@@ -469,10 +901,21 @@ impl IGame for RpcGame {
         player_secret: PlayerSecret,
         unit_id: UnitID,
     ) -> UmpireResult<BTreeSet<Location>> {
-        self.game
-            .player_unit_legal_one_step_destinations(context::current(), player_secret, unit_id)
-            .await
-            .unwrap()
+        call!(
+            self,
+            player_unit_legal_one_step_destinations(player_secret, unit_id)
+        )
+    }
+
+    async fn player_unit_reachable_this_turn(
+        &self,
+        player_secret: PlayerSecret,
+        unit_id: UnitID,
+    ) -> UmpireResult<BTreeSet<Location>> {
+        call!(
+            self,
+            player_unit_reachable_this_turn(player_secret, unit_id)
+        )
     }
 
     async fn player_unit_legal_directions(
@@ -480,10 +923,7 @@ impl IGame for RpcGame {
         player_secret: PlayerSecret,
         unit_id: UnitID,
     ) -> UmpireResult<Vec<Direction>> {
-        self.game
-            .player_unit_legal_directions(context::current(), player_secret, unit_id)
-            .await
-            .unwrap()
+        call!(self, player_unit_legal_directions(player_secret, unit_id))
     }
 
     async fn player_tile(
@@ -491,11 +931,7 @@ impl IGame for RpcGame {
         player_secret: PlayerSecret,
         loc: Location,
     ) -> UmpireResult<Option<Cow<Tile>>> {
-        self.game
-            .player_tile(context::current(), player_secret, loc)
-            .await
-            .unwrap()
-            .map(|tile| tile.map(Cow::Owned))
+        call!(self, player_tile(player_secret, loc)).map(|tile| tile.map(Cow::Owned))
     }
 
     async fn player_obs(
@@ -503,68 +939,64 @@ impl IGame for RpcGame {
         player_secret: PlayerSecret,
         loc: Location,
     ) -> UmpireResult<Option<Obs>> {
-        self.game
-            .player_obs(context::current(), player_secret, loc)
-            .await
-            .unwrap()
+        call!(self, player_obs(player_secret, loc))
     }
 
     async fn player_observations(&self, player_secret: PlayerSecret) -> UmpireResult<ObsTracker> {
-        self.game
-            .player_observations(context::current(), player_secret)
-            .await
-            .unwrap()
+        call!(self, player_observations(player_secret))
+    }
+
+    async fn spectator_observations(&self, viewer: Option<PlayerNum>) -> UmpireResult<ObsTracker> {
+        call!(self, spectator_observations(viewer))
+    }
+
+    async fn player_observations_since(
+        &self,
+        player_secret: PlayerSecret,
+        revision: ObsRevision,
+    ) -> UmpireResult<ObsDiff> {
+        call!(self, player_observations_since(player_secret, revision))
+    }
+
+    async fn player_turn_delta(
+        &self,
+        player_secret: PlayerSecret,
+        since: Option<SyncPoint>,
+    ) -> UmpireResult<TurnDelta> {
+        call!(self, player_turn_delta(player_secret, since))
     }
 
     async fn player_cities(&self, player_secret: PlayerSecret) -> UmpireResult<Vec<City>> {
-        self.game
-            .player_cities(context::current(), player_secret)
-            .await
-            .unwrap()
+        call!(self, player_cities(player_secret))
     }
 
     async fn player_cities_with_production_target(
         &self,
         player_secret: PlayerSecret,
     ) -> UmpireResult<Vec<City>> {
-        self.game
-            .player_cities_with_production_target(context::current(), player_secret)
-            .await
-            .unwrap()
+        call!(self, player_cities_with_production_target(player_secret))
     }
 
     async fn player_city_count(&self, player_secret: PlayerSecret) -> UmpireResult<usize> {
-        self.game
-            .player_city_count(context::current(), player_secret)
-            .await
-            .unwrap()
+        call!(self, player_city_count(player_secret))
     }
 
     async fn player_cities_producing_or_not_ignored(
         &self,
         player_secret: PlayerSecret,
     ) -> UmpireResult<usize> {
-        self.game
-            .player_cities_producing_or_not_ignored(context::current(), player_secret)
-            .await
-            .unwrap()
+        call!(self, player_cities_producing_or_not_ignored(player_secret))
     }
 
     async fn player_units(&self, player_secret: PlayerSecret) -> UmpireResult<Vec<Unit>> {
-        self.game
-            .player_units(context::current(), player_secret)
-            .await
-            .unwrap()
+        call!(self, player_units(player_secret))
     }
 
     async fn player_unit_type_counts(
         &self,
         player_secret: PlayerSecret,
     ) -> UmpireResult<BTreeMap<UnitType, usize>> {
-        self.game
-            .player_unit_type_counts(context::current(), player_secret)
-            .await
-            .unwrap()
+        call!(self, player_unit_type_counts(player_secret))
     }
 
     async fn player_city_by_loc(
@@ -572,10 +1004,7 @@ impl IGame for RpcGame {
         player_secret: PlayerSecret,
         loc: Location,
     ) -> UmpireResult<Option<City>> {
-        self.game
-            .player_city_by_loc(context::current(), player_secret, loc)
-            .await
-            .unwrap()
+        call!(self, player_city_by_loc(player_secret, loc))
     }
 
     async fn player_city_by_id(
@@ -583,10 +1012,7 @@ impl IGame for RpcGame {
         player_secret: PlayerSecret,
         city_id: CityID,
     ) -> UmpireResult<Option<City>> {
-        self.game
-            .player_city_by_id(context::current(), player_secret, city_id)
-            .await
-            .unwrap()
+        call!(self, player_city_by_id(player_secret, city_id))
     }
 
     async fn player_unit_by_id(
@@ -594,10 +1020,7 @@ impl IGame for RpcGame {
         player_secret: PlayerSecret,
         id: UnitID,
     ) -> UmpireResult<Option<Unit>> {
-        self.game
-            .player_unit_by_id(context::current(), player_secret, id)
-            .await
-            .unwrap()
+        call!(self, player_unit_by_id(player_secret, id))
     }
 
     async fn player_unit_loc(
@@ -605,10 +1028,7 @@ impl IGame for RpcGame {
         player_secret: PlayerSecret,
         id: UnitID,
     ) -> UmpireResult<Option<Location>> {
-        self.game
-            .player_unit_loc(context::current(), player_secret, id)
-            .await
-            .unwrap()
+        call!(self, player_unit_loc(player_secret, id))
     }
 
     async fn player_toplevel_unit_by_loc(
@@ -616,70 +1036,49 @@ impl IGame for RpcGame {
         player_secret: PlayerSecret,
         loc: Location,
     ) -> UmpireResult<Option<Unit>> {
-        self.game
-            .player_toplevel_unit_by_loc(context::current(), player_secret, loc)
-            .await
-            .unwrap()
+        call!(self, player_toplevel_unit_by_loc(player_secret, loc))
     }
 
     async fn player_production_set_requests(
         &self,
         player_secret: PlayerSecret,
     ) -> UmpireResult<Vec<Location>> {
-        self.game
-            .player_production_set_requests(context::current(), player_secret)
-            .await
-            .unwrap()
+        call!(self, player_production_set_requests(player_secret))
     }
 
     async fn player_unit_orders_requests(
         &self,
         player_secret: PlayerSecret,
     ) -> UmpireResult<Vec<UnitID>> {
-        self.game
-            .player_unit_orders_requests(context::current(), player_secret)
-            .await
-            .unwrap()
+        call!(self, player_unit_orders_requests(player_secret))
     }
 
     async fn player_units_with_orders_requests(
         &self,
         player_secret: PlayerSecret,
     ) -> UmpireResult<Vec<Unit>> {
-        self.game
-            .player_units_with_orders_requests(context::current(), player_secret)
-            .await
-            .unwrap()
+        call!(self, player_units_with_orders_requests(player_secret))
     }
 
     async fn player_units_with_pending_orders(
         &self,
         player_secret: PlayerSecret,
     ) -> UmpireResult<Vec<UnitID>> {
-        self.game
-            .player_units_with_pending_orders(context::current(), player_secret)
-            .await
-            .unwrap()
+        call!(self, player_units_with_pending_orders(player_secret))
     }
 
     async fn player_next_unit_legal_actions(
         &self,
         player_secret: PlayerSecret,
     ) -> UmpireResult<BTreeSet<NextUnitAction>> {
-        self.game
-            .player_next_unit_legal_actions(context::current(), player_secret)
-            .await
-            .unwrap()
+        call!(self, player_next_unit_legal_actions(player_secret))
     }
 
     async fn player_next_city_legal_actions(
         &self,
         player_secret: PlayerSecret,
     ) -> UmpireResult<BTreeSet<NextCityAction>> {
-        self.game
-            .player_next_city_legal_actions(context::current(), player_secret)
-            .await
-            .unwrap()
+        call!(self, player_next_city_legal_actions(player_secret))
     }
 
     async fn move_toplevel_unit_by_id(
@@ -688,10 +1087,7 @@ impl IGame for RpcGame {
         unit_id: UnitID,
         dest: Location,
     ) -> UmpireResult<Move> {
-        self.game
-            .move_toplevel_unit_by_id(context::current(), player_secret, unit_id, dest)
-            .await
-            .unwrap()
+        call_once!(self, move_toplevel_unit_by_id(player_secret, unit_id, dest))
     }
 
     async fn move_toplevel_unit_by_id_avoiding_combat(
@@ -700,15 +1096,10 @@ impl IGame for RpcGame {
         unit_id: UnitID,
         dest: Location,
     ) -> UmpireResult<Move> {
-        self.game
-            .move_toplevel_unit_by_id_avoiding_combat(
-                context::current(),
-                player_secret,
-                unit_id,
-                dest,
-            )
-            .await
-            .unwrap()
+        call_once!(
+            self,
+            move_toplevel_unit_by_id_avoiding_combat(player_secret, unit_id, dest)
+        )
     }
 
     async fn move_toplevel_unit_by_loc(
@@ -717,10 +1108,7 @@ impl IGame for RpcGame {
         src: Location,
         dest: Location,
     ) -> UmpireResult<Move> {
-        self.game
-            .move_toplevel_unit_by_loc(context::current(), player_secret, src, dest)
-            .await
-            .unwrap()
+        call_once!(self, move_toplevel_unit_by_loc(player_secret, src, dest))
     }
 
     async fn move_toplevel_unit_by_loc_avoiding_combat(
@@ -729,10 +1117,10 @@ impl IGame for RpcGame {
         src: Location,
         dest: Location,
     ) -> UmpireResult<Move> {
-        self.game
-            .move_toplevel_unit_by_loc_avoiding_combat(context::current(), player_secret, src, dest)
-            .await
-            .unwrap()
+        call_once!(
+            self,
+            move_toplevel_unit_by_loc_avoiding_combat(player_secret, src, dest)
+        )
     }
 
     async fn move_unit_by_id_in_direction(
@@ -741,10 +1129,10 @@ impl IGame for RpcGame {
         unit_id: UnitID,
         direction: Direction,
     ) -> UmpireResult<Move> {
-        self.game
-            .move_unit_by_id_in_direction(context::current(), player_secret, unit_id, direction)
-            .await
-            .unwrap()
+        call_once!(
+            self,
+            move_unit_by_id_in_direction(player_secret, unit_id, direction)
+        )
     }
 
     async fn move_unit_by_id(
@@ -753,10 +1141,7 @@ impl IGame for RpcGame {
         unit_id: UnitID,
         dest: Location,
     ) -> UmpireResult<Move> {
-        self.game
-            .move_unit_by_id(context::current(), player_secret, unit_id, dest)
-            .await
-            .unwrap()
+        call_once!(self, move_unit_by_id(player_secret, unit_id, dest))
     }
 
     async fn propose_move_unit_by_id(
@@ -765,10 +1150,7 @@ impl IGame for RpcGame {
         id: UnitID,
         dest: Location,
     ) -> ProposedResult<Move, GameError> {
-        self.game
-            .propose_move_unit_by_id(context::current(), player_secret, id, dest)
-            .await
-            .unwrap()
+        call!(self, propose_move_unit_by_id(player_secret, id, dest))
     }
 
     async fn move_unit_by_id_avoiding_combat(
@@ -777,10 +1159,10 @@ impl IGame for RpcGame {
         id: UnitID,
         dest: Location,
     ) -> UmpireResult<Move> {
-        self.game
-            .move_unit_by_id_avoiding_combat(context::current(), player_secret, id, dest)
-            .await
-            .unwrap()
+        call_once!(
+            self,
+            move_unit_by_id_avoiding_combat(player_secret, id, dest)
+        )
     }
 
     async fn propose_move_unit_by_id_avoiding_combat(
@@ -789,10 +1171,155 @@ impl IGame for RpcGame {
         id: UnitID,
         dest: Location,
     ) -> ProposedResult<Move, GameError> {
-        self.game
-            .propose_move_unit_by_id_avoiding_combat(context::current(), player_secret, id, dest)
-            .await
-            .unwrap()
+        call!(
+            self,
+            propose_move_unit_by_id_avoiding_combat(player_secret, id, dest)
+        )
+    }
+
+    async fn combat_odds(
+        &self,
+        player_secret: PlayerSecret,
+        attacker_id: UnitID,
+        defender_loc: Location,
+    ) -> UmpireResult<CombatOdds> {
+        call!(self, combat_odds(player_secret, attacker_id, defender_loc))
+    }
+
+    async fn load_unit(
+        &mut self,
+        player_secret: PlayerSecret,
+        unit_id: UnitID,
+        carrier_id: UnitID,
+    ) -> UmpireResult<Move> {
+        call_once!(self, load_unit(player_secret, unit_id, carrier_id))
+    }
+
+    async fn unload_unit(
+        &mut self,
+        player_secret: PlayerSecret,
+        unit_id: UnitID,
+        dest: Location,
+    ) -> UmpireResult<Move> {
+        call_once!(self, unload_unit(player_secret, unit_id, dest))
+    }
+
+    async fn rebase_unit(
+        &mut self,
+        player_secret: PlayerSecret,
+        unit_id: UnitID,
+        base: Location,
+    ) -> UmpireResult<Move> {
+        call_once!(self, rebase_unit(player_secret, unit_id, base))
+    }
+
+    async fn player_air_bases(
+        &self,
+        player_secret: PlayerSecret,
+        unit_id: UnitID,
+    ) -> UmpireResult<Vec<Location>> {
+        call!(self, player_air_bases(player_secret, unit_id))
+    }
+
+    async fn bombard(
+        &mut self,
+        player_secret: PlayerSecret,
+        unit_id: UnitID,
+        target: Location,
+    ) -> UmpireResult<Bombardment> {
+        call_once!(self, bombard(player_secret, unit_id, target))
+    }
+
+    async fn player_unit_bombard_targets(
+        &self,
+        player_secret: PlayerSecret,
+        unit_id: UnitID,
+    ) -> UmpireResult<Vec<Location>> {
+        call!(self, player_unit_bombard_targets(player_secret, unit_id))
+    }
+
+    async fn paradrop(
+        &mut self,
+        player_secret: PlayerSecret,
+        unit_id: UnitID,
+        dest: Location,
+    ) -> UmpireResult<Move> {
+        call_once!(self, paradrop(player_secret, unit_id, dest))
+    }
+
+    async fn player_unit_drop_zones(
+        &self,
+        player_secret: PlayerSecret,
+        unit_id: UnitID,
+    ) -> UmpireResult<Vec<Location>> {
+        call!(self, player_unit_drop_zones(player_secret, unit_id))
+    }
+
+    async fn lay_mines(
+        &mut self,
+        player_secret: PlayerSecret,
+        unit_id: UnitID,
+    ) -> UmpireResult<Location> {
+        call_once!(self, lay_mines(player_secret, unit_id))
+    }
+
+    async fn sweep_mines(
+        &mut self,
+        player_secret: PlayerSecret,
+        unit_id: UnitID,
+    ) -> UmpireResult<Vec<Location>> {
+        call_once!(self, sweep_mines(player_secret, unit_id))
+    }
+
+    async fn player_known_minefields(
+        &self,
+        player_secret: PlayerSecret,
+    ) -> UmpireResult<Vec<Location>> {
+        call!(self, player_known_minefields(player_secret))
+    }
+
+    async fn diplomatic_status(
+        &self,
+        player_secret: PlayerSecret,
+        other: PlayerNum,
+    ) -> UmpireResult<DiplomaticStatus> {
+        call!(self, diplomatic_status(player_secret, other))
+    }
+
+    async fn treaty_proposals(
+        &self,
+        player_secret: PlayerSecret,
+    ) -> UmpireResult<Vec<(PlayerNum, DiplomaticStatus)>> {
+        call!(self, treaty_proposals(player_secret))
+    }
+
+    async fn propose_treaty(
+        &mut self,
+        player_secret: PlayerSecret,
+        other: PlayerNum,
+        status: DiplomaticStatus,
+    ) -> UmpireResult<()> {
+        call_once!(self, propose_treaty(player_secret, other, status))
+    }
+
+    async fn accept_treaty(
+        &mut self,
+        player_secret: PlayerSecret,
+        other: PlayerNum,
+    ) -> UmpireResult<DiplomaticStatus> {
+        call_once!(self, accept_treaty(player_secret, other))
+    }
+
+    async fn declare_war(
+        &mut self,
+        player_secret: PlayerSecret,
+        other: PlayerNum,
+    ) -> UmpireResult<DiplomaticStatus> {
+        call_once!(self, declare_war(player_secret, other))
+    }
+
+    async fn resign(&mut self, player_secret: PlayerSecret) -> UmpireResult<Resigned> {
+        call_once!(self, resign(player_secret))
     }
 
     async fn disband_unit_by_id(
@@ -800,10 +1327,7 @@ impl IGame for RpcGame {
         player_secret: PlayerSecret,
         unit_id: UnitID,
     ) -> UmpireResult<UnitDisbanded> {
-        self.game
-            .disband_unit_by_id(context::current(), player_secret, unit_id)
-            .await
-            .unwrap()
+        call_once!(self, disband_unit_by_id(player_secret, unit_id))
     }
 
     async fn set_production_by_loc(
@@ -812,10 +1336,7 @@ impl IGame for RpcGame {
         loc: Location,
         production: UnitType,
     ) -> UmpireResult<ProductionSet> {
-        self.game
-            .set_production_by_loc(context::current(), player_secret, loc, production)
-            .await
-            .unwrap()
+        call_once!(self, set_production_by_loc(player_secret, loc, production))
     }
 
     async fn set_production_by_id(
@@ -824,10 +1345,26 @@ impl IGame for RpcGame {
         city_id: CityID,
         production: UnitType,
     ) -> UmpireResult<ProductionSet> {
-        self.game
-            .set_production_by_id(context::current(), player_secret, city_id, production)
-            .await
-            .unwrap()
+        call_once!(
+            self,
+            set_production_by_id(player_secret, city_id, production)
+        )
+    }
+
+    async fn build_city_walls(
+        &mut self,
+        player_secret: PlayerSecret,
+        city_id: CityID,
+    ) -> UmpireResult<WallsBuilt> {
+        call_once!(self, build_city_walls(player_secret, city_id))
+    }
+
+    async fn build_city_radar(
+        &mut self,
+        player_secret: PlayerSecret,
+        city_id: CityID,
+    ) -> UmpireResult<RadarBuilt> {
+        call_once!(self, build_city_radar(player_secret, city_id))
     }
 
     async fn clear_production(
@@ -836,15 +1373,10 @@ impl IGame for RpcGame {
         loc: Location,
         ignore_cleared_production: bool,
     ) -> UmpireResult<ProductionCleared> {
-        self.game
-            .clear_production(
-                context::current(),
-                player_secret,
-                loc,
-                ignore_cleared_production,
-            )
-            .await
-            .unwrap()
+        call_once!(
+            self,
+            clear_production(player_secret, loc, ignore_cleared_production)
+        )
     }
 
     async fn clear_productions(
@@ -852,47 +1384,45 @@ impl IGame for RpcGame {
         player_secret: PlayerSecret,
         ignore_cleared_productions: bool,
     ) -> UmpireResult<Vec<ProductionCleared>> {
-        self.game
-            .clear_productions(
-                context::current(),
-                player_secret,
-                ignore_cleared_productions,
-            )
-            .await
-            .unwrap()
+        call_once!(
+            self,
+            clear_productions(player_secret, ignore_cleared_productions)
+        )
     }
 
-    async fn turn(&self) -> TurnNum {
-        self.game.turn(context::current()).await.unwrap()
+    async fn turn(&self) -> UmpireResult<TurnNum> {
+        Ok(call!(self, turn()))
     }
 
     async fn player_action(&self, player_secret: PlayerSecret) -> UmpireResult<ActionNum> {
-        self.game
-            .player_action(context::current(), player_secret)
-            .await
-            .unwrap()
+        call!(self, player_action(player_secret))
+    }
+
+    async fn turn_phase(&self) -> UmpireResult<TurnPhase> {
+        Ok(call!(self, turn_phase()))
     }
 
-    async fn turn_phase(&self) -> TurnPhase {
-        self.game.turn_phase(context::current()).await.unwrap()
+    async fn current_player(&self) -> UmpireResult<PlayerNum> {
+        Ok(call!(self, current_player()))
     }
 
-    async fn current_player(&self) -> PlayerNum {
-        self.game.current_player(context::current()).await.unwrap()
+    async fn paused(&self) -> UmpireResult<bool> {
+        Ok(call!(self, paused()))
     }
 
-    async fn dims(&self) -> Dims {
-        self.game.dims(context::current()).await.unwrap()
+    async fn dims(&self) -> UmpireResult<Dims> {
+        Ok(call!(self, dims()))
     }
 
-    async fn wrapping(&self) -> Wrap2d {
-        self.game.wrapping(context::current()).await.unwrap()
+    async fn wrapping(&self) -> UmpireResult<Wrap2d> {
+        Ok(call!(self, wrapping()))
     }
 
     async fn player_features(
         &self,
         _player_secret: PlayerSecret,
         _focus: TrainingFocus,
+        _window: usize,
     ) -> UmpireResult<Vec<fX>> {
         unimplemented!();
     }
@@ -902,10 +1432,7 @@ impl IGame for RpcGame {
         player_secret: PlayerSecret,
         loc: Location,
     ) -> UmpireResult<Vec<UnitType>> {
-        self.game
-            .valid_productions(context::current(), player_secret, loc)
-            .await
-            .unwrap()
+        call!(self, valid_productions(player_secret, loc))
     }
 
     async fn valid_productions_conservative(
@@ -913,10 +1440,7 @@ impl IGame for RpcGame {
         player_secret: PlayerSecret,
         loc: Location,
     ) -> UmpireResult<Vec<UnitType>> {
-        self.game
-            .valid_productions_conservative(context::current(), player_secret, loc)
-            .await
-            .unwrap()
+        call!(self, valid_productions_conservative(player_secret, loc))
     }
 
     async fn order_unit_sentry(
@@ -924,10 +1448,7 @@ impl IGame for RpcGame {
         player_secret: PlayerSecret,
         unit_id: UnitID,
     ) -> UmpireResult<OrdersSet> {
-        self.game
-            .order_unit_sentry(context::current(), player_secret, unit_id)
-            .await
-            .unwrap()
+        call_once!(self, order_unit_sentry(player_secret, unit_id))
     }
 
     async fn order_unit_skip(
@@ -935,10 +1456,7 @@ impl IGame for RpcGame {
         player_secret: PlayerSecret,
         unit_id: UnitID,
     ) -> UmpireResult<OrdersSet> {
-        self.game
-            .order_unit_skip(context::current(), player_secret, unit_id)
-            .await
-            .unwrap()
+        call_once!(self, order_unit_skip(player_secret, unit_id))
     }
 
     async fn order_unit_go_to(
@@ -947,10 +1465,7 @@ impl IGame for RpcGame {
         unit_id: UnitID,
         dest: Location,
     ) -> OrdersResult {
-        self.game
-            .order_unit_go_to(context::current(), player_secret, unit_id, dest)
-            .await
-            .unwrap()
+        call_once!(self, order_unit_go_to(player_secret, unit_id, dest))
     }
 
     async fn propose_order_unit_go_to(
@@ -959,10 +1474,7 @@ impl IGame for RpcGame {
         unit_id: UnitID,
         dest: Location,
     ) -> ProposedOrdersResult {
-        self.game
-            .propose_order_unit_go_to(context::current(), player_secret, unit_id, dest)
-            .await
-            .unwrap()
+        call!(self, propose_order_unit_go_to(player_secret, unit_id, dest))
     }
 
     async fn order_unit_explore(
@@ -970,10 +1482,7 @@ impl IGame for RpcGame {
         player_secret: PlayerSecret,
         unit_id: UnitID,
     ) -> OrdersResult {
-        self.game
-            .order_unit_explore(context::current(), player_secret, unit_id)
-            .await
-            .unwrap()
+        call_once!(self, order_unit_explore(player_secret, unit_id))
     }
 
     async fn propose_order_unit_explore(
@@ -981,10 +1490,85 @@ impl IGame for RpcGame {
         player_secret: PlayerSecret,
         unit_id: UnitID,
     ) -> ProposedOrdersResult {
-        self.game
-            .propose_order_unit_explore(context::current(), player_secret, unit_id)
-            .await
-            .unwrap()
+        call!(self, propose_order_unit_explore(player_secret, unit_id))
+    }
+
+    async fn order_unit_escort(
+        &mut self,
+        player_secret: PlayerSecret,
+        unit_id: UnitID,
+        target: UnitID,
+    ) -> OrdersResult {
+        call_once!(self, order_unit_escort(player_secret, unit_id, target))
+    }
+
+    async fn propose_order_unit_escort(
+        &self,
+        player_secret: PlayerSecret,
+        unit_id: UnitID,
+        target: UnitID,
+    ) -> ProposedOrdersResult {
+        call!(
+            self,
+            propose_order_unit_escort(player_secret, unit_id, target)
+        )
+    }
+
+    async fn order_unit_build(
+        &mut self,
+        player_secret: PlayerSecret,
+        unit_id: UnitID,
+        improvement: Improvement,
+    ) -> OrdersResult {
+        call_once!(self, order_unit_build(player_secret, unit_id, improvement))
+    }
+
+    async fn propose_order_unit_build(
+        &self,
+        player_secret: PlayerSecret,
+        unit_id: UnitID,
+        improvement: Improvement,
+    ) -> ProposedOrdersResult {
+        call!(
+            self,
+            propose_order_unit_build(player_secret, unit_id, improvement)
+        )
+    }
+
+    async fn unit_groups(
+        &self,
+        player_secret: PlayerSecret,
+    ) -> UmpireResult<Vec<(String, Vec<UnitID>)>> {
+        call!(self, unit_groups(player_secret))
+    }
+
+    async fn form_unit_group(
+        &mut self,
+        player_secret: PlayerSecret,
+        name: String,
+        unit_ids: Vec<UnitID>,
+    ) -> UmpireResult<()> {
+        call_once!(
+            self,
+            form_unit_group(player_secret, name.clone(), unit_ids.clone())
+        )
+    }
+
+    async fn disband_unit_group(
+        &mut self,
+        player_secret: PlayerSecret,
+        name: String,
+    ) -> UmpireResult<Vec<UnitID>> {
+        call_once!(self, disband_unit_group(player_secret, name.clone()))
+    }
+
+    async fn order_unit_group(
+        &mut self,
+        player_secret: PlayerSecret,
+        name: String,
+        orders: Orders,
+    ) -> UmpireResult<Vec<OrdersResult>> {
+        call_once!(self, order_unit_group(player_secret, name.clone(), orders))
     }
 
     async fn activate_unit_by_loc(
@@ -992,10 +1576,7 @@ impl IGame for RpcGame {
         player_secret: PlayerSecret,
         loc: Location,
     ) -> UmpireResult<LocatedObsLite> {
-        self.game
-            .activate_unit_by_loc(context::current(), player_secret, loc)
-            .await
-            .unwrap()
+        call_once!(self, activate_unit_by_loc(player_secret, loc))
     }
 
     async fn set_orders(
@@ -1004,10 +1585,7 @@ impl IGame for RpcGame {
         id: UnitID,
         orders: Orders,
     ) -> UmpireResult<OrdersSet> {
-        self.game
-            .set_orders(context::current(), player_secret, id, orders)
-            .await
-            .unwrap()
+        call_once!(self, set_orders(player_secret, id, orders))
     }
 
     async fn clear_orders(
@@ -1015,10 +1593,7 @@ impl IGame for RpcGame {
         player_secret: PlayerSecret,
         id: UnitID,
     ) -> UmpireResult<Option<Orders>> {
-        self.game
-            .clear_orders(context::current(), player_secret, id)
-            .await
-            .unwrap()
+        call_once!(self, clear_orders(player_secret, id))
     }
 
     async fn propose_set_and_follow_orders(
@@ -1027,10 +1602,10 @@ impl IGame for RpcGame {
         id: UnitID,
         orders: Orders,
     ) -> ProposedOrdersResult {
-        self.game
-            .propose_set_and_follow_orders(context::current(), player_secret, id, orders)
-            .await
-            .unwrap()
+        call!(
+            self,
+            propose_set_and_follow_orders(player_secret, id, orders)
+        )
     }
 
     async fn set_and_follow_orders(
@@ -1039,35 +1614,52 @@ impl IGame for RpcGame {
         id: UnitID,
         orders: Orders,
     ) -> OrdersResult {
-        self.game
-            .set_and_follow_orders(context::current(), player_secret, id, orders)
-            .await
-            .unwrap()
+        call_once!(self, set_and_follow_orders(player_secret, id, orders))
     }
 
-    async fn current_player_score(&self) -> f64 {
-        self.game
-            .current_player_score(context::current())
-            .await
-            .unwrap()
+    async fn current_player_score(&self) -> UmpireResult<f64> {
+        Ok(call!(self, current_player_score()))
     }
 
     async fn player_score(&self, player_secret: PlayerSecret) -> UmpireResult<f64> {
-        self.game
-            .player_score(context::current(), player_secret)
-            .await
-            .unwrap()
+        call!(self, player_score(player_secret))
     }
 
     async fn player_score_by_idx(&self, player: PlayerNum) -> UmpireResult<f64> {
-        self.game
-            .player_score_by_idx(context::current(), player)
-            .await
-            .unwrap()
+        call!(self, player_score_by_idx(player))
+    }
+
+    async fn player_scores(&self) -> UmpireResult<Vec<f64>> {
+        Ok(call!(self, player_scores()))
     }
 
-    async fn player_scores(&self) -> Vec<f64> {
-        self.game.player_scores(context::current()).await.unwrap()
+    async fn player_histories(&self) -> UmpireResult<Vec<PlayerHistory>> {
+        Ok(call!(self, player_histories()))
+    }
+
+    async fn send_chat_message(
+        &mut self,
+        player_secret: PlayerSecret,
+        text: String,
+    ) -> UmpireResult<()> {
+        call_once!(self, send_chat_message(player_secret, text.clone()))
+    }
+
+    async fn chat_messages_since(
+        &self,
+        player_secret: PlayerSecret,
+        since: usize,
+    ) -> UmpireResult<Vec<ChatMessage>> {
+        call!(self, chat_messages_since(player_secret, since))
+    }
+
+    async fn wait_chat_messages(
+        &self,
+        player_secret: PlayerSecret,
+        since: usize,
+        timeout: Duration,
+    ) -> UmpireResult<Vec<ChatMessage>> {
+        call!(self, wait_chat_messages(player_secret, since, timeout))
     }
 
     async fn take_simple_action(
@@ -1075,10 +1667,7 @@ impl IGame for RpcGame {
         player_secret: PlayerSecret,
         action: AiPlayerAction,
     ) -> UmpireResult<PlayerActionOutcome> {
-        self.game
-            .take_simple_action(context::current(), player_secret, action)
-            .await
-            .unwrap()
+        call_once!(self, take_simple_action(player_secret, action))
     }
 
     async fn take_action(
@@ -1086,10 +1675,7 @@ impl IGame for RpcGame {
         player_secret: PlayerSecret,
         action: PlayerAction,
     ) -> UmpireResult<PlayerActionOutcome> {
-        self.game
-            .take_action(context::current(), player_secret, action)
-            .await
-            .unwrap()
+        call_once!(self, take_action(player_secret, action))
     }
 
     async fn propose_action(
@@ -1097,10 +1683,7 @@ impl IGame for RpcGame {
         player_secret: PlayerSecret,
         action: PlayerAction,
     ) -> ProposedActionResult {
-        self.game
-            .propose_action(context::current(), player_secret, action)
-            .await
-            .unwrap()
+        call!(self, propose_action(player_secret, action))
     }
 
     //FIXME Refused bequest
@@ -1110,3 +1693,156 @@ impl IGame for RpcGame {
         ))
     }
 }
+
+#[cfg(test)]
+mod test {
+    use std::{
+        net::SocketAddr,
+        sync::mpsc::{channel, Sender},
+    };
+
+    use futures::{SinkExt, StreamExt};
+    use tarpc::{
+        client::{self, RpcError},
+        transport::channel as rpc_channel,
+        ClientMessage, Response,
+    };
+    use tokio::net::TcpListener;
+
+    use crate::game::{error::GameError, IGame, PlayerSecret, TurnNum};
+
+    use super::{
+        transport::{self, Compression},
+        RpcGame, UmpireRpcClient, UmpireRpcRequest, UmpireRpcResponse,
+    };
+
+    const TURN: TurnNum = 7;
+
+    /// A connection that passes one request along to `requests` and then drops, as if the network went down
+    fn dropping_connection(requests: Sender<UmpireRpcRequest>) -> UmpireRpcClient {
+        let (client_transport, mut server_transport) = rpc_channel::unbounded();
+        tokio::spawn(async move {
+            if let Some(Ok(ClientMessage::Request(request))) = server_transport.next().await {
+                requests.send(request.message).unwrap();
+            }
+        });
+        UmpireRpcClient::new(client::Config::default(), client_transport).spawn()
+    }
+
+    /// A server that lets a spectator join and tells the turn, passing along every request it gets to `requests`
+    async fn answering_server(requests: Sender<UmpireRpcRequest>) -> SocketAddr {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let server_addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            let (stream, _) = listener.accept().await.unwrap();
+            let mut transport = transport::accept::<
+                ClientMessage<UmpireRpcRequest>,
+                Response<UmpireRpcResponse>,
+            >(stream, Compression::None)
+            .await
+            .unwrap();
+
+            while let Some(Ok(ClientMessage::Request(request))) = transport.next().await {
+                let answer = match request.message {
+                    UmpireRpcRequest::JoinGame { .. } => Some(UmpireRpcResponse::JoinGame(Ok(()))),
+                    UmpireRpcRequest::Spectate { .. } => Some(UmpireRpcResponse::Spectate(Ok(()))),
+                    UmpireRpcRequest::Turn { .. } => Some(UmpireRpcResponse::Turn(TURN)),
+                    _ => None,
+                };
+                requests.send(request.message).unwrap();
+
+                if let Some(message) = answer {
+                    transport
+                        .send(Response {
+                            request_id: request.id,
+                            message: Ok(message),
+                        })
+                        .await
+                        .unwrap();
+                }
+            }
+        });
+        server_addr
+    }
+
+    #[tokio::test]
+    async fn test_read_made_again() {
+        let (first_tx, first_rx) = channel();
+        let (second_tx, second_rx) = channel();
+
+        let game = RpcGame::new(
+            dropping_connection(first_tx),
+            answering_server(second_tx).await,
+            Compression::None,
+            0,
+            vec![None, None],
+        );
+
+        assert_eq!(game.turn().await, Ok(TURN));
+
+        let first: Vec<_> = first_rx.try_iter().collect();
+        assert!(matches!(first[..], [UmpireRpcRequest::Turn { .. }]));
+
+        let second: Vec<_> = second_rx.try_iter().collect();
+        assert!(matches!(
+            second[..],
+            [
+                UmpireRpcRequest::JoinGame { .. },
+                UmpireRpcRequest::Spectate { .. },
+                UmpireRpcRequest::Turn { .. }
+            ]
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_change_not_made_again() {
+        let (first_tx, first_rx) = channel();
+        let (second_tx, second_rx) = channel();
+
+        let mut game = RpcGame::new(
+            dropping_connection(first_tx),
+            answering_server(second_tx).await,
+            Compression::None,
+            0,
+            vec![None, None],
+        );
+
+        assert_eq!(
+            game.end_turn(PlayerSecret::nil()).await,
+            Err(GameError::CallInterrupted)
+        );
+
+        let first: Vec<_> = first_rx.try_iter().collect();
+        assert!(matches!(first[..], [UmpireRpcRequest::EndTurn { .. }]));
+
+        // Reconnected, but the turn wasn't ended a second time
+        let second: Vec<_> = second_rx.try_iter().collect();
+        assert!(matches!(
+            second[..],
+            [
+                UmpireRpcRequest::JoinGame { .. },
+                UmpireRpcRequest::Spectate { .. }
+            ]
+        ));
+
+        // The new connection serves later calls
+        assert_eq!(game.turn().await, Ok(TURN));
+    }
+
+    #[tokio::test]
+    async fn test_reconnect_other_error() {
+        let (tx, _rx) = channel();
+        let game = RpcGame::new(
+            dropping_connection(tx),
+            "127.0.0.1:1".parse().unwrap(),
+            Compression::None,
+            0,
+            vec![None, None],
+        );
+
+        assert!(matches!(
+            game.reconnect(0, RpcError::DeadlineExceeded).await,
+            Err(GameError::Rpc { .. })
+        ));
+    }
+}
@@ -0,0 +1,297 @@
+//! The transport RPC calls travel over, optionally compressing their payloads
+//!
+//! Before any calls are made, the client says which compression it wants and the server answers with the compression
+//! the connection will use---the client's choice if the server allows it, none otherwise. After that the connection
+//! carries length-delimited frames as usual.
+use std::{
+    borrow::Cow,
+    fmt,
+    io::{self, Read, Result as IoResult},
+    marker::PhantomData,
+    net::SocketAddr,
+    pin::Pin,
+};
+
+use serde::{Deserialize, Serialize};
+use tarpc::{
+    serde_transport::{self, Transport},
+    tokio_serde::{Deserializer, Serializer},
+    tokio_util::codec::{Framed, LengthDelimitedCodec},
+};
+use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt},
+    net::TcpStream,
+};
+
+use bytes::{Bytes, BytesMut};
+
+/// Payloads smaller than this many bytes aren't worth compressing, and go as they are
+const MIN_COMPRESSED_LEN: usize = 1024;
+
+/// The most bytes a frame may hold, or a compressed frame expand to; bigger ones are refused rather than read into memory
+const MAX_FRAME_LEN: usize = 64 * 1024 * 1024;
+
+/// The zstd compression level; low, as payloads are compressed on the fly
+const ZSTD_LEVEL: i32 = 3;
+
+/// Marks a frame that went as it was
+const FRAME_RAW: u8 = 0;
+
+/// Marks a frame that was compressed with zstd
+const FRAME_ZSTD: u8 = 1;
+
+/// How RPC payloads are compressed on the wire
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Compression {
+    None,
+
+    /// Payloads big enough to be worth it are compressed with zstd
+    #[default]
+    Zstd,
+}
+
+impl Compression {
+    fn to_byte(self) -> u8 {
+        match self {
+            Self::None => 0,
+            Self::Zstd => 1,
+        }
+    }
+
+    fn from_byte(b: u8) -> IoResult<Self> {
+        match b {
+            0 => Ok(Self::None),
+            1 => Ok(Self::Zstd),
+            b => Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("Unrecognized compression {}", b),
+            )),
+        }
+    }
+}
+
+impl TryFrom<&str> for Compression {
+    type Error = String;
+
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        match value {
+            "none" | "off" => Ok(Self::None),
+            "zstd" | "on" => Ok(Self::Zstd),
+            x => Err(format!("Unrecognized compression {}", x)),
+        }
+    }
+}
+
+impl fmt::Display for Compression {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{}",
+            match self {
+                Self::None => "none",
+                Self::Zstd => "zstd",
+            }
+        )
+    }
+}
+
+fn bincode_err(err: bincode::Error) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, err)
+}
+
+/// Decompress a zstd frame, failing rather than expanding it past `limit` bytes
+fn decompress(compressed: &[u8], limit: usize) -> IoResult<Vec<u8>> {
+    let mut payload = Vec::new();
+    zstd::stream::Decoder::new(compressed)?
+        .take(limit as u64 + 1)
+        .read_to_end(&mut payload)?;
+
+    if payload.len() > limit {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("Frame decompresses to more than {} bytes", limit),
+        ));
+    }
+
+    Ok(payload)
+}
+
+/// Bincode, with frames compressed as negotiated
+///
+/// Compressed frames lead with a byte saying whether the rest was compressed, so small payloads like heartbeats can
+/// go as they are.
+pub struct Compressed<Item, SinkItem> {
+    compression: Compression,
+    items: PhantomData<fn(SinkItem) -> Item>,
+}
+
+impl<Item, SinkItem> Compressed<Item, SinkItem> {
+    pub fn new(compression: Compression) -> Self {
+        Self {
+            compression,
+            items: PhantomData,
+        }
+    }
+}
+
+impl<Item, SinkItem: Serialize> Serializer<SinkItem> for Compressed<Item, SinkItem> {
+    type Error = io::Error;
+
+    fn serialize(self: Pin<&mut Self>, item: &SinkItem) -> IoResult<Bytes> {
+        let bytes = bincode::serialize(item).map_err(bincode_err)?;
+
+        match self.compression {
+            Compression::None => Ok(Bytes::from(bytes)),
+            Compression::Zstd => {
+                let mut frame = Vec::with_capacity(bytes.len() + 1);
+                if bytes.len() < MIN_COMPRESSED_LEN {
+                    frame.push(FRAME_RAW);
+                    frame.extend_from_slice(&bytes);
+                } else {
+                    frame.push(FRAME_ZSTD);
+                    frame.extend(zstd::encode_all(&bytes[..], ZSTD_LEVEL)?);
+                }
+                Ok(Bytes::from(frame))
+            }
+        }
+    }
+}
+
+impl<Item, SinkItem> Deserializer<Item> for Compressed<Item, SinkItem>
+where
+    for<'de> Item: Deserialize<'de>,
+{
+    type Error = io::Error;
+
+    fn deserialize(self: Pin<&mut Self>, src: &BytesMut) -> IoResult<Item> {
+        match self.compression {
+            Compression::None => bincode::deserialize(src).map_err(bincode_err),
+            Compression::Zstd => {
+                let payload = match src.split_first() {
+                    Some((&FRAME_RAW, rest)) => Cow::Borrowed(rest),
+                    Some((&FRAME_ZSTD, rest)) => Cow::Owned(decompress(rest, MAX_FRAME_LEN)?),
+                    Some((b, _)) => {
+                        return Err(io::Error::new(
+                            io::ErrorKind::InvalidData,
+                            format!("Unrecognized frame marker {}", b),
+                        ))
+                    }
+                    None => {
+                        return Err(io::Error::new(io::ErrorKind::UnexpectedEof, "Empty frame"))
+                    }
+                };
+                bincode::deserialize(&payload).map_err(bincode_err)
+            }
+        }
+    }
+}
+
+pub type CompressedTransport<Item, SinkItem> =
+    Transport<TcpStream, Item, SinkItem, Compressed<Item, SinkItem>>;
+
+fn transport<Item, SinkItem>(
+    stream: TcpStream,
+    compression: Compression,
+) -> CompressedTransport<Item, SinkItem>
+where
+    for<'de> Item: Deserialize<'de>,
+    SinkItem: Serialize,
+{
+    let framed = Framed::new(
+        stream,
+        LengthDelimitedCodec::builder()
+            .max_frame_length(MAX_FRAME_LEN)
+            .new_codec(),
+    );
+    serde_transport::new(framed, Compressed::new(compression))
+}
+
+/// Connect to the server at `server_addr`, asking for `compression`
+///
+/// The server may not go along with it, in which case the connection is left uncompressed.
+pub async fn connect<Item, SinkItem>(
+    server_addr: SocketAddr,
+    compression: Compression,
+) -> IoResult<CompressedTransport<Item, SinkItem>>
+where
+    for<'de> Item: Deserialize<'de>,
+    SinkItem: Serialize,
+{
+    let mut stream = TcpStream::connect(server_addr).await?;
+    stream.set_nodelay(true)?;
+
+    stream.write_u8(compression.to_byte()).await?;
+    let compression = Compression::from_byte(stream.read_u8().await?)?;
+
+    Ok(transport(stream, compression))
+}
+
+/// Take a connection from a client, agreeing to the compression it asks for if `allowed` is it
+pub async fn accept<Item, SinkItem>(
+    mut stream: TcpStream,
+    allowed: Compression,
+) -> IoResult<CompressedTransport<Item, SinkItem>>
+where
+    for<'de> Item: Deserialize<'de>,
+    SinkItem: Serialize,
+{
+    stream.set_nodelay(true)?;
+
+    let requested = Compression::from_byte(stream.read_u8().await?)?;
+    let compression = if requested == allowed {
+        requested
+    } else {
+        Compression::None
+    };
+    stream.write_u8(compression.to_byte()).await?;
+
+    Ok(transport(stream, compression))
+}
+
+#[cfg(test)]
+mod test {
+    use std::pin::Pin;
+
+    use bytes::BytesMut;
+    use tarpc::tokio_serde::{Deserializer, Serializer};
+
+    use super::{decompress, Compressed, Compression, MIN_COMPRESSED_LEN, ZSTD_LEVEL};
+
+    fn round_trip(compression: Compression, item: Vec<u64>) -> (usize, Vec<u64>) {
+        let mut codec: Compressed<Vec<u64>, Vec<u64>> = Compressed::new(compression);
+
+        let bytes = Pin::new(&mut codec).serialize(&item).unwrap();
+        let len = bytes.len();
+
+        (
+            len,
+            Pin::new(&mut codec)
+                .deserialize(&BytesMut::from(&bytes[..]))
+                .unwrap(),
+        )
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let small = vec![1, 2, 3];
+        let big = vec![7; MIN_COMPRESSED_LEN];
+
+        for compression in [Compression::None, Compression::Zstd] {
+            assert_eq!(round_trip(compression, small.clone()).1, small);
+            assert_eq!(round_trip(compression, big.clone()).1, big);
+        }
+
+        let (uncompressed_len, _) = round_trip(Compression::None, big.clone());
+        let (compressed_len, _) = round_trip(Compression::Zstd, big);
+        assert!(compressed_len < uncompressed_len);
+    }
+
+    #[test]
+    fn test_decompress_limit() {
+        let payload = vec![7u8; 1000];
+        let compressed = zstd::encode_all(&payload[..], ZSTD_LEVEL).unwrap();
+
+        assert_eq!(decompress(&compressed, 1000).unwrap(), payload);
+        assert!(decompress(&compressed, 999).is_err());
+    }
+}
@@ -0,0 +1,221 @@
+//! Commands the server's operator gives at its console, one per line on standard input
+
+use std::{
+    collections::BTreeMap,
+    io::BufRead,
+    str::FromStr,
+    sync::{Arc, RwLock as RwLockStd},
+};
+
+use common::lobby::GameID;
+use tokio::sync::{mpsc, Notify};
+
+use crate::{ConnectionID, HostedGame, UmpireServer};
+
+const HELP: &str = "Commands:
+  list                 List the games, their players, and the connections
+  kick <connection>    Drop a connection, releasing its players
+  pause <game>         Hold play in a game once the turn underway ends
+  resume <game>        Let play carry on in a paused game
+  save <game> [path]   Save a game, to umpire-<game>.sav unless a path is given
+  shutdown             Close every connection and stop the server
+  help                 Show this list";
+
+pub enum Command {
+    List,
+    Kick(ConnectionID),
+    Pause(GameID),
+    Resume(GameID),
+    Save {
+        game_id: GameID,
+        path: Option<String>,
+    },
+    Shutdown,
+    Help,
+}
+
+impl FromStr for Command {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut words = s.split_whitespace();
+        let command = words.next().unwrap_or_default();
+        let args: Vec<&str> = words.collect();
+
+        match command {
+            "list" => Ok(Self::List),
+            "kick" => parse_id(command, &args).map(Self::Kick),
+            "pause" => parse_id(command, &args).map(Self::Pause),
+            "resume" => parse_id(command, &args).map(Self::Resume),
+            "save" => Ok(Self::Save {
+                game_id: parse_id(command, &args)?,
+                path: args.get(1).map(|path| path.to_string()),
+            }),
+            "shutdown" => Ok(Self::Shutdown),
+            "help" => Ok(Self::Help),
+            command => Err(format!("Unknown command '{}'; try 'help'", command)),
+        }
+    }
+}
+
+/// The ID of a game or connection given as the first of `command`'s arguments
+fn parse_id<T: FromStr>(command: &str, args: &[&str]) -> Result<T, String> {
+    let arg = args
+        .first()
+        .ok_or_else(|| format!("'{}' needs an ID", command))?;
+    arg.parse()
+        .map_err(|_| format!("'{}' isn't a valid ID", arg))
+}
+
+/// Read commands from standard input, passing them along to be carried out, until the input ends
+///
+/// Blocks, so it gets a thread of its own.
+pub fn read_commands(commands: mpsc::UnboundedSender<Command>) {
+    for line in std::io::stdin().lock().lines() {
+        let Ok(line) = line else {
+            break;
+        };
+
+        if line.trim().is_empty() {
+            continue;
+        }
+
+        match line.parse() {
+            Ok(command) => {
+                if commands.send(command).is_err() {
+                    break;
+                }
+            }
+            Err(err) => println!("{}", err),
+        }
+    }
+}
+
+/// Carries out the operator's commands
+pub struct Admin {
+    pub games: Arc<BTreeMap<GameID, HostedGame>>,
+
+    /// The connections being served right now
+    pub connections: Arc<RwLockStd<BTreeMap<ConnectionID, UmpireServer>>>,
+
+    /// Told when the operator asks for the server to stop
+    pub shutdown: Arc<Notify>,
+}
+
+impl Admin {
+    /// Carry out commands as they come, until there are no more
+    pub async fn run(self, mut commands: mpsc::UnboundedReceiver<Command>) {
+        while let Some(command) = commands.recv().await {
+            if let Err(err) = self.execute(command).await {
+                println!("{}", err);
+            }
+        }
+    }
+
+    fn hosted(&self, game_id: GameID) -> Result<&HostedGame, String> {
+        self.games
+            .get(&game_id)
+            .ok_or_else(|| format!("No game with ID {}", game_id))
+    }
+
+    async fn execute(&self, command: Command) -> Result<(), String> {
+        match command {
+            Command::List => self.list().await,
+            Command::Kick(connection) => {
+                let connections = self.connections.read().unwrap();
+                let server = connections
+                    .get(&connection)
+                    .ok_or_else(|| format!("No connection {}", connection))?;
+                server.kicked.notify_one();
+            }
+            Command::Pause(game_id) => {
+                let hosted = self.hosted(game_id)?;
+                hosted.game.write().await.set_paused(true);
+                // The turn timer holds its clock once it notices
+                hosted.play_watch.send_replace(());
+                println!("Game {} paused", game_id);
+            }
+            Command::Resume(game_id) => {
+                let hosted = self.hosted(game_id)?;
+                hosted.game.write().await.set_paused(false);
+                // The AIs and the turn timer won't otherwise notice until play passes on
+                hosted.play_watch.send_replace(());
+                println!("Game {} resumed", game_id);
+            }
+            Command::Save { game_id, path } => {
+                let path = path.unwrap_or_else(|| format!("umpire-{}.sav", game_id));
+                self.hosted(game_id)?
+                    .game
+                    .read()
+                    .await
+                    .save(&path)
+                    .map_err(|err| format!("Error saving game {} to {}: {}", game_id, path, err))?;
+                println!("Game {} saved to {}", game_id, path);
+            }
+            Command::Shutdown => self.shutdown.notify_one(),
+            Command::Help => println!("{}", HELP),
+        }
+
+        Ok(())
+    }
+
+    async fn list(&self) {
+        for (game_id, hosted) in self.games.iter() {
+            let game = hosted.game.read().await;
+            let lobby = hosted.lobby.read().unwrap().clone();
+
+            let status = if let Some(game_over) = game.game_over() {
+                format!("over, won by player {}", game_over.victor)
+            } else if !lobby.started() {
+                format!("in the lobby, waiting on {}", lobby.waiting_on())
+            } else {
+                format!(
+                    "turn {}, player {} to play{}",
+                    game.turn(),
+                    game.current_player(),
+                    if game.paused() { ", paused" } else { "" }
+                )
+            };
+            println!("Game {}: {}", game_id, status);
+
+            let controllers = hosted.controllers.read().unwrap();
+            let stood_in_for = hosted.stood_in_for.read().unwrap();
+            for (player, slot) in lobby.slots().iter().enumerate() {
+                let mut row = format!("  Player {}: {}", player, slot);
+                if let Some(name) = &game.player_names()[player] {
+                    row.push_str(format!(" \"{}\"", name).as_str());
+                }
+                if let Some(connection) = controllers[player] {
+                    row.push_str(format!(", connection {}", connection).as_str());
+                }
+                if stood_in_for.contains(&player) {
+                    row.push_str(", played by the stand-in AI");
+                }
+                println!("{}", row);
+            }
+        }
+
+        for (connection, server) in self.connections.read().unwrap().iter() {
+            let role = if *server.spectating.read().unwrap() {
+                String::from("spectating")
+            } else {
+                let players: Vec<String> = server
+                    .known_secrets
+                    .read()
+                    .unwrap()
+                    .iter()
+                    .enumerate()
+                    .filter(|(_, secret)| secret.is_some())
+                    .map(|(player, _)| player.to_string())
+                    .collect();
+                format!("players [{}]", players.join(", "))
+            };
+            println!(
+                "Connection {}: game {}, {}",
+                connection,
+                server.joined(),
+                role
+            );
+        }
+    }
+}
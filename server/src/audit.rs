@@ -0,0 +1,92 @@
+//! A record of every change players make to the games on the server
+//!
+//! Each accepted mutation---who made it, in which game and turn, with what arguments, and what came of it---is
+//! appended to the log as a line of JSON, for tracking down desyncs and looking into suspected cheating after the fact.
+//! Rejected calls change nothing and aren't recorded.
+
+use std::{
+    fs::{File, OpenOptions},
+    io::{Result as IoResult, Write},
+    path::Path,
+    sync::Mutex,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use common::{
+    game::{PlayerNum, TurnNum},
+    lobby::GameID,
+};
+use serde::Serialize;
+
+use crate::ConnectionID;
+
+#[derive(Serialize)]
+struct AuditEntry<'a, A, O> {
+    /// Seconds since the Unix epoch
+    time: f64,
+    game: GameID,
+    connection: ConnectionID,
+    player: PlayerNum,
+
+    /// The turn when the call was made, which the call itself may have ended
+    turn: TurnNum,
+
+    /// The name of the game method called to make the change
+    action: &'a str,
+    args: &'a A,
+    outcome: &'a O,
+}
+
+pub struct AuditLog {
+    file: Mutex<File>,
+}
+
+impl AuditLog {
+    /// Append to the log at `path`, creating it if need be
+    pub fn open<P: AsRef<Path>>(path: P) -> IoResult<Self> {
+        let file = OpenOptions::new().create(true).append(true).open(path)?;
+        Ok(Self {
+            file: Mutex::new(file),
+        })
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    pub fn record<A: Serialize, O: Serialize>(
+        &self,
+        game: GameID,
+        connection: ConnectionID,
+        player: PlayerNum,
+        turn: TurnNum,
+        action: &str,
+        args: &A,
+        outcome: &O,
+    ) {
+        let entry = AuditEntry {
+            time: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map_or(0.0, |since| since.as_secs_f64()),
+            game,
+            connection,
+            player,
+            turn,
+            action,
+            args,
+            outcome,
+        };
+
+        // A failure to record shouldn't take down the game, so it's only reported
+        let mut line = match serde_json::to_string(&entry) {
+            Ok(line) => line,
+            Err(err) => {
+                tracing::error!("Error recording {} in the audit log: {}", action, err);
+                return;
+            }
+        };
+        line.push('\n');
+
+        // The whole line goes in one write, so entries from concurrent calls don't interleave
+        if let Err(err) = self.file.lock().unwrap().write_all(line.as_bytes()) {
+            tracing::error!("Error writing to the audit log: {}", err);
+        }
+    }
+}
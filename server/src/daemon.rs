@@ -0,0 +1,102 @@
+//! Running the server unattended
+//!
+//! As a daemon the server takes no commands on standard input and keeps quiet on standard output. What it has to say
+//! goes out as structured logs instead, one JSON object per line, to a file or to standard error---where the service
+//! manager, journald included, picks them up. A pid file tells others which process to signal.
+
+use std::{
+    fs::{self, OpenOptions},
+    io::Result as IoResult,
+    path::{Path, PathBuf},
+    sync::Mutex,
+};
+
+use tracing::Level;
+use tracing_subscriber::{filter::Targets, layer::SubscriberExt, util::SubscriberInitExt};
+
+/// Send the server's logs where they belong
+///
+/// In the foreground they go to standard output as plain lines, as they always have. As a daemon they're logged as
+/// JSON to `log_file`, appended to, or to standard error if there's none.
+pub fn init_logging(daemon: bool, log_file: Option<&Path>) -> IoResult<()> {
+    // Our own messages, but only warnings from the libraries beneath, tarpc having something to say of every request
+    let filter = Targets::new()
+        .with_target(env!("CARGO_CRATE_NAME"), Level::INFO)
+        .with_default(Level::WARN);
+
+    if !daemon {
+        tracing_subscriber::fmt()
+            .without_time()
+            .with_level(false)
+            .with_target(false)
+            .finish()
+            .with(filter)
+            .init();
+        return Ok(());
+    }
+
+    let logs = tracing_subscriber::fmt().json();
+
+    match log_file {
+        Some(path) => {
+            let file = OpenOptions::new().create(true).append(true).open(path)?;
+            logs.with_writer(Mutex::new(file))
+                .finish()
+                .with(filter)
+                .init();
+        }
+        None => logs
+            .with_writer(std::io::stderr)
+            .finish()
+            .with(filter)
+            .init(),
+    }
+
+    Ok(())
+}
+
+/// A file holding the server's process ID, removed when dropped
+pub struct PidFile {
+    path: PathBuf,
+}
+
+impl PidFile {
+    pub fn create<P: Into<PathBuf>>(path: P) -> IoResult<Self> {
+        let path = path.into();
+        fs::write(&path, format!("{}\n", std::process::id()))?;
+        Ok(Self { path })
+    }
+}
+
+impl Drop for PidFile {
+    fn drop(&mut self) {
+        if let Err(err) = fs::remove_file(&self.path) {
+            tracing::warn!("Could not remove pid file {}: {}", self.path.display(), err);
+        }
+    }
+}
+
+/// Wait until the server is told to terminate, by SIGTERM or, where there's no such thing, Ctrl-C
+pub async fn terminated() {
+    #[cfg(unix)]
+    {
+        use tokio::signal::unix::{signal, SignalKind};
+
+        match signal(SignalKind::terminate()) {
+            Ok(mut sigterm) => {
+                sigterm.recv().await;
+            }
+            Err(err) => {
+                tracing::warn!("Could not listen for SIGTERM: {}", err);
+                std::future::pending::<()>().await;
+            }
+        }
+    }
+
+    #[cfg(not(unix))]
+    {
+        if tokio::signal::ctrl_c().await.is_err() {
+            std::future::pending::<()>().await;
+        }
+    }
+}
@@ -1,6 +1,9 @@
 use std::{
     collections::{BTreeMap, BTreeSet},
+    net::SocketAddr,
+    path::Path,
     sync::{Arc, RwLock as RwLockStd},
+    time::{Duration, Instant},
 };
 
 use common::{
@@ -10,40 +13,69 @@ use common::{
         action::{
             AiPlayerAction, NextCityAction, NextUnitAction, PlayerAction, PlayerActionOutcome,
         },
-        ai::{fX, AiDevice, TrainingFocus},
+        ai::{fX, AISpec, AiBackendKind, AiDevice, TrainingFocus},
+        chat::ChatMessage,
         city::{City, CityID},
+        combat::CombatOdds,
+        delta::{SyncPoint, TurnDelta, TurnSnapshot},
+        diplomacy::DiplomaticStatus,
         error::GameError,
-        map::{gen::MapType, Tile},
+        history::PlayerHistory,
+        map::{
+            gen::{MapType, Symmetry},
+            Improvement, MapData, Tile,
+        },
         move_::Move,
-        obs::{LocatedObs, LocatedObsLite, Obs, ObsTracker},
+        obs::{
+            FogOfWar, LocatedObs, LocatedObsLite, Obs, ObsDiff, ObsRevision, ObsTracker,
+            ObsTrackerI,
+        },
         player::PlayerControl,
         turn_async::TurnTaker,
         unit::{
             orders::{Orders, OrdersResult},
+            stats::{self, UnitStatsRegistry},
             Unit, UnitID, UnitType,
         },
-        ActionNum, Game, IGame, OrdersSet, PlayerNum, PlayerSecret, PlayerType, ProductionCleared,
-        ProductionSet, ProposedActionResult, ProposedOrdersResult, ProposedResult, TurnEnded,
-        TurnNum, TurnPhase, TurnStart, UmpireResult, UnitDisbanded,
+        ActionNum, Bombardment, Game, GameOver, IGame, OrdersSet, PlayerNum, PlayerSecret,
+        PlayerType, ProductionCleared, ProductionSet, ProposedActionResult, ProposedOrdersResult,
+        ProposedResult, RadarBuilt, Resigned, TurnEnded, TurnNum, TurnPhase, TurnStart,
+        UmpireResult, UnitDisbanded, WallsBuilt,
     },
+    lobby::{GameID, Lobby},
     name::{city_namer, unit_namer},
-    rpc::UmpireRpc,
+    rpc::{
+        transport::{self, Compression},
+        UmpireRpc,
+    },
     util::{init_rng, Dims, Direction, Location, Wrap2d},
 };
 
 use anyhow::anyhow;
-use burn::backend::Wgpu;
-use clap::Arg;
+use clap::{Arg, ArgAction};
 use futures::{future, prelude::*};
 use get_if_addrs::get_if_addrs;
 use serde::{Deserialize, Serialize};
 use tarpc::{
     context::Context,
     server::{self, incoming::Incoming, Channel},
-    tokio_serde::formats::Bincode,
 };
-use tokio::sync::RwLock as RwLockTokio;
-use umpire_ai::AI;
+use tokio::{
+    net::{lookup_host, TcpListener},
+    sync::{mpsc, watch, Notify, OwnedSemaphorePermit, RwLock as RwLockTokio, Semaphore},
+};
+use tracing::{info, warn};
+use umpire_ai::AnyBackendAI;
+
+use self::{
+    admin::Admin, audit::AuditLog, daemon::PidFile, spectate::SpectatorFeed, turn_timer::TurnClock,
+};
+
+mod admin;
+mod audit;
+mod daemon;
+mod spectate;
+mod turn_timer;
 
 #[derive(Debug, Deserialize, Serialize)]
 enum ServerEvent {
@@ -61,39 +93,391 @@ enum ServerEvent {
     },
 }
 
+/// One of the games the server hosts
+struct HostedGame {
+    game: Arc<RwLockTokio<Game>>,
+
+    /// Every player's secret, handed out as slots are claimed and checked when players are reclaimed on reconnection
+    secrets: Vec<PlayerSecret>,
+
+    /// The players gathering before the game starts, shared by all connections
+    lobby: Arc<RwLockStd<Lobby>>,
+
+    /// Bumped when the lobby changes, waking clients waiting on it
+    lobby_watch: watch::Sender<()>,
+
+    /// What spectators are shown of the game
+    spectator_feed: Arc<RwLockStd<SpectatorFeed>>,
+
+    player_types: Vec<PlayerType>,
+
+    /// The connection controlling each player, if any, so a lost connection gives up only the players still its own
+    controllers: RwLockStd<Vec<Option<ConnectionID>>>,
+
+    /// The AI to play for human players whose connections are lost once the game is underway, if any
+    stand_in: Option<AISpec>,
+
+    /// How long a human player whose connection was lost has to come back before the stand-in AI takes over
+    stand_in_grace: Duration,
+
+    /// When each human player's connection was lost, if it was once the game was underway and they haven't been
+    /// reclaimed since
+    released_at: RwLockStd<Vec<Option<Instant>>>,
+
+    /// The human players the stand-in AI is playing for until they're reclaimed
+    stood_in_for: Arc<RwLockStd<BTreeSet<PlayerNum>>>,
+
+    /// Bumped when play may pick up other than by passing to the next player: when the game starts or resumes, or a
+    /// player is handed to the stand-in AI
+    ///
+    /// The AIs and the turn timer sleep until then, or until the turn changes, rather than checking over and over.
+    play_watch: watch::Sender<()>,
+
+    /// How much time the turn underway has left, if it's being timed
+    turn_clock: Arc<RwLockStd<Option<TurnClock>>>,
+}
+
+/// Identifies one of the connections the server has served, in the order they were made
+type ConnectionID = usize;
+
 // Implementation of the server API
 #[derive(Clone)]
 struct UmpireServer {
-    game: Arc<RwLockTokio<Game>>,
+    /// Every game on the server, by ID
+    games: Arc<BTreeMap<GameID, HostedGame>>,
+
+    /// The game this connection's requests are about
+    ///
+    /// Shared by all requests on the connection, as joining a game changes it.
+    joined: Arc<RwLockStd<GameID>>,
 
     /// The player secrets for players controlled by this connection will be given, the rest omitted
-    known_secrets: Vec<Option<PlayerSecret>>,
+    ///
+    /// Shared by all requests on the connection, as claiming slots and reconnecting add to it.
+    known_secrets: Arc<RwLockStd<Vec<Option<PlayerSecret>>>>,
 
-    player_types: Vec<PlayerType>,
+    /// Whether this connection is registered as a spectator, which lets it watch but never play
+    spectating: Arc<RwLockStd<bool>>,
+
+    /// The seats spectators take, shared by all connections and kept apart from the room for players
+    spectator_seats: Arc<Semaphore>,
+
+    /// The seat this connection holds while spectating
+    spectator_seat: Arc<RwLockStd<Option<OwnedSemaphorePermit>>>,
+
+    connection: ConnectionID,
+
+    /// When the connection last sent a heartbeat, or was made if it hasn't yet
+    last_heard: Arc<RwLockStd<Instant>>,
+
+    /// Told when the server's operator drops the connection
+    kicked: Arc<Notify>,
+
+    /// What was last sent of each player's units and cities by `player_turn_delta`, to tell what changed since
+    turn_snapshots: Arc<RwLockStd<BTreeMap<PlayerNum, TurnSnapshot>>>,
+
+    /// Where accepted changes to the game are recorded, if anywhere
+    audit_log: Option<Arc<AuditLog>>,
 }
 
-impl UmpireRpc for UmpireServer {
-    /// NOTE This is really aggressive!
-    async fn wait_my_turn(self, _: Context) -> PlayerNum {
+impl UmpireServer {
+    fn joined(&self) -> GameID {
+        *self.joined.read().unwrap()
+    }
+
+    /// The game this connection has joined
+    fn hosted(&self) -> &HostedGame {
+        &self.games[&self.joined()]
+    }
+
+    fn game(&self) -> &RwLockTokio<Game> {
+        &self.hosted().game
+    }
+
+    /// Control `player`, identified by `secret`, on this connection, taking the player back from the stand-in AI
+    fn take_control(&self, player: PlayerNum, secret: PlayerSecret) {
+        self.known_secrets.write().unwrap()[player] = Some(secret);
+        self.hosted().controllers.write().unwrap()[player] = Some(self.connection);
+        self.hosted().released_at.write().unwrap()[player] = None;
+        self.hosted().stood_in_for.write().unwrap().remove(&player);
+    }
+
+    /// Make a change to the joined game by calling `mutation` with `args`, recording it in the audit log if accepted
+    async fn audited<A: Serialize, T: Serialize, E>(
+        &self,
+        action: &str,
+        player_secret: PlayerSecret,
+        args: A,
+        mutation: impl FnOnce(&mut Game, A) -> Result<T, E>,
+    ) -> Result<T, E> {
+        let mut game = self.game().write().await;
+
+        let Some(ref audit_log) = self.audit_log else {
+            return mutation(&mut game, args);
+        };
+
+        // Taken beforehand, as ending a turn changes whose it is
+        let player = game.player_with_secret(player_secret);
+        let turn = game.turn();
+
+        // The arguments are recorded as they came, before the mutation takes them
+        let args_recorded = serde_json::to_value(&args);
+
+        let result = mutation(&mut game, args);
+
+        if let (Ok(outcome), Ok(player), Ok(args)) = (&result, player, args_recorded) {
+            audit_log.record(
+                self.joined(),
+                self.connection,
+                player,
+                turn,
+                action,
+                &args,
+                outcome,
+            );
+        }
+
+        result
+    }
+
+    /// Wait until the connection has gone `timeout` without a heartbeat
+    async fn lost(&self, timeout: Duration) {
         loop {
-            let g = self.game.read().await;
-            let player = g.current_player();
-            if self.known_secrets[player].is_some() {
-                return player;
+            let silence = self.last_heard.read().unwrap().elapsed();
+            if silence >= timeout {
+                return;
+            }
+
+            tokio::time::sleep(timeout - silence).await;
+        }
+    }
+
+    /// Give up the players this connection still controls, its client having gone
+    ///
+    /// Their slots are opened up to be claimed again. If the game is underway, the stand-in AI, if there is one, plays
+    /// for them until they are, once they've had the grace period to come back.
+    fn release_players(&self) {
+        let hosted = self.hosted();
+        let mut controllers = hosted.controllers.write().unwrap();
+        let mut lobby = hosted.lobby.write().unwrap();
+
+        for (player, controller) in controllers.iter_mut().enumerate() {
+            if *controller != Some(self.connection) {
+                continue;
+            }
+
+            *controller = None;
+            lobby.release(player).unwrap();
+            hosted.lobby_watch.send_replace(());
+
+            if lobby.started() && hosted.stand_in.is_some() {
+                let released_at = Instant::now();
+                hosted.released_at.write().unwrap()[player] = Some(released_at);
+                tokio::spawn(stand_in_after_grace(
+                    Arc::clone(&self.games),
+                    self.joined(),
+                    player,
+                    released_at,
+                ));
+                info!(
+                    "Player {} released in game {}; the AI takes over unless they're back within {:?}",
+                    player,
+                    self.joined(),
+                    hosted.stand_in_grace
+                );
+            } else {
+                info!("Player {} released in game {}", player, self.joined());
             }
         }
     }
+}
+
+impl UmpireRpc for UmpireServer {
+    async fn wait_my_turn(self, _: Context, timeout: Duration) -> Option<PlayerNum> {
+        // Watch from before checking whose turn it is, so a turn passing or play resuming between the two isn't missed
+        let mut turns = self.game().read().await.watch_turns();
+        let mut play = self.hosted().play_watch.subscribe();
+
+        let wait = async {
+            loop {
+                {
+                    let game = self.game().read().await;
+                    let player = game.current_player();
+                    // The turn can't begin while play is held
+                    if !game.paused() && self.known_secrets.read().unwrap()[player].is_some() {
+                        return player;
+                    }
+                }
+
+                // Sleep until play passes to the next player or picks up again. The game lives as long as the server,
+                // so the watches can't close
+                tokio::select! {
+                    changed = turns.changed() => changed.unwrap(),
+                    changed = play.changed() => changed.unwrap(),
+                }
+            }
+        };
+
+        tokio::time::timeout(timeout, wait).await.ok()
+    }
+
+    async fn heartbeat(self, _: Context) {
+        *self.last_heard.write().unwrap() = Instant::now();
+    }
 
     async fn player_secrets_known(self, _: Context) -> Vec<Option<PlayerSecret>> {
-        self.known_secrets
+        self.known_secrets.read().unwrap().clone()
+    }
+
+    async fn reconnect(self, _: Context, player_secret: PlayerSecret) -> UmpireResult<PlayerNum> {
+        if *self.spectating.read().unwrap() {
+            return Err(GameError::Spectating);
+        }
+
+        // Only humans connect; the AIs are played here on the server
+        let player = self
+            .hosted()
+            .secrets
+            .iter()
+            .position(|secret| *secret == player_secret)
+            .filter(|player| self.hosted().player_types[*player] == PlayerType::Human)
+            .ok_or(GameError::NoPlayerIdentifiedBySecret)?;
+
+        // The slot was released if the old connection was found to be lost before this one was made
+        {
+            let mut lobby = self.hosted().lobby.write().unwrap();
+            if lobby.open_slots().any(|open| open == player) {
+                lobby.claim(player)?;
+                self.hosted().lobby_watch.send_replace(());
+            }
+        }
+
+        self.take_control(player, player_secret);
+
+        info!("Player {} reconnected to game {}", player, self.joined());
+
+        Ok(player)
+    }
+
+    async fn games(self, _: Context) -> Vec<(GameID, Lobby)> {
+        self.games
+            .iter()
+            .map(|(game_id, hosted)| (*game_id, hosted.lobby.read().unwrap().clone()))
+            .collect()
+    }
+
+    async fn join_game(self, _: Context, game_id: GameID) -> UmpireResult<()> {
+        let hosted = self
+            .games
+            .get(&game_id)
+            .ok_or(GameError::NoSuchGame { id: game_id })?;
+
+        let mut known_secrets = self.known_secrets.write().unwrap();
+
+        // Players can't be carried from one game to another
+        if known_secrets.iter().any(Option::is_some) {
+            return Err(GameError::AlreadyPlaying);
+        }
+
+        *self.joined.write().unwrap() = game_id;
+        *known_secrets = vec![None; hosted.player_types.len()];
+
+        Ok(())
+    }
+
+    async fn spectate(self, _: Context) -> UmpireResult<()> {
+        if self
+            .known_secrets
+            .read()
+            .unwrap()
+            .iter()
+            .any(Option::is_some)
+        {
+            return Err(GameError::AlreadyPlaying);
+        }
+
+        let mut seat = self.spectator_seat.write().unwrap();
+        if seat.is_none() {
+            *seat = Some(
+                Arc::clone(&self.spectator_seats)
+                    .try_acquire_owned()
+                    .map_err(|_| GameError::NoSpectatorSeatsAvailable)?,
+            );
+        }
+
+        *self.spectating.write().unwrap() = true;
+
+        info!("A spectator joined game {}", self.joined());
+
+        Ok(())
+    }
+
+    async fn lobby(self, _: Context) -> Lobby {
+        self.hosted().lobby.read().unwrap().clone()
+    }
+
+    async fn wait_lobby(self, _: Context, seen: Lobby, timeout: Duration) -> Lobby {
+        // Watch from before looking at the lobby, so a change between the two isn't missed
+        let mut changes = self.hosted().lobby_watch.subscribe();
+
+        let wait = async {
+            loop {
+                let lobby = self.hosted().lobby.read().unwrap().clone();
+                if lobby != seen {
+                    return lobby;
+                }
+
+                // Like the turn watch, the lobby watch lasts as long as the server
+                changes.changed().await.unwrap();
+            }
+        };
+
+        tokio::time::timeout(timeout, wait)
+            .await
+            .unwrap_or_else(|_| self.hosted().lobby.read().unwrap().clone())
+    }
+
+    async fn claim_slot(self, _: Context, player: PlayerNum) -> UmpireResult<PlayerSecret> {
+        if *self.spectating.read().unwrap() {
+            return Err(GameError::Spectating);
+        }
+
+        self.hosted().lobby.write().unwrap().claim(player)?;
+        self.hosted().lobby_watch.send_replace(());
+
+        let secret = self.hosted().secrets[player];
+        self.take_control(player, secret);
+
+        info!("Player {} claimed in game {}", player, self.joined());
+
+        Ok(secret)
+    }
+
+    async fn ready(self, _: Context) -> UmpireResult<bool> {
+        let known_secrets = self.known_secrets.read().unwrap().clone();
+        let mut lobby = self.hosted().lobby.write().unwrap();
+
+        for (player, _secret) in known_secrets
+            .iter()
+            .enumerate()
+            .filter(|(_, secret)| secret.is_some())
+        {
+            if lobby.ready(player)? {
+                info!("All players ready; game {} begins", self.joined());
+                self.hosted().play_watch.send_replace(());
+            }
+        }
+        self.hosted().lobby_watch.send_replace(());
+
+        Ok(lobby.started())
     }
 
     async fn player_types(self, _: Context) -> Vec<PlayerType> {
-        self.player_types
+        self.hosted().player_types.clone()
     }
 
     async fn num_players(self, _: Context) -> PlayerNum {
-        self.game.read().await.num_players()
+        self.game().read().await.num_players()
     }
 
     async fn turn_is_done(
@@ -102,11 +486,11 @@ impl UmpireRpc for UmpireServer {
         player: PlayerNum,
         turn: TurnNum,
     ) -> UmpireResult<bool> {
-        self.game.read().await.turn_is_done(player, turn)
+        self.game().read().await.turn_is_done(player, turn)
     }
 
     async fn current_turn_is_done(self, _: Context) -> bool {
-        self.game.read().await.current_turn_is_done()
+        self.game().read().await.current_turn_is_done()
     }
 
     async fn begin_turn(
@@ -115,14 +499,26 @@ impl UmpireRpc for UmpireServer {
         player_secret: PlayerSecret,
         clear_after_unit_production: bool,
     ) -> UmpireResult<TurnStart> {
-        self.game
-            .write()
-            .await
-            .begin_turn(player_secret, clear_after_unit_production)
+        if !self.hosted().lobby.read().unwrap().started() {
+            return Err(GameError::GameNotStarted);
+        }
+
+        self.audited(
+            "begin_turn",
+            player_secret,
+            clear_after_unit_production,
+            |game, clear_after_unit_production| {
+                game.begin_turn(player_secret, clear_after_unit_production)
+            },
+        )
+        .await
     }
 
     async fn end_turn(self, _: Context, player_secret: PlayerSecret) -> UmpireResult<TurnEnded> {
-        self.game.write().await.end_turn(player_secret)
+        self.audited("end_turn", player_secret, (), |game, ()| {
+            game.end_turn(player_secret)
+        })
+        .await
     }
 
     async fn force_end_turn(
@@ -130,11 +526,34 @@ impl UmpireRpc for UmpireServer {
         _: Context,
         player_secret: PlayerSecret,
     ) -> UmpireResult<TurnEnded> {
-        self.game.write().await.force_end_turn(player_secret)
+        self.audited("force_end_turn", player_secret, (), |game, ()| {
+            game.force_end_turn(player_secret)
+        })
+        .await
     }
 
     async fn is_player_turn(self, _: Context, secret: PlayerSecret) -> UmpireResult<bool> {
-        self.game.read().await.is_player_turn(secret)
+        self.game().read().await.is_player_turn(secret)
+    }
+
+    async fn turn_time_remaining(
+        self,
+        _: Context,
+        player_secret: PlayerSecret,
+    ) -> UmpireResult<Option<Duration>> {
+        let game = self.game().read().await;
+        if !game.is_player_turn(player_secret)? {
+            return Err(GameError::NotPlayersTurn {
+                player: game.player_with_secret(player_secret)?,
+            });
+        }
+
+        Ok(self
+            .hosted()
+            .turn_clock
+            .read()
+            .unwrap()
+            .map(TurnClock::remaining))
     }
 
     async fn end_then_begin_turn(
@@ -144,11 +563,19 @@ impl UmpireRpc for UmpireServer {
         next_player_secret: PlayerSecret,
         clear_after_unit_production: bool,
     ) -> UmpireResult<TurnStart> {
-        self.game.write().await.end_then_begin_turn(
+        self.audited(
+            "end_then_begin_turn",
             player_secret,
-            next_player_secret,
-            clear_after_unit_production,
+            (next_player_secret, clear_after_unit_production),
+            |game, (next_player_secret, clear_after_unit_production)| {
+                game.end_then_begin_turn(
+                    player_secret,
+                    next_player_secret,
+                    clear_after_unit_production,
+                )
+            },
         )
+        .await
     }
 
     async fn force_end_then_begin_turn(
@@ -158,11 +585,19 @@ impl UmpireRpc for UmpireServer {
         next_player_secret: PlayerSecret,
         clear_after_unit_production: bool,
     ) -> UmpireResult<TurnStart> {
-        self.game.write().await.force_end_then_begin_turn(
+        self.audited(
+            "force_end_then_begin_turn",
             player_secret,
-            next_player_secret,
-            clear_after_unit_production,
+            (next_player_secret, clear_after_unit_production),
+            |game, (next_player_secret, clear_after_unit_production)| {
+                game.force_end_then_begin_turn(
+                    player_secret,
+                    next_player_secret,
+                    clear_after_unit_production,
+                )
+            },
         )
+        .await
     }
 
     /// The victor---if any---meaning the player who has defeated all other players.
@@ -170,7 +605,15 @@ impl UmpireRpc for UmpireServer {
     /// It is the user's responsibility to check for a victor---the game will continue to function even when somebody
     /// has won.
     async fn victor(self, _: Context) -> Option<PlayerNum> {
-        self.game.read().await.victor()
+        self.game().read().await.victor()
+    }
+
+    async fn game_over(self, _: Context) -> Option<GameOver> {
+        self.game().read().await.game_over()
+    }
+
+    async fn turn_limit(self, _: Context) -> Option<TurnNum> {
+        self.game().read().await.turn_limit()
     }
 
     async fn player_unit_legal_one_step_destinations(
@@ -179,19 +622,31 @@ impl UmpireRpc for UmpireServer {
         player_secret: PlayerSecret,
         unit_id: UnitID,
     ) -> UmpireResult<BTreeSet<Location>> {
-        self.game
+        self.game()
             .read()
             .await
             .player_unit_legal_one_step_destinations(player_secret, unit_id)
     }
 
+    async fn player_unit_reachable_this_turn(
+        self,
+        _: Context,
+        player_secret: PlayerSecret,
+        unit_id: UnitID,
+    ) -> UmpireResult<BTreeSet<Location>> {
+        self.game()
+            .read()
+            .await
+            .player_unit_reachable_this_turn(player_secret, unit_id)
+    }
+
     async fn player_unit_legal_directions(
         self,
         _: Context,
         player_secret: PlayerSecret,
         unit_id: UnitID,
     ) -> UmpireResult<Vec<Direction>> {
-        self.game
+        self.game()
             .read()
             .await
             .player_unit_legal_directions(player_secret, unit_id)
@@ -204,7 +659,7 @@ impl UmpireRpc for UmpireServer {
         player_secret: PlayerSecret,
         loc: Location,
     ) -> UmpireResult<Option<Tile>> {
-        self.game
+        self.game()
             .read()
             .await
             .player_tile(player_secret, loc)
@@ -217,7 +672,7 @@ impl UmpireRpc for UmpireServer {
         player_secret: PlayerSecret,
         loc: Location,
     ) -> UmpireResult<Option<Obs>> {
-        self.game
+        self.game()
             .read()
             .await
             .player_obs(player_secret, loc)
@@ -229,11 +684,58 @@ impl UmpireRpc for UmpireServer {
         _: Context,
         player_secret: PlayerSecret,
     ) -> UmpireResult<ObsTracker> {
-        self.game
+        self.game()
+            .read()
+            .await
+            .player_observations(player_secret)
+            .map(ObsTracker::from)
+    }
+
+    async fn spectator_observations(
+        self,
+        _: Context,
+        viewer: Option<PlayerNum>,
+    ) -> UmpireResult<ObsTracker> {
+        if !*self.spectating.read().unwrap() {
+            return Err(GameError::NotSpectating);
+        }
+
+        let game = self.game().read().await;
+        self.hosted()
+            .spectator_feed
+            .read()
+            .unwrap()
+            .observations(&game, viewer)
+    }
+
+    async fn player_observations_since(
+        self,
+        _: Context,
+        player_secret: PlayerSecret,
+        revision: ObsRevision,
+    ) -> UmpireResult<ObsDiff> {
+        self.game()
             .read()
             .await
             .player_observations(player_secret)
-            .map(|observations| observations.clone())
+            .map(|observations| observations.since(revision))
+    }
+
+    async fn player_turn_delta(
+        self,
+        _: Context,
+        player_secret: PlayerSecret,
+        since: Option<SyncPoint>,
+    ) -> UmpireResult<TurnDelta> {
+        let game = self.game().read().await;
+        let player = game.player_with_secret(player_secret)?;
+
+        let mut turn_snapshots = self.turn_snapshots.write().unwrap();
+        game.player_turn_delta(
+            player_secret,
+            since,
+            turn_snapshots.entry(player).or_default(),
+        )
     }
 
     /// Every city controlled by the player whose secret is provided
@@ -242,7 +744,7 @@ impl UmpireRpc for UmpireServer {
         _: Context,
         player_secret: PlayerSecret,
     ) -> UmpireResult<Vec<City>> {
-        self.game
+        self.game()
             .read()
             .await
             .player_cities(player_secret)
@@ -255,7 +757,7 @@ impl UmpireRpc for UmpireServer {
         _: Context,
         player_secret: PlayerSecret,
     ) -> UmpireResult<Vec<City>> {
-        self.game
+        self.game()
             .read()
             .await
             .player_cities_with_production_target(player_secret)
@@ -267,7 +769,7 @@ impl UmpireRpc for UmpireServer {
         _: Context,
         player_secret: PlayerSecret,
     ) -> UmpireResult<usize> {
-        self.game.read().await.player_city_count(player_secret)
+        self.game().read().await.player_city_count(player_secret)
     }
 
     async fn player_cities_producing_or_not_ignored(
@@ -275,7 +777,7 @@ impl UmpireRpc for UmpireServer {
         _: Context,
         player_secret: PlayerSecret,
     ) -> UmpireResult<usize> {
-        self.game
+        self.game()
             .read()
             .await
             .player_cities_producing_or_not_ignored(player_secret)
@@ -286,7 +788,7 @@ impl UmpireRpc for UmpireServer {
         _: Context,
         player_secret: PlayerSecret,
     ) -> UmpireResult<Vec<Unit>> {
-        self.game
+        self.game()
             .read()
             .await
             .player_units(player_secret)
@@ -298,7 +800,7 @@ impl UmpireRpc for UmpireServer {
         _: Context,
         player_secret: PlayerSecret,
     ) -> UmpireResult<BTreeMap<UnitType, usize>> {
-        self.game
+        self.game()
             .read()
             .await
             .player_unit_type_counts(player_secret)
@@ -311,7 +813,7 @@ impl UmpireRpc for UmpireServer {
         player_secret: PlayerSecret,
         loc: Location,
     ) -> UmpireResult<Option<City>> {
-        self.game
+        self.game()
             .read()
             .await
             .player_city_by_loc(player_secret, loc)
@@ -324,7 +826,7 @@ impl UmpireRpc for UmpireServer {
         player_secret: PlayerSecret,
         city_id: CityID,
     ) -> UmpireResult<Option<City>> {
-        self.game
+        self.game()
             .read()
             .await
             .player_city_by_id(player_secret, city_id)
@@ -337,7 +839,7 @@ impl UmpireRpc for UmpireServer {
         player_secret: PlayerSecret,
         id: UnitID,
     ) -> UmpireResult<Option<Unit>> {
-        self.game
+        self.game()
             .read()
             .await
             .player_unit_by_id(player_secret, id)
@@ -350,7 +852,7 @@ impl UmpireRpc for UmpireServer {
         player_secret: PlayerSecret,
         id: UnitID,
     ) -> UmpireResult<Option<Location>> {
-        self.game.read().await.player_unit_loc(player_secret, id)
+        self.game().read().await.player_unit_loc(player_secret, id)
     }
 
     async fn player_toplevel_unit_by_loc(
@@ -359,7 +861,7 @@ impl UmpireRpc for UmpireServer {
         player_secret: PlayerSecret,
         loc: Location,
     ) -> UmpireResult<Option<Unit>> {
-        self.game
+        self.game()
             .read()
             .await
             .player_toplevel_unit_by_loc(player_secret, loc)
@@ -371,7 +873,7 @@ impl UmpireRpc for UmpireServer {
         _: Context,
         player_secret: PlayerSecret,
     ) -> UmpireResult<Vec<Location>> {
-        self.game
+        self.game()
             .read()
             .await
             .player_production_set_requests(player_secret)
@@ -383,7 +885,7 @@ impl UmpireRpc for UmpireServer {
         _: Context,
         player_secret: PlayerSecret,
     ) -> UmpireResult<Vec<UnitID>> {
-        self.game
+        self.game()
             .read()
             .await
             .player_unit_orders_requests(player_secret)
@@ -395,7 +897,7 @@ impl UmpireRpc for UmpireServer {
         _: Context,
         player_secret: PlayerSecret,
     ) -> UmpireResult<Vec<Unit>> {
-        self.game
+        self.game()
             .read()
             .await
             .player_units_with_orders_requests(player_secret)
@@ -407,7 +909,7 @@ impl UmpireRpc for UmpireServer {
         _: Context,
         player_secret: PlayerSecret,
     ) -> UmpireResult<Vec<UnitID>> {
-        self.game
+        self.game()
             .read()
             .await
             .player_units_with_pending_orders(player_secret)
@@ -419,7 +921,7 @@ impl UmpireRpc for UmpireServer {
         _: Context,
         player_secret: PlayerSecret,
     ) -> UmpireResult<BTreeSet<NextUnitAction>> {
-        self.game
+        self.game()
             .read()
             .await
             .player_next_unit_legal_actions(player_secret)
@@ -430,7 +932,7 @@ impl UmpireRpc for UmpireServer {
         _: Context,
         player_secret: PlayerSecret,
     ) -> UmpireResult<BTreeSet<NextCityAction>> {
-        self.game
+        self.game()
             .read()
             .await
             .player_next_city_legal_actions(player_secret)
@@ -445,10 +947,13 @@ impl UmpireRpc for UmpireServer {
         unit_id: UnitID,
         dest: Location,
     ) -> UmpireResult<Move> {
-        self.game
-            .write()
-            .await
-            .move_toplevel_unit_by_id(player_secret, unit_id, dest)
+        self.audited(
+            "move_toplevel_unit_by_id",
+            player_secret,
+            (unit_id, dest),
+            |game, (unit_id, dest)| game.move_toplevel_unit_by_id(player_secret, unit_id, dest),
+        )
+        .await
     }
 
     async fn move_toplevel_unit_by_id_avoiding_combat(
@@ -458,10 +963,15 @@ impl UmpireRpc for UmpireServer {
         unit_id: UnitID,
         dest: Location,
     ) -> UmpireResult<Move> {
-        self.game
-            .write()
-            .await
-            .move_toplevel_unit_by_id_avoiding_combat(player_secret, unit_id, dest)
+        self.audited(
+            "move_toplevel_unit_by_id_avoiding_combat",
+            player_secret,
+            (unit_id, dest),
+            |game, (unit_id, dest)| {
+                game.move_toplevel_unit_by_id_avoiding_combat(player_secret, unit_id, dest)
+            },
+        )
+        .await
     }
 
     async fn move_toplevel_unit_by_loc(
@@ -471,10 +981,13 @@ impl UmpireRpc for UmpireServer {
         src: Location,
         dest: Location,
     ) -> UmpireResult<Move> {
-        self.game
-            .write()
-            .await
-            .move_toplevel_unit_by_loc(player_secret, src, dest)
+        self.audited(
+            "move_toplevel_unit_by_loc",
+            player_secret,
+            (src, dest),
+            |game, (src, dest)| game.move_toplevel_unit_by_loc(player_secret, src, dest),
+        )
+        .await
     }
 
     async fn move_toplevel_unit_by_loc_avoiding_combat(
@@ -484,10 +997,15 @@ impl UmpireRpc for UmpireServer {
         src: Location,
         dest: Location,
     ) -> UmpireResult<Move> {
-        self.game
-            .write()
-            .await
-            .move_toplevel_unit_by_loc_avoiding_combat(player_secret, src, dest)
+        self.audited(
+            "move_toplevel_unit_by_loc_avoiding_combat",
+            player_secret,
+            (src, dest),
+            |game, (src, dest)| {
+                game.move_toplevel_unit_by_loc_avoiding_combat(player_secret, src, dest)
+            },
+        )
+        .await
     }
 
     async fn move_unit_by_id_in_direction(
@@ -497,10 +1015,13 @@ impl UmpireRpc for UmpireServer {
         id: UnitID,
         direction: Direction,
     ) -> UmpireResult<Move> {
-        self.game
-            .write()
-            .await
-            .move_unit_by_id_in_direction(player_secret, id, direction)
+        self.audited(
+            "move_unit_by_id_in_direction",
+            player_secret,
+            (id, direction),
+            |game, (id, direction)| game.move_unit_by_id_in_direction(player_secret, id, direction),
+        )
+        .await
     }
 
     async fn move_unit_by_id(
@@ -510,10 +1031,13 @@ impl UmpireRpc for UmpireServer {
         unit_id: UnitID,
         dest: Location,
     ) -> UmpireResult<Move> {
-        self.game
-            .write()
-            .await
-            .move_unit_by_id(player_secret, unit_id, dest)
+        self.audited(
+            "move_unit_by_id",
+            player_secret,
+            (unit_id, dest),
+            |game, (unit_id, dest)| game.move_unit_by_id(player_secret, unit_id, dest),
+        )
+        .await
     }
 
     async fn propose_move_unit_by_id(
@@ -523,7 +1047,7 @@ impl UmpireRpc for UmpireServer {
         id: UnitID,
         dest: Location,
     ) -> ProposedResult<Move, GameError> {
-        self.game
+        self.game()
             .read()
             .await
             .propose_move_unit_by_id(player_secret, id, dest)
@@ -536,10 +1060,13 @@ impl UmpireRpc for UmpireServer {
         id: UnitID,
         dest: Location,
     ) -> UmpireResult<Move> {
-        self.game
-            .write()
-            .await
-            .move_unit_by_id_avoiding_combat(player_secret, id, dest)
+        self.audited(
+            "move_unit_by_id_avoiding_combat",
+            player_secret,
+            (id, dest),
+            |game, (id, dest)| game.move_unit_by_id_avoiding_combat(player_secret, id, dest),
+        )
+        .await
     }
 
     async fn propose_move_unit_by_id_avoiding_combat(
@@ -549,109 +1076,392 @@ impl UmpireRpc for UmpireServer {
         id: UnitID,
         dest: Location,
     ) -> ProposedResult<Move, GameError> {
-        self.game
+        self.game()
             .read()
             .await
             .propose_move_unit_by_id_avoiding_combat(player_secret, id, dest)
     }
 
-    async fn disband_unit_by_id(
+    async fn combat_odds(
         self,
         _: Context,
         player_secret: PlayerSecret,
-        id: UnitID,
-    ) -> UmpireResult<UnitDisbanded> {
-        self.game
-            .write()
+        attacker_id: UnitID,
+        defender_loc: Location,
+    ) -> UmpireResult<CombatOdds> {
+        self.game()
+            .read()
             .await
-            .disband_unit_by_id(player_secret, id)
+            .combat_odds(player_secret, attacker_id, defender_loc)
     }
 
-    /// Sets the production of the current player's city at location `loc` to `production`.
-    ///
-    /// Returns GameError::NoCityAtLocation if no city belonging to the current player exists at that location.
-    async fn set_production_by_loc(
+    async fn load_unit(
         self,
         _: Context,
         player_secret: PlayerSecret,
-        loc: Location,
-        production: UnitType,
-    ) -> UmpireResult<ProductionSet> {
-        self.game
-            .write()
-            .await
-            .set_production_by_loc(player_secret, loc, production)
+        unit_id: UnitID,
+        carrier_id: UnitID,
+    ) -> UmpireResult<Move> {
+        self.audited(
+            "load_unit",
+            player_secret,
+            (unit_id, carrier_id),
+            |game, (unit_id, carrier_id)| game.load_unit(player_secret, unit_id, carrier_id),
+        )
+        .await
     }
 
-    /// Sets the production of the current player's city with ID `city_id` to `production`.
-    ///
-    /// Returns GameError::NoCityAtLocation if no city with the given ID belongs to the current player.
-    async fn set_production_by_id(
+    async fn unload_unit(
         self,
         _: Context,
         player_secret: PlayerSecret,
-        city_id: CityID,
-        production: UnitType,
-    ) -> UmpireResult<ProductionSet> {
-        self.game
-            .write()
-            .await
-            .set_production_by_id(player_secret, city_id, production)
+        unit_id: UnitID,
+        dest: Location,
+    ) -> UmpireResult<Move> {
+        self.audited(
+            "unload_unit",
+            player_secret,
+            (unit_id, dest),
+            |game, (unit_id, dest)| game.unload_unit(player_secret, unit_id, dest),
+        )
+        .await
     }
 
-    async fn clear_production(
+    async fn rebase_unit(
         self,
         _: Context,
         player_secret: PlayerSecret,
-        loc: Location,
-        ignore_cleared_production: bool,
-    ) -> UmpireResult<ProductionCleared> {
-        self.game
-            .write()
-            .await
-            .clear_production(player_secret, loc, ignore_cleared_production)
+        unit_id: UnitID,
+        base: Location,
+    ) -> UmpireResult<Move> {
+        self.audited(
+            "rebase_unit",
+            player_secret,
+            (unit_id, base),
+            |game, (unit_id, base)| game.rebase_unit(player_secret, unit_id, base),
+        )
+        .await
     }
 
-    async fn clear_productions(
+    async fn player_air_bases(
         self,
         _: Context,
         player_secret: PlayerSecret,
-        ignore_cleared_production: bool,
-    ) -> UmpireResult<Vec<ProductionCleared>> {
-        self.game
-            .write()
+        unit_id: UnitID,
+    ) -> UmpireResult<Vec<Location>> {
+        self.game()
+            .read()
             .await
-            .clear_productions(player_secret, ignore_cleared_production)
-            .map(|prods_cleared| prods_cleared.collect())
-    }
-
-    async fn turn(self, _: Context) -> TurnNum {
-        self.game.read().await.turn()
-    }
-
-    async fn turn_phase(self, _: Context) -> TurnPhase {
-        self.game.read().await.turn_phase()
+            .player_air_bases(player_secret, unit_id)
     }
 
-    async fn player_action(
+    async fn bombard(
         self,
         _: Context,
         player_secret: PlayerSecret,
-    ) -> UmpireResult<ActionNum> {
-        self.game.read().await.player_action(player_secret)
+        unit_id: UnitID,
+        target: Location,
+    ) -> UmpireResult<Bombardment> {
+        self.audited(
+            "bombard",
+            player_secret,
+            (unit_id, target),
+            |game, (unit_id, target)| game.bombard(player_secret, unit_id, target),
+        )
+        .await
     }
 
-    async fn current_player(self, _: Context) -> PlayerNum {
-        self.game.read().await.current_player()
+    async fn player_unit_bombard_targets(
+        self,
+        _: Context,
+        player_secret: PlayerSecret,
+        unit_id: UnitID,
+    ) -> UmpireResult<Vec<Location>> {
+        self.game()
+            .read()
+            .await
+            .player_unit_bombard_targets(player_secret, unit_id)
+    }
+
+    async fn paradrop(
+        self,
+        _: Context,
+        player_secret: PlayerSecret,
+        unit_id: UnitID,
+        dest: Location,
+    ) -> UmpireResult<Move> {
+        self.audited(
+            "paradrop",
+            player_secret,
+            (unit_id, dest),
+            |game, (unit_id, dest)| game.paradrop(player_secret, unit_id, dest),
+        )
+        .await
+    }
+
+    async fn player_unit_drop_zones(
+        self,
+        _: Context,
+        player_secret: PlayerSecret,
+        unit_id: UnitID,
+    ) -> UmpireResult<Vec<Location>> {
+        self.game()
+            .read()
+            .await
+            .player_unit_drop_zones(player_secret, unit_id)
+    }
+
+    async fn lay_mines(
+        self,
+        _: Context,
+        player_secret: PlayerSecret,
+        unit_id: UnitID,
+    ) -> UmpireResult<Location> {
+        self.audited("lay_mines", player_secret, unit_id, |game, unit_id| {
+            game.lay_mines(player_secret, unit_id)
+        })
+        .await
+    }
+
+    async fn sweep_mines(
+        self,
+        _: Context,
+        player_secret: PlayerSecret,
+        unit_id: UnitID,
+    ) -> UmpireResult<Vec<Location>> {
+        self.audited("sweep_mines", player_secret, unit_id, |game, unit_id| {
+            game.sweep_mines(player_secret, unit_id)
+        })
+        .await
+    }
+
+    async fn player_known_minefields(
+        self,
+        _: Context,
+        player_secret: PlayerSecret,
+    ) -> UmpireResult<Vec<Location>> {
+        self.game()
+            .read()
+            .await
+            .player_known_minefields(player_secret)
+    }
+
+    async fn diplomatic_status(
+        self,
+        _: Context,
+        player_secret: PlayerSecret,
+        other: PlayerNum,
+    ) -> UmpireResult<DiplomaticStatus> {
+        self.game()
+            .read()
+            .await
+            .diplomatic_status(player_secret, other)
+    }
+
+    async fn treaty_proposals(
+        self,
+        _: Context,
+        player_secret: PlayerSecret,
+    ) -> UmpireResult<Vec<(PlayerNum, DiplomaticStatus)>> {
+        self.game().read().await.treaty_proposals(player_secret)
+    }
+
+    async fn propose_treaty(
+        self,
+        _: Context,
+        player_secret: PlayerSecret,
+        other: PlayerNum,
+        status: DiplomaticStatus,
+    ) -> UmpireResult<()> {
+        self.audited(
+            "propose_treaty",
+            player_secret,
+            (other, status),
+            |game, (other, status)| game.propose_treaty(player_secret, other, status),
+        )
+        .await
+    }
+
+    async fn accept_treaty(
+        self,
+        _: Context,
+        player_secret: PlayerSecret,
+        other: PlayerNum,
+    ) -> UmpireResult<DiplomaticStatus> {
+        self.audited("accept_treaty", player_secret, other, |game, other| {
+            game.accept_treaty(player_secret, other)
+        })
+        .await
+    }
+
+    async fn declare_war(
+        self,
+        _: Context,
+        player_secret: PlayerSecret,
+        other: PlayerNum,
+    ) -> UmpireResult<DiplomaticStatus> {
+        self.audited("declare_war", player_secret, other, |game, other| {
+            game.declare_war(player_secret, other)
+        })
+        .await
+    }
+
+    async fn resign(self, _: Context, player_secret: PlayerSecret) -> UmpireResult<Resigned> {
+        self.audited("resign", player_secret, (), |game, ()| {
+            game.resign(player_secret)
+        })
+        .await
+    }
+
+    async fn disband_unit_by_id(
+        self,
+        _: Context,
+        player_secret: PlayerSecret,
+        id: UnitID,
+    ) -> UmpireResult<UnitDisbanded> {
+        self.audited("disband_unit_by_id", player_secret, id, |game, id| {
+            game.disband_unit_by_id(player_secret, id)
+        })
+        .await
+    }
+
+    /// Sets the production of the current player's city at location `loc` to `production`.
+    ///
+    /// Returns GameError::NoCityAtLocation if no city belonging to the current player exists at that location.
+    async fn set_production_by_loc(
+        self,
+        _: Context,
+        player_secret: PlayerSecret,
+        loc: Location,
+        production: UnitType,
+    ) -> UmpireResult<ProductionSet> {
+        self.audited(
+            "set_production_by_loc",
+            player_secret,
+            (loc, production),
+            |game, (loc, production)| game.set_production_by_loc(player_secret, loc, production),
+        )
+        .await
+    }
+
+    /// Sets the production of the current player's city with ID `city_id` to `production`.
+    ///
+    /// Returns GameError::NoCityAtLocation if no city with the given ID belongs to the current player.
+    async fn set_production_by_id(
+        self,
+        _: Context,
+        player_secret: PlayerSecret,
+        city_id: CityID,
+        production: UnitType,
+    ) -> UmpireResult<ProductionSet> {
+        self.audited(
+            "set_production_by_id",
+            player_secret,
+            (city_id, production),
+            |game, (city_id, production)| {
+                game.set_production_by_id(player_secret, city_id, production)
+            },
+        )
+        .await
+    }
+
+    async fn build_city_walls(
+        self,
+        _: Context,
+        player_secret: PlayerSecret,
+        city_id: CityID,
+    ) -> UmpireResult<WallsBuilt> {
+        self.audited(
+            "build_city_walls",
+            player_secret,
+            city_id,
+            |game, city_id| game.build_city_walls(player_secret, city_id),
+        )
+        .await
+    }
+
+    async fn build_city_radar(
+        self,
+        _: Context,
+        player_secret: PlayerSecret,
+        city_id: CityID,
+    ) -> UmpireResult<RadarBuilt> {
+        self.audited(
+            "build_city_radar",
+            player_secret,
+            city_id,
+            |game, city_id| game.build_city_radar(player_secret, city_id),
+        )
+        .await
+    }
+
+    async fn clear_production(
+        self,
+        _: Context,
+        player_secret: PlayerSecret,
+        loc: Location,
+        ignore_cleared_production: bool,
+    ) -> UmpireResult<ProductionCleared> {
+        self.audited(
+            "clear_production",
+            player_secret,
+            (loc, ignore_cleared_production),
+            |game, (loc, ignore_cleared_production)| {
+                game.clear_production(player_secret, loc, ignore_cleared_production)
+            },
+        )
+        .await
+    }
+
+    async fn clear_productions(
+        self,
+        _: Context,
+        player_secret: PlayerSecret,
+        ignore_cleared_production: bool,
+    ) -> UmpireResult<Vec<ProductionCleared>> {
+        self.audited(
+            "clear_productions",
+            player_secret,
+            ignore_cleared_production,
+            |game, ignore_cleared_production| {
+                game.clear_productions(player_secret, ignore_cleared_production)
+            },
+        )
+        .await
+        .map(|prods_cleared| prods_cleared.collect())
+    }
+
+    async fn turn(self, _: Context) -> TurnNum {
+        self.game().read().await.turn()
+    }
+
+    async fn turn_phase(self, _: Context) -> TurnPhase {
+        self.game().read().await.turn_phase()
+    }
+
+    async fn player_action(
+        self,
+        _: Context,
+        player_secret: PlayerSecret,
+    ) -> UmpireResult<ActionNum> {
+        self.game().read().await.player_action(player_secret)
+    }
+
+    async fn current_player(self, _: Context) -> PlayerNum {
+        self.game().read().await.current_player()
+    }
+
+    async fn paused(self, _: Context) -> bool {
+        self.game().read().await.paused()
     }
 
     /// The logical dimensions of the game map
     async fn dims(self, _: Context) -> Dims {
-        self.game.read().await.dims()
+        self.game().read().await.dims()
     }
 
     async fn wrapping(self, _: Context) -> Wrap2d {
-        self.game.read().await.wrapping()
+        self.game().read().await.wrapping()
     }
 
     /// Units that could be produced by a city located at the given location
@@ -661,7 +1471,7 @@ impl UmpireRpc for UmpireServer {
         player_secret: PlayerSecret,
         loc: Location,
     ) -> UmpireResult<Vec<UnitType>> {
-        self.game
+        self.game()
             .read()
             .await
             .valid_productions(player_secret, loc)
@@ -676,7 +1486,7 @@ impl UmpireRpc for UmpireServer {
         player_secret: PlayerSecret,
         loc: Location,
     ) -> UmpireResult<Vec<UnitType>> {
-        self.game
+        self.game()
             .read()
             .await
             .valid_productions_conservative(player_secret, loc)
@@ -690,10 +1500,13 @@ impl UmpireRpc for UmpireServer {
         player_secret: PlayerSecret,
         unit_id: UnitID,
     ) -> UmpireResult<OrdersSet> {
-        self.game
-            .write()
-            .await
-            .order_unit_sentry(player_secret, unit_id)
+        self.audited(
+            "order_unit_sentry",
+            player_secret,
+            unit_id,
+            |game, unit_id| game.order_unit_sentry(player_secret, unit_id),
+        )
+        .await
     }
 
     async fn order_unit_skip(
@@ -702,10 +1515,13 @@ impl UmpireRpc for UmpireServer {
         player_secret: PlayerSecret,
         unit_id: UnitID,
     ) -> UmpireResult<OrdersSet> {
-        self.game
-            .write()
-            .await
-            .order_unit_skip(player_secret, unit_id)
+        self.audited(
+            "order_unit_skip",
+            player_secret,
+            unit_id,
+            |game, unit_id| game.order_unit_skip(player_secret, unit_id),
+        )
+        .await
     }
 
     async fn order_unit_go_to(
@@ -715,10 +1531,13 @@ impl UmpireRpc for UmpireServer {
         unit_id: UnitID,
         dest: Location,
     ) -> OrdersResult {
-        self.game
-            .write()
-            .await
-            .order_unit_go_to(player_secret, unit_id, dest)
+        self.audited(
+            "order_unit_go_to",
+            player_secret,
+            (unit_id, dest),
+            |game, (unit_id, dest)| game.order_unit_go_to(player_secret, unit_id, dest),
+        )
+        .await
     }
 
     async fn propose_order_unit_go_to(
@@ -728,7 +1547,7 @@ impl UmpireRpc for UmpireServer {
         unit_id: UnitID,
         dest: Location,
     ) -> ProposedOrdersResult {
-        self.game
+        self.game()
             .read()
             .await
             .propose_order_unit_go_to(player_secret, unit_id, dest)
@@ -740,10 +1559,13 @@ impl UmpireRpc for UmpireServer {
         player_secret: PlayerSecret,
         unit_id: UnitID,
     ) -> OrdersResult {
-        self.game
-            .write()
-            .await
-            .order_unit_explore(player_secret, unit_id)
+        self.audited(
+            "order_unit_explore",
+            player_secret,
+            unit_id,
+            |game, unit_id| game.order_unit_explore(player_secret, unit_id),
+        )
+        .await
     }
 
     async fn propose_order_unit_explore(
@@ -752,12 +1574,124 @@ impl UmpireRpc for UmpireServer {
         player_secret: PlayerSecret,
         unit_id: UnitID,
     ) -> ProposedOrdersResult {
-        self.game
+        self.game()
             .read()
             .await
             .propose_order_unit_explore(player_secret, unit_id)
     }
 
+    async fn order_unit_escort(
+        self,
+        _: Context,
+        player_secret: PlayerSecret,
+        unit_id: UnitID,
+        target: UnitID,
+    ) -> OrdersResult {
+        self.audited(
+            "order_unit_escort",
+            player_secret,
+            (unit_id, target),
+            |game, (unit_id, target)| game.order_unit_escort(player_secret, unit_id, target),
+        )
+        .await
+    }
+
+    async fn propose_order_unit_escort(
+        self,
+        _: Context,
+        player_secret: PlayerSecret,
+        unit_id: UnitID,
+        target: UnitID,
+    ) -> ProposedOrdersResult {
+        self.game()
+            .read()
+            .await
+            .propose_order_unit_escort(player_secret, unit_id, target)
+    }
+
+    async fn order_unit_build(
+        self,
+        _: Context,
+        player_secret: PlayerSecret,
+        unit_id: UnitID,
+        improvement: Improvement,
+    ) -> OrdersResult {
+        self.audited(
+            "order_unit_build",
+            player_secret,
+            (unit_id, improvement),
+            |game, (unit_id, improvement)| {
+                game.order_unit_build(player_secret, unit_id, improvement)
+            },
+        )
+        .await
+    }
+
+    async fn propose_order_unit_build(
+        self,
+        _: Context,
+        player_secret: PlayerSecret,
+        unit_id: UnitID,
+        improvement: Improvement,
+    ) -> ProposedOrdersResult {
+        self.game()
+            .read()
+            .await
+            .propose_order_unit_build(player_secret, unit_id, improvement)
+    }
+
+    async fn unit_groups(
+        self,
+        _: Context,
+        player_secret: PlayerSecret,
+    ) -> UmpireResult<Vec<(String, Vec<UnitID>)>> {
+        self.game().read().await.unit_groups(player_secret)
+    }
+
+    async fn form_unit_group(
+        self,
+        _: Context,
+        player_secret: PlayerSecret,
+        name: String,
+        unit_ids: Vec<UnitID>,
+    ) -> UmpireResult<()> {
+        self.audited(
+            "form_unit_group",
+            player_secret,
+            (name, unit_ids),
+            |game, (name, unit_ids)| game.form_unit_group(player_secret, name, unit_ids),
+        )
+        .await
+    }
+
+    async fn disband_unit_group(
+        self,
+        _: Context,
+        player_secret: PlayerSecret,
+        name: String,
+    ) -> UmpireResult<Vec<UnitID>> {
+        self.audited("disband_unit_group", player_secret, name, |game, name| {
+            game.disband_unit_group(player_secret, name)
+        })
+        .await
+    }
+
+    async fn order_unit_group(
+        self,
+        _: Context,
+        player_secret: PlayerSecret,
+        name: String,
+        orders: Orders,
+    ) -> UmpireResult<Vec<OrdersResult>> {
+        self.audited(
+            "order_unit_group",
+            player_secret,
+            (name, orders),
+            |game, (name, orders)| game.order_unit_group(player_secret, name, orders),
+        )
+        .await
+    }
+
     /// If a unit at the location owned by the current player exists, activate it and any units it carries
     async fn activate_unit_by_loc(
         self,
@@ -765,10 +1699,10 @@ impl UmpireRpc for UmpireServer {
         player_secret: PlayerSecret,
         loc: Location,
     ) -> UmpireResult<LocatedObsLite> {
-        self.game
-            .write()
-            .await
-            .activate_unit_by_loc(player_secret, loc)
+        self.audited("activate_unit_by_loc", player_secret, loc, |game, loc| {
+            game.activate_unit_by_loc(player_secret, loc)
+        })
+        .await
     }
 
     async fn set_orders(
@@ -778,10 +1712,13 @@ impl UmpireRpc for UmpireServer {
         id: UnitID,
         orders: Orders,
     ) -> UmpireResult<OrdersSet> {
-        self.game
-            .write()
-            .await
-            .set_orders(player_secret, id, orders)
+        self.audited(
+            "set_orders",
+            player_secret,
+            (id, orders),
+            |game, (id, orders)| game.set_orders(player_secret, id, orders),
+        )
+        .await
     }
 
     async fn clear_orders(
@@ -790,7 +1727,10 @@ impl UmpireRpc for UmpireServer {
         player_secret: PlayerSecret,
         id: UnitID,
     ) -> UmpireResult<Option<Orders>> {
-        self.game.write().await.clear_orders(player_secret, id)
+        self.audited("clear_orders", player_secret, id, |game, id| {
+            game.clear_orders(player_secret, id)
+        })
+        .await
     }
 
     async fn propose_set_and_follow_orders(
@@ -800,7 +1740,7 @@ impl UmpireRpc for UmpireServer {
         id: UnitID,
         orders: Orders,
     ) -> ProposedOrdersResult {
-        self.game
+        self.game()
             .read()
             .await
             .propose_set_and_follow_orders(player_secret, id, orders)
@@ -813,52 +1753,116 @@ impl UmpireRpc for UmpireServer {
         id: UnitID,
         orders: Orders,
     ) -> OrdersResult {
-        self.game
-            .write()
-            .await
-            .set_and_follow_orders(player_secret, id, orders)
+        self.audited(
+            "set_and_follow_orders",
+            player_secret,
+            (id, orders),
+            |game, (id, orders)| game.set_and_follow_orders(player_secret, id, orders),
+        )
+        .await
     }
 
-    /// Feature vector for use in AI training
-    ///
-    /// Map of the output vector:
-    ///
-    /// # 15: 1d features
-    /// * 1: current turn
-    /// * 1: current player city count
-    /// * 1: number of tiles observed by current player
-    /// * 1: percentage of tiles observed by current player
-    /// * 11: the type of unit being represented, where "city" is also a type of unit (one hot encoded)
-    /// * 10: number of units controlled by current player (infantry, armor, fighters, bombers, transports, destroyers
-    ///                                                     submarines, cruisers, battleships, carriers)
-    /// # 363: 2d features, three layers
-    /// * 121: is_enemy_belligerent (11x11)
-    /// * 121: is_observed (11x11)
-    /// * 121: is_neutral (11x11)
+    /// Feature vector for use in AI training, its 2d features covering a window `window` tiles wide
     ///
+    /// See `Game::player_features` for the map of the output vector.
     async fn player_features(
         self,
         _: Context,
         player_secret: PlayerSecret,
         focus: TrainingFocus,
+        window: usize,
     ) -> UmpireResult<Vec<fX>> {
-        self.game.read().await.player_features(player_secret, focus)
+        self.game()
+            .read()
+            .await
+            .player_features(player_secret, focus, window)
     }
 
     async fn current_player_score(self, _: Context) -> f64 {
-        self.game.read().await.current_player_score()
+        self.game().read().await.current_player_score()
     }
 
     async fn player_score(self, _: Context, player_secret: PlayerSecret) -> UmpireResult<f64> {
-        self.game.read().await.player_score(player_secret)
+        self.game().read().await.player_score(player_secret)
     }
 
     async fn player_score_by_idx(self, _: Context, player: PlayerNum) -> UmpireResult<f64> {
-        self.game.read().await.player_score_by_idx(player)
+        self.game().read().await.player_score_by_idx(player)
     }
 
     async fn player_scores(self, _: Context) -> Vec<f64> {
-        self.game.read().await.player_scores()
+        self.game().read().await.player_scores()
+    }
+
+    async fn player_histories(self, _: Context) -> Vec<PlayerHistory> {
+        self.game().read().await.player_histories()
+    }
+
+    async fn send_chat_message(
+        self,
+        _: Context,
+        player_secret: PlayerSecret,
+        text: String,
+    ) -> UmpireResult<()> {
+        self.audited("send_chat_message", player_secret, text, |game, text| {
+            game.send_chat_message(player_secret, text)
+        })
+        .await
+    }
+
+    async fn chat_messages_since(
+        self,
+        _: Context,
+        player_secret: PlayerSecret,
+        since: usize,
+    ) -> UmpireResult<Vec<ChatMessage>> {
+        self.game()
+            .read()
+            .await
+            .chat_messages_since(player_secret, since)
+    }
+
+    async fn wait_chat_messages(
+        self,
+        _: Context,
+        player_secret: PlayerSecret,
+        since: usize,
+        timeout: Duration,
+    ) -> UmpireResult<Vec<ChatMessage>> {
+        // Watch from before checking for messages, so one sent between the two isn't missed
+        let mut chat = self.game().read().await.watch_chat();
+
+        let wait = async {
+            loop {
+                let messages = self
+                    .game()
+                    .read()
+                    .await
+                    .chat_messages_since(player_secret, since)?;
+                if !messages.is_empty() {
+                    return Ok(messages);
+                }
+
+                // Like the turn watch, the chat watch lasts as long as the server
+                chat.changed().await.unwrap();
+            }
+        };
+
+        tokio::time::timeout(timeout, wait)
+            .await
+            .unwrap_or_else(|_| Ok(Vec::new()))
+    }
+
+    async fn set_player_name(
+        self,
+        _: Context,
+        player_secret: PlayerSecret,
+        name: String,
+    ) -> UmpireResult<()> {
+        self.audited("set_player_name", player_secret, name, |game, name| {
+            game.set_player_name(player_secret, name)
+        })
+        .await
     }
 
     async fn take_simple_action(
@@ -867,7 +1871,10 @@ impl UmpireRpc for UmpireServer {
         player_secret: PlayerSecret,
         action: AiPlayerAction,
     ) -> UmpireResult<PlayerActionOutcome> {
-        self.game.write().await.take_action(player_secret, action)
+        self.audited("take_action", player_secret, action, |game, action| {
+            game.take_action(player_secret, action)
+        })
+        .await
     }
 
     async fn take_action(
@@ -876,7 +1883,10 @@ impl UmpireRpc for UmpireServer {
         player_secret: PlayerSecret,
         action: PlayerAction,
     ) -> Result<PlayerActionOutcome, GameError> {
-        self.game.write().await.take_action(player_secret, action)
+        self.audited("take_action", player_secret, action, |game, action| {
+            game.take_action(player_secret, action)
+        })
+        .await
     }
 
     async fn propose_action(
@@ -885,7 +1895,10 @@ impl UmpireRpc for UmpireServer {
         player_secret: PlayerSecret,
         action: PlayerAction,
     ) -> ProposedActionResult {
-        self.game.read().await.propose_action(player_secret, action)
+        self.game()
+            .read()
+            .await
+            .propose_action(player_secret, action)
     }
 }
 
@@ -893,218 +1906,717 @@ async fn spawn(fut: impl Future<Output = ()> + Send + 'static) {
     tokio::spawn(fut);
 }
 
+/// The AIs playing as `player_types` and, should a human player leave, `stand_in`, one for each distinct spec
+fn load_ais(
+    player_types: &[PlayerType],
+    stand_in: Option<&AISpec>,
+    backend: AiBackendKind,
+) -> Result<BTreeMap<PlayerType, AnyBackendAI>, String> {
+    player_types
+        .iter()
+        .filter_map(|ptype| match ptype {
+            PlayerType::AI(aispec) => Some(aispec),
+            PlayerType::Human => None,
+        })
+        .chain(stand_in)
+        .collect::<BTreeSet<&AISpec>>()
+        .into_iter()
+        .map(|aispec| {
+            let ai = AnyBackendAI::new(aispec.clone(), backend)?;
+            Ok((PlayerType::AI(aispec.clone()), ai))
+        })
+        .collect()
+}
+
+/// Play the AI players' turns in game `game_id` until the game is over
+///
+/// Nothing is played until the game starts, once its human players are ready. Rather than check over and over whose
+/// turn it is, the AIs sleep until play passes to the next player or `wake` is bumped.
+#[allow(clippy::too_many_arguments)]
+async fn drive_ais(
+    game_id: GameID,
+    game: Arc<RwLockTokio<Game>>,
+    player_types: Vec<PlayerType>,
+    secrets: Vec<PlayerSecret>,
+    lobby: Arc<RwLockStd<Lobby>>,
+    stand_in: Option<AISpec>,
+    stood_in_for: Arc<RwLockStd<BTreeSet<PlayerNum>>>,
+    mut wake: watch::Receiver<()>,
+    mut ais: BTreeMap<PlayerType, AnyBackendAI>,
+) {
+    let num_players = player_types.len();
+    let device: AiDevice = Default::default();
+
+    let stand_in = stand_in.map(PlayerType::AI);
+
+    let mut ai_ctrls: Vec<Option<PlayerControl>> = Vec::with_capacity(num_players);
+
+    for player in 0..num_players {
+        ai_ctrls.push(match player_types[player] {
+            PlayerType::AI(ref _aispec) => {
+                let secret = secrets[player];
+                Some(
+                    PlayerControl::new(
+                        Arc::clone(&game) as Arc<RwLockTokio<dyn IGame>>,
+                        player,
+                        secret,
+                    )
+                    .await,
+                )
+            }
+            _ => None,
+        });
+    }
+
+    // The game lives as long as the server, so the watch can't close
+    let mut turns = game.read().await.watch_turns();
+
+    loop {
+        // Mark the turn seen before checking whose it is, so play passing on in between still wakes us
+        turns.borrow_and_update();
+        wake.borrow_and_update();
+
+        let player = if lobby.read().unwrap().started() {
+            let g = game.read().await;
+
+            // Once the game is decided there's nothing left for the AIs to do
+            if let Some(game_over) = g.game_over() {
+                info!(
+                    "Game {} over: player {} won ({:?})",
+                    game_id, game_over.victor, game_over.reason
+                );
+                break;
+            }
+
+            // The AIs wait out a pause along with everyone else
+            (!g.paused()).then(|| g.current_player())
+        } else {
+            None
+        };
+
+        if let Some(player) = player {
+            let stood_in = stood_in_for.read().unwrap().contains(&player);
+            let ptype = if stood_in {
+                stand_in.as_ref().unwrap()
+            } else {
+                if player_types[player] == PlayerType::Human {
+                    // Back in human hands; a later stand-in starts from what the player knows then
+                    ai_ctrls[player] = None;
+                }
+                &player_types[player]
+            };
+
+            if let Some(ai) = ais.get_mut(ptype) {
+                if stood_in && ai_ctrls[player].is_none() {
+                    ai_ctrls[player] = Some(
+                        PlayerControl::new(
+                            Arc::clone(&game) as Arc<RwLockTokio<dyn IGame>>,
+                            player,
+                            secrets[player],
+                        )
+                        .await,
+                    );
+                }
+
+                let ctrl = &mut ai_ctrls[player].as_mut().unwrap();
+
+                // Always clear on unit production for the robots
+                let mut turn = ctrl.turn_ctrl(true).await;
+
+                ai.take_turn(&mut turn, None, device).await;
+
+                turn.force_end_turn().await.unwrap();
+
+                // The next player may be an AI too
+                continue;
+            }
+        }
+
+        tokio::select! {
+            _ = turns.changed() => {}
+            _ = wake.changed() => {}
+        }
+    }
+}
+
+/// Hand `player` in game `game_id`, released at `released_at`, to the stand-in AI once the grace period is over
+///
+/// Nothing's done if the player was reclaimed in the meantime, or released again, as the later release has its own
+/// grace period.
+async fn stand_in_after_grace(
+    games: Arc<BTreeMap<GameID, HostedGame>>,
+    game_id: GameID,
+    player: PlayerNum,
+    released_at: Instant,
+) {
+    let hosted = &games[&game_id];
+
+    tokio::time::sleep(hosted.stand_in_grace).await;
+
+    {
+        // Held while handing the player over, so they can't be reclaimed in between
+        let released = hosted.released_at.read().unwrap();
+        if released[player] != Some(released_at) {
+            return;
+        }
+
+        hosted.stood_in_for.write().unwrap().insert(player);
+    }
+
+    // It may be the player's turn already
+    hosted.play_watch.send_replace(());
+
+    info!(
+        "Player {} in game {} is now played by the AI",
+        player, game_id
+    );
+}
+
+/// Keep a record of game `game_id` for its spectators, as each turn comes, until the game is over
+///
+/// Sleeps until play passes to the next player rather than checking over and over.
+async fn record_spectator_views(
+    game_id: GameID,
+    game: Arc<RwLockTokio<Game>>,
+    feed: Arc<RwLockStd<SpectatorFeed>>,
+) {
+    // The game lives as long as the server, so the watch can't close
+    let mut turns = game.read().await.watch_turns();
+
+    loop {
+        turns.borrow_and_update();
+
+        {
+            let g = game.read().await;
+            if let Err(err) = feed.write().unwrap().record(&g) {
+                warn!(
+                    "Could not record game {} for its spectators: {}",
+                    game_id, err
+                );
+            }
+
+            if g.game_over().is_some() {
+                break;
+            }
+        }
+
+        if turns.changed().await.is_err() {
+            break;
+        }
+    }
+}
+
+/// Resolves the address to bind to from an address or hostname, which takes the default port unless it gives its own
+async fn resolve_bind_addr(bind: &str) -> anyhow::Result<SocketAddr> {
+    let mut addrs = match lookup_host(bind).await {
+        Ok(addrs) => addrs,
+        // No port was given, or it's a hostname that won't resolve with one
+        Err(_) => lookup_host((bind, conf::PORT))
+            .await
+            .map_err(|err| anyhow!("Could not resolve bind address {}: {}", bind, err))?,
+    };
+
+    addrs.next().ok_or(anyhow!("No address found for {}", bind))
+}
+
 #[tokio::main]
 async fn main() -> anyhow::Result<()> {
-    println!("umpire-server");
-
-    let matches = cli::app("umpired", "fwWHMS")
+    let matches = cli::app("umpired", "fwWHMSLUYmbk")
+        .arg(
+            Arg::new("bind")
+                .long("bind")
+                .help("The address or hostname to bind to, optionally with a port [default: 127.0.0.1]")
+                .conflicts_with("interface"),
+        )
         .arg(
             Arg::new("interface")
                 .short('i')
                 .long("iface")
-                .help("The network interface to bind to")
-                .default_value("lo"),
+                .help("The network interface to bind to, when its address isn't known"),
         )
         .version(conf::APP_VERSION)
         .author("Josh Hansen <hansen.joshuaa@gmail.com>")
         .about(conf::APP_SUBTITLE)
         .arg(players_arg().default_value("h123"))
+        .arg(
+            Arg::new("games")
+                .long("games")
+                .help("How many games to host at once, each with the same settings")
+                .value_parser(clap::value_parser!(GameID).range(1..))
+                .default_value("1"),
+        )
+        .arg(
+            Arg::new("spectator_delay")
+                .long("spectator-delay")
+                .help("How many turns behind the game spectators are kept, so they can't tip off the players")
+                .value_parser(clap::value_parser!(TurnNum))
+                .default_value("0"),
+        )
+        .arg(
+            Arg::new("max_spectators")
+                .long("max-spectators")
+                .help("How many spectators can watch at once, across all games, without taking room meant for players")
+                .value_parser(clap::value_parser!(usize))
+                .default_value("16"),
+        )
+        .arg(
+            Arg::new("connection_timeout")
+                .long("connection-timeout")
+                .help("Seconds without a heartbeat after which a client's connection is given up as lost")
+                .value_parser(clap::value_parser!(u64).range(1..))
+                .default_value("30"),
+        )
+        .arg(
+            Arg::new("turn_time_limit")
+                .long("turn-time-limit")
+                .help("Seconds human players have to play each turn before the server ends it for them")
+                .value_parser(clap::value_parser!(u64).range(1..)),
+        )
+        .arg(
+            Arg::new("compression")
+                .long("compression")
+                .help("Compression to agree to when clients ask for it: 'zstd' or 'none'")
+                .default_value("zstd")
+                .value_parser(|s: &str| Compression::try_from(s)),
+        )
+        .arg(
+            Arg::new("audit_log")
+                .long("audit-log")
+                .help("File to append a record of every change players make to the games to, as lines of JSON"),
+        )
+        .arg(
+            Arg::new("daemon")
+                .long("daemon")
+                .help("Run unattended: take no commands on standard input, and log as JSON rather than printing")
+                .action(ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("log_file")
+                .long("log-file")
+                .help("File to append the daemon's logs to; standard error otherwise")
+                .requires("daemon"),
+        )
+        .arg(
+            Arg::new("pid_file")
+                .long("pid-file")
+                .help("File to write the server's process ID to while it runs"),
+        )
+        .arg(
+            Arg::new("stand_in")
+                .long("stand-in")
+                .help("AI to play for human players whose connections are lost mid-game, until they reconnect; level 0 if not given")
+                .num_args(0..=1)
+                .default_missing_value("0")
+                .value_parser(|s: &str| AISpec::try_from(s.to_string())),
+        )
+        .arg(
+            Arg::new("stand_in_grace")
+                .long("stand-in-grace")
+                .help("Seconds a player whose connection was lost has to reconnect before the stand-in AI takes over")
+                .value_parser(clap::value_parser!(u64))
+                .default_value("60"),
+        )
         .get_matches();
 
-    let fog_of_war = matches.get_one::<bool>("fog").copied().unwrap();
+    let daemon = matches.get_flag("daemon");
+    daemon::init_logging(daemon, matches.get_one::<String>("log_file").map(Path::new))?;
+
+    info!("umpire-server");
+
+    if let Some(unit_stats) = matches.get_one::<UnitStatsRegistry>("unit_stats") {
+        stats::install(unit_stats.clone()).unwrap();
+    }
+
+    let fog_of_war = matches.get_one::<FogOfWar>("fog").copied().unwrap();
 
-    println!("\tFog of war: {}", fog_of_war);
+    info!("Fog of war: {}", fog_of_war);
 
     let player_types = matches
         .get_one::<Vec<PlayerType>>("players")
         .unwrap()
         .clone();
 
-    println!(
-        "\tPlayer types: {}",
+    info!(
+        "Player types: {}",
         player_types.iter().map(|pt| pt.spec()).collect::<String>()
     );
 
     let num_players: PlayerNum = player_types.len();
 
-    let human_player_indices: Vec<usize> = player_types
+    let num_humans = player_types
         .iter()
         .filter(|pt| **pt == PlayerType::Human)
-        .enumerate()
-        .map(|(i, _pt)| i)
-        .collect();
-    let num_humans = human_player_indices.len();
+        .count();
 
     let map_width = matches.get_one::<u16>("map_width").copied().unwrap();
     let map_height = matches.get_one::<u16>("map_height").copied().unwrap();
     let wrapping = matches.get_one::<Wrap2d>("wrapping").copied().unwrap();
     let map_type = matches.get_one::<MapType>("map_type").copied().unwrap();
 
-    let map_dims: Dims = Dims::new(map_width, map_height);
+    let map_file = matches.get_one::<MapData>("map_file");
+    if let Some(map) = map_file {
+        if map.players() != num_players {
+            return Err(anyhow!(
+                "The map file has {} players but {} player types were specified",
+                map.players(),
+                num_players
+            ));
+        }
+    }
+
+    let map_dims: Dims = map_file.map_or(Dims::new(map_width, map_height), MapData::dims);
     if (map_dims.area() as PlayerNum) < num_players {
         panic!("Map dimensions of {} give an area of {} which is not enough room for {} players; area of {} or greater required.",
         map_dims, map_dims.area(), num_players, num_players);
     }
 
-    println!("\tMap dimensions: {}", map_dims);
-    println!("\tWrapping: {:?}", wrapping);
+    info!("Map dimensions: {}", map_dims);
+    info!("Wrapping: {:?}", wrapping);
 
-    let seed = matches.get_one::<u64>("random_seed").cloned();
-    let mut rng = init_rng(seed);
-
-    let city_namer = city_namer(&mut rng);
-    let unit_namer = unit_namer(Some(init_rng(seed)));
-
-    let (game, secrets) = Game::new(
-        Some(init_rng(seed)), // instantiate another rng here to be owned by Game
-        false,
-        map_dims,
-        map_type,
-        city_namer,
-        num_players,
-        fog_of_war,
-        Some(Arc::new(std::sync::RwLock::new(unit_namer))),
-        wrapping,
-    );
+    let turn_limit = matches.get_one::<TurnNum>("turn_limit").copied();
+    if let Some(turn_limit) = turn_limit {
+        info!("Turn limit: {}", turn_limit);
+    }
 
-    // Vector of known player secrets for each player's connection
-    let known_secrets: Vec<Vec<Option<PlayerSecret>>> = (0..num_players)
-        .map(|player| {
-            secrets
-                .iter()
-                .enumerate()
-                .map(|(i, secret)| if i == player { Some(*secret) } else { None })
-                .collect()
-        })
-        .collect();
+    let symmetry = matches.get_one::<Symmetry>("symmetry").copied();
+    if let (Some(symmetry), None) = (symmetry, map_file) {
+        symmetry
+            .check(map_dims, num_players)
+            .map_err(|err| anyhow!(err))?;
+        info!("Symmetry: {}", symmetry);
+    }
 
-    let game = Arc::new(RwLockTokio::new(game));
+    let seed = matches.get_one::<u64>("random_seed").cloned();
+    let num_games = matches.get_one::<GameID>("games").copied().unwrap();
 
-    let connection_count = Arc::new(RwLockStd::new(0usize));
+    let spectator_delay = matches
+        .get_one::<TurnNum>("spectator_delay")
+        .copied()
+        .unwrap();
+    if spectator_delay > 0 {
+        info!("Spectator delay: {} turns", spectator_delay);
+    }
 
-    let iface_name: String = matches.get_one::<String>("interface").cloned().unwrap();
+    let max_spectators = matches.get_one::<usize>("max_spectators").copied().unwrap();
+    info!("Max spectators: {}", max_spectators);
+    let spectator_seats = Arc::new(Semaphore::new(max_spectators));
 
-    let ifaces = get_if_addrs()?;
+    let connection_timeout = Duration::from_secs(
+        matches
+            .get_one::<u64>("connection_timeout")
+            .copied()
+            .unwrap(),
+    );
+    info!("Connection timeout: {:?}", connection_timeout);
+
+    let turn_time_limit = matches
+        .get_one::<u64>("turn_time_limit")
+        .copied()
+        .map(Duration::from_secs);
+    if let Some(turn_time_limit) = turn_time_limit {
+        info!("Turn time limit: {:?}", turn_time_limit);
+    }
+
+    let compression = matches
+        .get_one::<Compression>("compression")
+        .copied()
+        .unwrap();
+    info!("Compression: {}", compression);
+
+    let audit_log = match matches.get_one::<String>("audit_log") {
+        Some(path) => {
+            info!("Audit log: {}", path);
+            Some(Arc::new(AuditLog::open(path).map_err(|err| {
+                anyhow!("Could not open audit log {}: {}", path, err)
+            })?))
+        }
+        None => None,
+    };
 
-    let iface = ifaces
-        .iter()
-        .find(|i| i.name == iface_name)
-        .ok_or(anyhow!("Could not bind to interface {}", iface_name))?;
-
-    let server_addr = (iface.addr.ip(), conf::PORT);
-
-    println!("Binding to {}", server_addr.0);
-
-    let mut listener = tarpc::serde_transport::tcp::listen(&server_addr, Bincode::default).await?;
-
-    println!("Listening on port {}", listener.local_addr().port());
-
-    // tracing::info!("Listening on port {}", listener.local_addr().port());
-    listener.config_mut().max_frame_length(usize::MAX);
-
-    let ai_thread = {
-        let game = Arc::clone(&game);
-        let player_types = player_types.clone();
-        let device: AiDevice = Default::default();
-        tokio::spawn(async move {
-            let unique_ai_ptypes: BTreeSet<PlayerType> = player_types
-                .iter()
-                .filter(|ptype| **ptype != PlayerType::Human)
-                .cloned()
-                .collect();
-
-            let mut ais: BTreeMap<PlayerType, AI<Wgpu>> = BTreeMap::new();
-
-            let mut ai_ctrls: Vec<Option<PlayerControl>> = Vec::with_capacity(num_players);
-
-            for player in 0..num_players {
-                ai_ctrls.push(match player_types[player] {
-                    PlayerType::AI(ref _aispec) => {
-                        let secret = secrets[player];
-                        Some(
-                            PlayerControl::new(
-                                Arc::clone(&game) as Arc<RwLockTokio<dyn IGame>>,
-                                player,
-                                secret,
-                            )
-                            .await,
-                        )
-                    }
-                    _ => None,
-                });
+    let backend = *matches.get_one::<AiBackendKind>("backend").unwrap();
+    info!("AI backend: {}", backend);
+
+    let stand_in = matches.get_one::<AISpec>("stand_in").cloned();
+    let stand_in_grace =
+        Duration::from_secs(matches.get_one::<u64>("stand_in_grace").copied().unwrap());
+    if let Some(ref stand_in) = stand_in {
+        info!("Stand-in AI: {}, after {:?}", stand_in, stand_in_grace);
+    }
+
+    let mut games: BTreeMap<GameID, HostedGame> = BTreeMap::new();
+    for game_id in 0..num_games {
+        // Games get maps of their own, even from the same seed
+        let seed = seed.map(|seed| seed.wrapping_add(game_id));
+        let mut rng = init_rng(seed);
+
+        let mut city_namer = city_namer(&mut rng);
+        let unit_namer = unit_namer(Some(init_rng(seed)));
+
+        let (mut game, secrets) = if let Some(map) = map_file {
+            Game::new_with_map(
+                Some(init_rng(seed)),
+                false,
+                map.clone(),
+                num_players,
+                fog_of_war,
+                Some(Arc::new(std::sync::RwLock::new(unit_namer))),
+                wrapping,
+            )
+        } else if matches.get_flag("fair_starts") {
+            let mut game_rng = init_rng(seed);
+            let (map, report) = map_type.generate_fair(
+                &mut game_rng,
+                map_dims,
+                num_players,
+                &mut city_namer,
+                symmetry,
+                wrapping,
+            );
+
+            info!("Starts:");
+            for start in &report.starts {
+                info!("{}", start);
             }
-
-            for ptype in unique_ai_ptypes.iter() {
-                let ai: AI<Wgpu> = match ptype {
-                    PlayerType::AI(aispec) => aispec.clone().into(),
-                    _ => unreachable!(),
-                };
-                ais.insert(ptype.clone(), ai);
+            for imbalance in report.imbalances() {
+                warn!("Unbalanced starts. {}", imbalance);
             }
 
-            loop {
-                tokio::time::sleep(tokio::time::Duration::from_millis(500)).await;
+            Game::new_with_map(
+                Some(game_rng),
+                false,
+                map,
+                num_players,
+                fog_of_war,
+                Some(Arc::new(std::sync::RwLock::new(unit_namer))),
+                wrapping,
+            )
+        } else {
+            Game::new(
+                Some(init_rng(seed)), // instantiate another rng here to be owned by Game
+                false,
+                map_dims,
+                map_type,
+                symmetry,
+                city_namer,
+                num_players,
+                fog_of_war,
+                Some(Arc::new(std::sync::RwLock::new(unit_namer))),
+                wrapping,
+            )
+        };
+        game.set_turn_limit(turn_limit);
+
+        games.insert(
+            game_id,
+            HostedGame {
+                game: Arc::new(RwLockTokio::new(game)),
+                secrets,
+                // Players claim their slots in the lobby rather than being assigned them, and the game waits until all
+                // are ready
+                lobby: Arc::new(RwLockStd::new(Lobby::new(&player_types))),
+                lobby_watch: watch::channel(()).0,
+                spectator_feed: Arc::new(RwLockStd::new(SpectatorFeed::new(spectator_delay))),
+                player_types: player_types.clone(),
+                controllers: RwLockStd::new(vec![None; num_players]),
+                stand_in: stand_in.clone(),
+                stand_in_grace,
+                released_at: RwLockStd::new(vec![None; num_players]),
+                stood_in_for: Arc::new(RwLockStd::new(BTreeSet::new())),
+                play_watch: watch::channel(()).0,
+                turn_clock: Arc::new(RwLockStd::new(None)),
+            },
+        );
+    }
+    let games = Arc::new(games);
+
+    // The connections being served right now
+    let connections: Arc<RwLockStd<BTreeMap<ConnectionID, UmpireServer>>> =
+        Arc::new(RwLockStd::new(BTreeMap::new()));
+    let mut next_connection: ConnectionID = 0;
+
+    // The operator's commands are read on a thread of their own, as reading them blocks
+    let shutdown = Arc::new(Notify::new());
+    let (commands_tx, commands_rx) = mpsc::unbounded_channel();
+    // A daemon has no operator at a terminal to take commands from
+    if !daemon {
+        std::thread::spawn(move || admin::read_commands(commands_tx));
+    }
+    tokio::spawn(
+        Admin {
+            games: Arc::clone(&games),
+            connections: Arc::clone(&connections),
+            shutdown: Arc::clone(&shutdown),
+        }
+        .run(commands_rx),
+    );
 
-                let g = game.read().await;
+    let server_addr: SocketAddr = if let Some(iface_name) = matches.get_one::<String>("interface") {
+        let ifaces = get_if_addrs()?;
 
-                let player = g.current_player();
+        let iface = ifaces
+            .iter()
+            .find(|i| &i.name == iface_name)
+            .ok_or(anyhow!("Could not bind to interface {}", iface_name))?;
 
-                let ptype = &player_types[player];
+        (iface.addr.ip(), conf::PORT).into()
+    } else {
+        let bind = matches
+            .get_one::<String>("bind")
+            .map_or("127.0.0.1", String::as_str);
 
-                if let Some(ai) = ais.get_mut(ptype) {
-                    let ctrl = &mut ai_ctrls[player].as_mut().unwrap();
+        resolve_bind_addr(bind).await?
+    };
 
-                    // Always clear on unit production for the robots
-                    let mut turn = ctrl.turn_ctrl(true).await;
+    info!("Binding to {}", server_addr);
 
-                    ai.take_turn(&mut turn, None, device).await;
+    let listener = TcpListener::bind(server_addr).await?;
 
-                    turn.force_end_turn().await.unwrap();
-                }
-            }
+    // Held until the server stops, when the file is removed
+    let _pid_file = matches
+        .get_one::<String>("pid_file")
+        .map(PidFile::create)
+        .transpose()
+        .map_err(|err| anyhow!("Could not write pid file: {}", err))?;
+
+    info!("Listening on port {}", listener.local_addr()?.port());
+
+    // Each game's AIs are played by a task of their own, all loaded up front so a bad model stops the server now
+    let ai_drivers: Vec<_> = games
+        .iter()
+        .map(|(game_id, hosted)| -> anyhow::Result<_> {
+            let ais = load_ais(&hosted.player_types, hosted.stand_in.as_ref(), backend)
+                .map_err(|err| anyhow!("Could not load the AIs of game {}: {}", game_id, err))?;
+
+            Ok(tokio::spawn(drive_ais(
+                *game_id,
+                Arc::clone(&hosted.game),
+                hosted.player_types.clone(),
+                hosted.secrets.clone(),
+                Arc::clone(&hosted.lobby),
+                hosted.stand_in.clone(),
+                Arc::clone(&hosted.stood_in_for),
+                hosted.play_watch.subscribe(),
+                ais,
+            )))
         })
-    };
+        .collect::<anyhow::Result<_>>()?;
+
+    // Each game's turns are timed by a task of their own, if they're timed at all
+    let turn_timers: Vec<_> = games
+        .iter()
+        .filter_map(|(game_id, hosted)| {
+            let limit = turn_time_limit?;
+            Some(tokio::spawn(turn_timer::time_turns(
+                *game_id,
+                Arc::clone(&hosted.game),
+                hosted.player_types.clone(),
+                hosted.secrets.clone(),
+                Arc::clone(&hosted.lobby),
+                Arc::clone(&hosted.stood_in_for),
+                hosted.play_watch.subscribe(),
+                limit,
+                Arc::clone(&hosted.turn_clock),
+            )))
+        })
+        .collect();
+
+    // Without a delay spectators are shown each game as it stands, so there's no need to keep a record
+    if spectator_delay > 0 {
+        for (game_id, hosted) in games.iter() {
+            tokio::spawn(record_spectator_views(
+                *game_id,
+                Arc::clone(&hosted.game),
+                Arc::clone(&hosted.spectator_feed),
+            ));
+        }
+    }
 
-    listener
-        // Ignore accept errors.
-        .filter_map(|r| future::ready(r.ok()))
+    let incoming = stream::unfold(listener, |listener| async move {
+        let accepted = listener.accept().await;
+        Some((accepted, listener))
+    })
+    // Ignore accept errors.
+    .filter_map(|r| future::ready(r.ok()))
+    // Settle each connection's compression, giving up on clients that don't say what they want in time
+    .map(|(socket, _)| {
+        tokio::time::timeout(connection_timeout, transport::accept(socket, compression))
+    })
+    .buffer_unordered(conf::MAX_PENDING_HANDSHAKES)
+    .filter_map(|r| future::ready(r.ok().and_then(Result::ok)));
+
+    let serving = incoming
         .map(server::BaseChannel::with_defaults)
         // Limit channels to 4 per IP.
         .max_channels_per_key(4, |t| t.transport().peer_addr().unwrap().ip())
         // serve is generated by the service attribute. It takes as input any type implementing
         // the generated World trait.
-        .filter(|_channel| {
-            let cc = connection_count.clone();
-
-            let human = *cc.read().unwrap();
-
-            future::ready(human_player_indices.contains(&human))
-        })
         .map(|channel| {
-            let cc = connection_count.clone();
+            let connections = Arc::clone(&connections);
 
-            let human = *cc.read().unwrap();
+            let connection = next_connection;
+            next_connection += 1;
 
-            *cc.write().unwrap() += 1;
+            let server = UmpireServer {
+                games: Arc::clone(&games),
+                joined: Arc::new(RwLockStd::new(0)),
+                known_secrets: Arc::new(RwLockStd::new(vec![None; num_players])),
+                spectating: Arc::new(RwLockStd::new(false)),
+                spectator_seats: Arc::clone(&spectator_seats),
+                spectator_seat: Arc::new(RwLockStd::new(None)),
+                connection,
+                last_heard: Arc::new(RwLockStd::new(Instant::now())),
+                kicked: Arc::new(Notify::new()),
+                turn_snapshots: Arc::new(RwLockStd::new(BTreeMap::new())),
+                audit_log: audit_log.clone(),
+            };
 
-            let player = human_player_indices[human];
+            let open = {
+                let mut connections = connections.write().unwrap();
+                connections.insert(connection, server.clone());
+                connections.len()
+            };
 
-            println!("Serving player {} on connection {}", player, human);
+            // Connections start out in the first game, controlling no players until they claim slots in the lobby or
+            // reclaim them by reconnecting
+            info!("Serving connection {} ({} open)", connection, open);
 
-            let server = UmpireServer {
-                game: Arc::clone(&game),
-                known_secrets: known_secrets[player].clone(),
-                player_types: player_types.clone(),
-            };
+            async move {
+                // A connection ends when the client closes it, or when it goes quiet for too long---the client might
+                // be gone without the connection having been closed
+                tokio::select! {
+                    _ = channel.execute(server.clone().serve()).for_each(spawn) => {
+                        info!("Connection {} closed", connection);
+                    }
+                    _ = server.lost(connection_timeout) => {
+                        info!("Connection {} timed out", connection);
+                    }
+                    _ = server.kicked.notified() => {
+                        info!("Connection {} kicked", connection);
+                    }
+                }
 
-            channel.execute(server.serve()).for_each(spawn)
+                server.release_players();
+                server.spectator_seat.write().unwrap().take();
+                connections.write().unwrap().remove(&connection);
+            }
         })
-        // Max channels.
-        .buffer_unordered(num_humans)
-        .for_each(|_| async {})
-        .await;
+        // Max channels. Leave room for players reconnecting before their old connections are found to have dropped,
+        // and for spectators in seats of their own so they can't crowd the players out
+        .buffer_unordered(2 * num_humans * num_games as usize + max_spectators)
+        .for_each(|_| async {});
+
+    // Serve until the operator or the service manager says to stop; the connections close as they're dropped
+    tokio::select! {
+        _ = serving => {}
+        _ = shutdown.notified() => {}
+        _ = daemon::terminated() => {}
+    }
+
+    info!("Shutting down");
 
-    ai_thread.await.unwrap();
+    for task in ai_drivers.into_iter().chain(turn_timers) {
+        task.abort();
+    }
 
     Ok(())
 }
@@ -0,0 +1,83 @@
+//! What spectators are shown of the games they watch
+
+use std::collections::VecDeque;
+
+use common::game::{error::GameError, obs::ObsTracker, Game, PlayerNum, TurnNum, UmpireResult};
+
+/// A game as its spectators see it, held back some number of turns
+///
+/// A delay keeps spectators from telling the players what's going on elsewhere on the map while it still matters.
+/// Views are recorded as turns go by, and spectators are shown those recorded `delay` turns before the current one;
+/// until that many turns have gone by, they're shown nothing.
+pub struct SpectatorFeed {
+    delay: TurnNum,
+
+    /// The views recorded at the turns given, oldest first, each with the whole map followed by each player's view
+    views: VecDeque<(TurnNum, Vec<ObsTracker>)>,
+}
+
+impl SpectatorFeed {
+    pub fn new(delay: TurnNum) -> Self {
+        Self {
+            delay,
+            views: VecDeque::new(),
+        }
+    }
+
+    /// Record the views of `game` as it stands, unless they were already recorded this turn
+    pub fn record(&mut self, game: &Game) -> UmpireResult<()> {
+        // Spectators without a delay are shown the game as it stands, so there's nothing to keep
+        if self.delay == 0 {
+            return Ok(());
+        }
+
+        let turn = game.turn();
+        if self
+            .views
+            .back()
+            .map_or(false, |(recorded, _)| *recorded == turn)
+        {
+            return Ok(());
+        }
+
+        let views = std::iter::once(None)
+            .chain((0..game.num_players()).map(Some))
+            .map(|viewer| game.spectator_observations(viewer))
+            .collect::<UmpireResult<Vec<ObsTracker>>>()?;
+        self.views.push_back((turn, views));
+
+        // Only the latest view old enough to be shown is needed of those old enough
+        while self
+            .views
+            .get(1)
+            .map_or(false, |(recorded, _)| recorded + self.delay <= turn)
+        {
+            self.views.pop_front();
+        }
+
+        Ok(())
+    }
+
+    /// What a spectator watching `game` from `viewer`'s point of view is shown, or the whole map if `None`
+    pub fn observations(&self, game: &Game, viewer: Option<PlayerNum>) -> UmpireResult<ObsTracker> {
+        if self.delay == 0 {
+            return game.spectator_observations(viewer);
+        }
+
+        if let Some(player) = viewer {
+            if player >= game.num_players() {
+                return Err(GameError::NoSuchPlayer { player });
+            }
+        }
+
+        let turn = game.turn();
+        Ok(self
+            .views
+            .front()
+            .filter(|(recorded, _)| recorded + self.delay <= turn)
+            .map_or_else(
+                || ObsTracker::new(game.dims()),
+                |(_, views)| views[viewer.map_or(0, |player| player + 1)].clone(),
+            ))
+    }
+}
@@ -0,0 +1,269 @@
+//! Holding human players to a time limit on their turns
+//!
+//! A player who walks away mid-game would otherwise stall everyone else. Once a turn runs past the limit the server
+//! ends it for them, leaving whatever requests they hadn't gotten to unfulfilled, as `force_end_turn` does. The turn
+//! passing on wakes the other players' clients as usual; the player's own client finds out the next time it asks how
+//! long it has left.
+//!
+//! AI turns aren't timed, nor are turns the stand-in AI plays for absent players.
+
+use std::{
+    collections::BTreeSet,
+    sync::{Arc, RwLock as RwLockStd},
+    time::{Duration, Instant},
+};
+
+use common::{
+    game::{Game, PlayerNum, PlayerSecret, PlayerType, TurnNum, TurnPhase, UmpireResult},
+    lobby::{GameID, Lobby},
+};
+use tokio::sync::{watch, RwLock as RwLockTokio};
+use tracing::{info, warn};
+
+/// How much time the turn underway has left
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TurnClock {
+    /// Running down to the deadline given
+    Running(Instant),
+
+    /// Held by a pause, with this much left
+    Held(Duration),
+}
+
+impl TurnClock {
+    pub fn remaining(self) -> Duration {
+        match self {
+            Self::Running(deadline) => deadline.saturating_duration_since(Instant::now()),
+            Self::Held(remaining) => remaining,
+        }
+    }
+
+    /// Stop the clock, keeping what time is left
+    fn hold(self) -> Self {
+        Self::Held(self.remaining())
+    }
+
+    /// Start the clock again, running down from what time is left
+    fn resume(self) -> Self {
+        Self::Running(Instant::now() + self.remaining())
+    }
+}
+
+/// End human players' turns in game `game_id` that go longer than `limit`, until the game is over
+///
+/// The clock of the turn underway, if it's timed, is kept in `clock`. A pause holds the clock, so the time spent paused
+/// doesn't count against the player; `wake` must be bumped on pausing and resuming for the clock to be held promptly.
+#[allow(clippy::too_many_arguments)]
+pub async fn time_turns(
+    game_id: GameID,
+    game: Arc<RwLockTokio<Game>>,
+    player_types: Vec<PlayerType>,
+    secrets: Vec<PlayerSecret>,
+    lobby: Arc<RwLockStd<Lobby>>,
+    stood_in_for: Arc<RwLockStd<BTreeSet<PlayerNum>>>,
+    mut wake: watch::Receiver<()>,
+    limit: Duration,
+    clock: Arc<RwLockStd<Option<TurnClock>>>,
+) {
+    // The game lives as long as the server, so the watch can't close
+    let mut turns = game.read().await.watch_turns();
+
+    // The turn being timed and its clock
+    let mut timing: Option<((TurnNum, PlayerNum), TurnClock)> = None;
+
+    loop {
+        // Mark the turn seen before checking whose it is, so play passing on in between still wakes us
+        turns.borrow_and_update();
+        wake.borrow_and_update();
+
+        let timed = if lobby.read().unwrap().started() {
+            let g = game.read().await;
+
+            if g.game_over().is_some() {
+                *clock.write().unwrap() = None;
+                break;
+            }
+
+            let player = g.current_player();
+            (player_types[player] == PlayerType::Human
+                && !stood_in_for.read().unwrap().contains(&player))
+            .then(|| ((g.turn(), player), g.paused()))
+        } else {
+            None
+        };
+
+        let Some((turn, paused)) = timed else {
+            timing = None;
+            *clock.write().unwrap() = None;
+
+            tokio::select! {
+                _ = turns.changed() => {}
+                _ = wake.changed() => {}
+            }
+            continue;
+        };
+
+        // Being woken mid-turn doesn't restart the clock
+        let turn_clock = match timing {
+            Some((timed_turn, turn_clock)) if timed_turn == turn => turn_clock,
+            _ => TurnClock::Held(limit),
+        };
+        let turn_clock = if paused {
+            turn_clock.hold()
+        } else {
+            turn_clock.resume()
+        };
+        timing = Some((turn, turn_clock));
+        *clock.write().unwrap() = Some(turn_clock);
+
+        let TurnClock::Running(turn_deadline) = turn_clock else {
+            // Nothing runs out while the clock's held
+            tokio::select! {
+                _ = turns.changed() => {}
+                _ = wake.changed() => {}
+            }
+            continue;
+        };
+
+        tokio::select! {
+            _ = turns.changed() => continue,
+            _ = wake.changed() => continue,
+            _ = tokio::time::sleep_until(turn_deadline.into()) => {}
+        }
+
+        let (turn_num, player) = turn;
+        let mut g = game.write().await;
+
+        // The turn may have ended, or gone to the stand-in AI, while waiting on the lock
+        let moved_on = (g.turn(), g.current_player()) != turn;
+        if moved_on || stood_in_for.read().unwrap().contains(&player) {
+            continue;
+        }
+
+        // Paused just as time ran out, with no wake yet; the clock's held next time round, with no time left
+        if g.paused() {
+            continue;
+        }
+
+        match time_out(&mut g, secrets[player]) {
+            Ok(()) => info!(
+                "Player {} ran out of time on turn {} in game {}; turn ended",
+                player, turn_num, game_id
+            ),
+            Err(err) => {
+                warn!(
+                    "Could not end player {}'s turn {} in game {}: {}",
+                    player, turn_num, game_id, err
+                );
+                // Try again once the limit's run out again
+                timing = Some((turn, TurnClock::Held(limit)));
+            }
+        }
+    }
+}
+
+/// End the current turn of the player identified by `secret`, beginning it first if they never did
+fn time_out(game: &mut Game, secret: PlayerSecret) -> UmpireResult<()> {
+    if game.turn_phase() == TurnPhase::Pre {
+        game.begin_turn(secret, false)?;
+    }
+
+    game.force_end_turn(secret)?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use std::{
+        collections::BTreeSet,
+        sync::{Arc, RwLock as RwLockStd},
+        thread::sleep,
+        time::Duration,
+    };
+
+    use common::{
+        game::{test_support::game_two_cities_two_infantry, PlayerType},
+        lobby::Lobby,
+    };
+    use tokio::sync::{watch, RwLock as RwLockTokio};
+
+    use super::{time_turns, TurnClock};
+
+    #[test]
+    fn test_turn_clock() {
+        let held = TurnClock::Held(Duration::from_secs(10));
+        sleep(Duration::from_millis(20));
+        assert_eq!(held.remaining(), Duration::from_secs(10));
+        assert_eq!(held.hold(), held);
+
+        let running = held.resume();
+        assert!(matches!(running, TurnClock::Running(_)));
+        assert!(running.remaining() <= Duration::from_secs(10));
+        assert!(running.remaining() > Duration::from_secs(9));
+
+        sleep(Duration::from_millis(20));
+        let held_again = running.hold();
+        let TurnClock::Held(remaining) = held_again else {
+            panic!("Clock still running after being held");
+        };
+        assert!(remaining < Duration::from_secs(10));
+
+        sleep(Duration::from_millis(20));
+        assert_eq!(held_again.remaining(), remaining);
+
+        let ran_out = TurnClock::Held(Duration::ZERO).resume();
+        assert_eq!(ran_out.remaining(), Duration::ZERO);
+    }
+
+    #[tokio::test]
+    async fn test_time_turns_pause() {
+        let (mut game, secrets) = game_two_cities_two_infantry();
+        game.set_paused(true);
+        let game = Arc::new(RwLockTokio::new(game));
+
+        let player_types = vec![PlayerType::Human, PlayerType::Human];
+        let mut lobby = Lobby::new(&player_types);
+        for player in 0..player_types.len() {
+            lobby.claim(player).unwrap();
+            lobby.ready(player).unwrap();
+        }
+        assert!(lobby.started());
+
+        let (wake_tx, wake_rx) = watch::channel(());
+        let clock = Arc::new(RwLockStd::new(None));
+        let limit = Duration::from_millis(100);
+
+        let timer = tokio::spawn(time_turns(
+            0,
+            Arc::clone(&game),
+            player_types,
+            secrets,
+            Arc::new(RwLockStd::new(lobby)),
+            Arc::new(RwLockStd::new(BTreeSet::new())),
+            wake_rx,
+            limit,
+            Arc::clone(&clock),
+        ));
+
+        // Paused well past the limit, the turn's still the first player's, with all its time left
+        tokio::time::sleep(limit * 4).await;
+        {
+            let g = game.read().await;
+            assert_eq!((g.turn(), g.current_player()), (0, 0));
+        }
+        assert_eq!(*clock.read().unwrap(), Some(TurnClock::Held(limit)));
+
+        // Once resumed, the turn runs out
+        game.write().await.set_paused(false);
+        wake_tx.send(()).unwrap();
+
+        tokio::time::sleep(limit * 4).await;
+        {
+            let g = game.read().await;
+            assert_ne!((g.turn(), g.current_player()), (0, 0));
+        }
+
+        timer.abort();
+    }
+}
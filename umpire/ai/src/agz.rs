@@ -1,10 +1,11 @@
 //! AlphaGo Zero style action model
 //!
-//! Based on self-play game outcomes, learn P(victory|action; environment)
+//! Based on self-play game outcomes, learn a policy---which action to take in an environment---and the value of the
+//! environment, P(victory|environment), from a trunk the two heads share.
 //!
-//! Divided into two sub-models, one for city actions, one for unit actions
+//! The policy covers city and unit actions alike, city actions first
 use std::collections::BTreeSet;
-use std::{fmt, path::Path};
+use std::{fmt, fs, path::Path};
 
 use async_trait::async_trait;
 
@@ -13,15 +14,14 @@ use burn::module::Module;
 use burn::nn::conv::{Conv2d, Conv2dConfig};
 use burn::nn::loss::{MseLoss, Reduction};
 use burn::nn::{Dropout, DropoutConfig, Linear, LinearConfig};
-use burn::record::{BinBytesRecorder, BinFileRecorder, FullPrecisionSettings, Recorder};
-use burn::tensor::activation::{relu, sigmoid};
+use burn::tensor::activation::{log_softmax, relu, sigmoid};
 use burn::tensor::backend::{AutodiffBackend, Backend};
-use burn::tensor::{Int, Tensor};
+use burn::tensor::Tensor;
 use burn_train::{RegressionOutput, TrainOutput, TrainStep, ValidStep};
 
 use common::game::ai::{
-    AiBackend, AiBackendDevice, AiDevice, PER_ACTION_CHANNELS, POSSIBLE_ACTIONS,
-    POSSIBLE_CITY_ACTIONS, POSSIBLE_UNIT_ACTIONS, P_DROPOUT,
+    AiDevice, ADDED_WIDE_FEATURES, PER_ACTION_CHANNELS, POSSIBLE_ACTIONS, POSSIBLE_CITY_ACTIONS,
+    POSSIBLE_UNIT_ACTIONS, P_DROPOUT,
 };
 use num_traits::ToPrimitive;
 
@@ -30,17 +30,45 @@ use serde::de::{self, Visitor};
 use common::game::{
     action::{NextCityAction, NextUnitAction},
     ai::{
-        fX, TrainingFocus, BASE_CONV_FEATS, DEEP_HEIGHT, DEEP_OUT_LEN, DEEP_WIDTH, FEATS_LEN,
-        WIDE_LEN,
+        fX, feats_len, TrainingFocus, BASE_CONV_FEATS, DEEP_OUT_TILES, DEEP_OUT_WIDTH, DEEP_WIDTH,
+        FEATURES_VERSION, WIDE_LEN,
     },
     player::PlayerTurn,
     turn_async::ActionwiseTurnTaker2,
+    unit::{POSSIBLE_UNIT_TYPES, POSSIBLE_UNIT_TYPES_WRIT_LARGE},
 };
 use common::util::max_sample_idx;
 
+use crate::precision::{self, Precision};
 use crate::LoadableFromBytes;
 use crate::{data::AgzBatch, Loadable, Storable};
 
+/// The layout of the features the original models were trained on, before the feature layout was versioned
+///
+/// Every part of it survives in the current layout, later additions having gone at the end of each part: the first
+/// 20 observation channels of each tile; of the wide features, the first 13 general ones and those of the original
+/// 10 unit types; and of the actions, producing the original unit types and the unit actions but bombard.
+const BASELINE_CONV_FEATS: usize = 20;
+const BASELINE_ADDED_WIDE_FEATURES: usize = 13;
+const BASELINE_UNIT_TYPES: usize = 10;
+const BASELINE_WIDE_FEATS: usize = BASELINE_ADDED_WIDE_FEATURES + 2 * BASELINE_UNIT_TYPES + 1;
+const BASELINE_UNIT_ACTIONS: usize = POSSIBLE_UNIT_ACTIONS - 1;
+const BASELINE_POSSIBLE_ACTIONS: usize = BASELINE_UNIT_TYPES + BASELINE_UNIT_ACTIONS;
+
+/// The narrowest window a model can see, leaving room for a single convolution
+pub const MIN_WINDOW: usize = DEEP_OUT_WIDTH + 2;
+
+/// Leads models that see a window other than the default `DEEP_WIDTH`, followed by the window's width as a
+/// little-endian u16
+const WINDOW_MAGIC: &[u8; 4] = b"UMPW";
+
+/// Leads models trained under a versioned layout of the features, followed by its `FEATURES_VERSION` as a
+/// little-endian u32
+///
+/// Models without it were trained on the original layout, or on the current one before models were tagged; the shapes
+/// of their weights tell which.
+const VERSION_MAGIC: &[u8; 4] = b"UMPV";
+
 struct BytesVisitor;
 impl<'de> Visitor<'de> for BytesVisitor {
     type Value = Vec<u8>;
@@ -62,29 +90,56 @@ pub struct AgzActionModelConfig {
     pub possible_actions: usize,
 
     pub dropout_config: DropoutConfig,
+
+    /// Whether to give the model a value head
+    #[config(default = true)]
+    pub value_head: bool,
+
+    /// How many observation channels each tile has
+    ///
+    /// Only other than `BASE_CONV_FEATS` to load models trained on the original layout of the features.
+    #[config(default = "BASE_CONV_FEATS")]
+    pub conv_feats: usize,
+
+    /// How many wide features there are, likewise only other than `WIDE_LEN` for the original layout
+    #[config(default = "WIDE_LEN")]
+    pub wide_feats: usize,
+
+    /// The width of the window of tiles the model sees around the city or unit in focus
+    ///
+    /// Must be odd and at least `MIN_WINDOW`. Wider windows give more context, at the cost of a convolution for each
+    /// two tiles more.
+    #[config(default = "DEEP_WIDTH")]
+    pub window: usize,
 }
 
 impl AgzActionModelConfig {
     pub fn init<B: Backend>(&self, device: B::Device) -> AgzActionModel<B> {
         let dropout = self.dropout_config.init();
 
+        assert!(
+            self.window >= MIN_WINDOW && self.window % 2 == 1,
+            "Feature window must be odd and at least {}, not {}",
+            MIN_WINDOW,
+            self.window
+        );
+
         let channels = self.possible_actions * PER_ACTION_CHANNELS;
 
-        let convs = vec![
-            Conv2dConfig::new([BASE_CONV_FEATS, channels], [3, 3]).init(&device), // -> 13x13
-            Conv2dConfig::new([channels, channels], [3, 3]).init(&device),        // -> 11x11
-            Conv2dConfig::new([channels, channels], [3, 3]).init(&device),        // -> 9x9
-            Conv2dConfig::new([channels, channels], [3, 3]).init(&device),        // -> 7x7
-            Conv2dConfig::new([channels, channels], [3, 3]).init(&device),        // -> 5x5
-            Conv2dConfig::new([channels, channels], [3, 3]).init(&device),        // -> 3x3
-        ];
+        // Each convolution trims a tile from every side, until the window's down to 3x3
+        let convs = (0..(self.window - DEEP_OUT_WIDTH) / 2)
+            .map(|i| {
+                let in_channels = if i == 0 { self.conv_feats } else { channels };
+                Conv2dConfig::new([in_channels, channels], [3, 3]).init(&device)
+            })
+            .collect();
 
         let dense_common = vec![
-            LinearConfig::new(WIDE_LEN + DEEP_OUT_LEN, 64).init(&device),
+            LinearConfig::new(self.wide_feats + DEEP_OUT_TILES * channels, 64).init(&device),
             LinearConfig::new(64, 32).init(&device),
         ];
 
-        let dense_per_action = (0..POSSIBLE_ACTIONS)
+        let dense_per_action = (0..self.possible_actions)
             .map(|_| {
                 vec![
                     LinearConfig::new(32, 16).init(&device),
@@ -94,11 +149,19 @@ impl AgzActionModelConfig {
             })
             .collect();
 
+        let value = self.value_head.then(|| {
+            vec![
+                LinearConfig::new(32, 16).init(&device),
+                LinearConfig::new(16, 1).init(&device),
+            ]
+        });
+
         AgzActionModel {
             dropout,
             convs,
             dense_common,
             dense_per_action,
+            value,
         }
     }
 }
@@ -107,7 +170,7 @@ impl AgzActionModelConfig {
 ///
 /// Use the neural network to extract features rather than proclaiming them a priori
 ///
-/// Reduces 11x11 with 16 channels down to 3x3 with 16 channels
+/// Reduces the window of tiles around the city or unit in focus down to 3x3, a channel per action
 ///
 /// See `Obs::features` and `Game::player_features` for more information
 #[derive(Debug, Module)]
@@ -115,31 +178,127 @@ pub struct AgzActionModel<B: Backend> {
     dropout: Dropout,
     convs: Vec<Conv2d<B>>,
     dense_common: Vec<Linear<B>>,
+
+    /// The policy head: each action's logit
+    dense_per_action: Vec<Vec<Linear<B>>>,
+
+    /// The value head: the victory probability
+    ///
+    /// Models stored before there was a value head don't have one; the victory probability of their best action
+    /// stands in.
+    value: Option<Vec<Linear<B>>>,
+}
+
+/// The model as stored before it had a value head
+#[derive(Debug, Module)]
+struct AgzActionModelV0<B: Backend> {
+    dropout: Dropout,
+    convs: Vec<Conv2d<B>>,
+    dense_common: Vec<Linear<B>>,
     dense_per_action: Vec<Vec<Linear<B>>>,
 }
+
+impl<B: Backend> From<AgzActionModel<B>> for AgzActionModelV0<B> {
+    fn from(model: AgzActionModel<B>) -> Self {
+        Self {
+            dropout: model.dropout,
+            convs: model.convs,
+            dense_common: model.dense_common,
+            dense_per_action: model.dense_per_action,
+        }
+    }
+}
+
+impl<B: Backend> From<AgzActionModelV0<B>> for AgzActionModel<B> {
+    fn from(model: AgzActionModelV0<B>) -> Self {
+        Self {
+            dropout: model.dropout,
+            convs: model.convs,
+            dense_common: model.dense_common,
+            dense_per_action: model.dense_per_action,
+            value: None,
+        }
+    }
+}
+
 impl<B: Backend> AgzActionModel<B> {
-    async fn features(turn: &PlayerTurn<'_>, focus: TrainingFocus) -> Vec<fX> {
-        turn.player_features(focus).await
+    async fn features(&self, turn: &PlayerTurn<'_>, focus: TrainingFocus) -> Vec<fX> {
+        turn.player_features(focus, self.window()).await
+    }
+
+    /// The width of the window of tiles the model sees, which its convolutions reduce to 3x3
+    pub fn window(&self) -> usize {
+        DEEP_OUT_WIDTH + 2 * self.convs.len()
+    }
+
+    /// Whether the model was trained on the original layout of the features, as told by the shape of its weights
+    ///
+    /// Such a model reads the parts of the current features it knows, and its logits are spread out over the
+    /// current actions.
+    fn baseline(&self) -> bool {
+        self.dense_per_action.len() == BASELINE_POSSIBLE_ACTIONS
+            && self.convs[0].weight.val().dims()[1] == BASELINE_CONV_FEATS
+    }
+
+    /// features: [batch,feat]
+    ///
+    /// -> ([batch,action_idx] (policy logits), [batch,1] (victory prob))
+    fn forward(&self, features: Tensor<B, 2>) -> (Tensor<B, 2>, Tensor<B, 2>) {
+        let trunk = self.forward_trunk(features);
+        let batches = trunk.dims()[0];
+
+        let mut logits = self.forward_per_action(trunk.clone());
+        if self.baseline() {
+            logits = baseline_logits(logits);
+        }
+
+        debug_assert_eq!(logits.dims(), [batches, POSSIBLE_ACTIONS]);
+
+        let value = match self.value.as_ref() {
+            Some(value) => {
+                let mut out = trunk;
+                for (i, dense) in value.iter().enumerate() {
+                    out = dense.forward(out);
+                    // Only relu non-finally
+                    if i < value.len() - 1 {
+                        out = relu(out);
+                    }
+                }
+                sigmoid(out)
+            }
+            None => sigmoid(logits.clone().max_dim(1)),
+        };
+
+        (logits, value)
     }
 
+    /// The layers shared by the policy and value heads
+    ///
     /// features: [batch,feat]
-    /// actions: [batch]
     ///
-    /// -> [batch,action_idx] (victory prob)
-    fn forward(&self, features: Tensor<B, 2>) -> Tensor<B, 2> {
+    /// -> [batch,common_feat]
+    fn forward_trunk(&self, features: Tensor<B, 2>) -> Tensor<B, 2> {
         // Wide features that will pass through to the dense layers directly
         // [batch,wide_feat]
         let batches = features.dims()[0];
-        let wide = features.clone().slice([0..batches, 0..WIDE_LEN]);
+        let mut wide = features.clone().slice([0..batches, 0..WIDE_LEN]);
 
         // Input features to the 2d convolution
         // [batch,conv_feat,x,y]
-        let mut deep = features.slice([0..batches, WIDE_LEN..FEATS_LEN]).reshape([
-            batches as i32,
-            BASE_CONV_FEATS as i32,
-            DEEP_HEIGHT as i32,
-            DEEP_WIDTH as i32,
-        ]);
+        let window = self.window();
+        let mut deep = features
+            .slice([0..batches, WIDE_LEN..feats_len(window)])
+            .reshape([
+                batches as i32,
+                BASE_CONV_FEATS as i32,
+                window as i32,
+                window as i32,
+            ]);
+
+        if self.baseline() {
+            wide = baseline_wide(wide);
+            deep = deep.slice([0..batches, 0..BASELINE_CONV_FEATS, 0..window, 0..window]);
+        }
 
         // Batch norm
         // deep = self.bn.forward(deep);
@@ -153,7 +312,8 @@ impl<B: Backend> AgzActionModel<B> {
 
         // Reshape back to vector
         // [batch,deep_feat]
-        let deep_flat: Tensor<B, 2> = deep.reshape([batches as i32, DEEP_OUT_LEN as i32]);
+        let deep_out_len = DEEP_OUT_TILES * self.dense_per_action.len() * PER_ACTION_CHANNELS;
+        let deep_flat: Tensor<B, 2> = deep.reshape([batches as i32, deep_out_len as i32]);
 
         // [batch,feat]
         let wide_and_deep = Tensor::cat(vec![wide, deep_flat], 1);
@@ -163,74 +323,185 @@ impl<B: Backend> AgzActionModel<B> {
             out_common = d.forward(out_common);
         }
 
-        let out: Vec<Tensor<B, 2>> = (0..POSSIBLE_ACTIONS)
-            .map(|action_idx| {
-                let mut out = out_common.clone();
-                for (i, dense) in self.dense_per_action[action_idx].iter().enumerate() {
-                    out = dense.forward(out);
-                    // Only relu non-finally
-                    if i < self.dense_per_action[action_idx].len() - 1 {
-                        out = relu(out);
-                    }
-                }
-                out
-            })
-            .collect();
+        out_common
+    }
 
-        let action_probs = Tensor::cat(out, 1);
+    /// Run every action's dense layers on the output of the common ones
+    ///
+    /// Rather than a matrix multiplication per action per layer---hundreds of tiny kernel launches per decision on a
+    /// GPU---each layer's weights are stacked across actions so all the actions go through in one batched
+    /// multiplication.
+    ///
+    /// out_common: [batch,common_feat]
+    ///
+    /// -> [batch,action_idx] (logits)
+    fn forward_per_action(&self, out_common: Tensor<B, 2>) -> Tensor<B, 2> {
+        let batches = out_common.dims()[0];
+        let actions = self.dense_per_action.len();
+        let layers = self.dense_per_action[0].len();
+
+        // [action_idx,batch,feat]
+        let mut out: Tensor<B, 3> = out_common.unsqueeze::<3>().repeat(0, actions);
+
+        for layer in 0..layers {
+            // [action_idx,in_feat,out_feat]
+            let weights: Tensor<B, 3> = Tensor::stack(
+                self.dense_per_action
+                    .iter()
+                    .map(|dense| dense[layer].weight.val())
+                    .collect(),
+                0,
+            );
+            out = out.matmul(weights);
+
+            let biases: Option<Vec<Tensor<B, 1>>> = self
+                .dense_per_action
+                .iter()
+                .map(|dense| dense[layer].bias.as_ref().map(|bias| bias.val()))
+                .collect();
+            if let Some(biases) = biases {
+                // [action_idx,1,out_feat]
+                let biases: Tensor<B, 2> = Tensor::stack(biases, 0);
+                out = out + biases.unsqueeze_dim(1);
+            }
 
-        debug_assert_eq!(action_probs.dims().len(), 2);
-        debug_assert_eq!(action_probs.dims()[0], batches);
-        debug_assert_eq!(action_probs.dims()[1], POSSIBLE_ACTIONS);
+            // Only relu non-finally
+            if layer < layers - 1 {
+                out = relu(out);
+            }
+        }
 
-        sigmoid(action_probs)
+        // [action_idx,batch,1] -> [batch,action_idx]
+        out.reshape([actions, batches]).transpose()
     }
 
-    fn forward_by_action(
-        &self,
-        features: Tensor<B, 2>,
-        actions: Tensor<B, 1, Int>,
-    ) -> Tensor<B, 2> {
-        let batches = features.dims()[0];
-        let action_victory_probs = self.forward(features);
+    /// [batch,feat] -> the policy logits and victory probabilities, batch by batch
+    fn evaluate_tensors(&self, features: Tensor<B, 2>) -> (Vec<fX>, Vec<fX>) {
+        let (logits, value) = self.forward(features);
 
-        let actions_by_batch = actions.reshape([batches, 1]);
-        action_victory_probs.gather(1, actions_by_batch)
+        let to_vec = |tensor: Tensor<B, 2>| -> Vec<fX> {
+            tensor
+                .into_data()
+                .value
+                .into_iter()
+                .map(|x| x.to_f32().unwrap())
+                .collect()
+        };
+
+        (to_vec(logits), to_vec(value))
     }
 
-    /// [batch,feat]
-    fn evaluate_tensors(&self, features: Tensor<B, 2>) -> Vec<fX> {
-        let result_tensor = self.forward(features);
+    /// The policy logit of each possible action, city actions first, and the victory probability, given a single
+    /// feature vector
+    pub(crate) fn evaluate(&self, features: &[fX], device: &B::Device) -> (Vec<fX>, fX) {
+        // [batch,feat] (a batch of one)
+        let features: Tensor<B, 2> = Tensor::from_floats(features, device).reshape([1, -1]);
+        let (logits, value) = self.evaluate_tensors(features);
+        (logits, value[0])
+    }
 
-        // debug_assert!(result_tensor.device().is_cuda());
+    /// The loss on a batch: the value head's squared error predicting the outcome, plus the policy gradient loss,
+    /// the log-probability of the action taken weighted by how much better it went than the value head expected
+    ///
+    /// The output and targets reported are the value head's.
+    fn forward_training(&self, batch: AgzBatch<B>) -> RegressionOutput<B> {
+        let batches = batch.features.dims()[0];
 
-        // result_tensor.try_into().unwrap()
-        result_tensor
-            .into_data()
-            .value
-            .into_iter()
-            .map(|x| x.to_f32().unwrap())
-            .collect()
-    }
+        let (logits, value) = self.forward(batch.features);
 
-    /**
-     xs: [batch,feat]
-     targets: [batch,target] - we're forced into 2d by RegressionOutput, target will always be 0
-    */
-    fn forward_regression_bulk(
-        &self,
-        features: Tensor<B, 2>,
-        actions: Tensor<B, 1, Int>,
-        targets: Tensor<B, 1>,
-    ) -> RegressionOutput<B> {
-        let output = self.forward_by_action(features, actions);
-        let targets_batched = targets.reshape([-1, 1]);
-        let loss = MseLoss::new().forward(output.clone(), targets_batched.clone(), Reduction::Mean);
-
-        RegressionOutput::new(loss, output, targets_batched)
+        // [batch,1]
+        let targets = batch.targets.reshape([-1, 1]);
+
+        let value_loss = MseLoss::new().forward(value.clone(), targets.clone(), Reduction::Mean);
+
+        // Illegal actions get no share of the policy; the max is subtracted for numerical stability
+        let logits = logits
+            .clone()
+            .sub(logits.max_dim(1).detach())
+            .mask_fill(batch.illegal, ILLEGAL_LOGIT);
+
+        // [batch,1]
+        let log_prob_taken = log_softmax(logits, 1).gather(1, batch.actions.reshape([batches, 1]));
+
+        let advantage = targets.clone() - value.clone().detach();
+        let policy_loss = (advantage * log_prob_taken).mean().neg();
+
+        RegressionOutput::new(value_loss + policy_loss, value, targets)
     }
 }
 
+/// Stands in for the logits of illegal actions, leaving them no probability to speak of
+const ILLEGAL_LOGIT: fX = -1e9;
+
+/// The probability the policy gives each of the `legal` actions, by index, given its logits for all actions
+pub(crate) fn legal_policy(
+    logits: &[fX],
+    legal: impl IntoIterator<Item = usize>,
+) -> Vec<(usize, fX)> {
+    let legal_logits: Vec<(usize, fX)> = legal.into_iter().map(|i| (i, logits[i])).collect();
+
+    let max = legal_logits
+        .iter()
+        .map(|(_, logit)| *logit)
+        .fold(fX::NEG_INFINITY, fX::max);
+
+    let exps: Vec<(usize, fX)> = legal_logits
+        .into_iter()
+        .map(|(i, logit)| (i, (logit - max).exp()))
+        .collect();
+
+    let total: fX = exps.iter().map(|(_, exp)| *exp).sum();
+
+    exps.into_iter().map(|(i, exp)| (i, exp / total)).collect()
+}
+
+/// The wide features of the original layout, picked out of the current ones
+///
+/// wide: [batch,wide_feat] -> [batch,baseline_wide_feat]
+fn baseline_wide<B: Backend>(wide: Tensor<B, 2>) -> Tensor<B, 2> {
+    let batches = wide.dims()[0];
+
+    // The general features, then the unit type in focus writ large, then the counts of each unit type
+    let unit_type = ADDED_WIDE_FEATURES;
+    let is_city = unit_type + POSSIBLE_UNIT_TYPES;
+    let counts = unit_type + POSSIBLE_UNIT_TYPES_WRIT_LARGE;
+
+    Tensor::cat(
+        vec![
+            wide.clone()
+                .slice([0..batches, 0..BASELINE_ADDED_WIDE_FEATURES]),
+            wide.clone()
+                .slice([0..batches, unit_type..unit_type + BASELINE_UNIT_TYPES]),
+            wide.clone().slice([0..batches, is_city..is_city + 1]),
+            wide.slice([0..batches, counts..counts + BASELINE_UNIT_TYPES]),
+        ],
+        1,
+    )
+}
+
+/// The logits of a model of the original layout spread out over the current actions
+///
+/// The actions it doesn't know---producing the later unit types, and bombarding---get its lowest logit, so they're
+/// never preferred over those it does.
+///
+/// logits: [batch,baseline_action_idx] -> [batch,action_idx]
+fn baseline_logits<B: Backend>(logits: Tensor<B, 2>) -> Tensor<B, 2> {
+    let batches = logits.dims()[0];
+    let unknown = |n: usize| logits.clone().min_dim(1).repeat(1, n);
+
+    Tensor::cat(
+        vec![
+            logits.clone().slice([0..batches, 0..BASELINE_UNIT_TYPES]),
+            unknown(POSSIBLE_CITY_ACTIONS - BASELINE_UNIT_TYPES),
+            logits
+                .clone()
+                .slice([0..batches, BASELINE_UNIT_TYPES..BASELINE_POSSIBLE_ACTIONS]),
+            unknown(POSSIBLE_UNIT_ACTIONS - BASELINE_UNIT_ACTIONS),
+        ],
+        1,
+    )
+}
+
 impl<B: Backend> Loadable<B> for AgzActionModel<B> {
     fn load<P: AsRef<Path>>(path: P, device: B::Device) -> Result<Self, String> {
         let path = path.as_ref();
@@ -241,16 +512,10 @@ impl<B: Backend> Loadable<B> for AgzActionModel<B> {
             ));
         }
 
-        let recorder: BinFileRecorder<FullPrecisionSettings> = BinFileRecorder::new();
-
-        let dropout_config = DropoutConfig::new(P_DROPOUT);
-        let config = AgzActionModelConfig::new(POSSIBLE_ACTIONS, dropout_config);
-
-        let model: AgzActionModel<B> = config.init(device.clone());
+        // Read whole rather than through a file recorder, which couldn't tell what precision it was stored at
+        let bytes = fs::read(path).map_err(|e| e.to_string())?;
 
-        model
-            .load_file(path, &recorder, &device)
-            .map_err(|e| e.to_string())
+        Self::load_from_bytes(bytes.as_slice(), device)
     }
 }
 
@@ -259,29 +524,112 @@ impl<B: Backend> LoadableFromBytes<B> for AgzActionModel<B> {
         let dropout_config = DropoutConfig::new(P_DROPOUT);
         let config = AgzActionModelConfig::new(POSSIBLE_ACTIONS, dropout_config);
 
-        let model: AgzActionModel<B> = config.init(device.clone());
-
         let mut buf = Vec::new();
-        bytes.read_to_end(&mut buf).unwrap();
-
-        let record = BinBytesRecorder::<FullPrecisionSettings>::default()
-            .load(buf, &device)
-            .unwrap();
-
-        Ok(model.load_record(record))
+        bytes.read_to_end(&mut buf).map_err(|e| e.to_string())?;
+
+        let buf = match buf.strip_prefix(VERSION_MAGIC.as_slice()) {
+            Some([b0, b1, b2, b3, rest @ ..]) => {
+                let version = u32::from_le_bytes([*b0, *b1, *b2, *b3]);
+                if version != FEATURES_VERSION {
+                    return Err(format!(
+                        "Model was trained on version {} of the feature layout but version {} is current; retrain it",
+                        version, FEATURES_VERSION
+                    ));
+                }
+                rest.to_vec()
+            }
+            Some(_) => {
+                return Err(String::from(
+                    "Model ends before saying its version of the feature layout",
+                ))
+            }
+            None => buf,
+        };
+
+        let (config, buf) = match buf.strip_prefix(WINDOW_MAGIC.as_slice()) {
+            Some([lo, hi, rest @ ..]) => (
+                config.with_window(u16::from_le_bytes([*lo, *hi]) as usize),
+                rest.to_vec(),
+            ),
+            Some(_) => return Err(String::from("Model ends before saying its window")),
+            None => (config, buf),
+        };
+
+        let load = |config: &AgzActionModelConfig| {
+            precision::load_bytes(config.init::<B>(device.clone()), buf.clone(), &device).or_else(
+                |err| {
+                    // Perhaps it was stored before there was a value head
+                    let legacy = AgzActionModelV0::from(config.init::<B>(device.clone()));
+                    precision::load_bytes(legacy, buf.clone(), &device)
+                        .map(AgzActionModel::from)
+                        .map_err(|_| err)
+                },
+            )
+        };
+
+        load(&config).or_else(|err| {
+            // Perhaps it was trained on the original layout of the features, which it can still read
+            let baseline = config
+                .clone()
+                .with_possible_actions(BASELINE_POSSIBLE_ACTIONS)
+                .with_conv_feats(BASELINE_CONV_FEATS)
+                .with_wide_feats(BASELINE_WIDE_FEATS);
+
+            load(&baseline).map_err(|_| {
+                format!(
+                    "Model fits neither the current layout of the features nor the original one; retrain it: {}",
+                    err
+                )
+            })
+        })
     }
 }
 
 impl<B: Backend> Storable for AgzActionModel<B> {
+    /// Store the model at full precision, at `path` with its extension made .bin, as a file recorder would
     fn store(self, path: &Path) -> Result<(), String> {
-        let recorder: BinFileRecorder<FullPrecisionSettings> = BinFileRecorder::new();
+        let bytes = self.store_bytes(Precision::Full)?;
+        fs::write(path.with_extension("bin"), bytes).map_err(|e| e.to_string())
+    }
+}
+
+impl<B: Backend> AgzActionModel<B> {
+    /// Store the model at `path` exactly, its weights at the given precision
+    ///
+    /// Reduced precision makes for a smaller file; the weights are widened back to full precision when loaded.
+    pub fn store_with_precision(self, path: &Path, precision: Precision) -> Result<(), String> {
+        let bytes = self.store_bytes(precision)?;
+        fs::write(path, bytes).map_err(|e| e.to_string())
+    }
 
-        self.save_file(path, &recorder).map_err(|e| e.to_string())
+    /// The model's bytes, its weights at the given precision, led by its window if that isn't the default
+    ///
+    /// Models of the current layout are led by its version; those of the original layout are left untagged, as they
+    /// always were.
+    fn store_bytes(self, precision: Precision) -> Result<Vec<u8>, String> {
+        let window = self.window();
+        let baseline = self.baseline();
+        let weights = precision::store_bytes(self, precision)?;
+
+        let mut bytes = Vec::with_capacity(weights.len() + 12);
+        if !baseline {
+            bytes.extend_from_slice(VERSION_MAGIC);
+            bytes.extend(FEATURES_VERSION.to_le_bytes());
+        }
+        if window != DEEP_WIDTH {
+            bytes.extend_from_slice(WINDOW_MAGIC);
+            bytes.extend((window as u16).to_le_bytes());
+        }
+        bytes.extend(weights);
+        Ok(bytes)
     }
 }
 
 #[async_trait]
-impl ActionwiseTurnTaker2 for AgzActionModel<AiBackend> {
+impl<B: Backend> ActionwiseTurnTaker2 for AgzActionModel<B>
+where
+    B::Device: From<AiDevice>,
+{
     async fn next_city_action(
         &mut self,
         turn: &PlayerTurn,
@@ -297,24 +645,23 @@ impl ActionwiseTurnTaker2 for AgzActionModel<AiBackend> {
             return None;
         }
 
-        let device: AiBackendDevice = device.into();
+        let device: B::Device = device.into();
 
-        let feats = Self::features(turn, TrainingFocus::City).await;
+        let feats = self.features(turn, TrainingFocus::City).await;
 
         // [batch,feat] (a batch of one)
-        let feats: Tensor<AiBackend, 2> =
-            Tensor::from_floats(feats.as_slice(), &device).reshape([1, -1]);
+        let feats: Tensor<B, 2> = Tensor::from_floats(feats.as_slice(), &device).reshape([1, -1]);
 
-        let probs = self.evaluate_tensors(feats);
+        let (logits, _value) = self.evaluate_tensors(feats);
 
         // No offset is subtracted because city actions go first
-        let city_action_probs: Vec<(usize, fX)> = probs
+        let city_action_logits: Vec<(usize, fX)> = logits
             .into_iter()
             .enumerate() // enumerating yields city action indices because city actions go first
-            .filter(|(i, _p_victory_ish)| legal_action_indices.contains(i))
+            .filter(|(i, _logit)| legal_action_indices.contains(i))
             .collect();
 
-        let city_action_idx = max_sample_idx(&city_action_probs);
+        let city_action_idx = max_sample_idx(&city_action_logits);
 
         debug_assert!(
             city_action_idx < POSSIBLE_CITY_ACTIONS,
@@ -342,21 +689,22 @@ impl ActionwiseTurnTaker2 for AgzActionModel<AiBackend> {
             return None;
         }
 
-        let device: AiBackendDevice = device.into();
+        let device: B::Device = device.into();
 
-        let feats = Self::features(turn, TrainingFocus::Unit).await;
+        let feats = self.features(turn, TrainingFocus::Unit).await;
 
         let feats = Tensor::from_floats(feats.as_slice(), &device).reshape([1, -1]);
 
-        let unit_action_probs: Vec<(usize, fX)> = self
-            .evaluate_tensors(feats)
+        let (logits, _value) = self.evaluate_tensors(feats);
+
+        let unit_action_logits: Vec<(usize, fX)> = logits
             .into_iter()
             .skip(POSSIBLE_CITY_ACTIONS) // ignore the city prefix
             .enumerate() // enumerate now so we get unit action indices
-            .filter(|(i, _p_victory_ish)| legal_action_indices.contains(i))
+            .filter(|(i, _logit)| legal_action_indices.contains(i))
             .collect();
 
-        let unit_action_idx = max_sample_idx(&unit_action_probs);
+        let unit_action_idx = max_sample_idx(&unit_action_logits);
 
         debug_assert!(
             unit_action_idx < POSSIBLE_UNIT_ACTIONS,
@@ -371,7 +719,7 @@ impl ActionwiseTurnTaker2 for AgzActionModel<AiBackend> {
 
 impl<B: AutodiffBackend> TrainStep<AgzBatch<B>, RegressionOutput<B>> for AgzActionModel<B> {
     fn step(&self, batch: AgzBatch<B>) -> TrainOutput<RegressionOutput<B>> {
-        let item = self.forward_regression_bulk(batch.features, batch.actions, batch.targets);
+        let item = self.forward_training(batch);
 
         TrainOutput::new(self, item.loss.backward(), item)
     }
@@ -379,6 +727,226 @@ impl<B: AutodiffBackend> TrainStep<AgzBatch<B>, RegressionOutput<B>> for AgzActi
 
 impl<B: Backend> ValidStep<AgzBatch<B>, RegressionOutput<B>> for AgzActionModel<B> {
     fn step(&self, batch: AgzBatch<B>) -> RegressionOutput<B> {
-        self.forward_regression_bulk(batch.features, batch.actions, batch.targets)
+        self.forward_training(batch)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use burn::{
+        backend::{ndarray::NdArrayDevice, NdArray},
+        nn::DropoutConfig,
+        tensor::{activation::relu, Distribution, Tensor},
+    };
+    use num_traits::ToPrimitive;
+
+    use common::game::ai::{
+        fX, feats_len, DEEP_WIDTH, FEATS_LEN, FEATURES_VERSION, POSSIBLE_ACTIONS,
+        POSSIBLE_CITY_ACTIONS, P_DROPOUT, WIDE_LEN,
+    };
+
+    use crate::{precision, LoadableFromBytes};
+
+    use super::{
+        baseline_wide, legal_policy, AgzActionModel, AgzActionModelConfig, AgzActionModelV0,
+        BASELINE_CONV_FEATS, BASELINE_POSSIBLE_ACTIONS, BASELINE_UNIT_TYPES, BASELINE_WIDE_FEATS,
+        MIN_WINDOW, VERSION_MAGIC,
+    };
+
+    #[test]
+    fn test_legal_policy() {
+        let logits = vec![0.0, 100.0, 2.0f32.ln(), 0.0];
+
+        let policy = legal_policy(&logits, [0, 2, 3]);
+
+        assert_eq!(
+            policy.iter().map(|(i, _)| *i).collect::<Vec<_>>(),
+            vec![0, 2, 3]
+        );
+        for ((_, p), expected) in policy.iter().zip([0.25, 0.5, 0.25]) {
+            assert!((p - expected).abs() < 1e-6);
+        }
+    }
+
+    /// Models stored before there was a value head still load, without one
+    #[test]
+    fn test_load_without_value_head() {
+        let device = NdArrayDevice::Cpu;
+        let config = AgzActionModelConfig::new(POSSIBLE_ACTIONS, DropoutConfig::new(P_DROPOUT));
+
+        let legacy = AgzActionModelV0::from(config.init::<NdArray>(device));
+        let bytes = precision::store_bytes(legacy, precision::Precision::Full).unwrap();
+
+        let model = AgzActionModel::<NdArray>::load_from_bytes(bytes.as_slice(), device).unwrap();
+        assert!(model.value.is_none());
+
+        let bytes =
+            precision::store_bytes(config.init::<NdArray>(device), precision::Precision::Full)
+                .unwrap();
+
+        let model = AgzActionModel::<NdArray>::load_from_bytes(bytes.as_slice(), device).unwrap();
+        assert!(model.value.is_some());
+    }
+
+    /// Models trained on the original layout of the features load, and play the current actions
+    #[test]
+    fn test_load_baseline_layout() {
+        let device = NdArrayDevice::Cpu;
+        let config = AgzActionModelConfig::new(POSSIBLE_ACTIONS, DropoutConfig::new(P_DROPOUT));
+        let baseline = config
+            .clone()
+            .with_possible_actions(BASELINE_POSSIBLE_ACTIONS)
+            .with_conv_feats(BASELINE_CONV_FEATS)
+            .with_wide_feats(BASELINE_WIDE_FEATS);
+
+        for precision in [precision::Precision::Full, precision::Precision::Int8] {
+            let legacy = AgzActionModelV0::from(baseline.init::<NdArray>(device));
+            let bytes = precision::store_bytes(legacy, precision).unwrap();
+
+            let model =
+                AgzActionModel::<NdArray>::load_from_bytes(bytes.as_slice(), device).unwrap();
+            assert!(model.baseline());
+
+            let (logits, _value) = model.evaluate(&[0.0; FEATS_LEN], &device);
+            assert_eq!(logits.len(), POSSIBLE_ACTIONS);
+
+            // Bombarding and producing the later unit types are never preferred
+            let lowest = logits.iter().copied().fold(fX::INFINITY, fX::min);
+            for unknown in
+                (BASELINE_UNIT_TYPES..POSSIBLE_CITY_ACTIONS).chain([POSSIBLE_ACTIONS - 1])
+            {
+                assert_eq!(logits[unknown], lowest);
+            }
+
+            let other = config.clone().with_conv_feats(BASELINE_CONV_FEATS + 1);
+            let bytes = precision::store_bytes(other.init::<NdArray>(device), precision).unwrap();
+
+            let err = AgzActionModel::<NdArray>::load_from_bytes(bytes.as_slice(), device)
+                .err()
+                .unwrap();
+            assert!(err.contains("retrain"), "{}", err);
+        }
+    }
+
+    #[test]
+    fn test_baseline_wide() {
+        let device = NdArrayDevice::Cpu;
+
+        let wide: Vec<fX> = (0..WIDE_LEN).map(|i| i as fX).collect();
+        let wide: Tensor<NdArray, 2> =
+            Tensor::from_floats(wide.as_slice(), &device).reshape([1, -1]);
+
+        let picked: Vec<fX> = baseline_wide(wide)
+            .into_data()
+            .value
+            .into_iter()
+            .map(|x| x.to_f32().unwrap())
+            .collect();
+
+        // The general features, the original unit types writ large, whether a city, and the original unit types' counts
+        let expected: Vec<fX> = (0..13)
+            .chain(14..24)
+            .chain([29])
+            .chain(30..40)
+            .map(|i| i as fX)
+            .collect();
+        assert_eq!(picked, expected);
+        assert_eq!(picked.len(), BASELINE_WIDE_FEATS);
+    }
+
+    /// Models are tagged with the version of the feature layout they were trained under, and refused under another
+    #[test]
+    fn test_version() {
+        let device = NdArrayDevice::Cpu;
+        let config = AgzActionModelConfig::new(POSSIBLE_ACTIONS, DropoutConfig::new(P_DROPOUT));
+
+        let bytes = config
+            .init::<NdArray>(device)
+            .store_bytes(precision::Precision::Full)
+            .unwrap();
+        assert!(bytes.starts_with(VERSION_MAGIC));
+        assert!(AgzActionModel::<NdArray>::load_from_bytes(bytes.as_slice(), device).is_ok());
+
+        let mut stale = VERSION_MAGIC.to_vec();
+        stale.extend((FEATURES_VERSION + 1).to_le_bytes());
+        stale.extend(&bytes[VERSION_MAGIC.len() + 4..]);
+        let err = AgzActionModel::<NdArray>::load_from_bytes(stale.as_slice(), device)
+            .err()
+            .unwrap();
+        assert!(err.contains("retrain"), "{}", err);
+
+        // Models of the original layout stay untagged
+        let baseline = config
+            .with_possible_actions(BASELINE_POSSIBLE_ACTIONS)
+            .with_conv_feats(BASELINE_CONV_FEATS)
+            .with_wide_feats(BASELINE_WIDE_FEATS)
+            .init::<NdArray>(device);
+        let bytes = baseline.store_bytes(precision::Precision::Full).unwrap();
+        assert!(!bytes.starts_with(VERSION_MAGIC));
+        assert!(AgzActionModel::<NdArray>::load_from_bytes(bytes.as_slice(), device).is_ok());
+    }
+
+    /// Models see windows as wide as configured, and remember how wide when stored
+    #[test]
+    fn test_window() {
+        let device = NdArrayDevice::Cpu;
+        let config = AgzActionModelConfig::new(POSSIBLE_ACTIONS, DropoutConfig::new(P_DROPOUT));
+
+        assert_eq!(config.init::<NdArray>(device).window(), DEEP_WIDTH);
+
+        for window in [MIN_WINDOW, 21] {
+            let model = config.clone().with_window(window).init::<NdArray>(device);
+            assert_eq!(model.window(), window);
+
+            let (logits, _value) = model.evaluate(&vec![0.0; feats_len(window)], &device);
+            assert_eq!(logits.len(), POSSIBLE_ACTIONS);
+
+            for precision in [precision::Precision::Full, precision::Precision::Half] {
+                let bytes = model.clone().store_bytes(precision).unwrap();
+                let loaded =
+                    AgzActionModel::<NdArray>::load_from_bytes(bytes.as_slice(), device).unwrap();
+                assert_eq!(loaded.window(), window);
+            }
+        }
+    }
+
+    /// The batched per-action layers give what running each action's layers in turn would
+    #[test]
+    fn test_forward_per_action() {
+        let device = NdArrayDevice::Cpu;
+        let model = AgzActionModelConfig::new(POSSIBLE_ACTIONS, DropoutConfig::new(P_DROPOUT))
+            .init::<NdArray>(device);
+
+        let out_common: Tensor<NdArray, 2> =
+            Tensor::random([3, 32], Distribution::Default, &device);
+
+        let one_at_a_time: Vec<Tensor<NdArray, 2>> = model
+            .dense_per_action
+            .iter()
+            .map(|denses| {
+                let mut out = out_common.clone();
+                for (i, dense) in denses.iter().enumerate() {
+                    out = dense.forward(out);
+                    if i < denses.len() - 1 {
+                        out = relu(out);
+                    }
+                }
+                out
+            })
+            .collect();
+        let one_at_a_time = Tensor::cat(one_at_a_time, 1);
+
+        let batched = model.forward_per_action(out_common);
+
+        assert_eq!(batched.dims(), [3, POSSIBLE_ACTIONS]);
+
+        for (x, y) in batched
+            .into_data()
+            .value
+            .into_iter()
+            .zip(one_at_a_time.into_data().value)
+        {
+            assert!((x.to_f32().unwrap() - y.to_f32().unwrap()).abs() < 1e-5);
+        }
     }
 }
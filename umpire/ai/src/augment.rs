@@ -0,0 +1,270 @@
+//! Augmenting training data by the board's symmetries
+//!
+//! A position turned or mirrored is just as good a position, and the move made in it turned or mirrored just as good
+//! a move. So each training datum can stand for eight: the window of 2d features rotated and reflected, the
+//! directions of moves remapped to match, and the wide features that depend on which way is up---the map's
+//! dimensions and wrapping, and where the city or unit in focus stands---adjusted likewise.
+
+use common::{
+    game::{
+        action::{AiPlayerAction, NextUnitAction},
+        ai::{fX, BASE_CONV_FEATS, WIDE_LEN},
+    },
+    util::{Direction, Vec2d},
+};
+
+use crate::data::{features_window, AgzDatum};
+
+// Indices of the wide features that depend on the board's orientation, as laid out by `Game::player_features`
+const MAP_WIDTH: usize = 4;
+const MAP_HEIGHT: usize = 5;
+const WRAP_HORIZ: usize = 6;
+const WRAP_VERT: usize = 7;
+const LOC_X: usize = 8;
+const LOC_Y: usize = 9;
+const LOC_X_FRAC: usize = 10;
+const LOC_Y_FRAC: usize = 11;
+
+/// One of the eight symmetries of a square: a transposition about the main diagonal, or not, followed by horizontal
+/// and vertical flips, or not
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct BoardSymmetry {
+    transpose: bool,
+    flip_x: bool,
+    flip_y: bool,
+}
+
+impl BoardSymmetry {
+    pub const IDENTITY: Self = Self {
+        transpose: false,
+        flip_x: false,
+        flip_y: false,
+    };
+
+    /// All eight symmetries, the identity first
+    pub fn values() -> [Self; 8] {
+        let mut values = [Self::IDENTITY; 8];
+        for (i, value) in values.iter_mut().enumerate() {
+            value.transpose = i & 4 != 0;
+            value.flip_x = i & 2 != 0;
+            value.flip_y = i & 1 != 0;
+        }
+        values
+    }
+
+    /// Where the offset `offset` from the center ends up
+    fn offset(self, offset: Vec2d<i32>) -> Vec2d<i32> {
+        let Vec2d { mut x, mut y } = offset;
+        if self.transpose {
+            (x, y) = (y, x);
+        }
+        if self.flip_x {
+            x = -x;
+        }
+        if self.flip_y {
+            y = -y;
+        }
+        Vec2d::new(x, y)
+    }
+
+    pub fn direction(self, direction: Direction) -> Direction {
+        Direction::try_from(self.offset(direction.into())).unwrap()
+    }
+
+    /// The action with any direction remapped; actions without one are unchanged
+    pub fn action(self, action: AiPlayerAction) -> AiPlayerAction {
+        match action {
+            AiPlayerAction::Unit(NextUnitAction::Move { direction }) => {
+                AiPlayerAction::Unit(NextUnitAction::Move {
+                    direction: self.direction(direction),
+                })
+            }
+            action => action,
+        }
+    }
+
+    /// The feature vector as it would be seen on the board transformed by this symmetry
+    pub fn features(self, features: &[fX]) -> Result<Vec<fX>, String> {
+        let window = features_window(features)?;
+        let half = (window / 2) as i32;
+
+        let (wide, deep) = features.split_at(WIDE_LEN);
+
+        let mut wide = wide.to_vec();
+        if self.transpose {
+            wide.swap(MAP_WIDTH, MAP_HEIGHT);
+            wide.swap(WRAP_HORIZ, WRAP_VERT);
+            wide.swap(LOC_X, LOC_Y);
+            wide.swap(LOC_X_FRAC, LOC_Y_FRAC);
+        }
+        if self.flip_x {
+            wide[LOC_X] = wide[MAP_WIDTH] - 1.0 - wide[LOC_X];
+            wide[LOC_X_FRAC] = wide[LOC_X] / wide[MAP_WIDTH];
+        }
+        if self.flip_y {
+            wide[LOC_Y] = wide[MAP_HEIGHT] - 1.0 - wide[LOC_Y];
+            wide[LOC_Y_FRAC] = wide[LOC_Y] / wide[MAP_HEIGHT];
+        }
+
+        let mut transformed = wide;
+        transformed.resize(features.len(), 0.0);
+
+        for y in 0..window {
+            for x in 0..window {
+                let to = self.offset(Vec2d::new(x as i32 - half, y as i32 - half));
+                let to_x = (to.x + half) as usize;
+                let to_y = (to.y + half) as usize;
+
+                for c in 0..BASE_CONV_FEATS {
+                    let channel = c * window * window;
+                    transformed[WIDE_LEN + channel + to_y * window + to_x] =
+                        deep[channel + y * window + x];
+                }
+            }
+        }
+
+        Ok(transformed)
+    }
+
+    pub fn datum(self, datum: &AgzDatum) -> Result<AgzDatum, String> {
+        Ok(AgzDatum {
+            features: self.features(&datum.features)?,
+            legal_actions: datum
+                .legal_actions
+                .iter()
+                .map(|action| self.action(*action))
+                .collect(),
+            action: self.action(datum.action),
+            turns_until_outcome: datum.turns_until_outcome,
+            outcome: datum.outcome,
+        })
+    }
+}
+
+/// The data along with their images under every other symmetry, eight times as many in all
+pub fn augment(data: Vec<AgzDatum>) -> Result<Vec<AgzDatum>, String> {
+    let mut augmented = Vec::with_capacity(data.len() * BoardSymmetry::values().len());
+    for datum in data {
+        for symmetry in BoardSymmetry::values().into_iter().skip(1) {
+            augmented.push(symmetry.datum(&datum)?);
+        }
+        augmented.push(datum);
+    }
+    Ok(augmented)
+}
+
+#[cfg(test)]
+mod test {
+    use std::collections::BTreeSet;
+
+    use common::{
+        game::ai::{deep_in_len, fX, BASE_CONV_FEATS, WIDE_LEN},
+        util::{Direction, Vec2d},
+    };
+
+    use super::{BoardSymmetry, LOC_X, LOC_X_FRAC, LOC_Y, MAP_HEIGHT, MAP_WIDTH};
+
+    #[test]
+    fn test_directions() {
+        let symmetries = BoardSymmetry::values();
+        assert_eq!(symmetries[0], BoardSymmetry::IDENTITY);
+
+        // Each symmetry moves things differently
+        let mappings: BTreeSet<[Direction; 8]> = symmetries
+            .iter()
+            .map(|symmetry| Direction::values().map(|direction| symmetry.direction(direction)))
+            .collect();
+        assert_eq!(mappings.len(), symmetries.len());
+
+        for symmetry in symmetries {
+            let directions: BTreeSet<Direction> = Direction::values()
+                .into_iter()
+                .map(|direction| symmetry.direction(direction))
+                .collect();
+            assert_eq!(directions.len(), Direction::values().len());
+        }
+
+        let transpose = BoardSymmetry {
+            transpose: true,
+            flip_x: false,
+            flip_y: false,
+        };
+        assert_eq!(transpose.direction(Direction::Up), Direction::Left);
+        assert_eq!(transpose.direction(Direction::UpRight), Direction::DownLeft);
+
+        let flip_x = BoardSymmetry {
+            transpose: false,
+            flip_x: true,
+            flip_y: false,
+        };
+        assert_eq!(flip_x.direction(Direction::UpLeft), Direction::UpRight);
+        assert_eq!(flip_x.direction(Direction::Down), Direction::Down);
+    }
+
+    /// What lies in a direction lies, after the features are transformed, in that direction transformed
+    #[test]
+    fn test_features() {
+        let window = 5;
+        let center = window / 2;
+
+        for direction in Direction::values() {
+            let Vec2d { x: dx, y: dy } = Vec2d::from(direction);
+            let idx = |c: usize, dx: i32, dy: i32| {
+                let x = (center as i32 + 2 * dx) as usize;
+                let y = (center as i32 + 2 * dy) as usize;
+                WIDE_LEN + c * window * window + y * window + x
+            };
+
+            let mut features: Vec<fX> = vec![0.0; WIDE_LEN + deep_in_len(window)];
+            for c in 0..BASE_CONV_FEATS {
+                features[idx(c, dx, dy)] = (c + 1) as fX;
+            }
+
+            for symmetry in BoardSymmetry::values() {
+                let transformed = symmetry.features(&features).unwrap();
+                let to = Vec2d::from(symmetry.direction(direction));
+
+                for c in 0..BASE_CONV_FEATS {
+                    assert_eq!(transformed[idx(c, to.x, to.y)], (c + 1) as fX);
+                }
+                assert_eq!(
+                    transformed[WIDE_LEN..]
+                        .iter()
+                        .filter(|x| **x != 0.0)
+                        .count(),
+                    BASE_CONV_FEATS
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_wide_features() {
+        let mut features: Vec<fX> = vec![0.0; WIDE_LEN + deep_in_len(3)];
+        features[MAP_WIDTH] = 10.0;
+        features[MAP_HEIGHT] = 20.0;
+        features[LOC_X] = 2.0;
+        features[LOC_Y] = 5.0;
+
+        let flip_x = BoardSymmetry {
+            transpose: false,
+            flip_x: true,
+            flip_y: false,
+        };
+        let flipped = flip_x.features(&features).unwrap();
+        assert_eq!(flipped[LOC_X], 7.0);
+        assert_eq!(flipped[LOC_X_FRAC], 0.7);
+        assert_eq!(flipped[LOC_Y], 5.0);
+
+        let transpose = BoardSymmetry {
+            transpose: true,
+            flip_x: false,
+            flip_y: false,
+        };
+        let transposed = transpose.features(&features).unwrap();
+        assert_eq!(transposed[MAP_WIDTH], 20.0);
+        assert_eq!(transposed[MAP_HEIGHT], 10.0);
+        assert_eq!(transposed[LOC_X], 5.0);
+        assert_eq!(transposed[LOC_Y], 2.0);
+    }
+}
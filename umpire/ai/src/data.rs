@@ -1,16 +1,100 @@
+use std::{
+    collections::BTreeSet,
+    io::{Read, Write},
+};
+
 use burn::{
     data::{dataloader::batcher::Batcher, dataset::Dataset},
-    tensor::{backend::Backend, Int, Tensor},
+    tensor::{backend::Backend, Bool, Int, Tensor},
 };
 use common::game::{
     action::AiPlayerAction,
-    ai::{fX, TrainingOutcome},
+    ai::{
+        deep_in_len, fX, TrainingOutcome, BASE_CONV_FEATS, FEATURES_VERSION, POSSIBLE_ACTIONS,
+        WIDE_LEN,
+    },
     TurnNum,
 };
 
+/// Leads each file of training data, followed by the `FEATURES_VERSION` its features were generated under
+const DATA_MAGIC: &[u8; 4] = b"UMPD";
+
+/// Begin a file of training data, tagging it with the current layout of the features
+pub fn write_data_header<W: Write>(w: &mut W) -> Result<(), String> {
+    w.write_all(DATA_MAGIC).map_err(|err| err.to_string())?;
+    bincode::serialize_into(w, &FEATURES_VERSION).map_err(|err| err.to_string())
+}
+
+/// Read past the header of a file of training data, making sure its features are laid out as they are now
+pub fn read_data_header<R: Read>(r: &mut R) -> Result<(), String> {
+    let mut magic = [0u8; 4];
+    r.read_exact(&mut magic).map_err(|err| err.to_string())?;
+
+    // Data from before there were versions has no header, and no telling which of the earlier layouts it's in
+    if &magic != DATA_MAGIC {
+        return Err(String::from(
+            "Training data is unversioned, its layout of the features unknown; regenerate it",
+        ));
+    }
+
+    let version: u32 = bincode::deserialize_from(r).map_err(|err| err.to_string())?;
+
+    if version != FEATURES_VERSION {
+        return Err(format!(
+            "Training data has feature version {} but version {} is expected; regenerate it",
+            version, FEATURES_VERSION
+        ));
+    }
+
+    Ok(())
+}
+
+/// The width of the window of tiles that feature vector `features` covers
+pub(crate) fn features_window(features: &[fX]) -> Result<usize, String> {
+    let deep_len = features.len().saturating_sub(WIDE_LEN);
+    (0..)
+        .take_while(|w| deep_in_len(*w) <= deep_len)
+        .find(|w| deep_in_len(*w) == deep_len)
+        .ok_or_else(|| format!("{} features don't make a square window", features.len()))
+}
+
+/// Crop feature vector `features` so its 2d features cover a window only `window` tiles wide, about the same center
+///
+/// Lets data generated with a wider window train models with narrower ones.
+pub fn crop_features(features: &[fX], window: usize) -> Result<Vec<fX>, String> {
+    let from = features_window(features)?;
+
+    if from < window {
+        return Err(format!(
+            "Features cover a window {} tiles wide, narrower than the {} wanted",
+            from, window
+        ));
+    }
+
+    let (wide, deep) = features.split_at(WIDE_LEN);
+    let offset = (from - window) / 2;
+
+    let mut cropped = Vec::with_capacity(WIDE_LEN + deep_in_len(window));
+    cropped.extend_from_slice(wide);
+    for c in 0..BASE_CONV_FEATS {
+        for y in 0..window {
+            let start = c * from * from + (y + offset) * from + offset;
+            cropped.extend_from_slice(&deep[start..start + window]);
+        }
+    }
+
+    Ok(cropped)
+}
+
 #[derive(Clone, Debug)]
 pub struct AgzDatum {
     pub features: Vec<fX>,
+
+    /// The actions among which the player chose
+    ///
+    /// If empty, all actions are taken to have been legal.
+    pub legal_actions: BTreeSet<AiPlayerAction>,
+
     pub action: AiPlayerAction,
     pub turns_until_outcome: TurnNum,
     pub outcome: TrainingOutcome,
@@ -47,13 +131,19 @@ impl<B: Backend> AgzBatcher<B> {
 }
 
 /**
- * A batch of AlphaGo Zero style state-action victory probabilities.
+ * A batch of AlphaGo Zero style training targets: the actions taken, for the policy, and the victory probabilities,
+ * for the value.
 */
 #[derive(Clone, Debug)]
 pub struct AgzBatch<B: Backend> {
     /// [batch_size, feature_idx]
     pub features: Tensor<B, 2>,
 
+    /// Which actions weren't available to choose from
+    ///
+    /// [batch_size, action_idx]
+    pub illegal: Tensor<B, 2, Bool>,
+
     /// [batch_size]
     pub actions: Tensor<B, 1, Int>,
 
@@ -73,6 +163,21 @@ impl<B: Backend> Batcher<AgzDatum, AgzBatch<B>> for AgzBatcher<B> {
 
         let features = Tensor::cat(features, 0).to_device(&self.device);
 
+        let legal: Vec<i32> = items
+            .iter()
+            .flat_map(|item| {
+                (0..POSSIBLE_ACTIONS).map(|idx| {
+                    let legal = item.legal_actions.is_empty()
+                        || item.legal_actions.contains(&AiPlayerAction::from(idx));
+                    legal as i32
+                })
+            })
+            .collect();
+        let illegal: Tensor<B, 2, Bool> =
+            Tensor::<B, 1, Int>::from_ints(legal.as_slice(), &self.device)
+                .reshape([items.len(), POSSIBLE_ACTIONS])
+                .equal_elem(0);
+
         let actions: Vec<i32> = items
             .iter()
             .map(|item| {
@@ -90,8 +195,58 @@ impl<B: Backend> Batcher<AgzDatum, AgzBatch<B>> for AgzBatcher<B> {
 
         AgzBatch {
             features,
+            illegal,
             actions,
             targets,
         }
     }
 }
+
+#[cfg(test)]
+mod test {
+    use common::game::ai::{deep_in_len, fX, BASE_CONV_FEATS, FEATURES_VERSION, WIDE_LEN};
+
+    use super::{crop_features, read_data_header, write_data_header};
+
+    #[test]
+    fn test_data_header() {
+        let mut bytes: Vec<u8> = Vec::new();
+        write_data_header(&mut bytes).unwrap();
+        assert_eq!(read_data_header(&mut bytes.as_slice()), Ok(()));
+
+        // Unversioned data, which begins with the first instance's player number
+        let legacy = bincode::serialize(&0usize).unwrap();
+        assert!(read_data_header(&mut legacy.as_slice())
+            .unwrap_err()
+            .contains("unversioned"));
+
+        let mut stale: Vec<u8> = b"UMPD".to_vec();
+        stale.extend(bincode::serialize(&(FEATURES_VERSION - 1)).unwrap());
+        assert!(read_data_header(&mut stale.as_slice()).is_err());
+    }
+
+    #[test]
+    fn test_crop_features() {
+        // Each 2d feature its channel, row, and column, as digits
+        let features = |window: usize, offset: usize| -> Vec<fX> {
+            let mut features: Vec<fX> = (0..WIDE_LEN).map(|i| i as fX).collect();
+            for c in 0..BASE_CONV_FEATS {
+                for y in 0..window {
+                    for x in 0..window {
+                        features.push((100 * c + 10 * (y + offset) + x + offset) as fX);
+                    }
+                }
+            }
+            features
+        };
+
+        let cropped = crop_features(&features(5, 0), 3).unwrap();
+        assert_eq!(cropped.len(), WIDE_LEN + deep_in_len(3));
+        assert_eq!(cropped, features(3, 1));
+
+        assert_eq!(crop_features(&features(5, 0), 5), Ok(features(5, 0)));
+
+        assert!(crop_features(&features(3, 0), 5).is_err());
+        assert!(crop_features(&features(3, 0)[1..], 3).is_err());
+    }
+}
@@ -0,0 +1,473 @@
+//! A hand-written AI, stronger than random but no neural network
+//!
+//! Cities build infantry to garrison and expand with, then armor; once there's nowhere left to expand to over land,
+//! coastal cities build transports. Units take the likeliest-won fight next to them, if it's likely enough.
+//! Otherwise land units head for the nearest city that isn't theirs, or into the unknown, boarding a transport when
+//! the way's blocked by water; transports wait in port for a load and carry it to the nearest city that isn't
+//! theirs. A city's lone defender stays put while enemies are near.
+//!
+//! Everything goes by what the player has observed, so it plays fair under fog of war.
+
+use std::collections::{BTreeMap, VecDeque};
+
+use async_trait::async_trait;
+
+use common::{
+    game::{
+        action::{NextCityAction, NextUnitAction},
+        ai::AiDevice,
+        alignment::Alignment,
+        map::{dijkstra::Source, Tile},
+        obs::Obs,
+        player::PlayerTurn,
+        turn_async::ActionwiseTurnTaker2,
+        unit::{TransportMode, Unit, UnitType},
+    },
+    util::{Direction, Location, Wrap},
+};
+
+/// The least chance of victory worth attacking at
+const ATTACK_THRESHOLD: f64 = 0.5;
+
+/// How near, in moves, an enemy has to be for a city's lone defender to stay put
+const THREAT_RADIUS: u16 = 3;
+
+/// How many units a transport waits for before setting out, if it can carry that many
+const TRANSPORT_LOAD: usize = 2;
+
+pub struct HeuristicAI;
+
+impl HeuristicAI {
+    /// The first step on a shortest path from `from` to the nearest tile for which `is_target` is true, passing only
+    /// through observed tiles for which `passable` is true
+    ///
+    /// `from` itself doesn't count as a target.
+    fn first_step(
+        turn: &PlayerTurn,
+        from: Location,
+        passable: impl Fn(&Tile) -> bool,
+        is_target: impl Fn(Location, &Obs) -> bool,
+    ) -> Option<Direction> {
+        let dims = turn.dims();
+        let wrapping = turn.wrapping();
+        let observations = turn.observations();
+
+        // Each location reached, by the first step taken toward it
+        let mut reached: BTreeMap<Location, Option<Direction>> = BTreeMap::new();
+        reached.insert(from, None);
+
+        let mut frontier: VecDeque<Location> = VecDeque::new();
+        frontier.push_back(from);
+
+        while let Some(loc) = frontier.pop_front() {
+            let first = reached[&loc];
+
+            for direction in Direction::values() {
+                let Some(neighb) = loc.shift_wrapped(direction, dims, wrapping) else {
+                    continue;
+                };
+                if reached.contains_key(&neighb) {
+                    continue;
+                }
+
+                let first = first.or(Some(direction));
+                reached.insert(neighb, first);
+
+                let Some(obs) = observations.get(neighb) else {
+                    continue;
+                };
+
+                if is_target(neighb, obs) {
+                    return first;
+                }
+
+                if let Obs::Observed { tile, .. } = obs {
+                    if passable(tile) {
+                        frontier.push_back(neighb);
+                    }
+                }
+            }
+        }
+
+        None
+    }
+
+    /// Whether infantry could head somewhere new over land from `loc`
+    fn can_expand_overland(turn: &PlayerTurn, player: Alignment, loc: Location) -> bool {
+        Self::first_step(
+            turn,
+            loc,
+            |tile| UnitType::Infantry.can_move_on_tile(tile) && tile.unit.is_none(),
+            |_, obs| is_expansion_target(player, UnitType::Infantry, obs),
+        )
+        .is_some()
+    }
+
+    /// The direction of the adjacent enemy the unit stands the best chance against, if it's a good enough chance
+    async fn weakest_neighbor(
+        turn: &PlayerTurn,
+        unit: &Unit,
+        legal: &[Direction],
+    ) -> Option<Direction> {
+        let mut best: Option<(Direction, f64)> = None;
+
+        for direction in legal.iter().copied() {
+            let Some(dest) = unit
+                .loc
+                .shift_wrapped(direction, turn.dims(), turn.wrapping())
+            else {
+                continue;
+            };
+            let Some(tile) = turn.tile(dest) else {
+                continue;
+            };
+
+            let hostile = tile
+                .unit
+                .as_ref()
+                .map_or(false, |defender| defender.alignment != unit.alignment)
+                || tile
+                    .city
+                    .as_ref()
+                    .map_or(false, |city| city.alignment != unit.alignment);
+            if !hostile || !unit.can_attack_tile(&tile) {
+                continue;
+            }
+
+            let Ok(odds) = turn.combat_odds(unit.id, dest).await else {
+                continue;
+            };
+
+            if best.map_or(true, |(_, prob)| odds.victory_prob > prob) {
+                best = Some((direction, odds.victory_prob));
+            }
+        }
+
+        best.filter(|(_, prob)| *prob >= ATTACK_THRESHOLD)
+            .map(|(direction, _)| direction)
+    }
+
+    /// Whether `unit` is the only defender of one of its player's cities, with an enemy near
+    fn should_garrison(turn: &PlayerTurn, unit: &Unit) -> bool {
+        let Some(tile) = turn.tile(unit.loc) else {
+            return false;
+        };
+
+        let in_own_city = tile
+            .city
+            .as_ref()
+            .map_or(false, |city| city.alignment == unit.alignment);
+        if !in_own_city || !tile.stack.is_empty() {
+            return false;
+        }
+
+        let dims = turn.dims();
+        let wrapping = turn.wrapping();
+        let observations = turn.observations();
+
+        dims.iter_locs().any(|loc| {
+            wrapped_distance(dims.width, wrapping.horiz, unit.loc.x, loc.x) <= THREAT_RADIUS
+                && wrapped_distance(dims.height, wrapping.vert, unit.loc.y, loc.y) <= THREAT_RADIUS
+                && matches!(
+                    observations.get(loc),
+                    Some(Obs::Observed { tile, .. }) if tile.unit.as_ref().map_or(false, |other| {
+                        other.alignment.is_belligerent() && other.alignment != unit.alignment
+                    })
+                )
+        })
+    }
+
+    async fn land_unit_action(
+        turn: &PlayerTurn,
+        unit: &Unit,
+        legal: &[Direction],
+    ) -> Option<NextUnitAction> {
+        if Self::should_garrison(turn, unit) {
+            return Some(NextUnitAction::Skip);
+        }
+
+        let passable = |tile: &Tile| {
+            unit.type_.can_move_on_tile(tile) && tile.unit.is_none() && tile.city.is_none()
+                || tile
+                    .city
+                    .as_ref()
+                    .map_or(false, |city| city.alignment == unit.alignment)
+        };
+
+        // Somewhere new over land
+        let step = Self::first_step(turn, unit.loc, passable, |_, obs| {
+            is_expansion_target(unit.alignment, unit.type_, obs)
+        })
+        // Failing that, a ride over the water
+        .or_else(|| {
+            Self::first_step(turn, unit.loc, passable, |_, obs| match obs {
+                Obs::Observed { tile, .. } => tile.unit.as_ref().map_or(false, |transport| {
+                    transport.alignment == unit.alignment
+                        && transport.type_ == UnitType::Transport
+                        && transport.carried_units().count() < transport.type_.carrying_capacity()
+                }),
+                Obs::Unobserved => false,
+            })
+        });
+
+        step.filter(|direction| legal.contains(direction))
+            .map(|direction| NextUnitAction::Move { direction })
+    }
+
+    async fn transport_action(
+        turn: &PlayerTurn,
+        unit: &Unit,
+        legal: &[Direction],
+    ) -> Option<NextUnitAction> {
+        let load = unit.carried_units().count();
+        let passable = |tile: &Tile| {
+            !tile.terrain.is_land() && tile.unit.is_none()
+                || tile
+                    .city
+                    .as_ref()
+                    .map_or(false, |city| city.alignment == unit.alignment)
+        };
+
+        let dims = turn.dims();
+        let wrapping = turn.wrapping();
+        let observations = turn.observations();
+
+        // Next to a city the load could take
+        let beside_target = |loc: Location| {
+            Direction::values().into_iter().any(|direction| {
+                loc.shift_wrapped(direction, dims, wrapping)
+                    .and_then(|neighb| observations.get(neighb))
+                    .map_or(false, |obs| match obs {
+                        Obs::Observed { tile, .. } => tile
+                            .city
+                            .as_ref()
+                            .map_or(false, |city| city.alignment != unit.alignment),
+                        Obs::Unobserved => false,
+                    })
+            })
+        };
+
+        let step = if load >= unit.type_.carrying_capacity().min(TRANSPORT_LOAD) {
+            // Let the load off where it can do some good
+            if beside_target(unit.loc) {
+                return Some(NextUnitAction::Skip);
+            }
+
+            Self::first_step(turn, unit.loc, passable, |loc, obs| {
+                matches!(obs, Obs::Observed { tile, .. } if passable(tile)) && beside_target(loc)
+            })
+            // Or at least somewhere unknown
+            .or_else(|| Self::first_step(turn, unit.loc, passable, |_, obs| obs.is_unobserved()))
+        } else {
+            // Wait for a load in port
+            let in_port = turn.tile(unit.loc).map_or(false, |tile| {
+                tile.city
+                    .as_ref()
+                    .map_or(false, |city| city.alignment == unit.alignment)
+            });
+            if in_port {
+                return Some(NextUnitAction::Skip);
+            }
+
+            Self::first_step(turn, unit.loc, passable, |_, obs| match obs {
+                Obs::Observed { tile, .. } => tile
+                    .city
+                    .as_ref()
+                    .map_or(false, |city| city.alignment == unit.alignment),
+                Obs::Unobserved => false,
+            })
+        };
+
+        step.filter(|direction| legal.contains(direction))
+            .map(|direction| NextUnitAction::Move { direction })
+    }
+}
+
+/// Whether a unit of type `type_` belonging to `player` would do well to head for the tile observed as `obs`
+///
+/// That's any city not the player's own, if the unit can take cities, and anywhere not yet seen.
+fn is_expansion_target(player: Alignment, type_: UnitType, obs: &Obs) -> bool {
+    match obs {
+        Obs::Observed { tile, .. } => {
+            type_.can_occupy_cities()
+                && tile
+                    .city
+                    .as_ref()
+                    .map_or(false, |city| city.alignment != player)
+        }
+        Obs::Unobserved => true,
+    }
+}
+
+/// The distance between coordinates `a` and `b` in a dimension of size `width`, the short way round if it wraps
+fn wrapped_distance(width: u16, wrap: Wrap, a: u16, b: u16) -> u16 {
+    let distance = a.abs_diff(b);
+    match wrap {
+        Wrap::Wrapping => distance.min(width - distance),
+        Wrap::NonWrapping => distance,
+    }
+}
+
+#[async_trait]
+impl ActionwiseTurnTaker2 for HeuristicAI {
+    async fn next_city_action(
+        &mut self,
+        turn: &PlayerTurn,
+        _device: AiDevice,
+    ) -> Option<NextCityAction> {
+        let city_loc = turn
+            .player_production_set_requests()
+            .await
+            .first()
+            .copied()?;
+
+        let valid_productions: Vec<UnitType> = turn.valid_productions_conservative(city_loc).await;
+        let player = turn.player_city_by_loc(city_loc).await?.alignment;
+
+        let units = turn.player_units().await;
+        let count = |type_: UnitType| units.iter().filter(|unit| unit.type_ == type_).count();
+        let land_units = units
+            .iter()
+            .filter(|unit| unit.transport_mode() == TransportMode::Land)
+            .count();
+        let cities = turn.player_cities().await.len();
+
+        let coastal = Direction::values().into_iter().any(|direction| {
+            city_loc
+                .shift_wrapped(direction, turn.dims(), turn.wrapping())
+                .and_then(|neighb| turn.tile(neighb))
+                .map_or(false, |tile| !tile.terrain.is_land())
+        });
+
+        let wanted = if coastal
+            && !Self::can_expand_overland(turn, player, city_loc)
+            && count(UnitType::Transport) * 2 < land_units.max(1)
+        {
+            // Blocked by water
+            UnitType::Transport
+        } else if land_units <= cities || count(UnitType::Infantry) <= count(UnitType::Armor) {
+            UnitType::Infantry
+        } else {
+            UnitType::Armor
+        };
+
+        let unit_type = if valid_productions.contains(&wanted) {
+            wanted
+        } else if valid_productions.contains(&UnitType::Infantry) {
+            UnitType::Infantry
+        } else {
+            *valid_productions.first()?
+        };
+
+        Some(NextCityAction::SetProduction { unit_type })
+    }
+
+    async fn next_unit_action(
+        &mut self,
+        turn: &PlayerTurn,
+        _device: AiDevice,
+    ) -> Option<NextUnitAction> {
+        let unit_id = turn.player_unit_orders_requests().await.first().copied()?;
+        let unit = turn.player_unit_by_id(unit_id).await?;
+
+        // Never pass up a shot at an enemy in range
+        if !turn
+            .player_unit_bombard_targets(unit_id)
+            .await
+            .unwrap_or_default()
+            .is_empty()
+        {
+            return Some(NextUnitAction::Bombard);
+        }
+
+        let legal: Vec<Direction> = turn
+            .player_unit_legal_directions(unit_id)
+            .await
+            .unwrap_or_default();
+
+        if let Some(direction) = Self::weakest_neighbor(turn, &unit, &legal).await {
+            return Some(NextUnitAction::Move { direction });
+        }
+
+        let action = match (unit.type_, unit.transport_mode()) {
+            (UnitType::Transport, _) => Self::transport_action(turn, &unit, &legal).await,
+            (_, TransportMode::Land) => Self::land_unit_action(turn, &unit, &legal).await,
+            // Other ships explore; aircraft stay home rather than run out of fuel
+            (_, TransportMode::Sea) => Self::first_step(
+                turn,
+                unit.loc,
+                |tile| !tile.terrain.is_land() && tile.unit.is_none(),
+                |_, obs| obs.is_unobserved(),
+            )
+            .filter(|direction| legal.contains(direction))
+            .map(|direction| NextUnitAction::Move { direction }),
+            (_, TransportMode::Air) => None,
+        };
+
+        Some(action.unwrap_or(NextUnitAction::Skip))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::sync::Arc;
+
+    use tokio::sync::RwLock as RwLockTokio;
+
+    use common::{
+        game::{
+            ai::AiDevice,
+            alignment::Alignment,
+            map::{terrain::Terrain, MapData},
+            player::PlayerControl,
+            turn_async::TurnTaker,
+            Game,
+        },
+        util::{Dims, Location, Wrap, Wrap2d},
+    };
+
+    use super::{wrapped_distance, HeuristicAI};
+
+    #[test]
+    fn test_wrapped_distance() {
+        assert_eq!(wrapped_distance(10, Wrap::NonWrapping, 1, 9), 8);
+        assert_eq!(wrapped_distance(10, Wrap::Wrapping, 1, 9), 2);
+        assert_eq!(wrapped_distance(10, Wrap::Wrapping, 4, 4), 0);
+    }
+
+    /// Left to itself on an island with a neutral city, the AI takes it
+    #[tokio::test]
+    pub async fn test_heuristic_ai_expands() {
+        let device: AiDevice = Default::default();
+
+        let mut map = MapData::new(Dims::new(20, 10), |loc| {
+            if loc.y < 5 {
+                Terrain::Land
+            } else {
+                Terrain::Water
+            }
+        });
+        map.new_city(
+            Location::new(0, 0),
+            Alignment::Belligerent { player: 0 },
+            "Hebevund",
+        )
+        .unwrap();
+        map.new_city(Location::new(8, 2), Alignment::Neutral, "Uniuod")
+            .unwrap();
+
+        let (game, secrets) = Game::new_with_map(None, false, map, 1, true, None, Wrap2d::NEITHER);
+        let game = Arc::new(RwLockTokio::new(game));
+        let mut ctrl = PlayerControl::new(game, 0, secrets[0]).await;
+
+        let mut ai = HeuristicAI;
+
+        for _ in 0..200 {
+            let mut turn = ctrl.turn_ctrl(true).await;
+            ai.take_turn(&mut turn, None, device).await;
+            turn.force_end_turn().await.unwrap();
+        }
+
+        assert_eq!(ctrl.player_cities().await.len(), 2);
+    }
+}
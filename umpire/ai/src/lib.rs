@@ -2,8 +2,12 @@ use std::{fmt, path::Path};
 
 use async_trait::async_trait;
 
+#[cfg(feature = "candle")]
+use burn::backend::Candle;
+#[cfg(feature = "tch")]
+use burn::backend::LibTorch;
 use burn::{
-    backend::{wgpu::WgpuDevice, Wgpu},
+    backend::{NdArray, Wgpu},
     tensor::backend::Backend,
 };
 use futures::lock::Mutex as MutexAsync;
@@ -11,7 +15,7 @@ use rand::rngs::StdRng;
 
 use common::{
     game::{
-        ai::{AISpec, AiDevice},
+        ai::{AISpec, AiBackendKind, AiDevice, DEFAULT_PLAYOUTS},
         player::PlayerTurn,
         turn::TurnOutcome,
         turn_async::TurnTaker as TurnTakerAsync,
@@ -37,8 +41,12 @@ pub trait LoadableFromBytes<B: Backend>: Sized {
 
 // Sub-modules
 pub mod agz;
+pub mod augment;
 pub mod data;
+pub mod precision;
 
+mod heuristic;
+mod mcts;
 mod random;
 mod skip;
 
@@ -51,8 +59,14 @@ pub enum AI<B: Backend> {
 
     Skip(SkipAI),
 
+    /// Hand-written rules of thumb
+    Heuristic(HeuristicAI),
+
     /// AlphaGo Zero style action model
     AGZ(MutexAsync<AgzActionModel<B>>),
+
+    /// Monte Carlo Tree Search guided by an AGZ action model
+    Mcts(MutexAsync<MctsAI<B>>),
 }
 
 impl<B: Backend> AI<B> {
@@ -70,42 +84,83 @@ impl<B: Backend> fmt::Debug for AI<B> {
                 Self::Random(_) => "random",
                 Self::RandomPlus(_) => "random+",
                 Self::Skip(_) => "skip",
+                Self::Heuristic(_) => "heuristic",
                 Self::AGZ(_) => "agz",
+                Self::Mcts(_) => "mcts",
             }
         )
     }
 }
 
-impl From<AISpec> for AI<Wgpu> {
-    fn from(ai_type: AISpec) -> Self {
-        match ai_type {
+impl<B: Backend> TryFrom<AISpec> for AI<B>
+where
+    B::Device: From<AiDevice>,
+{
+    type Error = String;
+
+    /// The AI specified, which fails if it's one whose model can't be loaded
+    fn try_from(ai_type: AISpec) -> Result<Self, String> {
+        Ok(match ai_type {
             AISpec::Random { seed } => Self::Random(RandomAI::new(init_rng(seed))),
             AISpec::RandomPlus { seed } => Self::RandomPlus(RandomPlusAI::new(init_rng(seed))),
             AISpec::Skip => AI::Skip(SkipAI {}),
+            AISpec::Heuristic => Self::Heuristic(HeuristicAI),
             AISpec::FromPath { path, device } => {
-                let device: WgpuDevice = device.into();
-                Self::load(Path::new(path.as_str()), device).unwrap()
+                Self::load(Path::new(path.as_str()), device.into())?
             }
             AISpec::FromLevel { level, device } => {
-                let device: WgpuDevice = device.into();
-                let agz = match level {
-                    0 => {
-                        let bytes = include_bytes!("../../../ai/agz/15x15/0.agz.bin");
-                        AgzActionModel::<Wgpu>::load_from_bytes(bytes.as_slice(), device).unwrap()
-                    }
-                    1 => {
-                        let bytes = include_bytes!("../../../ai/agz/15x15/1.agz.bin");
-                        AgzActionModel::<Wgpu>::load_from_bytes(bytes.as_slice(), device).unwrap()
+                let agz = agz_from_level(level, device)?;
+                match level_playouts(level) {
+                    Some(playouts) => Self::Mcts(MutexAsync::new(MctsAI::new(agz, playouts))),
+                    None => Self::AGZ(MutexAsync::new(agz)),
+                }
+            }
+            AISpec::Mcts { model, playouts } => {
+                let agz: AgzActionModel<B> = match *model {
+                    AISpec::FromPath { path, device } => {
+                        AgzActionModel::load(Path::new(path.as_str()), device.into())?
                     }
-                    level => unreachable!("Unsupported AI level: {}", level),
+                    AISpec::FromLevel { level, device } => agz_from_level(level, device)?,
+                    model => unreachable!("Tree search can't be guided by {}", model),
                 };
 
-                Self::AGZ(MutexAsync::new(agz))
+                Self::Mcts(MutexAsync::new(MctsAI::new(agz, playouts)))
             }
-        }
+        })
     }
 }
 
+/// How many playouts preset AI level `level` searches before each action, if it searches at all
+///
+/// The levels above 1 get their strength from tree search guided by the level 1 model, not from models of their own.
+fn level_playouts(level: usize) -> Option<usize> {
+    match level {
+        2 => Some(DEFAULT_PLAYOUTS),
+        3 => Some(4 * DEFAULT_PLAYOUTS),
+        _ => None,
+    }
+}
+
+/// The AGZ action model of preset AI level `level`, or that guiding its search
+///
+/// Levels 2 and 3 have no models of their own; they're level 1 strengthened by tree search. The bundled models were
+/// trained on the original layout of the features, and read the current one through a translation; this fails only
+/// if a bundled model fits neither.
+fn agz_from_level<B: Backend>(level: usize, device: AiDevice) -> Result<AgzActionModel<B>, String>
+where
+    B::Device: From<AiDevice>,
+{
+    let device: B::Device = device.into();
+    let bytes: &[u8] = match level {
+        0 => include_bytes!("../../../ai/agz/15x15/0.agz.bin"),
+        1..=3 => include_bytes!("../../../ai/agz/15x15/1.agz.bin"),
+        level => return Err(format!("Unsupported AI level: {}", level)),
+    };
+
+    AgzActionModel::<B>::load_from_bytes(bytes, device)
+        .map_err(|err| format!("Could not load the model of AI level {}: {}", level, err))
+}
+
 impl<B: Backend> Loadable<B> for AI<B> {
     /// Loads the actual AI instance from a file.
     ///
@@ -148,13 +203,18 @@ impl<B: Backend> Storable for AI<B> {
             Self::Random(_) => Err(String::from("Cannot store random AI; load explicitly using the appropriate specification (r/rand/random)")),
             Self::RandomPlus(_) => Err(String::from("Cannot store random AI; load explicitly using the appropriate specification (R)")),
             Self::Skip(_) => Err(String::from("Cannot store skip-only AI; load explicitly using the appropriate specification (s)")),
+            Self::Heuristic(_) => Err(String::from("Cannot store heuristic AI; load explicitly using the appropriate specification (H)")),
             Self::AGZ(agz) => agz.into_inner().store(path),
+            Self::Mcts(_) => Err(String::from("Cannot store tree search AI; store the model guiding it instead")),
         }
     }
 }
 
 #[async_trait]
-impl TurnTakerAsync for AI<Wgpu> {
+impl<B: Backend> TurnTakerAsync for AI<B>
+where
+    B::Device: From<AiDevice>,
+{
     async fn take_turn(
         &mut self,
         turn: &mut PlayerTurn,
@@ -165,12 +225,99 @@ impl TurnTakerAsync for AI<Wgpu> {
             Self::Random(ai) => ai.take_turn(turn, datagen_prob, device).await,
             Self::RandomPlus(ai) => ai.take_turn(turn, datagen_prob, device).await,
             Self::Skip(ai) => ai.take_turn(turn, datagen_prob, device).await,
+            Self::Heuristic(ai) => ai.take_turn(turn, datagen_prob, device).await,
             Self::AGZ(agz) => agz.lock().await.take_turn(turn, datagen_prob, device).await,
+            Self::Mcts(ai) => ai.lock().await.take_turn(turn, datagen_prob, device).await,
+        }
+    }
+}
+
+/// An AI on whichever backend was chosen at runtime
+pub enum AnyBackendAI {
+    Wgpu(AI<Wgpu>),
+
+    NdArray(AI<NdArray>),
+
+    #[cfg(feature = "tch")]
+    Tch(AI<LibTorch>),
+
+    #[cfg(feature = "candle")]
+    Candle(AI<Candle>),
+}
+
+impl AnyBackendAI {
+    /// The AI specified by `spec`, with any neural network it has running on `backend`
+    pub fn new(spec: AISpec, backend: AiBackendKind) -> Result<Self, String> {
+        Ok(match backend {
+            AiBackendKind::Wgpu => Self::Wgpu(spec.try_into()?),
+            AiBackendKind::NdArray => Self::NdArray(spec.try_into()?),
+            #[cfg(feature = "tch")]
+            AiBackendKind::Tch => Self::Tch(spec.try_into()?),
+            #[cfg(feature = "candle")]
+            AiBackendKind::Candle => Self::Candle(spec.try_into()?),
+            #[allow(unreachable_patterns)]
+            backend => unreachable!("Support for the {} backend wasn't built in", backend),
+        })
+    }
+}
+
+impl fmt::Debug for AnyBackendAI {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Wgpu(ai) => ai.fmt(f),
+            Self::NdArray(ai) => ai.fmt(f),
+            #[cfg(feature = "tch")]
+            Self::Tch(ai) => ai.fmt(f),
+            #[cfg(feature = "candle")]
+            Self::Candle(ai) => ai.fmt(f),
+        }
+    }
+}
+
+#[async_trait]
+impl TurnTakerAsync for AnyBackendAI {
+    async fn take_turn(
+        &mut self,
+        turn: &mut PlayerTurn,
+        datagen_prob: Option<f64>,
+        device: AiDevice,
+    ) -> TurnOutcome {
+        match self {
+            Self::Wgpu(ai) => ai.take_turn(turn, datagen_prob, device).await,
+            Self::NdArray(ai) => ai.take_turn(turn, datagen_prob, device).await,
+            #[cfg(feature = "tch")]
+            Self::Tch(ai) => ai.take_turn(turn, datagen_prob, device).await,
+            #[cfg(feature = "candle")]
+            Self::Candle(ai) => ai.take_turn(turn, datagen_prob, device).await,
         }
     }
 }
 
 // Exports
+pub use heuristic::HeuristicAI;
+pub use mcts::MctsAI;
 pub use random::RandomAI;
 pub use random::RandomPlusAI;
 pub use skip::SkipAI;
+
+#[cfg(test)]
+mod test {
+    use burn::backend::NdArray;
+
+    use common::game::ai::{AISpec, AiDevice, AI_LEVELS};
+
+    use crate::AI;
+
+    /// Every preset level loads, those above 1 searching ahead
+    #[test]
+    fn test_levels() {
+        for level in 0..AI_LEVELS {
+            let spec = AISpec::FromLevel {
+                level,
+                device: AiDevice::Cpu,
+            };
+            let ai = AI::<NdArray>::try_from(spec).unwrap();
+            assert_eq!(matches!(ai, AI::Mcts(_)), level >= 2, "level {}", level);
+        }
+    }
+}
@@ -12,15 +12,20 @@ use std::{
     cell::RefCell,
     cmp::Ordering,
     collections::{BTreeMap, BTreeSet},
-    fs::File,
+    fs::{self, File},
     io::stdout,
     path::{Path, PathBuf},
+    process::{Command, Stdio},
     rc::Rc,
     sync::Arc,
 };
 
+#[cfg(feature = "candle")]
+use burn::backend::Candle;
+#[cfg(feature = "tch")]
+use burn::backend::LibTorch;
 use burn::{
-    backend::{wgpu::WgpuDevice, Autodiff, Wgpu},
+    backend::{ndarray::NdArrayDevice, Autodiff, NdArray, Wgpu},
     data::{dataloader::DataLoaderBuilder, dataset::Dataset},
     nn::DropoutConfig,
     optim::SgdConfig,
@@ -39,22 +44,31 @@ use clap::{builder::BoolishValueParser, value_parser, Arg, ArgAction};
 use crossterm::{
     cursor::{MoveTo, Show},
     execute,
+    style::ResetColor,
     terminal::{size, Clear, ClearType, EnterAlternateScreen, LeaveAlternateScreen},
 };
 
 use flate2::{read::GzDecoder, write::GzEncoder, Compression};
 
 use umpire_ai::{
-    agz::AgzActionModelConfig,
-    data::{AgzBatcher, AgzData, AgzDatum},
-    Storable,
+    agz::{AgzActionModel, AgzActionModelConfig, MIN_WINDOW},
+    augment::augment,
+    data::{crop_features, read_data_header, write_data_header, AgzBatcher, AgzData, AgzDatum},
+    precision::Precision,
+    Loadable, Storable,
 };
 
 use common::{
     game::{
         action::AiPlayerAction,
-        ai::{AiBackend, AiDevice, TrainingOutcome, POSSIBLE_ACTIONS, P_DROPOUT},
-        map::gen::MapType,
+        ai::{AiBackendKind, AiDevice, TrainingOutcome, DEEP_WIDTH, POSSIBLE_ACTIONS, P_DROPOUT},
+        map::{
+            fairness,
+            gen::{MapType, Symmetry},
+            MapData,
+        },
+        obs::{FogOfWar, ObsTracker},
+        unit::stats::{self, UnitStatsRegistry},
         TurnNum,
     },
     util::{densify, init_rng},
@@ -70,7 +84,7 @@ use common::{
     conf,
     game::{
         ai::{AISpec, TrainingInstance},
-        player::{PlayerControl, PlayerNum},
+        player::{PlayerControl, PlayerNum, PlayerTurn},
         turn_async::TurnTaker,
         Game, IGame,
     },
@@ -78,8 +92,13 @@ use common::{
     util::{Dims, Rect, Vec2d, Wrap2d},
 };
 
-use umpire_ai::AI;
-use umpire_tui::{color::palette16, map::Map, Component, Draw};
+use umpire_ai::AnyBackendAI;
+use umpire_tui::{
+    color::{palette16, Theme},
+    map::Map,
+    screen::Screen,
+    Component, Draw,
+};
 
 const SEED_INTERVAL: u64 = 924898;
 
@@ -92,20 +111,40 @@ fn parse_ai_specs(specs: &Vec<String>) -> Result<Vec<AISpec>, String> {
     Ok(ai_specs)
 }
 
-fn load_ais(ai_types: &Vec<AISpec>) -> Result<Vec<Rc<RefCell<AI<Wgpu>>>>, String> {
-    let mut unique_ais: BTreeMap<AISpec, Rc<RefCell<AI<Wgpu>>>> = BTreeMap::new();
+/// A width of the window of tiles around the city or unit in focus for features to cover
+fn parse_feature_window(s: &str) -> Result<usize, String> {
+    let window: usize = s
+        .parse()
+        .map_err(|_| format!("Feature window {} isn't a number", s))?;
+
+    if window < MIN_WINDOW || window % 2 == 0 {
+        return Err(format!(
+            "Feature window must be odd and at least {}, not {}",
+            MIN_WINDOW, window
+        ));
+    }
+
+    Ok(window)
+}
+
+fn load_ais(
+    ai_types: &Vec<AISpec>,
+    backend: AiBackendKind,
+) -> Result<Vec<Rc<RefCell<AnyBackendAI>>>, String> {
+    let mut unique_ais: BTreeMap<AISpec, Rc<RefCell<AnyBackendAI>>> = BTreeMap::new();
 
     for ai_type in ai_types {
+        if unique_ais.contains_key(ai_type) {
+            continue;
+        }
         eprintln!("Loading AI type {}", ai_type);
-        unique_ais.entry(ai_type.clone()).or_insert_with(|| {
-            let ai: AI<Wgpu> = ai_type.clone().into();
-            Rc::new(RefCell::new(ai))
-        });
+        let ai = AnyBackendAI::new(ai_type.clone(), backend)?;
+        unique_ais.insert(ai_type.clone(), Rc::new(RefCell::new(ai)));
     }
 
-    let mut ais: Vec<Rc<RefCell<AI<Wgpu>>>> = Vec::with_capacity(ai_types.len());
+    let mut ais: Vec<Rc<RefCell<AnyBackendAI>>> = Vec::with_capacity(ai_types.len());
     for ai_type in ai_types {
-        let ai: Rc<RefCell<AI<Wgpu>>> = Rc::clone(&unique_ais[ai_type]);
+        let ai: Rc<RefCell<AnyBackendAI>> = Rc::clone(&unique_ais[ai_type]);
         ais.push(ai);
     }
     Ok(ais)
@@ -113,10 +152,16 @@ fn load_ais(ai_types: &Vec<AISpec>) -> Result<Vec<Rc<RefCell<AI<Wgpu>>>>, String
 
 static AI_MODEL_SPECS_HELP: &str = "AI model specifications, comma-separated. The models to be evaluated. 'r' or 'random' for the purely random AI, or a serialized AI model file path, or directory path for TensorFlow SavedModel format";
 
+static SUBCMD_AGZCONVERT: &str = "agzconvert";
+
+static SUBCMD_AGZLOOP: &str = "agzloop";
+
 static SUBCMD_AGZTRAIN: &str = "agztrain";
 
 static SUBCMD_EVAL: &str = "eval";
 
+static SUBCMD_MAPGEN: &str = "mapgen";
+
 #[tokio::main]
 async fn main() -> Result<(), String> {
     let matches = cli::app("Umpire AI Trainer", "v")
@@ -139,7 +184,7 @@ async fn main() -> Result<(), String> {
         .action(ArgAction::SetTrue)
     )
     .subcommand(
-        cli::app(SUBCMD_EVAL, "MSwHWfg")
+        cli::app(SUBCMD_EVAL, "MSwHWfgLUYmbk")
         .about(format!("Have a set of AIs duke it out to see who plays the game of {} best", conf::APP_NAME))
         .arg(
             Arg::new("ai_models")
@@ -176,6 +221,12 @@ async fn main() -> Result<(), String> {
             .value_parser(BoolishValueParser::new())
             .default_value("false")
         )
+        .arg(
+            Arg::new("feature_window")
+            .long("feature-window")
+            .help("Width of the window of tiles around the city or unit in focus that the generated data's features cover; odd, and 15 if not given")
+            .value_parser(parse_feature_window)
+        )
         .arg(
             Arg::new("detsec")
             .long("detsec")
@@ -199,7 +250,7 @@ async fn main() -> Result<(), String> {
         )
     )
     .subcommand(
-        cli::app(SUBCMD_AGZTRAIN, "DSg")
+        cli::app(SUBCMD_AGZTRAIN, "DSgk")
         .about(format!("Train an AlphaGo Zero-inspired neural network AI for the game of {}", conf::APP_NAME))
         .arg_required_else_help(true)
         // .arg(
@@ -258,6 +309,18 @@ async fn main() -> Result<(), String> {
                 .value_parser(value_parser!(usize))
                 .default_value("8")
         )
+        .arg(
+            Arg::new("feature_window")
+                .long("feature-window")
+                .help("Width of the window of tiles around the city or unit in focus for the model to see; odd, and 15 if not given. Data from a wider window is cropped to fit")
+                .value_parser(parse_feature_window)
+        )
+        .arg(
+            Arg::new("augment")
+                .long("augment")
+                .help("Multiply the training data eightfold by turning and mirroring the board, moves turned to match")
+                .action(ArgAction::SetTrue)
+        )
         .arg(
             Arg::new("input")
                 .help("Input files containing TrainingInstances")
@@ -265,6 +328,130 @@ async fn main() -> Result<(), String> {
                 .required(true)
         )
     )// subcommand agztrain
+    .subcommand(
+        cli::app(SUBCMD_AGZLOOP, "Dgk")
+        .about("Improve an AlphaGo Zero-inspired AI by alternating self-play and training, keeping only models that beat their predecessor")
+        .arg(
+            Arg::new("dir")
+                .help("Directory to keep the data shards and models in; a loop left off there is picked back up")
+                .required(true)
+        )
+        .arg(
+            Arg::new("iterations")
+                .short('n')
+                .long("iterations")
+                .help("Number of rounds of self-play, training, and evaluation")
+                .value_parser(value_parser!(usize))
+                .default_value("10")
+        )
+        .arg(
+            Arg::new("initial")
+                .short('i')
+                .long("initial")
+                .help("The model to start from: an AI level, or the path of a model file")
+                .default_value("0")
+        )
+        .arg(
+            Arg::new("selfplay_games")
+                .long("selfplay-games")
+                .help("Number of games of self-play to generate data from each round")
+                .value_parser(value_parser!(usize))
+                .default_value("1000")
+        )
+        .arg(
+            Arg::new("playouts")
+                .long("playouts")
+                .help("Have the incumbent search this many playouts per action in self-play, rather than play the model's picks")
+                .value_parser(value_parser!(usize).range(1..))
+        )
+        .arg(
+            Arg::new("epochs")
+                .long("epochs")
+                .help("Number of epochs to train each new model for")
+                .value_parser(value_parser!(usize))
+                .default_value("10")
+        )
+        .arg(
+            Arg::new("window")
+                .long("window")
+                .help("Number of the latest rounds' data shards to train on; older shards are deleted")
+                .value_parser(value_parser!(usize).range(1..))
+                .default_value("5")
+        )
+        .arg(
+            Arg::new("feature_window")
+                .long("feature-window")
+                .help("Width of the window of tiles around the city or unit in focus for new models to see; odd, and 15 if not given")
+                .value_parser(parse_feature_window)
+        )
+        .arg(
+            Arg::new("augment")
+                .long("augment")
+                .help("Train on the data turned and mirrored as well, moves turned to match")
+                .action(ArgAction::SetTrue)
+        )
+        .arg(
+            Arg::new("eval_games")
+                .long("eval-games")
+                .help("Number of games a new model plays against the incumbent from each seat")
+                .value_parser(value_parser!(usize).range(1..))
+                .default_value("50")
+        )
+        .arg(
+            Arg::new("threshold")
+                .short('t')
+                .long("threshold")
+                .help("Share of the evaluation games a new model must win to replace the incumbent, draws counting half")
+                .value_parser(value_parser!(f64))
+                .default_value("0.55")
+        )
+        .arg(
+            Arg::new("curriculum")
+                .long("curriculum")
+                .help("Start on small maps with two players and no fog, moving on to bigger maps, more players, and fog as the incumbent learns to beat the random AI; the schedule is kept in the directory's config.json, where it can be edited")
+                .action(ArgAction::SetTrue)
+        )
+        .arg(
+            Arg::new("game_args")
+                .help("Further arguments to eval for the self-play and evaluation games, such as map dimensions, given after --; with a curriculum, leave map dimensions and fog to it")
+                .num_args(0..)
+                .last(true)
+        )
+    )// subcommand agzloop
+    .subcommand(
+        cli::app(SUBCMD_AGZCONVERT, "")
+        .about("Store an AlphaGo Zero-inspired AI model at a different precision, to make it smaller")
+        .arg(
+            Arg::new("precision")
+                .short('p')
+                .long("precision")
+                .help("Precision to store the model's weights at: full, half, or int8")
+                .value_parser(|s: &str| Precision::try_from(s))
+                .default_value("half")
+        )
+        .arg(
+            Arg::new("input")
+                .help("The model to convert, stored at any precision")
+                .required(true)
+        )
+        .arg(
+            Arg::new("output")
+                .help("Path to store the converted model at, exactly as given")
+                .required(true)
+        )
+    )// subcommand agzconvert
+    .subcommand(
+        cli::app(SUBCMD_MAPGEN, "MSwHWYb")
+        .about("Generate a map and show it, along with how fair the players' starts are, without playing on it")
+        .arg(
+            Arg::new("num_players")
+            .short('p')
+            .long("players")
+            .help("The number of players to place starting cities for")
+            .value_parser(value_parser!(PlayerNum))
+            .default_value("2")
+        )
+    )
 
     .get_matches();
 
@@ -281,11 +468,17 @@ async fn main() -> Result<(), String> {
     match subcommand {
         "eval" => eprintln!("Evaluating {} AIs", conf::APP_NAME),
         "agztrain" => eprintln!("Training {} AI - a la AlphaGo Zero", conf::APP_NAME),
+        "agzloop" => eprintln!("Improving {} AI by self-play - a la AlphaGo Zero", conf::APP_NAME),
+        "agzconvert" => eprintln!("Converting {} AI model", conf::APP_NAME),
+        "mapgen" => eprintln!("Generating {} map", conf::APP_NAME),
         c => unreachable!("Unrecognized subcommand {} should have been caught by the agument parser; there's a bug somehere", c)
     }
 
     let mut stdout = stdout();
 
+    // Maps are drawn through the screen, while text reports are written around them straight to stdout
+    let mut screen = Screen::new(stdout(), Dims::new(term_width, term_height));
+
     if fix_output_loc {
         execute!(stdout, EnterAlternateScreen).unwrap();
         execute!(stdout, Clear(ClearType::All)).unwrap();
@@ -321,7 +514,15 @@ async fn main() -> Result<(), String> {
             .unwrap()
             .cloned()
             .collect();
-        let fog_of_war = sub_matches.get_one::<bool>("fog").copied().unwrap();
+        let fog_of_war = sub_matches.get_one::<FogOfWar>("fog").copied().unwrap();
+        let turn_limit = sub_matches.get_one::<TurnNum>("turn_limit").copied();
+        let symmetry = sub_matches.get_one::<Symmetry>("symmetry").copied();
+        let map_file = sub_matches.get_one::<MapData>("map_file");
+        let fair_starts = sub_matches.get_flag("fair_starts");
+
+        if let Some(unit_stats) = sub_matches.get_one::<UnitStatsRegistry>("unit_stats") {
+            stats::install(unit_stats.clone()).unwrap();
+        }
 
         let ai_specs_s: Vec<String> = sub_matches
             .get_many::<String>("ai_models")
@@ -331,6 +532,7 @@ async fn main() -> Result<(), String> {
 
         let gpu = sub_matches.get_one::<usize>("gpu").copied();
         let device = gpu.map_or_else(Default::default, AiDevice::DiscreteGpu);
+        let backend = *sub_matches.get_one::<AiBackendKind>("backend").unwrap();
 
         // Load up the AI specifications, respecting --gpu if present
         let ai_specs: Vec<AISpec> = {
@@ -348,6 +550,17 @@ async fn main() -> Result<(), String> {
                     } => {
                         *device_ = device;
                     }
+                    AISpec::Mcts { model, .. } => {
+                        if let AISpec::FromPath {
+                            device: device_, ..
+                        }
+                        | AISpec::FromLevel {
+                            device: device_, ..
+                        } = model.as_mut()
+                        {
+                            *device_ = device;
+                        }
+                    }
                     _ => {
                         // do nothing
                     }
@@ -356,9 +569,19 @@ async fn main() -> Result<(), String> {
             ai_specs
         };
 
-        let mut ais: Vec<Rc<RefCell<AI<AiBackend>>>> = load_ais(&ai_specs)?;
+        let mut ais: Vec<Rc<RefCell<AnyBackendAI>>> = load_ais(&ai_specs, backend)?;
         let num_ais = ais.len();
 
+        if let Some(map) = map_file {
+            if map.players() != num_ais {
+                return Err(format!(
+                    "The map file has {} players but {} AIs were specified",
+                    map.players(),
+                    num_ais
+                ));
+            }
+        }
+
         // Players we will record data from; defaults to everyone.
         let captured_players: BTreeSet<PlayerNum> = {
             let mut captured: BTreeSet<PlayerNum> = sub_matches
@@ -403,16 +626,23 @@ async fn main() -> Result<(), String> {
 
         let datagen_qty_eq: bool = sub_matches.get_one("datagenqty_eq").copied().unwrap();
 
+        let feature_window: usize = sub_matches
+            .get_one("feature_window")
+            .copied()
+            .unwrap_or(DEEP_WIDTH);
+
         if let Some(datagen_qty) = datagen_qty {
             eprintln!("Datagen qty: {}", datagen_qty);
         }
 
         let mut data_outfile = datagenpath.map(|datagenpath| {
             let w = File::create(datagenpath).unwrap();
-            GzEncoder::new(w, Compression::default())
+            let mut w = GzEncoder::new(w, Compression::default());
+            write_data_header(&mut w).unwrap();
+            w
         });
 
-        let palette = palette16(num_ais).unwrap();
+        let palette = palette16(num_ais, &Theme::default()).unwrap();
 
         let print_results = |victory_counts: &BTreeMap<Option<PlayerNum>, usize>,
                              game_lengths: &BTreeMap<TurnNum, usize>| {
@@ -474,9 +704,14 @@ async fn main() -> Result<(), String> {
             }
             let city_namer = IntNamer::new("city");
 
-            let map_width = map_widths.choose(&mut rng).copied().unwrap();
-            let map_height = map_heights.choose(&mut rng).copied().unwrap();
-            let map_dims = Dims::new(map_width, map_height);
+            let map_dims = match map_file {
+                Some(map) => map.dims(),
+                None => Dims::new(
+                    map_widths.choose(&mut rng).copied().unwrap(),
+                    map_heights.choose(&mut rng).copied().unwrap(),
+                ),
+            };
+            let (map_width, map_height) = (map_dims.width, map_dims.height);
             let map_type = map_types.choose(&mut rng).copied().unwrap();
             let wrapping = wrappings.choose(&mut rng).cloned().unwrap();
 
@@ -502,24 +737,67 @@ async fn main() -> Result<(), String> {
                 Vec::new()
             };
 
-            let game_rng = init_rng(seed);
-            let (game, secrets) = Game::new(
-                Some(game_rng),
-                deterministic_secrets,
-                map_dims,
-                map_type,
-                city_namer,
-                num_ais,
-                fog_of_war,
-                None,
-                wrapping,
-            );
+            if let (Some(symmetry), None) = (symmetry, map_file) {
+                symmetry.check(map_dims, num_ais)?;
+            }
+
+            let mut game_rng = init_rng(seed);
+            let (mut game, secrets) = if let Some(map) = map_file {
+                Game::new_with_map(
+                    Some(game_rng),
+                    deterministic_secrets,
+                    map.clone(),
+                    num_ais,
+                    fog_of_war,
+                    None,
+                    wrapping,
+                )
+            } else if fair_starts {
+                let mut city_namer = city_namer;
+                let (map, report) = map_type.generate_fair(
+                    &mut game_rng,
+                    map_dims,
+                    num_ais,
+                    &mut city_namer,
+                    symmetry,
+                    wrapping,
+                );
+                for imbalance in report.imbalances() {
+                    eprintln!("Warning: unbalanced starts. {}", imbalance);
+                }
+
+                Game::new_with_map(
+                    Some(game_rng),
+                    deterministic_secrets,
+                    map,
+                    num_ais,
+                    fog_of_war,
+                    None,
+                    wrapping,
+                )
+            } else {
+                Game::new(
+                    Some(game_rng),
+                    deterministic_secrets,
+                    map_dims,
+                    map_type,
+                    symmetry,
+                    city_namer,
+                    num_ais,
+                    fog_of_war,
+                    None,
+                    wrapping,
+                )
+            };
+            game.set_turn_limit(turn_limit);
 
             let game = Arc::new(RwLockTokio::new(game)) as Arc<RwLockTokio<dyn IGame>>;
 
             let mut ctrls: Vec<PlayerControl> = Vec::with_capacity(num_ais);
             for (player, secret) in secrets.iter().cloned().enumerate() {
-                ctrls.push(PlayerControl::new(Arc::clone(&game), player, secret).await);
+                let mut ctrl = PlayerControl::new(Arc::clone(&game), player, secret).await;
+                ctrl.set_feature_window(feature_window);
+                ctrls.push(ctrl);
             }
 
             if fix_output_loc {
@@ -538,7 +816,7 @@ async fn main() -> Result<(), String> {
             'steps: for s in 0..steps {
                 last_turn = s as TurnNum;
                 for (player, ctrl) in ctrls.iter_mut().enumerate() {
-                    if ctrl.victor().await.is_some() {
+                    if ctrl.game_over().await.is_some() {
                         break 'steps;
                     }
 
@@ -568,7 +846,7 @@ async fn main() -> Result<(), String> {
                             if draw {
                                 maps.get_mut(0)
                                     .unwrap()
-                                    .draw(&turn, &mut stdout, &palette)
+                                    .draw(&turn, &mut screen, &palette)
                                     .await
                                     .unwrap();
                             }
@@ -576,7 +854,7 @@ async fn main() -> Result<(), String> {
                             debug_assert!(maps.len() > 1);
                             maps.get_mut(player)
                                 .unwrap()
-                                .draw(&turn, &mut stdout, &palette)
+                                .draw(&turn, &mut screen, &palette)
                                 .await
                                 .unwrap();
                         }
@@ -592,6 +870,13 @@ async fn main() -> Result<(), String> {
 
             *game_lengths.entry(last_turn).or_default() += 1;
 
+            let victor = game
+                .read()
+                .await
+                .game_over()
+                .await
+                .map(|game_over| game_over.victor);
+
             let mut data_by_outcome: BTreeMap<TrainingOutcome, Vec<TrainingInstance>> =
                 BTreeMap::new();
             for t in TrainingOutcome::values() {
@@ -600,7 +885,7 @@ async fn main() -> Result<(), String> {
             if let Some(player_partial_data) = player_partial_data {
                 // Mark the training instances (if we've been tracking them) with the game's outcome
 
-                if let Some(victor) = game.read().await.victor().await {
+                if let Some(victor) = victor {
                     for (player, partial_data) in player_partial_data.into_iter() {
                         for mut instance in partial_data {
                             if player == victor {
@@ -689,9 +974,7 @@ async fn main() -> Result<(), String> {
                 );
             }
 
-            *victory_counts
-                .entry(game.read().await.victor().await)
-                .or_default() += 1;
+            *victory_counts.entry(victor).or_default() += 1;
 
             if verbosity > 1 {
                 println!();
@@ -704,7 +987,7 @@ async fn main() -> Result<(), String> {
 
             if fix_output_loc {
                 for map in maps.iter_mut() {
-                    map.clear(&mut stdout);
+                    map.clear(&mut screen);
                 }
             }
         } // end for each episode
@@ -724,12 +1007,14 @@ async fn main() -> Result<(), String> {
             .copied()
             .unwrap();
         let gpu = sub_matches.get_one::<usize>("gpu").copied();
+        let backend = *sub_matches.get_one::<AiBackendKind>("backend").unwrap();
 
         println!("Batch size: {}", batch_size);
         println!("Learning rate: {}", learning_rate);
         if let Some(gpu) = gpu {
             println!("GPU: {}", gpu);
         }
+        println!("Backend: {}", backend);
 
         let dataload_threads: usize = sub_matches.get_one("dataload_threads").copied().unwrap();
         println!("Dataload threads: {}", dataload_threads);
@@ -739,10 +1024,17 @@ async fn main() -> Result<(), String> {
         let output_path: String = sub_matches.get_one("out").cloned().unwrap();
         let output_path = Path::new(&output_path).to_owned();
 
-        let device = gpu.map_or_else(Default::default, WgpuDevice::DiscreteGpu);
+        let device = gpu.map_or_else(Default::default, AiDevice::DiscreteGpu);
+
+        let feature_window: usize = sub_matches
+            .get_one("feature_window")
+            .copied()
+            .unwrap_or(DEEP_WIDTH);
+        println!("Feature window: {}", feature_window);
 
         let dropout_config = DropoutConfig::new(P_DROPOUT);
-        let model_config = AgzActionModelConfig::new(POSSIBLE_ACTIONS, dropout_config);
+        let model_config =
+            AgzActionModelConfig::new(POSSIBLE_ACTIONS, dropout_config).with_window(feature_window);
 
         let sample_prob: f64 = sub_matches.get_one("sampleprob").copied().unwrap();
         let valid_prob: f64 = sub_matches.get_one("validprob").copied().unwrap();
@@ -767,8 +1059,9 @@ async fn main() -> Result<(), String> {
                 println!("Loading {}", input_path);
             }
 
-            let r = File::open(input_path).unwrap();
+            let r = File::open(&input_path).unwrap();
             let mut r = GzDecoder::new(r);
+            read_data_header(&mut r).map_err(|err| format!("{}: {}", input_path, err))?;
 
             let mut count = 0usize;
 
@@ -782,13 +1075,20 @@ async fn main() -> Result<(), String> {
                         let outcome = instance.outcome.unwrap();
                         count += 1;
 
+                        let features = crop_features(
+                            &densify(instance.num_features, &instance.features),
+                            feature_window,
+                        )
+                        .map_err(|err| format!("{}: {}", input_path, err))?;
+
                         action_class_data
                             .entry(instance.action)
                             .or_default()
                             .entry(outcome)
                             .or_default()
                             .push(AgzDatum {
-                                features: densify(instance.num_features, &instance.features),
+                                features,
+                                legal_actions: instance.legal_actions,
                                 turns_until_outcome: instance.last_turn.unwrap() - instance.turn,
                                 action: instance.action,
                                 outcome,
@@ -878,6 +1178,12 @@ async fn main() -> Result<(), String> {
             }
         }
 
+        // Only the training data is augmented, so none of it's a turned copy of validation data
+        if sub_matches.get_flag("augment") {
+            train_data = augment(train_data)?;
+            println!("Augmented by symmetry");
+        }
+
         let train_data: AgzData = AgzData::new(train_data);
         let valid_data: AgzData = AgzData::new(valid_data);
 
@@ -893,14 +1199,340 @@ async fn main() -> Result<(), String> {
         train_config.learning_rate = learning_rate;
         train_config.num_epochs = episodes;
 
-        train::<Autodiff<Wgpu>, PathBuf>(
-            &output_path,
-            train_config,
-            device,
-            train_data,
-            valid_data,
-            resume_epoch,
+        match backend {
+            AiBackendKind::Wgpu => train::<Autodiff<Wgpu>, PathBuf>(
+                &output_path,
+                train_config,
+                device.into(),
+                train_data,
+                valid_data,
+                resume_epoch,
+            ),
+            AiBackendKind::NdArray => train::<Autodiff<NdArray>, PathBuf>(
+                &output_path,
+                train_config,
+                device.into(),
+                train_data,
+                valid_data,
+                resume_epoch,
+            ),
+            #[cfg(feature = "tch")]
+            AiBackendKind::Tch => train::<Autodiff<LibTorch>, PathBuf>(
+                &output_path,
+                train_config,
+                device.into(),
+                train_data,
+                valid_data,
+                resume_epoch,
+            ),
+            #[cfg(feature = "candle")]
+            AiBackendKind::Candle => train::<Autodiff<Candle>, PathBuf>(
+                &output_path,
+                train_config,
+                device.into(),
+                train_data,
+                valid_data,
+                resume_epoch,
+            ),
+            #[allow(unreachable_patterns)]
+            backend => unreachable!("Support for the {} backend wasn't built in", backend),
+        }
+    } else if subcommand == SUBCMD_AGZLOOP {
+        let dir = PathBuf::from(sub_matches.get_one::<String>("dir").unwrap());
+        let iterations: usize = sub_matches.get_one("iterations").copied().unwrap();
+        let selfplay_games: usize = sub_matches.get_one("selfplay_games").copied().unwrap();
+        let playouts: Option<usize> = sub_matches.get_one("playouts").copied();
+        let epochs: usize = sub_matches.get_one("epochs").copied().unwrap();
+        let window: usize = sub_matches.get_one("window").copied().unwrap();
+        let feature_window: Option<usize> = sub_matches.get_one("feature_window").copied();
+        let augment_data = sub_matches.get_flag("augment");
+        let eval_games: usize = sub_matches.get_one("eval_games").copied().unwrap();
+        let threshold: f64 = sub_matches.get_one("threshold").copied().unwrap();
+        let learning_rate: f64 = sub_matches.get_one("dnn_learning_rate").copied().unwrap();
+        let gpu: Option<usize> = sub_matches.get_one("gpu").copied();
+        let backend = *sub_matches.get_one::<AiBackendKind>("backend").unwrap();
+
+        // Arguments for both the games and the training
+        let mut device_args: Vec<String> = vec!["--backend".to_string(), backend.to_string()];
+        if let Some(gpu) = gpu {
+            device_args.extend(["-g".to_string(), gpu.to_string()]);
+        }
+
+        // Arguments for every game played
+        let mut game_args: Vec<String> = sub_matches
+            .get_many::<String>("game_args")
+            .unwrap_or_default()
+            .cloned()
+            .collect();
+        game_args.extend(device_args.iter().cloned());
+
+        // Arguments for both the data generation and the training
+        let feature_window_args: Vec<String> = feature_window
+            .map(|window| vec!["--feature-window".to_string(), window.to_string()])
+            .unwrap_or_default();
+
+        let data_dir = dir.join("data");
+        let models_dir = dir.join("models");
+        for subdir in [&data_dir, &models_dir] {
+            fs::create_dir_all(subdir)
+                .map_err(|err| format!("Could not create {}: {}", subdir.display(), err))?;
+        }
+
+        // The model to beat, as an AI specification; kept on disk so the loop can be picked back up
+        let incumbent_path = dir.join("incumbent");
+        let mut incumbent: String = match fs::read_to_string(&incumbent_path) {
+            Ok(incumbent) => incumbent.trim().to_string(),
+            Err(_) => sub_matches.get_one::<String>("initial").cloned().unwrap(),
+        };
+        match AISpec::try_from(incumbent.clone())? {
+            AISpec::FromLevel { .. } | AISpec::FromPath { .. } => {}
+            spec => return Err(format!("Can only improve on a model, not {}", spec)),
+        }
+
+        let mut shards = data_shards(&data_dir)?;
+        let first_iteration = shards.last().map_or(0, |(iteration, _)| iteration + 1);
+
+        // The run's config, recording the curriculum and how far along it the loop has gotten
+        let config_path = dir.join("config.json");
+        let mut loop_config = if config_path.exists() {
+            LoopConfig::load(&config_path)
+                .map_err(|err| format!("Could not read {}: {}", config_path.display(), err))?
+        } else {
+            let curriculum = if sub_matches.get_flag("curriculum") {
+                default_curriculum()
+            } else {
+                Vec::new()
+            };
+            LoopConfig::new(curriculum, vec![first_iteration])
+        };
+        let save_loop_config = |loop_config: &LoopConfig| {
+            loop_config
+                .save(&config_path)
+                .map_err(|err| format!("Could not write {}: {}", config_path.display(), err))
+        };
+        save_loop_config(&loop_config)?;
+
+        println!("Incumbent: {}", incumbent);
+
+        for iteration in first_iteration..first_iteration + iterations {
+            println!("Round {} / {}", iteration - first_iteration + 1, iterations);
+
+            let stage = loop_config.stage();
+            let players = stage.as_ref().map_or(2, |stage| stage.players);
+            let mut game_args = game_args.clone();
+            if let Some(stage) = &stage {
+                println!(
+                    "Curriculum stage {} / {}: {}",
+                    loop_config.stages_reached.len(),
+                    loop_config.curriculum.len(),
+                    stage.desc()
+                );
+                game_args.extend(stage.game_args());
+            }
+
+            // Self-play
+            let shard = data_dir.join(format!("{}.data", iteration));
+            let player = playouts.map_or_else(
+                || incumbent.clone(),
+                |playouts| format!("m{}:{}", playouts, incumbent),
+            );
+            let mut args: Vec<String> = vec![
+                "-e".to_string(),
+                selfplay_games.to_string(),
+                SUBCMD_EVAL.to_string(),
+                "-P".to_string(),
+                shard.display().to_string(),
+            ];
+            args.extend(feature_window_args.iter().cloned());
+            args.extend(game_args.iter().cloned());
+            args.extend(vec![player; players]);
+            run_subcommand(&args)?;
+
+            shards.push((iteration, shard));
+
+            // Only the latest shards are trained on, from models nearest the incumbent
+            let stale = shards.len().saturating_sub(window);
+            for (_, shard) in shards.drain(..stale) {
+                fs::remove_file(&shard)
+                    .map_err(|err| format!("Could not delete {}: {}", shard.display(), err))?;
+            }
+
+            // Training
+            let candidate_dir = models_dir.join(format!("{}.agz", iteration));
+            let mut args: Vec<String> = vec![
+                "-e".to_string(),
+                epochs.to_string(),
+                SUBCMD_AGZTRAIN.to_string(),
+                "-D".to_string(),
+                learning_rate.to_string(),
+                "-o".to_string(),
+                candidate_dir.display().to_string(),
+            ];
+            args.extend(device_args.iter().cloned());
+            args.extend(feature_window_args.iter().cloned());
+            if augment_data {
+                args.push("--augment".to_string());
+            }
+            args.extend(shards.iter().map(|(_, shard)| shard.display().to_string()));
+            run_subcommand(&args)?;
+
+            let candidate = candidate_dir.join("model.bin").display().to_string();
+
+            // Evaluation, from every seat in case one has the edge
+            let (wins, losses, draws) =
+                play_each_seat(&candidate, &incumbent, players, eval_games, &game_args)?;
+
+            // Against the incumbent in a single seat, as there may be several
+            let losses = losses as f64 / (players - 1) as f64;
+            let score = (wins as f64 + draws as f64 / 2.0) / (wins as f64 + losses + draws as f64);
+            println!(
+                "Candidate {} won {} and drew {} of {} games against incumbent {}",
+                candidate,
+                wins,
+                draws,
+                players * eval_games,
+                incumbent
+            );
+
+            if score >= threshold {
+                println!("Promoted {} ({:.3} >= {})", candidate, score, threshold);
+                incumbent = candidate;
+                fs::write(&incumbent_path, format!("{}\n", incumbent)).map_err(|err| {
+                    format!("Could not write {}: {}", incumbent_path.display(), err)
+                })?;
+            } else {
+                println!("Rejected {} ({:.3} < {})", candidate, score, threshold);
+                fs::remove_dir_all(&candidate_dir).map_err(|err| {
+                    format!("Could not delete {}: {}", candidate_dir.display(), err)
+                })?;
+            }
+
+            // Moving on to the next stage of the curriculum once the incumbent's mastered this one
+            if let Some(stage) = &stage {
+                if loop_config.stages_reached.len() < loop_config.curriculum.len() {
+                    let (wins, _, _) =
+                        play_each_seat(&incumbent, "r", players, eval_games, &game_args)?;
+                    let win_rate = wins as f64 / (players * eval_games) as f64;
+
+                    if win_rate >= stage.advance_at {
+                        println!(
+                            "Incumbent beat the random AI in {:.3} of games (>= {}); moving on",
+                            win_rate, stage.advance_at
+                        );
+                        loop_config.stages_reached.push(iteration + 1);
+                        save_loop_config(&loop_config)?;
+                    } else {
+                        println!(
+                            "Incumbent beat the random AI in {:.3} of games (< {})",
+                            win_rate, stage.advance_at
+                        );
+                    }
+                }
+            }
+        }
+
+        println!("Incumbent: {}", incumbent);
+    } else if subcommand == SUBCMD_AGZCONVERT {
+        let precision = *sub_matches.get_one::<Precision>("precision").unwrap();
+        let input = PathBuf::from(sub_matches.get_one::<String>("input").unwrap());
+        let output = PathBuf::from(sub_matches.get_one::<String>("output").unwrap());
+
+        // The weights only pass through, so the CPU does fine
+        let model = AgzActionModel::<NdArray>::load(&input, NdArrayDevice::Cpu)?;
+        model.store_with_precision(&output, precision)?;
+
+        let size = |path: &Path| fs::metadata(path).map(|meta| meta.len()).unwrap_or(0);
+        println!(
+            "Stored {} at {} precision as {} ({} bytes -> {} bytes)",
+            input.display(),
+            precision,
+            output.display(),
+            size(&input),
+            size(&output)
         );
+    } else if subcommand == SUBCMD_MAPGEN {
+        let map_width = sub_matches.get_one::<u16>("map_width").copied().unwrap();
+        let map_height = sub_matches.get_one::<u16>("map_height").copied().unwrap();
+        let map_dims = Dims::new(map_width, map_height);
+        let map_type = sub_matches.get_one::<MapType>("map_type").copied().unwrap();
+        let wrapping = sub_matches.get_one::<Wrap2d>("wrapping").copied().unwrap();
+        let symmetry = sub_matches.get_one::<Symmetry>("symmetry").copied();
+        let num_players = sub_matches
+            .get_one::<PlayerNum>("num_players")
+            .copied()
+            .unwrap();
+        let seed = sub_matches.get_one::<u64>("random_seed").copied();
+
+        if let Some(symmetry) = symmetry {
+            symmetry.check(map_dims, num_players)?;
+        }
+
+        let mut rng = init_rng(seed);
+        let mut city_namer = IntNamer::new("city");
+
+        let (map, report) = if sub_matches.get_flag("fair_starts") {
+            map_type.generate_fair(
+                &mut rng,
+                map_dims,
+                num_players,
+                &mut city_namer,
+                symmetry,
+                wrapping,
+            )
+        } else {
+            let map = map_type.generate_with_symmetry(
+                &mut rng,
+                map_dims,
+                num_players,
+                &mut city_namer,
+                symmetry,
+            );
+            let report = fairness::assess_starts(&map, wrapping);
+            (map, report)
+        };
+
+        // Render the map through a throwaway game in which player 0 sees everything
+        let mut observations = ObsTracker::new(map_dims);
+        for loc in map_dims.iter_locs() {
+            observations.track_observation(loc, map.tile(loc).unwrap(), 0, 0);
+        }
+
+        let (game, secrets) =
+            Game::new_with_map(None, false, map, num_players, false, None, wrapping);
+        let game = Arc::new(RwLockTokio::new(game)) as Arc<RwLockTokio<dyn IGame>>;
+        let mut ctrl =
+            PlayerControl::from_observations(game, 0, secrets[0], map_dims, observations, wrapping);
+        let turn = PlayerTurn::spectate(&mut ctrl).await;
+
+        // Leave room below the map for the report
+        let report_lines = report.starts.len() as u16 + 4;
+        let rect = Rect::new(
+            0,
+            0,
+            map_width.min(term_width),
+            map_height.min(term_height.saturating_sub(report_lines)),
+        );
+        let mut map_view = Map::new(rect, map_dims, false);
+        map_view.set_viewport_offset(Vec2d::new(0, 0));
+
+        let palette = palette16(num_players, &Theme::default()).unwrap();
+
+        map_view
+            .draw(&turn, &mut screen, &palette)
+            .await
+            .map_err(|err| format!("Error drawing map: {}", err))?;
+        execute!(stdout, ResetColor, MoveTo(0, rect.height)).unwrap();
+
+        println!(
+            "{:?} map of {} for {} players, seed {:?}",
+            map_type, map_dims, num_players, seed
+        );
+        for start in report.starts.iter() {
+            println!("{}", start);
+        }
+        for imbalance in report.imbalances() {
+            println!("Unbalanced: {}", imbalance);
+        }
     } else {
         return Err(String::from("A subcommand must be given"));
     }
@@ -913,6 +1545,124 @@ async fn main() -> Result<(), String> {
     Ok(())
 }
 
+/// Run another of this tool's subcommands to completion, returning what it printed to standard output
+///
+/// What it has to say on standard error is passed along.
+fn run_subcommand(args: &[String]) -> Result<String, String> {
+    let exe = std::env::current_exe()
+        .map_err(|err| format!("Could not locate this executable: {}", err))?;
+
+    let output = Command::new(exe)
+        .args(args)
+        .stdin(Stdio::null())
+        .stderr(Stdio::inherit())
+        .output()
+        .map_err(|err| format!("Could not run '{}': {}", args.join(" "), err))?;
+
+    if !output.status.success() {
+        return Err(format!("'{}' failed: {}", args.join(" "), output.status));
+    }
+
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+/// Have `ai` play `games` games from each of the `players` seats in turn, `other` in the rest
+///
+/// Returns how many games `ai` won, how many the others won between them, and how many were draws.
+fn play_each_seat(
+    ai: &str,
+    other: &str,
+    players: usize,
+    games: usize,
+    game_args: &[String],
+) -> Result<(usize, usize, usize), String> {
+    let mut wins = 0usize;
+    let mut losses = 0usize;
+    let mut draws = 0usize;
+
+    for seat in 0..players {
+        let mut args: Vec<String> =
+            vec!["-e".to_string(), games.to_string(), SUBCMD_EVAL.to_string()];
+        args.extend(game_args.iter().cloned());
+        args.extend((0..players).map(|player| {
+            if player == seat {
+                ai.to_string()
+            } else {
+                other.to_string()
+            }
+        }));
+
+        let (seat_wins, seat_draws) = parse_eval_results(&run_subcommand(&args)?, players)?;
+        for (player, player_wins) in seat_wins.into_iter().enumerate() {
+            if player == seat {
+                wins += player_wins;
+            } else {
+                losses += player_wins;
+            }
+        }
+        draws += seat_draws;
+    }
+
+    Ok((wins, losses, draws))
+}
+
+/// Each player's wins and the number of draws in the output of an `eval` of `players` players
+///
+/// These come from the last line, which reads: spec, wins, spec, wins, ..., "draw", draws---tab-separated. Terminal
+/// control codes may come before it.
+fn parse_eval_results(stdout: &str, players: usize) -> Result<(Vec<usize>, usize), String> {
+    let line = stdout
+        .lines()
+        .rev()
+        .find(|line| line.contains('\t'))
+        .ok_or_else(|| String::from("No results in eval's output"))?;
+
+    let fields: Vec<&str> = line.split('\t').collect();
+    let unrecognized = || format!("Unrecognized eval results '{}'", line);
+
+    // Counting from the end, as the first spec may have picked up control codes
+    let Some(start) = fields.len().checked_sub(2 * players + 2) else {
+        return Err(unrecognized());
+    };
+    let fields = &fields[start..];
+
+    if fields[2 * players] != "draw" {
+        return Err(unrecognized());
+    }
+
+    let count =
+        |i: usize| -> Result<usize, String> { fields[i].parse().map_err(|_| unrecognized()) };
+
+    let wins = (0..players)
+        .map(|player| count(2 * player + 1))
+        .collect::<Result<Vec<usize>, String>>()?;
+
+    Ok((wins, count(2 * players + 1)?))
+}
+
+/// The self-play data shards in `dir`, by the round that generated them, in order
+fn data_shards(dir: &Path) -> Result<Vec<(usize, PathBuf)>, String> {
+    let entries =
+        fs::read_dir(dir).map_err(|err| format!("Could not read {}: {}", dir.display(), err))?;
+
+    let mut shards: Vec<(usize, PathBuf)> = entries
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter_map(|path| {
+            let iteration = path
+                .file_name()?
+                .to_str()?
+                .strip_suffix(".data")?
+                .parse()
+                .ok()?;
+            Some((iteration, path))
+        })
+        .collect();
+
+    shards.sort();
+
+    Ok(shards)
+}
+
 #[derive(Config)]
 pub struct TrainingConfig {
     pub model: AgzActionModelConfig,
@@ -933,6 +1683,80 @@ pub struct TrainingConfig {
     pub learning_rate: f64,
 }
 
+/// The games of one stage of an `agzloop` curriculum, and how well the incumbent must do to move on from them
+#[derive(Config, Debug)]
+pub struct CurriculumStage {
+    pub map_width: u16,
+
+    pub map_height: u16,
+
+    pub players: usize,
+
+    pub fog: FogOfWar,
+
+    /// The share of games against the random AI the incumbent must win to move on to the next stage
+    pub advance_at: f64,
+}
+
+impl CurriculumStage {
+    /// Arguments to eval for games at this stage, but for the players
+    fn game_args(&self) -> Vec<String> {
+        let fog = match self.fog {
+            FogOfWar::Off => "off",
+            FogOfWar::Partial => "partial",
+            FogOfWar::Full => "on",
+        };
+
+        vec![
+            "-W".to_string(),
+            self.map_width.to_string(),
+            "-H".to_string(),
+            self.map_height.to_string(),
+            "-f".to_string(),
+            fog.to_string(),
+        ]
+    }
+
+    fn desc(&self) -> String {
+        format!(
+            "{}x{} map, {} players, fog {:?}",
+            self.map_width, self.map_height, self.players, self.fog
+        )
+    }
+}
+
+/// Tiny maps with two players and everything in sight first; bigger, more crowded, and foggier maps as the model
+/// improves
+fn default_curriculum() -> Vec<CurriculumStage> {
+    vec![
+        CurriculumStage::new(10, 10, 2, FogOfWar::Off, 0.8),
+        CurriculumStage::new(15, 15, 2, FogOfWar::Off, 0.8),
+        CurriculumStage::new(20, 20, 2, FogOfWar::Partial, 0.75),
+        CurriculumStage::new(30, 30, 3, FogOfWar::Full, 0.7),
+        CurriculumStage::new(40, 40, 4, FogOfWar::Full, 1.0),
+    ]
+}
+
+/// How an `agzloop` run is set up, kept in its directory as config.json
+#[derive(Config)]
+pub struct LoopConfig {
+    /// The stages of the curriculum, in order; if there are none the games are just as given
+    pub curriculum: Vec<CurriculumStage>,
+
+    /// The round in which each stage of the curriculum reached so far was reached
+    pub stages_reached: Vec<usize>,
+}
+
+impl LoopConfig {
+    /// The stage of the curriculum the loop is on, if there's a curriculum
+    fn stage(&self) -> Option<CurriculumStage> {
+        let stage = self.stages_reached.len().checked_sub(1)?;
+        self.curriculum
+            .get(stage.min(self.curriculum.len().checked_sub(1)?))
+            .cloned()
+    }
+}
+
 fn create_artifact_dir<P: AsRef<Path>>(artifact_dir: &P) {
     // Remove existing artifacts before to get an accurate learner summary
     std::fs::remove_dir_all(artifact_dir).ok();
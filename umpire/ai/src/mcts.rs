@@ -0,0 +1,283 @@
+//! Monte Carlo Tree Search guided by the AGZ action model
+//!
+//! Rather than play whichever action the model rates highest, look ahead first: play out sequences of actions in a
+//! sandboxed copy of the game, using the model's policy as priors on which actions to try, and its value head's
+//! ratings of the positions reached as estimates of how they'll turn out. The action tried most often is the one
+//! played.
+//!
+//! The search only looks ahead through the rest of the player's own turn, as the other players can't be played for
+//! without their secrets---nor without seeing what they see.
+//!
+//! The sandbox's random number generator is reseeded, so the search can't foresee how fights in the real game will
+//! go. Each playout replays its path from the root in a fresh copy, so the positions along a path are the same every
+//! time.
+
+use std::collections::BTreeMap;
+
+use async_trait::async_trait;
+use burn::tensor::backend::Backend;
+
+use common::game::{
+    action::AiPlayerAction,
+    ai::{fX, AiDevice, TrainingFocus},
+    player::PlayerTurn,
+    turn_async::ActionwiseTurnTaker,
+    Game, PlayerNum, PlayerSecret,
+};
+
+use crate::agz::{legal_policy, AgzActionModel};
+
+/// How much weight the model's priors get relative to what the playouts find; higher explores more
+const EXPLORATION: fX = 1.5;
+
+/// A position in the search tree, reached by a sequence of actions from the root
+struct Node {
+    /// The policy's probability of the action leading here, among the alternatives
+    prior: fX,
+
+    visits: usize,
+
+    /// Sum of the values of all playouts through here
+    value_sum: fX,
+
+    /// The positions each legal action leads to; `None` until expanded
+    children: Option<BTreeMap<AiPlayerAction, Node>>,
+}
+
+impl Node {
+    fn new(prior: fX) -> Self {
+        Self {
+            prior,
+            visits: 0,
+            value_sum: 0.0,
+            children: None,
+        }
+    }
+
+    /// Mean value of the playouts through here
+    fn value(&self) -> fX {
+        if self.visits == 0 {
+            0.0
+        } else {
+            self.value_sum / self.visits as fX
+        }
+    }
+
+    /// The action to try next by the PUCT rule, if there are any
+    fn select(&self) -> Option<AiPlayerAction> {
+        let sqrt_visits = (self.visits as fX).sqrt();
+
+        let score = |child: &Node| {
+            child.value() + EXPLORATION * child.prior * sqrt_visits / (1 + child.visits) as fX
+        };
+
+        self.children
+            .as_ref()?
+            .iter()
+            .max_by(|(_, a), (_, b)| score(a).total_cmp(&score(b)))
+            .map(|(action, _)| *action)
+    }
+
+    /// The action tried most often
+    fn most_visited(&self) -> Option<AiPlayerAction> {
+        self.children
+            .as_ref()?
+            .iter()
+            .max_by_key(|(_, child)| child.visits)
+            .map(|(action, _)| *action)
+    }
+
+    fn backup(&mut self, value: fX) {
+        self.visits += 1;
+        self.value_sum += value;
+    }
+}
+
+pub struct MctsAI<B: Backend> {
+    model: AgzActionModel<B>,
+
+    /// How many playouts to run before each action
+    playouts: usize,
+}
+
+impl<B: Backend> MctsAI<B> {
+    pub fn new(model: AgzActionModel<B>, playouts: usize) -> Self {
+        Self { model, playouts }
+    }
+
+    /// Search from `root` for the best action for `player`, whose secret is `secret`
+    fn search(
+        &self,
+        root: &Game,
+        player: PlayerNum,
+        secret: PlayerSecret,
+        device: &B::Device,
+    ) -> Option<AiPlayerAction> {
+        let mut tree = Node::new(1.0);
+
+        for _ in 0..self.playouts {
+            // Shares the map and observations with the root until the playout changes them
+            let mut game = root.sandbox();
+            self.playout(&mut tree, &mut game, player, secret, device);
+        }
+
+        tree.most_visited()
+    }
+
+    /// Play out one sequence of actions from `node`, whose position is `game`'s, returning the value found at its end
+    fn playout(
+        &self,
+        node: &mut Node,
+        game: &mut Game,
+        player: PlayerNum,
+        secret: PlayerSecret,
+        device: &B::Device,
+    ) -> fX {
+        let value = match node.select() {
+            Some(action) => match game.take_action(secret, action) {
+                Ok(_) => {
+                    let child = node.children.as_mut().unwrap().get_mut(&action).unwrap();
+                    self.playout(child, game, player, secret, device)
+                }
+                // Shouldn't happen as the positions along a path don't change, but if it does there's no going on
+                Err(_) => self.estimate(game, player, secret, device).1,
+            },
+            None => {
+                let (priors, value) = self.estimate(game, player, secret, device);
+                node.children = Some(
+                    priors
+                        .into_iter()
+                        .map(|(action, prior)| (action, Node::new(prior)))
+                        .collect(),
+                );
+                value
+            }
+        };
+
+        node.backup(value);
+        value
+    }
+
+    /// The legal actions in `game`'s position with their priors, and the position's value to `player`
+    ///
+    /// Once the game is won or lost the value is certain. Otherwise it's the model's estimate.
+    fn estimate(
+        &self,
+        game: &Game,
+        player: PlayerNum,
+        secret: PlayerSecret,
+        device: &B::Device,
+    ) -> (Vec<(AiPlayerAction, fX)>, fX) {
+        if let Some(victor) = game.victor() {
+            return (Vec::new(), if victor == player { 1.0 } else { 0.0 });
+        }
+
+        let window = self.model.window();
+        let features = |focus| game.player_features(secret, focus, window).unwrap();
+
+        if game.current_turn_is_done() {
+            let (_logits, value) = self
+                .model
+                .evaluate(&features(TrainingFocus::UnitIfExistsElseCity), device);
+            return (Vec::new(), value);
+        }
+
+        // City actions come first, as when taking the turn for real
+        let city_actions: Vec<AiPlayerAction> = game
+            .player_next_city_legal_actions(secret)
+            .unwrap()
+            .into_iter()
+            .map(AiPlayerAction::City)
+            .collect();
+
+        let (legal, focus) = if city_actions.is_empty() {
+            let unit_actions = game
+                .player_next_unit_legal_actions(secret)
+                .unwrap()
+                .into_iter()
+                .map(AiPlayerAction::Unit)
+                .collect();
+            (unit_actions, TrainingFocus::Unit)
+        } else {
+            (city_actions, TrainingFocus::City)
+        };
+
+        let (logits, value) = self.model.evaluate(&features(focus), device);
+
+        let priors = legal_policy(&logits, legal.iter().copied().map(usize::from))
+            .into_iter()
+            .map(|(i, prior)| (AiPlayerAction::from(i), prior))
+            .collect();
+
+        (priors, value)
+    }
+}
+
+#[async_trait]
+impl<B: Backend> ActionwiseTurnTaker for MctsAI<B>
+where
+    B::Device: From<AiDevice>,
+{
+    async fn next_action(&mut self, turn: &PlayerTurn, device: AiDevice) -> Option<AiPlayerAction> {
+        // Without the whole game to search, as when playing over the network, just go with the model
+        let Ok(mut root) = turn.clone_underlying_game_state().await else {
+            return self.model.next_action(turn, device).await;
+        };
+
+        if root.current_turn_is_done() {
+            return None;
+        }
+
+        root.reseed(None);
+
+        // Nothing done in the search needs recording, and a replay would be copied with every playout
+        root.take_replay();
+
+        let player = root.current_player();
+        let secret = root.current_player_secret();
+        let device: B::Device = device.into();
+
+        self.search(&root, player, secret, &device)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use common::game::action::{AiPlayerAction, NextUnitAction};
+
+    use super::Node;
+
+    #[test]
+    fn test_select() {
+        let skip = AiPlayerAction::Unit(NextUnitAction::Skip);
+        let disband = AiPlayerAction::Unit(NextUnitAction::Disband);
+
+        let mut node = Node::new(1.0);
+        assert_eq!(node.select(), None);
+
+        node.children = Some(
+            [(skip, Node::new(0.75)), (disband, Node::new(0.25))]
+                .into_iter()
+                .collect(),
+        );
+        node.backup(0.5);
+
+        // Untried, the model's favorite goes first
+        assert_eq!(node.select(), Some(skip));
+
+        // Until it disappoints
+        {
+            let children = node.children.as_mut().unwrap();
+            let skip_node = children.get_mut(&skip).unwrap();
+            for _ in 0..10 {
+                skip_node.backup(0.0);
+            }
+        }
+        for _ in 0..10 {
+            node.backup(0.0);
+        }
+        assert_eq!(node.select(), Some(disband));
+
+        assert_eq!(node.most_visited(), Some(skip));
+    }
+}
@@ -0,0 +1,290 @@
+//! Storing models' weights at reduced precision
+//!
+//! Half precision halves a model's size on disk; int8 quarters it, each tensor's weights scaled to fit in a byte.
+//! Either way the weights are widened again on loading, so it's only the size that's reduced, not the precision of
+//! the arithmetic.
+//!
+//! Full-precision models are stored as they always have been. Others lead with a header saying how they're stored,
+//! so they can be told apart on loading.
+
+use std::fmt;
+
+use burn::{
+    module::{Module, ModuleMapper, ModuleVisitor, ParamId},
+    record::{BinBytesRecorder, FullPrecisionSettings, HalfPrecisionSettings, Recorder},
+    tensor::{backend::Backend, Data, Tensor},
+};
+use num_traits::ToPrimitive;
+use serde::{Deserialize, Serialize};
+
+use common::game::ai::fX;
+
+/// Leads models stored at anything but full precision, followed by the precision's byte
+const MAGIC: &[u8; 4] = b"UMPQ";
+
+/// How precisely a model's weights are stored
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Precision {
+    /// 32-bit floats
+    #[default]
+    Full,
+
+    /// 16-bit floats
+    Half,
+
+    /// 8-bit integers, with a scale per tensor
+    Int8,
+}
+
+impl Precision {
+    fn to_byte(self) -> u8 {
+        match self {
+            Self::Full => 0,
+            Self::Half => 1,
+            Self::Int8 => 2,
+        }
+    }
+
+    fn from_byte(b: u8) -> Result<Self, String> {
+        match b {
+            0 => Ok(Self::Full),
+            1 => Ok(Self::Half),
+            2 => Ok(Self::Int8),
+            b => Err(format!("Unrecognized model precision {}", b)),
+        }
+    }
+}
+
+impl TryFrom<&str> for Precision {
+    type Error = String;
+
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        match value {
+            "full" | "f32" => Ok(Self::Full),
+            "half" | "f16" => Ok(Self::Half),
+            "int8" | "i8" => Ok(Self::Int8),
+            x => Err(format!("Unrecognized precision {}", x)),
+        }
+    }
+}
+
+impl fmt::Display for Precision {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Full => "full",
+            Self::Half => "half",
+            Self::Int8 => "int8",
+        })
+    }
+}
+
+/// A tensor's weights in 8 bits apiece, the largest in magnitude mapped to ±127
+#[derive(Debug, Deserialize, PartialEq, Serialize)]
+struct QuantizedTensor {
+    scale: fX,
+    values: Vec<i8>,
+}
+
+impl QuantizedTensor {
+    fn quantize(values: &[fX]) -> Self {
+        let max = values.iter().fold(0.0, |max: fX, x| max.max(x.abs()));
+        let scale = if max > 0.0 { max / 127.0 } else { 1.0 };
+
+        Self {
+            scale,
+            values: values
+                .iter()
+                .map(|x| (x / scale).round().clamp(-127.0, 127.0) as i8)
+                .collect(),
+        }
+    }
+
+    fn dequantize(&self) -> Vec<fX> {
+        self.values.iter().map(|q| *q as fX * self.scale).collect()
+    }
+}
+
+/// Quantizes each of a module's float tensors, in the order visited
+struct Quantizer {
+    tensors: Vec<QuantizedTensor>,
+}
+
+impl<B: Backend> ModuleVisitor<B> for Quantizer {
+    fn visit_float<const D: usize>(&mut self, _id: &ParamId, tensor: &Tensor<B, D>) {
+        let values: Vec<fX> = tensor
+            .to_data()
+            .value
+            .into_iter()
+            .map(|x| x.to_f32().unwrap())
+            .collect();
+        self.tensors.push(QuantizedTensor::quantize(&values));
+    }
+}
+
+/// Collects the shape of each of a module's float tensors, in the order visited
+struct Shapes {
+    shapes: Vec<Vec<usize>>,
+}
+
+impl<B: Backend> ModuleVisitor<B> for Shapes {
+    fn visit_float<const D: usize>(&mut self, _id: &ParamId, tensor: &Tensor<B, D>) {
+        self.shapes.push(tensor.dims().to_vec());
+    }
+}
+
+fn shapes<B: Backend, M: Module<B>>(module: &M) -> Vec<Vec<usize>> {
+    let mut shapes = Shapes { shapes: Vec::new() };
+    module.visit(&mut shapes);
+    shapes.shapes
+}
+
+/// Replaces each of a module's float tensors, in the order visited, with the dequantized weights
+struct Dequantizer {
+    tensors: std::vec::IntoIter<QuantizedTensor>,
+    error: Option<String>,
+}
+
+impl<B: Backend> ModuleMapper<B> for Dequantizer {
+    fn map_float<const D: usize>(&mut self, _id: &ParamId, tensor: Tensor<B, D>) -> Tensor<B, D> {
+        let shape = tensor.shape();
+
+        let Some(quantized) = self.tensors.next() else {
+            self.error = Some(String::from("Model has more tensors than were stored"));
+            return tensor;
+        };
+
+        if quantized.values.len() != shape.num_elements() {
+            self.error = Some(format!(
+                "Stored tensor of {} weights doesn't fit the model's tensor of shape {:?}",
+                quantized.values.len(),
+                shape.dims
+            ));
+            return tensor;
+        }
+
+        let data = Data::new(quantized.dequantize(), shape).convert();
+        Tensor::from_data(data, &tensor.device())
+    }
+}
+
+/// The bytes of `module` stored at `precision`
+pub fn store_bytes<B: Backend, M: Module<B>>(
+    module: M,
+    precision: Precision,
+) -> Result<Vec<u8>, String> {
+    let payload = match precision {
+        Precision::Full => {
+            return BinBytesRecorder::<FullPrecisionSettings>::default()
+                .record(module.into_record(), ())
+                .map_err(|err| err.to_string())
+        }
+        Precision::Half => BinBytesRecorder::<HalfPrecisionSettings>::default()
+            .record(module.into_record(), ())
+            .map_err(|err| err.to_string())?,
+        Precision::Int8 => {
+            let mut quantizer = Quantizer {
+                tensors: Vec::new(),
+            };
+            module.visit(&mut quantizer);
+            bincode::serialize(&quantizer.tensors).map_err(|err| err.to_string())?
+        }
+    };
+
+    let mut bytes = Vec::with_capacity(MAGIC.len() + 1 + payload.len());
+    bytes.extend_from_slice(MAGIC);
+    bytes.push(precision.to_byte());
+    bytes.extend(payload);
+    Ok(bytes)
+}
+
+/// Load the weights in `bytes`, stored at whatever precision, into `module`
+///
+/// The module must be configured as the stored one was; if its tensors' shapes don't match those stored, that's an
+/// error.
+pub fn load_bytes<B: Backend, M: Module<B>>(
+    module: M,
+    bytes: Vec<u8>,
+    device: &B::Device,
+) -> Result<M, String> {
+    let (precision, payload) = match bytes.strip_prefix(MAGIC.as_slice()) {
+        Some([precision, payload @ ..]) => (Precision::from_byte(*precision)?, payload.to_vec()),
+        Some([]) => return Err(String::from("Model ends before saying how it's stored")),
+        None => (Precision::Full, bytes),
+    };
+
+    // Records load whatever their shapes, so check they fit
+    let expected = shapes(&module);
+    let fit = |module: M| {
+        if shapes(&module) == expected {
+            Ok(module)
+        } else {
+            Err(String::from(
+                "Stored model's tensors don't fit the shapes of the model's",
+            ))
+        }
+    };
+
+    match precision {
+        Precision::Full => BinBytesRecorder::<FullPrecisionSettings>::default()
+            .load(payload, device)
+            .map_err(|err| err.to_string())
+            .and_then(|record| fit(module.load_record(record))),
+        Precision::Half => BinBytesRecorder::<HalfPrecisionSettings>::default()
+            .load(payload, device)
+            .map_err(|err| err.to_string())
+            .and_then(|record| fit(module.load_record(record))),
+        Precision::Int8 => {
+            let tensors: Vec<QuantizedTensor> =
+                bincode::deserialize(&payload).map_err(|err| err.to_string())?;
+
+            let mut dequantizer = Dequantizer {
+                tensors: tensors.into_iter(),
+                error: None,
+            };
+            let module = module.map(&mut dequantizer);
+
+            if let Some(err) = dequantizer.error {
+                return Err(err);
+            }
+            if dequantizer.tensors.next().is_some() {
+                return Err(String::from("Model has fewer tensors than were stored"));
+            }
+
+            Ok(module)
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{Precision, QuantizedTensor};
+
+    #[test]
+    fn test_quantize() {
+        let values = vec![0.5, -1.0, 0.0, 0.25, 1.0];
+        let quantized = QuantizedTensor::quantize(&values);
+
+        assert_eq!(quantized.values, vec![64, -127, 0, 32, 127]);
+
+        for (x, y) in values.iter().zip(quantized.dequantize()) {
+            assert!((x - y).abs() <= quantized.scale / 2.0);
+        }
+
+        // Nothing but zeros
+        assert_eq!(
+            QuantizedTensor::quantize(&[0.0; 3]).dequantize(),
+            vec![0.0; 3]
+        );
+    }
+
+    #[test]
+    fn test_precision_bytes() {
+        for precision in [Precision::Full, Precision::Half, Precision::Int8] {
+            assert_eq!(Precision::from_byte(precision.to_byte()), Ok(precision));
+            assert_eq!(
+                Precision::try_from(precision.to_string().as_str()),
+                Ok(precision)
+            );
+        }
+    }
+}
@@ -223,6 +223,16 @@ impl ActionwiseTurnTaker for RandomPlusAI {
             let unit = ctrl.player_unit_by_id(unit_id).await.unwrap();
             // let unit_id = unit.id;
 
+            // Never pass up a shot at an enemy in range
+            if !ctrl
+                .player_unit_bombard_targets(unit_id)
+                .await
+                .unwrap()
+                .is_empty()
+            {
+                return Some(AiPlayerAction::Unit(NextUnitAction::Bombard));
+            }
+
             // let possible: Vec<Location> = match ctrl.current_player_unit_legal_one_step_destinations(unit_id) {
             //     Ok(it) => it,
             //     Err(e) => {
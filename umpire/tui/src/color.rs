@@ -7,6 +7,9 @@
 //!
 //! The default is 256 colors, but the color scheme can be selected by a command line flag.
 //!
+//! Any of the palettes can be adjusted by a theme: a TOML file in the format of `data/theme.toml` giving colors to use
+//! in place of the built-in ones. A theme need only list the colors it changes.
+//!
 //! Umpire's colors are divided into two categories: normal and paired.
 //!
 //! Normal colors are single colors. Just like normal.
@@ -18,6 +21,8 @@
 //! * map: colors only used on the map
 //! * text: colors used in textual output outside of the map
 
+use std::{fs, path::Path};
+
 use common::{colors::Colors, game::PlayerNum};
 use crossterm::style::Color;
 
@@ -30,6 +35,11 @@ use pastel::{
     Color as PastelColor, RGBA,
 };
 use rand::{rngs::StdRng, Rng};
+use serde::Deserialize;
+use thiserror::Error;
+
+/// How much foggy colors are darkened when neither the theme nor the caller says
+const DEFAULT_FOG_DARKNESS: f64 = 0.1;
 
 pub trait PairColorized {
     fn color_pair(&self, palette: &Palette) -> Option<ColorPair>;
@@ -60,6 +70,8 @@ pub struct Palette {
 
     // other
     scroll_marks: Color,
+
+    reachable: Color,
 }
 
 impl Palette {
@@ -71,6 +83,7 @@ impl Palette {
             Colors::Combat => self.combat,
             Colors::Cursor => self.cursor,
             Colors::ScrollMarks => self.scroll_marks,
+            Colors::Reachable => self.reachable,
             _ => panic!("Color {:?} is not a single color", color),
         }
     }
@@ -87,6 +100,7 @@ impl Palette {
             Colors::Cursor => self.cursor,
             Colors::Combat => self.combat,
             Colors::ScrollMarks => self.scroll_marks,
+            Colors::Reachable => self.reachable,
         }
     }
 
@@ -101,14 +115,9 @@ impl Palette {
     }
 }
 
-pub fn palette16(num_players: PlayerNum) -> Result<Palette, String> {
-    if num_players > 5 {
-        Err(format!(
-            "Chosen color palette only supports 5 players, but {} were specified",
-            num_players
-        ))
-    } else {
-        Ok(Palette {
+pub fn palette16(num_players: PlayerNum, theme: &Theme) -> Result<Palette, String> {
+    let palette = theme.apply(
+        Palette {
             background: Color::Reset, // Color::Black,
             land: ColorPair::new(Color::Green, Color::DarkGreen),
             ocean: ColorPair::new(Color::Blue, Color::DarkBlue),
@@ -125,30 +134,42 @@ pub fn palette16(num_players: PlayerNum) -> Result<Palette, String> {
             cursor: Color::White,
             combat: Color::Red,
             scroll_marks: Color::Yellow,
-        })
+            reachable: Color::DarkGrey,
+        },
+        DEFAULT_FOG_DARKNESS,
+    );
+
+    if num_players > palette.players.len() {
+        Err(format!(
+            "Chosen color palette only supports {} players, but {} were specified",
+            palette.players.len(),
+            num_players
+        ))
+    } else {
+        Ok(palette)
     }
 }
 
-pub fn palette256(num_players: PlayerNum) -> Result<Palette, String> {
-    palette16(num_players) //These are the same for now
-                           // Palette {
-                           //     background: AnsiValue(0),
-                           //     land: ColorPair::new_ansi(10, 2),
-                           //     ocean: ColorPair::new_ansi(12, 4),
-                           //     players: vec![
-                           //         ColorPair::new_ansi(9, 1),// red
-                           //         ColorPair::new_ansi(15, 7),// white
-                           //         ColorPair::new_ansi(13, 5),// purple
-                           //         ColorPair::new_ansi(11, 3),// yellow
-                           //         ColorPair::new_ansi(14, 6),// cyan
-                           //     ],
-                           //     neutral: ColorPair::new_ansi(8, 8),// gray
-                           //     text: AnsiValue(15),
-                           //     notice: AnsiValue(14),
-                           //     cursor: AnsiValue(15),
-                           //     combat: AnsiValue(9),
-                           //     scroll_marks: AnsiValue(11),
-                           // }
+pub fn palette256(num_players: PlayerNum, theme: &Theme) -> Result<Palette, String> {
+    palette16(num_players, theme) //These are the same for now
+                                  // Palette {
+                                  //     background: AnsiValue(0),
+                                  //     land: ColorPair::new_ansi(10, 2),
+                                  //     ocean: ColorPair::new_ansi(12, 4),
+                                  //     players: vec![
+                                  //         ColorPair::new_ansi(9, 1),// red
+                                  //         ColorPair::new_ansi(15, 7),// white
+                                  //         ColorPair::new_ansi(13, 5),// purple
+                                  //         ColorPair::new_ansi(11, 3),// yellow
+                                  //         ColorPair::new_ansi(14, 6),// cyan
+                                  //     ],
+                                  //     neutral: ColorPair::new_ansi(8, 8),// gray
+                                  //     text: AnsiValue(15),
+                                  //     notice: AnsiValue(14),
+                                  //     cursor: AnsiValue(15),
+                                  //     combat: AnsiValue(9),
+                                  //     scroll_marks: AnsiValue(11),
+                                  // }
 }
 
 fn pastel_color_to_rgb(pastel_color: &PastelColor) -> Color {
@@ -234,7 +255,18 @@ pub fn distinct_colors_from_rng<R: Rng>(
     (annealing.get_colors(), result)
 }
 
-pub fn palette24(rng: StdRng, num_players: PlayerNum, darken_percent: f64) -> Palette {
+/// A true color palette with a distinct color for each player
+///
+/// Players the theme gives no color are assigned colors as unlike the others as can be found. The theme's fog darkness,
+/// if any, takes the place of `darken_percent`.
+pub fn palette24(
+    rng: StdRng,
+    num_players: PlayerNum,
+    darken_percent: f64,
+    theme: &Theme,
+) -> Palette {
+    let darken_percent = theme.fog_darkness.unwrap_or(darken_percent);
+
     let land = color_to_rgb_pair(
         Color::Rgb {
             r: 24,
@@ -260,38 +292,59 @@ pub fn palette24(rng: StdRng, num_players: PlayerNum, darken_percent: f64) -> Pa
         darken_percent,
     );
 
-    let preexisting = vec![
-        color_to_pastel_color(land.active),
-        color_to_pastel_color(ocean.active),
-        color_to_pastel_color(neutral.active),
-    ];
+    let land = theme.land.map_or(land, |pair| pair.resolve(darken_percent));
+    let ocean = theme
+        .ocean
+        .map_or(ocean, |pair| pair.resolve(darken_percent));
+    let neutral = theme
+        .neutral
+        .map_or(neutral, |pair| pair.resolve(darken_percent));
 
-    let num_preexisting = preexisting.len();
-
-    let mut callback = |_stats: &IterationStatistics| {};
+    let mut players: Vec<ColorPair> = theme
+        .players
+        .iter()
+        .take(num_players)
+        .map(|pair| pair.resolve(darken_percent))
+        .collect();
 
-    let distinct: Vec<PastelColor> = distinct_colors_from_rng(
-        rng,
-        num_players + num_preexisting,
-        // DistanceMetric::CIE76,
-        DistanceMetric::CIEDE2000,
-        preexisting,
-        &mut callback,
-    )
-    .0
-    .iter()
-    .skip(num_preexisting)
-    .cloned()
-    .collect();
+    // Colors the generated player colors should stand apart from; only RGB colors can be compared
+    let preexisting: Vec<PastelColor> = [land.active, ocean.active, neutral.active]
+        .into_iter()
+        .chain(players.iter().map(|pair| pair.active))
+        .filter(|color| matches!(color, Color::Rgb { .. }))
+        .map(color_to_pastel_color)
+        .collect();
 
-    let players: Vec<ColorPair> = distinct
+    let num_preexisting = preexisting.len();
+    let num_generated = num_players - players.len();
+
+    if num_generated > 0 {
+        let mut callback = |_stats: &IterationStatistics| {};
+
+        let distinct: Vec<PastelColor> = distinct_colors_from_rng(
+            rng,
+            num_generated + num_preexisting,
+            // DistanceMetric::CIE76,
+            DistanceMetric::CIEDE2000,
+            preexisting,
+            &mut callback,
+        )
+        .0
         .iter()
-        .map(|pastel_color| pastel_color_to_rgb_pair(pastel_color, darken_percent))
+        .skip(num_preexisting)
+        .cloned()
         .collect();
 
+        players.extend(
+            distinct
+                .iter()
+                .map(|pastel_color| pastel_color_to_rgb_pair(pastel_color, darken_percent)),
+        );
+    }
+
     debug_assert_eq!(players.len(), num_players);
 
-    Palette {
+    let palette = Palette {
         background: Color::Rgb { r: 0, g: 0, b: 0 },
         land,
         ocean,
@@ -322,7 +375,22 @@ pub fn palette24(rng: StdRng, num_players: PlayerNum, darken_percent: f64) -> Pa
             g: 202,
             b: 0,
         },
+        reachable: Color::Rgb {
+            r: 64,
+            g: 64,
+            b: 64,
+        },
+    };
+
+    // The map and player colors were settled above
+    Theme {
+        land: None,
+        ocean: None,
+        neutral: None,
+        players: Vec::new(),
+        ..theme.clone()
     }
+    .apply(palette, darken_percent)
 }
 
 #[derive(Copy, Clone)]
@@ -344,3 +412,211 @@ impl ColorPair {
         }
     }
 }
+
+#[derive(Debug, Error)]
+pub enum ThemeError {
+    #[error("Error reading theme file: {0}")]
+    Io(#[from] std::io::Error),
+
+    #[error("Error parsing theme: {0}")]
+    Parse(#[from] toml::de::Error),
+}
+
+/// A color as written in a theme file
+///
+/// Either a crossterm color name such as "dark_green", an ANSI color number from 0 to 255, or an RGB hex code such as
+/// "#18d843".
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq)]
+#[serde(try_from = "String")]
+pub struct ThemeColor(Color);
+
+impl TryFrom<String> for ThemeColor {
+    type Error = String;
+
+    fn try_from(s: String) -> Result<Self, Self::Error> {
+        let s = s.trim();
+
+        if let Some(hex) = s.strip_prefix('#') {
+            let channel = |i: usize| {
+                hex.get(i..i + 2)
+                    .and_then(|channel| u8::from_str_radix(channel, 16).ok())
+            };
+            return match (hex.len(), channel(0), channel(2), channel(4)) {
+                (6, Some(r), Some(g), Some(b)) => Ok(Self(Color::Rgb { r, g, b })),
+                _ => Err(format!("'{}' is not an RGB hex code", s)),
+            };
+        }
+
+        if let Ok(value) = s.parse::<u8>() {
+            return Ok(Self(Color::AnsiValue(value)));
+        }
+
+        Color::try_from(s)
+            .map(Self)
+            .map_err(|_| format!("'{}' is not a color", s))
+    }
+}
+
+/// A color pair as written in a theme file
+///
+/// When no foggy color is given, the active color is darkened to make one, so long as it's an RGB color; any other
+/// color serves as its own foggy color.
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct ThemeColorPair {
+    active: ThemeColor,
+    foggy: Option<ThemeColor>,
+}
+
+impl ThemeColorPair {
+    fn resolve(&self, darken_percent: f64) -> ColorPair {
+        match (self.active.0, self.foggy) {
+            (active, Some(foggy)) => ColorPair::new(active, foggy.0),
+            (active @ Color::Rgb { .. }, None) => color_to_rgb_pair(active, darken_percent),
+            (active, None) => ColorPair::new(active, active),
+        }
+    }
+}
+
+/// Colors to use in place of those of the built-in palettes
+///
+/// Anything left out keeps the palette's own color.
+#[derive(Clone, Debug, Default, Deserialize, PartialEq)]
+#[serde(default, deny_unknown_fields)]
+pub struct Theme {
+    background: Option<ThemeColor>,
+
+    land: Option<ThemeColorPair>,
+    ocean: Option<ThemeColorPair>,
+    neutral: Option<ThemeColorPair>,
+
+    /// Player colors, in player order; players beyond the end of the list keep the palette's colors
+    players: Vec<ThemeColorPair>,
+
+    text: Option<ThemeColor>,
+    notice: Option<ThemeColor>,
+    cursor: Option<ThemeColor>,
+    combat: Option<ThemeColor>,
+    scroll_marks: Option<ThemeColor>,
+    reachable: Option<ThemeColor>,
+
+    /// Between 0.0 and 1.0, how much to darken RGB colors for the fog of war where no foggy color is given
+    fog_darkness: Option<f64>,
+}
+
+impl Theme {
+    pub fn from_toml(toml: &str) -> Result<Self, ThemeError> {
+        Ok(toml::from_str(toml)?)
+    }
+
+    /// Read a theme file in the format of `data/theme.toml`
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self, ThemeError> {
+        Self::from_toml(fs::read_to_string(path)?.as_str())
+    }
+
+    /// `palette` with this theme's colors in place of its own
+    fn apply(&self, mut palette: Palette, darken_percent: f64) -> Palette {
+        let darken_percent = self.fog_darkness.unwrap_or(darken_percent);
+
+        let singles = [
+            (&mut palette.background, self.background),
+            (&mut palette.text, self.text),
+            (&mut palette.notice, self.notice),
+            (&mut palette.cursor, self.cursor),
+            (&mut palette.combat, self.combat),
+            (&mut palette.scroll_marks, self.scroll_marks),
+            (&mut palette.reachable, self.reachable),
+        ];
+        for (color, themed) in singles {
+            if let Some(themed) = themed {
+                *color = themed.0;
+            }
+        }
+
+        let pairs = [
+            (&mut palette.land, self.land),
+            (&mut palette.ocean, self.ocean),
+            (&mut palette.neutral, self.neutral),
+        ];
+        for (pair, themed) in pairs {
+            if let Some(themed) = themed {
+                *pair = themed.resolve(darken_percent);
+            }
+        }
+
+        for (player, themed) in self.players.iter().enumerate() {
+            let pair = themed.resolve(darken_percent);
+            if player < palette.players.len() {
+                palette.players[player] = pair;
+            } else {
+                palette.players.push(pair);
+            }
+        }
+
+        palette
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crossterm::style::Color;
+
+    use super::{palette16, Theme, ThemeColor, ThemeError};
+
+    #[test]
+    fn test_theme() {
+        assert_eq!(
+            ThemeColor::try_from(String::from("#18d843")),
+            Ok(ThemeColor(Color::Rgb {
+                r: 24,
+                g: 216,
+                b: 67
+            }))
+        );
+        assert_eq!(
+            ThemeColor::try_from(String::from("208")),
+            Ok(ThemeColor(Color::AnsiValue(208)))
+        );
+        assert_eq!(
+            ThemeColor::try_from(String::from("dark_green")),
+            Ok(ThemeColor(Color::DarkGreen))
+        );
+        assert!(ThemeColor::try_from(String::from("#18d8")).is_err());
+        assert!(ThemeColor::try_from(String::from("chartreuse")).is_err());
+
+        let theme = Theme::from_toml(
+            "background = \"black\"\n\
+             fog_darkness = 0.5\n\
+             land = { active = \"#00ff00\", foggy = \"green\" }\n\
+             players = [\n\
+                 { active = \"blue\" },\n\
+                 { active = \"#ffffff\" },\n\
+                 { active = \"red\" },\n\
+                 { active = \"yellow\" },\n\
+                 { active = \"cyan\" },\n\
+                 { active = \"magenta\" },\n\
+             ]\n",
+        )
+        .unwrap();
+
+        let palette = palette16(6, &theme).unwrap();
+        assert_eq!(palette.background, Color::Black);
+        assert_eq!(palette.land.get(true), Color::Rgb { r: 0, g: 255, b: 0 });
+        assert_eq!(palette.land.get(false), Color::Green);
+        assert_eq!(palette.players.len(), 6);
+        assert_eq!(palette.players[0].get(false), Color::Blue);
+        assert_ne!(palette.players[1].get(false), palette.players[1].get(true));
+
+        // Colors left out keep their built-in values
+        assert_eq!(palette.ocean.get(true), Color::Blue);
+        assert_eq!(palette.text, Color::White);
+
+        // Without the theme's extra player there aren't enough colors
+        assert!(palette16(6, &Theme::default()).is_err());
+
+        assert!(matches!(
+            Theme::from_toml("sky = \"blue\"\n"),
+            Err(ThemeError::Parse(_))
+        ));
+    }
+}
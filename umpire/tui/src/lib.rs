@@ -1,16 +1,18 @@
 //! Shared text UI elements
 
-use std::io::{Result as IoResult, Stdout, Write};
+use std::io::Result as IoResult;
 
 use async_trait::async_trait;
 
 use color::Palette;
 use common::{game::player::PlayerTurn, util::Rect};
-use crossterm::{cursor::MoveTo, queue, style::Print};
+use crossterm::cursor::MoveTo;
+use screen::Screen;
 
 pub mod color;
 pub mod map;
 pub mod obs_draw;
+pub mod screen;
 pub mod scroll;
 pub mod sym;
 pub mod tile;
@@ -20,16 +22,16 @@ pub trait Draw {
     async fn draw(
         &mut self,
         game: &PlayerTurn<'_>,
-        stdout: &mut Stdout,
+        screen: &mut Screen,
         palette: &Palette,
     ) -> IoResult<()> {
-        self.draw_no_flush(game, stdout, palette).await?;
-        stdout.flush()
+        self.draw_no_flush(game, screen, palette).await?;
+        screen.flush()
     }
     async fn draw_no_flush(
         &mut self,
         game: &PlayerTurn<'_>,
-        stdout: &mut Stdout,
+        screen: &mut Screen,
         palette: &Palette,
     ) -> IoResult<()>;
 }
@@ -49,14 +51,8 @@ pub trait Component: Draw {
         MoveTo(rect.left + x, rect.top + y)
     }
 
-    fn clear(&self, stdout: &mut Stdout) {
-        let rect = self.rect();
-        let blank_string = (0..rect.width).map(|_| " ").collect::<String>();
-        for y in 0..rect.height {
-            // write!(*stdout, "{}{}", self.goto(0, y), blank_string).unwrap();
-            queue!(*stdout, self.goto(0, y), Print(blank_string.clone())).unwrap();
-            //FIXME clear component without cloning a bunch of strings
-        }
+    fn clear(&self, screen: &mut Screen) {
+        screen.fill(self.rect());
     }
 
     // fn draw_window_frame(&self, title: &str, stdout: &mut termion::raw::RawTerminal<StdoutLock>) {
@@ -1,30 +1,30 @@
-use std::{
-    borrow::Cow,
-    io::{Result as IoResult, Stdout, Write},
-};
+use std::{borrow::Cow, collections::BTreeSet, io::Result as IoResult};
 
 use async_trait::async_trait;
 
 use crossterm::{
     cursor::Hide,
     style::{Attribute, Print, SetAttribute, SetBackgroundColor, SetForegroundColor},
-    QueueableCommand,
 };
 
 use common::{
     colors::{Colorized, Colors},
+    conf,
     game::{
         alignment::AlignedMaybe,
         city::City,
         map::{LocationGrid, Tile},
-        obs::Obs,
+        obs::{Obs, ObsRevision, ObsTrackerId},
         player::PlayerTurn,
         unit::{orders::Orders, Unit},
+        TurnNum,
     },
     util::{Dims, Location, Rect, Vec2d},
 };
 
-use crate::{color::Palette, scroll::ScrollableComponent, sym::Sym, Component, Draw};
+use crate::{
+    color::Palette, screen::Screen, scroll::ScrollableComponent, sym::Sym, Component, Draw,
+};
 
 fn nonnegative_mod(x: i32, max: u16) -> u16 {
     let mut result = x;
@@ -176,6 +176,32 @@ fn map_to_viewport_coords(
     None
 }
 
+/// How recently an observed tile was seen, as it bears on how the tile is drawn
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Freshness {
+    /// In sight right now
+    Current,
+
+    /// Out of sight, but seen within the last `conf::STALE_OBS_TURNS` turns
+    Remembered,
+
+    /// Seen so long ago that it's likely changed since; drawn dimmer than what's merely remembered
+    Stale,
+}
+
+impl Freshness {
+    /// The freshness as of `turn` of an observation made on `obs_turn`
+    fn of(current: bool, obs_turn: TurnNum, turn: TurnNum) -> Self {
+        if current {
+            Self::Current
+        } else if turn.saturating_sub(obs_turn) > conf::STALE_OBS_TURNS {
+            Self::Stale
+        } else {
+            Self::Remembered
+        }
+    }
+}
+
 /// The map widget
 pub struct Map {
     rect: Rect,
@@ -183,20 +209,38 @@ pub struct Map {
     old_viewport_offset: Vec2d<u16>,
     viewport_offset: Vec2d<u16>,
     displayed_tiles: LocationGrid<Option<Tile>>,
-    displayed_tile_currentness: LocationGrid<Option<bool>>,
+    displayed_tile_freshness: LocationGrid<Option<Freshness>>,
+
+    /// The observations the map was last drawn from, their revision then, the turn then, and the viewport offset then
+    ///
+    /// While the observations, turn, and offset stay the same, only tiles observed anew since need drawing. `None`
+    /// when every tile must be checked against what's displayed.
+    drawn: Option<(ObsTrackerId, ObsRevision, TurnNum, Vec2d<u16>)>,
+
+    /// Viewport locations showing something other than the tile as observed---highlighted, animated, or covered over
+    ///
+    /// These are drawn as observed the next time the map is drawn.
+    overdrawn: BTreeSet<Location>,
+
+    /// Map locations shaded to show where the selected unit can go
+    reachable: BTreeSet<Location>,
+
     unicode: bool,
 }
 impl Map {
     pub fn new(rect: Rect, map_dims: Dims, unicode: bool) -> Self {
         let displayed_tiles = LocationGrid::new(rect.dims(), |_loc| None);
-        let displayed_tile_currentness = LocationGrid::new(rect.dims(), |_loc| None);
+        let displayed_tile_freshness = LocationGrid::new(rect.dims(), |_loc| None);
         Map {
             rect,
             map_dims,
             old_viewport_offset: Vec2d::new(0, 0),
             viewport_offset: Vec2d::new(rect.width / 2, rect.height / 2),
             displayed_tiles,
-            displayed_tile_currentness,
+            displayed_tile_freshness,
+            drawn: None,
+            overdrawn: BTreeSet::new(),
+            reachable: BTreeSet::new(),
             unicode,
         }
     }
@@ -205,6 +249,43 @@ impl Map {
         self.rect.dims()
     }
 
+    pub fn set_unicode(&mut self, unicode: bool) {
+        self.unicode = unicode;
+
+        // Every tile may need a different symbol, so none of what's displayed can be trusted
+        self.displayed_tiles = LocationGrid::new(self.rect.dims(), |_loc| None);
+        self.drawn = None;
+    }
+
+    /// Note that something was drawn over the part of the map within `rect`, in terminal coordinates
+    ///
+    /// The tiles covered are redrawn the next time the map is drawn.
+    pub fn invalidate_rect(&mut self, rect: Rect) {
+        for y in rect.top.max(self.rect.top)..rect.bottom().min(self.rect.bottom()) {
+            for x in rect.left.max(self.rect.left)..rect.right().min(self.rect.right()) {
+                self.overdrawn
+                    .insert(Location::new(x - self.rect.left, y - self.rect.top));
+            }
+        }
+    }
+
+    /// Shade the tiles at the map locations `reachable`, in place of any shaded before
+    ///
+    /// Tiles gaining or losing their shading are redrawn the next time the map is drawn.
+    pub fn set_reachable(&mut self, reachable: BTreeSet<Location>) {
+        let changed: Vec<Location> = self
+            .reachable
+            .symmetric_difference(&reachable)
+            .copied()
+            .collect();
+        for map_loc in changed {
+            if let Some(viewport_loc) = self.map_to_viewport_coords(map_loc) {
+                self.overdrawn.insert(viewport_loc);
+            }
+        }
+        self.reachable = reachable;
+    }
+
     #[deprecated = "Replace with ScrollableComponent::scroll_relative"]
     pub fn shift_viewport<V: Into<Vec2d<i32>>>(&mut self, shift: V) {
         let shift: Vec2d<i32> = shift.into();
@@ -301,11 +382,11 @@ impl Map {
 
     /// Renders a particular location in the viewport
     ///
-    /// Flushes stdout for convenience
+    /// Flushes the screen for convenience
     pub fn draw_tile_and_flush(
         &mut self,
         game: &PlayerTurn<'_>,
-        stdout: &mut Stdout,
+        screen: &mut Screen,
         viewport_loc: Location,
         highlight: bool,   // Highlighting as for a cursor
         unit_active: bool, // Indicate that the unit (if present) is active, i.e. ready to respond to orders
@@ -327,7 +408,7 @@ impl Map {
     ) -> IoResult<()> {
         self.draw_tile_no_flush(
             game,
-            stdout,
+            screen,
             viewport_loc,
             highlight,
             unit_active,
@@ -337,14 +418,14 @@ impl Map {
             obs_override,
             palette,
         )?;
-        stdout.flush()
+        screen.flush()
     }
 
     /// Renders a particular location in the viewport
     pub fn draw_tile_no_flush(
         &mut self,
         game: &PlayerTurn<'_>,
-        stdout: &mut Stdout,
+        screen: &mut Screen,
         viewport_loc: Location,
         highlight: bool,   // Highlighting as for a cursor
         unit_active: bool, // Indicate that the unit (if present) is active, i.e. ready to respond to orders
@@ -364,14 +445,26 @@ impl Map {
 
         palette: &Palette,
     ) -> IoResult<()> {
-        stdout.queue(SetAttribute(Attribute::Reset))?;
-        stdout.queue(SetBackgroundColor(palette.get_single(Colors::Background)))?;
+        if highlight
+            || unit_active
+            || city_override.is_some()
+            || unit_override.is_some()
+            || symbol_override.is_some()
+            || obs_override.is_some()
+        {
+            self.overdrawn.insert(viewport_loc);
+        } else {
+            self.overdrawn.remove(&viewport_loc);
+        }
 
-        stdout.queue(self.goto(viewport_loc.x, viewport_loc.y))?;
+        screen.paint(SetAttribute(Attribute::Reset));
+        screen.paint(SetBackgroundColor(palette.get_single(Colors::Background)));
+
+        screen.paint(self.goto(viewport_loc.x, viewport_loc.y));
 
         let should_clear = if let Some(tile_loc) = self.viewport_to_map_coords(game, viewport_loc) {
             if tile_loc.y == game.dims().height - 1 {
-                stdout.queue(SetAttribute(Attribute::Underlined)).unwrap();
+                screen.paint(SetAttribute(Attribute::Underlined));
             }
 
             let obs = if let Some(obs_override) = obs_override {
@@ -380,14 +473,25 @@ impl Map {
                 game.obs(tile_loc)
             };
 
-            if let Some(Obs::Observed { tile, current, .. }) = obs {
+            if let Some(Obs::Observed {
+                tile,
+                turn,
+                current,
+                ..
+            }) = obs
+            {
+                let freshness = Freshness::of(current, turn, game.start().turn);
+                if freshness == Freshness::Stale {
+                    screen.paint(SetAttribute(Attribute::Dim));
+                }
+
                 if highlight {
-                    stdout.queue(SetAttribute(Attribute::Reverse)).unwrap();
+                    screen.paint(SetAttribute(Attribute::Reverse));
                 }
 
                 if unit_active {
-                    stdout.queue(SetAttribute(Attribute::SlowBlink)).unwrap();
-                    stdout.queue(SetAttribute(Attribute::Bold)).unwrap();
+                    screen.paint(SetAttribute(Attribute::SlowBlink));
+                    screen.paint(SetAttribute(Attribute::Bold));
                 }
 
                 let city: Option<&City> = if let Some(city_override) = city_override {
@@ -418,10 +522,15 @@ impl Map {
                 let (sym, fg_color, bg_color) = if let Some(unit) = unit {
                     if let Some(orders) = unit.orders {
                         if orders == Orders::Sentry {
-                            stdout.queue(SetAttribute(Attribute::Italic)).unwrap();
+                            screen.paint(SetAttribute(Attribute::Italic));
                         }
                     }
 
+                    // Only the top of a stack is shown; embolden it to hint at the others
+                    if unit_override.is_none() && !tile.stack.is_empty() {
+                        screen.paint(SetAttribute(Attribute::Bold));
+                    }
+
                     (unit.sym(self.unicode), unit.color(), tile.terrain.color())
                 } else if let Some(city) = city {
                     (
@@ -434,21 +543,17 @@ impl Map {
                 };
 
                 if let Some(fg_color) = fg_color {
-                    stdout
-                        .queue(SetForegroundColor(palette.get(fg_color, current)))
-                        .unwrap();
+                    screen.paint(SetForegroundColor(palette.get(fg_color, current)));
                 }
-                if let Some(bg_color) = bg_color {
-                    stdout
-                        .queue(SetBackgroundColor(palette.get(bg_color, current)))
-                        .unwrap();
+                if self.reachable.contains(&tile_loc) {
+                    screen.paint(SetBackgroundColor(palette.get_single(Colors::Reachable)));
+                } else if let Some(bg_color) = bg_color {
+                    screen.paint(SetBackgroundColor(palette.get(bg_color, current)));
                 }
-                stdout
-                    .queue(Print(String::from(symbol_override.unwrap_or(sym))))
-                    .unwrap();
+                screen.paint(Print(String::from(symbol_override.unwrap_or(sym))));
 
                 self.displayed_tiles[viewport_loc] = Some(tile);
-                self.displayed_tile_currentness[viewport_loc] = Some(current);
+                self.displayed_tile_freshness[viewport_loc] = Some(freshness);
 
                 false
             } else {
@@ -460,21 +565,81 @@ impl Map {
 
         if should_clear {
             if highlight {
-                stdout.queue(SetBackgroundColor(palette.get_single(Colors::Cursor)))?;
+                screen.paint(SetBackgroundColor(palette.get_single(Colors::Cursor)));
             }
-            stdout.queue(Print(String::from(" ")))?;
+            screen.paint(Print(String::from(" ")));
             self.displayed_tiles[viewport_loc] = None;
-            self.displayed_tile_currentness[viewport_loc] = None;
+            self.displayed_tile_freshness[viewport_loc] = None;
         }
 
         // write!(stdout, "{}", StrongReset::new(&self.palette)).unwrap();
-        stdout.queue(SetAttribute(Attribute::Reset))?;
-        stdout.queue(SetBackgroundColor(palette.get_single(Colors::Background)))?;
+        screen.paint(SetAttribute(Attribute::Reset));
+        screen.paint(SetBackgroundColor(palette.get_single(Colors::Background)));
 
         Ok(())
         // stdout.flush().unwrap();
     }
 
+    /// Whether the tile at `viewport_loc` no longer matches what's displayed there
+    ///
+    /// This compares against the tile as last drawn, for when it isn't known what changed since.
+    fn tile_differs(&self, game: &PlayerTurn<'_>, viewport_loc: Location) -> bool {
+        // let old_map_loc = viewport_to_map_coords(game.dims(), viewport_loc, self.old_viewport_offset);
+        // let new_map_loc = viewport_to_map_coords(game.dims(), viewport_loc, self.viewport_offset);
+
+        let old_map_loc: Option<Location> =
+            self.viewport_to_map_coords_by_offset(game, viewport_loc, self.old_viewport_offset);
+        let new_map_loc: Option<Location> = self.viewport_to_map_coords(game, viewport_loc);
+
+        let new_obs = new_map_loc.and_then(|new_map_loc| game.obs(new_map_loc));
+
+        let old_freshness = self.displayed_tile_freshness[viewport_loc];
+        let new_freshness = if let Some(Obs::Observed { turn, current, .. }) = new_obs {
+            Some(Freshness::of(current, turn, game.start().turn))
+        } else {
+            None
+        };
+
+        let old_tile = self.displayed_tiles[viewport_loc].as_ref();
+
+        let new_tile = if let Some(new_map_loc) = new_map_loc {
+            game.tile(new_map_loc)
+        } else {
+            None
+        };
+
+        // let new_tile = &new_obs.tile;
+
+        (old_freshness != new_freshness)
+            || (old_tile.is_some() && new_tile.is_none())
+            || (old_tile.is_none() && new_tile.is_some())
+            || (old_tile.is_some() && new_tile.is_some() && {
+                let old = old_tile.unwrap();
+                let new = new_tile.unwrap();
+
+                // redraw for mismatch
+                !(old.terrain == new.terrain
+                    && old.sym(self.unicode) == new.sym(self.unicode)
+                    && old.alignment_maybe() == new.alignment_maybe()
+                    && old.stack.is_empty() == new.stack.is_empty())
+            })
+            || {
+                // redraw for border
+                if let Some(old_map_loc) = old_map_loc {
+                    let dims = game.dims();
+                    if let Some(new_map_loc) = new_map_loc {
+                        old_map_loc.y != new_map_loc.y
+                            && (old_map_loc.y == dims.height - 1
+                                || new_map_loc.y == dims.height - 1)
+                    } else {
+                        false
+                    }
+                } else {
+                    false
+                }
+            }
+    }
+
     pub async fn current_player_tile<'a>(
         &self,
         game: &'a PlayerTurn<'_>,
@@ -506,7 +671,9 @@ impl Component for Map {
 
         // When the rectangle this widget represents is reset, it invalidates our caches; re-initialize
         self.displayed_tiles = LocationGrid::new(rect.dims(), |_loc| None);
-        self.displayed_tile_currentness = LocationGrid::new(rect.dims(), |_loc| None);
+        self.displayed_tile_freshness = LocationGrid::new(rect.dims(), |_loc| None);
+        self.drawn = None;
+        self.overdrawn.clear();
     }
 
     fn rect(&self) -> Rect {
@@ -519,78 +686,44 @@ impl Draw for Map {
     async fn draw_no_flush(
         &mut self,
         game: &PlayerTurn<'_>,
-        stdout: &mut Stdout,
+        screen: &mut Screen,
         palette: &Palette,
     ) -> IoResult<()> {
-        for viewport_loc in self.viewport_dims().iter_locs() {
-            let should_draw_tile = {
-                // let old_map_loc = viewport_to_map_coords(game.dims(), viewport_loc, self.old_viewport_offset);
-                // let new_map_loc = viewport_to_map_coords(game.dims(), viewport_loc, self.viewport_offset);
-
-                let old_map_loc: Option<Location> = self.viewport_to_map_coords_by_offset(
-                    game,
-                    viewport_loc,
-                    self.old_viewport_offset,
-                );
-                let new_map_loc: Option<Location> = self.viewport_to_map_coords(game, viewport_loc);
-
-                let new_obs = new_map_loc.and_then(|new_map_loc| game.obs(new_map_loc));
-
-                let old_currentness = self.displayed_tile_currentness[viewport_loc];
-                // let new_currentness = if let Obs::Observed{current,..} = new_obs {
-                //     Some(*current)
-                // } else {
-                //     None
-                // };
-                let new_currentness = if let Some(Obs::Observed { current, .. }) = new_obs {
-                    Some(current)
-                } else {
-                    None
-                };
+        let observations = game.observations();
+        let turn = game.start().turn;
+        let drawn_revision = match self.drawn {
+            // A new turn can leave remembered tiles stale, so it calls for every tile to be checked
+            Some((id, revision, drawn_turn, offset))
+                if id == observations.id()
+                    && drawn_turn == turn
+                    && offset == self.viewport_offset =>
+            {
+                Some(revision)
+            }
+            _ => None,
+        };
 
-                let old_tile = self.displayed_tiles[viewport_loc].as_ref();
+        // Shading doesn't figure in whether a tile differs from what's displayed, so while any is shown a scroll
+        // means drawing every tile
+        let redraw_all = drawn_revision.is_none() && !self.reachable.is_empty();
 
-                let new_tile = if let Some(new_map_loc) = new_map_loc {
-                    game.tile(new_map_loc)
-                } else {
-                    None
+        for viewport_loc in self.viewport_dims().iter_locs() {
+            let should_draw_tile = redraw_all
+                || self.overdrawn.contains(&viewport_loc)
+                || match drawn_revision {
+                    // Nothing has scrolled, so only tiles observed anew can have changed
+                    Some(revision) => self
+                        .viewport_to_map_coords(game, viewport_loc)
+                        .map_or(false, |map_loc| {
+                            observations.changed_since(map_loc, revision)
+                        }),
+                    None => self.tile_differs(game, viewport_loc),
                 };
 
-                // let new_tile = &new_obs.tile;
-
-                (old_currentness != new_currentness)
-                    || (old_tile.is_some() && new_tile.is_none())
-                    || (old_tile.is_none() && new_tile.is_some())
-                    || (old_tile.is_some() && new_tile.is_some() && {
-                        let old = old_tile.unwrap();
-                        let new = new_tile.unwrap();
-
-                        // redraw for mismatch
-                        !(old.terrain == new.terrain
-                            && old.sym(self.unicode) == new.sym(self.unicode)
-                            && old.alignment_maybe() == new.alignment_maybe())
-                    })
-                    || {
-                        // redraw for border
-                        if let Some(old_map_loc) = old_map_loc {
-                            let dims = game.dims();
-                            if let Some(new_map_loc) = new_map_loc {
-                                old_map_loc.y != new_map_loc.y
-                                    && (old_map_loc.y == dims.height - 1
-                                        || new_map_loc.y == dims.height - 1)
-                            } else {
-                                false
-                            }
-                        } else {
-                            false
-                        }
-                    }
-            };
-
             if should_draw_tile {
                 self.draw_tile_no_flush(
                     game,
-                    stdout,
+                    screen,
                     viewport_loc,
                     false,
                     false,
@@ -603,10 +736,17 @@ impl Draw for Map {
             }
         }
 
+        self.drawn = Some((
+            observations.id(),
+            observations.revision(),
+            turn,
+            self.viewport_offset,
+        ));
+
         // write!(stdout, "{}{}", StrongReset::new(&self.palette), Hide).unwrap();
-        stdout.queue(SetAttribute(Attribute::Reset))?;
-        stdout.queue(SetBackgroundColor(palette.get_single(Colors::Background)))?;
-        stdout.queue(Hide)?;
+        screen.paint(SetAttribute(Attribute::Reset));
+        screen.paint(SetBackgroundColor(palette.get_single(Colors::Background)));
+        screen.paint(Hide);
 
         Ok(())
     }
@@ -0,0 +1,403 @@
+//! The terminal, double-buffered
+//!
+//! Components paint into a back buffer of cells rather than writing to the terminal directly. The back buffer is
+//! retained from frame to frame, so a component need only paint what it knows has changed. On flush the back buffer
+//! is compared with what the terminal was last sent, and only the cells that differ are written out.
+//!
+//! The buffers are ratatui's, so ratatui widgets can be rendered onto the screen alongside what's painted cell by
+//! cell.
+
+use std::{
+    fmt::{self, Display, Write as FmtWrite},
+    io::{Result as IoResult, Stdout},
+};
+
+use crossterm::{
+    cursor::{Hide, MoveTo, Show},
+    style::{Attribute, Print, ResetColor, SetAttribute, SetBackgroundColor, SetForegroundColor},
+    terminal::{Clear, ClearType},
+    Command, QueueableCommand,
+};
+
+use ratatui::{
+    backend::{Backend, CrosstermBackend},
+    buffer::Buffer,
+    layout::Rect as Area,
+    style::{Color, Modifier, Style},
+    widgets::{StatefulWidget, Widget},
+};
+
+use common::util::{Dims, Rect};
+
+/// The ratatui area covering `rect`
+pub fn area(rect: Rect) -> Area {
+    Area::new(rect.left, rect.top, rect.width, rect.height)
+}
+
+/// The rect covering the ratatui area `area`
+pub fn rect(area: Area) -> Rect {
+    Rect::new(area.x, area.y, area.width, area.height)
+}
+
+/// The terminal as components see it
+///
+/// Painting is done with the same commands as are queued to a terminal, by way of `paint!`, but lands in the back
+/// buffer. Widgets are rendered into the back buffer too. Nothing reaches the terminal until `flush`.
+pub struct Screen {
+    backend: CrosstermBackend<Stdout>,
+    dims: Dims,
+
+    /// What the next flush should leave on the terminal
+    back: Buffer,
+
+    /// What the terminal was left showing by the last flush
+    front: Buffer,
+
+    /// Whether what the terminal shows is unknown, so the next flush must repaint everything
+    stale: bool,
+
+    /// Where the next character painted will go
+    x: u16,
+    y: u16,
+
+    /// The style the next character painted will take
+    fg: Color,
+    bg: Color,
+    modifier: Modifier,
+
+    cursor_hidden: bool,
+}
+
+impl Screen {
+    pub fn new(out: Stdout, dims: Dims) -> Self {
+        let full = Area::new(0, 0, dims.width, dims.height);
+        Self {
+            backend: CrosstermBackend::new(out),
+            dims,
+            back: Buffer::empty(full),
+            front: Buffer::empty(full),
+            stale: true,
+            x: 0,
+            y: 0,
+            fg: Color::Reset,
+            bg: Color::Reset,
+            modifier: Modifier::empty(),
+            cursor_hidden: false,
+        }
+    }
+
+    pub fn dims(&self) -> Dims {
+        self.dims
+    }
+
+    /// Fit the buffers to a terminal of new dimensions
+    ///
+    /// Everything is blanked, and the terminal is repainted entirely on the next flush.
+    pub fn resize(&mut self, dims: Dims) {
+        let full = Area::new(0, 0, dims.width, dims.height);
+        self.dims = dims;
+        self.back = Buffer::empty(full);
+        self.front = Buffer::empty(full);
+        self.stale = true;
+    }
+
+    /// Forget what the terminal shows, as when something other than the screen has written to it
+    ///
+    /// The next flush repaints everything.
+    pub fn invalidate(&mut self) {
+        self.stale = true;
+    }
+
+    /// Send a command straight to the terminal, bypassing the buffers
+    ///
+    /// Meant for commands that change the terminal's mode, like entering the alternate screen, rather than what it
+    /// shows.
+    pub fn queue_raw(&mut self, command: impl Command) -> IoResult<()> {
+        self.backend.queue(command)?;
+        Ok(())
+    }
+
+    /// Blank every cell within `rect`, in the current background color
+    pub fn fill(&mut self, rect: Rect) {
+        let rect = area(rect).intersection(*self.back.area());
+        for y in rect.top()..rect.bottom() {
+            for x in rect.left()..rect.right() {
+                let cell = self.back.get_mut(x, y);
+                cell.reset();
+                cell.set_bg(self.bg);
+            }
+        }
+    }
+
+    /// Paint one command into the back buffer
+    pub fn paint(&mut self, command: impl Paint) {
+        command.paint(self);
+    }
+
+    /// Render a ratatui widget into the back buffer, within `rect`
+    ///
+    /// Whatever the widget leaves untouched within `rect` stays as it was.
+    pub fn render_widget(&mut self, widget: impl Widget, rect: Rect) {
+        let area = area(rect).intersection(*self.back.area());
+        widget.render(area, &mut self.back);
+    }
+
+    /// Render a stateful ratatui widget into the back buffer, within `rect`
+    pub fn render_stateful_widget<W: StatefulWidget>(
+        &mut self,
+        widget: W,
+        rect: Rect,
+        state: &mut W::State,
+    ) {
+        let area = area(rect).intersection(*self.back.area());
+        widget.render(area, &mut self.back, state);
+    }
+
+    fn put(&mut self, c: char) {
+        if c == '\n' {
+            self.x = 0;
+            self.y = self.y.saturating_add(1);
+            return;
+        }
+
+        // Anything off the edge of the screen is clipped
+        if self.x < self.dims.width && self.y < self.dims.height {
+            let style = Style::default()
+                .fg(self.fg)
+                .bg(self.bg)
+                .add_modifier(self.modifier);
+            let cell = self.back.get_mut(self.x, self.y);
+            cell.reset();
+            cell.set_char(c).set_style(style);
+        }
+        self.x = self.x.saturating_add(1);
+    }
+
+    fn set_attribute(&mut self, attr: Attribute) {
+        match attr {
+            Attribute::Reset => {
+                self.modifier = Modifier::empty();
+                self.fg = Color::Reset;
+                self.bg = Color::Reset;
+            }
+            Attribute::NormalIntensity => self.modifier.remove(Modifier::BOLD | Modifier::DIM),
+            Attribute::NoItalic => self.modifier.remove(Modifier::ITALIC),
+            Attribute::NoUnderline => self.modifier.remove(Modifier::UNDERLINED),
+            Attribute::NoBlink => self
+                .modifier
+                .remove(Modifier::SLOW_BLINK | Modifier::RAPID_BLINK),
+            Attribute::NoReverse => self.modifier.remove(Modifier::REVERSED),
+            attr => self.modifier.insert(Modifier::from(attr)),
+        }
+    }
+
+    /// Write to the terminal whatever cells changed since the last flush
+    pub fn flush(&mut self) -> IoResult<()> {
+        if self.stale {
+            self.backend.clear()?;
+            self.front.reset();
+            self.stale = false;
+        }
+
+        self.backend.draw(self.front.diff(&self.back).into_iter())?;
+        self.front.clone_from(&self.back);
+
+        if self.cursor_hidden {
+            self.backend.hide_cursor()?;
+        } else {
+            self.backend.show_cursor()?;
+        }
+
+        Backend::flush(&mut self.backend)
+    }
+}
+
+impl FmtWrite for Screen {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        for c in s.chars() {
+            self.put(c);
+        }
+        Ok(())
+    }
+}
+
+/// A command that can be painted onto the screen
+///
+/// These are the terminal commands components use, applied to the back buffer instead.
+pub trait Paint {
+    fn paint(self, screen: &mut Screen);
+}
+
+impl Paint for MoveTo {
+    fn paint(self, screen: &mut Screen) {
+        screen.x = self.0;
+        screen.y = self.1;
+    }
+}
+
+impl Paint for SetForegroundColor {
+    fn paint(self, screen: &mut Screen) {
+        screen.fg = self.0.into();
+    }
+}
+
+impl Paint for SetBackgroundColor {
+    fn paint(self, screen: &mut Screen) {
+        screen.bg = self.0.into();
+    }
+}
+
+impl Paint for ResetColor {
+    fn paint(self, screen: &mut Screen) {
+        screen.fg = Color::Reset;
+        screen.bg = Color::Reset;
+    }
+}
+
+impl Paint for SetAttribute {
+    fn paint(self, screen: &mut Screen) {
+        screen.set_attribute(self.0);
+    }
+}
+
+impl<T: Display> Paint for Print<T> {
+    fn paint(self, screen: &mut Screen) {
+        // Writing to the screen can't fail
+        write!(screen, "{}", self.0).unwrap();
+    }
+}
+
+impl Paint for Hide {
+    fn paint(self, screen: &mut Screen) {
+        screen.cursor_hidden = true;
+    }
+}
+
+impl Paint for Show {
+    fn paint(self, screen: &mut Screen) {
+        screen.cursor_hidden = false;
+    }
+}
+
+impl Paint for Clear {
+    fn paint(self, screen: &mut Screen) {
+        match self.0 {
+            ClearType::All | ClearType::Purge => screen.fill(Rect::new(
+                0,
+                0,
+                screen.dims.width,
+                screen.dims.height,
+            )),
+            ClearType::CurrentLine => screen.fill(Rect::new(0, screen.y, screen.dims.width, 1)),
+            ClearType::UntilNewLine => screen.fill(Rect::new(
+                screen.x,
+                screen.y,
+                screen.dims.width.saturating_sub(screen.x),
+                1,
+            )),
+            ClearType::FromCursorDown => {
+                screen.fill(Rect::new(
+                    screen.x,
+                    screen.y,
+                    screen.dims.width.saturating_sub(screen.x),
+                    1,
+                ));
+                screen.fill(Rect::new(
+                    0,
+                    screen.y + 1,
+                    screen.dims.width,
+                    screen.dims.height.saturating_sub(screen.y + 1),
+                ));
+            }
+            ClearType::FromCursorUp => {
+                screen.fill(Rect::new(0, 0, screen.dims.width, screen.y));
+                screen.fill(Rect::new(0, screen.y, screen.x + 1, 1));
+            }
+        }
+    }
+}
+
+/// Paint commands onto a `Screen`, as `crossterm::queue!` queues them to a terminal
+///
+/// Evaluates to `Ok(())`, so it can stand in for `queue!`; painting itself can't fail.
+#[macro_export]
+macro_rules! paint {
+    ($screen:expr $(, $command:expr)* $(,)?) => {{
+        $( $crate::screen::Screen::paint(&mut $screen, $command); )*
+        ::std::io::Result::<()>::Ok(())
+    }};
+}
+
+#[cfg(test)]
+mod test {
+    use std::io::stdout;
+
+    use crossterm::{
+        cursor::MoveTo,
+        style::{Color, Print, SetForegroundColor},
+        terminal::{Clear, ClearType},
+    };
+
+    use ratatui::{style::Color as RColor, widgets::Paragraph};
+
+    use common::util::{Dims, Rect};
+
+    use super::Screen;
+
+    fn row(screen: &Screen, y: u16) -> String {
+        (0..screen.dims.width)
+            .map(|x| screen.back.get(x, y).symbol())
+            .collect()
+    }
+
+    #[test]
+    fn test_paint() {
+        let mut screen = Screen::new(stdout(), Dims::new(5, 2));
+
+        paint!(screen, MoveTo(1, 0), Print("abc")).unwrap();
+        assert_eq!(row(&screen, 0), " abc ");
+
+        // Clipped at the edge
+        paint!(screen, MoveTo(3, 1), SetForegroundColor(Color::Red), Print("xyz")).unwrap();
+        assert_eq!(row(&screen, 1), "   xy");
+        assert_eq!(screen.back.get(4, 1).fg, RColor::Red);
+        assert_eq!(screen.back.get(2, 1).fg, RColor::Reset);
+
+        screen.fill(Rect::new(0, 0, 2, 2));
+        assert_eq!(row(&screen, 0), "  bc ");
+        assert_eq!(row(&screen, 1), "   xy");
+
+        // Widgets land in the same buffer, clipped to the screen
+        screen.render_widget(Paragraph::new("hello"), Rect::new(2, 1, 10, 1));
+        assert_eq!(row(&screen, 1), "  hel");
+    }
+
+    #[test]
+    fn test_partial_clears() {
+        let mut screen = Screen::new(stdout(), Dims::new(3, 3));
+        let fill = |screen: &mut Screen| {
+            paint!(
+                *screen,
+                MoveTo(0, 0),
+                Print("abc"),
+                MoveTo(0, 1),
+                Print("def"),
+                MoveTo(0, 2),
+                Print("ghi")
+            )
+            .unwrap();
+        };
+
+        fill(&mut screen);
+        paint!(screen, MoveTo(1, 1), Clear(ClearType::FromCursorDown)).unwrap();
+        assert_eq!(row(&screen, 0), "abc");
+        assert_eq!(row(&screen, 1), "d  ");
+        assert_eq!(row(&screen, 2), "   ");
+
+        fill(&mut screen);
+        paint!(screen, MoveTo(1, 1), Clear(ClearType::FromCursorUp)).unwrap();
+        assert_eq!(row(&screen, 0), "   ");
+        assert_eq!(row(&screen, 1), "  f");
+        assert_eq!(row(&screen, 2), "ghi");
+    }
+}
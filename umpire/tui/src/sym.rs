@@ -2,7 +2,7 @@
 
 use common::game::{
     city::City,
-    map::{Terrain, Tile},
+    map::{Improvement, River, Terrain, Tile},
     unit::{Unit, UnitType},
 };
 
@@ -13,14 +13,64 @@ pub trait Sym {
 #[derive(Copy, Clone)]
 pub enum Symbols {
     Land,
+    Forest,
+    Mountains,
+    River,
+    Ford,
+    Road,
+    Fortification,
+    Radar,
     Ocean,
     City,
 }
 
 impl Symbols {
-    pub fn get(self, _unicode: bool) -> &'static str {
+    pub fn get(self, unicode: bool) -> &'static str {
         match self {
             Symbols::Land => "·",
+            Symbols::Forest => {
+                if unicode {
+                    "♣"
+                } else {
+                    "%"
+                }
+            }
+            Symbols::Mountains => {
+                if unicode {
+                    "▲"
+                } else {
+                    "^"
+                }
+            }
+            Symbols::River => {
+                if unicode {
+                    "≈"
+                } else {
+                    "="
+                }
+            }
+            Symbols::Ford => "+",
+            Symbols::Road => {
+                if unicode {
+                    "═"
+                } else {
+                    ":"
+                }
+            }
+            Symbols::Fortification => {
+                if unicode {
+                    "▣"
+                } else {
+                    "*"
+                }
+            }
+            Symbols::Radar => {
+                if unicode {
+                    "◎"
+                } else {
+                    "@"
+                }
+            }
             Symbols::Ocean => "~",
             Symbols::City => "#",
         }
@@ -37,11 +87,32 @@ impl Sym for Terrain {
     fn sym(&self, unicode: bool) -> &'static str {
         match *self {
             Terrain::Land => Symbols::Land.get(unicode),
+            Terrain::Forest => Symbols::Forest.get(unicode),
+            Terrain::Mountains => Symbols::Mountains.get(unicode),
             Terrain::Water => Symbols::Ocean.get(unicode),
         }
     }
 }
 
+impl Sym for River {
+    fn sym(&self, unicode: bool) -> &'static str {
+        match *self {
+            River::Channel => Symbols::River.get(unicode),
+            River::Ford => Symbols::Ford.get(unicode),
+        }
+    }
+}
+
+impl Sym for Improvement {
+    fn sym(&self, unicode: bool) -> &'static str {
+        match *self {
+            Improvement::Road => Symbols::Road.get(unicode),
+            Improvement::Fortification => Symbols::Fortification.get(unicode),
+            Improvement::Radar => Symbols::Radar.get(unicode),
+        }
+    }
+}
+
 //NOTE `Map::draw_tile_no_flush implements a similar symbol selection algorithm that allows for city and unit overrides.
 impl Sym for Tile {
     fn sym(&self, unicode: bool) -> &'static str {
@@ -49,6 +120,14 @@ impl Sym for Tile {
             unit.sym(unicode)
         } else if let Some(ref city) = self.city {
             city.sym(unicode)
+        } else if self.has_improvement(Improvement::Radar) {
+            Improvement::Radar.sym(unicode)
+        } else if self.has_improvement(Improvement::Fortification) {
+            Improvement::Fortification.sym(unicode)
+        } else if self.has_improvement(Improvement::Road) {
+            Improvement::Road.sym(unicode)
+        } else if let Some(river) = self.river {
+            river.sym(unicode)
         } else {
             self.terrain.sym(unicode)
         }
@@ -80,6 +159,11 @@ impl Sym for UnitType {
             UnitType::Cruiser => "c",
             UnitType::Battleship => "B",
             UnitType::Carrier => "C",
+            UnitType::Artillery => "R",
+            UnitType::Helicopter => "h",
+            UnitType::Paratrooper => "j",
+            UnitType::Missile => "!",
+            UnitType::Engineer => "e",
         }
     }
 }
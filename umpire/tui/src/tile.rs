@@ -1,69 +1,105 @@
-use std::io::{Result as IoResult, Write};
+use std::io::Result as IoResult;
 
 use async_trait::async_trait;
 
-use crossterm::{
-    style::{Color, ResetColor, SetForegroundColor},
-    QueueableCommand,
-};
+use crossterm::style::{Color, Print, ResetColor, SetForegroundColor};
 
 use common::game::{
     alignment::{Aligned, AlignedMaybe},
-    map::{Terrain, Tile},
+    map::{Improvement, River, Terrain, Tile},
     player::PlayerTurn,
 };
 
-use crate::Draw;
+use crate::{paint, screen::Screen, Draw};
 
 #[async_trait]
 impl Draw for Tile {
     async fn draw_no_flush(
         &mut self,
         _game: &PlayerTurn<'_>,
-        stdout: &mut std::io::Stdout,
+        screen: &mut Screen,
         _palette: &crate::color::Palette,
     ) -> IoResult<()> {
         // If there's a unit, show the unit
         if let Some(ref unit) = self.unit {
             // Capitalize if it belongs to player 1
             if unit.belongs_to_player(1) {
-                stdout.queue(SetForegroundColor(Color::Red)).unwrap();
+                screen.paint(SetForegroundColor(Color::Red));
             } else {
-                stdout.queue(SetForegroundColor(Color::White)).unwrap();
+                screen.paint(SetForegroundColor(Color::White));
             };
 
-            let result = write!(stdout, "{}", unit.type_.key());
-            stdout.queue(ResetColor).unwrap();
+            let result = paint!(*screen, Print(unit.type_.key()));
+            screen.paint(ResetColor);
             return result;
         }
 
         // If there's a city, show the city
         if let Some(ref city) = self.city {
             if city.is_neutral() {
-                stdout.queue(SetForegroundColor(Color::DarkGrey)).unwrap();
+                screen.paint(SetForegroundColor(Color::DarkGrey));
             } else if city.belongs_to_player(1) {
-                stdout.queue(SetForegroundColor(Color::Red)).unwrap();
+                screen.paint(SetForegroundColor(Color::Red));
             } else {
-                stdout.queue(SetForegroundColor(Color::White)).unwrap();
+                screen.paint(SetForegroundColor(Color::White));
             }
-            let result = write!(stdout, "#");
-            stdout.queue(ResetColor).unwrap();
+            let result = paint!(*screen, Print('#'));
+            screen.paint(ResetColor);
+            return result;
+        }
+
+        // If there are improvements, show the most significant
+        if self.has_improvement(Improvement::Radar) {
+            screen.paint(SetForegroundColor(Color::Magenta));
+            let result = paint!(*screen, Print('@'));
+            screen.paint(ResetColor);
+            return result;
+        }
+        if self.has_improvement(Improvement::Fortification) {
+            screen.paint(SetForegroundColor(Color::Yellow));
+            let result = paint!(*screen, Print('*'));
+            screen.paint(ResetColor);
+            return result;
+        }
+        if self.has_improvement(Improvement::Road) {
+            screen.paint(SetForegroundColor(Color::DarkYellow));
+            let result = paint!(*screen, Print(':'));
+            screen.paint(ResetColor);
+            return result;
+        }
+
+        // If there's a river, show the river
+        if let Some(river) = self.river {
+            screen.paint(SetForegroundColor(Color::Cyan));
+            let result = match river {
+                River::Channel => paint!(*screen, Print('=')),
+                River::Ford => paint!(*screen, Print('+')),
+            };
+            screen.paint(ResetColor);
             return result;
         }
 
         // Otherwise, show the terrain
         let result = match self.terrain {
             Terrain::Land => {
-                stdout.queue(SetForegroundColor(Color::Green)).unwrap();
-                write!(stdout, "·")
+                screen.paint(SetForegroundColor(Color::Green));
+                paint!(*screen, Print('·'))
             }
             Terrain::Water => {
-                stdout.queue(SetForegroundColor(Color::Blue)).unwrap();
-                write!(stdout, "~")
+                screen.paint(SetForegroundColor(Color::Blue));
+                paint!(*screen, Print('~'))
+            }
+            Terrain::Forest => {
+                screen.paint(SetForegroundColor(Color::DarkGreen));
+                paint!(*screen, Print('%'))
+            }
+            Terrain::Mountains => {
+                screen.paint(SetForegroundColor(Color::Grey));
+                paint!(*screen, Print('^'))
             }
         };
 
-        stdout.queue(ResetColor).unwrap();
+        screen.paint(ResetColor);
 
         result
     }